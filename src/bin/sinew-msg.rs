@@ -50,7 +50,17 @@ fn main() {
         eprintln!("  set <id> key=value [...]        Set module properties");
         eprintln!("  get <id> [property]             Get module properties");
         eprintln!("  list                            List all modules (JSON)");
-        eprintln!("  trigger <id> update|popup       Trigger module event");
+        eprintln!("  trigger <id> update|popup|click Trigger module event");
+        eprintln!("  pin | unpin                     Pin or unpin the open popup");
+        eprintln!("  snooze <id> <minutes|reload|restart>");
+        eprintln!("                                  Hide a module for a while");
+        eprintln!("  unsnooze <id>                   Bring a snoozed module back");
+        eprintln!("  toast <message> [--timeout s] [--style info|success|warning|error]");
+        eprintln!("                                  Show a message in the bar");
+        eprintln!("  progress <name> <0-1|N%|remove> Show or update a progress bar");
+        eprintln!("  profile [<name>|auto|none]      Switch config profile, or show it (JSON)");
+        eprintln!("  watch <id> [...]                Print a module's text, value and color");
+        eprintln!("                                  (JSON) each time they change");
         std::process::exit(1);
     }
 
@@ -69,6 +79,20 @@ fn main() {
             }
 
             let mut reader = BufReader::new(stream);
+            // `watch` keeps the connection open and sends a line per change
+            if args[0] == "watch" {
+                for line in reader.lines() {
+                    match line {
+                        Ok(line) => println!("{}", line),
+                        Err(e) => {
+                            eprintln!("Failed to read response: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                return;
+            }
+
             let mut response = String::new();
             if let Err(e) = reader.read_line(&mut response) {
                 eprintln!("Failed to read response: {}", e);
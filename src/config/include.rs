@@ -0,0 +1,193 @@
+//! Config fragments pulled in with `include`.
+//!
+//! ```toml
+//! include = ["modules/*.toml", "theme.toml"]
+//! ```
+//!
+//! Paths are relative to the config directory (`~` is expanded), and `*` and
+//! `?` wildcards are allowed in the file name. Matches are read in name order,
+//! pattern by pattern, and merged before config.toml itself, so config.toml
+//! has the last word. Tables merge key by key; arrays of tables (module
+//! lists) are appended; any other value from a later file replaces the
+//! earlier one.
+
+use std::path::{Path, PathBuf};
+
+/// Returns true if `name` matches a pattern with `*` and `?` wildcards.
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name index it was tried at
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn expand_tilde(pattern: &str) -> PathBuf {
+    match pattern.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+        None => PathBuf::from(pattern),
+    }
+}
+
+/// Returns the files an include pattern names, sorted. A pattern without
+/// wildcards names its file whether or not it exists.
+pub fn expand(pattern: &str, base: &Path) -> Vec<PathBuf> {
+    let path = base.join(expand_tilde(pattern));
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return Vec::new();
+    };
+    if !name.contains(['*', '?']) {
+        return vec![path];
+    }
+    let dir = path.parent().unwrap_or(base);
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|file| file.is_file())
+        .filter(|file| {
+            file.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| wildcard_match(name, n))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Returns the directories include patterns read from, for the watcher.
+pub fn directories(patterns: &[String], base: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = patterns
+        .iter()
+        .filter_map(|pattern| {
+            base.join(expand_tilde(pattern))
+                .parent()
+                .map(Path::to_path_buf)
+        })
+        .collect();
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+/// Reads the `include` list from a parsed config.
+pub fn patterns(config: &toml::Table) -> Vec<String> {
+    config
+        .get("include")
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn is_table_array(items: &[toml::Value]) -> bool {
+    items.iter().all(toml::Value::is_table)
+}
+
+/// Merges `overlay` into `base` (see the module docs for the rules).
+pub fn merge(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(table)) => {
+                merge(existing, table);
+            }
+            (Some(toml::Value::Array(existing)), toml::Value::Array(items))
+                if is_table_array(existing) && is_table_array(&items) =>
+            {
+                existing.extend(items);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(wildcard_match("*.toml", "left.toml"));
+        assert!(wildcard_match("mod?.toml", "mod1.toml"));
+        assert!(wildcard_match("a*b*c", "aXbYbZc"));
+        assert!(!wildcard_match("*.toml", "left.toml.bak"));
+        assert!(!wildcard_match("mod?.toml", "mod.toml"));
+    }
+
+    #[test]
+    fn merges_tables_and_appends_module_lists() {
+        let mut base: toml::Table = toml::from_str(
+            r##"
+[bar]
+font_size = 12.0
+background_color = "#000000"
+
+[[modules.left.left]]
+type = "clock"
+
+[bar.calendar]
+holidays = ["12-25"]
+"##,
+        )
+        .unwrap();
+        let overlay: toml::Table = toml::from_str(
+            r#"
+[bar]
+font_size = 14.0
+
+[[modules.left.left]]
+type = "cpu"
+
+[bar.calendar]
+holidays = ["01-01"]
+"#,
+        )
+        .unwrap();
+        merge(&mut base, overlay);
+
+        let bar = &base["bar"];
+        assert_eq!(bar["font_size"].as_float(), Some(14.0));
+        assert_eq!(bar["background_color"].as_str(), Some("#000000"));
+        assert_eq!(
+            bar["calendar"]["holidays"].as_array().unwrap().len(),
+            1,
+            "plain arrays are replaced"
+        );
+        let types: Vec<&str> = base["modules"]["left"]["left"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|m| m["type"].as_str())
+            .collect();
+        assert_eq!(types, ["clock", "cpu"]);
+    }
+}
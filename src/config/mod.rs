@@ -1,11 +1,20 @@
+mod include;
+pub mod profile;
+mod schema;
+mod secrets;
 mod types;
 
-pub use types::{parse_hex_color, BarConfig, Config, ModuleConfig};
+pub use types::{
+    parse_duration, parse_hex_color, AlertSource, AlertWhen, AlertsConfig, BarConfig,
+    CalendarConfig, Condition, Config, ConfigError, Holiday, HttpConfig, HudConfig, IdleConfig,
+    MenuItemConfig, ModuleConfig, ModulesConfig, NotchConfig, PowerSavingConfig, TooltipConfig,
+};
 
 use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Receiver};
 use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use types::ConfigIssue;
 
 pub type SharedConfig = Arc<RwLock<Config>>;
 
@@ -25,33 +34,49 @@ pub fn known_module_types() -> Vec<String> {
         .unwrap_or_default()
 }
 
+/// Errors from the last load, until a load succeeds
+static LOAD_ERRORS: Mutex<Vec<ConfigError>> = Mutex::new(Vec::new());
+
+/// Loads the config, falling back to the defaults if it has errors.
 pub fn load_config() -> Config {
-    let config_path = get_config_path();
+    try_load_config().unwrap_or_else(|_| {
+        log::error!("Config has errors; falling back to defaults.");
+        Config::default()
+    })
+}
 
-    let config = if config_path.exists() {
-        match std::fs::read_to_string(&config_path) {
-            Ok(contents) => match toml::from_str(&contents) {
-                Ok(config) => {
-                    log::info!("Loaded config from {:?}", config_path);
-                    config
-                }
-                Err(e) => {
-                    log::error!("Failed to parse config: {}", e);
-                    Config::default()
-                }
-            },
-            Err(e) => {
-                log::error!("Failed to read config file: {}", e);
-                Config::default()
-            }
-        }
+/// Loads the config, logging its issues. The errors that keep it from
+/// loading are also kept for [`load_errors`] until a load succeeds.
+pub fn try_load_config() -> Result<Config, Vec<ConfigError>> {
+    let config_path = get_config_path();
+    let result = if config_path.exists() {
+        load_from(&config_path)
     } else {
         log::info!("No config file found at {:?}, using defaults", config_path);
-        Config::default()
+        Ok(Config::default())
     };
+    if let Ok(mut errors) = LOAD_ERRORS.lock() {
+        *errors = result.as_ref().err().cloned().unwrap_or_default();
+    }
+    result
+}
+
+/// Returns the errors that kept the last load from succeeding.
+pub fn load_errors() -> Vec<ConfigError> {
+    LOAD_ERRORS
+        .lock()
+        .map(|errors| errors.clone())
+        .unwrap_or_default()
+}
 
-    // Validate configuration and report issues
-    let issues = config.validate();
+fn load_from(config_path: &Path) -> Result<Config, Vec<ConfigError>> {
+    let (config, issues) = parse_config(config_path).map_err(|e| {
+        log::error!("Failed to load config: {}", e);
+        vec![e]
+    })?;
+    log::info!("Loaded config from {:?}", config_path);
+
+    // Report validation issues
     let errors: Vec<_> = issues.iter().filter(|i| i.is_error).collect();
     let warnings: Vec<_> = issues.iter().filter(|i| !i.is_error).collect();
 
@@ -71,14 +96,302 @@ pub fn load_config() -> Config {
     }
 
     if !errors.is_empty() {
-        log::error!("Config has errors; falling back to defaults.");
-        return Config::default();
+        let config_dir = config_path.parent().unwrap_or(Path::new("."));
+        return Err(errors
+            .into_iter()
+            .map(|issue| issue_error(issue, config_dir))
+            .collect());
+    }
+
+    Ok(config)
+}
+
+/// Turns an error-level issue into a [`ConfigError`] quoting its line.
+fn issue_error(issue: &ConfigIssue, config_dir: &Path) -> ConfigError {
+    let snippet = issue.location.as_ref().and_then(|location| {
+        let contents = std::fs::read_to_string(config_dir.join(&location.file)).ok()?;
+        let line = contents.lines().nth(location.line.checked_sub(1)?)?;
+        Some(line.trim_end().to_string())
+    });
+    ConfigError {
+        message: format!("{}: {}", issue.path, issue.message),
+        location: issue.location.clone(),
+        column: None,
+        snippet,
+    }
+}
+
+/// Validates the config file and prints each issue, for `sinew --check-config`.
+///
+/// Returns the process exit code: 0 when the file has no errors, 1 otherwise.
+pub fn check_config() -> i32 {
+    let config_path = get_config_path();
+    let issues = match parse_config(&config_path) {
+        Ok((_, issues)) => issues,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+
+    for issue in &issues {
+        println!("{}", issue);
+    }
+    let errors = issues.iter().filter(|i| i.is_error).count();
+    println!(
+        "{}: {} error(s), {} warning(s)",
+        config_path.display(),
+        errors,
+        issues.len() - errors
+    );
+    if errors > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Prints the effective config as TOML, for `sinew --dump-config`.
+///
+/// Defaults fill in everything the file leaves out. `secret:` references are
+/// printed as written, never resolved. Returns the process exit code.
+pub fn dump_config() -> i32 {
+    let config_path = get_config_path();
+    let config = if config_path.exists() {
+        let parsed = read_config(&config_path).and_then(|merged| {
+            merged
+                .value
+                .try_into::<Config>()
+                .map_err(|e| ConfigError::new(e.to_string()))
+        });
+        match parsed {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("{}", e);
+                return 1;
+            }
+        }
+    } else {
+        Config::default()
+    };
+
+    // Mirror load_config: a config with errors is replaced by the defaults
+    let config = if config.validate().iter().any(|i| i.is_error) {
+        eprintln!(
+            "{} has errors (see sinew --check-config); showing the defaults used instead",
+            config_path.display()
+        );
+        Config::default()
+    } else {
+        config
+    };
+
+    match toml::to_string_pretty(&config) {
+        Ok(text) => {
+            print!("{}", text);
+            0
+        }
+        Err(e) => {
+            eprintln!("Failed to serialize config: {}", e);
+            1
+        }
+    }
+}
+
+/// Commented starter config written by `sinew --init`.
+const STARTER_CONFIG: &str = include_str!("starter.toml");
+
+/// Writes the starter config, for `sinew --init`. An existing config is
+/// never overwritten. Returns the process exit code.
+pub fn init_config() -> i32 {
+    let config_path = get_config_path();
+    if config_path.exists() {
+        eprintln!(
+            "{} already exists; move it away to start over",
+            config_path.display()
+        );
+        return 1;
+    }
+    let written = config_path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&config_path, STARTER_CONFIG));
+    match written {
+        Ok(()) => {
+            println!("Wrote {}", config_path.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("{}: {}", config_path.display(), e);
+            1
+        }
+    }
+}
+
+/// Config files read from disk and merged.
+struct MergedConfig {
+    value: toml::Value,
+    /// Unknown-key issues, located in the file they came from
+    issues: Vec<ConfigIssue>,
+    /// Text of config.toml when it includes nothing and no profile is
+    /// active. Paths in the merged config only map onto a single file's
+    /// lines in that case.
+    contents: Option<String>,
+}
+
+/// Name of a config file in messages: its path relative to the config
+/// directory.
+fn display_name(path: &Path, base: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+fn read_table(path: &Path, base: &Path) -> Result<(String, toml::Table), ConfigError> {
+    let name = display_name(path, base);
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| ConfigError::new(format!("{}: {}", name, e)))?;
+    let table = toml::from_str(&contents).map_err(|e| parse_error(&e, &name, &contents))?;
+    Ok((contents, table))
+}
+
+/// Locates a TOML error in `source`, the contents of config file `file`.
+fn parse_error(error: &toml::de::Error, file: &str, source: &str) -> ConfigError {
+    let message = error.message().to_string();
+    match error.span() {
+        Some(span) => ConfigError::at(message, file, source, span.start),
+        None => ConfigError::new(format!("{}: {}", file, message)),
+    }
+}
+
+fn warning(path: &str, message: String) -> ConfigIssue {
+    ConfigIssue {
+        path: path.to_string(),
+        message,
+        is_error: false,
+        location: None,
     }
+}
+
+/// Reads config.toml and the fragments it includes, merged into one value
+/// (see [`include`] for the merge rules), with the active profile applied.
+fn read_config(config_path: &Path) -> Result<MergedConfig, ConfigError> {
+    let base = config_path.parent().unwrap_or(Path::new("."));
+    let (contents, mut main) = read_table(config_path, base)?;
+    let patterns = include::patterns(&main);
+    main.remove("include");
+
+    let mut main_issues = Vec::new();
+    let mut fragment_issues = Vec::new();
+    let mut merged = toml::Table::new();
+    let mut seen = vec![config_path.to_path_buf()];
+    for pattern in &patterns {
+        let files = include::expand(pattern, base);
+        if !files.iter().any(|file| file.exists()) {
+            main_issues.push(warning(
+                "include",
+                format!("'{}' doesn't match any file", pattern),
+            ));
+        }
+        for file in files.into_iter().filter(|file| file.exists()) {
+            if seen.contains(&file) {
+                continue;
+            }
+            seen.push(file.clone());
+            let (text, mut fragment) = read_table(&file, base)?;
+            let mut issues = Vec::new();
+            if fragment.remove("include").is_some() {
+                issues.push(warning(
+                    "include",
+                    "include is only read from config.toml".to_string(),
+                ));
+            }
+            schema::check_keys(&fragment, &mut issues);
+            schema::add_lines(&text, &display_name(&file, base), &mut issues);
+            fragment_issues.extend(issues);
+            include::merge(&mut merged, fragment);
+        }
+    }
+
+    schema::check_keys(&main, &mut main_issues);
+    schema::add_lines(
+        &contents,
+        &display_name(config_path, base),
+        &mut main_issues,
+    );
+    include::merge(&mut merged, main);
+
+    let mut profile_issues = Vec::new();
+    let active = profile::apply(&mut merged, &mut profile_issues);
+    schema::add_lines(
+        &contents,
+        &display_name(config_path, base),
+        &mut profile_issues,
+    );
 
-    config
+    main_issues.extend(profile_issues);
+    main_issues.extend(fragment_issues);
+    Ok(MergedConfig {
+        value: toml::Value::Table(merged),
+        issues: main_issues,
+        contents: (patterns.is_empty() && active.is_none()).then_some(contents),
+    })
+}
+
+/// Reads and merges the config files, resolves `secret:` references and
+/// validates the result. Issues carry their location when it's known.
+fn parse_config(config_path: &Path) -> Result<(Config, Vec<ConfigIssue>), ConfigError> {
+    let MergedConfig {
+        mut value,
+        mut issues,
+        contents,
+    } = read_config(config_path)?;
+    let mut validation = Vec::new();
+    for (path, error) in secrets::resolve_secrets(&mut value) {
+        // The value becomes an empty string
+        validation.push(warning(&path, error));
+    }
+    let config: Config = value.try_into().map_err(|e: toml::de::Error| {
+        // The merged value has no positions; deserialize the file itself
+        // to find the offending line
+        contents
+            .as_deref()
+            .and_then(|contents| {
+                let error = toml::from_str::<Config>(contents).err()?;
+                Some(parse_error(&error, "config.toml", contents))
+            })
+            .unwrap_or_else(|| ConfigError::new(e.to_string()))
+    })?;
+    validation.extend(config.validate());
+    if let Some(contents) = contents {
+        schema::add_lines(&contents, "config.toml", &mut validation);
+    }
+    issues.extend(validation);
+    Ok((config, issues))
+}
+
+/// Reads the `include` patterns from config.toml, if it parses.
+fn include_patterns(config_path: &Path) -> Vec<String> {
+    std::fs::read_to_string(config_path)
+        .ok()
+        .and_then(|contents| contents.parse::<toml::Table>().ok())
+        .map(|table| include::patterns(&table))
+        .unwrap_or_default()
+}
+
+/// Config file given on the command line, read instead of the default one.
+static CONFIG_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Reads the config from `path` instead of ~/.config/sinew/config.toml.
+pub fn set_config_path(path: PathBuf) {
+    let _ = CONFIG_PATH_OVERRIDE.set(path);
 }
 
 pub fn get_config_path() -> PathBuf {
+    if let Some(path) = CONFIG_PATH_OVERRIDE.get() {
+        return path.clone();
+    }
     dirs::home_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join(".config")
@@ -86,19 +399,54 @@ pub fn get_config_path() -> PathBuf {
         .join("config.toml")
 }
 
+/// Line of config.toml that `path` (e.g. `modules.left.left[2]`) is set
+/// on, or None if it comes from an included file or isn't set.
+pub fn config_line(path: &str) -> Option<usize> {
+    let source = std::fs::read_to_string(get_config_path()).ok()?;
+    schema::line_of(&source, path)
+}
+
+/// Directory for disposable caches (downloaded images, etc).
+pub fn get_cache_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".cache")
+        .join("sinew")
+}
+
+/// Directory for persisted runtime state (recents, history, etc).
+pub fn get_state_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".local")
+        .join("state")
+        .join("sinew")
+}
+
 pub struct ConfigWatcher {
-    _watcher: RecommendedWatcher,
+    watcher: Mutex<RecommendedWatcher>,
     receiver: Receiver<Result<Event, notify::Error>>,
+    /// Directories holding included fragments
+    include_dirs: Mutex<Vec<PathBuf>>,
     config: SharedConfig,
     last_reload: Mutex<std::time::Instant>,
+    /// Profile generation the loaded config was built for
+    profile_generation: Mutex<u64>,
 }
 
 impl ConfigWatcher {
-    pub fn new(config: SharedConfig) -> Result<Self, notify::Error> {
+    /// Watches the config directory for changes to `config`. `wake` runs on
+    /// the watcher's thread after every file event, so the owner knows to
+    /// call [`Self::check_and_reload`].
+    pub fn new(
+        config: SharedConfig,
+        wake: impl Fn() + Send + 'static,
+    ) -> Result<Self, notify::Error> {
         let (tx, rx) = channel();
 
         let mut watcher = notify::recommended_watcher(move |res| {
             let _ = tx.send(res);
+            wake();
         })?;
 
         let config_path = get_config_path();
@@ -112,15 +460,58 @@ impl ConfigWatcher {
         watcher.watch(config_dir, RecursiveMode::NonRecursive)?;
         log::info!("Watching config directory: {:?}", config_dir);
 
-        Ok(Self {
-            _watcher: watcher,
+        let config_watcher = Self {
+            watcher: Mutex::new(watcher),
             receiver: rx,
+            include_dirs: Mutex::new(Vec::new()),
             config,
             last_reload: Mutex::new(std::time::Instant::now()),
-        })
+            profile_generation: Mutex::new(profile::generation()),
+        };
+        config_watcher.watch_includes();
+        Ok(config_watcher)
+    }
+
+    /// Watches the directories of the fragments config.toml includes.
+    /// Directories that are no longer included stay watched, but their
+    /// events are ignored.
+    fn watch_includes(&self) {
+        let config_path = get_config_path();
+        let config_dir = config_path.parent().unwrap_or(&config_path);
+        let dirs = include::directories(&include_patterns(&config_path), config_dir);
+        let (Ok(mut watcher), Ok(mut include_dirs)) =
+            (self.watcher.lock(), self.include_dirs.lock())
+        else {
+            return;
+        };
+        for dir in &dirs {
+            if dir == config_dir || include_dirs.contains(dir) || !dir.is_dir() {
+                continue;
+            }
+            match watcher.watch(dir, RecursiveMode::NonRecursive) {
+                Ok(()) => log::info!("Watching included config directory: {:?}", dir),
+                Err(e) => log::warn!("Failed to watch {:?}: {}", dir, e),
+            }
+        }
+        *include_dirs = dirs;
+    }
+
+    /// Returns true if a change to `path` can change the config.
+    fn affects_config(&self, path: &Path) -> bool {
+        if path.file_name().is_some_and(|n| n == "config.toml") {
+            return true;
+        }
+        let is_toml = path.extension().is_some_and(|ext| ext == "toml");
+        let included = path.parent().is_some_and(|dir| {
+            self.include_dirs
+                .lock()
+                .is_ok_and(|dirs| dirs.iter().any(|d| d == dir))
+        });
+        is_toml && included
     }
 
-    /// Check for config changes and reload if needed. Returns true if config was reloaded.
+    /// Check for config changes and reload if needed. Returns true if the
+    /// config was reloaded or failed to load (the previous one is kept).
     pub fn check_and_reload(&self) -> bool {
         use std::time::Duration;
 
@@ -130,12 +521,11 @@ impl ConfigWatcher {
         while let Ok(event) = self.receiver.try_recv() {
             match event {
                 Ok(event) => {
-                    let is_config = event
-                        .paths
-                        .iter()
-                        .any(|p| p.file_name().map(|n| n == "config.toml").unwrap_or(false));
+                    let is_config = event.paths.iter().any(|p| self.affects_config(p));
+                    let changed =
+                        event.kind.is_modify() || event.kind.is_create() || event.kind.is_remove();
 
-                    if is_config && (event.kind.is_modify() || event.kind.is_create()) {
+                    if is_config && changed {
                         should_reload = true;
                     }
                 }
@@ -145,27 +535,151 @@ impl ConfigWatcher {
             }
         }
 
+        // A profile switch reloads right away; file changes are debounced
+        let generation = profile::generation();
+        let profile_switched = self
+            .profile_generation
+            .lock()
+            .is_ok_and(|seen| *seen != generation);
+
         // Debounce: only reload if 500ms have passed since last reload
+        let now = std::time::Instant::now();
         if should_reload {
-            let now = std::time::Instant::now();
             let elapsed = self
                 .last_reload
                 .lock()
                 .map(|t| now.duration_since(*t))
                 .unwrap_or(Duration::ZERO);
-            if elapsed > Duration::from_millis(500) {
-                log::info!("Config file changed, reloading...");
-                let new_config = load_config();
+            should_reload = elapsed > Duration::from_millis(500);
+        }
+
+        if profile_switched {
+            log::info!("Config profile changed, reloading...");
+        } else if should_reload {
+            log::info!("Config file changed, reloading...");
+        } else {
+            return false;
+        }
+
+        if let Ok(mut seen) = self.profile_generation.lock() {
+            *seen = generation;
+        }
+        let reloaded = try_load_config();
+        self.watch_includes();
+        if let Ok(mut t) = self.last_reload.lock() {
+            *t = now;
+        }
+        match reloaded {
+            Ok(new_config) => {
                 if let Ok(mut cfg) = self.config.write() {
                     *cfg = new_config;
-                    if let Ok(mut t) = self.last_reload.lock() {
-                        *t = now;
-                    }
-                    return true;
                 }
             }
+            // The bar still rebuilds, to show the errors
+            Err(_) => log::error!("Config has errors; keeping the previous config."),
         }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starter_config_is_clean_and_covers_every_module_type() {
+        let table: toml::Table = toml::from_str(STARTER_CONFIG).expect("starter should parse");
+        let mut issues = Vec::new();
+        schema::check_keys(&table, &mut issues);
+        let config: Config = toml::Value::Table(table)
+            .try_into()
+            .expect("starter should deserialize");
+        issues.extend(config.validate());
+        assert!(issues.is_empty(), "{:?}", issues);
 
-        false
+        for module_type in types::DEFAULT_MODULE_TYPES {
+            assert!(
+                STARTER_CONFIG.contains(&format!("\"{}\"", module_type)),
+                "no example for {}",
+                module_type
+            );
+        }
+    }
+
+    #[test]
+    fn dumped_config_parses_back() {
+        let dumped = toml::to_string_pretty(&Config::default()).unwrap();
+        let parsed: Config = toml::from_str(&dumped).expect("dump should parse");
+        assert_eq!(toml::to_string_pretty(&parsed).unwrap(), dumped);
+        assert!(dumped.contains("height = \"auto\""));
+    }
+
+    #[test]
+    fn locates_parse_and_type_errors() {
+        let dir = std::env::temp_dir().join(format!("sinew-errors-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        let error_for = |contents: &str| {
+            std::fs::write(&path, contents).unwrap();
+            parse_config(&path).unwrap_err()
+        };
+
+        let syntax = error_for("[bar]\nfont_size = 13.0\npadding = \n");
+        let type_error = error_for("[bar]\nfont_size = \"big\"\n");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let location = syntax.location.as_ref().unwrap();
+        assert_eq!((location.file.as_str(), location.line), ("config.toml", 3));
+        assert_eq!(syntax.column, Some(11));
+        assert_eq!(syntax.snippet.as_deref(), Some("padding ="));
+
+        assert_eq!(type_error.location.as_ref().map(|l| l.line), Some(2));
+        assert_eq!(type_error.snippet.as_deref(), Some("font_size = \"big\""));
+        assert!(type_error.to_string().starts_with("config.toml:2:13: "));
+    }
+
+    #[test]
+    fn merges_included_fragments_before_main_config() {
+        let dir = std::env::temp_dir().join(format!("sinew-include-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("modules")).unwrap();
+        let write = |name: &str, contents: &str| std::fs::write(dir.join(name), contents).unwrap();
+        write(
+            "config.toml",
+            "include = [\"modules/*.toml\", \"missing.toml\"]\n\n[bar]\nfont_size = 15.0\n",
+        );
+        write(
+            "modules/b.toml",
+            "[[modules.left.left]]\ntype = \"memory\"\n",
+        );
+        write(
+            "modules/a.toml",
+            "[bar]\nfont_size = 11.0\npadding = 8.0\n\n[[modules.left.left]]\ntype = \"cpu\"\nlable = \"CPU\"\n",
+        );
+
+        let (config, issues) = parse_config(&dir.join("config.toml")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(config.bar.font_size, 15.0);
+        assert_eq!(config.bar.padding, 8.0);
+        let types: Vec<&str> = config
+            .modules
+            .left
+            .outer
+            .iter()
+            .map(|m| m.module_type.as_str())
+            .collect();
+        assert_eq!(types, ["cpu", "memory"]);
+
+        let located: Vec<String> = issues.iter().map(|issue| issue.to_string()).collect();
+        assert!(
+            located[0].starts_with("[WARNING] config.toml:1: include: 'missing.toml'"),
+            "{:?}",
+            located
+        );
+        assert!(
+            located[1].starts_with("[WARNING] modules/a.toml:7: "),
+            "{:?}",
+            located
+        );
     }
 }
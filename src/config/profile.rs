@@ -0,0 +1,405 @@
+//! Config profiles: named overrides switched at runtime.
+//!
+//! ```toml
+//! [profile.work]
+//! when = { ssid = ["CorpNet"], displays = ">= 2" }
+//!
+//! [profile.work.bar]
+//! background_color = "#1e3a5f"
+//!
+//! [[profile.work.modules.right.right]]
+//! type = "clock"
+//! ```
+//!
+//! The active profile is laid over the rest of the config: tables merge key
+//! by key and anything else, module lists included, is replaced. A profile
+//! is active when it was picked with `sinew --profile` or the `profile` IPC
+//! command, or otherwise when all of its `when` rules match; profiles are
+//! checked in name order and the first match wins.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use super::types::{Condition, ConfigIssue};
+
+/// Requests that turn the `when` rules back on.
+pub const AUTO: &str = "auto";
+/// Requests the config without any profile.
+pub const NONE: &str = "none";
+
+/// What `when` rules are checked against.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Environment {
+    /// Wi-Fi network name, when connected and a rule asks for it
+    pub ssid: Option<String>,
+    /// Number of connected displays
+    pub displays: usize,
+}
+
+/// A profile's `when` rules. A profile without rules is only used when
+/// picked by name.
+#[derive(Debug, Clone, Default)]
+struct Rules {
+    /// Any of these networks matches
+    ssid: Vec<String>,
+    displays: Option<Condition>,
+}
+
+impl Rules {
+    /// Parses a profile's `when` table, reporting values it can't read.
+    fn parse(when: Option<&toml::Value>, path: &str, issues: &mut Vec<ConfigIssue>) -> Self {
+        let mut rules = Self::default();
+        let Some(when) = when else {
+            return rules;
+        };
+        let Some(table) = when.as_table() else {
+            issues.push(warning(path, "when must be a table".to_string()));
+            return rules;
+        };
+        match table.get("ssid") {
+            None => {}
+            Some(toml::Value::String(ssid)) => rules.ssid.push(ssid.clone()),
+            Some(toml::Value::Array(items)) if items.iter().all(|v| v.is_str()) => {
+                rules.ssid = items
+                    .iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect();
+            }
+            Some(_) => issues.push(warning(
+                &format!("{}.ssid", path),
+                "ssid must be a network name or a list of names".to_string(),
+            )),
+        }
+        match table.get("displays") {
+            None => {}
+            Some(toml::Value::Integer(count)) => {
+                rules.displays = Condition::parse(&format!("== {}", count));
+            }
+            Some(toml::Value::String(condition)) => match Condition::parse(condition) {
+                Some(condition) => rules.displays = Some(condition),
+                None => issues.push(warning(
+                    &format!("{}.displays", path),
+                    format!(
+                        "invalid condition '{}', expected e.g. '>= 2' or a number",
+                        condition
+                    ),
+                )),
+            },
+            Some(_) => issues.push(warning(
+                &format!("{}.displays", path),
+                "displays must be a number or a condition such as '>= 2'".to_string(),
+            )),
+        }
+        rules
+    }
+
+    fn is_empty(&self) -> bool {
+        self.ssid.is_empty() && self.displays.is_none()
+    }
+
+    /// Returns true if every rule matches. Empty rules never match.
+    fn matches(&self, environment: &Environment) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let ssid = self.ssid.is_empty()
+            || environment
+                .ssid
+                .as_ref()
+                .is_some_and(|ssid| self.ssid.contains(ssid));
+        let displays = self
+            .displays
+            .is_none_or(|condition| condition.matches(environment.displays as f64));
+        ssid && displays
+    }
+}
+
+/// Picks the profile to use: the requested one, or else the first whose
+/// rules match. Returns None for the plain config.
+fn select(
+    profiles: &[(String, Rules)],
+    requested: Option<&str>,
+    environment: Option<&Environment>,
+) -> Option<String> {
+    match requested {
+        Some(NONE) => return None,
+        Some(name) if profiles.iter().any(|(n, _)| n == name) => return Some(name.to_string()),
+        _ => {}
+    }
+    let environment = environment?;
+    profiles
+        .iter()
+        .find(|(_, rules)| rules.matches(environment))
+        .map(|(name, _)| name.clone())
+}
+
+/// Lays `profile` over `base`: tables merge, other values are replaced.
+fn overlay(base: &mut toml::Table, profile: toml::Table) {
+    for (key, value) in profile {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(table)) => {
+                overlay(existing, table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn warning(path: &str, message: String) -> ConfigIssue {
+    ConfigIssue {
+        path: path.to_string(),
+        message,
+        is_error: false,
+        location: None,
+    }
+}
+
+#[derive(Debug, Default)]
+struct ProfileState {
+    /// Profile picked by name; None follows the rules
+    requested: Option<String>,
+    environment: Option<Environment>,
+    /// Rules of every profile in the loaded config, in name order
+    profiles: Vec<(String, Rules)>,
+    /// Profile the loaded config uses
+    active: Option<String>,
+}
+
+static STATE: OnceLock<Mutex<ProfileState>> = OnceLock::new();
+/// Bumped whenever a different profile should be active
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn state() -> &'static Mutex<ProfileState> {
+    STATE.get_or_init(|| Mutex::new(ProfileState::default()))
+}
+
+/// Bumps the generation if `state` now selects a different profile.
+fn note_selection(state: &ProfileState) -> bool {
+    let selected = select(
+        &state.profiles,
+        state.requested.as_deref(),
+        state.environment.as_ref(),
+    );
+    let changed = selected != state.active;
+    if changed {
+        GENERATION.fetch_add(1, Ordering::Relaxed);
+    }
+    changed
+}
+
+/// Picks a profile by name, or returns to the rules with None or "auto".
+/// Returns true if the config needs reloading.
+pub fn request(name: Option<&str>) -> bool {
+    let Ok(mut state) = state().lock() else {
+        return false;
+    };
+    state.requested = name.filter(|n| *n != AUTO).map(String::from);
+    note_selection(&state)
+}
+
+/// Records the environment the rules are checked against. Returns true if
+/// the config needs reloading.
+pub fn set_environment(environment: Environment) -> bool {
+    let Ok(mut state) = state().lock() else {
+        return false;
+    };
+    if state.environment.as_ref() == Some(&environment) {
+        return false;
+    }
+    state.environment = Some(environment);
+    note_selection(&state)
+}
+
+/// Counter that changes whenever a different profile should be active.
+pub fn generation() -> u64 {
+    GENERATION.load(Ordering::Relaxed)
+}
+
+/// Returns true if any profile has an `ssid` rule.
+pub fn needs_ssid() -> bool {
+    state().lock().is_ok_and(|state| {
+        state
+            .profiles
+            .iter()
+            .any(|(_, rules)| !rules.ssid.is_empty())
+    })
+}
+
+/// Active profile, requested profile and all profile names.
+pub fn status() -> (Option<String>, Option<String>, Vec<String>) {
+    state()
+        .lock()
+        .map(|state| {
+            let names = state
+                .profiles
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect();
+            (state.active.clone(), state.requested.clone(), names)
+        })
+        .unwrap_or_default()
+}
+
+/// Removes the `profile` table from a merged config and lays the active
+/// profile over the rest. Returns the name of the profile applied.
+pub fn apply(root: &mut toml::Table, issues: &mut Vec<ConfigIssue>) -> Option<String> {
+    let mut tables = match root.remove("profile") {
+        Some(toml::Value::Table(tables)) => tables,
+        Some(_) => {
+            issues.push(warning("profile", "profile must be a table".to_string()));
+            toml::Table::new()
+        }
+        None => toml::Table::new(),
+    };
+    let mut profiles = Vec::new();
+    for (name, value) in &tables {
+        let path = format!("profile.{}", name);
+        if name == AUTO || name == NONE {
+            issues.push(warning(
+                &path,
+                format!("'{}' is reserved and can't name a profile", name),
+            ));
+            continue;
+        }
+        let Some(table) = value.as_table() else {
+            issues.push(warning(&path, "a profile must be a table".to_string()));
+            continue;
+        };
+        let rules = Rules::parse(table.get("when"), &format!("{}.when", path), issues);
+        profiles.push((name.clone(), rules));
+    }
+
+    let Ok(mut state) = state().lock() else {
+        return None;
+    };
+    if let Some(requested) = state.requested.as_deref() {
+        if requested != NONE && !profiles.iter().any(|(name, _)| name == requested) {
+            issues.push(warning(
+                "profile",
+                format!("profile '{}' isn't defined; using the rules", requested),
+            ));
+        }
+    }
+    let active = select(
+        &profiles,
+        state.requested.as_deref(),
+        state.environment.as_ref(),
+    );
+    state.profiles = profiles;
+    state.active = active.clone();
+    drop(state);
+
+    if let Some(name) = &active {
+        if let Some(toml::Value::Table(mut profile)) = tables.remove(name) {
+            profile.remove("when");
+            overlay(root, profile);
+        }
+        log::info!("Using config profile '{}'", name);
+    }
+    active
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(source: &str) -> Rules {
+        let value: toml::Value = toml::from_str(source).unwrap();
+        let mut issues = Vec::new();
+        let rules = Rules::parse(value.get("when"), "profile.test.when", &mut issues);
+        assert!(issues.is_empty(), "{:?}", issues);
+        rules
+    }
+
+    fn environment(ssid: Option<&str>, displays: usize) -> Environment {
+        Environment {
+            ssid: ssid.map(String::from),
+            displays,
+        }
+    }
+
+    #[test]
+    fn rules_match_ssid_and_display_count() {
+        let work = rules(r#"when = { ssid = ["Corp", "Corp-5G"], displays = ">= 2" }"#);
+        assert!(work.matches(&environment(Some("Corp-5G"), 2)));
+        assert!(!work.matches(&environment(Some("Corp"), 1)));
+        assert!(!work.matches(&environment(None, 3)));
+
+        let docked = rules("when = { displays = 3 }");
+        assert!(docked.matches(&environment(None, 3)));
+        assert!(!docked.matches(&environment(None, 2)));
+
+        assert!(!rules("other = 1").matches(&environment(Some("Corp"), 1)));
+    }
+
+    #[test]
+    fn reports_unreadable_rules() {
+        let value: toml::Value =
+            toml::from_str(r#"when = { ssid = 5, displays = "lots" }"#).unwrap();
+        let mut issues = Vec::new();
+        Rules::parse(value.get("when"), "profile.x.when", &mut issues);
+        let paths: Vec<&str> = issues.iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(paths, ["profile.x.when.ssid", "profile.x.when.displays"]);
+    }
+
+    #[test]
+    fn requested_profile_wins_over_rules() {
+        let profiles = vec![
+            ("home".to_string(), rules(r#"when = { ssid = "Home" }"#)),
+            ("work".to_string(), rules(r#"when = { ssid = "Corp" }"#)),
+        ];
+        let at_home = environment(Some("Home"), 1);
+        assert_eq!(
+            select(&profiles, None, Some(&at_home)),
+            Some("home".to_string())
+        );
+        assert_eq!(
+            select(&profiles, Some("work"), Some(&at_home)),
+            Some("work".to_string())
+        );
+        assert_eq!(select(&profiles, Some(NONE), Some(&at_home)), None);
+        // Unknown names fall back to the rules
+        assert_eq!(
+            select(&profiles, Some("gym"), Some(&at_home)),
+            Some("home".to_string())
+        );
+        assert_eq!(select(&profiles, None, None), None);
+    }
+
+    #[test]
+    fn overlay_replaces_module_lists() {
+        let mut base: toml::Table = toml::from_str(
+            r##"
+[bar]
+font_size = 13.0
+background_color = "#000000"
+
+[[modules.right.right]]
+type = "clock"
+
+[[modules.right.right]]
+type = "battery"
+"##,
+        )
+        .unwrap();
+        let profile: toml::Table = toml::from_str(
+            r##"
+[bar]
+background_color = "#1e3a5f"
+
+[[modules.right.right]]
+type = "vpn"
+"##,
+        )
+        .unwrap();
+        overlay(&mut base, profile);
+
+        assert_eq!(base["bar"]["font_size"].as_float(), Some(13.0));
+        assert_eq!(base["bar"]["background_color"].as_str(), Some("#1e3a5f"));
+        let modules = base["modules"]["right"]["right"].as_array().unwrap();
+        assert_eq!(modules.len(), 1);
+        assert_eq!(modules[0]["type"].as_str(), Some("vpn"));
+    }
+}
@@ -0,0 +1,669 @@
+//! Known config keys, for catching typos.
+//!
+//! Serde skips keys it doesn't recognize, so a misspelled option silently
+//! does nothing. [`check_keys`] walks the raw TOML and reports every key
+//! that no table (or, for modules, no module type) reads, with a "did you
+//! mean" suggestion when a known key is close. [`add_lines`] then maps each
+//! issue's path back to its line in the file.
+
+use toml_edit::{ImDocument, Item, Table, TableLike, Value};
+
+use super::types::{ConfigIssue, Location, DEFAULT_MODULE_TYPES};
+
+const TOP_LEVEL_KEYS: &[&str] = &[
+    "include", "bar", "modules", "hotkeys", "alerts", "clock", "profile",
+];
+
+const ALERTS_KEYS: &[&str] = &["interval", "rules"];
+
+/// Keys of an `[[alerts.rules]]` entry
+const ALERT_RULE_KEYS: &[&str] = &[
+    "name", "when", "message", "actions", "command", "style", "repeat",
+];
+
+/// Keys of a `[profile.<name>]` table
+const PROFILE_KEYS: &[&str] = &["when", "bar", "modules", "hotkeys"];
+
+const WHEN_KEYS: &[&str] = &["ssid", "displays"];
+
+const BAR_KEYS: &[&str] = &[
+    "height",
+    "background_color",
+    "text_color",
+    "font_size",
+    "font_family",
+    "padding",
+    "hover_effects",
+    "border_color",
+    "border_width",
+    "border_radius",
+    "screen_corner_radius",
+    "popup_background_color",
+    "popup_text_color",
+    "theme",
+    "camera_indicator",
+    "launch_at_login",
+    "debug_layout",
+    "overflow",
+    "module_spacing",
+    "justify",
+    "hud",
+    "tooltip",
+    "notch",
+    "fake_notch",
+    "toast",
+    "progress",
+    "notify",
+    "calendar",
+    "http",
+    "idle",
+    "power_saving",
+    "editor",
+];
+
+const THEME_KEYS: &[&str] = &[
+    "muted",
+    "muted_foreground",
+    "accent",
+    "accent_foreground",
+    "destructive",
+    "success",
+    "warning",
+    "card",
+    "card_foreground",
+    "border",
+];
+
+const HUD_KEYS: &[&str] = &["enabled", "style", "timeout_ms", "replace_system"];
+
+const TOOLTIP_KEYS: &[&str] = &["enabled", "delay_ms"];
+
+const NOTCH_KEYS: &[&str] = &[
+    "enabled",
+    "trigger",
+    "width",
+    "now_playing",
+    "calendar",
+    "widgets",
+];
+
+const FAKE_NOTCH_KEYS: &[&str] = &["enabled", "width", "color", "displays"];
+
+const FAKE_NOTCH_DISPLAY_KEYS: &[&str] = &["name", "enabled", "width", "color"];
+
+const TOAST_KEYS: &[&str] = &["zone", "timeout"];
+
+const PROGRESS_KEYS: &[&str] = &["zone", "hide_delay"];
+
+const NOTIFY_KEYS: &[&str] = &["zone", "ttl"];
+
+const CALENDAR_KEYS: &[&str] = &["week_numbers", "weekends", "holidays", "holidays_file"];
+
+const HTTP_KEYS: &[&str] = &["enabled", "address", "port", "token"];
+
+const IDLE_KEYS: &[&str] = &["pause_when_locked", "pause_after"];
+
+const POWER_SAVING_KEYS: &[&str] = &[
+    "battery_below",
+    "low_power_mode",
+    "slowdown",
+    "pause_animations",
+];
+
+/// Legacy top-level `[clock]` table
+const CLOCK_KEYS: &[&str] = &["format", "position"];
+
+const HALF_KEYS: &[&str] = &["left", "right"];
+
+const SECTION_KEYS: &[&str] = &["left", "right", "center"];
+
+/// Keys every module accepts (the fields of `ModuleConfig`)
+const MODULE_KEYS: &[&str] = &[
+    "type",
+    "id",
+    "text",
+    "icon",
+    "format",
+    "date_format",
+    "time_format",
+    "font_size",
+    "color",
+    "background",
+    "border_color",
+    "border_width",
+    "corner_radius",
+    "flex",
+    "min_width",
+    "max_width",
+    "margin_left",
+    "margin_right",
+    "spill",
+    "separator_type",
+    "separator_width",
+    "separator_color",
+    "path",
+    "max_length",
+    "padding",
+    "command",
+    "interval",
+    "click_command",
+    "right_click_command",
+    "middle_click_command",
+    "double_click_command",
+    "group",
+    "critical_color",
+    "warning_color",
+    "critical_threshold",
+    "warning_threshold",
+    "color_rules",
+    "popup",
+    "popup_width",
+    "popup_height",
+    "popup_max_height",
+    "popup_command",
+    "popup_anchor",
+    "popup_on_hover",
+    "popup_hover_delay_ms",
+    "location",
+    "update_interval",
+    "show_while_loading",
+    "power_saving",
+    "history",
+    "context_menu",
+    "menu",
+    "toggle",
+    "toggle_group",
+    "active_background",
+    "active_border_color",
+    "active_color",
+    "label",
+    "label_font_size",
+    "label_align",
+    "value_fixed_width",
+    "temp_unit",
+    "display",
+    "skeleton_width",
+    "skeleton_height",
+];
+
+/// Returns the extra options a module type reads beyond [`MODULE_KEYS`].
+fn module_options(module_type: &str) -> &'static [&'static str] {
+    match module_type {
+        "clock" => &["zones", "blink", "hour12", "toggle_hour12"],
+        "battery" => &[
+            "emergency",
+            "alerts",
+            "format_charging",
+            "format_full",
+            "format_low",
+        ],
+        "disk" => &["io"],
+        "vpn" => &["name"],
+        "shortcuts" => &["shortcuts"],
+        "window_title" => &["rewrite", "marquee"],
+        "now_playing" => &["sources", "marquee"],
+        "static" => &["image"],
+        "launcher" => &["commands"],
+        "emoji" => &["action"],
+        "privacy" => &[
+            "camera",
+            "microphone",
+            "screen",
+            "flash",
+            "flash_ms",
+            "border",
+        ],
+        "docker" => &["socket"],
+        "ics" => &["urls", "url"],
+        "timetrack" => &["project", "toggl_token", "toggl_workspace", "idle_stop"],
+        "screenshare" => &["processes"],
+        "mqtt" => &[
+            "broker",
+            "topics",
+            "topic",
+            "json_path",
+            "click_topic",
+            "click_payload",
+            "username",
+            "password",
+        ],
+        "homeassistant" => &[
+            "url",
+            "token",
+            "entities",
+            "entity",
+            "attribute",
+            "count_state",
+            "click_service",
+            "click_data",
+        ],
+        "ci" => &["pipelines", "token", "runs"],
+        "updates" => &["mas", "brew_update", "update_command"],
+        "folder" => &["empty_trash"],
+        "applescript" => &["script", "file", "language"],
+        "wasm" => &["config"],
+        "script" => &[
+            "waybar_compat",
+            "restart_interval",
+            "format_icons",
+            "class_colors",
+        ],
+        _ => &[],
+    }
+}
+
+/// Returns the candidate closest to `key`, if it's a plausible typo of it.
+pub fn suggest<'a>(key: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let limit = (key.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(key, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Reports keys of `table` that aren't in `known` or `extra`.
+fn check_table(
+    table: &toml::Table,
+    path: &str,
+    known: &[&'static str],
+    extra: &[&'static str],
+    owner: &str,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    let mut keys: Vec<&String> = table.keys().collect();
+    keys.sort();
+    for key in keys {
+        if known.contains(&key.as_str()) || extra.contains(&key.as_str()) {
+            continue;
+        }
+        let message = match suggest(key, known.iter().chain(extra).copied()) {
+            Some(similar) => format!(
+                "unknown option '{}' for {}, did you mean '{}'?",
+                key, owner, similar
+            ),
+            None => format!("unknown option '{}' for {}", key, owner),
+        };
+        issues.push(ConfigIssue {
+            path: child_path(path, key),
+            message,
+            is_error: false, // Warning, the key is ignored
+            location: None,
+        });
+    }
+}
+
+fn check_module(module: &toml::Value, path: &str, issues: &mut Vec<ConfigIssue>) {
+    let Some(table) = module.as_table() else {
+        return;
+    };
+    let Some(module_type) = table.get("type").and_then(|v| v.as_str()) else {
+        return;
+    };
+    // Plugin modules read options only the plugin knows
+    if !DEFAULT_MODULE_TYPES.contains(&module_type) {
+        return;
+    }
+    check_table(
+        table,
+        path,
+        MODULE_KEYS,
+        module_options(module_type),
+        &format!("{} modules", module_type),
+        issues,
+    );
+}
+
+/// Reports keys in the raw config that nothing reads.
+pub fn check_keys(root: &toml::Table, issues: &mut Vec<ConfigIssue>) {
+    check_table(root, "", TOP_LEVEL_KEYS, &[], "the config", issues);
+    check_sections(root, "", issues);
+
+    if let Some(alerts) = root.get("alerts").and_then(|v| v.as_table()) {
+        check_table(alerts, "alerts", ALERTS_KEYS, &[], "[alerts]", issues);
+        let rules = alerts.get("rules").and_then(|v| v.as_array());
+        for (i, rule) in rules.into_iter().flatten().enumerate() {
+            if let Some(rule) = rule.as_table() {
+                let path = format!("alerts.rules[{}]", i);
+                check_table(
+                    rule,
+                    &path,
+                    ALERT_RULE_KEYS,
+                    &[],
+                    "[[alerts.rules]]",
+                    issues,
+                );
+            }
+        }
+    }
+
+    if let Some(clock) = root.get("clock").and_then(|v| v.as_table()) {
+        check_table(clock, "clock", CLOCK_KEYS, &[], "[clock]", issues);
+    }
+
+    let profiles = root.get("profile").and_then(|v| v.as_table());
+    for (name, profile) in profiles.into_iter().flatten() {
+        let Some(profile) = profile.as_table() else {
+            continue;
+        };
+        let path = child_path("profile", name);
+        let owner = format!("[{}]", path);
+        check_table(profile, &path, PROFILE_KEYS, &[], &owner, issues);
+        if let Some(when) = profile.get("when").and_then(|v| v.as_table()) {
+            let path = child_path(&path, "when");
+            let owner = format!("{}.when", owner);
+            check_table(when, &path, WHEN_KEYS, &[], &owner, issues);
+        }
+        check_sections(profile, &path, issues);
+    }
+}
+
+/// Checks the `bar` and `modules` tables under `prefix` (the top level or
+/// a profile).
+fn check_sections(root: &toml::Table, prefix: &str, issues: &mut Vec<ConfigIssue>) {
+    if let Some(bar) = root.get("bar").and_then(|v| v.as_table()) {
+        let bar_path = child_path(prefix, "bar");
+        check_table(
+            bar,
+            &bar_path,
+            BAR_KEYS,
+            &[],
+            &format!("[{}]", bar_path),
+            issues,
+        );
+        for (key, known) in [
+            ("theme", THEME_KEYS),
+            ("hud", HUD_KEYS),
+            ("tooltip", TOOLTIP_KEYS),
+            ("notch", NOTCH_KEYS),
+            ("fake_notch", FAKE_NOTCH_KEYS),
+            ("toast", TOAST_KEYS),
+            ("progress", PROGRESS_KEYS),
+            ("notify", NOTIFY_KEYS),
+            ("calendar", CALENDAR_KEYS),
+            ("http", HTTP_KEYS),
+            ("idle", IDLE_KEYS),
+            ("power_saving", POWER_SAVING_KEYS),
+        ] {
+            if let Some(table) = bar.get(key).and_then(|v| v.as_table()) {
+                let path = child_path(&bar_path, key);
+                check_table(table, &path, known, &[], &format!("[{}]", path), issues);
+            }
+        }
+        let widgets = bar
+            .get("notch")
+            .and_then(|notch| notch.get("widgets"))
+            .and_then(|v| v.as_array());
+        for (i, module) in widgets.into_iter().flatten().enumerate() {
+            let path = format!("{}.notch.widgets[{}]", bar_path, i);
+            check_module(module, &path, issues);
+        }
+        let displays = bar
+            .get("fake_notch")
+            .and_then(|notch| notch.get("displays"))
+            .and_then(|v| v.as_array());
+        for (i, display) in displays.into_iter().flatten().enumerate() {
+            let Some(display) = display.as_table() else {
+                continue;
+            };
+            let path = format!("{}.fake_notch.displays[{}]", bar_path, i);
+            let owner = format!("[[{}.fake_notch.displays]]", bar_path);
+            check_table(display, &path, FAKE_NOTCH_DISPLAY_KEYS, &[], &owner, issues);
+        }
+    }
+
+    if let Some(modules) = root.get("modules").and_then(|v| v.as_table()) {
+        let modules_path = child_path(prefix, "modules");
+        let owner = format!("[{}]", modules_path);
+        check_table(modules, &modules_path, HALF_KEYS, &[], &owner, issues);
+        for half in HALF_KEYS {
+            let Some(sections) = modules.get(*half).and_then(|v| v.as_table()) else {
+                continue;
+            };
+            let path = child_path(&modules_path, half);
+            check_table(
+                sections,
+                &path,
+                SECTION_KEYS,
+                &[],
+                &format!("[{}]", path),
+                issues,
+            );
+            for section in SECTION_KEYS {
+                let entries = sections.get(*section).and_then(|v| v.as_array());
+                for (i, module) in entries.into_iter().flatten().enumerate() {
+                    check_module(module, &format!("{}.{}[{}]", path, section, i), issues);
+                }
+            }
+        }
+    }
+}
+
+/// Splits an issue path segment like `left[2]` into its key and index.
+fn split_index(segment: &str) -> (&str, Option<usize>) {
+    segment
+        .strip_suffix(']')
+        .and_then(|rest| rest.split_once('['))
+        .and_then(|(key, index)| Some((key, Some(index.parse().ok()?))))
+        .unwrap_or((segment, None))
+}
+
+/// Returns the start offset and (if it's a table) contents of element
+/// `index` of an array item.
+fn element(item: &Item, index: usize) -> Option<(Option<usize>, Option<&dyn TableLike>)> {
+    match item {
+        Item::ArrayOfTables(tables) => {
+            let table = tables.get(index)?;
+            Some((table.span().map(|s| s.start), Some(table as &dyn TableLike)))
+        }
+        Item::Value(Value::Array(values)) => {
+            let value = values.get(index)?;
+            let table = value.as_inline_table().map(|t| t as &dyn TableLike);
+            Some((value.span().map(|s| s.start), table))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the byte offset of the deepest part of `path` present in `root`.
+///
+/// Segments are split on dots, but a table key that itself contains dots
+/// (like the zone names under `bar.justify`) is matched whole.
+fn locate(root: &Table, path: &str) -> Option<usize> {
+    let segments: Vec<&str> = path.split('.').collect();
+    let mut table: &dyn TableLike = root;
+    let mut offset = None;
+    let mut start = 0;
+    while start < segments.len() {
+        let found = (start + 1..=segments.len()).rev().find_map(|end| {
+            let joined = segments[start..end].join(".");
+            let (name, index) = split_index(&joined);
+            let (key, item) = table.get_key_value(name)?;
+            Some((end, key, item, index))
+        });
+        let Some((end, key, item, index)) = found else {
+            break;
+        };
+        offset = key
+            .span()
+            .or_else(|| item.span())
+            .map(|s| s.start)
+            .or(offset);
+        start = end;
+        let next = match index {
+            Some(index) => match element(item, index) {
+                Some((element_offset, next)) => {
+                    offset = element_offset.or(offset);
+                    next
+                }
+                None => None,
+            },
+            None => item.as_table_like(),
+        };
+        match next {
+            Some(next) => table = next,
+            None => break,
+        }
+    }
+    offset
+}
+
+/// Sets each issue's location from where its path appears in `source`,
+/// the contents of config file `file`.
+pub fn add_lines(source: &str, file: &str, issues: &mut [ConfigIssue]) {
+    let Ok(document) = ImDocument::parse(source) else {
+        return;
+    };
+    for issue in issues {
+        issue.location = line(document.as_table(), source, &issue.path).map(|line| Location {
+            file: file.to_string(),
+            line,
+        });
+    }
+}
+
+/// Returns the line `path` appears on in `source`, if it does.
+pub fn line_of(source: &str, path: &str) -> Option<usize> {
+    let document = ImDocument::parse(source).ok()?;
+    line(document.as_table(), source, path)
+}
+
+fn line(root: &Table, source: &str, path: &str) -> Option<usize> {
+    locate(root, path).map(|offset| source[..offset].matches('\n').count() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issues_for(source: &str) -> Vec<ConfigIssue> {
+        let table: toml::Table = toml::from_str(source).unwrap();
+        let mut issues = Vec::new();
+        check_keys(&table, &mut issues);
+        add_lines(source, "config.toml", &mut issues);
+        issues
+    }
+
+    #[test]
+    fn suggests_close_keys_only() {
+        assert_eq!(
+            suggest("colour", MODULE_KEYS.iter().copied()),
+            Some("color")
+        );
+        assert_eq!(suggest("clok", ["clock", "cpu"]), Some("clock"));
+        assert_eq!(suggest("banana", MODULE_KEYS.iter().copied()), None);
+    }
+
+    #[test]
+    fn reports_unknown_module_options_with_lines() {
+        let issues = issues_for(
+            r##"
+[bar]
+font_sise = 13
+
+[[modules.left.left]]
+type = "clock"
+blink = true
+colour = "#fff"
+
+[modules.right]
+right = [{ type = "disk", io = true, marquee = true }]
+"##,
+        );
+        let paths: Vec<(&str, Option<usize>)> = issues
+            .iter()
+            .map(|issue| {
+                let line = issue.location.as_ref().map(|location| location.line);
+                (issue.path.as_str(), line)
+            })
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("bar.font_sise", Some(3)),
+                ("modules.left.left[0].colour", Some(8)),
+                ("modules.right.right[0].marquee", Some(11)),
+            ]
+        );
+        assert!(issues[1].message.contains("did you mean 'color'"));
+        assert!(issues.iter().all(|issue| !issue.is_error));
+    }
+
+    #[test]
+    fn leaves_plugin_module_options_to_the_plugin() {
+        let issues = issues_for(
+            r#"
+[[modules.left.left]]
+type = "pomodoro"
+minutes = 25
+"#,
+        );
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn locates_dotted_keys_and_array_elements() {
+        let source = "[bar.justify]\n\"left.left\" = \"middle\"\n\n[bar.calendar]\nholidays = [\n  \"12-25\",\n  \"nope\",\n]\n";
+        let line = |path: &str| line_of(source, path);
+        assert_eq!(line("bar.justify.left.left"), Some(2));
+        assert_eq!(line("bar.calendar.holidays[1]"), Some(7));
+        assert_eq!(line("hotkeys.f1"), None);
+    }
+
+    #[test]
+    fn checks_profile_sections() {
+        let issues = issues_for(
+            r#"
+[profile.work]
+when = { ssid = "Corp", display = 2 }
+
+[profile.work.bar]
+font_sise = 14
+
+[[profile.work.modules.right.right]]
+type = "clock"
+colour = "red"
+"#,
+        );
+        let paths: Vec<(&str, Option<usize>)> = issues
+            .iter()
+            .map(|issue| {
+                let line = issue.location.as_ref().map(|location| location.line);
+                (issue.path.as_str(), line)
+            })
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("profile.work.when.display", Some(3)),
+                ("profile.work.bar.font_sise", Some(6)),
+                ("profile.work.modules.right.right[0].colour", Some(10)),
+            ]
+        );
+        assert!(issues[0].message.contains("did you mean 'displays'"));
+    }
+}
@@ -0,0 +1,264 @@
+//! Secret references in config values.
+//!
+//! Any string value of the form `secret:keychain/<service>/<account>` is
+//! replaced at load time with the matching generic password from the login
+//! Keychain, so API tokens never have to live in plain TOML:
+//!
+//! ```toml
+//! [[modules.right.right]]
+//! type = "script"
+//! command = "gh-status"
+//! token = "secret:keychain/sinew/github"
+//! ```
+//!
+//! Add the item with `security add-generic-password -s sinew -a github -w`.
+//! The service is everything up to the first `/`; the account is the rest.
+//! Resolved values are cached for the life of the process so config reloads
+//! don't hit the Keychain (or its permission prompt) again.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use core_foundation::base::{CFType, CFTypeRef, TCFType};
+use core_foundation::boolean::CFBoolean;
+use core_foundation::data::{CFData, CFDataRef};
+use core_foundation::dictionary::CFDictionary;
+use core_foundation::string::CFString;
+
+/// Prefix marking a config string as a secret reference.
+const SECRET_PREFIX: &str = "secret:";
+
+// Security framework FFI bindings
+mod ffi {
+    use core_foundation::base::CFTypeRef;
+    use core_foundation::dictionary::CFDictionaryRef;
+    use core_foundation::string::CFStringRef;
+
+    pub type OSStatus = i32;
+
+    pub const ERR_SEC_SUCCESS: OSStatus = 0;
+    pub const ERR_SEC_ITEM_NOT_FOUND: OSStatus = -25300;
+
+    #[link(name = "Security", kind = "framework")]
+    extern "C" {
+        pub static kSecClass: CFStringRef;
+        pub static kSecClassGenericPassword: CFStringRef;
+        pub static kSecAttrService: CFStringRef;
+        pub static kSecAttrAccount: CFStringRef;
+        pub static kSecReturnData: CFStringRef;
+        pub static kSecMatchLimit: CFStringRef;
+        pub static kSecMatchLimitOne: CFStringRef;
+
+        pub fn SecItemCopyMatching(query: CFDictionaryRef, result: *mut CFTypeRef) -> OSStatus;
+    }
+}
+
+/// A parsed `secret:` reference.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SecretRef {
+    /// Generic password in the Keychain
+    Keychain { service: String, account: String },
+}
+
+impl SecretRef {
+    /// Parses a config string. Returns `None` if it isn't a secret reference,
+    /// or an error if it is one but malformed.
+    pub fn parse(value: &str) -> Option<Result<Self, String>> {
+        let reference = value.strip_prefix(SECRET_PREFIX)?;
+        let (provider, rest) = reference.split_once('/').unwrap_or((reference, ""));
+        Some(match provider {
+            "keychain" => match rest.split_once('/') {
+                Some((service, account)) if !service.is_empty() && !account.is_empty() => {
+                    Ok(Self::Keychain {
+                        service: service.to_string(),
+                        account: account.to_string(),
+                    })
+                }
+                _ => Err(format!(
+                    "expected secret:keychain/<service>/<account>, got '{}'",
+                    value
+                )),
+            },
+            _ => Err(format!("unknown secret provider '{}'", provider)),
+        })
+    }
+
+    fn lookup(&self) -> Result<String, String> {
+        match self {
+            Self::Keychain { service, account } => keychain_password(service, account),
+        }
+    }
+}
+
+impl std::fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Keychain { service, account } => {
+                write!(f, "{}keychain/{}/{}", SECRET_PREFIX, service, account)
+            }
+        }
+    }
+}
+
+fn cache() -> &'static Mutex<HashMap<SecretRef, String>> {
+    static CACHE: OnceLock<Mutex<HashMap<SecretRef, String>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Reads a generic password from the Keychain.
+fn keychain_password(service: &str, account: &str) -> Result<String, String> {
+    let query = unsafe {
+        CFDictionary::<CFType, CFType>::from_CFType_pairs(&[
+            (
+                CFString::wrap_under_get_rule(ffi::kSecClass).as_CFType(),
+                CFString::wrap_under_get_rule(ffi::kSecClassGenericPassword).as_CFType(),
+            ),
+            (
+                CFString::wrap_under_get_rule(ffi::kSecAttrService).as_CFType(),
+                CFString::new(service).as_CFType(),
+            ),
+            (
+                CFString::wrap_under_get_rule(ffi::kSecAttrAccount).as_CFType(),
+                CFString::new(account).as_CFType(),
+            ),
+            (
+                CFString::wrap_under_get_rule(ffi::kSecReturnData).as_CFType(),
+                CFBoolean::true_value().as_CFType(),
+            ),
+            (
+                CFString::wrap_under_get_rule(ffi::kSecMatchLimit).as_CFType(),
+                CFString::wrap_under_get_rule(ffi::kSecMatchLimitOne).as_CFType(),
+            ),
+        ])
+    };
+
+    let mut result: CFTypeRef = std::ptr::null();
+    let status = unsafe { ffi::SecItemCopyMatching(query.as_concrete_TypeRef(), &mut result) };
+    match status {
+        ffi::ERR_SEC_SUCCESS if !result.is_null() => {
+            let data = unsafe { CFData::wrap_under_create_rule(result as CFDataRef) };
+            String::from_utf8(data.bytes().to_vec())
+                .map_err(|_| "Keychain item is not valid UTF-8".to_string())
+        }
+        ffi::ERR_SEC_ITEM_NOT_FOUND => Err("no matching Keychain item".to_string()),
+        status => Err(format!("Keychain lookup failed (OSStatus {})", status)),
+    }
+}
+
+/// Replaces every secret reference in `value` with the secret it names.
+///
+/// Returns one `(path, message)` per reference that couldn't be resolved;
+/// those values are replaced with an empty string so a broken reference is
+/// never sent anywhere as if it were the secret.
+pub fn resolve_secrets(value: &mut toml::Value) -> Vec<(String, String)> {
+    let mut errors = Vec::new();
+    resolve_with(value, "", &mut cached_lookup, &mut errors);
+    errors
+}
+
+fn cached_lookup(reference: &SecretRef) -> Result<String, String> {
+    if let Some(secret) = cache().lock().ok().and_then(|c| c.get(reference).cloned()) {
+        return Ok(secret);
+    }
+    let secret = reference.lookup()?;
+    if let Ok(mut cache) = cache().lock() {
+        cache.insert(reference.clone(), secret.clone());
+    }
+    Ok(secret)
+}
+
+fn resolve_with(
+    value: &mut toml::Value,
+    path: &str,
+    lookup: &mut impl FnMut(&SecretRef) -> Result<String, String>,
+    errors: &mut Vec<(String, String)>,
+) {
+    match value {
+        toml::Value::String(s) => {
+            let resolved = match SecretRef::parse(s) {
+                None => return,
+                Some(Ok(reference)) => {
+                    lookup(&reference).map_err(|e| format!("{}: {}", reference, e))
+                }
+                Some(Err(e)) => Err(e),
+            };
+            *s = resolved.unwrap_or_else(|e| {
+                errors.push((path.to_string(), e));
+                String::new()
+            });
+        }
+        toml::Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                resolve_with(item, &format!("{}[{}]", path, i), lookup, errors);
+            }
+        }
+        toml::Value::Table(table) => {
+            for (key, item) in table.iter_mut() {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                resolve_with(item, &child, lookup, errors);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_keychain_references() {
+        assert_eq!(
+            SecretRef::parse("secret:keychain/sinew/github/work"),
+            Some(Ok(SecretRef::Keychain {
+                service: "sinew".into(),
+                account: "github/work".into(),
+            }))
+        );
+        assert_eq!(SecretRef::parse("plain value"), None);
+        assert!(matches!(
+            SecretRef::parse("secret:keychain/sinew"),
+            Some(Err(_))
+        ));
+        assert!(matches!(SecretRef::parse("secret:vault/a/b"), Some(Err(_))));
+    }
+
+    #[test]
+    fn resolves_nested_values_and_reports_failures() {
+        let mut value: toml::Value = toml::from_str(
+            r#"
+            [[modules.right.right]]
+            type = "weather"
+            api_key = "secret:keychain/sinew/weather"
+
+            [[modules.right.right]]
+            type = "script"
+            token = "secret:keychain/sinew/missing"
+            "#,
+        )
+        .unwrap();
+        let mut errors = Vec::new();
+        resolve_with(
+            &mut value,
+            "",
+            &mut |reference| match reference {
+                SecretRef::Keychain { account, .. } if account == "weather" => {
+                    Ok("hunter2".to_string())
+                }
+                _ => Err("no matching Keychain item".to_string()),
+            },
+            &mut errors,
+        );
+
+        let modules = value["modules"]["right"]["right"].as_array().unwrap();
+        assert_eq!(modules[0]["api_key"].as_str(), Some("hunter2"));
+        assert_eq!(modules[1]["token"].as_str(), Some(""));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "modules.right.right[1].token");
+        assert!(errors[0].1.contains("secret:keychain/sinew/missing"));
+    }
+}
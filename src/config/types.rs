@@ -1,8 +1,10 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 use std::collections::HashMap;
 
+use super::schema::suggest;
+
 /// Known module types (fallback when registry not initialized)
-const DEFAULT_MODULE_TYPES: &[&str] = &[
+pub(super) const DEFAULT_MODULE_TYPES: &[&str] = &[
     "clock",
     "date",
     "datetime",
@@ -17,6 +19,8 @@ const DEFAULT_MODULE_TYPES: &[&str] = &[
     "network",
     "wifi",
     "volume",
+    "audio_devices",
+    "display",
     "app_name",
     "window_title",
     "now_playing",
@@ -25,46 +29,189 @@ const DEFAULT_MODULE_TYPES: &[&str] = &[
     "separator",
     "skeleton",
     "external",
+    "launcher",
+    "emoji",
+    "logs",
+    "screenshare",
+    "privacy",
+    "vpn",
+    "docker",
+    "sysmon",
+    "power",
+    "peripherals",
+    "appearance",
+    "shortcuts",
+    "reminders",
+    "timetrack",
+    "ics",
+    "i3bar_input",
+    "mqtt",
+    "homeassistant",
+    "ci",
+    "updates",
+    "folder",
+    "applescript",
+    "wasm",
 ];
 
 /// Known separator types
 const KNOWN_SEPARATOR_TYPES: &[&str] = &["space", "line", "dot", "icon"];
 
 /// Known popup types
-const KNOWN_POPUP_TYPES: &[&str] = &["calendar", "demo", "info", "script", "panel"];
+const KNOWN_POPUP_TYPES: &[&str] = &["calendar", "demo", "info", "script", "panel", "history"];
 
 /// Known popup anchor positions
 const KNOWN_POPUP_ANCHORS: &[&str] = &["left", "center", "right"];
 
+/// Known bar overflow strategies
+const KNOWN_OVERFLOW_STRATEGIES: &[&str] = &["none", "hide"];
+
+/// Known zone justify options
+const KNOWN_JUSTIFY: &[&str] = &["start", "end", "space-between"];
+
+/// Known notch HUD styles
+const KNOWN_HUD_STYLES: &[&str] = &["bar", "ring"];
+
+/// Known notch expansion triggers
+const KNOWN_NOTCH_TRIGGERS: &[&str] = &["hover", "click"];
+
+/// Cameras a fake notch can match, with the width of their body in millimeters
+const NOTCH_CAMERAS: &[(&str, f64)] = &[("brio", 102.0), ("c920", 94.0)];
+
+/// Known targets for module color rules
+const KNOWN_COLOR_RULE_TARGETS: &[&str] = &["text", "background", "icon"];
+
+/// Known zones for toasts and progress items, named after the module sections
+const KNOWN_BAR_ZONES: &[&str] = &[
+    "left.left",
+    "left.center",
+    "left.right",
+    "right.left",
+    "right.center",
+    "right.right",
+];
+
 /// A configuration warning or error
 #[derive(Debug, Clone)]
 pub struct ConfigIssue {
     pub path: String,
     pub message: String,
     pub is_error: bool,
+    /// Where the option is written, when known
+    pub location: Option<Location>,
+}
+
+/// A line in one of the config files
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    /// File name relative to the config directory
+    pub file: String,
+    pub line: usize,
+}
+
+/// A problem that keeps the config from loading: a file that can't be read
+/// or parsed, or an error-level issue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub message: String,
+    pub location: Option<Location>,
+    /// Column of the problem on its line, when known
+    pub column: Option<usize>,
+    /// The offending line
+    pub snippet: Option<String>,
+}
+
+impl ConfigError {
+    /// Creates an error without a location.
+    pub fn new(message: String) -> Self {
+        Self {
+            message,
+            location: None,
+            column: None,
+            snippet: None,
+        }
+    }
+
+    /// Creates an error at byte `offset` of `source`, the contents of
+    /// config file `file`.
+    pub fn at(message: String, file: &str, source: &str, offset: usize) -> Self {
+        let offset = offset.min(source.len());
+        let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[offset..]
+            .find('\n')
+            .map_or(source.len(), |i| offset + i);
+        Self {
+            message,
+            location: Some(Location {
+                file: file.to_string(),
+                line: source[..offset].matches('\n').count() + 1,
+            }),
+            column: Some(source[line_start..offset].chars().count() + 1),
+            snippet: Some(source[line_start..line_end].trim_end().to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.location, self.column) {
+            (Some(location), Some(column)) => {
+                write!(f, "{}:{}:{}: ", location.file, location.line, column)?
+            }
+            (Some(location), None) => write!(f, "{}:{}: ", location.file, location.line)?,
+            _ => {}
+        }
+        write!(f, "{}", self.message)?;
+        if let (Some(location), Some(snippet)) = (&self.location, &self.snippet) {
+            let gutter = location.line.to_string();
+            write!(f, "\n {} | {}", gutter, snippet)?;
+            if let Some(column) = self.column {
+                write!(
+                    f,
+                    "\n {} | {}^",
+                    " ".repeat(gutter.len()),
+                    " ".repeat(column - 1)
+                )?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl std::fmt::Display for ConfigIssue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let level = if self.is_error { "ERROR" } else { "WARNING" };
-        write!(f, "[{}] {}: {}", level, self.path, self.message)
+        match &self.location {
+            Some(location) => write!(
+                f,
+                "[{}] {}:{}: {}: {}",
+                level, location.file, location.line, self.path, self.message
+            ),
+            None => write!(f, "[{}] {}: {}", level, self.path, self.message),
+        }
     }
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Deserialize, Clone, Default)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct Config {
     #[serde(default)]
     pub bar: BarConfig,
     #[serde(default)]
     pub modules: ModulesConfig,
-    // Legacy clock config - will be removed in future versions
+    /// Global hotkeys: key combination -> action
+    #[serde(default, serialize_with = "serialize_sorted")]
+    pub hotkeys: HashMap<String, String>,
+    /// Alert rules over metrics and module values
     #[serde(default)]
+    pub alerts: AlertsConfig,
+    // Legacy clock config - will be removed in future versions
+    #[serde(default, skip_serializing)]
     pub clock: ClockConfig,
 }
 
 /// Module configuration organized by zones
-#[derive(Debug, Deserialize, Clone, Default)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct ModulesConfig {
     #[serde(default)]
     pub left: HalfModulesConfig,
@@ -73,7 +220,7 @@ pub struct ModulesConfig {
 }
 
 /// Modules for one half of the bar (left or right of notch/center)
-#[derive(Debug, Deserialize, Clone, Default)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct HalfModulesConfig {
     /// Modules aligned to the outer edge (left edge for left half, right edge for right half)
     #[serde(default, rename = "left")]
@@ -81,11 +228,89 @@ pub struct HalfModulesConfig {
     /// Modules aligned to the inner edge (toward center/notch)
     #[serde(default, rename = "right")]
     pub inner: Vec<ModuleConfig>,
+    /// Modules anchored to the horizontal center of the half
+    /// (screen center on displays without a notch)
+    #[serde(default)]
+    pub center: Vec<ModuleConfig>,
+}
+
+/// Comparison in a color rule's `when`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Above,
+    AtLeast,
+    Below,
+    AtMost,
+    Equals,
+}
+
+/// A parsed `when` condition such as `>= 80`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Condition {
+    pub comparison: Comparison,
+    pub value: f64,
+}
+
+impl Condition {
+    /// Parses `>`, `>=`, `<`, `<=` or `==` followed by a number.
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        // Two-character operators first so ">=" isn't read as ">"
+        let (comparison, rest) = [
+            (">=", Comparison::AtLeast),
+            ("<=", Comparison::AtMost),
+            ("==", Comparison::Equals),
+            (">", Comparison::Above),
+            ("<", Comparison::Below),
+            ("=", Comparison::Equals),
+        ]
+        .iter()
+        .find_map(|(op, comparison)| input.strip_prefix(op).map(|rest| (*comparison, rest)))?;
+        let value = rest.trim().parse::<f64>().ok().filter(|v| v.is_finite())?;
+        Some(Self { comparison, value })
+    }
+
+    /// Returns true if `value` satisfies the condition.
+    pub fn matches(&self, value: f64) -> bool {
+        match self.comparison {
+            Comparison::Above => value > self.value,
+            Comparison::AtLeast => value >= self.value,
+            Comparison::Below => value < self.value,
+            Comparison::AtMost => value <= self.value,
+            Comparison::Equals => (value - self.value).abs() < f64::EPSILON,
+        }
+    }
+}
+
+/// One entry of a module's `color_rules`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ColorRuleConfig {
+    /// Condition on the module's value, e.g. ">= 80"
+    pub when: String,
+    /// Color in hex format
+    pub color: String,
+    /// What the color applies to: "text", "background" or "icon"
+    /// Default: "text"
+    #[serde(default = "default_color_rule_target")]
+    pub apply: String,
+}
+
+/// One entry of a module's context `menu`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct MenuItemConfig {
+    /// Text shown in the menu
+    pub label: String,
+    /// Shell command run when the entry is chosen
+    pub command: String,
+}
+
+fn default_color_rule_target() -> String {
+    "text".to_string()
 }
 
 /// Configuration for a single module
 #[allow(dead_code)]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ModuleConfig {
     /// Module type: "clock", "static", "battery", "cpu", etc.
     #[serde(rename = "type")]
@@ -125,13 +350,16 @@ pub struct ModuleConfig {
     pub margin_left: Option<f64>,
     /// Right margin
     pub margin_right: Option<f64>,
+    /// Move into free space in the other half of the bar when this one is full
+    #[serde(default)]
+    pub spill: bool,
     /// Separator type: "space", "line", "dot", "icon"
     pub separator_type: Option<String>,
     /// Separator width/radius
     pub separator_width: Option<f64>,
     /// Separator color
     pub separator_color: Option<String>,
-    /// Path for disk module
+    /// Path for disk, folder and wasm modules
     pub path: Option<String>,
     /// Max text length for app_name, now_playing modules
     pub max_length: Option<f64>,
@@ -143,18 +371,27 @@ pub struct ModuleConfig {
     pub interval: Option<f64>,
     /// Command to run when module is clicked
     pub click_command: Option<String>,
-    /// Command to run when module is right-clicked
+    /// Command to run when module is right-clicked (replaces the context menu)
     pub right_click_command: Option<String>,
+    /// Command to run when module is middle-clicked
+    pub middle_click_command: Option<String>,
+    /// Command to run when module is double-clicked
+    pub double_click_command: Option<String>,
     /// Group ID for shared backgrounds
     pub group: Option<String>,
-    /// Color when value is critical (e.g., battery < 20%)
+    /// Color when value is at or below critical_threshold
+    /// (deprecated, use color_rules)
     pub critical_color: Option<String>,
-    /// Color when value is warning (e.g., battery < 40%)
+    /// Color when value is at or below warning_threshold
+    /// (deprecated, use color_rules)
     pub warning_color: Option<String>,
-    /// Threshold for critical state (percentage)
+    /// Threshold for critical state
     pub critical_threshold: Option<f64>,
-    /// Threshold for warning state (percentage)
+    /// Threshold for warning state
     pub warning_threshold: Option<f64>,
+    /// Value-based colors, checked in order; the first match per target wins
+    #[serde(default)]
+    pub color_rules: Vec<ColorRuleConfig>,
     /// Popup type: "calendar", "info", "script"
     pub popup: Option<String>,
     /// Popup width in pixels
@@ -167,6 +404,13 @@ pub struct ModuleConfig {
     pub popup_command: Option<String>,
     /// Popup anchor position: "left", "center", "right" (default "center")
     pub popup_anchor: Option<String>,
+    /// Open the popup when the pointer rests on the module, and close it
+    /// when the pointer leaves the module and the popup
+    #[serde(default)]
+    pub popup_on_hover: bool,
+    /// How long the pointer rests on the module before the popup opens
+    #[serde(default = "default_popup_hover_delay_ms")]
+    pub popup_hover_delay_ms: u64,
     /// Location for weather module (e.g., "New York", "London", or "auto" for auto-detect)
     pub location: Option<String>,
     /// Update interval in seconds for weather module
@@ -174,6 +418,19 @@ pub struct ModuleConfig {
     /// Show module while loading (true = show "Loading...", false = hidden until loaded)
     #[serde(default = "default_show_while_loading")]
     pub show_while_loading: bool,
+    /// Slow down with the rest of the bar while saving power (false keeps
+    /// this module at full speed)
+    #[serde(default = "default_module_power_saving")]
+    pub power_saving: bool,
+    /// Record the module's value over time for the history popup
+    #[serde(default)]
+    pub history: bool,
+    /// Open a context menu on right-click
+    #[serde(default = "default_context_menu")]
+    pub context_menu: bool,
+    /// Extra context menu entries, shown above the built-in ones
+    #[serde(default)]
+    pub menu: Vec<MenuItemConfig>,
     /// Enable toggle behavior (on/off state)
     #[serde(default)]
     pub toggle: bool,
@@ -195,12 +452,14 @@ pub struct ModuleConfig {
     pub value_fixed_width: Option<bool>,
     /// Temperature unit: "c" or "f" (default "c")
     pub temp_unit: Option<String>,
+    /// Display mode for the battery module: "icon" or "ring" (default "icon")
+    pub display: Option<String>,
     /// Width for skeleton module
     pub skeleton_width: Option<f64>,
     /// Height for skeleton module
     pub skeleton_height: Option<f64>,
     /// Extra module-specific configuration for custom modules
-    #[serde(flatten, default)]
+    #[serde(flatten, default, serialize_with = "serialize_sorted")]
     pub extras: HashMap<String, toml::Value>,
 }
 
@@ -208,6 +467,18 @@ fn default_show_while_loading() -> bool {
     true
 }
 
+fn default_module_power_saving() -> bool {
+    true
+}
+
+fn default_context_menu() -> bool {
+    true
+}
+
+fn default_popup_hover_delay_ms() -> u64 {
+    300
+}
+
 impl Config {
     /// Validate the configuration and return a list of issues (warnings and errors)
     pub fn validate(&self) -> Vec<ConfigIssue> {
@@ -219,6 +490,8 @@ impl Config {
         // Validate modules
         self.modules.validate("modules", &mut issues);
 
+        self.alerts.validate("alerts", &mut issues);
+
         issues
     }
 }
@@ -249,6 +522,7 @@ impl BarConfig {
                 path: format!("{}.font_size", path),
                 message: format!("font_size must be positive, got {}", self.font_size),
                 is_error: true,
+                location: None,
             });
         }
         if self.padding < 0.0 {
@@ -256,6 +530,7 @@ impl BarConfig {
                 path: format!("{}.padding", path),
                 message: format!("padding cannot be negative, got {}", self.padding),
                 is_error: true,
+                location: None,
             });
         }
         if self.border_width < 0.0 {
@@ -263,6 +538,237 @@ impl BarConfig {
                 path: format!("{}.border_width", path),
                 message: format!("border_width cannot be negative, got {}", self.border_width),
                 is_error: true,
+                location: None,
+            });
+        }
+
+        if !self.screen_corner_radius.is_valid() {
+            issues.push(ConfigIssue {
+                path: format!("{}.screen_corner_radius", path),
+                message: format!(
+                    "screen_corner_radius should be 'auto' or a radius of at least 0, got {}",
+                    self.screen_corner_radius
+                ),
+                is_error: false, // Warning, the corners stay square
+                location: None,
+            });
+        }
+
+        if self.module_spacing < 0.0 {
+            issues.push(ConfigIssue {
+                path: format!("{}.module_spacing", path),
+                message: format!(
+                    "module_spacing cannot be negative, got {}",
+                    self.module_spacing
+                ),
+                is_error: true,
+                location: None,
+            });
+        }
+        let mut justified: Vec<_> = self.justify.iter().collect();
+        justified.sort();
+        for (zone, justify) in justified {
+            if !KNOWN_BAR_ZONES.contains(&zone.as_str()) {
+                issues.push(ConfigIssue {
+                    path: format!("{}.justify.{}", path, zone),
+                    message: format!(
+                        "unknown zone '{}', expected one of: {}",
+                        zone,
+                        KNOWN_BAR_ZONES.join(", ")
+                    ),
+                    is_error: false, // Warning, the entry is ignored
+                    location: None,
+                });
+            } else if !KNOWN_JUSTIFY.contains(&justify.as_str()) {
+                issues.push(ConfigIssue {
+                    path: format!("{}.justify.{}", path, zone),
+                    message: format!(
+                        "unknown justify '{}', expected one of: {}",
+                        justify,
+                        KNOWN_JUSTIFY.join(", ")
+                    ),
+                    is_error: false, // Warning, the zone keeps its default
+                    location: None,
+                });
+            }
+        }
+
+        if !KNOWN_OVERFLOW_STRATEGIES.contains(&self.overflow.as_str()) {
+            issues.push(ConfigIssue {
+                path: format!("{}.overflow", path),
+                message: format!(
+                    "unknown overflow strategy '{}', expected one of: {}",
+                    self.overflow,
+                    KNOWN_OVERFLOW_STRATEGIES.join(", ")
+                ),
+                is_error: false, // Warning, will default to "none"
+                location: None,
+            });
+        }
+
+        if !KNOWN_HUD_STYLES.contains(&self.hud.style.as_str()) {
+            issues.push(ConfigIssue {
+                path: format!("{}.hud.style", path),
+                message: format!(
+                    "unknown HUD style '{}', expected one of: {}",
+                    self.hud.style,
+                    KNOWN_HUD_STYLES.join(", ")
+                ),
+                is_error: false, // Warning, will default to "bar"
+                location: None,
+            });
+        }
+
+        if !KNOWN_NOTCH_TRIGGERS.contains(&self.notch.trigger.as_str()) {
+            issues.push(ConfigIssue {
+                path: format!("{}.notch.trigger", path),
+                message: format!(
+                    "unknown notch trigger '{}', expected one of: {}",
+                    self.notch.trigger,
+                    KNOWN_NOTCH_TRIGGERS.join(", ")
+                ),
+                is_error: false, // Warning, will default to "hover"
+                location: None,
+            });
+        }
+        for (i, module) in self.notch.widgets.iter().enumerate() {
+            module.validate(&format!("{}.notch.widgets[{}]", path, i), issues);
+        }
+        self.fake_notch
+            .validate(&format!("{}.fake_notch", path), issues);
+
+        if !KNOWN_BAR_ZONES.contains(&self.toast.zone.as_str()) {
+            issues.push(ConfigIssue {
+                path: format!("{}.toast.zone", path),
+                message: format!(
+                    "unknown toast zone '{}', expected one of: {}",
+                    self.toast.zone,
+                    KNOWN_BAR_ZONES.join(", ")
+                ),
+                is_error: false, // Warning, will default to "right.left"
+                location: None,
+            });
+        }
+        if self.toast.timeout <= 0.0 {
+            issues.push(ConfigIssue {
+                path: format!("{}.toast.timeout", path),
+                message: format!("toast timeout must be positive, got {}", self.toast.timeout),
+                is_error: true,
+                location: None,
+            });
+        }
+
+        if !KNOWN_BAR_ZONES.contains(&self.progress.zone.as_str()) {
+            issues.push(ConfigIssue {
+                path: format!("{}.progress.zone", path),
+                message: format!(
+                    "unknown progress zone '{}', expected one of: {}",
+                    self.progress.zone,
+                    KNOWN_BAR_ZONES.join(", ")
+                ),
+                is_error: false, // Warning, will default to "right.left"
+                location: None,
+            });
+        }
+        if self.progress.hide_delay < 0.0 {
+            issues.push(ConfigIssue {
+                path: format!("{}.progress.hide_delay", path),
+                message: format!(
+                    "hide_delay cannot be negative, got {}",
+                    self.progress.hide_delay
+                ),
+                is_error: true,
+                location: None,
+            });
+        }
+
+        if !KNOWN_BAR_ZONES.contains(&self.notify.zone.as_str()) {
+            issues.push(ConfigIssue {
+                path: format!("{}.notify.zone", path),
+                message: format!(
+                    "unknown notify zone '{}', expected one of: {}",
+                    self.notify.zone,
+                    KNOWN_BAR_ZONES.join(", ")
+                ),
+                is_error: false, // Warning, will default to "right.left"
+                location: None,
+            });
+        }
+        if self.notify.ttl < 0.0 {
+            issues.push(ConfigIssue {
+                path: format!("{}.notify.ttl", path),
+                message: format!("ttl cannot be negative, got {}", self.notify.ttl),
+                is_error: true,
+                location: None,
+            });
+        }
+
+        for (i, entry) in self.calendar.holidays.iter().enumerate() {
+            if Holiday::parse(entry).is_none() {
+                issues.push(ConfigIssue {
+                    path: format!("{}.calendar.holidays[{}]", path, i),
+                    message: format!(
+                        "invalid holiday '{}', expected \"YYYY-MM-DD Name\" or \"MM-DD Name\"",
+                        entry
+                    ),
+                    is_error: false, // Warning, the entry is skipped
+                    location: None,
+                });
+            }
+        }
+
+        if self.http.enabled {
+            if self.http.token.is_empty() {
+                issues.push(ConfigIssue {
+                    path: format!("{}.http.token", path),
+                    message: "the HTTP API needs a token to be enabled".to_string(),
+                    is_error: true,
+                    location: None,
+                });
+            }
+            if self.http.address.parse::<std::net::IpAddr>().is_err() {
+                issues.push(ConfigIssue {
+                    path: format!("{}.http.address", path),
+                    message: format!(
+                        "invalid address '{}', expected an IP address like \"127.0.0.1\"",
+                        self.http.address
+                    ),
+                    is_error: true,
+                    location: None,
+                });
+            }
+        }
+
+        let power_saving = &self.power_saving;
+        if !(0.0..=100.0).contains(&power_saving.battery_below) {
+            issues.push(ConfigIssue {
+                path: format!("{}.power_saving.battery_below", path),
+                message: format!(
+                    "battery_below must be between 0 and 100, got {}",
+                    power_saving.battery_below
+                ),
+                is_error: true,
+                location: None,
+            });
+        }
+        if power_saving.slowdown < 1.0 {
+            issues.push(ConfigIssue {
+                path: format!("{}.power_saving.slowdown", path),
+                message: format!("slowdown must be at least 1, got {}", power_saving.slowdown),
+                is_error: true,
+                location: None,
+            });
+        }
+
+        if self.idle.pause_after < 0.0 {
+            issues.push(ConfigIssue {
+                path: format!("{}.idle.pause_after", path),
+                message: format!(
+                    "pause_after cannot be negative, got {}",
+                    self.idle.pause_after
+                ),
+                is_error: true,
+                location: None,
             });
         }
     }
@@ -277,6 +783,9 @@ impl ModulesConfig {
         for (i, module) in self.left.inner.iter().enumerate() {
             module.validate(&format!("{}.left.right[{}]", path, i), issues);
         }
+        for (i, module) in self.left.center.iter().enumerate() {
+            module.validate(&format!("{}.left.center[{}]", path, i), issues);
+        }
 
         // Validate right half
         for (i, module) in self.right.outer.iter().enumerate() {
@@ -285,6 +794,9 @@ impl ModulesConfig {
         for (i, module) in self.right.inner.iter().enumerate() {
             module.validate(&format!("{}.right.right[{}]", path, i), issues);
         }
+        for (i, module) in self.right.center.iter().enumerate() {
+            module.validate(&format!("{}.right.center[{}]", path, i), issues);
+        }
     }
 }
 
@@ -296,14 +808,22 @@ impl ModuleConfig {
             known = DEFAULT_MODULE_TYPES.iter().map(|s| s.to_string()).collect();
         }
         if !known.iter().any(|t| t == &self.module_type) {
-            issues.push(ConfigIssue {
-                path: format!("{}.type", path),
-                message: format!(
+            let message = match suggest(&self.module_type, known.iter().map(String::as_str)) {
+                Some(similar) => format!(
+                    "unknown module type '{}', did you mean '{}'?",
+                    self.module_type, similar
+                ),
+                None => format!(
                     "unknown module type '{}', expected one of: {}",
                     self.module_type,
                     known.join(", ")
                 ),
+            };
+            issues.push(ConfigIssue {
+                path: format!("{}.type", path),
+                message,
                 is_error: true,
+                location: None,
             });
         }
 
@@ -326,6 +846,33 @@ impl ModuleConfig {
         if let Some(ref color) = self.warning_color {
             validate_color(color, &format!("{}.warning_color", path), issues);
         }
+        for (i, rule) in self.color_rules.iter().enumerate() {
+            let rule_path = format!("{}.color_rules[{}]", path, i);
+            if Condition::parse(&rule.when).is_none() {
+                issues.push(ConfigIssue {
+                    path: format!("{}.when", rule_path),
+                    message: format!(
+                        "invalid condition '{}', expected an operator (>, >=, <, <=, ==) and a number",
+                        rule.when
+                    ),
+                    is_error: true,
+                    location: None,
+                });
+            }
+            validate_color(&rule.color, &format!("{}.color", rule_path), issues);
+            if !KNOWN_COLOR_RULE_TARGETS.contains(&rule.apply.as_str()) {
+                issues.push(ConfigIssue {
+                    path: format!("{}.apply", rule_path),
+                    message: format!(
+                        "unknown color target '{}', expected one of: {}",
+                        rule.apply,
+                        KNOWN_COLOR_RULE_TARGETS.join(", ")
+                    ),
+                    is_error: false, // Warning, the rule is skipped
+                    location: None,
+                });
+            }
+        }
         if let Some(ref color) = self.active_background {
             validate_color(color, &format!("{}.active_background", path), issues);
         }
@@ -336,6 +883,20 @@ impl ModuleConfig {
             validate_color(color, &format!("{}.active_color", path), issues);
         }
 
+        for (name, margin) in [
+            ("margin_left", self.margin_left),
+            ("margin_right", self.margin_right),
+        ] {
+            if let Some(margin) = margin.filter(|m| *m < 0.0) {
+                issues.push(ConfigIssue {
+                    path: format!("{}.{}", path, name),
+                    message: format!("{} cannot be negative, got {}", name, margin),
+                    is_error: true,
+                    location: None,
+                });
+            }
+        }
+
         // Validate separator_type
         if let Some(ref sep_type) = self.separator_type {
             if !KNOWN_SEPARATOR_TYPES.contains(&sep_type.as_str()) {
@@ -347,6 +908,7 @@ impl ModuleConfig {
                         KNOWN_SEPARATOR_TYPES.join(", ")
                     ),
                     is_error: false, // Warning, will default to "space"
+                    location: None,
                 });
             }
         }
@@ -362,6 +924,27 @@ impl ModuleConfig {
                         KNOWN_POPUP_TYPES.join(", ")
                     ),
                     is_error: false,
+                    location: None,
+                });
+            }
+            if popup_type == "history" && !self.history {
+                issues.push(ConfigIssue {
+                    path: format!("{}.popup", path),
+                    message: "the history popup needs `history = true`".to_string(),
+                    is_error: false,
+                    location: None,
+                });
+            }
+        }
+
+        // Validate context menu entries
+        for (i, item) in self.menu.iter().enumerate() {
+            if item.label.trim().is_empty() || item.command.trim().is_empty() {
+                issues.push(ConfigIssue {
+                    path: format!("{}.menu[{}]", path, i),
+                    message: "menu entries need a label and a command".to_string(),
+                    is_error: false,
+                    location: None,
                 });
             }
         }
@@ -377,6 +960,7 @@ impl ModuleConfig {
                         KNOWN_POPUP_ANCHORS.join(", ")
                     ),
                     is_error: false,
+                    location: None,
                 });
             }
         }
@@ -388,6 +972,7 @@ impl ModuleConfig {
                     path: format!("{}.critical_threshold", path),
                     message: format!("critical_threshold should be 0-100, got {}", threshold),
                     is_error: false,
+                    location: None,
                 });
             }
         }
@@ -397,6 +982,7 @@ impl ModuleConfig {
                     path: format!("{}.warning_threshold", path),
                     message: format!("warning_threshold should be 0-100, got {}", threshold),
                     is_error: false,
+                    location: None,
                 });
             }
         }
@@ -408,6 +994,7 @@ impl ModuleConfig {
                     path: format!("{}.popup_max_height", path),
                     message: format!("popup_max_height should be 0-100, got {}", max_height),
                     is_error: false,
+                    location: None,
                 });
             }
         }
@@ -419,6 +1006,7 @@ impl ModuleConfig {
                     path: format!("{}.font_size", path),
                     message: format!("font_size must be positive, got {}", size),
                     is_error: true,
+                    location: None,
                 });
             }
         }
@@ -428,6 +1016,7 @@ impl ModuleConfig {
                     path: format!("{}.border_width", path),
                     message: format!("border_width cannot be negative, got {}", width),
                     is_error: true,
+                    location: None,
                 });
             }
         }
@@ -437,6 +1026,7 @@ impl ModuleConfig {
                     path: format!("{}.padding", path),
                     message: format!("padding cannot be negative, got {}", padding),
                     is_error: true,
+                    location: None,
                 });
             }
         }
@@ -449,6 +1039,53 @@ impl ModuleConfig {
                         path: format!("{}.command", path),
                         message: "script module requires 'command' field".to_string(),
                         is_error: false, // Warning, will use default
+                        location: None,
+                    });
+                }
+            }
+            "applescript" => {
+                let option = |key: &str| self.extras.get(key).and_then(|v| v.as_str());
+                if option("script").is_none() && option("file").is_none() {
+                    issues.push(ConfigIssue {
+                        path: path.to_string(),
+                        message: "applescript module requires 'script' or 'file'".to_string(),
+                        is_error: false, // Warning, shows nothing
+                        location: None,
+                    });
+                }
+                if let Some(language) = option("language") {
+                    if !matches!(
+                        language.to_ascii_lowercase().as_str(),
+                        "applescript" | "javascript" | "jxa"
+                    ) {
+                        issues.push(ConfigIssue {
+                            path: format!("{}.language", path),
+                            message: format!(
+                                "unknown language '{}', expected 'applescript' or 'javascript'",
+                                language
+                            ),
+                            is_error: false, // Warning, runs as AppleScript
+                            location: None,
+                        });
+                    }
+                }
+            }
+            "wasm" => {
+                if !cfg!(feature = "wasm") {
+                    issues.push(ConfigIssue {
+                        path: format!("{}.type", path),
+                        message: "sinew was built without wasm support; rebuild with \
+                                  `--features wasm` to use wasm modules"
+                            .to_string(),
+                        is_error: true,
+                        location: None,
+                    });
+                } else if self.path.is_none() {
+                    issues.push(ConfigIssue {
+                        path: format!("{}.path", path),
+                        message: "wasm module requires 'path' to a .wasm component".to_string(),
+                        is_error: false, // Warning, the module is skipped
+                        location: None,
                     });
                 }
             }
@@ -458,6 +1095,7 @@ impl ModuleConfig {
                         path: path.to_string(),
                         message: "static module should have 'text' and/or 'icon' field".to_string(),
                         is_error: false,
+                        location: None,
                     });
                 }
             }
@@ -476,15 +1114,20 @@ fn validate_color(color: &str, path: &str, issues: &mut Vec<ConfigIssue>) {
                 color
             ),
             is_error: true,
+            location: None,
         });
     }
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct BarConfig {
     /// Height in pixels, or None for "auto" (uses system menu bar height)
-    #[serde(default, deserialize_with = "deserialize_height")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_height",
+        serialize_with = "serialize_height"
+    )]
     pub height: Option<f64>,
     /// Background color in hex format (#RRGGBB or #RRGGBBAA)
     #[serde(default = "default_bg_color")]
@@ -513,6 +1156,11 @@ pub struct BarConfig {
     /// Border corner radius (for connected popup effect)
     #[serde(default)]
     pub border_radius: f64,
+    /// Radius of the bar's outer top corners, to follow rounded display
+    /// corners: pixels, or "auto" for the display's own
+    /// Default: 0
+    #[serde(default)]
+    pub screen_corner_radius: CornerRadius,
     /// Popup/panel background color (defaults to bar background_color)
     pub popup_background_color: Option<String>,
     /// Popup/panel text color (defaults to bar text_color)
@@ -528,16 +1176,924 @@ pub struct BarConfig {
     /// Default: false
     #[serde(default)]
     pub launch_at_login: bool,
+    /// Outline modules whose bounds overlap another zone
+    /// Default: false
+    #[serde(default)]
+    pub debug_layout: bool,
+    /// What to do when zones in a bar half collide: "none" (overlap) or "hide"
+    /// Default: "none"
+    #[serde(default = "default_overflow")]
+    pub overflow: String,
+    /// Gap between modules in a zone, in pixels
+    /// Default: 4.0
+    #[serde(default = "default_module_spacing")]
+    pub module_spacing: f64,
+    /// Per-zone module alignment: "start", "end" or "space-between", keyed
+    /// by zone name ("left.left" ... "right.right")
+    #[serde(default, serialize_with = "serialize_sorted")]
+    pub justify: HashMap<String, String>,
+    /// Notch HUD for volume/brightness keys
+    #[serde(default)]
+    pub hud: HudConfig,
+    /// Hover tooltips of modules
+    #[serde(default)]
+    pub tooltip: TooltipConfig,
+    /// Expandable panel under the notch
+    #[serde(default)]
+    pub notch: NotchConfig,
+    /// Notch drawn on displays without one
+    #[serde(default)]
+    pub fake_notch: FakeNotchConfig,
+    /// Transient messages sent over IPC
+    #[serde(default)]
+    pub toast: ToastConfig,
+    /// Progress bars set over IPC
+    #[serde(default)]
+    pub progress: ProgressConfig,
+    /// Text items pushed over IPC or the HTTP API
+    #[serde(default)]
+    pub notify: NotifyConfig,
+    /// Calendar popup grid
+    #[serde(default)]
+    pub calendar: CalendarConfig,
+    /// Localhost HTTP control API
+    #[serde(default)]
+    pub http: HttpConfig,
+    /// Pausing updates while nobody is at the machine
+    #[serde(default)]
+    pub idle: IdleConfig,
+    /// Slower updates on a low battery or in Low Power Mode
+    #[serde(default)]
+    pub power_saving: PowerSavingConfig,
+    /// Command the context menu's "Open config" runs, with `{file}` and
+    /// `{line}` replaced. Default: the default text editor
+    pub editor: Option<String>,
 }
 
-fn default_camera_indicator() -> bool {
-    true
+/// Radius of the bar's top corners (`bar.screen_corner_radius`)
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum CornerRadius {
+    Pixels(f64),
+    /// "auto": the radius of the display's rounded corners
+    Named(String),
 }
 
-impl Default for BarConfig {
+/// Corner radius of notched MacBook displays, in points
+const NOTCHED_DISPLAY_CORNER_RADIUS: f64 = 10.0;
+
+impl CornerRadius {
+    fn is_valid(&self) -> bool {
+        match self {
+            Self::Pixels(pixels) => *pixels >= 0.0,
+            Self::Named(name) => name == "auto",
+        }
+    }
+
+    /// Radius in pixels on a display with or without a notch; "auto" only
+    /// rounds notched MacBook displays, the ones with rounded corners.
+    pub fn pixels(&self, has_notch: bool) -> f64 {
+        match self {
+            Self::Pixels(pixels) => pixels.max(0.0),
+            Self::Named(name) if name == "auto" && has_notch => NOTCHED_DISPLAY_CORNER_RADIUS,
+            Self::Named(_) => 0.0,
+        }
+    }
+}
+
+impl Default for CornerRadius {
     fn default() -> Self {
-        Self {
-            height: None,
+        Self::Pixels(0.0)
+    }
+}
+
+impl std::fmt::Display for CornerRadius {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pixels(pixels) => write!(f, "{}", pixels),
+            Self::Named(name) => write!(f, "'{}'", name),
+        }
+    }
+}
+
+/// Notch HUD configuration (`[bar.hud]`)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct HudConfig {
+    /// Show the HUD when volume/brightness keys are pressed
+    /// Default: false
+    #[serde(default)]
+    pub enabled: bool,
+    /// Level indicator: "bar" or "ring"
+    /// Default: "bar"
+    #[serde(default = "default_hud_style")]
+    pub style: String,
+    /// Milliseconds the HUD stays up after the last key press
+    /// Default: 1500
+    #[serde(default = "default_hud_timeout")]
+    pub timeout_ms: u64,
+    /// Handle the keys ourselves so the system HUD doesn't appear
+    /// (needs the Accessibility permission)
+    /// Default: true
+    #[serde(default = "default_hud_replace_system")]
+    pub replace_system: bool,
+}
+
+fn default_hud_style() -> String {
+    "bar".to_string()
+}
+
+fn default_hud_timeout() -> u64 {
+    1500
+}
+
+fn default_hud_replace_system() -> bool {
+    true
+}
+
+impl Default for HudConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            style: default_hud_style(),
+            timeout_ms: default_hud_timeout(),
+            replace_system: default_hud_replace_system(),
+        }
+    }
+}
+
+/// Hover tooltip configuration (`[bar.tooltip]`)
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct TooltipConfig {
+    /// Show a tooltip when the pointer rests on a module
+    /// Default: true
+    #[serde(default = "default_tooltip_enabled")]
+    pub enabled: bool,
+    /// Milliseconds the pointer rests on a module before the tooltip shows
+    /// Default: 600
+    #[serde(default = "default_tooltip_delay")]
+    pub delay_ms: u64,
+}
+
+fn default_tooltip_enabled() -> bool {
+    true
+}
+
+fn default_tooltip_delay() -> u64 {
+    600
+}
+
+impl Default for TooltipConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_tooltip_enabled(),
+            delay_ms: default_tooltip_delay(),
+        }
+    }
+}
+
+/// Notch expansion configuration (`[bar.notch]`)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct NotchConfig {
+    /// Expand a panel under the notch
+    /// Default: false
+    #[serde(default)]
+    pub enabled: bool,
+    /// What expands the panel: "hover" or "click"
+    /// Default: "hover"
+    #[serde(default = "default_notch_trigger")]
+    pub trigger: String,
+    /// Panel width in pixels (220-480)
+    /// Default: 360
+    #[serde(default = "default_notch_width")]
+    pub width: f64,
+    /// Show the now-playing track
+    /// Default: true
+    #[serde(default = "default_notch_now_playing")]
+    pub now_playing: bool,
+    /// Show today's date and the current week
+    /// Default: true
+    #[serde(default = "default_notch_calendar")]
+    pub calendar: bool,
+    /// Extra modules shown in a row at the bottom of the panel
+    #[serde(default)]
+    pub widgets: Vec<ModuleConfig>,
+}
+
+fn default_notch_trigger() -> String {
+    "hover".to_string()
+}
+
+fn default_notch_width() -> f64 {
+    360.0
+}
+
+fn default_notch_now_playing() -> bool {
+    true
+}
+
+fn default_notch_calendar() -> bool {
+    true
+}
+
+impl Default for NotchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            trigger: default_notch_trigger(),
+            width: default_notch_width(),
+            now_playing: default_notch_now_playing(),
+            calendar: default_notch_calendar(),
+            widgets: Vec::new(),
+        }
+    }
+}
+
+/// Width of a fake notch: pixels, "builtin" for the built-in display's
+/// notch, a camera from `NOTCH_CAMERAS`, or a camera width like "110mm"
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum FakeNotchWidth {
+    Pixels(f64),
+    Named(String),
+}
+
+impl FakeNotchWidth {
+    /// Millimeters of a camera width, or None for pixels and "builtin".
+    fn camera_mm(&self) -> Option<f64> {
+        let Self::Named(name) = self else {
+            return None;
+        };
+        if let Some(mm) = name.strip_suffix("mm") {
+            return mm.trim().parse().ok().filter(|mm: &f64| *mm > 0.0);
+        }
+        NOTCH_CAMERAS
+            .iter()
+            .find(|(camera, _)| camera == name)
+            .map(|(_, mm)| *mm)
+    }
+
+    fn is_valid(&self) -> bool {
+        match self {
+            Self::Pixels(pixels) => *pixels > 0.0,
+            Self::Named(name) => name == "builtin" || self.camera_mm().is_some(),
+        }
+    }
+
+    /// Width in pixels on a screen `screen_width` pixels and `screen_mm`
+    /// millimeters wide, or None when that can't be worked out (no built-in
+    /// notch to copy, or a display that doesn't report its size).
+    ///
+    /// @param builtin_notch - Width of the built-in display's notch, if any
+    pub fn pixels(
+        &self,
+        screen_width: f64,
+        screen_mm: Option<f64>,
+        builtin_notch: Option<f64>,
+    ) -> Option<f64> {
+        match self {
+            Self::Pixels(pixels) => Some(*pixels),
+            Self::Named(name) if name == "builtin" => builtin_notch,
+            Self::Named(_) => Some(self.camera_mm()? * screen_width / screen_mm?),
+        }
+    }
+}
+
+impl Default for FakeNotchWidth {
+    fn default() -> Self {
+        Self::Pixels(200.0)
+    }
+}
+
+/// Fake notch configuration (`[bar.fake_notch]`)
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct FakeNotchConfig {
+    /// Draw a notch between the bar halves on displays without one
+    /// Default: false
+    #[serde(default)]
+    pub enabled: bool,
+    /// Notch width; see `FakeNotchWidth`
+    /// Default: 200
+    #[serde(default)]
+    pub width: FakeNotchWidth,
+    /// Notch color, in hex
+    /// Default: "#000000"
+    pub color: Option<String>,
+    /// Settings for particular displays, first match wins
+    #[serde(default)]
+    pub displays: Vec<FakeNotchDisplay>,
+}
+
+/// Fake notch settings for displays whose name contains `name`
+/// (`[[bar.fake_notch.displays]]`)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct FakeNotchDisplay {
+    /// Part of the display name, ignoring case
+    pub name: String,
+    pub enabled: Option<bool>,
+    pub width: Option<FakeNotchWidth>,
+    pub color: Option<String>,
+}
+
+impl FakeNotchConfig {
+    /// Width and color of the fake notch on the display named `display`,
+    /// or None when it has no fake notch.
+    pub fn for_display(&self, display: &str) -> Option<(FakeNotchWidth, &str)> {
+        let display = display.to_lowercase();
+        let matched = self
+            .displays
+            .iter()
+            .find(|entry| display.contains(&entry.name.to_lowercase()));
+        let enabled = matched
+            .and_then(|entry| entry.enabled)
+            .unwrap_or(self.enabled);
+        if !enabled {
+            return None;
+        }
+        let width = matched
+            .and_then(|entry| entry.width.clone())
+            .unwrap_or_else(|| self.width.clone());
+        let color = matched
+            .and_then(|entry| entry.color.as_deref())
+            .or(self.color.as_deref())
+            .unwrap_or("#000000");
+        Some((width, color))
+    }
+
+    fn validate(&self, path: &str, issues: &mut Vec<ConfigIssue>) {
+        let widths = std::iter::once((format!("{}.width", path), Some(&self.width)));
+        let display_widths = self.displays.iter().enumerate().map(|(i, entry)| {
+            (
+                format!("{}.displays[{}].width", path, i),
+                entry.width.as_ref(),
+            )
+        });
+        for (width_path, width) in widths.chain(display_widths) {
+            let Some(width) = width.filter(|width| !width.is_valid()) else {
+                continue;
+            };
+            let shown = match width {
+                FakeNotchWidth::Pixels(pixels) => pixels.to_string(),
+                FakeNotchWidth::Named(name) => format!("'{}'", name),
+            };
+            let cameras: Vec<&str> = NOTCH_CAMERAS.iter().map(|(name, _)| *name).collect();
+            issues.push(ConfigIssue {
+                path: width_path,
+                message: format!(
+                    "invalid fake notch width {}, expected pixels, 'builtin', a camera ({}) or millimeters like '100mm'",
+                    shown,
+                    cameras.join(", ")
+                ),
+                is_error: false, // Warning, the notch keeps the default width
+                location: None,
+            });
+        }
+        if let Some(color) = &self.color {
+            validate_color(color, &format!("{}.color", path), issues);
+        }
+        for (i, entry) in self.displays.iter().enumerate() {
+            if let Some(color) = &entry.color {
+                validate_color(color, &format!("{}.displays[{}].color", path, i), issues);
+            }
+        }
+    }
+}
+
+/// Toast configuration (`[bar.toast]`)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ToastConfig {
+    /// Zone the toast appears in, named like the module sections
+    /// ("left.left", "right.center", ...)
+    /// Default: "right.left"
+    #[serde(default = "default_toast_zone")]
+    pub zone: String,
+    /// Seconds a toast stays when the command doesn't pass --timeout
+    /// Default: 3
+    #[serde(default = "default_toast_timeout")]
+    pub timeout: f64,
+}
+
+fn default_toast_zone() -> String {
+    "right.left".to_string()
+}
+
+fn default_toast_timeout() -> f64 {
+    3.0
+}
+
+impl Default for ToastConfig {
+    fn default() -> Self {
+        Self {
+            zone: default_toast_zone(),
+            timeout: default_toast_timeout(),
+        }
+    }
+}
+
+/// Progress item configuration (`[bar.progress]`)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ProgressConfig {
+    /// Zone progress items appear in, named like the module sections
+    /// Default: "right.left"
+    #[serde(default = "default_progress_zone")]
+    pub zone: String,
+    /// Seconds a finished item stays before it's removed
+    /// Default: 3
+    #[serde(default = "default_progress_hide_delay")]
+    pub hide_delay: f64,
+}
+
+fn default_progress_zone() -> String {
+    "right.left".to_string()
+}
+
+fn default_progress_hide_delay() -> f64 {
+    3.0
+}
+
+impl Default for ProgressConfig {
+    fn default() -> Self {
+        Self {
+            zone: default_progress_zone(),
+            hide_delay: default_progress_hide_delay(),
+        }
+    }
+}
+
+/// Notify item configuration (`[bar.notify]`)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct NotifyConfig {
+    /// Zone notify items appear in, named like the module sections
+    /// Default: "right.left"
+    #[serde(default = "default_notify_zone")]
+    pub zone: String,
+    /// Seconds an item stays when it doesn't set a ttl (0 = until removed)
+    /// Default: 0
+    #[serde(default)]
+    pub ttl: f64,
+}
+
+fn default_notify_zone() -> String {
+    "right.left".to_string()
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            zone: default_notify_zone(),
+            ttl: 0.0,
+        }
+    }
+}
+
+/// HTTP control API configuration (`[bar.http]`)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct HttpConfig {
+    /// Serve the HTTP API
+    /// Default: false
+    #[serde(default)]
+    pub enabled: bool,
+    /// Address to listen on; anything but loopback exposes the bar to the
+    /// network
+    /// Default: "127.0.0.1"
+    #[serde(default = "default_http_address")]
+    pub address: String,
+    /// Default: 7420
+    #[serde(default = "default_http_port")]
+    pub port: u16,
+    /// Token clients send as `Authorization: Bearer <token>`, best given as
+    /// a `secret:` reference
+    #[serde(default)]
+    pub token: String,
+}
+
+fn default_http_address() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_http_port() -> u16 {
+    7420
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: default_http_address(),
+            port: default_http_port(),
+            token: String::new(),
+        }
+    }
+}
+
+/// Idle and screen lock configuration (`[bar.idle]`)
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct IdleConfig {
+    /// Pause timed module updates while the screen is locked
+    /// Default: true
+    #[serde(default = "default_pause_when_locked")]
+    pub pause_when_locked: bool,
+    /// Also pause them after this many seconds without input (0 = never)
+    /// Default: 0
+    #[serde(default)]
+    pub pause_after: f64,
+}
+
+fn default_pause_when_locked() -> bool {
+    true
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        Self {
+            pause_when_locked: default_pause_when_locked(),
+            pause_after: 0.0,
+        }
+    }
+}
+
+impl IdleConfig {
+    /// Idle threshold for pausing, None when idle time doesn't pause.
+    pub fn idle_after(&self) -> Option<std::time::Duration> {
+        (self.pause_after > 0.0).then(|| std::time::Duration::from_secs_f64(self.pause_after))
+    }
+}
+
+/// Power saving configuration (`[bar.power_saving]`)
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct PowerSavingConfig {
+    /// Save power on battery below this charge, in percent (0 = never)
+    /// Default: 20
+    #[serde(default = "default_battery_below")]
+    pub battery_below: f64,
+    /// Save power while Low Power Mode is on
+    /// Default: true
+    #[serde(default = "default_low_power_mode")]
+    pub low_power_mode: bool,
+    /// Factor timed update intervals are stretched by
+    /// Default: 3
+    #[serde(default = "default_slowdown")]
+    pub slowdown: f64,
+    /// Stop animations such as scrolling text
+    /// Default: true
+    #[serde(default = "default_pause_animations")]
+    pub pause_animations: bool,
+}
+
+fn default_battery_below() -> f64 {
+    20.0
+}
+
+fn default_low_power_mode() -> bool {
+    true
+}
+
+fn default_slowdown() -> f64 {
+    3.0
+}
+
+fn default_pause_animations() -> bool {
+    true
+}
+
+impl Default for PowerSavingConfig {
+    fn default() -> Self {
+        Self {
+            battery_below: default_battery_below(),
+            low_power_mode: default_low_power_mode(),
+            slowdown: default_slowdown(),
+            pause_animations: default_pause_animations(),
+        }
+    }
+}
+
+/// Alert rules configuration (`[alerts]`)
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct AlertsConfig {
+    /// Seconds between rule evaluations
+    /// Default: 10
+    #[serde(default = "default_alerts_interval")]
+    pub interval: f64,
+    #[serde(default)]
+    pub rules: Vec<AlertRuleConfig>,
+}
+
+fn default_alerts_interval() -> f64 {
+    10.0
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            interval: default_alerts_interval(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+/// One `[[alerts.rules]]` entry
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct AlertRuleConfig {
+    /// Name used in messages (defaults to `when`)
+    pub name: Option<String>,
+    /// Condition, e.g. `disk("/") > 90` or `memory > 85 for 5m`
+    pub when: String,
+    /// Message with `{name}` and `{value}`
+    /// Default: "{name} ({value})"
+    pub message: Option<String>,
+    /// Where the message goes: "toast" and/or "notification"
+    /// Default: ["toast"]
+    #[serde(default = "default_alert_actions")]
+    pub actions: Vec<String>,
+    /// Shell command run when the alert fires
+    pub command: Option<String>,
+    /// Toast style: "info", "success", "warning" or "error"
+    /// Default: "warning"
+    #[serde(default = "default_alert_style")]
+    pub style: String,
+    /// Fire again this often while the condition holds, e.g. "1h"
+    /// (None = once until the condition clears)
+    pub repeat: Option<String>,
+}
+
+fn default_alert_actions() -> Vec<String> {
+    vec!["toast".to_string()]
+}
+
+fn default_alert_style() -> String {
+    "warning".to_string()
+}
+
+/// What an alert rule watches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlertSource {
+    Cpu,
+    Memory,
+    /// Usage of the volume containing this path
+    Disk(String),
+    Temperature,
+    Battery,
+    /// The value of the module with this id, as used by `color_rules`
+    Module(String),
+}
+
+impl AlertSource {
+    /// Parses `cpu`, `memory`, `disk`, `disk("/Volumes/Data")`,
+    /// `temperature`, `battery` or `module("id")`.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        let (name, argument) = match input.strip_suffix(')') {
+            Some(call) => {
+                let (name, argument) = call
+                    .split_once('(')
+                    .ok_or_else(|| format!("unbalanced parenthesis in '{}'", input))?;
+                let argument = argument.trim();
+                let unquoted = argument
+                    .strip_prefix('"')
+                    .and_then(|a| a.strip_suffix('"'))
+                    .or_else(|| {
+                        argument
+                            .strip_prefix('\'')
+                            .and_then(|a| a.strip_suffix('\''))
+                    })
+                    .unwrap_or(argument);
+                (name.trim(), Some(unquoted.to_string()))
+            }
+            None => (input, None),
+        };
+        match (name, argument) {
+            ("cpu", None) => Ok(Self::Cpu),
+            ("memory", None) => Ok(Self::Memory),
+            ("disk", None) => Ok(Self::Disk("/".to_string())),
+            ("disk", Some(path)) if !path.is_empty() => Ok(Self::Disk(path)),
+            ("temperature", None) => Ok(Self::Temperature),
+            ("battery", None) => Ok(Self::Battery),
+            ("module", Some(id)) if !id.is_empty() => Ok(Self::Module(id)),
+            ("disk" | "module", _) => Err(format!("{}() needs an argument", name)),
+            _ => Err(format!(
+                "unknown value '{}', expected cpu, memory, disk, temperature, battery or module(\"id\")",
+                input
+            )),
+        }
+    }
+}
+
+/// A parsed alert `when`, e.g. `memory > 85 for 5m`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertWhen {
+    pub source: AlertSource,
+    pub condition: Condition,
+    /// How long the condition must hold before the alert fires
+    pub hold: std::time::Duration,
+}
+
+impl AlertWhen {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let (expression, hold) = match input.rsplit_once(" for ") {
+            Some((expression, hold)) => (
+                expression,
+                parse_duration(hold).ok_or_else(|| {
+                    format!("invalid duration '{}', e.g. 30s, 5m or 1h", hold.trim())
+                })?,
+            ),
+            None => (input, std::time::Duration::ZERO),
+        };
+        // Skip a parenthesized argument, which may contain anything
+        let start = expression.rfind(')').map_or(0, |i| i + 1);
+        let at = expression[start..]
+            .find(['<', '>', '='])
+            .map(|i| start + i)
+            .ok_or_else(|| format!("expected a comparison like 'cpu > 90', got '{}'", input))?;
+        let source = AlertSource::parse(&expression[..at])?;
+        let condition = Condition::parse(&expression[at..]).ok_or_else(|| {
+            format!(
+                "invalid comparison '{}', expected >, >=, <, <= or == and a number",
+                expression[at..].trim()
+            )
+        })?;
+        Ok(Self {
+            source,
+            condition,
+            hold,
+        })
+    }
+}
+
+/// Parses a duration like `90`, `30s`, `5m`, `1.5h` or `1d` (no unit =
+/// seconds).
+pub fn parse_duration(input: &str) -> Option<std::time::Duration> {
+    let input = input.trim();
+    let (number, unit) = match input.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(i) => input.split_at(i),
+        None => (input, "s"),
+    };
+    let scale = match unit {
+        "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3600.0,
+        "d" => 86400.0,
+        _ => return None,
+    };
+    let value = number.trim().parse::<f64>().ok()?;
+    (value.is_finite() && value >= 0.0).then(|| std::time::Duration::from_secs_f64(value * scale))
+}
+
+impl AlertsConfig {
+    fn validate(&self, path: &str, issues: &mut Vec<ConfigIssue>) {
+        if self.interval <= 0.0 {
+            issues.push(ConfigIssue {
+                path: format!("{}.interval", path),
+                message: format!("interval must be positive, got {}", self.interval),
+                is_error: true,
+                location: None,
+            });
+        }
+        for (i, rule) in self.rules.iter().enumerate() {
+            let path = format!("{}.rules[{}]", path, i);
+            if let Err(message) = AlertWhen::parse(&rule.when) {
+                issues.push(ConfigIssue {
+                    path: format!("{}.when", path),
+                    message,
+                    is_error: true,
+                    location: None,
+                });
+            }
+            for action in &rule.actions {
+                if !["toast", "notification"].contains(&action.as_str()) {
+                    issues.push(ConfigIssue {
+                        path: format!("{}.actions", path),
+                        message: format!(
+                            "unknown action '{}', expected toast or notification",
+                            action
+                        ),
+                        is_error: false,
+                        location: None,
+                    });
+                }
+            }
+            if !["info", "success", "warning", "error"].contains(&rule.style.as_str()) {
+                issues.push(ConfigIssue {
+                    path: format!("{}.style", path),
+                    message: format!(
+                        "unknown style '{}', expected info, success, warning or error",
+                        rule.style
+                    ),
+                    is_error: false,
+                    location: None,
+                });
+            }
+            if let Some(repeat) = &rule.repeat {
+                if parse_duration(repeat).is_none_or(|d| d.is_zero()) {
+                    issues.push(ConfigIssue {
+                        path: format!("{}.repeat", path),
+                        message: format!("invalid duration '{}', e.g. 30m or 1h", repeat),
+                        is_error: true,
+                        location: None,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Calendar popup configuration (`[bar.calendar]`)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CalendarConfig {
+    /// Show ISO week numbers before each week
+    /// Default: false
+    #[serde(default)]
+    pub week_numbers: bool,
+    /// Dim Saturdays and Sundays
+    /// Default: true
+    #[serde(default = "default_calendar_weekends")]
+    pub weekends: bool,
+    /// Holidays as "YYYY-MM-DD Name" or, every year, "MM-DD Name"
+    #[serde(default)]
+    pub holidays: Vec<String>,
+    /// ICS file with more holidays (all events in it count)
+    #[serde(default)]
+    pub holidays_file: Option<String>,
+}
+
+fn default_calendar_weekends() -> bool {
+    true
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            week_numbers: false,
+            weekends: default_calendar_weekends(),
+            holidays: Vec::new(),
+            holidays_file: None,
+        }
+    }
+}
+
+/// When a holiday falls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HolidayDate {
+    Once(chrono::NaiveDate),
+    /// Month and day, every year
+    Yearly(u32, u32),
+}
+
+impl HolidayDate {
+    /// Returns true if the holiday falls on `date`.
+    pub fn matches(&self, date: chrono::NaiveDate) -> bool {
+        use chrono::Datelike;
+        match *self {
+            Self::Once(day) => day == date,
+            Self::Yearly(month, day) => date.month() == month && date.day() == day,
+        }
+    }
+}
+
+/// A parsed `bar.calendar.holidays` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Holiday {
+    pub date: HolidayDate,
+    pub name: String,
+}
+
+impl Holiday {
+    /// Parses "2026-12-24 Christmas Eve" or "12-25 Christmas"; the name is
+    /// optional.
+    pub fn parse(entry: &str) -> Option<Self> {
+        let entry = entry.trim();
+        let (date, name) = entry.split_once(' ').unwrap_or((entry, ""));
+        let date = match chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(day) => HolidayDate::Once(day),
+            Err(_) => {
+                let (month, day) = date.split_once('-')?;
+                let (month, day) = (month.parse().ok()?, day.parse().ok()?);
+                // Leap year, so Feb 29 is accepted
+                chrono::NaiveDate::from_ymd_opt(2024, month, day)?;
+                HolidayDate::Yearly(month, day)
+            }
+        };
+        Some(Self {
+            date,
+            name: name.trim().to_string(),
+        })
+    }
+}
+
+fn default_overflow() -> String {
+    "none".to_string()
+}
+
+fn default_module_spacing() -> f64 {
+    4.0
+}
+
+fn default_camera_indicator() -> bool {
+    true
+}
+
+impl Default for BarConfig {
+    fn default() -> Self {
+        Self {
+            height: None,
             background_color: default_bg_color(),
             text_color: default_text_color(),
             font_size: default_font_size(),
@@ -547,11 +2103,28 @@ impl Default for BarConfig {
             border_color: None,
             border_width: default_bar_border_width(),
             border_radius: 0.0,
+            screen_corner_radius: CornerRadius::default(),
             popup_background_color: None,
             popup_text_color: None,
             theme: ThemeConfig::default(),
             camera_indicator: default_camera_indicator(),
             launch_at_login: false,
+            debug_layout: false,
+            overflow: default_overflow(),
+            module_spacing: default_module_spacing(),
+            justify: HashMap::new(),
+            hud: HudConfig::default(),
+            tooltip: TooltipConfig::default(),
+            notch: NotchConfig::default(),
+            fake_notch: FakeNotchConfig::default(),
+            toast: ToastConfig::default(),
+            progress: ProgressConfig::default(),
+            notify: NotifyConfig::default(),
+            calendar: CalendarConfig::default(),
+            http: HttpConfig::default(),
+            idle: IdleConfig::default(),
+            power_saving: PowerSavingConfig::default(),
+            editor: None,
         }
     }
 }
@@ -569,7 +2142,7 @@ fn default_bar_border_width() -> f64 {
 }
 
 /// Theme configuration for semantic colors
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ThemeConfig {
     /// Muted text color (e.g., secondary text, captions)
     #[serde(default = "default_theme_muted")]
@@ -726,6 +2299,23 @@ where
     }
 }
 
+fn serialize_height<S: Serializer>(height: &Option<f64>, serializer: S) -> Result<S::Ok, S::Error> {
+    match height {
+        Some(pixels) => serializer.serialize_f64(*pixels),
+        None => serializer.serialize_str("auto"),
+    }
+}
+
+/// Serializes a map with its keys in order so dumped configs are stable.
+fn serialize_sorted<S: Serializer, V: Serialize>(
+    map: &HashMap<String, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    map.iter()
+        .collect::<std::collections::BTreeMap<_, _>>()
+        .serialize(serializer)
+}
+
 /// Parse a hex color string into RGBA components (0.0-1.0)
 pub fn parse_hex_color(hex: &str) -> Option<(f64, f64, f64, f64)> {
     let hex = hex.trim_start_matches('#');
@@ -787,6 +2377,369 @@ left = [{ type = "not_a_real_module" }]
             .any(|issue| { issue.is_error && issue.path.ends_with(".type") }));
     }
 
+    #[test]
+    fn validates_wasm_against_the_build() {
+        let config: Config = toml::from_str(
+            r#"
+[modules.left]
+left = [{ type = "wasm", path = "plugin.wasm" }]
+"#,
+        )
+        .expect("config should parse");
+
+        let rejected = config
+            .validate()
+            .iter()
+            .any(|issue| issue.is_error && issue.message.contains("built without wasm support"));
+        assert_eq!(rejected, !cfg!(feature = "wasm"));
+    }
+
+    #[test]
+    fn parses_color_rule_conditions() {
+        let at_least = Condition::parse(">= 80").unwrap();
+        assert_eq!(at_least.comparison, Comparison::AtLeast);
+        assert!(at_least.matches(80.0) && !at_least.matches(79.0));
+        assert!(Condition::parse("<20").unwrap().matches(19.0));
+        assert!(Condition::parse("== 100").unwrap().matches(100.0));
+        assert_eq!(Condition::parse("80"), None);
+        assert_eq!(Condition::parse(">= lots"), None);
+    }
+
+    #[test]
+    fn parses_holidays() {
+        let christmas = Holiday::parse("12-25 Christmas Day").unwrap();
+        assert_eq!(christmas.date, HolidayDate::Yearly(12, 25));
+        assert_eq!(christmas.name, "Christmas Day");
+        assert!(christmas
+            .date
+            .matches(chrono::NaiveDate::from_ymd_opt(2030, 12, 25).unwrap()));
+        let once = Holiday::parse("2026-05-25").unwrap();
+        assert!(once
+            .date
+            .matches(chrono::NaiveDate::from_ymd_opt(2026, 5, 25).unwrap()));
+        assert!(!once
+            .date
+            .matches(chrono::NaiveDate::from_ymd_opt(2027, 5, 25).unwrap()));
+        assert_eq!(Holiday::parse("13-01 Nope"), None);
+        assert_eq!(Holiday::parse("Christmas"), None);
+    }
+
+    #[test]
+    fn validates_color_rules() {
+        let config: Config = toml::from_str(
+            r##"
+[modules.left]
+left = [{ type = "cpu", color_rules = [
+    { when = ">= 80", color = "#f38ba8" },
+    { when = "high", color = "#f9e2af", apply = "border" },
+] }]
+"##,
+        )
+        .expect("config should parse");
+
+        let issues = config.validate();
+        assert_eq!(config.modules.left.outer[0].color_rules[0].apply, "text");
+        assert!(issues
+            .iter()
+            .any(|issue| issue.is_error && issue.path.ends_with("color_rules[1].when")));
+        assert!(issues
+            .iter()
+            .any(|issue| !issue.is_error && issue.path.ends_with("color_rules[1].apply")));
+        assert!(!issues
+            .iter()
+            .any(|issue| issue.path.contains("color_rules[0]")));
+    }
+
+    #[test]
+    fn validates_spacing_and_justify() {
+        let config: Config = toml::from_str(
+            r#"
+[bar]
+module_spacing = -2
+
+[bar.justify]
+"left.left" = "space-between"
+"right.right" = "middle"
+"top" = "start"
+
+[modules.left]
+left = [{ type = "clock", margin_left = -4 }]
+"#,
+        )
+        .expect("config should parse");
+
+        let issues = config.validate();
+        assert!(issues
+            .iter()
+            .any(|issue| issue.is_error && issue.path.ends_with("module_spacing")));
+        assert!(issues
+            .iter()
+            .any(|issue| issue.is_error && issue.path.ends_with("margin_left")));
+        assert!(issues
+            .iter()
+            .any(|issue| !issue.is_error && issue.path.ends_with("justify.right.right")));
+        assert!(issues
+            .iter()
+            .any(|issue| !issue.is_error && issue.path.ends_with("justify.top")));
+        assert!(!issues
+            .iter()
+            .any(|issue| issue.path.ends_with("justify.left.left")));
+    }
+
+    #[test]
+    fn validates_http_api() {
+        let config: Config = toml::from_str(
+            r#"
+[bar.http]
+enabled = true
+address = "localhost"
+"#,
+        )
+        .expect("config should parse");
+
+        let issues = config.validate();
+        assert!(issues
+            .iter()
+            .any(|issue| issue.is_error && issue.path.ends_with("http.token")));
+        assert!(issues
+            .iter()
+            .any(|issue| issue.is_error && issue.path.ends_with("http.address")));
+
+        let config: Config = toml::from_str("[bar.http]\ntoken = \"\"").unwrap();
+        assert!(!config
+            .validate()
+            .iter()
+            .any(|issue| issue.path.contains("http")));
+    }
+
+    #[test]
+    fn parses_alert_conditions() {
+        let when = AlertWhen::parse(r#"disk("/Volumes/Data") > 90"#).unwrap();
+        assert_eq!(when.source, AlertSource::Disk("/Volumes/Data".to_string()));
+        assert_eq!(when.condition.comparison, Comparison::Above);
+        assert_eq!(when.hold, std::time::Duration::ZERO);
+
+        let when = AlertWhen::parse("memory >= 85 for 5m").unwrap();
+        assert_eq!(when.source, AlertSource::Memory);
+        assert_eq!(when.condition.value, 85.0);
+        assert_eq!(when.hold, std::time::Duration::from_secs(300));
+
+        let when = AlertWhen::parse("module('vpn_1') == 0").unwrap();
+        assert_eq!(when.source, AlertSource::Module("vpn_1".to_string()));
+        assert_eq!(
+            AlertWhen::parse("battery<20").unwrap().source,
+            AlertSource::Battery
+        );
+        assert_eq!(
+            AlertWhen::parse("disk > 90").unwrap().source,
+            AlertSource::Disk("/".to_string())
+        );
+
+        assert!(AlertWhen::parse("gpu > 90").is_err());
+        assert!(AlertWhen::parse("cpu").is_err());
+        assert!(AlertWhen::parse("cpu > hot").is_err());
+        assert!(AlertWhen::parse("cpu > 90 for ever").is_err());
+        assert!(AlertWhen::parse("module() > 1").is_err());
+    }
+
+    #[test]
+    fn parses_durations() {
+        use std::time::Duration;
+        assert_eq!(parse_duration("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration(" 5m "), Some(Duration::from_secs(300)));
+        assert_eq!(parse_duration("1.5h"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("1d"), Some(Duration::from_secs(86400)));
+        assert_eq!(parse_duration("5 weeks"), None);
+        assert_eq!(parse_duration("-1m"), None);
+    }
+
+    #[test]
+    fn parses_idle_config() {
+        let config: Config = toml::from_str("[bar.idle]\npause_after = 300").unwrap();
+        assert!(config.bar.idle.pause_when_locked);
+        assert_eq!(
+            config.bar.idle.idle_after(),
+            Some(std::time::Duration::from_secs(300))
+        );
+        assert_eq!(BarConfig::default().idle.idle_after(), None);
+
+        let config: Config = toml::from_str("[bar.idle]\npause_after = -1").unwrap();
+        assert!(config
+            .validate()
+            .iter()
+            .any(|issue| issue.is_error && issue.path.ends_with("idle.pause_after")));
+    }
+
+    #[test]
+    fn parses_tooltip_config() {
+        let config: Config = toml::from_str("[bar.tooltip]\ndelay_ms = 250").unwrap();
+        assert!(config.bar.tooltip.enabled);
+        assert_eq!(config.bar.tooltip.delay_ms, 250);
+        assert_eq!(BarConfig::default().tooltip, TooltipConfig::default());
+    }
+
+    #[test]
+    fn parses_screen_corner_radius() {
+        let config: Config = toml::from_str("[bar]\nscreen_corner_radius = \"auto\"").unwrap();
+        assert_eq!(config.bar.screen_corner_radius.pixels(true), 10.0);
+        assert_eq!(config.bar.screen_corner_radius.pixels(false), 0.0);
+        let config: Config = toml::from_str("[bar]\nscreen_corner_radius = 12").unwrap();
+        assert_eq!(config.bar.screen_corner_radius.pixels(false), 12.0);
+        assert_eq!(BarConfig::default().screen_corner_radius.pixels(true), 0.0);
+
+        let config: Config = toml::from_str("[bar]\nscreen_corner_radius = \"round\"").unwrap();
+        assert!(config
+            .validate()
+            .iter()
+            .any(|issue| !issue.is_error && issue.path.ends_with("screen_corner_radius")));
+    }
+
+    #[test]
+    fn resolves_fake_notch_per_display() {
+        let config: Config = toml::from_str(
+            r##"
+[bar.fake_notch]
+enabled = true
+width = "builtin"
+
+[[bar.fake_notch.displays]]
+name = "dell"
+width = "brio"
+color = "#1e1e2e"
+
+[[bar.fake_notch.displays]]
+name = "Sidecar"
+enabled = false
+
+[[bar.fake_notch.displays]]
+name = "LG"
+width = "30mm"
+color = "red"
+"##,
+        )
+        .unwrap();
+        let notch = &config.bar.fake_notch;
+
+        let (width, color) = notch.for_display("DELL U2723QE").unwrap();
+        assert_eq!(color, "#1e1e2e");
+        // A 102mm camera on a 600mm wide, 2400 pixel display
+        assert_eq!(width.pixels(2400.0, Some(600.0), None), Some(408.0));
+        assert_eq!(width.pixels(2400.0, None, None), None);
+
+        let (width, color) = notch.for_display("Studio Display").unwrap();
+        assert_eq!(color, "#000000");
+        assert_eq!(width.pixels(2560.0, None, Some(185.0)), Some(185.0));
+        assert_eq!(width.pixels(2560.0, None, None), None);
+
+        assert!(notch.for_display("Sidecar Display (AirPlay)").is_none());
+        assert!(FakeNotchConfig::default().for_display("DELL").is_none());
+        assert_eq!(
+            FakeNotchConfig::default().width.pixels(1920.0, None, None),
+            Some(200.0)
+        );
+
+        let issues = config.validate();
+        assert!(issues
+            .iter()
+            .any(|issue| issue.is_error && issue.path.ends_with("displays[2].color")));
+        assert!(!issues.iter().any(|issue| issue.path.contains("width")));
+
+        let config: Config = toml::from_str("[bar.fake_notch]\nwidth = \"webcam\"").unwrap();
+        assert!(config
+            .validate()
+            .iter()
+            .any(|issue| !issue.is_error && issue.path.ends_with("fake_notch.width")));
+    }
+
+    #[test]
+    fn history_popup_needs_history() {
+        let config: Config =
+            toml::from_str("[[modules.left.left]]\ntype = \"cpu\"\npopup = \"history\"").unwrap();
+        assert!(config
+            .validate()
+            .iter()
+            .any(|issue| !issue.is_error && issue.message.contains("history = true")));
+
+        let config: Config = toml::from_str(
+            "[[modules.left.left]]\ntype = \"cpu\"\npopup = \"history\"\nhistory = true",
+        )
+        .unwrap();
+        assert!(config
+            .validate()
+            .iter()
+            .all(|issue| !issue.path.ends_with("popup")));
+    }
+
+    #[test]
+    fn parses_context_menu() {
+        let config: Config = toml::from_str(
+            r#"
+[[modules.left.left]]
+type = "cpu"
+menu = [
+    { label = "Activity Monitor", command = "open -a 'Activity Monitor'" },
+    { label = "", command = "true" },
+]
+"#,
+        )
+        .unwrap();
+        let module = &config.modules.left.outer[0];
+        assert!(module.context_menu);
+        assert_eq!(module.menu[0].label, "Activity Monitor");
+        let issues = config.validate();
+        assert!(issues
+            .iter()
+            .any(|issue| !issue.is_error && issue.path.ends_with("menu[1]")));
+        assert!(!issues.iter().any(|issue| issue.path.ends_with("menu[0]")));
+    }
+
+    #[test]
+    fn validates_power_saving() {
+        let config: Config = toml::from_str("[bar.power_saving]\nslowdown = 2").unwrap();
+        assert_eq!(config.bar.power_saving.battery_below, 20.0);
+        assert!(config.validate().iter().all(|issue| !issue.is_error));
+
+        let config: Config =
+            toml::from_str("[bar.power_saving]\nbattery_below = 120\nslowdown = 0.5").unwrap();
+        let issues = config.validate();
+        assert!(issues
+            .iter()
+            .any(|issue| issue.is_error && issue.path.ends_with("power_saving.battery_below")));
+        assert!(issues
+            .iter()
+            .any(|issue| issue.is_error && issue.path.ends_with("power_saving.slowdown")));
+    }
+
+    #[test]
+    fn validates_alerts() {
+        let config: Config = toml::from_str(
+            r#"
+[[alerts.rules]]
+when = "cpu > 90 for 2m"
+actions = ["toast", "notification"]
+
+[[alerts.rules]]
+when = "gpu > 90"
+actions = ["email"]
+repeat = "often"
+"#,
+        )
+        .expect("config should parse");
+
+        let issues = config.validate();
+        let paths: Vec<&str> = issues.iter().map(|issue| issue.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "alerts.rules[1].when",
+                "alerts.rules[1].actions",
+                "alerts.rules[1].repeat"
+            ]
+        );
+        assert_eq!(config.alerts.rules[0].style, "warning");
+    }
+
     #[test]
     fn test_parse_hex_color() {
         assert_eq!(parse_hex_color("#ffffff"), Some((1.0, 1.0, 1.0, 1.0)));
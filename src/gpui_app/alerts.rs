@@ -0,0 +1,344 @@
+//! Alert rules over system metrics and module values.
+//!
+//! Each `[[alerts.rules]]` entry has a condition like `disk("/") > 90` or
+//! `memory > 85 for 5m`. The scheduler evaluates every rule each
+//! `alerts.interval` seconds; when a condition has held for its `for`
+//! duration the rule fires once, showing a toast and/or a macOS
+//! notification and running its `command`. It fires again after `repeat`
+//! while the condition still holds, or the next time it becomes true.
+//! Evaluation pauses while the screen is locked, so a rule that started
+//! holding meanwhile fires once someone can see it.
+//!
+//! Metrics come from the shared sampler, which the engine subscribes to for
+//! as long as a rule needs them. `module("id")` reads the value a module
+//! last reported, the same value its `color_rules` see.
+
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::config::{parse_duration, AlertSource, AlertWhen, AlertsConfig};
+use crate::gpui_app::scheduler;
+use crate::gpui_app::toast::{self, ToastStyle};
+use crate::system::metrics::{self, Metric, MetricsSnapshot, Subscription};
+
+const DEFAULT_MESSAGE: &str = "{name} ({value})";
+
+/// The running engine, if any rules are configured.
+static ENGINE: Mutex<Option<Engine>> = Mutex::new(None);
+static MODULE_VALUES: OnceLock<Mutex<HashMap<String, u8>>> = OnceLock::new();
+
+fn module_values() -> &'static Mutex<HashMap<String, u8>> {
+    MODULE_VALUES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Records the value a module reported, for `module("id")` rules.
+pub fn record_module_value(id: &str, value: Option<u8>) {
+    if let Ok(mut values) = module_values().lock() {
+        match value {
+            Some(value) => {
+                values.insert(id.to_string(), value);
+            }
+            None => {
+                values.remove(id);
+            }
+        }
+    }
+}
+
+/// Evaluation state of one rule.
+#[derive(Debug, Default)]
+struct RuleState {
+    /// When the condition started holding
+    since: Option<Instant>,
+    /// When the rule last fired, while the condition holds
+    fired_at: Option<Instant>,
+}
+
+impl RuleState {
+    /// Returns true if the rule should fire for this evaluation.
+    fn check(
+        &mut self,
+        holds: bool,
+        hold: Duration,
+        repeat: Option<Duration>,
+        now: Instant,
+    ) -> bool {
+        if !holds {
+            *self = Self::default();
+            return false;
+        }
+        let since = *self.since.get_or_insert(now);
+        if now.duration_since(since) < hold {
+            return false;
+        }
+        let due = match self.fired_at {
+            None => true,
+            Some(at) => repeat.is_some_and(|repeat| now.duration_since(at) >= repeat),
+        };
+        if due {
+            self.fired_at = Some(now);
+        }
+        due
+    }
+}
+
+/// A rule ready to evaluate.
+struct Rule {
+    name: String,
+    when: AlertWhen,
+    message: String,
+    /// Toast style, None = no toast
+    toast: Option<ToastStyle>,
+    notification: bool,
+    command: Option<String>,
+    repeat: Option<Duration>,
+    state: RuleState,
+}
+
+impl Rule {
+    /// Fires the rule's actions for `value`.
+    fn fire(&self, value: f64) {
+        let value = format_value(value);
+        let message = self
+            .message
+            .replace("{name}", &self.name)
+            .replace("{value}", &value);
+        log::info!("Alert '{}': {}", self.name, message);
+        if let Some(style) = self.toast {
+            toast::show(&message, style, None);
+        }
+        let notification = self.notification.then(|| {
+            format!(
+                "display notification {} with title {}",
+                applescript_string(&message),
+                applescript_string(&self.name)
+            )
+        });
+        let command = self.command.clone();
+        let name = self.name.clone();
+        if notification.is_none() && command.is_none() {
+            return;
+        }
+        std::thread::spawn(move || {
+            if let Some(script) = notification {
+                if let Err(err) = Command::new("osascript").args(["-e", &script]).status() {
+                    log::warn!("Alert '{}': can't post notification: {}", name, err);
+                }
+            }
+            if let Some(command) = command {
+                match Command::new("sh")
+                    .args(["-c", &command])
+                    .env("SINEW_ALERT_NAME", &name)
+                    .env("SINEW_ALERT_VALUE", &value)
+                    .status()
+                {
+                    Ok(status) if !status.success() => {
+                        log::warn!("Alert '{}': command exited with {}", name, status)
+                    }
+                    Err(err) => log::warn!("Alert '{}': command failed: {}", name, err),
+                    Ok(_) => {}
+                }
+            }
+        });
+    }
+}
+
+/// Formats a value without decimals when it's whole.
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+/// Quotes `text` as an AppleScript string literal.
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The sampled metric behind `source`, None for module values.
+fn metric(source: &AlertSource) -> Option<Metric> {
+    match source {
+        AlertSource::Cpu => Some(Metric::Cpu),
+        AlertSource::Memory => Some(Metric::Memory),
+        AlertSource::Disk(path) => Some(Metric::Disk(path.clone())),
+        AlertSource::Temperature => Some(Metric::Temperature),
+        AlertSource::Battery => Some(Metric::Battery),
+        AlertSource::Module(_) => None,
+    }
+}
+
+/// Current value of `source`, None while it hasn't been read yet.
+fn current_value(source: &AlertSource, snapshot: Option<&MetricsSnapshot>) -> Option<f64> {
+    match source {
+        AlertSource::Module(id) => module_values()
+            .lock()
+            .ok()
+            .and_then(|values| values.get(id).copied())
+            .map(f64::from),
+        _ => snapshot?.value(&metric(source)?).map(f64::from),
+    }
+}
+
+/// Builds the rules of `config`, logging and skipping invalid ones (config
+/// validation reports them too).
+fn build_rules(config: &AlertsConfig) -> Vec<Rule> {
+    config
+        .rules
+        .iter()
+        .filter_map(|rule| {
+            let when = match AlertWhen::parse(&rule.when) {
+                Ok(when) => when,
+                Err(err) => {
+                    log::warn!("alerts: skipping '{}': {}", rule.when, err);
+                    return None;
+                }
+            };
+            let has = |action: &str| rule.actions.iter().any(|a| a == action);
+            Some(Rule {
+                name: rule.name.clone().unwrap_or_else(|| rule.when.clone()),
+                when,
+                message: rule
+                    .message
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_MESSAGE.to_string()),
+                toast: has("toast").then(|| ToastStyle::parse(&rule.style).unwrap_or_default()),
+                notification: has("notification"),
+                command: rule.command.clone(),
+                repeat: rule
+                    .repeat
+                    .as_deref()
+                    .and_then(parse_duration)
+                    .filter(|d| !d.is_zero()),
+                state: RuleState::default(),
+            })
+        })
+        .collect()
+}
+
+/// Evaluates every rule against the latest readings.
+fn evaluate(rules: &mut [Rule]) {
+    let snapshot = metrics::latest();
+    let now = Instant::now();
+    for rule in rules {
+        // Not read yet: keep the current state rather than resetting it
+        let Some(value) = current_value(&rule.when.source, snapshot.as_ref()) else {
+            continue;
+        };
+        let holds = rule.when.condition.matches(value);
+        if rule.state.check(holds, rule.when.hold, rule.repeat, now) {
+            rule.fire(value);
+        }
+    }
+}
+
+/// A scheduled set of rules.
+struct Engine {
+    config: AlertsConfig,
+    stop: Arc<AtomicBool>,
+    /// Keeps the metrics the rules read sampled
+    _subscriptions: Vec<Subscription>,
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Applies `[alerts]`, restarting the engine when the rules changed.
+pub fn sync(config: &AlertsConfig) {
+    let Ok(mut engine) = ENGINE.lock() else {
+        return;
+    };
+    if engine.as_ref().is_some_and(|e| e.config == *config) {
+        return;
+    }
+    // Dropping the old engine stops its task
+    *engine = None;
+
+    let mut rules = build_rules(config);
+    if rules.is_empty() {
+        return;
+    }
+    let interval = Duration::from_secs_f64(config.interval.max(1.0));
+    let mut metrics: Vec<Metric> = Vec::new();
+    for metric in rules.iter().filter_map(|r| metric(&r.when.source)) {
+        if !metrics.contains(&metric) {
+            metrics.push(metric);
+        }
+    }
+    let subscriptions = metrics
+        .into_iter()
+        .map(|metric| metrics::subscribe(metric, interval, |_| {}))
+        .collect();
+
+    let stop = Arc::new(AtomicBool::new(false));
+    log::info!(
+        "alerts: evaluating {} rule(s) every {:?}",
+        rules.len(),
+        interval
+    );
+    scheduler::schedule_unlocked(interval, Arc::clone(&stop), move || evaluate(&mut rules));
+    *engine = Some(Engine {
+        config: config.clone(),
+        stop,
+        _subscriptions: subscriptions,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fires_after_hold_then_rearms() {
+        let hold = Duration::from_secs(300);
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut state = RuleState::default();
+
+        assert!(!state.check(true, hold, None, at(0)));
+        assert!(!state.check(true, hold, None, at(200)));
+        assert!(state.check(true, hold, None, at(300)));
+        // Fires once per crossing
+        assert!(!state.check(true, hold, None, at(1000)));
+        // Dropping below restarts the hold
+        assert!(!state.check(false, hold, None, at(1010)));
+        assert!(!state.check(true, hold, None, at(1020)));
+        assert!(state.check(true, hold, None, at(1320)));
+    }
+
+    #[test]
+    fn repeats_while_condition_holds() {
+        let repeat = Some(Duration::from_secs(3600));
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut state = RuleState::default();
+
+        assert!(state.check(true, Duration::ZERO, repeat, at(0)));
+        assert!(!state.check(true, Duration::ZERO, repeat, at(1800)));
+        assert!(state.check(true, Duration::ZERO, repeat, at(3600)));
+    }
+
+    #[test]
+    fn reads_module_values() {
+        let source = AlertSource::Module("test-alerts-vpn".to_string());
+        assert_eq!(current_value(&source, None), None);
+        record_module_value("test-alerts-vpn", Some(42));
+        assert_eq!(current_value(&source, None), Some(42.0));
+        record_module_value("test-alerts-vpn", None);
+        assert_eq!(current_value(&source, None), None);
+        assert_eq!(current_value(&AlertSource::Cpu, None), None);
+    }
+
+    #[test]
+    fn formats_values() {
+        assert_eq!(format_value(93.0), "93");
+        assert_eq!(format_value(71.34), "71.3");
+    }
+}
@@ -4,8 +4,10 @@ use async_channel::{Receiver, Sender};
 use futures_util::future::FutureExt;
 use futures_util::{pin_mut, select};
 use gpui::{
-    div, prelude::*, px, Context, MouseButton, ParentElement, Styled, Task, WeakEntity, Window,
+    div, prelude::*, px, Context, MouseButton, ParentElement, Rgba, Styled, Task, WeakEntity,
+    Window,
 };
+use std::collections::{HashMap, HashSet};
 use std::process::Command;
 use std::ptr::NonNull;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
@@ -14,16 +16,60 @@ use std::sync::RwLock;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-use crate::config::{load_config, Config, ConfigWatcher, SharedConfig};
+use crate::config::{
+    load_config, parse_hex_color, Config, ConfigWatcher, IdleConfig, SharedConfig,
+};
+use crate::gpui_app::alerts;
 use crate::gpui_app::camera;
-use crate::gpui_app::modules::{create_module, PositionedModule};
+use crate::gpui_app::history;
+use crate::gpui_app::hotkeys;
+use crate::gpui_app::hover_intent;
+use crate::gpui_app::layout::{
+    Justify, LayoutReport, LayoutSnapshot, OverflowStrategy, Zone, HALVES, SPILL_ZONES,
+};
+use crate::gpui_app::modules::calendar;
+use crate::gpui_app::modules::config_error;
+use crate::gpui_app::modules::context_menu;
+use crate::gpui_app::modules::notch::{self, NotchTrigger};
+use crate::gpui_app::modules::privacy;
+use crate::gpui_app::modules::snoozed;
+use crate::gpui_app::modules::{
+    configured_modules, create_module, ColorTarget, MouseEvent, PositionedModule, RuleColors,
+};
+use crate::gpui_app::notify;
+use crate::gpui_app::power_saving::{self, Policy};
+use crate::gpui_app::progress;
+use crate::gpui_app::screenshot;
+use crate::gpui_app::snooze::{self, Until};
 use crate::gpui_app::theme::Theme;
+use crate::gpui_app::toast;
+use crate::gpui_app::tooltip;
+use crate::gpui_app::ui_state;
 use crate::ipc::{self, IpcCommand};
+use crate::system::idle::{self, Presence};
 
 /// Global registry of all bar views for synchronized updates
 static BAR_VIEWS: Mutex<Vec<(u64, WeakEntity<BarView>)>> = Mutex::new(Vec::new());
 static BAR_VIEW_COUNTER: AtomicU64 = AtomicU64::new(1);
 
+/// Horizontal padding of the bar root.
+const BAR_PADDING_X: f32 = 8.0;
+
+/// Width of the gap reserved for the notch between the bar halves.
+const NOTCH_GAP: f32 = 200.0;
+
+/// Corner radius of the bottom of a fake notch.
+const FAKE_NOTCH_RADIUS: f32 = 8.0;
+
+/// How long timed module updates wait while a popup is visible.
+const POPUP_UPDATE_DELAY: Duration = Duration::from_millis(500);
+
+/// How often paused bars check whether someone is back.
+const PAUSE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Longest the refresh task sleeps when no module has a timer pending.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
 /// Flag to ensure only one refresh task runs globally
 static REFRESH_TASK_STARTED: AtomicBool = AtomicBool::new(false);
 
@@ -41,7 +87,9 @@ fn auto_popup_id() -> Option<String> {
 
 /// Flag set when active application changes (checked by refresh task)
 static APP_CHANGED: AtomicBool = AtomicBool::new(false);
-static BAR_UPDATE_REQUESTED: AtomicBool = AtomicBool::new(false);
+/// Bumped by every wake-up request. Each bar compares it with the last value
+/// it saw, so every bar updates its modules once per request.
+static WAKE_GENERATION: AtomicU64 = AtomicU64::new(0);
 static REFRESH_PENDING: AtomicBool = AtomicBool::new(false);
 
 static REFRESH_BUS: OnceLock<RefreshBus> = OnceLock::new();
@@ -73,15 +121,93 @@ fn refresh_bus() -> &'static RefreshBus {
     REFRESH_BUS.get_or_init(RefreshBus::new)
 }
 
-/// Request an immediate bar refresh (called from modules that need fast updates)
+/// Wakes the bars to update every module and re-render. This is how
+/// background threads, IPC and system notifications deliver changes; the
+/// bar doesn't poll for them.
 pub fn request_immediate_refresh() {
-    BAR_UPDATE_REQUESTED.store(true, Ordering::Relaxed);
+    WAKE_GENERATION.fetch_add(1, Ordering::Relaxed);
     if REFRESH_PENDING.swap(true, Ordering::Relaxed) {
         return;
     }
     refresh_bus().notify();
 }
 
+/// When the bars next need to update a module on a timer.
+struct Schedule {
+    /// Earliest timed update of each bar, by bar id
+    deadlines: Vec<(u64, Instant)>,
+    /// When the refresh task's timer fires
+    waiting_until: Option<Instant>,
+}
+
+static SCHEDULE: Mutex<Schedule> = Mutex::new(Schedule {
+    deadlines: Vec::new(),
+    waiting_until: None,
+});
+
+/// Tells the refresh task to restart its timer for an earlier deadline.
+fn reschedule_channel() -> &'static (Sender<()>, Receiver<()>) {
+    static CHANNEL: OnceLock<(Sender<()>, Receiver<()>)> = OnceLock::new();
+    CHANNEL.get_or_init(|| async_channel::bounded(1))
+}
+
+/// Records when bar `id` next needs to update a module on a timer.
+fn set_deadline(id: u64, deadline: Option<Instant>) {
+    let Ok(mut schedule) = SCHEDULE.lock() else {
+        return;
+    };
+    schedule.deadlines.retain(|(bar, _)| *bar != id);
+    if let Some(deadline) = deadline {
+        schedule.deadlines.push((id, deadline));
+        if schedule
+            .waiting_until
+            .is_none_or(|waiting_until| deadline < waiting_until)
+        {
+            let _ = reschedule_channel().0.try_send(());
+        }
+    }
+}
+
+/// Time until the earliest timed update of any bar, dropping deadlines that
+/// have passed (the render they trigger records new ones).
+fn time_to_next_deadline() -> Duration {
+    let Ok(mut schedule) = SCHEDULE.lock() else {
+        return IDLE_TIMEOUT;
+    };
+    let now = Instant::now();
+    let next = schedule
+        .deadlines
+        .iter()
+        .map(|(_, deadline)| *deadline)
+        .min();
+    schedule.deadlines.retain(|(_, deadline)| *deadline > now);
+    let wait = next.map_or(IDLE_TIMEOUT, |next| next.saturating_duration_since(now));
+    schedule.waiting_until = Some(now + wait);
+    wait
+}
+
+/// The screen a bar sits on, as far as its notch is concerned.
+#[derive(Debug, Clone, Default)]
+pub struct BarScreen {
+    /// Whether a camera housing cuts into the menu bar
+    pub has_notch: bool,
+    /// Display name, matched by `bar.fake_notch.displays`
+    pub name: String,
+    /// Width in pixels
+    pub width: f64,
+    /// Physical width in millimeters, when the display reports it
+    pub width_mm: Option<f64>,
+    /// Width of the built-in display's notch, when it is on and has one
+    pub builtin_notch: Option<f64>,
+}
+
+/// A notch drawn between the bar halves on a screen without one.
+#[derive(Debug, Clone, Copy)]
+struct FakeNotch {
+    width: f32,
+    color: Rgba,
+}
+
 /// The main menu bar view rendered with GPUI.
 pub struct BarView {
     id: u64,
@@ -97,32 +223,95 @@ pub struct BarView {
     right_outer_modules: Vec<PositionedModule>,
     /// Right side inner modules (far right edge)
     right_inner_modules: Vec<PositionedModule>,
-    last_update: Instant,
-    update_interval: Duration,
+    /// Left side center modules (anchored to the middle of the left half)
+    left_center_modules: Vec<PositionedModule>,
+    /// Right side center modules (anchored to the middle of the right half)
+    right_center_modules: Vec<PositionedModule>,
+    /// The bar's screen; with a notch, center zones anchor to each half
+    /// instead of the screen center
+    screen: BarScreen,
+    /// Notch drawn on a screen without one, laid out like a real notch
+    fake_notch: Option<FakeNotch>,
+    /// Radius of the bar's top corners, following rounded display corners
+    corner_radius: f32,
+    /// How the notch zone expands its panel (None when disabled)
+    notch_trigger: Option<NotchTrigger>,
+    /// Last `WAKE_GENERATION` this bar updated its modules for
+    seen_wake: u64,
     camera_indicator: bool,
     /// Last known camera active state (for change detection)
     last_camera_active: bool,
+    /// Color the bottom border while the camera/microphone is in use
+    privacy_border: bool,
+    /// Outline modules involved in zone overlaps
+    debug_layout: bool,
+    /// How to resolve zones that collide
+    overflow: OverflowStrategy,
+    /// Horizontal gap between modules within a zone
+    module_spacing: f32,
+    /// Per-zone alignment from `bar.justify`
+    justify: HashMap<Zone, Justify>,
+    /// Zone that shows toast messages
+    toast_zone: Zone,
+    /// Zone that shows progress items
+    progress_zone: Zone,
+    /// Zone that shows notify items
+    notify_zone: Zone,
+    /// When timed updates pause for a locked screen or idle user
+    idle: IdleConfig,
+    /// Whether timed updates were paused at the last render
+    paused: bool,
+    /// Module bounds measured during the last prepaint
+    layout: Arc<Mutex<LayoutSnapshot>>,
+    /// Overlaps and hidden modules from the last layout analysis
+    layout_report: LayoutReport,
+    /// Offset of each half's center zone from the start of the half
+    center_offsets: [f32; 2],
+    /// Layout generation the report and center offsets were computed from
+    analyzed_generation: Option<u64>,
     /// Receiver for IPC commands (set, trigger, etc.)
     ipc_rx: Receiver<IpcCommand>,
-    /// Task that periodically checks camera state and triggers re-renders
+    /// Task that re-renders the bars when woken or when a module timer is due
     #[allow(dead_code)]
     refresh_task: Option<Task<()>>,
 }
 
 impl BarView {
-    pub fn new() -> Self {
+    pub fn new(screen: BarScreen) -> Self {
         let config = load_config();
         let camera_indicator = config.bar.camera_indicator;
+        let debug_layout = config.bar.debug_layout;
+        let privacy_border = privacy::border_enabled(&config.modules);
+        let overflow = OverflowStrategy::parse(&config.bar.overflow);
+        let module_spacing = config.bar.module_spacing.max(0.0) as f32;
+        let justify = Self::configure_justify(&config);
+        let toast_zone = Self::configure_toast(&config);
+        let progress_zone = Self::configure_progress(&config);
+        let notify_zone = Self::configure_notify(&config);
+        let idle = config.bar.idle.clone();
+        calendar::configure(&config.bar.calendar);
+        context_menu::set_editor(config.bar.editor.clone());
         let theme = Theme::from_config(&config.bar);
-        let (left_outer, left_inner, right_outer, right_inner) = Self::build_modules(&config);
+        let fake_notch = Self::configure_fake_notch(&config, &screen);
+        let corner_radius = config.bar.screen_corner_radius.pixels(screen.has_notch) as f32;
+        let notch_trigger = notch::register(
+            &config.bar.notch,
+            screen.has_notch || fake_notch.is_some(),
+            fake_notch.map_or(NOTCH_GAP, |notch| notch.width),
+        );
+        if !screenshot::active() {
+            hotkeys::register(&config.hotkeys);
+        }
+        let (left_outer, left_inner, right_outer, right_inner, left_center, right_center) =
+            Self::build_modules(&config);
         let shared_config: SharedConfig = Arc::new(RwLock::new(config));
 
         // Set up config file watcher
-        let config_watcher = ConfigWatcher::new(Arc::clone(&shared_config))
-            .map_err(|e| log::warn!("Failed to set up config watcher: {}", e))
-            .ok();
+        let config_watcher =
+            ConfigWatcher::new(Arc::clone(&shared_config), request_immediate_refresh)
+                .map_err(|e| log::warn!("Failed to set up config watcher: {}", e))
+                .ok();
 
-        let update_interval = Duration::from_millis(500);
         Self {
             id: BAR_VIEW_COUNTER.fetch_add(1, Ordering::Relaxed),
             config: shared_config,
@@ -133,18 +322,37 @@ impl BarView {
             left_inner_modules: left_inner,
             right_outer_modules: right_outer,
             right_inner_modules: right_inner,
-            // Initialize to past so first render triggers update immediately
-            last_update: Instant::now() - update_interval,
-            update_interval,
+            left_center_modules: left_center,
+            right_center_modules: right_center,
+            screen,
+            fake_notch,
+            corner_radius,
+            notch_trigger,
+            seen_wake: WAKE_GENERATION.load(Ordering::Relaxed),
             camera_indicator,
             last_camera_active: camera::is_camera_active(),
+            privacy_border,
+            debug_layout,
+            overflow,
+            module_spacing,
+            justify,
+            toast_zone,
+            progress_zone,
+            notify_zone,
+            idle,
+            paused: false,
+            layout: Arc::new(Mutex::new(LayoutSnapshot::default())),
+            layout_report: LayoutReport::default(),
+            center_offsets: [0.0; 2],
+            analyzed_generation: None,
             ipc_rx: ipc::subscribe_ipc_commands(),
             refresh_task: None,
         }
     }
 
     /// Registers this bar view and starts the global refresh task if needed.
-    /// Uses GPUI's async system to periodically check camera state and trigger re-renders.
+    /// The task re-renders the bars when something wakes them and when a
+    /// module's update timer is due; otherwise it sleeps.
     fn start_refresh_task(&mut self, cx: &Context<Self>) {
         if self.refresh_task.is_some() {
             return; // Already registered
@@ -173,44 +381,36 @@ impl BarView {
 
         // Start the global refresh task
         let refresh_rx = refresh_bus().subscribe();
+        let reschedule_rx = reschedule_channel().1.clone();
+        // Wake-ups requested before anyone listened were never delivered
+        REFRESH_PENDING.store(false, Ordering::Relaxed);
         let task = cx.spawn(async move |_, cx| {
-            let mut last_camera_active = camera::is_camera_active();
-
             loop {
                 let mut should_refresh = false;
                 let refresh_fut = refresh_rx.recv().fuse();
+                let reschedule_fut = reschedule_rx.recv().fuse();
                 let timer_fut = cx
                     .background_executor()
-                    .timer(Duration::from_secs(1))
+                    .timer(time_to_next_deadline())
                     .fuse();
-                pin_mut!(refresh_fut, timer_fut);
+                pin_mut!(refresh_fut, reschedule_fut, timer_fut);
 
                 select! {
                     signal = refresh_fut => {
                         if signal.is_ok() {
                             should_refresh = true;
                         }
-                    }
-                    _ = timer_fut => {
-                        // Always refresh on the 1s timer so modules with
-                        // background threads (weather, etc.) get their
-                        // dirty flags polled. The 500ms rate-limit on
-                        // update_modules() prevents excessive work.
-                        should_refresh = true;
-
-                        let current_active = camera::is_camera_active();
-                        if current_active != last_camera_active {
-                            log::info!(
-                                "Camera state changed: {} -> {}",
-                                last_camera_active,
-                                current_active
-                            );
-                            last_camera_active = current_active;
-                        }
                         if APP_CHANGED.swap(false, Ordering::SeqCst) {
                             log::debug!("Active app changed, refreshing");
                         }
                     }
+                    _ = reschedule_fut => {
+                        // A bar needs an update sooner; restart the timer
+                    }
+                    _ = timer_fut => {
+                        // A module's update timer is due
+                        should_refresh = true;
+                    }
                 }
 
                 let view_count = if let Ok(mut views) = BAR_VIEWS.lock() {
@@ -244,6 +444,57 @@ impl Drop for BarView {
         if let Ok(mut views) = BAR_VIEWS.lock() {
             views.retain(|(id, _)| *id != self.id);
         }
+        set_deadline(self.id, None);
+    }
+}
+
+/// Runs a module's `update()`, recording its duration for `status`. A panic
+/// is logged and counted as an error instead of taking down the bar.
+fn update_module(pm: &mut PositionedModule) -> bool {
+    let start = Instant::now();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| pm.module.update()));
+    let id = pm.module.id();
+    ipc::record_module_update(id, start.elapsed(), result.is_ok());
+    let mut changed = result.unwrap_or_else(|_| {
+        log::error!("Module '{}' panicked during update", id);
+        false
+    });
+    // Modules color their own value text and icon, so hand over rule changes
+    let value = pm.module.value();
+    alerts::record_module_value(id, value);
+    if pm.history {
+        history::record(id, value);
+    }
+    let rule_colors = RuleColors {
+        text: pm.style.rule_color(ColorTarget::Text, value),
+        icon: pm.style.rule_color(ColorTarget::Icon, value),
+    };
+    if ipc::is_watched(id) {
+        let color = rule_colors.text.or(pm.text_color);
+        ipc::record_module_state(id, pm.module.display_text(), value, color);
+    }
+    if rule_colors != pm.rule_colors {
+        pm.rule_colors = rule_colors;
+        pm.module.set_rule_colors(rule_colors);
+        changed = true;
+    }
+    // Modules that own their toggle state may change it themselves
+    match pm.module.toggle_state() {
+        Some(active) if active != pm.toggle_active => {
+            pm.toggle_active = active;
+            true
+        }
+        _ => changed,
+    }
+}
+
+/// Sets a module's toggle state and tells the module. The state of config
+/// toggles is saved so it survives reloads and restarts.
+fn set_toggle(pm: &mut PositionedModule, active: bool) {
+    pm.toggle_active = active;
+    pm.module.on_toggle(active);
+    if pm.module.toggle_state().is_none() {
+        ui_state::set_toggled(pm.module.id(), active);
     }
 }
 
@@ -255,7 +506,7 @@ fn setup_workspace_observer() {
 
     use block2::RcBlock;
     use objc2_app_kit::NSWorkspace;
-    use objc2_foundation::{NSNotification, NSNotificationName};
+    use objc2_foundation::{NSDistributedNotificationCenter, NSNotification, NSNotificationName};
 
     unsafe {
         let workspace = NSWorkspace::sharedWorkspace();
@@ -266,6 +517,7 @@ fn setup_workspace_observer() {
 
         let handler = RcBlock::new(|_notification: NonNull<NSNotification>| {
             APP_CHANGED.store(true, Ordering::SeqCst);
+            request_immediate_refresh();
         });
 
         notification_center.addObserverForName_object_queue_usingBlock(
@@ -275,12 +527,26 @@ fn setup_workspace_observer() {
             &handler,
         );
 
-        log::info!("Workspace observer set up for app activation notifications");
+        // Resume paused updates as soon as the screen unlocks
+        let distributed_center = NSDistributedNotificationCenter::defaultCenter();
+        let name = NSNotificationName::from_str("com.apple.screenIsUnlocked");
+        let unlock_handler = RcBlock::new(|_notification: NonNull<NSNotification>| {
+            request_immediate_refresh();
+        });
+        distributed_center.addObserverForName_object_queue_usingBlock(
+            Some(&name),
+            None,
+            None,
+            &unlock_handler,
+        );
+
+        log::info!("Workspace observer set up for app activation and unlock notifications");
     }
 }
 
 impl BarView {
-    /// Builds modules for the full-width bar, separated into 4 zones.
+    /// Builds modules for the full-width bar, separated into 6 zones.
+    #[allow(clippy::type_complexity)]
     fn build_modules(
         config: &Config,
     ) -> (
@@ -288,39 +554,42 @@ impl BarView {
         Vec<PositionedModule>,
         Vec<PositionedModule>,
         Vec<PositionedModule>,
+        Vec<PositionedModule>,
+        Vec<PositionedModule>,
     ) {
         let mut left_outer = Vec::new();
         let mut left_inner = Vec::new();
         let mut right_outer = Vec::new();
         let mut right_inner = Vec::new();
-
-        // Left side outer (far left edge)
-        for (i, cfg) in config.modules.left.outer.iter().enumerate() {
-            if let Some(module) = create_module(cfg, i) {
-                left_outer.push(module);
-            }
-        }
-        // Left side inner (toward notch/center)
-        for (i, cfg) in config.modules.left.inner.iter().enumerate() {
-            if let Some(module) = create_module(cfg, i + 1000) {
-                left_inner.push(module);
-            }
-        }
-
-        // Right side outer (toward notch/center)
-        for (i, cfg) in config.modules.right.outer.iter().enumerate() {
-            if let Some(module) = create_module(cfg, i + 2000) {
-                right_outer.push(module);
-            }
-        }
-        // Right side inner (far right edge)
-        for (i, cfg) in config.modules.right.inner.iter().enumerate() {
-            if let Some(module) = create_module(cfg, i + 3000) {
-                right_inner.push(module);
+        let mut left_center = Vec::new();
+        let mut right_center = Vec::new();
+
+        // The config error badge leads the far left zone
+        left_outer.extend(config_error::badge());
+        for (zone, index, cfg) in configured_modules(config) {
+            let Some(module) = create_module(cfg, index) else {
+                continue;
+            };
+            match zone {
+                Zone::LeftOuter => left_outer.push(module),
+                Zone::LeftInner => left_inner.push(module),
+                Zone::RightOuter => right_outer.push(module),
+                Zone::RightInner => right_inner.push(module),
+                Zone::LeftCenter => left_center.push(module),
+                Zone::RightCenter => right_center.push(module),
             }
         }
-
-        (left_outer, left_inner, right_outer, right_inner)
+        // The snooze chevron ends the far right zone
+        right_inner.push(snoozed::indicator());
+
+        (
+            left_outer,
+            left_inner,
+            right_outer,
+            right_inner,
+            left_center,
+            right_center,
+        )
     }
 
     /// Checks for config changes and rebuilds modules if needed.
@@ -329,25 +598,63 @@ impl BarView {
             if watcher.check_and_reload() {
                 log::info!("Config reloaded, rebuilding modules");
                 ipc::clear_module_ids();
+                snooze::on_reload();
 
                 // Get the updated config
                 if let Ok(config) = self.config.read() {
                     // Sync launch agent state
                     crate::launch_agent::sync(config.bar.launch_at_login);
+                    crate::http_api::sync(&config.bar.http);
+                    alerts::sync(&config.alerts);
+                    power_saving::sync(&config.bar.power_saving);
 
                     // Update theme
                     self.theme = Theme::from_config(&config.bar);
                     self.camera_indicator = config.bar.camera_indicator;
+                    self.debug_layout = config.bar.debug_layout;
+                    self.privacy_border = privacy::border_enabled(&config.modules);
+                    self.overflow = OverflowStrategy::parse(&config.bar.overflow);
+                    self.module_spacing = config.bar.module_spacing.max(0.0) as f32;
+                    self.justify = Self::configure_justify(&config);
+                    self.toast_zone = Self::configure_toast(&config);
+                    self.progress_zone = Self::configure_progress(&config);
+                    self.notify_zone = Self::configure_notify(&config);
+                    self.idle = config.bar.idle.clone();
+                    calendar::configure(&config.bar.calendar);
+                    context_menu::set_editor(config.bar.editor.clone());
+                    self.fake_notch = Self::configure_fake_notch(&config, &self.screen);
+                    self.corner_radius = config
+                        .bar
+                        .screen_corner_radius
+                        .pixels(self.screen.has_notch)
+                        as f32;
+                    self.notch_trigger =
+                        notch::register(&config.bar.notch, self.notched(), self.notch_width());
+                    hotkeys::register(&config.hotkeys);
 
                     // Rebuild modules
-                    let (left_outer, left_inner, right_outer, right_inner) =
-                        Self::build_modules(&config);
+                    let (
+                        left_outer,
+                        left_inner,
+                        right_outer,
+                        right_inner,
+                        left_center,
+                        right_center,
+                    ) = Self::build_modules(&config);
                     self.left_outer_modules = left_outer;
                     self.left_inner_modules = left_inner;
                     self.right_outer_modules = right_outer;
                     self.right_inner_modules = right_inner;
+                    self.left_center_modules = left_center;
+                    self.right_center_modules = right_center;
                     self.config_version += 1;
 
+                    // Old measurements refer to modules that no longer exist
+                    if let Ok(mut layout) = self.layout.lock() {
+                        layout.clear();
+                    }
+                    self.layout_report = LayoutReport::default();
+
                     return true;
                 }
             }
@@ -355,30 +662,48 @@ impl BarView {
         false
     }
 
-    /// Updates all modules and returns true if any changed.
-    fn update_modules(&mut self) -> bool {
+    /// Updates the modules whose timer is due, or all of them when `woken`,
+    /// and schedules their next timed update, later while `power_saving`.
+    /// Returns true if any changed.
+    fn update_modules(&mut self, woken: bool, power_saving: Option<Policy>) -> bool {
+        let now = Instant::now();
         let mut changed = false;
-        for pm in &mut self.left_outer_modules {
-            if pm.module.update() {
-                changed = true;
+        for pm in self.modules_mut() {
+            if !woken && pm.update_due.is_none_or(|due| due > now) {
+                continue;
             }
-        }
-        for pm in &mut self.left_inner_modules {
-            if pm.module.update() {
+            if update_module(pm) {
                 changed = true;
             }
+            let policy = power_saving.filter(|_| pm.power_saving);
+            pm.update_due = pm.module.next_update().map(|delay| {
+                let delay = policy.map_or(delay, |policy| policy.stretch(delay));
+                Instant::now() + delay
+            });
         }
-        for pm in &mut self.right_outer_modules {
-            if pm.module.update() {
-                changed = true;
-            }
+        changed
+    }
+
+    /// Whether timed updates should wait because the screen is locked or
+    /// nobody has touched the machine for `bar.idle.pause_after`.
+    fn updates_paused(&self) -> bool {
+        let idle_after = self.idle.idle_after();
+        if !self.idle.pause_when_locked && idle_after.is_none() {
+            return false;
         }
-        for pm in &mut self.right_inner_modules {
-            if pm.module.update() {
-                changed = true;
+        match Presence::current(idle_after) {
+            Presence::Active => false,
+            Presence::Idle => true,
+            Presence::Locked => {
+                self.idle.pause_when_locked
+                    || idle_after.is_some_and(|after| idle::idle_time() >= after)
             }
         }
-        changed
+    }
+
+    /// Earliest timed update of this bar's modules.
+    fn next_update_due(&self) -> Option<Instant> {
+        self.modules().filter_map(|pm| pm.update_due).min()
     }
 
     /// Drains pending IPC commands from the channel (max 100 per frame).
@@ -403,30 +728,469 @@ impl BarView {
                 IpcCommand::Trigger { module_id, event } => match event.as_str() {
                     "update" => {
                         if let Some(pm) = self.find_module_mut(&module_id) {
-                            pm.module.update();
+                            pm.module.refresh();
+                            update_module(pm);
                         }
                     }
                     "popup" => {
                         crate::gpui_app::popup_manager::toggle_popup(&module_id);
                     }
+                    "toggle" => self.toggle_module(&module_id),
+                    "hide" => snooze::snooze(&module_id, Until::Restart),
+                    "click" => self.click_module(&module_id),
                     _ => {}
                 },
             }
         }
     }
 
-    /// Finds a mutable reference to a positioned module by ID across all zones.
-    fn find_module_mut(&mut self, id: &str) -> Option<&mut PositionedModule> {
+    /// Iterates over the modules of all zones.
+    fn modules(&self) -> impl Iterator<Item = &PositionedModule> {
+        self.left_outer_modules
+            .iter()
+            .chain(self.left_inner_modules.iter())
+            .chain(self.right_outer_modules.iter())
+            .chain(self.right_inner_modules.iter())
+            .chain(self.left_center_modules.iter())
+            .chain(self.right_center_modules.iter())
+    }
+
+    /// Iterates mutably over the modules of all zones.
+    fn modules_mut(&mut self) -> impl Iterator<Item = &mut PositionedModule> {
         self.left_outer_modules
             .iter_mut()
             .chain(self.left_inner_modules.iter_mut())
             .chain(self.right_outer_modules.iter_mut())
             .chain(self.right_inner_modules.iter_mut())
-            .find(|pm| pm.module.id() == id)
+            .chain(self.left_center_modules.iter_mut())
+            .chain(self.right_center_modules.iter_mut())
+    }
+
+    /// Finds a mutable reference to a positioned module by ID across all zones.
+    fn find_module_mut(&mut self, id: &str) -> Option<&mut PositionedModule> {
+        self.modules_mut().find(|pm| pm.module.id() == id)
+    }
+
+    /// Does what a left click on the module does: opens its popup, flips
+    /// its toggle or runs its `click_command`, then tells the module.
+    fn click_module(&mut self, id: &str) {
+        let Some(pm) = self.find_module_mut(id) else {
+            return;
+        };
+        let toggle = pm.popup.is_none() && pm.toggle_enabled;
+        if let Some(ref popup_cfg) = pm.popup {
+            let extension_id = popup_cfg.popup_type.as_deref().unwrap_or(id);
+            crate::gpui_app::popup_manager::toggle_popup(extension_id);
+        } else if let Some(ref command) = pm.click_command {
+            execute_command(command);
+        }
+        if pm.module.on_mouse_event(MouseEvent::Click) {
+            update_module(pm);
+        }
+        if toggle {
+            self.toggle_module(id);
+        }
+    }
+
+    /// Flips a toggle module. Activating a module in a `toggle_group`
+    /// deactivates the rest of the group, like radio buttons.
+    fn toggle_module(&mut self, id: &str) {
+        let Some(pm) = self.find_module_mut(id) else {
+            return;
+        };
+        if !pm.toggle_enabled {
+            return;
+        }
+        let active = !pm.toggle_active;
+        let group = pm.toggle_group.clone();
+        set_toggle(pm, active);
+        if let (true, Some(group)) = (active, group) {
+            for other in self.modules_mut() {
+                if other.toggle_active
+                    && other.module.id() != id
+                    && other.toggle_group.as_deref() == Some(group.as_str())
+                {
+                    set_toggle(other, false);
+                }
+            }
+        }
+    }
+
+    /// Returns a zone's modules.
+    fn zone_modules(&self, zone: Zone) -> &[PositionedModule] {
+        match zone {
+            Zone::LeftOuter => &self.left_outer_modules,
+            Zone::LeftInner => &self.left_inner_modules,
+            Zone::RightOuter => &self.right_outer_modules,
+            Zone::RightInner => &self.right_inner_modules,
+            Zone::LeftCenter => &self.left_center_modules,
+            Zone::RightCenter => &self.right_center_modules,
+        }
+    }
+
+    /// Whether the bar is laid out around a notch, real or fake.
+    fn notched(&self) -> bool {
+        self.screen.has_notch || self.fake_notch.is_some()
+    }
+
+    /// Width of the notch, real or fake.
+    fn notch_width(&self) -> f32 {
+        self.fake_notch.map_or(NOTCH_GAP, |notch| notch.width)
+    }
+
+    /// Returns the width reserved between the bar halves.
+    ///
+    /// With a notch this is fixed; without one it grows to fit the center
+    /// zones measured in the last prepaint.
+    fn middle_width(&self, layout: &LayoutSnapshot) -> f32 {
+        if self.notched() {
+            return self.notch_width();
+        }
+        let widths: Vec<f32> = [Zone::LeftCenter, Zone::RightCenter]
+            .into_iter()
+            .map(|zone| layout.zone_width(zone))
+            .filter(|w| *w > 0.0)
+            .collect();
+        let centers: f32 = widths.iter().sum::<f32>()
+            + self.module_spacing * widths.len().saturating_sub(1) as f32;
+        NOTCH_GAP.max(centers + self.module_spacing * 2.0)
+    }
+
+    /// Analyzes the last measured layout, logging new overlaps and updating
+    /// the set of modules hidden by the overflow strategy.
+    ///
+    /// Returns the width to reserve between the bar halves.
+    fn analyze_layout(&mut self, viewport_width: f32) -> f32 {
+        let (report, middle_width) = {
+            let Ok(mut layout) = self.layout.lock() else {
+                return self.notch_width();
+            };
+            let middle_width = self.middle_width(&layout);
+            let half_width = ((viewport_width - BAR_PADDING_X * 2.0 - middle_width) / 2.0).max(0.0);
+            layout.record_halves(half_width, half_width);
+            layout.set_screen_center(!self.notched());
+            // Nothing was measured at a new size since the last analysis
+            if self.analyzed_generation == Some(layout.generation()) {
+                return middle_width;
+            }
+            self.analyzed_generation = Some(layout.generation());
+            self.center_offsets = [
+                layout.center_offset(0, self.module_spacing),
+                layout.center_offset(1, self.module_spacing),
+            ];
+            let order: HashMap<Zone, Vec<String>> = HALVES
+                .iter()
+                .flatten()
+                .copied()
+                .map(|zone| {
+                    let ids = self
+                        .zone_modules(zone)
+                        .iter()
+                        .filter(|pm| Self::is_shown(pm))
+                        .map(|pm| pm.module.id().to_string())
+                        .collect();
+                    (zone, ids)
+                })
+                .collect();
+            let spill: HashSet<String> = self
+                .modules()
+                .filter(|pm| pm.spill)
+                .map(|pm| pm.module.id().to_string())
+                .collect();
+            (
+                layout.analyze(&order, &spill, self.overflow, self.module_spacing),
+                middle_width,
+            )
+        };
+
+        if report.overlaps != self.layout_report.overlaps && !report.overlaps.is_empty() {
+            let pairs: Vec<String> = report
+                .overlaps
+                .iter()
+                .map(|(a, b)| format!("'{}' <-> '{}'", a, b))
+                .collect();
+            log::warn!(
+                "Bar layout: modules overlap across zones: {}{}",
+                pairs.join(", "),
+                if self.overflow == OverflowStrategy::None {
+                    " (set bar.overflow = \"hide\" to resolve)"
+                } else {
+                    ""
+                }
+            );
+        }
+        if report.hidden != self.layout_report.hidden {
+            ipc::set_hidden_modules(&report.hidden);
+            if !report.hidden.is_empty() {
+                let mut hidden: Vec<&String> = report.hidden.iter().collect();
+                hidden.sort();
+                log::info!("Bar layout: hiding modules that don't fit: {:?}", hidden);
+            }
+        }
+
+        if report.spilled != self.layout_report.spilled {
+            let spilled: Vec<&String> = report.spilled.iter().flatten().collect();
+            if !spilled.is_empty() {
+                log::info!(
+                    "Bar layout: moving modules to the other half: {:?}",
+                    spilled
+                );
+            }
+        }
+
+        self.layout_report = report;
+        middle_width
+    }
+
+    /// Resolves `bar.fake_notch` for the bar's screen; None on screens with
+    /// a real notch or where the fake one is off.
+    fn configure_fake_notch(config: &Config, screen: &BarScreen) -> Option<FakeNotch> {
+        if screen.has_notch {
+            return None;
+        }
+        let (width, color) = config.bar.fake_notch.for_display(&screen.name)?;
+        let width = width
+            .pixels(screen.width, screen.width_mm, screen.builtin_notch)
+            .filter(|width| *width > 0.0 && *width < screen.width)
+            .unwrap_or_else(|| {
+                log::warn!(
+                    "Fake notch: can't work out {:?} on '{}', using {}px",
+                    width,
+                    screen.name,
+                    NOTCH_GAP
+                );
+                f64::from(NOTCH_GAP)
+            });
+        let (r, g, b, a) = parse_hex_color(color).unwrap_or((0.0, 0.0, 0.0, 1.0));
+        Some(FakeNotch {
+            width: width as f32,
+            color: Rgba {
+                r: r as f32,
+                g: g as f32,
+                b: b as f32,
+                a: a as f32,
+            },
+        })
+    }
+
+    /// Applies `bar.toast` and returns the zone toasts appear in.
+    fn configure_toast(config: &Config) -> Zone {
+        toast::set_default_timeout(Duration::from_secs_f64(config.bar.toast.timeout.max(0.1)));
+        Zone::parse(&config.bar.toast.zone).unwrap_or(Zone::RightOuter)
+    }
+
+    /// Applies `bar.progress` and returns the zone progress items appear in.
+    fn configure_progress(config: &Config) -> Zone {
+        progress::set_hide_delay(Duration::from_secs_f64(
+            config.bar.progress.hide_delay.max(0.0),
+        ));
+        Zone::parse(&config.bar.progress.zone).unwrap_or(Zone::RightOuter)
+    }
+
+    /// Applies `bar.notify` and returns the zone notify items appear in.
+    fn configure_notify(config: &Config) -> Zone {
+        notify::set_default_ttl(Duration::from_secs_f64(config.bar.notify.ttl.max(0.0)));
+        Zone::parse(&config.bar.notify.zone).unwrap_or(Zone::RightOuter)
+    }
+
+    /// Reads `bar.justify`, skipping unknown zones and values.
+    fn configure_justify(config: &Config) -> HashMap<Zone, Justify> {
+        config
+            .bar
+            .justify
+            .iter()
+            .filter_map(|(zone, justify)| Some((Zone::parse(zone)?, Justify::parse(justify)?)))
+            .collect()
+    }
+
+    /// Returns whether a zone stretches across its half to apply a justify
+    /// other than its default. Center zones always fit their content.
+    fn zone_grows(&self, zone: Zone) -> bool {
+        if matches!(zone, Zone::LeftCenter | Zone::RightCenter) {
+            return false;
+        }
+        self.justify
+            .get(&zone)
+            .is_some_and(|justify| *justify != zone.default_justify())
+    }
+
+    /// Renders a zone, with progress and notify items and the current toast
+    /// beside its modules if they're configured for this zone.
+    fn render_zone(&self, zone: Zone, cx: &Context<Self>) -> gpui::Div {
+        let modules = self.render_zone_modules(zone, cx);
+        let mut extras: Vec<gpui::AnyElement> = Vec::new();
+        if zone == self.progress_zone {
+            extras.extend(progress::render(&self.theme));
+        }
+        if zone == self.notify_zone {
+            extras.extend(notify::render(&self.theme));
+        }
+        if zone == self.toast_zone {
+            extras.extend(toast::render(&self.theme));
+        }
+        if extras.is_empty() {
+            return modules;
+        }
+        // Keep extras out of the measured container so recorded bounds stay
+        // aligned with module ids; place them on the side facing the middle
+        // of the half
+        let mut row = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap(px(self.module_spacing));
+        if self.zone_grows(zone) {
+            row = row.flex_1();
+        }
+        if matches!(zone, Zone::LeftInner | Zone::RightInner) {
+            row.children(extras).child(modules)
+        } else {
+            row.child(modules).children(extras)
+        }
+    }
+
+    /// Whether a module is on the bar rather than snoozed. The snooze
+    /// chevron is only there while something is snoozed.
+    fn is_shown(pm: &PositionedModule) -> bool {
+        let id = pm.module.id();
+        if id == snoozed::ID {
+            !snooze::snoozed().is_empty()
+        } else {
+            !snooze::is_snoozed(id)
+        }
+    }
+
+    /// Renders a zone's visible modules and records their bounds after prepaint.
+    /// Modules spilled out of a full half render in the other half's
+    /// [`SPILL_ZONES`] entry, on the side next to the notch.
+    fn render_zone_modules(&self, zone: Zone, cx: &Context<Self>) -> gpui::Div {
+        let spilled = &self.layout_report.spilled;
+        let mut visible: Vec<&PositionedModule> = self
+            .zone_modules(zone)
+            .iter()
+            .filter(|pm| !self.layout_report.hidden.contains(pm.module.id()))
+            .filter(|pm| !spilled.iter().flatten().any(|id| id == pm.module.id()))
+            .filter(|pm| Self::is_shown(pm))
+            .collect();
+        if let Some(half) = SPILL_ZONES
+            .iter()
+            .position(|spill_zone| *spill_zone == zone)
+        {
+            let incoming: Vec<&PositionedModule> = spilled[half]
+                .iter()
+                .filter_map(|id| self.modules().find(|pm| pm.module.id() == id))
+                .collect();
+            visible = if half == 0 {
+                visible.into_iter().chain(incoming).collect()
+            } else {
+                incoming.into_iter().chain(visible).collect()
+            };
+        }
+        let ids: Vec<String> = visible
+            .iter()
+            .map(|pm| pm.module.id().to_string())
+            .collect();
+        let elements: Vec<gpui::AnyElement> = visible
+            .iter()
+            .map(|pm| pm.with_margins(self.render_module(pm, cx)))
+            .collect();
+
+        let mut container = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap(px(self.module_spacing));
+        if self.zone_grows(zone) {
+            container = match self.justify[&zone] {
+                Justify::Start => container.flex_1().justify_start(),
+                Justify::End => container.flex_1().justify_end(),
+                Justify::SpaceBetween => container.flex_1().justify_between(),
+            };
+        }
+
+        let layout = Arc::clone(&self.layout);
+        container
+            .children(elements)
+            .on_children_prepainted(move |bounds, _window, _cx| {
+                if let Ok(mut layout) = layout.lock() {
+                    layout.record_zone(zone, &ids, &bounds);
+                }
+            })
+    }
+
+    /// Renders one half of the bar: leading | spacer | trailing, with the
+    /// half's center zone overlaid at its resolved offset on notched displays.
+    /// A zone with a non-default justify takes the spacer's place.
+    fn render_half(&self, half: usize, cx: &Context<Self>) -> gpui::Div {
+        let [leading, center, trailing] = HALVES[half];
+        let mut container = div()
+            .relative()
+            .flex()
+            .flex_row()
+            .items_center()
+            .flex_1()
+            .child(self.render_zone(leading, cx));
+        if !self.zone_grows(leading) && !self.zone_grows(trailing) {
+            container = container.child(div().flex_grow());
+        }
+        container = container.child(self.render_zone(trailing, cx));
+
+        let shows_center = !self.zone_modules(center).is_empty()
+            || self.toast_zone == center
+            || self.progress_zone == center
+            || self.notify_zone == center;
+        if self.notched() && shows_center {
+            container = container.child(
+                div()
+                    .absolute()
+                    .top_0()
+                    .bottom_0()
+                    .left(px(self.center_offsets[half]))
+                    .flex()
+                    .items_center()
+                    .child(self.render_zone(center, cx)),
+            );
+        }
+        container
+    }
+
+    /// Renders the notch exclusion area, wired to the notch panel if enabled.
+    /// Clicking always toggles the panel; with the hover trigger, entering
+    /// the notch also expands it. A fake notch is painted in its color.
+    fn render_notch_zone(&self, width: f32) -> gpui::AnyElement {
+        let mut zone = div().id("notch-zone").w(px(width)).h_full();
+        if let Some(fake) = self.fake_notch {
+            zone = zone
+                .bg(fake.color)
+                .rounded_bl(px(FAKE_NOTCH_RADIUS))
+                .rounded_br(px(FAKE_NOTCH_RADIUS));
+        }
+        let Some(trigger) = self.notch_trigger else {
+            return zone.into_any_element();
+        };
+        zone = zone
+            .cursor_pointer()
+            .on_mouse_down(MouseButton::Left, |_event, _window, cx| {
+                notch::toggle();
+                crate::gpui_app::refresh_popup_windows(cx);
+            });
+        if trigger == NotchTrigger::Hover {
+            zone = zone.on_hover(|hovered, _window, cx| {
+                if *hovered {
+                    notch::expand();
+                    crate::gpui_app::refresh_popup_windows(cx);
+                }
+            });
+        }
+        zone.into_any_element()
     }
 
     /// Renders a single module with its styling.
-    fn render_module(&self, pm: &PositionedModule) -> gpui::Stateful<gpui::Div> {
+    fn render_module(
+        &self,
+        pm: &PositionedModule,
+        cx: &Context<Self>,
+    ) -> gpui::Stateful<gpui::Div> {
         // Get the module's rendered element
         let module_element = pm.module.render(&self.theme);
 
@@ -437,13 +1201,27 @@ impl BarView {
             .flex()
             .items_center();
 
-        // Apply custom text color if configured
-        if let Some(color) = pm.text_color {
+        // Apply custom text color if configured; matching color rules win
+        // over the static colors, the active ones over both
+        let value = pm.module.value();
+        let text_color = pm
+            .style
+            .active_text_color
+            .filter(|_| pm.toggle_active)
+            .or_else(|| pm.style.rule_color(ColorTarget::Text, value))
+            .or(pm.text_color);
+        if let Some(color) = text_color {
             wrapper = wrapper.text_color(color);
         }
 
-        // Apply background if configured
-        if let Some(bg) = pm.style.background {
+        // Apply background if configured (the active one while toggled on)
+        let background = pm
+            .style
+            .active_background
+            .filter(|_| pm.toggle_active)
+            .or_else(|| pm.style.rule_color(ColorTarget::Background, value))
+            .or(pm.style.background);
+        if let Some(bg) = background {
             wrapper = wrapper.bg(bg);
 
             // Apply corner radius
@@ -458,52 +1236,224 @@ impl BarView {
         }
 
         // Apply border if configured
-        if let Some(border) = pm.style.border_color {
-            if pm.style.border_width > 0.0 {
+        let border = pm
+            .style
+            .active_border_color
+            .filter(|_| pm.toggle_active)
+            .or(pm.style.border_color);
+        if let Some(border) = border {
+            if pm.style.border_width > 0.0 || pm.toggle_active {
                 wrapper = wrapper.border_color(border).border_1();
             }
         }
 
+        // Outline modules that collide with another zone
+        if self.debug_layout
+            && self
+                .layout_report
+                .overlapping_ids()
+                .contains(pm.module.id())
+        {
+            wrapper = wrapper
+                .border_1()
+                .border_color(self.theme.with_alpha(self.theme.destructive, 0.8));
+        }
+
         // Show pointer cursor for clickable modules (no hover effect due to window level)
-        let is_clickable = pm.click_command.is_some() || pm.popup.is_some();
+        let is_clickable = pm.click_command.is_some()
+            || pm.popup.is_some()
+            || pm.toggle_enabled
+            || pm.middle_click_command.is_some()
+            || pm.double_click_command.is_some();
         if is_clickable {
             wrapper = wrapper.cursor_pointer();
         }
 
+        // With a double-click command, the second press of a double click
+        // runs only that instead of repeating the click action.
+        let has_double_click = pm.double_click_command.is_some();
+
         // Add click handler for popup or command
         if let Some(ref popup_cfg) = pm.popup {
             let popup_type = popup_cfg.popup_type.clone();
+            let module_id = pm.module.id().to_string();
+            let layout = Arc::clone(&self.layout);
             wrapper = wrapper.on_mouse_down(MouseButton::Left, move |event, window, _cx| {
+                if has_double_click && event.click_count > 1 {
+                    return;
+                }
                 // Use extension-based popup toggle
                 let extension_id = popup_type.as_deref().unwrap_or("demo");
+                // A popup that hovering opened stays open
+                if hover_intent::claim(extension_id) {
+                    return;
+                }
                 log::info!("Module clicked, toggling extension popup: {}", extension_id);
-                let bounds = window.bounds();
-                let click_x: f64 = (bounds.origin.x + event.position.x).into();
-                let click_y: f64 = (bounds.origin.y + event.position.y).into();
-                crate::gpui_app::popup_manager::record_popup_anchor(click_x, click_y);
+                // Anchor to the module, or to the click if it wasn't measured
+                let click_x: f64 = (window.bounds().origin.x + event.position.x).into();
+                let (left, right) =
+                    module_screen_span(&layout, &module_id, window).unwrap_or((click_x, click_x));
+                crate::gpui_app::popup_manager::record_popup_anchor(left, right);
                 crate::gpui_app::popup_manager::record_popup_click(extension_id);
                 crate::gpui_app::popup_manager::toggle_popup(extension_id);
                 crate::gpui_app::refresh_popup_windows(_cx);
             });
+        } else if pm.toggle_enabled {
+            let module_id = pm.module.id().to_string();
+            let command = pm.click_command.clone();
+            wrapper = wrapper.on_mouse_down(MouseButton::Left, move |event, _window, _cx| {
+                if has_double_click && event.click_count > 1 {
+                    return;
+                }
+                ipc::trigger_module(&module_id, "toggle");
+                if let Some(ref command) = command {
+                    execute_command(command);
+                }
+            });
         } else if let Some(ref cmd) = pm.click_command {
             let command = cmd.clone();
-            wrapper = wrapper.on_mouse_down(MouseButton::Left, move |_event, _window, _cx| {
+            wrapper = wrapper.on_mouse_down(MouseButton::Left, move |event, _window, _cx| {
+                if has_double_click && event.click_count > 1 {
+                    return;
+                }
                 execute_command(&command);
             });
         }
 
-        // Add right-click handler if configured
+        // Add double-click handler if configured
+        if let Some(ref cmd) = pm.double_click_command {
+            let command = cmd.clone();
+            wrapper = wrapper.on_mouse_down(MouseButton::Left, move |event, _window, _cx| {
+                if event.click_count == 2 {
+                    execute_command(&command);
+                }
+            });
+        }
+
+        // Add right-click handler: the configured command or the context menu
         if let Some(ref cmd) = pm.right_click_command {
             let command = cmd.clone();
             wrapper = wrapper.on_mouse_down(MouseButton::Right, move |_event, _window, _cx| {
                 execute_command(&command);
             });
+        } else if pm.context_menu {
+            let module_id = pm.module.id().to_string();
+            wrapper = wrapper.on_mouse_down(
+                MouseButton::Right,
+                cx.listener(move |this, event: &gpui::MouseDownEvent, window, cx| {
+                    let text = this
+                        .find_module_mut(&module_id)
+                        .and_then(|pm| pm.module.copy_text());
+                    // The menu opens at the click, like a native context menu
+                    let click_x: f64 = (window.bounds().origin.x + event.position.x).into();
+                    crate::gpui_app::popup_manager::record_popup_anchor(click_x, click_x);
+                    context_menu::toggle(&module_id, text);
+                    crate::gpui_app::refresh_popup_windows(cx);
+                }),
+            );
         }
 
+        // Add middle-click handler if configured
+        if let Some(ref cmd) = pm.middle_click_command {
+            let command = cmd.clone();
+            wrapper = wrapper.on_mouse_down(MouseButton::Middle, move |_event, _window, _cx| {
+                execute_command(&command);
+            });
+        }
+
+        // Pass every click on to the module's on_mouse_event hook
+        for button in [MouseButton::Left, MouseButton::Right, MouseButton::Middle] {
+            let module_id = pm.module.id().to_string();
+            wrapper = wrapper.on_mouse_down(
+                button,
+                cx.listener(move |this, event: &gpui::MouseDownEvent, _window, cx| {
+                    let mouse_event = match event.button {
+                        MouseButton::Right => MouseEvent::RightClick,
+                        MouseButton::Middle => MouseEvent::MiddleClick,
+                        _ if event.click_count == 2 => MouseEvent::DoubleClick,
+                        _ => MouseEvent::Click,
+                    };
+                    tooltip::dismiss();
+                    if let Some(pm) = this.find_module_mut(&module_id) {
+                        if pm.module.on_mouse_event(mouse_event) {
+                            cx.notify();
+                        }
+                    }
+                }),
+            );
+        }
+
+        // Schedule the module's tooltip while the pointer rests on it, lay
+        // its popup out ahead of the click, and with `popup_on_hover` open it
+        // once the pointer stays
+        let module_id = pm.module.id().to_string();
+        let popup_id = pm
+            .popup
+            .as_ref()
+            .map(|popup| popup.popup_type.clone().unwrap_or_else(|| "demo".into()));
+        let hover_delay = pm.popup.as_ref().and_then(|popup| popup.hover_delay);
+        wrapper = wrapper.on_hover(cx.listener(move |this, hovered: &bool, window, cx| {
+            if *hovered {
+                let text = this
+                    .find_module_mut(&module_id)
+                    .and_then(|pm| pm.module.tooltip());
+                tooltip::enter(&module_id, text);
+                let prepared = popup_id
+                    .as_deref()
+                    .is_some_and(crate::gpui_app::popup_manager::prepare_popup);
+                if prepared {
+                    crate::gpui_app::refresh_popup_windows(cx);
+                }
+                if let (Some(popup_id), Some(delay)) = (&popup_id, hover_delay) {
+                    if let Some((left, right)) =
+                        module_screen_span(&this.layout, &module_id, window)
+                    {
+                        hover_intent::enter(popup_id, left, right, delay);
+                    }
+                }
+            } else {
+                tooltip::leave(&module_id);
+                if let Some(ref popup_id) = popup_id {
+                    hover_intent::leave(popup_id);
+                }
+            }
+        }));
+
+        // Route scrolls over the module to its on_scroll hook
+        let module_id = pm.module.id().to_string();
+        wrapper = wrapper.on_scroll_wheel(cx.listener(
+            move |this, event: &gpui::ScrollWheelEvent, _window, cx| {
+                let (delta_x, delta_y) = match event.delta {
+                    gpui::ScrollDelta::Pixels(delta) => (f32::from(delta.x), f32::from(delta.y)),
+                    gpui::ScrollDelta::Lines(delta) => (delta.x * 16.0, delta.y * 16.0),
+                };
+                if let Some(pm) = this.find_module_mut(&module_id) {
+                    if pm.module.on_scroll(delta_x, delta_y) {
+                        cx.notify();
+                    }
+                }
+            },
+        ));
+
         wrapper.child(module_element)
     }
 }
 
+/// Screen x edges of a module as measured in the last prepaint.
+fn module_screen_span(
+    layout: &Mutex<LayoutSnapshot>,
+    module_id: &str,
+    window: &Window,
+) -> Option<(f64, f64)> {
+    let layout = layout.lock().ok()?;
+    let span = layout.span(module_id)?;
+    let origin: f64 = window.bounds().origin.x.into();
+    Some((
+        origin + f64::from(span.left),
+        origin + f64::from(span.right),
+    ))
+}
+
 /// Execute a shell command in the background.
 fn execute_command(command: &str) {
     let cmd = command.to_string();
@@ -525,7 +1475,7 @@ fn execute_command(command: &str) {
 }
 
 impl Render for BarView {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         // Start the background refresh task on first render
         // This uses GPUI's async executor to periodically check camera state
         self.start_refresh_task(cx);
@@ -546,18 +1496,46 @@ impl Render for BarView {
         // Drain IPC commands (set, trigger) before updating modules
         self.drain_ipc_commands();
 
-        // Update modules periodically (rate-limited to every 500ms).
-        // Skip updates while a popup is visible to keep the UI responsive.
-        let needs_immediate = BAR_UPDATE_REQUESTED.swap(false, Ordering::Relaxed);
-        if needs_immediate
-            || (self.last_update.elapsed() > self.update_interval
-                && !crate::gpui_app::popup_manager::is_popup_visible())
-        {
-            if self.update_modules() {
-                cx.notify(); // Trigger re-render if any module changed
-            }
-            self.last_update = Instant::now();
+        // Hide the notch panel once its collapse animation finished
+        notch::apply_collapse_request();
+
+        // Open popups the pointer rested on, close the ones it left
+        hover_intent::apply_requests();
+
+        // Give pinned popups that were pushed aside a window of their own
+        crate::gpui_app::open_detached_popups(cx, &self.theme);
+        if !screenshot::active() {
+            ui_state::set_detached(&crate::gpui_app::popup_manager::detached_ids());
+        }
+
+        // Update every module after a wake-up, otherwise the ones whose
+        // timer is due. Timed updates wait while a popup is visible to keep
+        // the UI responsive, and while the screen is locked or the user is
+        // idle to save power; everything updates once they're back.
+        let paused = self.updates_paused();
+        let resumed = self.paused && !paused;
+        self.paused = paused;
+        let wake = WAKE_GENERATION.load(Ordering::Relaxed);
+        let woken = wake != self.seen_wake || resumed;
+        self.seen_wake = wake;
+        let popup_visible = crate::gpui_app::popup_manager::is_popup_visible();
+        let power_saving = power_saving::current();
+        if (woken || !(popup_visible || paused)) && self.update_modules(woken, power_saving) {
+            cx.notify(); // Trigger re-render if any module changed
+        }
+        let mut deadline = self.next_update_due();
+        if popup_visible || paused {
+            let delay = if paused {
+                PAUSE_CHECK_INTERVAL
+            } else {
+                POPUP_UPDATE_DELAY
+            };
+            let retry = Instant::now() + delay;
+            deadline = deadline.map(|deadline| deadline.max(retry));
         }
+        // Bring snoozed modules back on time
+        deadline = deadline.into_iter().chain(snooze::next_expiry()).min();
+        set_deadline(self.id, deadline);
 
         // Determine background color (red tint when camera is active, if enabled)
         let camera_active = camera::is_camera_active();
@@ -573,92 +1551,61 @@ impl Render for BarView {
         };
         self.last_camera_active = camera_active;
 
-        // Build all 4 module zones
-        let left_outer_elements: Vec<gpui::Stateful<gpui::Div>> = self
-            .left_outer_modules
-            .iter()
-            .map(|pm| self.render_module(pm))
-            .collect();
-
-        let left_inner_elements: Vec<gpui::Stateful<gpui::Div>> = self
-            .left_inner_modules
-            .iter()
-            .map(|pm| self.render_module(pm))
-            .collect();
+        // Resolve zone collisions using bounds measured in the previous frame
+        let viewport_width = f32::from(window.viewport_size().width);
+        let middle_width = self.analyze_layout(viewport_width);
 
-        let right_outer_elements: Vec<gpui::Stateful<gpui::Div>> = self
-            .right_outer_modules
-            .iter()
-            .map(|pm| self.render_module(pm))
-            .collect();
+        // Middle: notch exclusion area, or the screen-centered zones when there's no notch
+        let middle = if self.notched() {
+            self.render_notch_zone(middle_width)
+        } else {
+            div()
+                .w(px(middle_width))
+                .flex()
+                .flex_row()
+                .items_center()
+                .justify_center()
+                .gap(px(self.module_spacing))
+                .child(self.render_zone(Zone::LeftCenter, cx))
+                .child(self.render_zone(Zone::RightCenter, cx))
+                .into_any_element()
+        };
 
-        let right_inner_elements: Vec<gpui::Stateful<gpui::Div>> = self
-            .right_inner_modules
-            .iter()
-            .map(|pm| self.render_module(pm))
-            .collect();
+        // Keep drawing frames while a module animates (e.g. marquee text),
+        // unless animations are paused to save power
+        let pause_animations = power_saving.is_some_and(|policy| policy.pause_animations);
+        if self
+            .modules()
+            .any(|pm| pm.module.is_animating() && !(pause_animations && pm.power_saving))
+        {
+            window.request_animation_frame();
+        }
 
-        // Full-width bar layout: left_outer | left_inner | spacer | right_outer | right_inner
-        div()
-            .id("bar-root")
-            .flex()
+        // Full-width bar layout: left_outer | left_inner | middle | right_outer | right_inner
+        let mut root = div().id("bar-root");
+        if self.privacy_border {
+            if let Some(color) = privacy::border_color(&self.theme) {
+                root = root.border_b(px(2.0)).border_color(color);
+            }
+        }
+        if self.corner_radius > 0.0 {
+            // The window is transparent, so the corners outside the radius
+            // show the desktop like the display's own rounded corners
+            root = root
+                .rounded_tl(px(self.corner_radius))
+                .rounded_tr(px(self.corner_radius));
+        }
+        root.flex()
             .flex_row()
             .items_center()
             .w_full()
             .h_full()
             .bg(bg_color)
-            .px(px(8.0))
+            .px(px(BAR_PADDING_X))
             // Left section: outer | spacer | inner (toward notch)
-            .child(
-                div()
-                    .flex()
-                    .flex_row()
-                    .items_center()
-                    .flex_1()
-                    .child(
-                        div()
-                            .flex()
-                            .flex_row()
-                            .items_center()
-                            .gap(px(4.0))
-                            .children(left_outer_elements),
-                    )
-                    .child(div().flex_grow())
-                    .child(
-                        div()
-                            .flex()
-                            .flex_row()
-                            .items_center()
-                            .gap(px(4.0))
-                            .children(left_inner_elements),
-                    ),
-            )
-            // Notch gap
-            .child(div().w(px(200.0)))
+            .child(self.render_half(0, cx))
+            .child(middle)
             // Right section: outer (toward notch) | spacer | inner
-            .child(
-                div()
-                    .flex()
-                    .flex_row()
-                    .items_center()
-                    .flex_1()
-                    .child(
-                        div()
-                            .flex()
-                            .flex_row()
-                            .items_center()
-                            .gap(px(4.0))
-                            .children(right_outer_elements),
-                    )
-                    .child(div().flex_grow())
-                    .child(
-                        div()
-                            .flex()
-                            .flex_row()
-                            .items_center()
-                            .gap(px(4.0))
-                            .children(right_inner_elements),
-                    ),
-            )
+            .child(self.render_half(1, cx))
     }
 }
@@ -0,0 +1,317 @@
+//! `sinew --profile-modules`: measures what each configured module costs.
+//!
+//! Every module runs headless in its own child process for the given time,
+//! so CPU time, allocations and subprocesses can be pinned on it. The child
+//! calls `update()` at the bar's pace and times each call; the parent counts
+//! the subprocesses each child starts (scripts, curl, osascript…) by polling
+//! its process tree, then prints one line per module, busiest first.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashSet;
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::modules::{configured_modules, create_module, module_id};
+use crate::config::load_config;
+use crate::system::processes;
+
+/// Index of the module a child process runs, set by the parent.
+const MODULE_ENV: &str = "SINEW_PROFILE_MODULE";
+/// How often modules are updated, matching the bar's update rate limit.
+const UPDATE_INTERVAL: Duration = Duration::from_millis(500);
+/// How often the parent looks for new subprocesses.
+const SPAWN_POLL: Duration = Duration::from_millis(2);
+/// Extra time a child gets to start up and report before it's killed.
+const GRACE: Duration = Duration::from_secs(15);
+
+static COUNTING: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// System allocator that counts allocations while profiling.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation();
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn count_allocation() {
+    if COUNTING.load(Ordering::Relaxed) {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// What a child process measured for its module.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ModuleReport {
+    updates: u64,
+    /// Update durations, in microseconds
+    avg_us: u64,
+    p95_us: u64,
+    max_us: u64,
+    /// Allocations made inside `update()`
+    update_allocations: u64,
+    /// Allocations on all of the module's threads
+    allocations: u64,
+    /// CPU time of the module's process, excluding subprocesses
+    cpu_ns: u64,
+    /// Updates that panicked
+    panics: u64,
+}
+
+/// The value at percentile `p` (0–100) of sorted samples.
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (p / 100.0 * (sorted.len() - 1) as f64).round() as usize;
+    sorted[rank.min(sorted.len() - 1)]
+}
+
+/// Formats microseconds with a unit that keeps the number short.
+fn format_us(us: u64) -> String {
+    match us {
+        0..1_000 => format!("{}µs", us),
+        1_000..1_000_000 => format!("{:.1}ms", us as f64 / 1_000.0),
+        _ => format!("{:.2}s", us as f64 / 1_000_000.0),
+    }
+}
+
+/// Formats a count as 950, 12.3k or 4.5M.
+fn format_count(count: u64) -> String {
+    match count {
+        0..1_000 => count.to_string(),
+        1_000..1_000_000 => format!("{:.1}k", count as f64 / 1_000.0),
+        _ => format!("{:.1}M", count as f64 / 1_000_000.0),
+    }
+}
+
+/// Runs the module a child process was started for and prints its report
+/// as JSON.
+fn run_child(index: usize, duration: Duration) -> i32 {
+    COUNTING.store(true, Ordering::Relaxed);
+    let config = load_config();
+    let Some(&(_, creation_index, module_config)) = configured_modules(&config).get(index) else {
+        return 1;
+    };
+    let Some(mut module) = create_module(module_config, creation_index) else {
+        return 1;
+    };
+
+    let pid = std::process::id();
+    let cpu_start = processes::cpu_time(pid).unwrap_or(0);
+    let allocations_start = ALLOCATIONS.load(Ordering::Relaxed);
+    let mut report = ModuleReport::default();
+    let mut durations = Vec::new();
+    let deadline = Instant::now() + duration;
+    while Instant::now() < deadline {
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        let start = Instant::now();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            module.module.update();
+        }));
+        let elapsed = start.elapsed();
+        report.update_allocations += ALLOCATIONS.load(Ordering::Relaxed) - before;
+        report.updates += 1;
+        report.panics += u64::from(result.is_err());
+        durations.push(elapsed.as_micros() as u64);
+        std::thread::sleep(UPDATE_INTERVAL.saturating_sub(elapsed));
+    }
+
+    durations.sort_unstable();
+    report.avg_us = durations.iter().sum::<u64>() / durations.len().max(1) as u64;
+    report.p95_us = percentile(&durations, 95.0);
+    report.max_us = durations.last().copied().unwrap_or(0);
+    report.allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations_start;
+    report.cpu_ns = processes::cpu_time(pid)
+        .unwrap_or(0)
+        .saturating_sub(cpu_start);
+    match serde_json::to_string(&report) {
+        Ok(json) => {
+            println!("{}", json);
+            0
+        }
+        Err(_) => 1,
+    }
+}
+
+/// A child process profiling one module.
+struct Run {
+    id: String,
+    module_type: String,
+    child: Child,
+    /// Subprocesses seen so far
+    spawned: HashSet<u32>,
+    report: Option<ModuleReport>,
+}
+
+/// Profiles every configured module for `seconds` and prints a report.
+/// Returns the process exit code.
+pub fn run(seconds: u64) -> i32 {
+    let duration = Duration::from_secs(seconds.max(1));
+    if let Some(index) = std::env::var(MODULE_ENV).ok().and_then(|i| i.parse().ok()) {
+        return run_child(index, duration);
+    }
+
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            eprintln!("Can't find the sinew binary: {}", e);
+            return 1;
+        }
+    };
+    let config = load_config();
+    let modules = configured_modules(&config);
+    if modules.is_empty() {
+        eprintln!("No modules configured");
+        return 1;
+    }
+
+    println!(
+        "Profiling {} modules for {}s, updating every {}ms…",
+        modules.len(),
+        duration.as_secs(),
+        UPDATE_INTERVAL.as_millis()
+    );
+    let mut runs = Vec::new();
+    for (index, (_, creation_index, module)) in modules.iter().enumerate() {
+        let child = Command::new(&exe)
+            .args(["--profile-modules", &duration.as_secs().to_string()])
+            .env(MODULE_ENV, index.to_string())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+        match child {
+            Ok(child) => runs.push(Run {
+                id: module_id(module, *creation_index),
+                module_type: module.module_type.clone(),
+                child,
+                spawned: HashSet::new(),
+                report: None,
+            }),
+            Err(e) => {
+                eprintln!("Failed to start a profiling process: {}", e);
+                return 1;
+            }
+        }
+    }
+
+    // Watch for subprocesses until every child has reported
+    let deadline = Instant::now() + duration + GRACE;
+    let mut running = runs.len();
+    while running > 0 && Instant::now() < deadline {
+        running = 0;
+        for run in &mut runs {
+            if matches!(run.child.try_wait(), Ok(None)) {
+                running += 1;
+                run.spawned.extend(processes::child_pids(run.child.id()));
+            }
+        }
+        std::thread::sleep(SPAWN_POLL);
+    }
+    for run in &mut runs {
+        if matches!(run.child.try_wait(), Ok(None)) {
+            let _ = run.child.kill();
+            let _ = run.child.wait();
+            continue;
+        }
+        let mut output = String::new();
+        if let Some(mut stdout) = run.child.stdout.take() {
+            let _ = stdout.read_to_string(&mut output);
+        }
+        run.report = serde_json::from_str(output.trim()).ok();
+    }
+
+    print_report(&mut runs, duration);
+    0
+}
+
+fn print_report(runs: &mut [Run], duration: Duration) {
+    runs.sort_by_key(|run| {
+        std::cmp::Reverse(run.report.as_ref().map_or(0, |report| report.cpu_ns))
+    });
+    let minutes = duration.as_secs_f64() / 60.0;
+    println!();
+    println!(
+        "{:<22} {:<13} {:>6} {:>8} {:>8} {:>8} {:>10} {:>8} {:>7} {:>8}",
+        "MODULE", "TYPE", "CPU", "AVG", "P95", "MAX", "ALLOC/UPD", "ALLOCS", "SPAWNS", "SPAWN/MIN"
+    );
+    for run in runs.iter() {
+        let Some(report) = &run.report else {
+            println!(
+                "{:<22} {:<13} (failed to start or report)",
+                run.id, run.module_type
+            );
+            continue;
+        };
+        let cpu = report.cpu_ns as f64 / duration.as_nanos() as f64 * 100.0;
+        let mut line = format!(
+            "{:<22} {:<13} {:>5.1}% {:>8} {:>8} {:>8} {:>10} {:>8} {:>7} {:>8.1}",
+            run.id,
+            run.module_type,
+            cpu,
+            format_us(report.avg_us),
+            format_us(report.p95_us),
+            format_us(report.max_us),
+            format_count(report.update_allocations / report.updates.max(1)),
+            format_count(report.allocations),
+            run.spawned.len(),
+            run.spawned.len() as f64 / minutes
+        );
+        if report.panics > 0 {
+            line.push_str(&format!("  {} panicked", report.panics));
+        }
+        println!("{}", line);
+    }
+    println!();
+    println!("CPU: share of one core used by the module's own threads, not its subprocesses");
+    println!("AVG/P95/MAX: time per update() call; ALLOC/UPD: allocations per update() call");
+    println!("ALLOCS: allocations on all of the module's threads");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_percentiles() {
+        let samples: Vec<u64> = (1..=100).collect();
+        assert_eq!(percentile(&samples, 95.0), 95);
+        assert_eq!(percentile(&samples, 100.0), 100);
+        assert_eq!(percentile(&[7], 95.0), 7);
+        assert_eq!(percentile(&[], 95.0), 0);
+    }
+
+    #[test]
+    fn formats_units() {
+        assert_eq!(format_us(850), "850µs");
+        assert_eq!(format_us(12_300), "12.3ms");
+        assert_eq!(format_us(2_500_000), "2.50s");
+        assert_eq!(format_count(950), "950");
+        assert_eq!(format_count(12_340), "12.3k");
+    }
+}
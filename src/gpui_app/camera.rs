@@ -146,8 +146,11 @@ extern "C" fn camera_property_changed(
     ffi::K_CMIO_HARDWARE_NO_ERROR
 }
 
-/// Trigger a UI refresh by posting a synthetic event to wake up the run loop
+/// Wakes the bar so it picks up the new state, and posts a synthetic event
+/// to wake up the run loop
 fn trigger_ui_refresh() {
+    crate::gpui_app::request_immediate_refresh();
+
     #[link(name = "System", kind = "dylib")]
     extern "C" {
         fn dispatch_async_f(
@@ -0,0 +1,236 @@
+//! Module value history, kept across restarts.
+//!
+//! Modules with `history = true` have their value recorded at most once a
+//! minute. Each module's samples are appended to
+//! `~/.local/state/sinew/history/<id>.tsv` as `timestamp<TAB>value` lines
+//! and the last 24 hours are kept, which is what the history popup graphs.
+//! The file is rewritten now and then to drop expired lines.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use chrono::Utc;
+
+/// How long samples are kept, in seconds.
+pub const RETENTION_SECS: i64 = 24 * 60 * 60;
+/// Minimum time between two samples, in seconds.
+const RESOLUTION_SECS: i64 = 60;
+/// Expired lines tolerated in a file before it's rewritten.
+const COMPACT_AFTER: usize = 60;
+
+/// Samples of one module, oldest first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Series {
+    points: Vec<(i64, u8)>,
+    /// Lines in the file that have expired since it was last written
+    stale: usize,
+}
+
+impl Series {
+    /// Parses a history file, skipping malformed lines.
+    pub fn parse(content: &str) -> Self {
+        let mut points: Vec<(i64, u8)> = content
+            .lines()
+            .filter_map(|line| {
+                let (at, value) = line.split_once('\t')?;
+                Some((at.trim().parse().ok()?, value.trim().parse().ok()?))
+            })
+            .collect();
+        points.sort_by_key(|(at, _)| *at);
+        Self { points, stale: 0 }
+    }
+
+    /// Serializes the samples in the file format.
+    pub fn to_file_string(&self) -> String {
+        self.points
+            .iter()
+            .map(|(at, value)| format!("{}\t{}\n", at, value))
+            .collect()
+    }
+
+    /// Adds a sample unless the last one is more recent than the
+    /// resolution. Returns true if it was added.
+    pub fn push(&mut self, at: i64, value: u8) -> bool {
+        if self
+            .points
+            .last()
+            .is_some_and(|(last, _)| at - last < RESOLUTION_SECS)
+        {
+            return false;
+        }
+        self.points.push((at, value));
+        true
+    }
+
+    /// Drops samples older than the retention window.
+    pub fn prune(&mut self, now: i64) {
+        let cutoff = now - RETENTION_SECS;
+        let expired = self.points.partition_point(|(at, _)| *at < cutoff);
+        self.points.drain(..expired);
+        self.stale += expired;
+    }
+
+    /// Samples taken at or after `from`.
+    pub fn since(&self, from: i64) -> &[(i64, u8)] {
+        let start = self.points.partition_point(|(at, _)| *at < from);
+        &self.points[start..]
+    }
+}
+
+/// Averages `points` into `count` equal buckets spanning `window` seconds
+/// up to `now`. Empty buckets repeat the previous value, and buckets before
+/// the first sample are left out. Returns the values and how many seconds
+/// before `now` the first kept bucket starts.
+pub fn buckets(points: &[(i64, u8)], now: i64, window: i64, count: usize) -> (Vec<f32>, i64) {
+    if count == 0 || window <= 0 {
+        return (Vec::new(), 0);
+    }
+    let start = now - window;
+    let width = window as f64 / count as f64;
+    let mut sums = vec![(0.0f32, 0u32); count];
+    for (at, value) in points {
+        if *at < start || *at > now {
+            continue;
+        }
+        let index = (((at - start) as f64 / width) as usize).min(count - 1);
+        sums[index].0 += f32::from(*value);
+        sums[index].1 += 1;
+    }
+    let Some(first) = sums.iter().position(|(_, n)| *n > 0) else {
+        return (Vec::new(), 0);
+    };
+    let mut values = Vec::with_capacity(count - first);
+    let mut last = 0.0;
+    for (sum, n) in &sums[first..] {
+        if *n > 0 {
+            last = sum / *n as f32;
+        }
+        values.push(last);
+    }
+    let offset = window - (first as f64 * width) as i64;
+    (values, offset)
+}
+
+fn series_map() -> &'static Mutex<HashMap<String, Series>> {
+    static SERIES: OnceLock<Mutex<HashMap<String, Series>>> = OnceLock::new();
+    SERIES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// History file of module `id`.
+fn path(id: &str) -> PathBuf {
+    let name: String = id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    crate::config::get_state_dir()
+        .join("history")
+        .join(format!("{}.tsv", name))
+}
+
+fn load(id: &str) -> Series {
+    std::fs::read_to_string(path(id))
+        .map(|content| Series::parse(&content))
+        .unwrap_or_default()
+}
+
+/// Appends one sample to the file, or rewrites it when enough lines expired.
+fn save(id: &str, series: &mut Series, sample: (i64, u8)) {
+    let path = path(id);
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let result = if series.stale >= COMPACT_AFTER {
+        series.stale = 0;
+        std::fs::write(&path, series.to_file_string())
+    } else {
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| writeln!(file, "{}\t{}", sample.0, sample.1))
+    };
+    if let Err(err) = result {
+        log::warn!("history: failed to save '{}': {}", id, err);
+    }
+}
+
+/// Records the value module `id` reported.
+pub fn record(id: &str, value: Option<u8>) {
+    let Some(value) = value else {
+        return;
+    };
+    let now = Utc::now().timestamp();
+    let Ok(mut map) = series_map().lock() else {
+        return;
+    };
+    let series = map.entry(id.to_string()).or_insert_with(|| load(id));
+    if !series.push(now, value) {
+        return;
+    }
+    series.prune(now);
+    save(id, series, (now, value));
+}
+
+/// Samples of module `id` from the last 24 hours, oldest first.
+pub fn last_day(id: &str) -> Vec<(i64, u8)> {
+    let now = Utc::now().timestamp();
+    let Ok(mut map) = series_map().lock() else {
+        return Vec::new();
+    };
+    map.entry(id.to_string())
+        .or_insert_with(|| load(id))
+        .since(now - RETENTION_SECS)
+        .to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_serializes() {
+        let series = Series::parse("200\t42\nbad line\n100\t7\n300\tx\n");
+        assert_eq!(series.points, vec![(100, 7), (200, 42)]);
+        assert_eq!(series.to_file_string(), "100\t7\n200\t42\n");
+    }
+
+    #[test]
+    fn pushes_at_most_once_per_minute() {
+        let mut series = Series::default();
+        assert!(series.push(1000, 10));
+        assert!(!series.push(1030, 20));
+        assert!(series.push(1060, 30));
+        assert_eq!(series.points, vec![(1000, 10), (1060, 30)]);
+    }
+
+    #[test]
+    fn prunes_expired_samples() {
+        let mut series = Series::default();
+        series.push(0, 1);
+        series.push(100, 2);
+        series.push(RETENTION_SECS + 50, 3);
+        series.prune(RETENTION_SECS + 50);
+        assert_eq!(series.points, vec![(100, 2), (RETENTION_SECS + 50, 3)]);
+        assert_eq!(series.stale, 1);
+        assert_eq!(series.since(RETENTION_SECS), &[(RETENTION_SECS + 50, 3)]);
+    }
+
+    #[test]
+    fn buckets_average_and_fill_gaps() {
+        // Four 25s buckets over the last 100s
+        let points = [(930, 10), (940, 20), (990, 50)];
+        let (values, offset) = buckets(&points, 1000, 100, 4);
+        assert_eq!(values, vec![15.0, 15.0, 50.0]);
+        assert_eq!(offset, 75);
+        assert_eq!(buckets(&[], 1000, 100, 4), (Vec::new(), 0));
+        assert_eq!(buckets(&[(10, 1)], 1000, 100, 4), (Vec::new(), 0));
+    }
+}
@@ -0,0 +1,425 @@
+//! Global hotkeys.
+//!
+//! Binds key combinations from the `[hotkeys]` config table to bar actions
+//! using Carbon's `RegisterEventHotKey`, which works without Accessibility
+//! permission and swallows the key press system-wide:
+//!
+//! ```toml
+//! [hotkeys]
+//! "cmd+shift+space" = "toggle-popup launcher"
+//! "ctrl+alt+b" = "toggle-bar"
+//! "ctrl+alt+t" = "toggle timetrack-0"
+//! "ctrl+alt+s" = "run open -a Slack"
+//! ```
+//!
+//! Module actions go through the same queue as IPC `trigger` commands, so
+//! they run on the bar's next frame.
+
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::sync::{Mutex, OnceLock};
+
+use crate::ipc;
+
+/// Carbon modifier masks
+const CMD_KEY: u32 = 1 << 8;
+const SHIFT_KEY: u32 = 1 << 9;
+const OPTION_KEY: u32 = 1 << 11;
+const CONTROL_KEY: u32 = 1 << 12;
+
+/// Signature for our hotkey ids ('snew')
+const HOTKEY_SIGNATURE: u32 = u32::from_be_bytes(*b"snew");
+
+// Carbon event FFI bindings
+mod ffi {
+    use std::ffi::c_void;
+
+    pub type OSStatus = i32;
+    pub type EventTargetRef = *mut c_void;
+    pub type EventHandlerRef = *mut c_void;
+    pub type EventHandlerCallRef = *mut c_void;
+    pub type EventRef = *mut c_void;
+    pub type EventHotKeyRef = *mut c_void;
+    pub type EventHandlerProcPtr = extern "C" fn(
+        next: EventHandlerCallRef,
+        event: EventRef,
+        user_data: *mut c_void,
+    ) -> OSStatus;
+
+    pub const NO_ERR: OSStatus = 0;
+    pub const EVENT_CLASS_KEYBOARD: u32 = u32::from_be_bytes(*b"keyb");
+    pub const EVENT_HOTKEY_PRESSED: u32 = 5;
+    pub const EVENT_PARAM_DIRECT_OBJECT: u32 = u32::from_be_bytes(*b"----");
+    pub const TYPE_EVENT_HOTKEY_ID: u32 = u32::from_be_bytes(*b"hkid");
+
+    #[repr(C)]
+    pub struct EventTypeSpec {
+        pub event_class: u32,
+        pub event_kind: u32,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    pub struct EventHotKeyID {
+        pub signature: u32,
+        pub id: u32,
+    }
+
+    #[link(name = "Carbon", kind = "framework")]
+    extern "C" {
+        pub fn GetApplicationEventTarget() -> EventTargetRef;
+
+        pub fn InstallEventHandler(
+            target: EventTargetRef,
+            handler: EventHandlerProcPtr,
+            num_types: u32,
+            list: *const EventTypeSpec,
+            user_data: *mut c_void,
+            out_ref: *mut EventHandlerRef,
+        ) -> OSStatus;
+
+        pub fn GetEventParameter(
+            event: EventRef,
+            name: u32,
+            desired_type: u32,
+            actual_type: *mut u32,
+            buffer_size: usize,
+            actual_size: *mut usize,
+            data: *mut c_void,
+        ) -> OSStatus;
+
+        pub fn RegisterEventHotKey(
+            key_code: u32,
+            modifiers: u32,
+            id: EventHotKeyID,
+            target: EventTargetRef,
+            options: u32,
+            out_ref: *mut EventHotKeyRef,
+        ) -> OSStatus;
+
+        pub fn UnregisterEventHotKey(hotkey: EventHotKeyRef) -> OSStatus;
+    }
+}
+
+/// A key combination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hotkey {
+    /// Virtual key code (kVK_*)
+    pub key_code: u32,
+    /// Carbon modifier mask
+    pub modifiers: u32,
+}
+
+impl Hotkey {
+    /// Parses a combination like `cmd+shift+space` or `ctrl+alt+f5`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut modifiers = 0;
+        let mut key_code = None;
+        for part in spec.split('+').map(|p| p.trim().to_ascii_lowercase()) {
+            match part.as_str() {
+                "cmd" | "command" | "super" => modifiers |= CMD_KEY,
+                "shift" => modifiers |= SHIFT_KEY,
+                "alt" | "opt" | "option" => modifiers |= OPTION_KEY,
+                "ctrl" | "control" => modifiers |= CONTROL_KEY,
+                key => {
+                    if key_code.is_some() {
+                        return Err(format!("'{}' has more than one key", spec));
+                    }
+                    key_code = Some(
+                        key_code_for(key)
+                            .ok_or_else(|| format!("unknown key '{}' in '{}'", key, spec))?,
+                    );
+                }
+            }
+        }
+        let key_code = key_code.ok_or_else(|| format!("'{}' has no key", spec))?;
+        Ok(Self {
+            key_code,
+            modifiers,
+        })
+    }
+}
+
+/// Maps a key name to its ANSI virtual key code.
+fn key_code_for(key: &str) -> Option<u32> {
+    const LETTERS: [u32; 26] = [
+        0x00, 0x0B, 0x08, 0x02, 0x0E, 0x03, 0x05, 0x04, 0x22, 0x26, 0x28, 0x25, 0x2E, 0x2D, 0x1F,
+        0x23, 0x0C, 0x0F, 0x01, 0x11, 0x20, 0x09, 0x0D, 0x07, 0x10, 0x06,
+    ];
+    const DIGITS: [u32; 10] = [0x1D, 0x12, 0x13, 0x14, 0x15, 0x17, 0x16, 0x1A, 0x1C, 0x19];
+    const FUNCTION_KEYS: [u32; 12] = [
+        0x7A, 0x78, 0x63, 0x76, 0x60, 0x61, 0x62, 0x64, 0x65, 0x6D, 0x67, 0x6F,
+    ];
+
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_lowercase() {
+            return Some(LETTERS[(c as u8 - b'a') as usize]);
+        }
+        if c.is_ascii_digit() {
+            return Some(DIGITS[(c as u8 - b'0') as usize]);
+        }
+    }
+    if let Some(n) = key.strip_prefix('f').and_then(|n| n.parse::<usize>().ok()) {
+        return FUNCTION_KEYS.get(n.checked_sub(1)?).copied();
+    }
+    Some(match key {
+        "space" => 0x31,
+        "return" | "enter" => 0x24,
+        "tab" => 0x30,
+        "escape" | "esc" => 0x35,
+        "delete" | "backspace" => 0x33,
+        "left" => 0x7B,
+        "right" => 0x7C,
+        "down" => 0x7D,
+        "up" => 0x7E,
+        "-" | "minus" => 0x1B,
+        "=" | "equal" => 0x18,
+        "[" => 0x21,
+        "]" => 0x1E,
+        ";" => 0x29,
+        "'" => 0x27,
+        "," | "comma" => 0x2B,
+        "." | "period" => 0x2F,
+        "/" | "slash" => 0x2C,
+        "\\" | "backslash" => 0x2A,
+        "`" | "grave" => 0x32,
+        _ => return None,
+    })
+}
+
+/// What a hotkey does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HotkeyAction {
+    /// Open or close a module's popup
+    TogglePopup(String),
+    /// Flip a toggle module (switching its toggle group)
+    Toggle(String),
+    ShowBar,
+    HideBar,
+    ToggleBar,
+    /// Run a shell command
+    Run(String),
+}
+
+impl HotkeyAction {
+    /// Parses an action like `toggle-popup launcher` or `run open -a Mail`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        let (name, arg) = match spec.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (spec, ""),
+        };
+        let require_arg = |action: fn(String) -> Self| {
+            if arg.is_empty() {
+                Err(format!("'{}' needs an argument", name))
+            } else {
+                Ok(action(arg.to_string()))
+            }
+        };
+        match name {
+            "toggle-popup" => require_arg(Self::TogglePopup),
+            "toggle" => require_arg(Self::Toggle),
+            "run" => require_arg(Self::Run),
+            "show-bar" => Ok(Self::ShowBar),
+            "hide-bar" => Ok(Self::HideBar),
+            "toggle-bar" => Ok(Self::ToggleBar),
+            _ => Err(format!(
+                "unknown action '{}', expected one of: toggle-popup, toggle, run, show-bar, hide-bar, toggle-bar",
+                name
+            )),
+        }
+    }
+
+    fn perform(&self) {
+        match self {
+            Self::TogglePopup(id) => ipc::trigger_module(id, "popup"),
+            Self::Toggle(id) => ipc::trigger_module(id, "toggle"),
+            Self::ShowBar => super::set_bar_visible(Some(true)),
+            Self::HideBar => super::set_bar_visible(Some(false)),
+            Self::ToggleBar => super::set_bar_visible(None),
+            Self::Run(command) => {
+                let command = command.clone();
+                std::thread::spawn(move || {
+                    if let Err(err) = std::process::Command::new("sh")
+                        .args(["-c", &command])
+                        .status()
+                    {
+                        log::warn!("Hotkey command failed: {}: {}", command, err);
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Registered hotkey refs and the action behind each hotkey id.
+struct Bindings {
+    refs: Vec<usize>,
+    actions: HashMap<u32, HotkeyAction>,
+}
+
+fn bindings() -> &'static Mutex<Bindings> {
+    static BINDINGS: OnceLock<Mutex<Bindings>> = OnceLock::new();
+    BINDINGS.get_or_init(|| {
+        Mutex::new(Bindings {
+            refs: Vec::new(),
+            actions: HashMap::new(),
+        })
+    })
+}
+
+extern "C" fn handle_hotkey(
+    _next: ffi::EventHandlerCallRef,
+    event: ffi::EventRef,
+    _user_data: *mut c_void,
+) -> ffi::OSStatus {
+    let mut id = ffi::EventHotKeyID::default();
+    let status = unsafe {
+        ffi::GetEventParameter(
+            event,
+            ffi::EVENT_PARAM_DIRECT_OBJECT,
+            ffi::TYPE_EVENT_HOTKEY_ID,
+            std::ptr::null_mut(),
+            std::mem::size_of::<ffi::EventHotKeyID>(),
+            std::ptr::null_mut(),
+            &mut id as *mut _ as *mut c_void,
+        )
+    };
+    if status != ffi::NO_ERR || id.signature != HOTKEY_SIGNATURE {
+        return status;
+    }
+    let action = bindings()
+        .lock()
+        .ok()
+        .and_then(|b| b.actions.get(&id.id).cloned());
+    if let Some(action) = action {
+        log::info!("Hotkey {} -> {:?}", id.id, action);
+        action.perform();
+    }
+    ffi::NO_ERR
+}
+
+/// Installs the application hotkey handler (once).
+fn install_handler() {
+    static INSTALLED: OnceLock<()> = OnceLock::new();
+    INSTALLED.get_or_init(|| {
+        let spec = ffi::EventTypeSpec {
+            event_class: ffi::EVENT_CLASS_KEYBOARD,
+            event_kind: ffi::EVENT_HOTKEY_PRESSED,
+        };
+        let status = unsafe {
+            ffi::InstallEventHandler(
+                ffi::GetApplicationEventTarget(),
+                handle_hotkey,
+                1,
+                &spec,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        if status != ffi::NO_ERR {
+            log::error!("Failed to install hotkey handler (OSStatus {})", status);
+        }
+    });
+}
+
+/// Replaces all registered hotkeys with the configured ones. Must be called
+/// on the main thread.
+pub fn register(config: &HashMap<String, String>) {
+    let Ok(mut bindings) = bindings().lock() else {
+        return;
+    };
+    for hotkey in bindings.refs.drain(..) {
+        unsafe { ffi::UnregisterEventHotKey(hotkey as ffi::EventHotKeyRef) };
+    }
+    bindings.actions.clear();
+    if config.is_empty() {
+        return;
+    }
+    install_handler();
+
+    let mut specs: Vec<_> = config.iter().collect();
+    specs.sort();
+    for (id, (keys, action)) in (1u32..).zip(specs) {
+        let (hotkey, action) = match (Hotkey::parse(keys), HotkeyAction::parse(action)) {
+            (Ok(hotkey), Ok(action)) => (hotkey, action),
+            (Err(err), _) | (_, Err(err)) => {
+                log::warn!("hotkeys.\"{}\": {}", keys, err);
+                continue;
+            }
+        };
+        let mut hotkey_ref: ffi::EventHotKeyRef = std::ptr::null_mut();
+        let status = unsafe {
+            ffi::RegisterEventHotKey(
+                hotkey.key_code,
+                hotkey.modifiers,
+                ffi::EventHotKeyID {
+                    signature: HOTKEY_SIGNATURE,
+                    id,
+                },
+                ffi::GetApplicationEventTarget(),
+                0,
+                &mut hotkey_ref,
+            )
+        };
+        if status != ffi::NO_ERR {
+            log::warn!(
+                "hotkeys.\"{}\": could not register (OSStatus {}), already taken?",
+                keys,
+                status
+            );
+            continue;
+        }
+        bindings.refs.push(hotkey_ref as usize);
+        bindings.actions.insert(id, action);
+    }
+    log::info!("Registered {} hotkey(s)", bindings.refs.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_key_combinations() {
+        assert_eq!(
+            Hotkey::parse("cmd+shift+space"),
+            Ok(Hotkey {
+                key_code: 0x31,
+                modifiers: CMD_KEY | SHIFT_KEY,
+            })
+        );
+        assert_eq!(
+            Hotkey::parse("Ctrl + Alt + T"),
+            Ok(Hotkey {
+                key_code: 0x11,
+                modifiers: CONTROL_KEY | OPTION_KEY,
+            })
+        );
+        assert_eq!(Hotkey::parse("opt+f12").map(|h| h.key_code), Ok(0x6F));
+        assert_eq!(Hotkey::parse("cmd+1").map(|h| h.key_code), Ok(0x12));
+        assert!(Hotkey::parse("cmd+shift").is_err());
+        assert!(Hotkey::parse("cmd+a+b").is_err());
+        assert!(Hotkey::parse("hyper+x").is_err());
+        assert!(Hotkey::parse("f13").is_err());
+    }
+
+    #[test]
+    fn parses_actions() {
+        assert_eq!(
+            HotkeyAction::parse("toggle-popup launcher"),
+            Ok(HotkeyAction::TogglePopup("launcher".into()))
+        );
+        assert_eq!(
+            HotkeyAction::parse("run open -a Mail"),
+            Ok(HotkeyAction::Run("open -a Mail".into()))
+        );
+        assert_eq!(
+            HotkeyAction::parse("toggle-bar"),
+            Ok(HotkeyAction::ToggleBar)
+        );
+        assert!(HotkeyAction::parse("toggle").is_err());
+        assert!(HotkeyAction::parse("explode now").is_err());
+    }
+}
@@ -0,0 +1,378 @@
+//! Hover-intent popup opening.
+//!
+//! Modules with `popup_on_hover = true` open their popup once the pointer
+//! has rested on them for `popup_hover_delay_ms`, and close it again when
+//! the pointer has left both the module and the popup for a short grace
+//! period. The way down from the module to the popup counts as inside: a
+//! corridor widening from the module's top edge to the popup's, so crossing
+//! neighboring modules on a diagonal doesn't close it. Clicking the module
+//! keeps a hover-opened popup open like a clicked one.
+//!
+//! Enter and leave come from the bar's hover handlers. While a popup is
+//! hover-opened, mouse-moved monitors follow the pointer over the bar, the
+//! popup and other apps alike. Opening and closing happen from the bar's
+//! render pass (AppKit window changes must happen on the main thread).
+
+use std::cell::RefCell;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use block2::RcBlock;
+use objc2::rc::Retained;
+use objc2::runtime::AnyObject;
+use objc2_app_kit::{NSEvent, NSEventMask};
+
+use crate::gpui_app::modules::get_popup_spec;
+use crate::gpui_app::popup_manager;
+
+/// How long the pointer may be outside before a hover-opened popup closes.
+const CLOSE_GRACE: Duration = Duration::from_millis(300);
+
+/// Hover state shared by the bar's handlers, the timers and the monitors.
+static INTENT: Mutex<Intent> = Mutex::new(Intent::new());
+/// Whether the pointer was last seen outside the module, popup and corridor.
+static POINTER_OUTSIDE: AtomicBool = AtomicBool::new(false);
+/// Bumped whenever the pointer leaves or comes back; a pending close only
+/// goes ahead if it hasn't changed since.
+static POINTER_GENERATION: AtomicU64 = AtomicU64::new(0);
+/// Set once the pointer stayed outside for the grace period; applied by
+/// the bar.
+static CLOSE_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// Mouse-moved monitors installed while a hover-opened popup is shown
+    static POINTER_MONITORS: RefCell<Vec<Retained<AnyObject>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Screen rectangle in AppKit coordinates (origin at the bottom left).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    /// Returns true if the point is inside or on the edge.
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.height
+    }
+}
+
+/// Returns true if the pointer at (x, y) is on the module, on its popup, or
+/// on the way between them: the region that widens from the module's width
+/// at its top edge to also span the popup's at the popup's top edge.
+pub fn in_corridor(module: &Rect, popup: &Rect, x: f64, y: f64) -> bool {
+    if module.contains(x, y) || popup.contains(x, y) {
+        return true;
+    }
+    let top = module.y + module.height;
+    let bottom = popup.y + popup.height;
+    if top <= bottom || y > top || y < bottom {
+        return false;
+    }
+    // 0 at the module's top edge, 1 at the popup's
+    let t = (top - y) / (top - bottom);
+    let module_right = module.x + module.width;
+    let left = module.x + (module.x.min(popup.x) - module.x) * t;
+    let right = module_right + (module_right.max(popup.x + popup.width) - module_right) * t;
+    x >= left && x <= right
+}
+
+/// Which popup the pointer is resting on, and which one hovering opened.
+#[derive(Debug)]
+struct Intent {
+    /// Popup of the module under the pointer, and the module's rectangle
+    hovered: Option<(String, Rect)>,
+    /// Popup whose delay is up, waiting for the bar to open it
+    requested: Option<(String, Rect)>,
+    /// Popup opened by hovering, and the rectangle of its module
+    opened: Option<(String, Rect)>,
+    /// Bumped on every enter, leave and click; a pending open only goes
+    /// ahead if it hasn't changed since
+    generation: u64,
+}
+
+impl Intent {
+    const fn new() -> Self {
+        Self {
+            hovered: None,
+            requested: None,
+            opened: None,
+            generation: 0,
+        }
+    }
+
+    /// The pointer entered the module of `popup_id`. Returns the generation
+    /// the delay timer has to find unchanged.
+    fn enter(&mut self, popup_id: &str, module: Rect) -> u64 {
+        self.hovered = Some((popup_id.to_string(), module));
+        self.generation += 1;
+        self.generation
+    }
+
+    /// The pointer left the module of `popup_id`. Leaving a module the
+    /// pointer already moved on from changes nothing.
+    fn leave(&mut self, popup_id: &str) {
+        if self.hovered.as_ref().is_some_and(|(id, _)| id == popup_id) {
+            self.hovered = None;
+            self.generation += 1;
+        }
+    }
+
+    /// The delay started at `generation` is up. Returns true if the hovered
+    /// popup should open.
+    fn fire(&mut self, generation: u64) -> bool {
+        if generation != self.generation || self.hovered.is_none() {
+            return false;
+        }
+        self.requested = self.hovered.clone();
+        true
+    }
+
+    /// The module of `popup_id` was clicked, which cancels a pending open.
+    /// Returns true if hovering had opened the popup, which now stays open.
+    fn claim(&mut self, popup_id: &str) -> bool {
+        self.generation += 1;
+        self.requested = None;
+        if self.opened.as_ref().is_some_and(|(id, _)| id == popup_id) {
+            self.opened = None;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn is_open(popup_id: &str) -> bool {
+    popup_manager::is_popup_visible() && popup_manager::get_current_module_id() == popup_id
+}
+
+/// The pointer entered a module whose popup `popup_id` opens on hover.
+/// `left` and `right` are the module's screen x edges.
+pub fn enter(popup_id: &str, left: f64, right: f64, delay: Duration) {
+    let bar_height = popup_manager::bar_height();
+    let module = Rect {
+        x: left,
+        y: popup_manager::screen_height() - bar_height,
+        width: right - left,
+        height: bar_height,
+    };
+    let Ok(generation) = INTENT
+        .lock()
+        .map(|mut intent| intent.enter(popup_id, module))
+    else {
+        return;
+    };
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        let fired = INTENT
+            .lock()
+            .map(|mut intent| intent.fire(generation))
+            .unwrap_or(false);
+        if fired {
+            crate::gpui_app::request_immediate_refresh();
+        }
+    });
+}
+
+/// The pointer left the module of `popup_id`.
+pub fn leave(popup_id: &str) {
+    if let Ok(mut intent) = INTENT.lock() {
+        intent.leave(popup_id);
+    }
+}
+
+/// Handles a click on the module of `popup_id`. Returns true if the click
+/// keeps a hover-opened popup open instead of toggling it closed. Must be
+/// called on the main thread.
+pub fn claim(popup_id: &str) -> bool {
+    let claimed = INTENT
+        .lock()
+        .map(|mut intent| intent.claim(popup_id))
+        .unwrap_or(false);
+    if claimed {
+        stop_pointer_tracking();
+    }
+    claimed && is_open(popup_id)
+}
+
+/// Opens a popup whose hover delay is up, and closes a hover-opened one the
+/// pointer left. Called from the bar on the main thread.
+pub fn apply_requests() {
+    let Ok(mut intent) = INTENT.lock() else {
+        return;
+    };
+    let requested = intent.requested.take();
+    // Forget a hover-opened popup that closed some other way
+    let stale = intent.opened.as_ref().is_some_and(|(id, _)| !is_open(id));
+    let close = CLOSE_REQUESTED.swap(false, Ordering::SeqCst);
+    let closing = if stale || close {
+        intent.opened.take()
+    } else {
+        None
+    };
+    drop(intent);
+
+    if let Some((id, _)) = closing {
+        stop_pointer_tracking();
+        if close && is_open(&id) {
+            popup_manager::hide_popup();
+        }
+    }
+
+    let Some((id, module)) = requested else {
+        return;
+    };
+    // Already open, e.g. clicked during the delay
+    if is_open(&id) {
+        return;
+    }
+    popup_manager::record_popup_anchor(module.x, module.x + module.width);
+    popup_manager::record_popup_click(&id);
+    if popup_manager::toggle_popup(&id) {
+        if let Ok(mut intent) = INTENT.lock() {
+            intent.opened = Some((id, module));
+        }
+        start_pointer_tracking();
+    }
+}
+
+/// Checks the pointer after it moved, and asks for the hover-opened popup
+/// to close once the pointer has been outside for `CLOSE_GRACE`.
+fn pointer_moved() {
+    let Some((id, module)) = INTENT.lock().ok().and_then(|intent| intent.opened.clone()) else {
+        return;
+    };
+    let popup = get_popup_spec(&id)
+        .and_then(|spec| popup_manager::popup_window_frame(spec.popup_type))
+        .map(|(x, y, width, height)| Rect {
+            x,
+            y,
+            width,
+            height,
+        })
+        .unwrap_or_default();
+    let mouse = NSEvent::mouseLocation();
+    let outside = !in_corridor(&module, &popup, mouse.x, mouse.y);
+    if POINTER_OUTSIDE.swap(outside, Ordering::SeqCst) == outside {
+        return;
+    }
+    let generation = POINTER_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    if outside {
+        std::thread::spawn(move || {
+            std::thread::sleep(CLOSE_GRACE);
+            if POINTER_GENERATION.load(Ordering::SeqCst) == generation {
+                CLOSE_REQUESTED.store(true, Ordering::SeqCst);
+                crate::gpui_app::request_immediate_refresh();
+            }
+        });
+    }
+}
+
+/// Follows the pointer: a global monitor sees it move over other apps, a
+/// local one over sinew's own windows. Must be called on the main thread.
+fn start_pointer_tracking() {
+    POINTER_MONITORS.with(|monitors| {
+        let mut monitors = monitors.borrow_mut();
+        if !monitors.is_empty() {
+            return;
+        }
+        POINTER_OUTSIDE.store(false, Ordering::SeqCst);
+        let global = RcBlock::new(|_event: NonNull<NSEvent>| pointer_moved());
+        let local = RcBlock::new(|event: NonNull<NSEvent>| -> *mut NSEvent {
+            pointer_moved();
+            event.as_ptr()
+        });
+        monitors.extend(NSEvent::addGlobalMonitorForEventsMatchingMask_handler(
+            NSEventMask::MouseMoved,
+            &global,
+        ));
+        monitors.extend(unsafe {
+            NSEvent::addLocalMonitorForEventsMatchingMask_handler(NSEventMask::MouseMoved, &local)
+        });
+    });
+}
+
+/// Removes the pointer monitors. Must be called on the main thread.
+fn stop_pointer_tracking() {
+    POINTER_MONITORS.with(|monitors| {
+        for monitor in monitors.borrow_mut().drain(..) {
+            unsafe {
+                NSEvent::removeMonitor(&monitor);
+            }
+        }
+    });
+    // Cancels a pending close
+    POINTER_GENERATION.fetch_add(1, Ordering::SeqCst);
+    CLOSE_REQUESTED.store(false, Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f64, y: f64, width: f64, height: f64) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn corridor_widens_from_module_to_popup() {
+        // Module at the right end of a 32px bar, popup hanging left of it
+        let module = rect(900.0, 868.0, 40.0, 32.0);
+        let popup = rect(700.0, 568.0, 300.0, 300.0);
+        assert!(in_corridor(&module, &popup, 920.0, 890.0));
+        assert!(in_corridor(&module, &popup, 800.0, 700.0));
+        // Halfway down the bar, half of the popup's extra width is covered
+        assert!(in_corridor(&module, &popup, 805.0, 884.0));
+        assert!(!in_corridor(&module, &popup, 795.0, 884.0));
+        // Near the module's top edge, only the module itself
+        assert!(!in_corridor(&module, &popup, 880.0, 899.0));
+        assert!(!in_corridor(&module, &popup, 600.0, 700.0));
+    }
+
+    #[test]
+    fn delay_opens_only_if_pointer_stayed() {
+        let module = rect(0.0, 868.0, 40.0, 32.0);
+        let mut intent = Intent::new();
+
+        let generation = intent.enter("calendar", module);
+        assert!(intent.fire(generation));
+        assert_eq!(
+            intent.requested.as_ref().map(|(id, _)| id.as_str()),
+            Some("calendar")
+        );
+
+        // Leaving, or a click, before the delay is up cancels the open
+        let generation = intent.enter("weather", module);
+        intent.leave("weather");
+        assert!(!intent.fire(generation));
+        let generation = intent.enter("weather", module);
+        assert!(!intent.claim("weather"));
+        assert!(!intent.fire(generation));
+
+        // Leaving a module the pointer already moved on from is ignored
+        let generation = intent.enter("clock", module);
+        intent.leave("weather");
+        assert!(intent.fire(generation));
+    }
+
+    #[test]
+    fn click_keeps_hover_opened_popup() {
+        let module = rect(0.0, 868.0, 40.0, 32.0);
+        let mut intent = Intent::new();
+        intent.opened = Some(("calendar".to_string(), module));
+
+        assert!(!intent.claim("weather"));
+        assert!(intent.claim("calendar"));
+        assert!(intent.opened.is_none());
+    }
+}
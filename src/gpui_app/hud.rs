@@ -0,0 +1,697 @@
+//! Notch HUD for volume and brightness keys.
+//!
+//! A borderless window centered on the notch drops down a short strip with
+//! an icon and level whenever a volume or brightness key is pressed, then
+//! hides after `bar.hud.timeout_ms` without further presses.
+//!
+//! Keys are read with a CGEventTap on `NSSystemDefined` events. With
+//! `replace_system` the tap swallows the keys and applies the change itself,
+//! so the system HUD never appears; otherwise it only listens and reads the
+//! new level after macOS has applied it. Both need the Accessibility
+//! permission. Volume goes through AppleScript; brightness uses the private
+//! DisplayServices framework, loaded at runtime.
+
+use std::ffi::{c_char, c_void};
+use std::process::Command;
+use std::ptr::null_mut;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use std::sync::{mpsc, Mutex, OnceLock};
+use std::time::Duration;
+
+use async_channel::{Receiver, Sender};
+use core_foundation::base::TCFType;
+use core_foundation::runloop::{kCFRunLoopCommonModes, CFRunLoop, CFRunLoopSource};
+use futures_util::future::FutureExt;
+use futures_util::{pin_mut, select};
+use gpui::{
+    div, point, prelude::*, px, relative, size, App, AppContext, Bounds, Context, Rgba,
+    SharedString, Styled, Window, WindowBounds, WindowKind, WindowOptions,
+};
+use objc2::MainThreadMarker;
+
+use crate::config::HudConfig;
+use crate::gpui_app::primitives::icons;
+use crate::gpui_app::primitives::ProgressRing;
+use crate::gpui_app::theme::Theme;
+use crate::gpui_app::window_registry::{self, WindowRole};
+
+/// HUD window width.
+const HUD_WIDTH: f64 = 320.0;
+/// Height of the strip shown below the menu bar.
+const STRIP_HEIGHT: f64 = 30.0;
+/// Above the menu bar so the HUD can extend the notch.
+const HUD_WINDOW_LEVEL: i64 = 25;
+/// Volume and brightness move in 16 steps, like the system keys.
+const STEPS: f32 = 16.0;
+
+/// Sender feeding level changes to the HUD view.
+static HUD_TX: OnceLock<Sender<HudState>> = OnceLock::new();
+
+/// What the HUD is showing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HudKind {
+    Volume,
+    Brightness,
+}
+
+/// A level change to display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HudState {
+    pub kind: HudKind,
+    /// 0–100
+    pub level: u8,
+    pub muted: bool,
+}
+
+/// How the level is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HudStyle {
+    /// Horizontal bar
+    #[default]
+    Bar,
+    /// Progress ring
+    Ring,
+}
+
+impl HudStyle {
+    /// Parses a style name, defaulting to the bar.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "ring" => Self::Ring,
+            _ => Self::Bar,
+        }
+    }
+}
+
+/// Shows the HUD with a new level (no-op if the HUD isn't enabled).
+pub fn show(state: HudState) {
+    if let Some(tx) = HUD_TX.get() {
+        let _ = tx.try_send(state);
+    }
+}
+
+/// Media keys handled by the HUD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKey {
+    VolumeUp,
+    VolumeDown,
+    Mute,
+    BrightnessUp,
+    BrightnessDown,
+}
+
+/// Decodes an `NSSystemDefined` event into a media key and whether it's a
+/// key down (including repeats). Other events return None.
+pub fn decode_media_key(subtype: i16, data1: isize) -> Option<(MediaKey, bool)> {
+    // NSSystemDefined subtype 8 carries aux control buttons (NX_SUBTYPE_AUX_CONTROL_BUTTONS)
+    if subtype != 8 {
+        return None;
+    }
+    let data1 = data1 as u64;
+    let code = (data1 & 0xFFFF_0000) >> 16;
+    let down = (data1 & 0xFF00) >> 8 == 0x0A;
+    let key = match code {
+        0 => MediaKey::VolumeUp,
+        1 => MediaKey::VolumeDown,
+        2 => MediaKey::BrightnessUp,
+        3 => MediaKey::BrightnessDown,
+        7 => MediaKey::Mute,
+        _ => return None,
+    };
+    Some((key, down))
+}
+
+/// Moves a 0–100 level one step up or down, snapping to the 16-step grid.
+pub fn step_level(level: u8, up: bool) -> u8 {
+    let step = 100.0 / STEPS;
+    let current = (level as f32 / step).round();
+    let next = if up { current + 1.0 } else { current - 1.0 };
+    (next.clamp(0.0, STEPS) * step).round() as u8
+}
+
+/// Parses `get volume settings` output into (output volume, muted).
+pub fn parse_volume_settings(output: &str) -> Option<(u8, bool)> {
+    let mut volume = None;
+    let mut muted = false;
+    for part in output.trim().split(", ") {
+        match part.split_once(':') {
+            Some(("output volume", value)) => volume = value.parse().ok(),
+            Some(("output muted", value)) => muted = value == "true",
+            _ => {}
+        }
+    }
+    volume.map(|v| (v, muted))
+}
+
+fn osascript(script: &str) -> Option<String> {
+    Command::new("osascript")
+        .args(["-e", script])
+        .output()
+        .ok()
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+}
+
+fn volume_settings() -> Option<(u8, bool)> {
+    osascript("get volume settings").and_then(|out| parse_volume_settings(&out))
+}
+
+// DisplayServices (private) brightness functions, resolved with dlsym
+type GetBrightnessFn = unsafe extern "C" fn(display: u32, brightness: *mut f32) -> i32;
+type SetBrightnessFn = unsafe extern "C" fn(display: u32, brightness: f32) -> i32;
+
+extern "C" {
+    fn dlopen(path: *const c_char, mode: i32) -> *mut c_void;
+    fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+}
+
+fn display_services() -> Option<(GetBrightnessFn, SetBrightnessFn)> {
+    static FNS: OnceLock<Option<(GetBrightnessFn, SetBrightnessFn)>> = OnceLock::new();
+    *FNS.get_or_init(|| unsafe {
+        const RTLD_LAZY: i32 = 1;
+        let handle = dlopen(
+            c"/System/Library/PrivateFrameworks/DisplayServices.framework/DisplayServices".as_ptr(),
+            RTLD_LAZY,
+        );
+        if handle.is_null() {
+            log::warn!("HUD: DisplayServices unavailable, brightness keys disabled");
+            return None;
+        }
+        let get = dlsym(handle, c"DisplayServicesGetBrightness".as_ptr());
+        let set = dlsym(handle, c"DisplayServicesSetBrightness".as_ptr());
+        if get.is_null() || set.is_null() {
+            return None;
+        }
+        Some((
+            std::mem::transmute::<*mut c_void, GetBrightnessFn>(get),
+            std::mem::transmute::<*mut c_void, SetBrightnessFn>(set),
+        ))
+    })
+}
+
+fn brightness() -> Option<u8> {
+    let (get, _) = display_services()?;
+    let display = core_graphics::display::CGDisplay::main().id;
+    let mut value = 0.0_f32;
+    if unsafe { get(display, &mut value) } != 0 {
+        return None;
+    }
+    Some((value.clamp(0.0, 1.0) * 100.0).round() as u8)
+}
+
+fn set_brightness(level: u8) {
+    if let Some((_, set)) = display_services() {
+        let display = core_graphics::display::CGDisplay::main().id;
+        unsafe { set(display, level as f32 / 100.0) };
+    }
+}
+
+/// Applies a key press (when replacing the system handler) and returns the
+/// resulting state to display.
+fn handle_media_key(key: MediaKey, replace: bool) -> Option<HudState> {
+    if !replace {
+        // Give macOS a moment to apply the change before reading it back
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    match key {
+        MediaKey::VolumeUp | MediaKey::VolumeDown | MediaKey::Mute => {
+            let (mut level, mut muted) = volume_settings()?;
+            if replace {
+                if key == MediaKey::Mute {
+                    muted = !muted;
+                    osascript(&format!("set volume output muted {}", muted));
+                } else {
+                    level = step_level(level, key == MediaKey::VolumeUp);
+                    muted = false;
+                    osascript(&format!(
+                        "set volume output volume {} without output muted",
+                        level
+                    ));
+                }
+            }
+            Some(HudState {
+                kind: HudKind::Volume,
+                level,
+                muted,
+            })
+        }
+        MediaKey::BrightnessUp | MediaKey::BrightnessDown => {
+            let mut level = brightness()?;
+            if replace {
+                level = step_level(level, key == MediaKey::BrightnessUp);
+                set_brightness(level);
+            }
+            Some(HudState {
+                kind: HudKind::Brightness,
+                level,
+                muted: false,
+            })
+        }
+    }
+}
+
+// CGEventTap FFI bindings
+mod ffi {
+    use std::ffi::c_void;
+
+    pub type CGEventTapProxy = *const c_void;
+    pub type CGEventRef = *const c_void;
+    pub type CFMachPortRef = *const c_void;
+
+    pub const K_CG_SESSION_EVENT_TAP: u32 = 1;
+    pub const K_CG_HEAD_INSERT_EVENT_TAP: u32 = 0;
+    pub const K_CG_EVENT_TAP_OPTION_DEFAULT: u32 = 0;
+    pub const K_CG_EVENT_TAP_OPTION_LISTEN_ONLY: u32 = 1;
+    pub const NX_SYSDEFINED: u32 = 14;
+    pub const K_CG_EVENT_TAP_DISABLED_BY_TIMEOUT: u32 = 0xFFFF_FFFE;
+    pub const K_CG_EVENT_TAP_DISABLED_BY_USER_INPUT: u32 = 0xFFFF_FFFF;
+
+    pub type CGEventTapCallBack = extern "C" fn(
+        proxy: CGEventTapProxy,
+        event_type: u32,
+        event: CGEventRef,
+        user_info: *mut c_void,
+    ) -> CGEventRef;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        pub fn CGEventTapCreate(
+            tap: u32,
+            place: u32,
+            options: u32,
+            events_of_interest: u64,
+            callback: CGEventTapCallBack,
+            user_info: *mut c_void,
+        ) -> CFMachPortRef;
+
+        pub fn CGEventTapEnable(tap: CFMachPortRef, enable: bool);
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        pub fn CFMachPortCreateRunLoopSource(
+            allocator: *const c_void,
+            port: CFMachPortRef,
+            order: isize,
+        ) -> *mut c_void;
+    }
+}
+
+/// CGEvent pointer with the encoding `+[NSEvent eventWithCGEvent:]` expects.
+#[repr(transparent)]
+struct CGEventPtr(ffi::CGEventRef);
+
+unsafe impl objc2::encode::Encode for CGEventPtr {
+    const ENCODING: objc2::encode::Encoding =
+        objc2::encode::Encoding::Pointer(&objc2::encode::Encoding::Struct("__CGEvent", &[]));
+}
+
+static TAP: AtomicPtr<c_void> = AtomicPtr::new(null_mut());
+static REPLACE_SYSTEM: AtomicBool = AtomicBool::new(true);
+static KEY_TX: OnceLock<Mutex<mpsc::Sender<MediaKey>>> = OnceLock::new();
+
+extern "C" fn media_key_tap(
+    _proxy: ffi::CGEventTapProxy,
+    event_type: u32,
+    event: ffi::CGEventRef,
+    _user_info: *mut c_void,
+) -> ffi::CGEventRef {
+    if event_type == ffi::K_CG_EVENT_TAP_DISABLED_BY_TIMEOUT
+        || event_type == ffi::K_CG_EVENT_TAP_DISABLED_BY_USER_INPUT
+    {
+        let tap = TAP.load(Ordering::Relaxed);
+        if !tap.is_null() {
+            unsafe { ffi::CGEventTapEnable(tap, true) };
+        }
+        return event;
+    }
+    if event_type != ffi::NX_SYSDEFINED {
+        return event;
+    }
+
+    let decoded = unsafe {
+        let ns_event: Option<objc2::rc::Retained<objc2_app_kit::NSEvent>> = objc2::msg_send![
+            objc2::class!(NSEvent),
+            eventWithCGEvent: CGEventPtr(event)
+        ];
+        ns_event.and_then(|ns_event| {
+            let subtype: i16 = objc2::msg_send![&ns_event, subtype];
+            let data1: isize = objc2::msg_send![&ns_event, data1];
+            decode_media_key(subtype, data1)
+        })
+    };
+    let Some((key, down)) = decoded else {
+        return event;
+    };
+
+    if down {
+        if let Some(tx) = KEY_TX.get().and_then(|tx| tx.lock().ok()) {
+            let _ = tx.send(key);
+        }
+    }
+    if REPLACE_SYSTEM.load(Ordering::Relaxed) {
+        std::ptr::null()
+    } else {
+        event
+    }
+}
+
+/// Starts the key worker and installs the event tap on the main run loop.
+fn start_key_tap(replace: bool) {
+    REPLACE_SYSTEM.store(replace, Ordering::Relaxed);
+
+    let (tx, rx) = mpsc::channel::<MediaKey>();
+    if KEY_TX.set(Mutex::new(tx)).is_err() {
+        return; // Already started
+    }
+    std::thread::spawn(move || {
+        while let Ok(key) = rx.recv() {
+            if let Some(state) = handle_media_key(key, replace) {
+                show(state);
+            }
+        }
+    });
+
+    let options = if replace {
+        ffi::K_CG_EVENT_TAP_OPTION_DEFAULT
+    } else {
+        ffi::K_CG_EVENT_TAP_OPTION_LISTEN_ONLY
+    };
+    unsafe {
+        let tap = ffi::CGEventTapCreate(
+            ffi::K_CG_SESSION_EVENT_TAP,
+            ffi::K_CG_HEAD_INSERT_EVENT_TAP,
+            options,
+            1 << ffi::NX_SYSDEFINED,
+            media_key_tap,
+            null_mut(),
+        );
+        if tap.is_null() {
+            log::warn!("HUD: could not create event tap (grant Accessibility permission)");
+            return;
+        }
+        TAP.store(tap as *mut c_void, Ordering::Relaxed);
+        let source = ffi::CFMachPortCreateRunLoopSource(std::ptr::null(), tap, 0);
+        let source = CFRunLoopSource::wrap_under_create_rule(source as _);
+        CFRunLoop::get_main().add_source(&source, kCFRunLoopCommonModes);
+        ffi::CGEventTapEnable(tap, true);
+    }
+    log::info!("HUD media key tap installed (replace_system={})", replace);
+}
+
+/// Shows or hides the HUD window.
+fn set_window_visible(visible: bool) {
+    let Some(mtm) = MainThreadMarker::new() else {
+        return;
+    };
+    if let Some(ns_window) = window_registry::window(mtm, &WindowRole::Hud) {
+        if visible {
+            ns_window.orderFrontRegardless();
+        } else {
+            ns_window.orderOut(None);
+        }
+    }
+}
+
+/// View drawn inside the HUD window.
+pub struct HudView {
+    theme: Theme,
+    style: HudStyle,
+    bar_height: f64,
+    state: Option<HudState>,
+}
+
+impl HudView {
+    fn new(
+        theme: Theme,
+        style: HudStyle,
+        bar_height: f64,
+        timeout: Duration,
+        rx: Receiver<HudState>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        cx.spawn(async move |this, cx| {
+            while let Ok(state) = rx.recv().await {
+                let _ = this.update(cx, |view, cx| {
+                    view.state = Some(state);
+                    cx.notify();
+                });
+                set_window_visible(true);
+
+                // Stay up until no change arrives for `timeout`
+                loop {
+                    let next = rx.recv().fuse();
+                    let timer = cx.background_executor().timer(timeout).fuse();
+                    pin_mut!(next, timer);
+                    select! {
+                        state = next => match state {
+                            Ok(state) => {
+                                let _ = this.update(cx, |view, cx| {
+                                    view.state = Some(state);
+                                    cx.notify();
+                                });
+                            }
+                            Err(_) => return,
+                        },
+                        _ = timer => break,
+                    }
+                }
+                set_window_visible(false);
+            }
+        })
+        .detach();
+
+        Self {
+            theme,
+            style,
+            bar_height,
+            state: None,
+        }
+    }
+}
+
+impl Render for HudView {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        // Black and white so the strip reads as part of the notch
+        let black = Rgba {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+            a: 1.0,
+        };
+        let white = Rgba {
+            r: 1.0,
+            g: 1.0,
+            b: 1.0,
+            a: 1.0,
+        };
+        let track = self.theme.with_alpha(white, 0.25);
+
+        let mut strip = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap(px(10.0))
+            .h(px(STRIP_HEIGHT as f32))
+            .px(px(16.0))
+            .text_color(white)
+            .text_size(px(self.theme.font_size));
+
+        if let Some(state) = self.state {
+            let icon = match state.kind {
+                HudKind::Volume => icons::volume::for_level(state.level, state.muted),
+                HudKind::Brightness => icons::system::BRIGHTNESS,
+            };
+            let fraction = if state.muted {
+                0.0
+            } else {
+                state.level as f32 / 100.0
+            };
+            let label = if state.muted {
+                "Muted".to_string()
+            } else {
+                format!("{}%", state.level)
+            };
+
+            strip = strip.child(div().flex_none().child(SharedString::from(icon)));
+            strip = match self.style {
+                HudStyle::Bar => strip.child(
+                    div().flex_1().h(px(4.0)).rounded_full().bg(track).child(
+                        div()
+                            .h_full()
+                            .w(relative(fraction))
+                            .rounded_full()
+                            .bg(white),
+                    ),
+                ),
+                HudStyle::Ring => strip.child(div().flex_1()).child(
+                    ProgressRing::new(fraction)
+                        .size(18.0)
+                        .thickness(2.5)
+                        .track_color(track)
+                        .fill_color(white)
+                        .render(&self.theme),
+                ),
+            };
+            strip = strip.child(
+                div()
+                    .flex_none()
+                    .w(px(44.0))
+                    .flex()
+                    .justify_end()
+                    .child(SharedString::from(label)),
+            );
+        }
+
+        div()
+            .size_full()
+            .flex()
+            .flex_col()
+            .bg(black)
+            .rounded_b(px(14.0))
+            .child(div().h(px(self.bar_height as f32)))
+            .child(strip)
+    }
+}
+
+/// Creates the (hidden) HUD window and starts listening for media keys.
+///
+/// `screen` is the main screen frame (x, y, width, height) in AppKit
+/// coordinates.
+pub fn init(
+    cx: &mut App,
+    config: &HudConfig,
+    screen: (f64, f64, f64, f64),
+    bar_height: f64,
+    theme: Theme,
+) {
+    let (screen_x, screen_y, screen_width, screen_height) = screen;
+    let height = bar_height + STRIP_HEIGHT;
+    let x = screen_x + (screen_width - HUD_WIDTH) / 2.0;
+    let y = screen_y + screen_height - height;
+
+    let (tx, rx) = async_channel::unbounded();
+    if HUD_TX.set(tx).is_err() {
+        return; // Already initialized
+    }
+
+    let style = HudStyle::parse(&config.style);
+    let timeout = Duration::from_millis(config.timeout_ms);
+    let bounds = Bounds {
+        origin: point(px(x as f32), px(0.0)),
+        size: size(px(HUD_WIDTH as f32), px(height as f32)),
+    };
+    let before = window_registry::window_numbers();
+    let result = cx.open_window(
+        WindowOptions {
+            window_bounds: Some(WindowBounds::Windowed(bounds)),
+            titlebar: None,
+            kind: WindowKind::PopUp,
+            is_movable: false,
+            focus: false,
+            show: false,
+            window_background: gpui::WindowBackgroundAppearance::Transparent,
+            ..Default::default()
+        },
+        |_window, cx| cx.new(|cx| HudView::new(theme, style, bar_height, timeout, rx, cx)),
+    );
+    if let Err(err) = result {
+        log::warn!("Failed to create HUD window: {}", err);
+        return;
+    }
+    window_registry::tag_new(&before, WindowRole::Hud);
+
+    // Configure the NSWindow on the next run-loop turn, like the bar windows
+    let block = block2::RcBlock::new(move || {
+        let Some(mtm) = MainThreadMarker::new() else {
+            return;
+        };
+        configure_window(mtm, x, y, height);
+    });
+    unsafe {
+        objc2_foundation::NSRunLoop::mainRunLoop().performBlock(&block);
+    }
+
+    start_key_tap(config.replace_system);
+}
+
+/// Makes the HUD window a click-through overlay.
+fn configure_window(mtm: MainThreadMarker, x: f64, y: f64, height: f64) {
+    use objc2_app_kit::NSWindowStyleMask;
+    use objc2_foundation::{NSPoint, NSRect, NSSize};
+
+    let Some(ns_window) = window_registry::window(mtm, &WindowRole::Hud) else {
+        log::warn!("HUD window not found");
+        return;
+    };
+    unsafe {
+        ns_window.setStyleMask(NSWindowStyleMask::Borderless);
+        ns_window.setFrame_display(
+            NSRect::new(NSPoint::new(x, y), NSSize::new(HUD_WIDTH, height)),
+            true,
+        );
+        let _: () = objc2::msg_send![&ns_window, setLevel: HUD_WINDOW_LEVEL];
+        ns_window.setHasShadow(false);
+        ns_window.setOpaque(false);
+        ns_window.setIgnoresMouseEvents(true);
+        ns_window.orderOut(None);
+    }
+    log::info!(
+        "Configured HUD window: frame=({}, {}) {}x{}",
+        x,
+        y,
+        HUD_WIDTH,
+        height
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data1(code: isize, state: isize) -> isize {
+        (code << 16) | (state << 8)
+    }
+
+    #[test]
+    fn decodes_media_keys_and_key_state() {
+        assert_eq!(
+            decode_media_key(8, data1(0, 0x0A)),
+            Some((MediaKey::VolumeUp, true))
+        );
+        assert_eq!(
+            decode_media_key(8, data1(3, 0x0B)),
+            Some((MediaKey::BrightnessDown, false))
+        );
+        assert_eq!(
+            decode_media_key(8, data1(7, 0x0A) | 1),
+            Some((MediaKey::Mute, true))
+        );
+        assert_eq!(decode_media_key(8, data1(16, 0x0A)), None);
+        assert_eq!(decode_media_key(7, data1(0, 0x0A)), None);
+    }
+
+    #[test]
+    fn steps_snap_to_sixteenths_and_clamp() {
+        assert_eq!(step_level(50, true), 56);
+        assert_eq!(step_level(50, false), 44);
+        assert_eq!(step_level(0, false), 0);
+        assert_eq!(step_level(100, true), 100);
+        assert_eq!(step_level(97, true), 100);
+    }
+
+    #[test]
+    fn parses_volume_settings() {
+        let out = "output volume:31, input volume:75, alert volume:100, output muted:true\n";
+        assert_eq!(parse_volume_settings(out), Some((31, true)));
+        assert_eq!(
+            parse_volume_settings("output volume:missing value, output muted:false"),
+            None
+        );
+    }
+
+    #[test]
+    fn style_parses_ring_and_defaults_to_bar() {
+        assert_eq!(HudStyle::parse("ring"), HudStyle::Ring);
+        assert_eq!(HudStyle::parse("pill"), HudStyle::Bar);
+    }
+}
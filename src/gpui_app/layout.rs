@@ -0,0 +1,645 @@
+//! Bar layout diagnostics and overflow resolution.
+//!
+//! The bar is laid out with flexbox, so zone collisions only become visible
+//! after prepaint. Each zone container reports its children's bounds into a
+//! shared [`LayoutSnapshot`]; the next render analyzes the snapshot to log
+//! overlaps and, when an overflow strategy is enabled, hide modules that
+//! don't fit. Modules marked `spill` move into free space in the other half
+//! of the bar before anything is hidden. The snapshot counts its changes, so
+//! the analysis only reruns when a module was measured at a new size or the
+//! bar was resized.
+
+use std::collections::{HashMap, HashSet};
+
+use gpui::{Bounds, Pixels};
+
+/// Tolerance for sub-pixel rounding when comparing module edges.
+const OVERLAP_EPSILON: f32 = 0.5;
+
+/// A module zone within one half of the bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Zone {
+    /// Left half, aligned to the left screen edge
+    LeftOuter,
+    /// Left half, aligned toward the notch
+    LeftInner,
+    /// Left half, anchored to its horizontal center
+    LeftCenter,
+    /// Right half, aligned toward the notch
+    RightOuter,
+    /// Right half, aligned to the right screen edge
+    RightInner,
+    /// Right half, anchored to its horizontal center
+    RightCenter,
+}
+
+impl Zone {
+    /// Parses a zone named like its module section, e.g. `right.left`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "left.left" => Some(Self::LeftOuter),
+            "left.right" => Some(Self::LeftInner),
+            "left.center" => Some(Self::LeftCenter),
+            "right.left" => Some(Self::RightOuter),
+            "right.right" => Some(Self::RightInner),
+            "right.center" => Some(Self::RightCenter),
+            _ => None,
+        }
+    }
+
+    /// Returns the alignment a zone has without a `bar.justify` entry:
+    /// zones hug the side of their half they're named after.
+    pub fn default_justify(self) -> Justify {
+        match self {
+            Self::LeftInner | Self::RightInner => Justify::End,
+            _ => Justify::Start,
+        }
+    }
+}
+
+/// How a zone lines up its modules, in screen terms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Justify {
+    /// Packed against the left
+    Start,
+    /// Packed against the right
+    End,
+    /// First and last module at the zone's edges, the rest spread evenly
+    SpaceBetween,
+}
+
+impl Justify {
+    /// Parses a `bar.justify` value.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "start" => Some(Self::Start),
+            "end" => Some(Self::End),
+            "space-between" => Some(Self::SpaceBetween),
+            _ => None,
+        }
+    }
+}
+
+/// Bar halves as [leading, center, trailing] zones, ordered left to right.
+pub const HALVES: [[Zone; 3]; 2] = [
+    [Zone::LeftOuter, Zone::LeftCenter, Zone::LeftInner],
+    [Zone::RightOuter, Zone::RightCenter, Zone::RightInner],
+];
+
+/// Zone of each half that modules spilling into it join: the one next to
+/// the notch, so they carry on from where their own half ended.
+pub const SPILL_ZONES: [Zone; 2] = [Zone::LeftInner, Zone::RightOuter];
+
+/// Horizontal extent of a rendered module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleSpan {
+    pub id: String,
+    pub left: f32,
+    pub right: f32,
+}
+
+impl ModuleSpan {
+    /// Returns the rendered width of the module.
+    pub fn width(&self) -> f32 {
+        (self.right - self.left).max(0.0)
+    }
+}
+
+/// How to resolve modules that don't fit in their half of the bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowStrategy {
+    /// Leave modules overlapping (diagnostics only)
+    #[default]
+    None,
+    /// Hide the modules closest to the collision until the rest fit
+    Hide,
+}
+
+impl OverflowStrategy {
+    /// Parses the `bar.overflow` config value.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "hide" => Self::Hide,
+            _ => Self::None,
+        }
+    }
+}
+
+/// Measured layout from the most recent prepaint.
+#[derive(Debug, Default)]
+pub struct LayoutSnapshot {
+    /// Spans of the currently visible modules, per zone
+    zones: HashMap<Zone, Vec<ModuleSpan>>,
+    /// Last measured width per module (kept while a module is hidden)
+    widths: HashMap<String, f32>,
+    /// Measured width of each bar half (left, right)
+    half_widths: [f32; 2],
+    /// Center zones sit between the halves instead of inside them
+    /// (displays without a notch)
+    screen_center: bool,
+    /// Bumped whenever a measurement changes
+    generation: u64,
+}
+
+/// Result of analyzing a [`LayoutSnapshot`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LayoutReport {
+    /// Pairs of (leading, trailing) module ids whose bounds intersect
+    pub overlaps: Vec<(String, String)>,
+    /// Modules hidden by the overflow strategy
+    pub hidden: HashSet<String>,
+    /// Modules moved into each half (left, right) because their own is
+    /// full, in render order
+    pub spilled: [Vec<String>; 2],
+}
+
+impl LayoutReport {
+    /// Returns the ids of every module involved in an overlap.
+    pub fn overlapping_ids(&self) -> HashSet<String> {
+        self.overlaps
+            .iter()
+            .flat_map(|(a, b)| [a.clone(), b.clone()])
+            .collect()
+    }
+}
+
+impl LayoutSnapshot {
+    /// Records the prepainted bounds of a zone's children.
+    ///
+    /// `ids` must be in the same order the children were rendered.
+    pub fn record_zone(&mut self, zone: Zone, ids: &[String], bounds: &[Bounds<Pixels>]) {
+        let spans: Vec<ModuleSpan> = ids
+            .iter()
+            .zip(bounds)
+            .map(|(id, b)| ModuleSpan {
+                id: id.clone(),
+                left: f32::from(b.origin.x),
+                right: f32::from(b.origin.x + b.size.width),
+            })
+            .collect();
+        if self.zones.get(&zone) == Some(&spans) {
+            return;
+        }
+        for span in &spans {
+            self.widths.insert(span.id.clone(), span.width());
+        }
+        self.zones.insert(zone, spans);
+        self.generation += 1;
+    }
+
+    /// Records the prepainted bounds of the bar halves.
+    pub fn record_halves(&mut self, left: f32, right: f32) {
+        if self.half_widths != [left, right] {
+            self.half_widths = [left, right];
+            self.generation += 1;
+        }
+    }
+
+    /// Sets whether center zones are anchored to the screen center rather
+    /// than to the center of each half.
+    pub fn set_screen_center(&mut self, screen_center: bool) {
+        if self.screen_center != screen_center {
+            self.screen_center = screen_center;
+            self.generation += 1;
+        }
+    }
+
+    /// Counter that changes whenever the measurements do.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns the measured width of a zone, from its first to last module.
+    pub fn zone_width(&self, zone: Zone) -> f32 {
+        match self.zones.get(&zone).map(Vec::as_slice) {
+            Some([first, .., last]) => (last.right - first.left).max(0.0),
+            Some([only]) => only.width(),
+            _ => 0.0,
+        }
+    }
+
+    /// Returns the measured span of a visible module, in window coordinates.
+    pub fn span(&self, id: &str) -> Option<&ModuleSpan> {
+        self.zones.values().flatten().find(|span| span.id == id)
+    }
+
+    /// Returns where a half's center zone should start, relative to the half.
+    pub fn center_offset(&self, half: usize, gap: f32) -> f32 {
+        let [leading, center, trailing] = HALVES[half];
+        center_offset(
+            self.half_widths[half],
+            self.zone_width(leading),
+            self.zone_width(center),
+            self.zone_width(trailing),
+            gap,
+        )
+    }
+
+    /// Drops all measurements (called when modules are rebuilt).
+    pub fn clear(&mut self) {
+        *self = Self {
+            generation: self.generation + 1,
+            ..Self::default()
+        };
+    }
+
+    /// Finds zone collisions, moves `spill` modules out of full halves and
+    /// applies the overflow strategy.
+    ///
+    /// `order` lists each zone's module ids in render order (including hidden
+    /// and spilled modules, in their own zone) so the strategy can use cached
+    /// widths for modules that are not currently on screen.
+    pub fn analyze(
+        &self,
+        order: &HashMap<Zone, Vec<String>>,
+        spill: &HashSet<String>,
+        strategy: OverflowStrategy,
+        gap: f32,
+    ) -> LayoutReport {
+        let mut report = LayoutReport::default();
+        let empty = Vec::new();
+        let widths = |zone: &Zone| -> Vec<(String, f32)> {
+            order
+                .get(zone)
+                .map(|ids| {
+                    ids.iter()
+                        .map(|id| (id.clone(), self.widths.get(id).copied().unwrap_or(0.0)))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        // Edge zones of each half, and the room they have
+        let halves: Vec<(Vec<(String, f32)>, Vec<(String, f32)>, f32)> = HALVES
+            .iter()
+            .enumerate()
+            .map(|(half, [leading, center, trailing])| {
+                // The center zone stays put; edge zones share what's left of the half.
+                let center_width = if self.screen_center {
+                    0.0
+                } else {
+                    self.zone_width(*center)
+                };
+                let available = if center_width > 0.0 {
+                    self.half_widths[half] - center_width - gap * 2.0
+                } else {
+                    self.half_widths[half]
+                };
+                (widths(leading), widths(trailing), available.max(0.0))
+            })
+            .collect();
+        let free: Vec<f32> = halves
+            .iter()
+            .map(|(leading, trailing, available)| {
+                (available - used(leading, gap) - used(trailing, gap)).max(0.0)
+            })
+            .collect();
+
+        for (half, [leading, center, trailing]) in HALVES.iter().enumerate() {
+            let lead_spans = self.zones.get(leading).unwrap_or(&empty);
+            let center_spans = self.zones.get(center).unwrap_or(&empty);
+            let trail_spans = self.zones.get(trailing).unwrap_or(&empty);
+            report
+                .overlaps
+                .extend(find_overlaps(lead_spans, trail_spans));
+            report
+                .overlaps
+                .extend(find_overlaps(lead_spans, center_spans));
+            report
+                .overlaps
+                .extend(find_overlaps(center_spans, trail_spans));
+
+            if self.half_widths[half] <= 0.0 {
+                continue;
+            }
+            let (lead_items, trail_items, available) = &halves[half];
+            let spilled = resolve_spill(
+                *available,
+                free[1 - half],
+                gap,
+                lead_items,
+                trail_items,
+                spill,
+            );
+            if strategy == OverflowStrategy::Hide {
+                let stay = |items: &[(String, f32)]| -> Vec<(String, f32)> {
+                    items
+                        .iter()
+                        .filter(|(id, _)| !spilled.contains(id))
+                        .cloned()
+                        .collect()
+                };
+                report.hidden.extend(resolve_overflow(
+                    *available,
+                    gap,
+                    &stay(lead_items),
+                    &stay(trail_items),
+                ));
+            }
+            report.spilled[1 - half] = spilled;
+        }
+
+        report
+    }
+}
+
+/// Space taken by modules of the given widths in a row.
+fn used(items: &[(String, f32)], gap: f32) -> f32 {
+    let widths: f32 = items.iter().map(|(_, w)| w).sum();
+    widths + gap * items.len().saturating_sub(1) as f32
+}
+
+/// Space taken by the modules not in `removed`.
+fn used_except(items: &[(String, f32)], removed: &HashSet<String>, gap: f32) -> f32 {
+    let kept: Vec<(String, f32)> = items
+        .iter()
+        .filter(|(id, _)| !removed.contains(id))
+        .cloned()
+        .collect();
+    used(&kept, gap)
+}
+
+/// Returns (leading, trailing) id pairs whose spans intersect.
+pub fn find_overlaps(leading: &[ModuleSpan], trailing: &[ModuleSpan]) -> Vec<(String, String)> {
+    let mut overlaps = Vec::new();
+    for a in leading {
+        for b in trailing {
+            if a.right - b.left > OVERLAP_EPSILON && b.right - a.left > OVERLAP_EPSILON {
+                overlaps.push((a.id.clone(), b.id.clone()));
+            }
+        }
+    }
+    overlaps
+}
+
+/// Positions a center zone of `center` width within a half of `half` width.
+///
+/// The zone is centered when possible, otherwise clamped so it stays clear of
+/// the leading and trailing zones. If it can't clear both, it stays centered
+/// and the collision is left to the overlap diagnostics.
+pub fn center_offset(half: f32, leading: f32, center: f32, trailing: f32, gap: f32) -> f32 {
+    let ideal = ((half - center) / 2.0).max(0.0);
+    let min = if leading > 0.0 { leading + gap } else { 0.0 };
+    let max = half - center - if trailing > 0.0 { trailing + gap } else { 0.0 };
+    if min <= max {
+        ideal.clamp(min, max)
+    } else {
+        ideal
+    }
+}
+
+/// Lists the modules of a half in the order overflow removes them.
+///
+/// Collisions happen where the leading zone's last module meets the trailing
+/// zone's first module, so modules go from that point outward, always
+/// trimming whichever zone currently takes more space.
+fn trim_order<'a>(
+    gap: f32,
+    leading: &'a [(String, f32)],
+    trailing: &'a [(String, f32)],
+) -> Vec<&'a (String, f32)> {
+    let mut order = Vec::new();
+    let mut lead_end = leading.len();
+    let mut trail_start = 0;
+    while lead_end > 0 || trail_start < trailing.len() {
+        let lead = used(&leading[..lead_end], gap);
+        let trail = used(&trailing[trail_start..], gap);
+        if lead_end > 0 && (lead >= trail || trail_start == trailing.len()) {
+            lead_end -= 1;
+            order.push(&leading[lead_end]);
+        } else {
+            order.push(&trailing[trail_start]);
+            trail_start += 1;
+        }
+    }
+    order
+}
+
+/// Picks modules to hide so both zones of a half fit within `available`,
+/// in [`trim_order`].
+pub fn resolve_overflow(
+    available: f32,
+    gap: f32,
+    leading: &[(String, f32)],
+    trailing: &[(String, f32)],
+) -> HashSet<String> {
+    let mut hidden = HashSet::new();
+    for (id, _) in trim_order(gap, leading, trailing) {
+        if used_except(leading, &hidden, gap) + used_except(trailing, &hidden, gap) <= available {
+            break;
+        }
+        hidden.insert(id.clone());
+    }
+    hidden
+}
+
+/// Picks `spill` modules to move out of a half that doesn't fit within
+/// `available` into the other half's `room`, going in [`trim_order`] and
+/// skipping modules that aren't marked or don't fit there.
+///
+/// Returns the moved ids in render order.
+pub fn resolve_spill(
+    available: f32,
+    room: f32,
+    gap: f32,
+    leading: &[(String, f32)],
+    trailing: &[(String, f32)],
+    spill: &HashSet<String>,
+) -> Vec<String> {
+    let mut moved = HashSet::new();
+    let mut room = room;
+    for (id, width) in trim_order(gap, leading, trailing) {
+        if used_except(leading, &moved, gap) + used_except(trailing, &moved, gap) <= available {
+            break;
+        }
+        if spill.contains(id) && width + gap <= room {
+            room -= width + gap;
+            moved.insert(id.clone());
+        }
+    }
+    leading
+        .iter()
+        .chain(trailing)
+        .filter(|(id, _)| moved.contains(id))
+        .map(|(id, _)| id.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(id: &str, left: f32, right: f32) -> ModuleSpan {
+        ModuleSpan {
+            id: id.to_string(),
+            left,
+            right,
+        }
+    }
+
+    fn item(id: &str, width: f32) -> (String, f32) {
+        (id.to_string(), width)
+    }
+
+    #[test]
+    fn zone_parses_module_section_names() {
+        assert_eq!(Zone::parse("left.left"), Some(Zone::LeftOuter));
+        assert_eq!(Zone::parse("right.center"), Some(Zone::RightCenter));
+        assert_eq!(Zone::parse("middle"), None);
+    }
+
+    #[test]
+    fn justify_parses_and_defaults_per_zone() {
+        assert_eq!(Justify::parse("space-between"), Some(Justify::SpaceBetween));
+        assert_eq!(Justify::parse("center"), None);
+        assert_eq!(Zone::LeftOuter.default_justify(), Justify::Start);
+        assert_eq!(Zone::RightInner.default_justify(), Justify::End);
+    }
+
+    #[test]
+    fn find_overlaps_ignores_touching_edges() {
+        let leading = [span("a", 0.0, 50.0)];
+        let trailing = [span("b", 50.0, 90.0)];
+        assert!(find_overlaps(&leading, &trailing).is_empty());
+    }
+
+    #[test]
+    fn find_overlaps_reports_intersecting_pairs() {
+        let leading = [span("a", 0.0, 50.0), span("b", 54.0, 120.0)];
+        let trailing = [span("c", 100.0, 140.0), span("d", 144.0, 180.0)];
+        assert_eq!(
+            find_overlaps(&leading, &trailing),
+            vec![("b".to_string(), "c".to_string())]
+        );
+    }
+
+    #[test]
+    fn resolve_overflow_keeps_everything_when_it_fits() {
+        let hidden = resolve_overflow(200.0, 4.0, &[item("a", 50.0)], &[item("b", 50.0)]);
+        assert!(hidden.is_empty());
+    }
+
+    #[test]
+    fn resolve_overflow_trims_larger_zone_at_collision_point() {
+        let leading = [item("a", 60.0), item("b", 60.0)];
+        let trailing = [item("c", 40.0)];
+        let hidden = resolve_overflow(120.0, 0.0, &leading, &trailing);
+        assert_eq!(hidden, HashSet::from(["b".to_string()]));
+    }
+
+    #[test]
+    fn resolve_overflow_trims_trailing_zone_from_its_start() {
+        let leading = [item("a", 20.0)];
+        let trailing = [item("b", 60.0), item("c", 60.0)];
+        let hidden = resolve_overflow(90.0, 0.0, &leading, &trailing);
+        assert_eq!(hidden, HashSet::from(["b".to_string()]));
+    }
+
+    #[test]
+    fn resolve_spill_moves_marked_modules_that_fit_across() {
+        let leading = [item("a", 60.0), item("b", 60.0), item("c", 60.0)];
+        let trailing = [item("d", 40.0)];
+        let spill = HashSet::from(["a".to_string(), "b".to_string()]);
+        // "c" is at the collision point but isn't marked, so "b" moves
+        assert_eq!(
+            resolve_spill(180.0, 100.0, 0.0, &leading, &trailing, &spill),
+            vec!["b".to_string()]
+        );
+        // Needing more room than the other half has moves what fits
+        assert_eq!(
+            resolve_spill(60.0, 100.0, 0.0, &leading, &trailing, &spill),
+            vec!["b".to_string()]
+        );
+        assert_eq!(
+            resolve_spill(60.0, 200.0, 0.0, &leading, &trailing, &spill),
+            vec!["a".to_string(), "b".to_string()]
+        );
+        assert!(resolve_spill(220.0, 100.0, 0.0, &leading, &trailing, &spill).is_empty());
+    }
+
+    #[test]
+    fn analyze_spills_before_hiding() {
+        let mut snapshot = LayoutSnapshot::default();
+        snapshot.half_widths = [200.0, 200.0];
+        for (id, width) in [("a", 80.0), ("b", 80.0), ("c", 80.0), ("d", 50.0)] {
+            snapshot.widths.insert(id.to_string(), width);
+        }
+        let order = HashMap::from([
+            (Zone::LeftOuter, vec!["a".to_string(), "b".to_string()]),
+            (Zone::LeftInner, vec!["c".to_string()]),
+            (Zone::RightInner, vec!["d".to_string()]),
+        ]);
+        let spill = HashSet::from(["b".to_string()]);
+
+        let report = snapshot.analyze(&order, &spill, OverflowStrategy::Hide, 0.0);
+        assert_eq!(report.spilled, [vec![], vec!["b".to_string()]]);
+        assert!(report.hidden.is_empty());
+
+        let report = snapshot.analyze(&order, &HashSet::new(), OverflowStrategy::Hide, 0.0);
+        assert_eq!(report.spilled, [Vec::<String>::new(), vec![]]);
+        assert_eq!(report.hidden, HashSet::from(["b".to_string()]));
+    }
+
+    #[test]
+    fn center_offset_centers_when_unobstructed() {
+        assert_eq!(center_offset(400.0, 50.0, 100.0, 50.0, 4.0), 150.0);
+    }
+
+    #[test]
+    fn center_offset_shifts_away_from_wide_leading_zone() {
+        assert_eq!(center_offset(400.0, 180.0, 100.0, 20.0, 4.0), 184.0);
+    }
+
+    #[test]
+    fn center_offset_stays_centered_when_it_cannot_fit() {
+        assert_eq!(center_offset(400.0, 200.0, 100.0, 200.0, 4.0), 150.0);
+    }
+
+    #[test]
+    fn analyze_reports_overlaps_per_half() {
+        let mut snapshot = LayoutSnapshot::default();
+        snapshot
+            .zones
+            .insert(Zone::LeftOuter, vec![span("left-a", 0.0, 300.0)]);
+        snapshot
+            .zones
+            .insert(Zone::LeftInner, vec![span("left-b", 250.0, 400.0)]);
+        snapshot
+            .zones
+            .insert(Zone::RightOuter, vec![span("right-a", 600.0, 650.0)]);
+
+        let report = snapshot.analyze(
+            &HashMap::new(),
+            &HashSet::new(),
+            OverflowStrategy::None,
+            4.0,
+        );
+        assert_eq!(
+            report.overlaps,
+            vec![("left-a".to_string(), "left-b".to_string())]
+        );
+        assert!(report.hidden.is_empty());
+    }
+
+    #[test]
+    fn generation_changes_only_with_measurements() {
+        let bounds = |left: f32, width: f32| Bounds {
+            origin: gpui::point(gpui::px(left), gpui::px(0.0)),
+            size: gpui::size(gpui::px(width), gpui::px(24.0)),
+        };
+        let ids = ["clock".to_string()];
+        let mut snapshot = LayoutSnapshot::default();
+        snapshot.record_zone(Zone::LeftOuter, &ids, &[bounds(0.0, 80.0)]);
+        snapshot.record_halves(600.0, 600.0);
+        let measured = snapshot.generation();
+
+        snapshot.record_zone(Zone::LeftOuter, &ids, &[bounds(0.0, 80.0)]);
+        snapshot.record_halves(600.0, 600.0);
+        assert_eq!(snapshot.generation(), measured);
+
+        snapshot.record_zone(Zone::LeftOuter, &ids, &[bounds(0.0, 96.0)]);
+        assert_ne!(snapshot.generation(), measured);
+        let resized = snapshot.generation();
+        snapshot.clear();
+        assert_ne!(snapshot.generation(), resized);
+    }
+}
@@ -0,0 +1,347 @@
+//! Microphone detection module.
+//!
+//! Counterpart to the camera monitor: tracks whether any input device is
+//! being recorded from and, on macOS 14+, which processes are doing it.
+//!
+//! Uses CoreAudio property listeners on each input device's
+//! "is running somewhere" property and on the system process list, so
+//! changes are reported without polling.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+// CoreAudio FFI bindings
+mod ffi {
+    use std::ffi::{c_char, c_void};
+
+    pub type OSStatus = i32;
+    pub type AudioObjectID = u32;
+    pub type AudioObjectPropertySelector = u32;
+    pub type AudioObjectPropertyScope = u32;
+    pub type AudioObjectPropertyElement = u32;
+
+    pub const K_AUDIO_HARDWARE_NO_ERROR: OSStatus = 0;
+    pub const K_AUDIO_OBJECT_SYSTEM_OBJECT: AudioObjectID = 1;
+    pub const K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL: AudioObjectPropertyScope = 0x676C6F62; // 'glob'
+    pub const K_AUDIO_OBJECT_PROPERTY_SCOPE_INPUT: AudioObjectPropertyScope = 0x696E7074; // 'inpt'
+    pub const K_AUDIO_OBJECT_PROPERTY_ELEMENT_MAIN: AudioObjectPropertyElement = 0;
+    pub const K_AUDIO_HARDWARE_PROPERTY_DEVICES: AudioObjectPropertySelector = 0x64657623; // 'dev#'
+    pub const K_AUDIO_HARDWARE_PROPERTY_PROCESS_OBJECT_LIST: AudioObjectPropertySelector =
+        0x70727323; // 'prs#'
+    pub const K_AUDIO_DEVICE_PROPERTY_STREAMS: AudioObjectPropertySelector = 0x73746D23; // 'stm#'
+    pub const K_AUDIO_DEVICE_PROPERTY_DEVICE_IS_RUNNING_SOMEWHERE: AudioObjectPropertySelector =
+        0x676F6E65; // 'gone'
+    pub const K_AUDIO_PROCESS_PROPERTY_PID: AudioObjectPropertySelector = 0x70706964; // 'ppid'
+    pub const K_AUDIO_PROCESS_PROPERTY_IS_RUNNING_INPUT: AudioObjectPropertySelector = 0x70697269; // 'piri'
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct AudioObjectPropertyAddress {
+        pub selector: AudioObjectPropertySelector,
+        pub scope: AudioObjectPropertyScope,
+        pub element: AudioObjectPropertyElement,
+    }
+
+    /// Callback type for property listeners
+    pub type AudioObjectPropertyListenerProc = extern "C" fn(
+        object_id: AudioObjectID,
+        number_addresses: u32,
+        addresses: *const AudioObjectPropertyAddress,
+        client_data: *mut c_void,
+    ) -> OSStatus;
+
+    #[link(name = "CoreAudio", kind = "framework")]
+    extern "C" {
+        pub fn AudioObjectHasProperty(
+            object_id: AudioObjectID,
+            address: *const AudioObjectPropertyAddress,
+        ) -> bool;
+
+        pub fn AudioObjectGetPropertyDataSize(
+            object_id: AudioObjectID,
+            address: *const AudioObjectPropertyAddress,
+            qualifier_data_size: u32,
+            qualifier_data: *const c_void,
+            data_size: *mut u32,
+        ) -> OSStatus;
+
+        pub fn AudioObjectGetPropertyData(
+            object_id: AudioObjectID,
+            address: *const AudioObjectPropertyAddress,
+            qualifier_data_size: u32,
+            qualifier_data: *const c_void,
+            data_size: *mut u32,
+            data: *mut c_void,
+        ) -> OSStatus;
+
+        pub fn AudioObjectAddPropertyListener(
+            object_id: AudioObjectID,
+            address: *const AudioObjectPropertyAddress,
+            listener: AudioObjectPropertyListenerProc,
+            client_data: *mut c_void,
+        ) -> OSStatus;
+    }
+
+    extern "C" {
+        pub fn proc_name(pid: i32, buffer: *mut c_char, buffer_size: u32) -> i32;
+    }
+}
+
+/// Global microphone active state
+static MIC_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Names of processes recording audio (empty before macOS 14)
+static MIC_PROCESSES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Whether we've registered the property listeners
+static LISTENERS_REGISTERED: AtomicBool = AtomicBool::new(false);
+
+/// Returns true if any input device is currently in use.
+pub fn is_microphone_active() -> bool {
+    MIC_ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Returns the names of processes recording audio, when macOS reports them.
+pub fn microphone_processes() -> Vec<String> {
+    MIC_PROCESSES.lock().map(|p| p.clone()).unwrap_or_default()
+}
+
+/// Initialize microphone monitoring with property listeners.
+/// Safe to call more than once.
+pub fn start_monitoring() {
+    if LISTENERS_REGISTERED.swap(true, Ordering::Relaxed) {
+        return; // Already registered
+    }
+
+    refresh();
+    log::info!(
+        "Microphone monitoring started, initial state: {}",
+        if is_microphone_active() {
+            "active"
+        } else {
+            "inactive"
+        }
+    );
+
+    register_property_listeners();
+}
+
+/// Callback when a device or the process list changes
+extern "C" fn microphone_property_changed(
+    _object_id: ffi::AudioObjectID,
+    _number_addresses: u32,
+    _addresses: *const ffi::AudioObjectPropertyAddress,
+    _client_data: *mut std::ffi::c_void,
+) -> ffi::OSStatus {
+    if refresh() {
+        crate::gpui_app::request_immediate_refresh();
+    }
+    ffi::K_AUDIO_HARDWARE_NO_ERROR
+}
+
+/// Re-reads device and process state. Returns true if anything changed.
+///
+/// Listeners only fire when a device starts/stops or processes come and go,
+/// so callers may poll this to catch apps joining an already-running device.
+pub fn refresh() -> bool {
+    let is_active = input_devices().iter().any(|&id| device_is_running(id));
+    let processes = if is_active {
+        input_processes()
+    } else {
+        Vec::new()
+    };
+
+    let was_active = MIC_ACTIVE.swap(is_active, Ordering::Relaxed);
+    let mut changed = was_active != is_active;
+    if let Ok(mut guard) = MIC_PROCESSES.lock() {
+        if *guard != processes {
+            *guard = processes;
+            changed = true;
+        }
+    }
+    if was_active != is_active {
+        log::info!(
+            "Microphone state changed: {}",
+            if is_active { "active" } else { "inactive" }
+        );
+    }
+    changed
+}
+
+fn address(
+    selector: ffi::AudioObjectPropertySelector,
+    scope: ffi::AudioObjectPropertyScope,
+) -> ffi::AudioObjectPropertyAddress {
+    ffi::AudioObjectPropertyAddress {
+        selector,
+        scope,
+        element: ffi::K_AUDIO_OBJECT_PROPERTY_ELEMENT_MAIN,
+    }
+}
+
+/// Reads an array of object IDs from a property.
+fn object_list(
+    object_id: ffi::AudioObjectID,
+    address: &ffi::AudioObjectPropertyAddress,
+) -> Vec<ffi::AudioObjectID> {
+    use std::ptr::null;
+
+    unsafe {
+        if !ffi::AudioObjectHasProperty(object_id, address) {
+            return Vec::new();
+        }
+        let mut data_size: u32 = 0;
+        let status =
+            ffi::AudioObjectGetPropertyDataSize(object_id, address, 0, null(), &mut data_size);
+        if status != ffi::K_AUDIO_HARDWARE_NO_ERROR || data_size == 0 {
+            return Vec::new();
+        }
+        let count = data_size as usize / std::mem::size_of::<ffi::AudioObjectID>();
+        let mut ids: Vec<ffi::AudioObjectID> = vec![0; count];
+        let status = ffi::AudioObjectGetPropertyData(
+            object_id,
+            address,
+            0,
+            null(),
+            &mut data_size,
+            ids.as_mut_ptr() as *mut _,
+        );
+        if status != ffi::K_AUDIO_HARDWARE_NO_ERROR {
+            return Vec::new();
+        }
+        ids.truncate(data_size as usize / std::mem::size_of::<ffi::AudioObjectID>());
+        ids
+    }
+}
+
+/// Reads a 32-bit property value.
+fn read_u32(
+    object_id: ffi::AudioObjectID,
+    address: &ffi::AudioObjectPropertyAddress,
+) -> Option<u32> {
+    let mut value: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let status = unsafe {
+        ffi::AudioObjectGetPropertyData(
+            object_id,
+            address,
+            0,
+            std::ptr::null(),
+            &mut size,
+            &mut value as *mut _ as *mut _,
+        )
+    };
+    (status == ffi::K_AUDIO_HARDWARE_NO_ERROR).then_some(value)
+}
+
+/// Returns all audio devices that have input streams.
+fn input_devices() -> Vec<ffi::AudioObjectID> {
+    let devices = object_list(
+        ffi::K_AUDIO_OBJECT_SYSTEM_OBJECT,
+        &address(
+            ffi::K_AUDIO_HARDWARE_PROPERTY_DEVICES,
+            ffi::K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+        ),
+    );
+    let streams = address(
+        ffi::K_AUDIO_DEVICE_PROPERTY_STREAMS,
+        ffi::K_AUDIO_OBJECT_PROPERTY_SCOPE_INPUT,
+    );
+    devices
+        .into_iter()
+        .filter(|&id| !object_list(id, &streams).is_empty())
+        .collect()
+}
+
+fn device_is_running(device_id: ffi::AudioObjectID) -> bool {
+    let running = address(
+        ffi::K_AUDIO_DEVICE_PROPERTY_DEVICE_IS_RUNNING_SOMEWHERE,
+        ffi::K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+    );
+    read_u32(device_id, &running).unwrap_or(0) != 0
+}
+
+/// Returns the names of processes with running audio input (macOS 14+).
+fn input_processes() -> Vec<String> {
+    let processes = object_list(
+        ffi::K_AUDIO_OBJECT_SYSTEM_OBJECT,
+        &address(
+            ffi::K_AUDIO_HARDWARE_PROPERTY_PROCESS_OBJECT_LIST,
+            ffi::K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+        ),
+    );
+    let running_input = address(
+        ffi::K_AUDIO_PROCESS_PROPERTY_IS_RUNNING_INPUT,
+        ffi::K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+    );
+    let pid_address = address(
+        ffi::K_AUDIO_PROCESS_PROPERTY_PID,
+        ffi::K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+    );
+    let own_pid = std::process::id() as i32;
+
+    let mut names: Vec<String> = processes
+        .into_iter()
+        .filter(|&id| read_u32(id, &running_input).unwrap_or(0) != 0)
+        .filter_map(|id| read_u32(id, &pid_address).map(|pid| pid as i32))
+        .filter(|&pid| pid != own_pid)
+        .filter_map(process_name)
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Returns the executable name for a PID.
+fn process_name(pid: i32) -> Option<String> {
+    let mut buffer = [0 as std::ffi::c_char; 256];
+    let len = unsafe { ffi::proc_name(pid, buffer.as_mut_ptr(), buffer.len() as u32) };
+    if len <= 0 {
+        return None;
+    }
+    let bytes: Vec<u8> = buffer[..len as usize].iter().map(|&c| c as u8).collect();
+    String::from_utf8(bytes).ok()
+}
+
+/// Register property listeners for input devices and the process list
+fn register_property_listeners() {
+    let running = address(
+        ffi::K_AUDIO_DEVICE_PROPERTY_DEVICE_IS_RUNNING_SOMEWHERE,
+        ffi::K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+    );
+    let devices = input_devices();
+    for &device_id in &devices {
+        let status = unsafe {
+            ffi::AudioObjectAddPropertyListener(
+                device_id,
+                &running,
+                microphone_property_changed,
+                std::ptr::null_mut(),
+            )
+        };
+        if status == ffi::K_AUDIO_HARDWARE_NO_ERROR {
+            log::debug!("Registered microphone listener for device {}", device_id);
+        }
+    }
+
+    // Process list changes report which apps start/stop recording (macOS 14+)
+    let process_list = address(
+        ffi::K_AUDIO_HARDWARE_PROPERTY_PROCESS_OBJECT_LIST,
+        ffi::K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+    );
+    unsafe {
+        if ffi::AudioObjectHasProperty(ffi::K_AUDIO_OBJECT_SYSTEM_OBJECT, &process_list) {
+            ffi::AudioObjectAddPropertyListener(
+                ffi::K_AUDIO_OBJECT_SYSTEM_OBJECT,
+                &process_list,
+                microphone_property_changed,
+                std::ptr::null_mut(),
+            );
+        }
+    }
+
+    log::info!(
+        "Microphone property listeners registered for {} input devices",
+        devices.len()
+    );
+}
@@ -4,15 +4,32 @@
 //! replacing the CPU-based Core Graphics/Core Text rendering for smoother
 //! scrolling and better performance.
 
+pub mod alerts;
 mod bar;
+pub mod bench;
 pub mod camera;
+pub mod history;
+pub mod hotkeys;
+pub mod hover_intent;
+pub mod hud;
+pub mod layout;
+pub mod microphone;
 pub mod modules;
+pub mod notify;
 pub mod popup_manager;
+pub mod power_saving;
 #[allow(dead_code)]
 pub mod primitives;
+pub mod progress;
 pub mod scheduler;
+pub mod screenshot;
+pub mod snooze;
 #[allow(dead_code)]
 pub mod theme;
+pub mod toast;
+pub mod tooltip;
+pub mod ui_state;
+pub mod window_registry;
 
 use gpui::{
     point, px, size, App, AppContext, Application, Bounds, WindowBounds, WindowKind, WindowOptions,
@@ -21,10 +38,12 @@ use objc2::MainThreadMarker;
 use std::sync::{Mutex, OnceLock};
 
 pub use bar::request_immediate_refresh;
+use bar::BarScreen;
 pub use bar::BarView;
 
 use crate::config::load_config;
-use crate::window::get_main_screen_info;
+use crate::window::{builtin_notch_width, get_main_screen_info};
+use window_registry::WindowRole;
 
 /// Menu bar window level (-20) - same as SketchyBar.
 /// This allows the macOS menu bar (level 24) to appear above Sinew.
@@ -45,7 +64,13 @@ pub fn run() {
 
         // Load config
         let config = load_config();
-        crate::launch_agent::sync(config.bar.launch_at_login);
+        if !screenshot::active() {
+            crate::launch_agent::sync(config.bar.launch_at_login);
+            crate::http_api::sync(&config.bar.http);
+            alerts::sync(&config.alerts);
+            power_saving::sync(&config.bar.power_saving);
+        }
+        start_profile_monitor();
 
         // Get screen info
         let screen_info = get_main_screen_info(mtm).expect("No screen found");
@@ -60,13 +85,14 @@ pub fn run() {
         };
 
         log::info!(
-            "Creating GPUI menu bar: screen={}x{}, bar_height={} (config={:?}, system={}), macos_y={}",
+            "Creating GPUI menu bar: screen={}x{}, bar_height={} (config={:?}, system={}), macos_y={}, notch={}",
             screen_width,
             screen_height,
             bar_height,
             configured_bar_height,
             screen_info.menu_bar_height,
-            macos_y
+            macos_y,
+            screen_info.has_notch
         );
 
         // Start camera monitoring BEFORE creating bar windows
@@ -82,7 +108,28 @@ pub fn run() {
         let theme = theme::Theme::from_config(&config.bar);
         modules::init_modules(&theme);
 
-        create_bar_window(cx, screen_x, macos_y, screen_width, bar_height);
+        let bar_screen = BarScreen {
+            has_notch: screen_info.has_notch,
+            name: screen_info.name.clone(),
+            width: screen_width,
+            width_mm: screen_info.width_mm,
+            builtin_notch: builtin_notch_width(mtm),
+        };
+        // Rounded top corners need a transparent window to show through
+        let transparent = config.bar.screen_corner_radius.pixels(screen_info.has_notch) > 0.0;
+        create_bar_window(
+            cx,
+            screen_x,
+            macos_y,
+            screen_width,
+            bar_height,
+            bar_screen,
+            transparent,
+        );
+        // Popups left detached get their windows back
+        if !screenshot::active() {
+            popup_manager::restore_detached(&ui_state::detached());
+        }
 
         // Create the panel window (hidden by default)
         let panel_height = 500.0; // Max panel height, will resize based on content
@@ -104,7 +151,30 @@ pub fn run() {
         let popup_height = 720.0; // Initial estimate, will resize
         let popup_x = screen_x + screen_width - popup_width - 80.0;
 
-        create_popup_window(cx, popup_x, macos_y, popup_width, popup_height, theme);
+        create_popup_window(
+            cx,
+            popup_x,
+            macos_y,
+            popup_width,
+            popup_height,
+            theme.clone(),
+        );
+
+        // Notch HUD for volume/brightness keys (opt-in)
+        if config.bar.hud.enabled && !screenshot::active() {
+            hud::init(
+                cx,
+                &config.bar.hud,
+                screen_info.frame,
+                bar_height,
+                theme.clone(),
+            );
+        }
+
+        // Hover tooltips of modules
+        if config.bar.tooltip.enabled && !screenshot::active() {
+            tooltip::init(cx, &config.bar.tooltip, theme);
+        }
 
         // Defer AppKit window mutations until the next run-loop turn.
         // Running these while GPUI is mid-update causes re-entrant borrow errors.
@@ -119,7 +189,9 @@ pub fn run() {
             popup_x,
             popup_width,
             popup_height,
+            transparent,
         );
+        screenshot::start(cx);
 
         log::info!("GPUI app initialization complete");
     });
@@ -140,6 +212,7 @@ fn schedule_window_configuration(
     popup_x: f64,
     popup_width: f64,
     popup_height: f64,
+    bar_transparent: bool,
 ) {
     use block2::RcBlock;
     use objc2_foundation::NSRunLoop;
@@ -149,7 +222,7 @@ fn schedule_window_configuration(
             return;
         };
 
-        configure_bar_window(mtm, bar_x, bar_y, bar_width, bar_height);
+        configure_bar_window(mtm, bar_x, bar_y, bar_width, bar_height, bar_transparent);
         configure_panel_window(mtm, panel_x, bar_y, panel_width, panel_height);
         configure_popup_window(mtm, popup_x, bar_y, popup_width, popup_height);
 
@@ -164,6 +237,27 @@ fn schedule_window_configuration(
     }
 }
 
+/// Polls what config profile rules check (the Wi-Fi network and display
+/// count) and wakes the bar when a different profile should be active.
+fn start_profile_monitor() {
+    use crate::config::profile;
+    use crate::system::environment;
+
+    std::thread::spawn(|| loop {
+        let environment = profile::Environment {
+            // Skip the airport call unless a rule reads it
+            ssid: profile::needs_ssid()
+                .then(environment::current_ssid)
+                .flatten(),
+            displays: environment::display_count(),
+        };
+        if profile::set_environment(environment) {
+            request_immediate_refresh();
+        }
+        std::thread::sleep(std::time::Duration::from_secs(5));
+    });
+}
+
 fn popup_warmup_enabled() -> bool {
     std::env::var("SINEW_WARMUP_POPUPS")
         .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"))
@@ -202,6 +296,10 @@ static PANEL_WINDOW_HANDLE: OnceLock<Mutex<Option<gpui::WindowHandle<modules::Po
 static POPUP_WINDOW_HANDLE: OnceLock<Mutex<Option<gpui::WindowHandle<modules::PopupHostView>>>> =
     OnceLock::new();
 
+/// Windows of detached (pinned) popups, by module id.
+static DETACHED_WINDOW_HANDLES: Mutex<Vec<(String, gpui::WindowHandle<modules::PopupHostView>)>> =
+    Mutex::new(Vec::new());
+
 pub fn refresh_popup_windows<C: AppContext>(cx: &mut C) {
     if let Some(lock) = PANEL_WINDOW_HANDLE.get() {
         if let Ok(Some(handle)) = lock.lock().map(|g| *g) {
@@ -219,6 +317,122 @@ pub fn refresh_popup_windows<C: AppContext>(cx: &mut C) {
             });
         }
     }
+    if let Ok(mut handles) = DETACHED_WINDOW_HANDLES.lock() {
+        // Closed windows fail to update and are dropped
+        handles.retain(|(_id, handle)| {
+            handle
+                .update(cx, |_view, window, cx| {
+                    window.refresh();
+                    cx.notify();
+                })
+                .is_ok()
+        });
+    }
+}
+
+/// Opens a window for each pinned popup that another popup pushed aside.
+///
+/// The window takes over the frame the popup had in the shared popup
+/// window, so the pinned popup stays where it was.
+pub fn open_detached_popups(cx: &mut App, theme: &theme::Theme) {
+    for request in popup_manager::take_detach_requests() {
+        let (x, y, width, height) = request.frame;
+        let bounds = Bounds {
+            origin: point(px(x as f32), px(0.0)),
+            size: size(px(width as f32), px(height as f32)),
+        };
+        let theme = theme.clone();
+        let module_id = request.module_id.clone();
+        let popup_type = request.popup_type;
+        let before = window_registry::window_numbers();
+        let window = cx.open_window(
+            WindowOptions {
+                window_bounds: Some(WindowBounds::Windowed(bounds)),
+                titlebar: None,
+                kind: WindowKind::PopUp,
+                is_movable: false,
+                focus: false,
+                show: false,
+                window_background: gpui::WindowBackgroundAppearance::Opaque,
+                ..Default::default()
+            },
+            |_window, cx| {
+                cx.new(|cx| modules::PopupHostView::detached(theme, popup_type, module_id, cx))
+            },
+        );
+        let window = match window {
+            Ok(window) => window,
+            Err(err) => {
+                log::warn!(
+                    "Failed to open detached popup '{}': {}",
+                    request.module_id,
+                    err
+                );
+                popup_manager::close_detached(&request.module_id);
+                continue;
+            }
+        };
+        window_registry::tag_new(&before, WindowRole::Detached(request.module_id.clone()));
+        if let Ok(mut handles) = DETACHED_WINDOW_HANDLES.lock() {
+            handles.push((request.module_id.clone(), window));
+        }
+        schedule_detached_configuration(request.module_id, request.frame);
+    }
+}
+
+/// Places a detached popup window once AppKit has created it.
+fn schedule_detached_configuration(module_id: String, frame: (f64, f64, f64, f64)) {
+    use block2::RcBlock;
+    use objc2_foundation::NSRunLoop;
+
+    let block = RcBlock::new(move || {
+        let Some(mtm) = MainThreadMarker::new() else {
+            return;
+        };
+        configure_detached_window(mtm, &module_id, frame);
+    });
+
+    unsafe {
+        NSRunLoop::mainRunLoop().performBlock(&block);
+    }
+}
+
+fn configure_detached_window(mtm: MainThreadMarker, module_id: &str, frame: (f64, f64, f64, f64)) {
+    use objc2_app_kit::NSWindowStyleMask;
+    use objc2_foundation::NSRect;
+
+    let (x, y, width, height) = frame;
+    let role = WindowRole::Detached(module_id.to_string());
+    let Some(ns_window) = window_registry::window(mtm, &role) else {
+        log::warn!("No window found for detached popup '{}'", module_id);
+        return;
+    };
+
+    unsafe {
+        ns_window.setStyleMask(NSWindowStyleMask::Borderless);
+        let new_frame = NSRect::new(
+            objc2_foundation::NSPoint::new(x, y),
+            objc2_foundation::NSSize::new(width, height),
+        );
+        ns_window.setFrame_display(new_frame, true);
+
+        // Same level as a shown popup
+        let _: () = objc2::msg_send![&ns_window, setLevel: MENU_BAR_WINDOW_LEVEL + 1];
+        ns_window.setHasShadow(false);
+        ns_window.setOpaque(true);
+        ns_window.setAlphaValue(1.0);
+        ns_window.setIgnoresMouseEvents(false);
+        ns_window.orderFrontRegardless();
+    }
+
+    log::info!(
+        "Configured detached popup '{}': frame=({}, {}) {}x{}",
+        module_id,
+        x,
+        y,
+        width,
+        height
+    );
 }
 
 fn create_panel_window(
@@ -242,6 +456,7 @@ fn create_panel_window(
         macos_y - height // Panel appears below bar
     );
 
+    let before = window_registry::window_numbers();
     let window = cx
         .open_window(
             WindowOptions {
@@ -257,6 +472,7 @@ fn create_panel_window(
             |_window, cx| cx.new(|cx| modules::PopupHostView::panel(theme, cx)),
         )
         .expect("Failed to create panel window");
+    window_registry::tag_new(&before, WindowRole::Panel);
 
     {
         let lock = PANEL_WINDOW_HANDLE.get_or_init(|| Mutex::new(None));
@@ -271,56 +487,44 @@ fn create_panel_window(
 
 /// Configure the panel window
 fn configure_panel_window(mtm: MainThreadMarker, x: f64, bar_y: f64, width: f64, height: f64) {
-    use objc2_app_kit::{NSApplication, NSWindowStyleMask};
+    use objc2_app_kit::NSWindowStyleMask;
     use objc2_foundation::NSRect;
 
     // Panel Y is below the bar
     let panel_y = bar_y - height;
 
+    let Some(ns_window) = window_registry::window(mtm, &WindowRole::Panel) else {
+        log::warn!("Panel window not found");
+        return;
+    };
+
     unsafe {
-        let app = NSApplication::sharedApplication(mtm);
-        let windows = app.windows();
-
-        // Find the panel window (larger than bar height)
-        for i in (0..windows.len()).rev() {
-            let ns_window = windows.objectAtIndex(i);
-            let frame = ns_window.frame();
-
-            // Match by size (panel is taller than bar)
-            if frame.size.height > 100.0 {
-                ns_window.setStyleMask(NSWindowStyleMask::Borderless);
-
-                crate::gpui_app::popup_manager::register_window_observers(&ns_window, "panel");
-                crate::gpui_app::popup_manager::set_window_number(
-                    crate::gpui_app::modules::PopupType::Panel,
-                    ns_window.windowNumber() as i64,
-                );
+        ns_window.setStyleMask(NSWindowStyleMask::Borderless);
+        crate::gpui_app::popup_manager::register_window_observers(&ns_window, "panel");
 
-                let new_frame = NSRect::new(
-                    objc2_foundation::NSPoint::new(x, panel_y),
-                    objc2_foundation::NSSize::new(width, height),
-                );
-                ns_window.setFrame_display(new_frame, true);
-
-                // Same level as bar
-                let _: () = objc2::msg_send![&ns_window, setLevel: MENU_BAR_WINDOW_LEVEL];
-
-                // Let GPUI handle the background color - don't set NSWindow background
-                ns_window.setHasShadow(false);
-                ns_window.setOpaque(true);
-                ns_window.setIgnoresMouseEvents(false);
-
-                log::info!(
-                    "Configured panel window: frame=({}, {}) {}x{}",
-                    x,
-                    panel_y,
-                    width,
-                    height
-                );
-                return;
-            }
-        }
+        let new_frame = NSRect::new(
+            objc2_foundation::NSPoint::new(x, panel_y),
+            objc2_foundation::NSSize::new(width, height),
+        );
+        ns_window.setFrame_display(new_frame, true);
+
+        // Same level as bar
+        let level = screenshot::window_level(MENU_BAR_WINDOW_LEVEL);
+        let _: () = objc2::msg_send![&ns_window, setLevel: level];
+
+        // Let GPUI handle the background color - don't set NSWindow background
+        ns_window.setHasShadow(false);
+        ns_window.setOpaque(true);
+        ns_window.setIgnoresMouseEvents(false);
     }
+
+    log::info!(
+        "Configured panel window: frame=({}, {}) {}x{}",
+        x,
+        panel_y,
+        width,
+        height
+    );
 }
 
 fn create_popup_window(
@@ -344,6 +548,7 @@ fn create_popup_window(
         macos_y - height
     );
 
+    let before = window_registry::window_numbers();
     let window = cx
         .open_window(
             WindowOptions {
@@ -359,6 +564,7 @@ fn create_popup_window(
             |_window, cx| cx.new(|cx| modules::PopupHostView::popup(theme, cx)),
         )
         .expect("Failed to create popup window");
+    window_registry::tag_new(&before, WindowRole::Popup);
 
     {
         let lock = POPUP_WINDOW_HANDLE.get_or_init(|| Mutex::new(None));
@@ -372,69 +578,53 @@ fn create_popup_window(
 }
 
 fn configure_popup_window(mtm: MainThreadMarker, x: f64, bar_y: f64, width: f64, height: f64) {
-    use objc2_app_kit::{NSApplication, NSWindowStyleMask};
+    use objc2_app_kit::NSWindowStyleMask;
     use objc2_foundation::NSRect;
 
     let popup_y = bar_y - height;
 
+    let Some(ns_window) = window_registry::window(mtm, &WindowRole::Popup) else {
+        log::warn!("Popup window not found");
+        return;
+    };
+
     unsafe {
-        let app = NSApplication::sharedApplication(mtm);
-        let windows = app.windows();
+        ns_window.setStyleMask(NSWindowStyleMask::Borderless);
+        crate::gpui_app::popup_manager::register_window_observers(&ns_window, "popup");
 
-        log::debug!(
-            "configure_popup_window: checking {} windows for popup",
-            windows.len()
+        let new_frame = NSRect::new(
+            objc2_foundation::NSPoint::new(x, popup_y),
+            objc2_foundation::NSSize::new(width, height),
         );
+        ns_window.setFrame_display(new_frame, true);
 
-        // Find the popup window by its width (only window with width < 500)
-        for i in (0..windows.len()).rev() {
-            let ns_window = windows.objectAtIndex(i);
-            let frame = ns_window.frame();
-
-            log::trace!(
-                "Window {}: size {}x{}",
-                i,
-                frame.size.width,
-                frame.size.height
-            );
-
-            // Match popup by width (only popup window with width < 500)
-            if frame.size.width > 200.0 && frame.size.width < 500.0 && frame.size.height > 200.0 {
-                ns_window.setStyleMask(NSWindowStyleMask::Borderless);
+        let level = screenshot::window_level(MENU_BAR_WINDOW_LEVEL);
+        let _: () = objc2::msg_send![&ns_window, setLevel: level];
 
-                crate::gpui_app::popup_manager::register_window_observers(&ns_window, "popup");
-                crate::gpui_app::popup_manager::set_window_number(
-                    crate::gpui_app::modules::PopupType::Popup,
-                    ns_window.windowNumber() as i64,
-                );
-
-                let new_frame = NSRect::new(
-                    objc2_foundation::NSPoint::new(x, popup_y),
-                    objc2_foundation::NSSize::new(width, height),
-                );
-                ns_window.setFrame_display(new_frame, true);
-
-                let _: () = objc2::msg_send![&ns_window, setLevel: MENU_BAR_WINDOW_LEVEL];
-
-                ns_window.setHasShadow(false); // No shadow - popup extends from bar
-                ns_window.setOpaque(true);
-                // Background color is set by GPUI via the PopupHostView theme.
-                ns_window.setIgnoresMouseEvents(false);
-
-                log::info!(
-                    "Configured popup window: frame=({}, {}) {}x{}",
-                    x,
-                    popup_y,
-                    width,
-                    height
-                );
-                return;
-            }
-        }
+        ns_window.setHasShadow(false); // No shadow - popup extends from bar
+        ns_window.setOpaque(true);
+        // Background color is set by GPUI via the PopupHostView theme.
+        ns_window.setIgnoresMouseEvents(false);
     }
+
+    log::info!(
+        "Configured popup window: frame=({}, {}) {}x{}",
+        x,
+        popup_y,
+        width,
+        height
+    );
 }
 
-fn create_bar_window(cx: &mut App, x: f64, macos_y: f64, width: f64, height: f64) {
+fn create_bar_window(
+    cx: &mut App,
+    x: f64,
+    macos_y: f64,
+    width: f64,
+    height: f64,
+    screen: BarScreen,
+    transparent: bool,
+) {
     let bounds = Bounds {
         origin: point(px(x as f32), px(0.0)),
         size: size(px(width as f32), px(height as f32)),
@@ -448,6 +638,7 @@ fn create_bar_window(cx: &mut App, x: f64, macos_y: f64, width: f64, height: f64
         macos_y
     );
 
+    let before = window_registry::window_numbers();
     let _window = cx
         .open_window(
             WindowOptions {
@@ -457,54 +648,80 @@ fn create_bar_window(cx: &mut App, x: f64, macos_y: f64, width: f64, height: f64
                 is_movable: false,
                 focus: false,
                 show: true,
-                window_background: gpui::WindowBackgroundAppearance::Opaque,
+                window_background: if transparent {
+                    gpui::WindowBackgroundAppearance::Transparent
+                } else {
+                    gpui::WindowBackgroundAppearance::Opaque
+                },
                 ..Default::default()
             },
-            |_window, cx| cx.new(|_cx| BarView::new()),
+            |_window, cx| cx.new(|_cx| BarView::new(screen)),
         )
         .expect("Failed to create bar window");
+    window_registry::tag_new(&before, WindowRole::Bar);
 }
 
 /// Configure the NSWindow for menu bar appearance
-fn configure_bar_window(mtm: MainThreadMarker, x: f64, macos_y: f64, width: f64, height: f64) {
-    use objc2_app_kit::{NSApplication, NSWindowStyleMask};
+fn configure_bar_window(
+    mtm: MainThreadMarker,
+    x: f64,
+    macos_y: f64,
+    width: f64,
+    height: f64,
+    transparent: bool,
+) {
+    use objc2_app_kit::NSWindowStyleMask;
     use objc2_foundation::NSRect;
 
-    unsafe {
-        let app = NSApplication::sharedApplication(mtm);
-        let windows = app.windows();
+    let Some(ns_window) = window_registry::window(mtm, &WindowRole::Bar) else {
+        log::warn!("Bar window not found");
+        return;
+    };
 
-        // Find our window (most recently created small window)
-        for i in (0..windows.len()).rev() {
-            let ns_window = windows.objectAtIndex(i);
-            let frame = ns_window.frame();
+    unsafe {
+        ns_window.setStyleMask(NSWindowStyleMask::Borderless);
 
-            // Match by approximate size (height ~32)
-            if frame.size.height <= 40.0 && frame.size.height > 20.0 {
-                ns_window.setStyleMask(NSWindowStyleMask::Borderless);
+        let new_frame = NSRect::new(
+            objc2_foundation::NSPoint::new(x, macos_y),
+            objc2_foundation::NSSize::new(width, height),
+        );
+        ns_window.setFrame_display(new_frame, true);
 
-                let new_frame = NSRect::new(
-                    objc2_foundation::NSPoint::new(x, macos_y),
-                    objc2_foundation::NSSize::new(width, height),
-                );
-                ns_window.setFrame_display(new_frame, true);
+        let level = screenshot::window_level(MENU_BAR_WINDOW_LEVEL);
+        let _: () = objc2::msg_send![&ns_window, setLevel: level];
 
-                let _: () = objc2::msg_send![&ns_window, setLevel: MENU_BAR_WINDOW_LEVEL];
+        ns_window.setHasShadow(false);
+        // A transparent bar lets the desktop show past its rounded corners
+        ns_window.setOpaque(!transparent);
+        ns_window.setIgnoresMouseEvents(false);
+        ns_window.setAcceptsMouseMovedEvents(true);
+    }
 
-                ns_window.setHasShadow(false);
-                ns_window.setOpaque(true);
-                ns_window.setIgnoresMouseEvents(false);
-                ns_window.setAcceptsMouseMovedEvents(true);
+    log::info!(
+        "Configured bar window: frame=({}, {}) {}x{}",
+        x,
+        macos_y,
+        width,
+        height
+    );
+}
 
-                log::info!(
-                    "Configured bar window: frame=({}, {}) {}x{}",
-                    x,
-                    macos_y,
-                    width,
-                    height
-                );
-                return;
-            }
-        }
+/// Shows or hides the bar window (`None` flips it). Hiding the bar also
+/// closes any open popup. Must be called on the main thread.
+pub fn set_bar_visible(visible: Option<bool>) {
+    let Some(mtm) = MainThreadMarker::new() else {
+        log::error!("set_bar_visible: not on main thread");
+        return;
+    };
+    let Some(ns_window) = window_registry::window(mtm, &WindowRole::Bar) else {
+        return;
+    };
+    let visible = visible.unwrap_or(!ns_window.isVisible());
+    if visible {
+        ns_window.orderFrontRegardless();
+    } else {
+        popup_manager::hide_popup();
+        ns_window.orderOut(None);
     }
+    log::info!("Bar {}", if visible { "shown" } else { "hidden" });
 }
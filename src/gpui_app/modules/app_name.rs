@@ -4,6 +4,8 @@
 //! workspace observer, so `update()` runs on the main thread where
 //! `MainThreadMarker` is available and NSWorkspace can be queried directly.
 
+use std::time::Duration;
+
 use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
 
 use super::{truncate_text, GpuiModule};
@@ -76,4 +78,12 @@ impl GpuiModule for AppNameModule {
             false
         }
     }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn display_text(&self) -> Option<String> {
+        (!self.name.is_empty()).then(|| self.name.clone())
+    }
 }
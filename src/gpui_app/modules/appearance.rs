@@ -0,0 +1,155 @@
+//! Appearance module for switching between light and dark mode.
+//!
+//! Shows a sun or moon for the current system appearance and flips it on
+//! click through System Events' appearance preferences, the scriptable
+//! equivalent of the toggle in System Settings. The first click asks for
+//! permission to control System Events. Dark mode counts as the module's
+//! active state, so the `active_*` style fields apply while it's on.
+//!
+//! The appearance is read from the `AppleInterfaceStyle` default, which
+//! also follows automatic switching at sunset.
+//!
+//! ```toml
+//! { type = "appearance", active_background = "#313244" }
+//! ```
+
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+
+use super::{mark_dirty, GpuiModule};
+use crate::gpui_app::primitives::icons::appearance as appearance_icons;
+use crate::gpui_app::theme::Theme;
+
+/// Parses `defaults read -g AppleInterfaceStyle`. The key only exists in
+/// dark mode, so anything but `Dark` is light.
+pub fn is_dark(output: &str) -> bool {
+    output.trim() == "Dark"
+}
+
+/// AppleScript that switches the system appearance.
+fn set_dark_script(dark: bool) -> String {
+    format!(
+        "tell application \"System Events\" to tell appearance preferences to set dark mode to {}",
+        dark
+    )
+}
+
+fn read_dark() -> bool {
+    Command::new("defaults")
+        .args(["read", "-g", "AppleInterfaceStyle"])
+        .output()
+        .ok()
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|out| is_dark(&out))
+        .unwrap_or(false)
+}
+
+/// Appearance module that shows and toggles dark mode.
+pub struct AppearanceModule {
+    id: String,
+    dark: Arc<AtomicBool>,
+    dirty: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+}
+
+impl AppearanceModule {
+    /// Creates a new appearance module and starts watching for changes.
+    ///
+    /// @param id - Unique module identifier
+    pub fn new(id: &str) -> Self {
+        let dark = Arc::new(AtomicBool::new(read_dark()));
+        let dirty = Arc::new(AtomicBool::new(true));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let dark_handle = Arc::clone(&dark);
+        let dirty_handle = Arc::clone(&dirty);
+        let stop_handle = Arc::clone(&stop);
+        std::thread::spawn(move || {
+            while !stop_handle.load(Ordering::Relaxed) {
+                std::thread::sleep(Duration::from_secs(2));
+                let next = read_dark();
+                if dark_handle.swap(next, Ordering::Relaxed) != next {
+                    mark_dirty(&dirty_handle);
+                }
+            }
+        });
+
+        Self {
+            id: id.to_string(),
+            dark,
+            dirty,
+            stop,
+        }
+    }
+}
+
+impl GpuiModule for AppearanceModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let icon = if self.dark.load(Ordering::Relaxed) {
+            appearance_icons::DARK
+        } else {
+            appearance_icons::LIGHT
+        };
+        div()
+            .flex()
+            .items_center()
+            .text_color(theme.foreground)
+            .text_size(px(theme.font_size))
+            .child(SharedString::from(icon))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        self.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn toggle_state(&self) -> Option<bool> {
+        Some(self.dark.load(Ordering::Relaxed))
+    }
+
+    fn on_toggle(&mut self, active: bool) {
+        // Show the new mode right away; the watcher corrects it if the
+        // script is denied
+        self.dark.store(active, Ordering::Relaxed);
+        mark_dirty(&self.dirty);
+        std::thread::spawn(move || {
+            let status = Command::new("osascript")
+                .args(["-e", &set_dark_script(active)])
+                .status();
+            if !status.map(|s| s.success()).unwrap_or(false) {
+                log::warn!("appearance: failed to set dark mode to {}", active);
+            }
+        });
+    }
+}
+
+impl Drop for AppearanceModule {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_dark_mode_from_defaults() {
+        assert!(is_dark("Dark\n"));
+        // The key is missing in light mode; defaults prints nothing to stdout
+        assert!(!is_dark(""));
+        assert!(set_dark_script(true).ends_with("set dark mode to true"));
+    }
+}
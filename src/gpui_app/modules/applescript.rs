@@ -0,0 +1,290 @@
+//! AppleScript module showing the result of a script run in-process.
+//!
+//! Compiles an AppleScript (or JavaScript for Automation) snippet once with
+//! OSAKit and runs it every `interval` seconds, showing its result as text.
+//! No `osascript` process is started, which makes it far cheaper than a
+//! `script` module for asking Music, Mail or Finder something.
+//!
+//! `format` is a template (see `template.rs`) with `{output}` and `{icon}`.
+//! The hover tooltip shows the result with the time of the last run, or
+//! the error if the script failed.
+//!
+//! ```toml
+//! { type = "applescript", interval = 5, script = 'tell application "Music" to if running then name of current track' }
+//! { type = "applescript", language = "javascript", file = "~/scripts/unread.js" }
+//! ```
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+
+use super::template::{Template, Value};
+use super::{mark_dirty, sleep_unless_stopped, GpuiModule};
+use crate::gpui_app::theme::Theme;
+use crate::system::osa::{Language, Script};
+
+/// Where the script's source comes from.
+#[derive(Debug, Clone)]
+pub enum Source {
+    /// Source given in the config
+    Inline(String),
+    /// File read once when the module starts
+    File(PathBuf),
+}
+
+impl Source {
+    fn read(&self) -> Result<String, String> {
+        match self {
+            Self::Inline(source) => Ok(source.clone()),
+            Self::File(path) => std::fs::read_to_string(path)
+                .map_err(|e| format!("Can't read {}: {}", path.display(), e)),
+        }
+    }
+}
+
+/// Result of the last run.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Output {
+    /// Result of the last successful run
+    text: String,
+    /// Set when the last run (or compiling) failed
+    error: Option<String>,
+    /// When the script last ran
+    ran_at: Option<DateTime<Local>>,
+}
+
+impl Output {
+    /// Tooltip text: the error, or the result and the time of the last run.
+    /// None before the first run.
+    fn tooltip_text(&self) -> Option<String> {
+        if let Some(error) = &self.error {
+            return Some(error.clone());
+        }
+        let ran_at = self.ran_at?;
+        let body = if self.text.is_empty() {
+            "(no output)"
+        } else {
+            &self.text
+        };
+        Some(format!("{}\nLast run {}", body, ran_at.format("%H:%M:%S")))
+    }
+}
+
+/// Bar text: `format`, or the icon followed by the result.
+fn display_text(format: Option<&Template>, icon: Option<&str>, text: &str) -> String {
+    if let Some(format) = format {
+        return format.render(&[
+            ("output", Some(Value::from(text))),
+            ("icon", icon.map(Value::from)),
+        ]);
+    }
+    match icon {
+        Some(icon) if text.is_empty() => icon.to_string(),
+        Some(icon) => format!("{} {}", icon, text),
+        None => text.to_string(),
+    }
+}
+
+/// Compiles the script, then runs it until `stop` is set.
+fn run_loop(
+    id: &str,
+    source: &Source,
+    language: Language,
+    interval: Duration,
+    output: &Mutex<Output>,
+    dirty: &AtomicBool,
+    stop: &AtomicBool,
+) {
+    let script = source
+        .read()
+        .and_then(|source| Script::compile(&source, language).map_err(|e| e.to_string()));
+    let script = match script {
+        Ok(script) => script,
+        Err(err) => {
+            log::warn!("{}: {}", id, err);
+            if let Ok(mut guard) = output.lock() {
+                guard.error = Some(err);
+            }
+            mark_dirty(dirty);
+            return;
+        }
+    };
+
+    while !stop.load(Ordering::Relaxed) {
+        let result = script.run();
+        if let Ok(mut guard) = output.lock() {
+            let mut next = guard.clone();
+            match result {
+                Ok(text) => {
+                    next.text = text.trim().to_string();
+                    next.error = None;
+                }
+                Err(err) => {
+                    let err = err.to_string();
+                    // Only log when the error changes, not on every run
+                    if guard.error.as_ref() != Some(&err) {
+                        log::warn!("{}: {}", id, err);
+                    }
+                    next.error = Some(err);
+                }
+            }
+            // The tooltip shows the time of the last run
+            next.ran_at = Some(Local::now());
+            *guard = next;
+        }
+        mark_dirty(dirty);
+        sleep_unless_stopped(interval, stop);
+    }
+}
+
+/// Module showing the result of an AppleScript or JXA snippet.
+pub struct AppleScriptModule {
+    id: String,
+    icon: Option<String>,
+    format: Option<Template>,
+    output: Arc<Mutex<Output>>,
+    dirty: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+}
+
+impl AppleScriptModule {
+    /// Creates a new AppleScript module and starts running the script.
+    ///
+    /// @param id - Unique module identifier
+    /// @param source - Inline source or script file
+    /// @param language - AppleScript or JavaScript
+    /// @param interval - Time between runs
+    /// @param icon - Icon shown before the result
+    /// @param format - Text template (None = icon and result)
+    pub fn new(
+        id: &str,
+        source: Source,
+        language: Language,
+        interval: Duration,
+        icon: Option<&str>,
+        format: Option<&str>,
+    ) -> Self {
+        let output = Arc::new(Mutex::new(Output::default()));
+        let dirty = Arc::new(AtomicBool::new(true));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let worker_id = id.to_string();
+        let output_handle = Arc::clone(&output);
+        let dirty_handle = Arc::clone(&dirty);
+        let stop_handle = Arc::clone(&stop);
+        std::thread::spawn(move || {
+            run_loop(
+                &worker_id,
+                &source,
+                language,
+                interval,
+                &output_handle,
+                &dirty_handle,
+                &stop_handle,
+            );
+        });
+
+        Self {
+            id: id.to_string(),
+            icon: icon.map(String::from),
+            format: format.map(Template::parse_or_literal),
+            output,
+            dirty,
+            stop,
+        }
+    }
+
+    fn output(&self) -> Output {
+        self.output
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl GpuiModule for AppleScriptModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let output = self.output();
+        let text = display_text(self.format.as_ref(), self.icon.as_deref(), &output.text);
+        let color = if output.error.is_some() {
+            theme.warning
+        } else {
+            theme.foreground
+        };
+        div()
+            .flex()
+            .items_center()
+            .text_color(color)
+            .text_size(px(theme.font_size))
+            .child(SharedString::from(text))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        self.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn copy_text(&self) -> Option<String> {
+        let output = self.output();
+        (!output.text.is_empty()).then_some(output.text)
+    }
+
+    fn display_text(&self) -> Option<String> {
+        let output = self.output();
+        let text = display_text(self.format.as_ref(), self.icon.as_deref(), &output.text);
+        (!text.is_empty()).then_some(text)
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        self.output().tooltip_text()
+    }
+}
+
+impl Drop for AppleScriptModule {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_text_puts_icon_before_result() {
+        assert_eq!(display_text(None, Some("♪"), "Song"), "♪ Song");
+        assert_eq!(display_text(None, Some("♪"), ""), "♪");
+        assert_eq!(display_text(None, None, "Song"), "Song");
+        let format = Template::parse("{icon}[ {output}]").unwrap();
+        assert_eq!(display_text(Some(&format), Some("♪"), ""), "♪");
+        assert_eq!(display_text(Some(&format), Some("♪"), "Song"), "♪ Song");
+    }
+
+    #[test]
+    fn tooltip_shows_error_or_last_run() {
+        use chrono::TimeZone;
+
+        let mut output = Output::default();
+        assert_eq!(output.tooltip_text(), None);
+        output.text = "Song".to_string();
+        output.ran_at = Some(Local.with_ymd_and_hms(2024, 5, 1, 14, 3, 9).unwrap());
+        assert_eq!(
+            output.tooltip_text().as_deref(),
+            Some("Song\nLast run 14:03:09")
+        );
+        output.error = Some("Music got an error".to_string());
+        assert_eq!(output.tooltip_text().as_deref(), Some("Music got an error"));
+    }
+}
@@ -0,0 +1,462 @@
+//! Audio device switcher.
+//!
+//! Shows the default output device. Clicking it lists every output and
+//! input device and makes one the default with a click; Bluetooth headsets
+//! that report their battery show the level next to their name (AirPods show
+//! each bud and the case).
+//!
+//! ```toml
+//! { type = "audio_devices", format = "{icon} {output}[ {battery}]" }
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+
+use super::template::{Template, Value};
+use super::{mark_dirty, sleep_unless_stopped, GpuiModule, PopupAction, PopupEvent, PopupSpec};
+use crate::gpui_app::popup_manager::notify_popup_needs_render;
+use crate::gpui_app::primitives::{icons, Button};
+use crate::gpui_app::theme::{ButtonVariant, Theme};
+use crate::system::audio_devices::{self, AudioDevice, AudioDevices, Direction, HeadsetBattery};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// How often `{battery}` in the bar is refreshed
+const BATTERY_INTERVAL: Duration = Duration::from_secs(60);
+const DEFAULT_FORMAT: &str = "{icon} {output}";
+const POPUP_WIDTH: f64 = 320.0;
+const POPUP_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 24.0;
+const ROW_HEIGHT: f32 = 28.0;
+
+/// Icon for a device.
+fn device_icon(direction: Direction, device: &AudioDevice) -> &'static str {
+    match direction {
+        Direction::Input => icons::privacy::MICROPHONE,
+        Direction::Output if device.bluetooth => icons::audio::HEADPHONES,
+        Direction::Output => icons::audio::SPEAKER,
+    }
+}
+
+/// Battery of the device named `name`, if it reports one.
+fn battery_for<'a>(
+    name: &str,
+    batteries: &'a [(String, HeadsetBattery)],
+) -> Option<&'a HeadsetBattery> {
+    batteries
+        .iter()
+        .find(|(device, _)| device == name)
+        .map(|(_, battery)| battery)
+}
+
+/// Bar text: `format` filled with the default devices.
+fn bar_text(
+    format: &Template,
+    devices: &AudioDevices,
+    batteries: &[(String, HeadsetBattery)],
+) -> String {
+    let output = devices.default_device(Direction::Output);
+    let input = devices.default_device(Direction::Input);
+    let icon = output.map_or(icons::audio::SPEAKER, |device| {
+        device_icon(Direction::Output, device)
+    });
+    format.render(&[
+        ("icon", Some(Value::from(icon))),
+        (
+            "output",
+            output.map(|device| Value::from(device.name.as_str())),
+        ),
+        (
+            "input",
+            input.map(|device| Value::from(device.name.as_str())),
+        ),
+        (
+            "battery",
+            output
+                .and_then(|device| battery_for(&device.name, batteries))
+                .map(|battery| Value::from(battery.describe())),
+        ),
+    ])
+}
+
+/// Value passed back in `PopupAction::Select` to make a device the default.
+fn select_value(direction: Direction, id: u32) -> String {
+    match direction {
+        Direction::Output => format!("output:{}", id),
+        Direction::Input => format!("input:{}", id),
+    }
+}
+
+fn parse_select(value: &str) -> Option<(Direction, u32)> {
+    let (direction, id) = value.split_once(':')?;
+    let direction = match direction {
+        "output" => Direction::Output,
+        "input" => Direction::Input,
+        _ => return None,
+    };
+    Some((direction, id.parse().ok()?))
+}
+
+#[derive(Debug, Clone, Default)]
+struct DevicesState {
+    devices: AudioDevices,
+    /// Bluetooth battery levels by device name
+    batteries: Vec<(String, HeadsetBattery)>,
+    /// Why the last switch failed
+    error: Option<String>,
+}
+
+/// State shared between the bar and popup copies of the module.
+struct Shared {
+    state: Mutex<DevicesState>,
+    dirty: AtomicBool,
+    stop: AtomicBool,
+}
+
+impl Shared {
+    /// Re-reads the devices; returns true if they changed.
+    fn refresh(&self) -> bool {
+        let devices = audio_devices::devices();
+        let Ok(mut guard) = self.state.lock() else {
+            return false;
+        };
+        if guard.devices == devices {
+            return false;
+        }
+        guard.devices = devices;
+        true
+    }
+
+    /// Re-reads the Bluetooth battery levels; returns true if they changed.
+    fn refresh_batteries(&self) -> bool {
+        let batteries = audio_devices::bluetooth_batteries();
+        let Ok(mut guard) = self.state.lock() else {
+            return false;
+        };
+        if guard.batteries == batteries {
+            return false;
+        }
+        guard.batteries = batteries;
+        true
+    }
+}
+
+/// Lists audio devices and switches the defaults.
+pub struct AudioDevicesModule {
+    id: String,
+    format: Template,
+    shared: Arc<Shared>,
+}
+
+impl AudioDevicesModule {
+    pub fn new(id: &str, format: Option<&str>) -> Self {
+        let format = Template::parse_or_literal(format.unwrap_or(DEFAULT_FORMAT));
+        let shared = Arc::new(Shared {
+            state: Mutex::new(DevicesState::default()),
+            dirty: AtomicBool::new(true),
+            stop: AtomicBool::new(false),
+        });
+
+        let thread_shared = Arc::clone(&shared);
+        let thread_id = id.to_string();
+        let show_battery = format.uses("battery");
+        std::thread::spawn(move || {
+            let mut batteries_read: Option<Instant> = None;
+            while !thread_shared.stop.load(Ordering::Relaxed) {
+                let mut changed = thread_shared.refresh();
+                if show_battery && batteries_read.is_none_or(|at| at.elapsed() >= BATTERY_INTERVAL)
+                {
+                    changed |= thread_shared.refresh_batteries();
+                    batteries_read = Some(Instant::now());
+                }
+                if changed {
+                    mark_dirty(&thread_shared.dirty);
+                    notify_popup_needs_render(&thread_id);
+                }
+                sleep_unless_stopped(POLL_INTERVAL, &thread_shared.stop);
+            }
+        });
+
+        Self {
+            id: id.to_string(),
+            format,
+            shared,
+        }
+    }
+
+    /// Returns a copy that shares state, for the popup registry.
+    pub fn popup_handle(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            format: self.format.clone(),
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    fn state(&self) -> DevicesState {
+        self.shared
+            .state
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+
+    fn render_section(
+        &self,
+        theme: &Theme,
+        title: &'static str,
+        direction: Direction,
+        devices: &[AudioDevice],
+        batteries: &[(String, HeadsetBattery)],
+    ) -> gpui::Div {
+        let mut section = div().flex().flex_col().child(
+            div()
+                .h(px(HEADER_HEIGHT))
+                .flex()
+                .items_center()
+                .font_weight(gpui::FontWeight::SEMIBOLD)
+                .child(title),
+        );
+        if devices.is_empty() {
+            section = section.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .text_color(theme.foreground_muted)
+                    .child("No devices"),
+            );
+        }
+        for device in devices {
+            let mut row = div()
+                .h(px(ROW_HEIGHT))
+                .flex()
+                .items_center()
+                .gap(px(8.0))
+                .child(
+                    div()
+                        .text_color(theme.foreground_muted)
+                        .child(device_icon(direction, device)),
+                )
+                .child(
+                    div()
+                        .flex_1()
+                        .overflow_hidden()
+                        .child(SharedString::from(device.name.clone())),
+                );
+            if let Some(battery) = battery_for(&device.name, batteries) {
+                row = row.child(
+                    div()
+                        .text_size(px(theme.font_size - 2.0))
+                        .text_color(theme.foreground_muted)
+                        .child(SharedString::from(battery.describe())),
+                );
+            }
+            if device.default {
+                row = row.child(
+                    div()
+                        .text_size(px(theme.font_size - 2.0))
+                        .text_color(theme.accent)
+                        .child("Default"),
+                );
+            } else {
+                let value = select_value(direction, device.id);
+                row = row.child(
+                    Button::new(SharedString::from(format!("{}-{}", self.id, value)))
+                        .label("Use")
+                        .variant(ButtonVariant::Outline)
+                        .text_size(px(theme.font_size - 2.0))
+                        .on_popup_action(self.id.clone(), PopupAction::Select { value })
+                        .render(theme),
+                );
+            }
+            section = section.child(row);
+        }
+        section
+    }
+}
+
+impl GpuiModule for AudioDevicesModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let state = self.state();
+        let text = bar_text(&self.format, &state.devices, &state.batteries);
+        div()
+            .flex()
+            .items_center()
+            .text_color(theme.foreground)
+            .text_size(px(theme.font_size))
+            .child(SharedString::from(text))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        self.shared.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn display_text(&self) -> Option<String> {
+        let state = self.state();
+        Some(bar_text(&self.format, &state.devices, &state.batteries))
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let state = self.state();
+        let rows = state.devices.outputs.len().max(1) + state.devices.inputs.len().max(1);
+        let error = if state.error.is_some() {
+            ROW_HEIGHT
+        } else {
+            0.0
+        };
+        let height = POPUP_PADDING * 2.0 + HEADER_HEIGHT * 2.0 + ROW_HEIGHT * rows as f32 + error;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let state = self.state();
+        let mut popup = div()
+            .flex()
+            .flex_col()
+            .p(px(POPUP_PADDING))
+            .text_size(px(theme.font_size))
+            .text_color(theme.foreground)
+            .child(self.render_section(
+                theme,
+                "Output",
+                Direction::Output,
+                &state.devices.outputs,
+                &state.batteries,
+            ))
+            .child(self.render_section(
+                theme,
+                "Input",
+                Direction::Input,
+                &state.devices.inputs,
+                &state.batteries,
+            ));
+        if let Some(error) = state.error {
+            popup = popup.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .text_size(px(theme.font_size - 1.0))
+                    .text_color(theme.destructive)
+                    .child(SharedString::from(error)),
+            );
+        }
+        Some(popup.into_any_element())
+    }
+
+    fn on_popup_action(&mut self, action: PopupAction) {
+        let PopupAction::Select { value } = action else {
+            return;
+        };
+        let Some((direction, device_id)) = parse_select(&value) else {
+            return;
+        };
+        let error = audio_devices::set_default(direction, device_id).err();
+        if let Some(error) = &error {
+            log::warn!(
+                "audio_devices: can't switch to device {}: {}",
+                device_id,
+                error
+            );
+        }
+        if let Ok(mut guard) = self.shared.state.lock() {
+            guard.error = error;
+        }
+        self.shared.refresh();
+        mark_dirty(&self.shared.dirty);
+        notify_popup_needs_render(&self.id);
+    }
+
+    fn on_popup_event(&mut self, event: PopupEvent) {
+        if matches!(event, PopupEvent::Opened) {
+            if let Ok(mut guard) = self.shared.state.lock() {
+                guard.error = None;
+            }
+            let shared = Arc::clone(&self.shared);
+            let id = self.id.clone();
+            std::thread::spawn(move || {
+                if shared.refresh_batteries() {
+                    mark_dirty(&shared.dirty);
+                    notify_popup_needs_render(&id);
+                }
+            });
+        }
+    }
+}
+
+impl Drop for AudioDevicesModule {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(id: u32, name: &str, bluetooth: bool, default: bool) -> AudioDevice {
+        AudioDevice {
+            id,
+            name: name.to_string(),
+            bluetooth,
+            default,
+        }
+    }
+
+    #[test]
+    fn shows_the_default_devices_and_headset_battery() {
+        let devices = AudioDevices {
+            outputs: vec![
+                device(40, "AirPods Pro", true, true),
+                device(52, "MacBook Pro Speakers", false, false),
+            ],
+            inputs: vec![device(60, "MacBook Pro Microphone", false, true)],
+        };
+        let batteries = vec![(
+            "AirPods Pro".to_string(),
+            HeadsetBattery {
+                left: Some(80),
+                right: Some(75),
+                ..Default::default()
+            },
+        )];
+        let format = Template::parse_or_literal("{output} / {input}[ ({battery})]");
+        assert_eq!(
+            bar_text(&format, &devices, &batteries),
+            "AirPods Pro / MacBook Pro Microphone (L 80% R 75%)"
+        );
+        assert_eq!(
+            bar_text(&format, &devices, &[]),
+            "AirPods Pro / MacBook Pro Microphone"
+        );
+        let default = Template::parse_or_literal(DEFAULT_FORMAT);
+        assert_eq!(
+            bar_text(&default, &devices, &[]),
+            format!("{} AirPods Pro", icons::audio::HEADPHONES)
+        );
+    }
+
+    #[test]
+    fn round_trips_device_selections() {
+        for direction in [Direction::Output, Direction::Input] {
+            assert_eq!(
+                parse_select(&select_value(direction, 73)),
+                Some((direction, 73))
+            );
+        }
+        assert_eq!(parse_select("output:x"), None);
+        assert_eq!(parse_select("speaker:1"), None);
+    }
+}
@@ -1,96 +1,644 @@
-//! Battery module for displaying battery status.
+//! Battery module for displaying battery status from a `BatteryProvider`
+//! (the shared metrics service in the bar).
+//!
+//! While plugged in but not charging below 100% (held at a charge limit or
+//! paused by optimized charging) the icon switches to a plug. `alerts` run a
+//! command and/or show a toast when the level crosses a threshold, once per
+//! crossing: an alert re-arms only after the level moves `hysteresis`
+//! points back (default 3).
+//!
+//! `format` is a template (see `template.rs`) with `{value}` (alias
+//! `{level}`), `{icon}`, `{time_left}` ("H:MM", empty while macOS has no
+//! estimate), `{time_to_empty}` and `{time_to_full}` (the same, only while
+//! discharging or charging), `{cycle_count}` and `{state}` (charging,
+//! plugged or discharging). Using `{icon}` replaces the separate icon or
+//! ring. `format_charging`, `format_full` (plugged in, not charging) and
+//! `format_low` (discharging at 20% or less) replace `format` in that state.
+//!
+//! The hover tooltip shows the charge in mAh, the battery's health and its
+//! cycle count where IOKit reports them.
+//!
+//! `sinew-msg set <id> level=15 charging=false plugged=false` replaces the
+//! readings until the module is recreated, e.g. to try out alerts.
+//!
+//! ```toml
+//! [[modules.right.right]]
+//! type = "battery"
+//! format = "{value}%[ ({time_left})]"
+//!
+//! [[modules.right.right.alerts]]
+//! below = 20
+//! toast = "Battery at {level}%"
+//!
+//! [[modules.right.right.alerts]]
+//! below = 10
+//! command = "pmset -a lowpowermode 1"
+//!
+//! [[modules.right.right.alerts]]
+//! on = "full"
+//! toast = "Charged to {level}%, unplug to save the battery"
+//! ```
 
 use std::process::Command;
-use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
 
-use super::GpuiModule;
+use super::template::{Template, Value};
+use super::{mark_dirty, GpuiModule, RuleColors};
 use crate::gpui_app::primitives::icons::battery as battery_icons;
+use crate::gpui_app::primitives::ProgressRing;
 use crate::gpui_app::theme::Theme;
+use crate::gpui_app::toast::{self, ToastStyle};
+use crate::system::metrics::{BatteryCapacity, BatteryStatus};
+use crate::system::providers::BatteryProvider;
+
+/// How the battery level is drawn next to the percentage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BatteryDisplay {
+    /// Nerd Font battery glyph
+    #[default]
+    Icon,
+    /// Progress ring filled to the charge level
+    Ring,
+}
+
+impl BatteryDisplay {
+    /// Parses a display mode name, defaulting to the icon.
+    pub fn parse(value: Option<&str>) -> Self {
+        match value {
+            Some("ring") => Self::Ring,
+            _ => Self::Icon,
+        }
+    }
+}
+
+/// Level at or below which a discharging battery uses `format_low`.
+const LOW_LEVEL: u8 = 20;
+
+/// Power states with a format of their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FormatState {
+    Charging,
+    /// Plugged in but not charging: full or held at a limit
+    Full,
+    /// Discharging at or below `LOW_LEVEL`
+    Low,
+    Other,
+}
+
+impl FormatState {
+    fn new(level: u8, charging: bool, plugged: bool) -> Self {
+        if charging {
+            Self::Charging
+        } else if plugged {
+            Self::Full
+        } else if level > 0 && level <= LOW_LEVEL {
+            Self::Low
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// `format_charging`, `format_full` and `format_low`.
+#[derive(Default)]
+pub struct StateFormats {
+    charging: Option<Template>,
+    full: Option<Template>,
+    low: Option<Template>,
+}
+
+impl StateFormats {
+    pub fn new(charging: Option<&str>, full: Option<&str>, low: Option<&str>) -> Self {
+        Self {
+            charging: charging.map(Template::parse_or_literal),
+            full: full.map(Template::parse_or_literal),
+            low: low.map(Template::parse_or_literal),
+        }
+    }
+
+    /// The format for `state`, if one is set.
+    fn get(&self, state: FormatState) -> Option<&Template> {
+        match state {
+            FormatState::Charging => self.charging.as_ref(),
+            FormatState::Full => self.full.as_ref(),
+            FormatState::Low => self.low.as_ref(),
+            FormatState::Other => None,
+        }
+    }
+}
+
+/// Built-in emergency actions, selectable by name in `actions`.
+const EMERGENCY_PRESETS: &[(&str, &str)] = &[
+    (
+        "save",
+        "osascript -e 'tell application \"System Events\" to keystroke \"s\" using command down'",
+    ),
+    (
+        "low_power",
+        "osascript -e 'do shell script \"pmset -a lowpowermode 1\" with administrator privileges'",
+    ),
+    (
+        "dim",
+        "osascript -e 'tell application \"System Events\" to repeat 8 times' -e 'key code 145' -e 'end repeat'",
+    ),
+];
+
+/// Commands run once per discharge cycle when the battery hits a critical level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Emergency {
+    /// Critical percentage (fires at or below)
+    level: u8,
+    /// Shell commands, run in order
+    commands: Vec<String>,
+    /// Post a notification acknowledging the actions
+    notify: bool,
+}
+
+impl Emergency {
+    /// Parses the `emergency` table from config.
+    ///
+    /// Preset names in `actions` expand to their commands, followed by any
+    /// raw `commands`. Returns None if the table is absent.
+    pub fn parse(value: Option<&toml::Value>) -> Option<Self> {
+        let table = value?.as_table()?;
+        let strings = |key: &str| -> Vec<String> {
+            table
+                .get(key)
+                .and_then(|v| v.as_array())
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|v| v.as_str())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let mut commands = Vec::new();
+        for action in strings("actions") {
+            match EMERGENCY_PRESETS.iter().find(|(name, _)| *name == action) {
+                Some((_, command)) => commands.push(command.to_string()),
+                None => log::warn!("battery: unknown emergency action '{}'", action),
+            }
+        }
+        commands.extend(strings("commands"));
+
+        Some(Self {
+            level: table
+                .get("level")
+                .and_then(|v| v.as_integer())
+                .map(|v| v.clamp(1, 100) as u8)
+                .unwrap_or(5),
+            commands,
+            notify: table
+                .get("notify")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true),
+        })
+    }
+
+    /// Runs the commands in order, then posts the acknowledgment.
+    fn run(&self, level: u8) {
+        log::warn!("Battery at {}%, running emergency actions", level);
+        for command in &self.commands {
+            match Command::new("sh").args(["-c", command]).status() {
+                Ok(status) if !status.success() => {
+                    log::warn!("Emergency command '{}' exited with {}", command, status)
+                }
+                Err(err) => log::warn!("Emergency command '{}' failed: {}", command, err),
+                _ => {}
+            }
+        }
+        if self.notify {
+            let message = format!(
+                "Battery at {}%. Ran {} emergency action(s).",
+                level,
+                self.commands.len()
+            );
+            let script = format!(
+                "display notification \"{}\" with title \"Battery critical\"",
+                message
+            );
+            let _ = Command::new("osascript").args(["-e", &script]).status();
+        }
+    }
+}
+
+/// Fires once when a discharging battery drops to the threshold; re-arms
+/// when charging starts.
+#[derive(Debug, Default)]
+struct EmergencyLatch {
+    fired: bool,
+}
+
+impl EmergencyLatch {
+    /// Returns true if the emergency should fire for this reading.
+    /// A level of 0 means no battery was found and never fires.
+    fn check(&mut self, threshold: u8, level: u8, charging: bool) -> bool {
+        if charging {
+            self.fired = false;
+            return false;
+        }
+        if self.fired || level == 0 || level > threshold {
+            return false;
+        }
+        self.fired = true;
+        true
+    }
+}
+
+/// Points the level must move back before an alert can fire again.
+const DEFAULT_HYSTERESIS: u8 = 3;
+
+/// What makes a battery alert fire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertTrigger {
+    /// Discharging at or below the level
+    Below(u8),
+    /// At or above the level
+    Above(u8),
+    /// Plugged in and no longer charging: full or held at a charge limit
+    Full,
+}
+
+/// A command and/or toast run when the battery crosses a threshold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    trigger: AlertTrigger,
+    /// Shell command; `{level}` is replaced with the level
+    command: Option<String>,
+    /// Toast message; `{level}` is replaced with the level
+    toast: Option<String>,
+    style: ToastStyle,
+    hysteresis: u8,
+}
+
+impl Alert {
+    /// Parses the `alerts` array from config, skipping invalid entries.
+    pub fn parse_list(value: Option<&toml::Value>) -> Vec<Self> {
+        let Some(items) = value.and_then(|v| v.as_array()) else {
+            return Vec::new();
+        };
+        items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| {
+                let alert = item.as_table().and_then(Self::parse);
+                if alert.is_none() {
+                    log::warn!(
+                        "battery: alerts[{}] needs one of below, above or on = \"full\", \
+                         and a command or toast",
+                        i
+                    );
+                }
+                alert
+            })
+            .collect()
+    }
+
+    fn parse(table: &toml::value::Table) -> Option<Self> {
+        let level = |key: &str| {
+            table
+                .get(key)
+                .and_then(|v| v.as_integer())
+                .map(|v| v.clamp(0, 100) as u8)
+        };
+        let trigger = match (level("below"), level("above"), table.get("on")) {
+            (Some(level), None, None) => AlertTrigger::Below(level),
+            (None, Some(level), None) => AlertTrigger::Above(level),
+            (None, None, Some(on)) if on.as_str() == Some("full") => AlertTrigger::Full,
+            _ => return None,
+        };
+        let string = |key: &str| table.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        let (command, toast) = (string("command"), string("toast"));
+        if command.is_none() && toast.is_none() {
+            return None;
+        }
+        let style = string("style")
+            .and_then(|style| ToastStyle::parse(&style))
+            .unwrap_or(match trigger {
+                AlertTrigger::Below(_) => ToastStyle::Warning,
+                _ => ToastStyle::Success,
+            });
+
+        Some(Self {
+            trigger,
+            command,
+            toast,
+            style,
+            hysteresis: level("hysteresis").unwrap_or(DEFAULT_HYSTERESIS),
+        })
+    }
+
+    /// Shows the toast and runs the command.
+    fn run(&self, level: u8) {
+        let fill = |text: &str| text.replace("{level}", &level.to_string());
+        if let Some(message) = &self.toast {
+            toast::show(&fill(message), self.style, None);
+        }
+        if let Some(command) = &self.command {
+            let command = fill(command);
+            // Commands can be slow; keep the sampler moving
+            std::thread::spawn(
+                move || match Command::new("sh").args(["-c", &command]).status() {
+                    Ok(status) if !status.success() => {
+                        log::warn!("Battery alert '{}' exited with {}", command, status)
+                    }
+                    Err(err) => log::warn!("Battery alert '{}' failed: {}", command, err),
+                    _ => {}
+                },
+            );
+        }
+    }
+}
+
+/// Fires an alert once per crossing and re-arms it after the level moves
+/// back past the hysteresis band.
+#[derive(Debug)]
+struct AlertLatch {
+    armed: bool,
+}
+
+impl Default for AlertLatch {
+    fn default() -> Self {
+        Self { armed: true }
+    }
+}
+
+impl AlertLatch {
+    /// Returns true if the alert should fire for this reading.
+    fn check(&mut self, alert: &Alert, battery: &BatteryStatus) -> bool {
+        let level = battery.level;
+        let band = alert.hysteresis;
+        let (active, rearm) = match alert.trigger {
+            AlertTrigger::Below(threshold) => (
+                !battery.charging && level <= threshold,
+                level >= threshold.saturating_add(band),
+            ),
+            AlertTrigger::Above(threshold) => {
+                (level >= threshold, level.saturating_add(band) <= threshold)
+            }
+            AlertTrigger::Full => (battery.plugged && !battery.charging, !battery.plugged),
+        };
+        if active && self.armed {
+            self.armed = false;
+            return true;
+        }
+        if rearm {
+            self.armed = true;
+        }
+        false
+    }
+}
+
+/// Default `format`.
+const DEFAULT_FORMAT: &str = "{value}%";
+
+/// Formats minutes as "H:MM".
+fn format_minutes(minutes: u16) -> String {
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Tooltip text for a charge reading.
+fn describe_capacity(capacity: &BatteryCapacity) -> String {
+    let mut lines = vec![format!(
+        "{} of {} mAh",
+        capacity.charge_mah, capacity.full_mah
+    )];
+    if let Some(health) = capacity.health() {
+        lines.push(format!("Health {}%", health));
+    }
+    if let Some(cycles) = capacity.cycles {
+        lines.push(format!("{} cycles", cycles));
+    }
+    lines.join("\n")
+}
 
 /// Battery module that displays battery level and charging status.
 pub struct BatteryModule {
     id: String,
     label: Option<String>,
+    display: BatteryDisplay,
+    format: Template,
+    state_formats: StateFormats,
     level: Arc<AtomicU8>,
+    /// Estimated minutes to empty or full (0 = no estimate)
+    minutes_left: Arc<AtomicU16>,
     charging: Arc<AtomicBool>,
+    /// Plugged in but not charging (held at a limit or full)
+    held: Arc<AtomicBool>,
+    /// On AC power, charging or not
+    plugged: Arc<AtomicBool>,
+    capacity: Arc<Mutex<Option<BatteryCapacity>>>,
+    rule_colors: RuleColors,
     dirty: Arc<AtomicBool>,
-    stop: Arc<AtomicBool>,
+    /// Applies a reading: updates the state and runs alerts
+    record: Arc<dyn Fn(Option<BatteryStatus>) + Send + Sync>,
+    /// Set once a reading comes over IPC; the provider's are ignored after
+    fixed: Arc<Mutex<bool>>,
+    /// Dropping the module stops the readings
+    _watch: Box<dyn std::any::Any + Send + Sync>,
 }
 
 impl BatteryModule {
     /// Creates a new battery module.
-    pub fn new(id: &str, label: Option<&str>) -> Self {
+    ///
+    /// @param id - Unique module identifier
+    /// @param label - Optional label above the value
+    /// @param display - Icon or ring next to the text
+    /// @param format - Text template (None = "{value}%")
+    /// @param emergency - Actions at a critical level
+    /// @param alerts - Threshold alerts
+    /// @param battery - Where readings come from
+    pub fn new(
+        id: &str,
+        label: Option<&str>,
+        display: BatteryDisplay,
+        format: Option<&str>,
+        emergency: Option<Emergency>,
+        alerts: Vec<Alert>,
+        battery: Arc<dyn BatteryProvider>,
+    ) -> Self {
         let level = Arc::new(AtomicU8::new(0));
+        let minutes_left = Arc::new(AtomicU16::new(0));
         let charging = Arc::new(AtomicBool::new(false));
+        let held = Arc::new(AtomicBool::new(false));
+        let plugged = Arc::new(AtomicBool::new(false));
+        let capacity = Arc::new(Mutex::new(None));
         let dirty = Arc::new(AtomicBool::new(true));
-        let stop = Arc::new(AtomicBool::new(false));
 
         let level_handle = Arc::clone(&level);
+        let minutes_handle = Arc::clone(&minutes_left);
         let charging_handle = Arc::clone(&charging);
+        let held_handle = Arc::clone(&held);
+        let plugged_handle = Arc::clone(&plugged);
+        let capacity_handle = Arc::clone(&capacity);
         let dirty_handle = Arc::clone(&dirty);
-        let stop_handle = Arc::clone(&stop);
-        std::thread::spawn(move || {
-            let mut last_level = 0;
-            let mut last_charging = false;
-            while !stop_handle.load(Ordering::Relaxed) {
-                let (next_level, next_charging) = Self::fetch_status();
-                if next_level != last_level || next_charging != last_charging {
-                    level_handle.store(next_level, Ordering::Relaxed);
-                    charging_handle.store(next_charging, Ordering::Relaxed);
-                    dirty_handle.store(true, Ordering::Relaxed);
-                    last_level = next_level;
-                    last_charging = next_charging;
+        let latch = Mutex::new(EmergencyLatch::default());
+        let alert_latches: Mutex<Vec<AlertLatch>> =
+            Mutex::new(alerts.iter().map(|_| AlertLatch::default()).collect());
+        let record: Arc<dyn Fn(Option<BatteryStatus>) + Send + Sync> =
+            Arc::new(move |reading: Option<BatteryStatus>| {
+                // A level of 0 means no battery was found
+                let (next_level, next_charging) =
+                    reading.map(|b| (b.level, b.charging)).unwrap_or((0, false));
+                if let (Some(battery), Ok(mut latches)) = (reading, alert_latches.lock()) {
+                    for (alert, latch) in alerts.iter().zip(latches.iter_mut()) {
+                        if latch.check(alert, &battery) {
+                            alert.run(battery.level);
+                        }
+                    }
                 }
-                std::thread::sleep(Duration::from_secs(30));
-            }
-        });
+                let next_held = reading.is_some_and(|b| b.plugged && !b.charging && b.level < 100);
+                let next_minutes = reading.and_then(|b| b.minutes_left).unwrap_or(0);
+                // Only the cycle count is drawn; the rest shows on hover
+                if let Ok(mut guard) = capacity_handle.lock() {
+                    let next = reading.and_then(|b| b.capacity);
+                    if guard.and_then(|c| c.cycles) != next.and_then(|c| c.cycles) {
+                        mark_dirty(&dirty_handle);
+                    }
+                    *guard = next;
+                }
+                let next_plugged = reading.is_some_and(|b| b.plugged);
+                if plugged_handle.swap(next_plugged, Ordering::Relaxed) != next_plugged {
+                    mark_dirty(&dirty_handle);
+                }
+                if minutes_handle.swap(next_minutes, Ordering::Relaxed) != next_minutes {
+                    mark_dirty(&dirty_handle);
+                }
+                if held_handle.swap(next_held, Ordering::Relaxed) != next_held {
+                    mark_dirty(&dirty_handle);
+                }
+                if let (Some(emergency), Ok(mut latch)) = (&emergency, latch.lock()) {
+                    if latch.check(emergency.level, next_level, next_charging) {
+                        // Emergency commands can be slow; keep the sampler moving
+                        let emergency = emergency.clone();
+                        std::thread::spawn(move || emergency.run(next_level));
+                    }
+                }
+                let level_changed = level_handle.swap(next_level, Ordering::Relaxed) != next_level;
+                let charging_changed =
+                    charging_handle.swap(next_charging, Ordering::Relaxed) != next_charging;
+                if level_changed || charging_changed {
+                    mark_dirty(&dirty_handle);
+                }
+            });
+        let fixed = Arc::new(Mutex::new(false));
+        let record_handle = Arc::clone(&record);
+        let fixed_handle = Arc::clone(&fixed);
+        let watch = battery.watch(
+            Duration::from_secs(30),
+            Box::new(move |reading| {
+                // Held while recording so an IPC reading can't be overwritten
+                if let Ok(fixed) = fixed_handle.lock() {
+                    if !*fixed {
+                        record_handle(reading);
+                    }
+                }
+            }),
+        );
 
         Self {
             id: id.to_string(),
             label: label.map(|s| s.to_string()),
+            display,
+            format: Template::parse_or_literal(format.unwrap_or(DEFAULT_FORMAT)),
+            state_formats: StateFormats::default(),
             level,
+            minutes_left,
             charging,
+            held,
+            plugged,
+            capacity,
+            rule_colors: RuleColors::default(),
             dirty,
-            stop,
+            record,
+            fixed,
+            _watch: watch,
         }
     }
 
-    fn fetch_status() -> (u8, bool) {
-        let mut level = 0;
-        let mut charging = false;
-        let output = Command::new("pmset")
-            .args(["-g", "batt"])
-            .output()
-            .ok()
-            .and_then(|o| String::from_utf8(o.stdout).ok());
-
-        if let Some(out) = output {
-            for line in out.lines() {
-                if line.contains('%') {
-                    // Check for charging - only "charging" status, not "charged" or "discharging"
-                    // pmset shows: "charging", "discharging", "charged", "finishing charge"
-                    let lower = line.to_lowercase();
-                    charging = lower.contains("charging") && !lower.contains("discharging");
-
-                    // Extract percentage
-                    if let Some(pct_pos) = line.find('%') {
-                        let start = line[..pct_pos]
-                            .rfind(|c: char| !c.is_ascii_digit())
-                            .map(|i| i + 1)
-                            .unwrap_or(0);
-                        if let Ok(parsed_level) = line[start..pct_pos].parse::<u8>() {
-                            level = parsed_level;
-                        }
-                    }
-                    break;
-                }
-            }
+    /// Uses `formats` instead of `format` in their power states.
+    pub fn with_state_formats(mut self, formats: StateFormats) -> Self {
+        self.state_formats = formats;
+        self
+    }
+
+    /// Icon for the current level and power state.
+    fn icon(&self) -> &'static str {
+        if self.held.load(Ordering::Relaxed) {
+            battery_icons::PLUGGED
+        } else {
+            battery_icons::for_level(
+                self.level.load(Ordering::Relaxed),
+                self.charging.load(Ordering::Relaxed),
+            )
         }
-        (level, charging)
+    }
+
+    /// The latest reading.
+    fn reading(&self) -> BatteryStatus {
+        let minutes = self.minutes_left.load(Ordering::Relaxed);
+        BatteryStatus {
+            level: self.level.load(Ordering::Relaxed),
+            charging: self.charging.load(Ordering::Relaxed),
+            plugged: self.plugged.load(Ordering::Relaxed),
+            minutes_left: (minutes > 0).then_some(minutes),
+            capacity: self.capacity.lock().ok().and_then(|capacity| *capacity),
+        }
+    }
+
+    /// Format for the current power state.
+    fn current_format(&self) -> &Template {
+        self.state_formats
+            .get(FormatState::new(
+                self.level.load(Ordering::Relaxed),
+                self.charging.load(Ordering::Relaxed),
+                self.plugged.load(Ordering::Relaxed),
+            ))
+            .unwrap_or(&self.format)
+    }
+
+    /// Text the format renders for the latest reading.
+    fn text(&self) -> String {
+        let level = self.level.load(Ordering::Relaxed);
+        let charging = self.charging.load(Ordering::Relaxed);
+        let held = self.held.load(Ordering::Relaxed);
+        let minutes = self.minutes_left.load(Ordering::Relaxed);
+        let state = if charging {
+            "charging"
+        } else if held {
+            "plugged"
+        } else {
+            "discharging"
+        };
+        let time_left = (minutes > 0).then(|| format_minutes(minutes));
+        let cycles = self
+            .capacity
+            .lock()
+            .ok()
+            .and_then(|capacity| capacity.and_then(|c| c.cycles));
+        self.current_format().render(&[
+            ("value", Some(level.into())),
+            ("level", Some(level.into())),
+            ("icon", Some(self.icon().into())),
+            ("time_left", time_left.clone().map(Value::from)),
+            (
+                "time_to_empty",
+                time_left.clone().filter(|_| !charging).map(Value::from),
+            ),
+            (
+                "time_to_full",
+                time_left.filter(|_| charging).map(Value::from),
+            ),
+            ("cycle_count", cycles.map(|c| Value::from(c as f64))),
+            ("state", Some(Value::from(state))),
+        ])
     }
 }
 
@@ -102,8 +650,39 @@ impl GpuiModule for BatteryModule {
     fn render(&self, theme: &Theme) -> AnyElement {
         let level = self.level.load(Ordering::Relaxed);
         let charging = self.charging.load(Ordering::Relaxed);
-        let icon = battery_icons::for_level(level, charging);
-        let text = format!("{}%", level);
+        let icon = self.icon();
+        let format = self.current_format();
+        let text = self.text();
+        // The template draws the icon itself when it uses {icon}
+        let indicator = (!format.uses("icon")).then(|| match self.display {
+            BatteryDisplay::Icon => {
+                let mut glyph = div().child(SharedString::from(icon.to_string()));
+                if let Some(color) = self.rule_colors.icon {
+                    glyph = glyph.text_color(color);
+                }
+                glyph
+            }
+            BatteryDisplay::Ring => {
+                let fill = if let Some(color) = self.rule_colors.icon {
+                    color
+                } else if charging {
+                    theme.success
+                } else if level <= 20 {
+                    theme.destructive
+                } else {
+                    theme.foreground
+                };
+                let mut ring = ProgressRing::new(level as f32 / 100.0)
+                    .size(theme.font_size)
+                    .thickness(2.0)
+                    .fill_color(fill);
+                if charging {
+                    ring = ring.label(battery_icons::BOLT);
+                }
+                ring.render(theme)
+            }
+        });
+        let color = self.rule_colors.text.unwrap_or(theme.foreground);
 
         if let Some(ref label) = self.label {
             // Two-line layout with label - tight spacing
@@ -124,10 +703,10 @@ impl GpuiModule for BatteryModule {
                         .flex()
                         .items_center()
                         .gap(px(6.0)) // Gap between icon and text
-                        .text_color(theme.foreground)
+                        .text_color(color)
                         .text_size(px(theme.font_size))
                         .line_height(px(theme.font_size * 1.1))
-                        .child(SharedString::from(icon.to_string()))
+                        .children(indicator)
                         .child(SharedString::from(text)),
                 )
                 .into_any_element()
@@ -136,9 +715,9 @@ impl GpuiModule for BatteryModule {
                 .flex()
                 .items_center()
                 .gap(px(6.0)) // Gap between icon and text
-                .text_color(theme.foreground)
+                .text_color(color)
                 .text_size(px(theme.font_size))
-                .child(SharedString::from(icon.to_string()))
+                .children(indicator)
                 .child(SharedString::from(text))
                 .into_any_element()
         }
@@ -148,13 +727,268 @@ impl GpuiModule for BatteryModule {
         self.dirty.swap(false, Ordering::Relaxed)
     }
 
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
     fn value(&self) -> Option<u8> {
-        Some(self.level.load(Ordering::Relaxed))
+        // A level of 0 means no battery was found
+        let level = self.level.load(Ordering::Relaxed);
+        (level > 0).then_some(level)
+    }
+
+    fn display_text(&self) -> Option<String> {
+        self.value().map(|_| self.text())
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        let capacity = (*self.capacity.lock().ok()?)?;
+        Some(describe_capacity(&capacity))
+    }
+
+    fn set_rule_colors(&mut self, colors: RuleColors) {
+        self.rule_colors = colors;
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        let mut reading = self.reading();
+        let on = matches!(value, "on" | "true" | "1");
+        match key {
+            "level" => match value.parse::<u8>() {
+                Ok(level) if level <= 100 => reading.level = level,
+                _ => return false,
+            },
+            "charging" => {
+                reading.charging = on;
+                reading.plugged |= on;
+            }
+            "plugged" => {
+                reading.plugged = on;
+                reading.charging &= on;
+            }
+            _ => return false,
+        }
+        let Ok(mut fixed) = self.fixed.lock() else {
+            return false;
+        };
+        *fixed = true;
+        (self.record)(Some(reading));
+        true
     }
 }
 
-impl Drop for BatteryModule {
-    fn drop(&mut self) {
-        self.stop.store(true, Ordering::Relaxed);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::providers::mock::MockBattery;
+
+    #[test]
+    fn display_parses_ring_and_defaults_to_icon() {
+        assert_eq!(BatteryDisplay::parse(Some("ring")), BatteryDisplay::Ring);
+        assert_eq!(BatteryDisplay::parse(Some("bars")), BatteryDisplay::Icon);
+        assert_eq!(BatteryDisplay::parse(None), BatteryDisplay::Icon);
+    }
+
+    #[test]
+    fn emergency_fires_once_per_discharge_cycle() {
+        let mut latch = EmergencyLatch::default();
+        assert!(!latch.check(5, 6, false));
+        assert!(latch.check(5, 5, false));
+        assert!(!latch.check(5, 4, false));
+        assert!(!latch.check(5, 4, true));
+        assert!(latch.check(5, 3, false));
+    }
+
+    #[test]
+    fn emergency_ignores_missing_battery() {
+        let mut latch = EmergencyLatch::default();
+        assert!(!latch.check(5, 0, false));
+    }
+
+    fn reading(level: u8, charging: bool, plugged: bool) -> BatteryStatus {
+        BatteryStatus {
+            level,
+            charging,
+            plugged,
+            minutes_left: None,
+            capacity: None,
+        }
+    }
+
+    fn alert(toml: &str) -> Alert {
+        let value: toml::Value = toml::from_str(toml).unwrap();
+        Alert::parse(value.as_table().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn alert_fires_once_until_level_recovers() {
+        let low = alert("below = 20\ntoast = \"low\"");
+        let mut latch = AlertLatch::default();
+        assert!(!latch.check(&low, &reading(21, false, false)));
+        assert!(latch.check(&low, &reading(20, false, false)));
+        assert!(!latch.check(&low, &reading(19, false, false)));
+        // Hovering around the threshold doesn't re-fire
+        assert!(!latch.check(&low, &reading(21, false, false)));
+        assert!(!latch.check(&low, &reading(20, false, false)));
+        assert!(!latch.check(&low, &reading(23, true, true)));
+        assert!(latch.check(&low, &reading(20, false, false)));
+    }
+
+    #[test]
+    fn full_alert_fires_when_charging_stops_on_power() {
+        let full = alert("on = \"full\"\ncommand = \"say full\"");
+        let mut latch = AlertLatch::default();
+        assert!(!latch.check(&full, &reading(79, true, true)));
+        assert!(latch.check(&full, &reading(80, false, true)));
+        assert!(!latch.check(&full, &reading(80, false, true)));
+        assert!(!latch.check(&full, &reading(79, false, false)));
+        assert!(latch.check(&full, &reading(100, false, true)));
+    }
+
+    #[test]
+    fn alerts_need_one_trigger_and_an_action() {
+        let value: toml::Value = toml::from_str(
+            r#"alerts = [
+    { above = 80, toast = "unplug", style = "info" },
+    { below = 10 },
+    { below = 10, above = 90, toast = "both" },
+]"#,
+        )
+        .unwrap();
+        let alerts = Alert::parse_list(value.get("alerts"));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].trigger, AlertTrigger::Above(80));
+        assert_eq!(alerts[0].style, ToastStyle::Info);
+        assert_eq!(alerts[0].hysteresis, DEFAULT_HYSTERESIS);
+    }
+
+    #[test]
+    fn formats_time_left() {
+        assert_eq!(format_minutes(312), "5:12");
+        assert_eq!(format_minutes(45), "0:45");
+    }
+
+    #[test]
+    fn emergency_parses_presets_then_commands() {
+        let value: toml::Value = toml::from_str(
+            r#"level = 8
+actions = ["dim", "bogus"]
+commands = ["say low battery"]"#,
+        )
+        .unwrap();
+        let emergency = Emergency::parse(Some(&value)).unwrap();
+        assert_eq!(emergency.level, 8);
+        assert_eq!(emergency.commands.len(), 2);
+        assert!(emergency.commands[0].contains("key code 145"));
+        assert_eq!(emergency.commands[1], "say low battery");
+        assert!(emergency.notify);
+        assert_eq!(Emergency::parse(None), None);
+    }
+
+    #[test]
+    fn picks_the_format_for_the_power_state() {
+        assert_eq!(FormatState::new(50, true, true), FormatState::Charging);
+        assert_eq!(FormatState::new(100, false, true), FormatState::Full);
+        assert_eq!(FormatState::new(80, false, true), FormatState::Full);
+        assert_eq!(FormatState::new(20, false, false), FormatState::Low);
+        assert_eq!(FormatState::new(21, false, false), FormatState::Other);
+        // No battery
+        assert_eq!(FormatState::new(0, false, false), FormatState::Other);
+
+        let formats = StateFormats::new(Some("{time_to_full}"), None, Some("LOW {value}%"));
+        let render = |state| {
+            formats.get(state).map(|format| {
+                format.render(&[
+                    ("value", Some(Value::from(15u8))),
+                    ("time_to_full", Some(Value::from("1:05"))),
+                ])
+            })
+        };
+        assert_eq!(render(FormatState::Charging).as_deref(), Some("1:05"));
+        assert_eq!(render(FormatState::Low).as_deref(), Some("LOW 15%"));
+        assert_eq!(render(FormatState::Full), None);
+        assert_eq!(render(FormatState::Other), None);
+    }
+
+    #[test]
+    fn describes_capacity_in_mah() {
+        let capacity = BatteryCapacity {
+            charge_mah: 3912,
+            full_mah: 4382,
+            design_mah: Some(4790),
+            cycles: Some(213),
+        };
+        assert_eq!(
+            describe_capacity(&capacity),
+            "3912 of 4382 mAh\nHealth 91%\n213 cycles"
+        );
+        let bare = BatteryCapacity {
+            design_mah: None,
+            cycles: None,
+            ..capacity
+        };
+        assert_eq!(describe_capacity(&bare), "3912 of 4382 mAh");
+    }
+
+    #[test]
+    fn shows_the_latest_reading() {
+        let battery = Arc::new(MockBattery {
+            readings: vec![
+                Some(reading(40, true, true)),
+                Some(reading(80, false, true)),
+            ],
+        });
+        let mut module = BatteryModule::new(
+            "battery",
+            None,
+            BatteryDisplay::Icon,
+            None,
+            None,
+            Vec::new(),
+            battery,
+        );
+        assert!(module.update());
+        assert_eq!(module.value(), Some(80));
+        assert_eq!(module.display_text().as_deref(), Some("80%"));
+        assert!(!module.charging.load(Ordering::Relaxed));
+        assert!(module.held.load(Ordering::Relaxed));
+
+        let none = Arc::new(MockBattery {
+            readings: vec![None],
+        });
+        let module = BatteryModule::new(
+            "battery",
+            None,
+            BatteryDisplay::Icon,
+            None,
+            None,
+            Vec::new(),
+            none,
+        );
+        assert_eq!(module.value(), None);
+        assert_eq!(module.display_text(), None);
+    }
+
+    #[test]
+    fn takes_readings_over_ipc() {
+        let battery = Arc::new(MockBattery {
+            readings: vec![Some(reading(80, false, false))],
+        });
+        let mut module = BatteryModule::new(
+            "battery",
+            None,
+            BatteryDisplay::Icon,
+            None,
+            None,
+            Vec::new(),
+            battery,
+        );
+        assert!(module.set_property("level", "15"));
+        assert!(module.set_property("charging", "true"));
+        assert!(!module.set_property("level", "101"));
+        assert!(!module.set_property("health", "90"));
+        assert_eq!(module.value(), Some(15));
+        assert_eq!(module.display_text().as_deref(), Some("15%"));
+        assert!(module.plugged.load(Ordering::Relaxed));
     }
 }
@@ -3,26 +3,48 @@
 //! This module provides:
 //! - Bar item: Date and time display (clickable)
 //! - Popup: Calendar grid + timezone list with time scrubbing
+//!
+//! In the popup, the arrow keys move a day cursor (crossing into adjacent
+//! months), Page Up / Page Down switch months, and Enter opens the selected
+//! day in Calendar.app.
+//!
+//! `[bar.calendar]` adds ISO week numbers, dims weekends and colors holidays
+//! from a static list or an ICS file. Event sources mark days that have
+//! events with a dot through [`set_event_days`].
+//!
+//! ```toml
+//! [bar.calendar]
+//! week_numbers = true
+//! holidays = ["12-25 Christmas", "2026-05-25 Memorial Day"]
+//! holidays_file = "~/Calendars/holidays.ics"
+//! ```
 
-use chrono::{Datelike, Duration, FixedOffset, Local, NaiveDate, Timelike, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, RwLock};
+
+use chrono::{Datelike, Duration, Local, NaiveDate, Timelike, Utc};
 use gpui::{div, prelude::*, px, AnyElement, MouseButton, ParentElement, SharedString, Styled};
 
+use super::timezone::{format_offset, TimeZone};
 use super::{
-    dispatch_popup_action, GpuiModule, PopupAction, PopupAnchor, PopupEvent, PopupSpec, PopupType,
+    dispatch_popup_action, until_next_second, GpuiModule, PopupAction, PopupAnchor, PopupEvent,
+    PopupSpec, PopupType,
 };
-use crate::gpui_app::popup_manager::notify_popup_needs_render;
-use crate::gpui_app::primitives::{render_slider, SliderStyle};
+use crate::config::{CalendarConfig, Holiday, HolidayDate};
+use crate::gpui_app::popup_manager::{notify_popup_needs_render, request_hide_popup};
+use crate::gpui_app::primitives::{expand_tilde, render_slider, Button, SliderStyle};
 use crate::gpui_app::theme::Theme;
-
-/// Timezones to display: (display name, timezone abbreviation, UTC offset hours)
-pub const TIMEZONES: &[(&str, &str, i32)] = &[
-    ("Pacific", "PST", -8),
-    ("Mountain", "MST", -7),
-    ("Central", "CST", -6),
-    ("Eastern", "EST", -5),
-    ("Bangkok", "ICT", 7),
-    ("Hong Kong", "HKT", 8),
-    ("Japan", "JST", 9),
+use crate::system::ics;
+
+/// Timezones to display: (display name, IANA timezone)
+pub const TIMEZONES: &[(&str, &str)] = &[
+    ("Pacific", "America/Los_Angeles"),
+    ("Mountain", "America/Denver"),
+    ("Central", "America/Chicago"),
+    ("Eastern", "America/New_York"),
+    ("Bangkok", "Asia/Bangkok"),
+    ("Hong Kong", "Asia/Hong_Kong"),
+    ("Japan", "Asia/Tokyo"),
 ];
 
 /// Maximum time offset in minutes (12 hours each direction)
@@ -32,6 +54,85 @@ const CALENDAR_POPUP_WIDTH: f32 = 280.0;
 const TIMEZONE_PADDING_X: f32 = 12.0;
 const SLIDER_WIDTH: f32 = 232.0;
 
+/// Width of a day cell, narrower when week numbers take a column.
+const DAY_CELL: f32 = 32.0;
+const DAY_CELL_WITH_WEEKS: f32 = 28.0;
+const WEEK_NUMBER_WIDTH: f32 = 20.0;
+
+/// Grid options from `[bar.calendar]`.
+#[derive(Debug, Clone, Default)]
+struct GridSettings {
+    week_numbers: bool,
+    weekends: bool,
+    holidays: Vec<Holiday>,
+}
+
+static SETTINGS: RwLock<Option<GridSettings>> = RwLock::new(None);
+/// Days with events, by event source.
+static EVENT_DAYS: Mutex<Option<HashMap<String, HashSet<NaiveDate>>>> = Mutex::new(None);
+
+/// Applies `[bar.calendar]`, reading the holidays file if one is set.
+pub fn configure(config: &CalendarConfig) {
+    let mut holidays: Vec<Holiday> = config
+        .holidays
+        .iter()
+        .filter_map(|entry| Holiday::parse(entry))
+        .collect();
+    if let Some(path) = &config.holidays_file {
+        match std::fs::read_to_string(expand_tilde(path)) {
+            Ok(text) => holidays.extend(ics::parse_events(&text).into_iter().map(|event| {
+                let day = event.start.date();
+                Holiday {
+                    date: if event.is_yearly() {
+                        HolidayDate::Yearly(day.month(), day.day())
+                    } else {
+                        HolidayDate::Once(day)
+                    },
+                    name: event.summary,
+                }
+            })),
+            Err(err) => log::warn!("calendar: can't read holidays file '{}': {}", path, err),
+        }
+    }
+    if let Ok(mut settings) = SETTINGS.write() {
+        *settings = Some(GridSettings {
+            week_numbers: config.week_numbers,
+            weekends: config.weekends,
+            holidays,
+        });
+    }
+}
+
+/// Replaces the days marked with an event dot for one event source.
+pub fn set_event_days(source: &str, days: HashSet<NaiveDate>) {
+    if let Ok(mut all) = EVENT_DAYS.lock() {
+        all.get_or_insert_with(HashMap::new)
+            .insert(source.to_string(), days);
+    }
+    notify_popup_needs_render("calendar");
+}
+
+fn has_events(day: NaiveDate) -> bool {
+    EVENT_DAYS
+        .lock()
+        .ok()
+        .and_then(|all| {
+            all.as_ref()
+                .map(|all| all.values().any(|days| days.contains(&day)))
+        })
+        .unwrap_or(false)
+}
+
+/// Name of the holiday on `day`, if any.
+fn holiday_on(holidays: &[Holiday], day: NaiveDate) -> Option<&Holiday> {
+    holidays.iter().find(|holiday| holiday.date.matches(day))
+}
+
+/// ISO week number of a Sunday-first grid row, taken from its Monday.
+fn row_week_number(sunday: NaiveDate) -> u32 {
+    (sunday + Duration::days(1)).iso_week().week()
+}
+
 /// Calendar module providing datetime bar item and calendar/timezone popup.
 #[allow(dead_code)]
 pub struct CalendarModule {
@@ -43,6 +144,8 @@ pub struct CalendarModule {
     // Calendar navigation state
     displayed_year: i32,
     displayed_month: u32,
+    /// Day under the keyboard cursor
+    selected_day: Option<NaiveDate>,
     // Time scrubbing state
     offset_minutes: i32,
     scroll_accumulator: f32,
@@ -70,6 +173,7 @@ impl CalendarModule {
             time_text: now.format(&time_format).to_string(),
             displayed_year: today.year(),
             displayed_month: today.month(),
+            selected_day: None,
             offset_minutes: 0,
             scroll_accumulator: 0.0,
             is_dragging: false,
@@ -131,6 +235,54 @@ impl CalendarModule {
         let today = Local::now().date_naive();
         self.displayed_year = today.year();
         self.displayed_month = today.month();
+        self.selected_day = None;
+    }
+
+    /// Moves the day cursor by `days`, following it into other months. The
+    /// first move only places the cursor (on today, or the 1st of the
+    /// displayed month).
+    fn move_selection(&mut self, days: i64) {
+        let today = Local::now().date_naive();
+        let day = match self.selected_day {
+            Some(day) => day + Duration::days(days),
+            None if (today.year(), today.month())
+                == (self.displayed_year, self.displayed_month) =>
+            {
+                today
+            }
+            None => NaiveDate::from_ymd_opt(self.displayed_year, self.displayed_month, 1)
+                .unwrap_or(today),
+        };
+        self.selected_day = Some(day);
+        self.displayed_year = day.year();
+        self.displayed_month = day.month();
+    }
+
+    /// Opens Calendar.app on the given day.
+    fn open_in_calendar(day: NaiveDate) {
+        let script = format!(
+            "set d to current date\n\
+             set day of d to 1\n\
+             set year of d to {}\n\
+             set month of d to {}\n\
+             set day of d to {}\n\
+             tell application \"Calendar\"\n\
+             activate\n\
+             switch view to day view\n\
+             view calendar at d\n\
+             end tell",
+            day.year(),
+            day.month(),
+            day.day()
+        );
+        std::thread::spawn(move || {
+            if let Err(err) = std::process::Command::new("osascript")
+                .args(["-e", &script])
+                .status()
+            {
+                log::warn!("Failed to open Calendar: {}", err);
+            }
+        });
     }
 
     /// Navigate to previous month.
@@ -183,6 +335,16 @@ impl CalendarModule {
 
     /// Renders the calendar grid.
     fn render_calendar_grid(&self) -> gpui::Div {
+        let settings = SETTINGS
+            .read()
+            .ok()
+            .and_then(|s| s.clone())
+            .unwrap_or_default();
+        let cell_size = if settings.week_numbers {
+            DAY_CELL_WITH_WEEKS
+        } else {
+            DAY_CELL
+        };
         let today = Local::now().date_naive();
         let year = self.displayed_year;
         let month = self.displayed_month;
@@ -219,8 +381,6 @@ impl CalendarModule {
 
         // Header with navigation
         let header_text = format!("{} {}", month_name, year);
-        let nav_button_style = self.theme.surface_hover;
-        let text_color = self.theme.foreground;
 
         rows.push(
             div()
@@ -232,65 +392,43 @@ impl CalendarModule {
                 .py(px(8.0))
                 .px(px(8.0))
                 .child(
-                    div()
-                        .id("prev-month")
-                        .w(px(28.0))
-                        .h(px(28.0))
-                        .flex()
-                        .items_center()
-                        .justify_center()
-                        .rounded(px(4.0))
-                        .cursor_pointer()
-                        .hover(|s| s.bg(nav_button_style))
-                        .on_mouse_down(MouseButton::Left, move |_event, _window, _cx| {
-                            dispatch_popup_action("calendar", PopupAction::Prev);
-                            notify_popup_needs_render("calendar");
-                        })
-                        .text_color(text_color)
+                    Button::new("prev-month")
+                        .label("◀")
+                        .square(px(28.0))
                         .text_size(px(14.0))
-                        .child(SharedString::from("◀")),
+                        .on_popup_action("calendar", PopupAction::Prev)
+                        .render(&self.theme),
                 )
                 .child(
-                    div()
-                        .id("go-to-today")
-                        .px(px(8.0))
-                        .py(px(4.0))
-                        .rounded(px(4.0))
-                        .cursor_pointer()
-                        .hover(|s| s.bg(nav_button_style))
-                        .on_mouse_down(MouseButton::Left, move |_event, _window, _cx| {
-                            dispatch_popup_action("calendar", PopupAction::Today);
-                            notify_popup_needs_render("calendar");
-                        })
-                        .text_color(text_color)
+                    Button::new("go-to-today")
+                        .label(header_text)
                         .text_size(px(16.0))
-                        .font_weight(gpui::FontWeight::SEMIBOLD)
-                        .child(SharedString::from(header_text)),
+                        .on_popup_action("calendar", PopupAction::Today)
+                        .render(&self.theme)
+                        .font_weight(gpui::FontWeight::SEMIBOLD),
                 )
                 .child(
-                    div()
-                        .id("next-month")
-                        .w(px(28.0))
-                        .h(px(28.0))
-                        .flex()
-                        .items_center()
-                        .justify_center()
-                        .rounded(px(4.0))
-                        .cursor_pointer()
-                        .hover(|s| s.bg(nav_button_style))
-                        .on_mouse_down(MouseButton::Left, move |_event, _window, _cx| {
-                            dispatch_popup_action("calendar", PopupAction::Next);
-                            notify_popup_needs_render("calendar");
-                        })
-                        .text_color(text_color)
+                    Button::new("next-month")
+                        .label("▶")
+                        .square(px(28.0))
                         .text_size(px(14.0))
-                        .child(SharedString::from("▶")),
+                        .on_popup_action("calendar", PopupAction::Next)
+                        .render(&self.theme),
                 )
                 .into_any_element(),
         );
 
         // Weekday headers
         let weekdays = ["Su", "Mo", "Tu", "We", "Th", "Fr", "Sa"];
+        let week_header = settings.week_numbers.then(|| {
+            div()
+                .w(px(WEEK_NUMBER_WIDTH))
+                .text_color(self.theme.foreground_muted)
+                .text_size(px(10.0))
+                .flex()
+                .justify_center()
+                .child("Wk")
+        });
         rows.push(
             div()
                 .flex()
@@ -298,9 +436,10 @@ impl CalendarModule {
                 .justify_between()
                 .h(px(20.0))
                 .px(px(8.0))
+                .children(week_header)
                 .children(weekdays.iter().map(|day| {
                     div()
-                        .w(px(32.0))
+                        .w(px(cell_size))
                         .text_color(self.theme.foreground_muted)
                         .text_size(px(12.0))
                         .flex()
@@ -316,29 +455,74 @@ impl CalendarModule {
         for week in 0..6 {
             let mut week_cells: Vec<gpui::Div> = Vec::new();
 
+            if settings.week_numbers {
+                let sunday = first_day + Duration::days((week * 7) as i64 - first_weekday as i64);
+                week_cells.push(
+                    div()
+                        .w(px(WEEK_NUMBER_WIDTH))
+                        .h(px(cell_size))
+                        .flex()
+                        .items_center()
+                        .justify_center()
+                        .text_size(px(10.0))
+                        .text_color(self.theme.foreground_muted)
+                        .child(SharedString::from(row_week_number(sunday).to_string())),
+                );
+            }
+
             for weekday in 0..7 {
                 let cell_day = week * 7 + weekday;
                 if cell_day < first_weekday || day > days_in_month {
-                    week_cells.push(div().w(px(32.0)).h(px(32.0)));
+                    week_cells.push(div().w(px(cell_size)).h(px(cell_size)));
                 } else {
+                    let date = NaiveDate::from_ymd_opt(year, month, day).unwrap_or(today);
                     let is_today = is_current_month && day == today.day();
+                    let is_selected = self.selected_day == Some(date);
+                    let is_weekend = weekday == 0 || weekday == 6;
+                    let is_holiday = holiday_on(&settings.holidays, date).is_some();
                     let day_text = SharedString::from(day.to_string());
 
+                    // Keep a slot for the event dot so numbers line up
+                    let dot_color = if is_today {
+                        self.theme.on_accent
+                    } else {
+                        self.theme.accent
+                    };
+                    let mut dot = div().size(px(4.0)).rounded_full();
+                    if has_events(date) {
+                        dot = dot.bg(dot_color);
+                    }
+
                     let mut cell = div()
-                        .w(px(32.0))
-                        .h(px(32.0))
+                        .w(px(cell_size))
+                        .h(px(cell_size))
                         .flex()
+                        .flex_col()
                         .items_center()
                         .justify_center()
+                        .gap(px(1.0))
                         .text_size(px(13.0))
                         .rounded(px(6.0))
-                        .child(day_text);
+                        .child(day_text)
+                        .child(dot);
 
                     if is_today {
                         cell = cell.bg(self.theme.accent).text_color(self.theme.on_accent);
+                    } else if is_holiday {
+                        cell = cell.text_color(self.theme.destructive);
+                    } else if is_weekend && settings.weekends {
+                        cell = cell.text_color(self.theme.foreground_muted);
                     } else {
                         cell = cell.text_color(self.theme.foreground);
                     }
+                    if is_selected {
+                        let ring = if is_today {
+                            self.theme.on_accent
+                        } else {
+                            self.theme.accent
+                        };
+                        cell = cell.border_2().border_color(ring);
+                    }
 
                     week_cells.push(cell);
                     day += 1;
@@ -369,12 +553,21 @@ impl CalendarModule {
             }
         }
 
-        div()
-            .flex()
-            .flex_col()
-            .px(px(12.0))
-            .pb(px(16.0))
-            .children(rows)
+        // The selected day's holiday (or today's) fills the bottom margin
+        let focus_day = self.selected_day.unwrap_or(today);
+        let holiday_name = holiday_on(&settings.holidays, focus_day)
+            .map(|holiday| holiday.name.clone())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_default();
+
+        div().flex().flex_col().px(px(12.0)).children(rows).child(
+            div()
+                .h(px(16.0))
+                .px(px(8.0))
+                .text_size(px(11.0))
+                .text_color(self.theme.destructive)
+                .child(SharedString::from(holiday_name)),
+        )
     }
 
     /// Renders the timezone list with current times.
@@ -382,15 +575,17 @@ impl CalendarModule {
         let snapped_offset = self.snapped_offset();
         let now_utc = Utc::now() + Duration::minutes(snapped_offset as i64);
         let local_now = Local::now() + Duration::minutes(snapped_offset as i64);
-        let local_offset_secs = Local::now().offset().local_minus_utc();
+        let local_offset = TimeZone::Local.offset_minutes(now_utc);
 
         let mut rows: Vec<gpui::AnyElement> = Vec::new();
 
-        for (name, _tz_abbrev, offset_hours) in TIMEZONES {
-            let tz_offset = FixedOffset::east_opt(offset_hours * 3600).unwrap();
-            let tz_time = now_utc.with_timezone(&tz_offset);
-
-            let diff_hours = offset_hours - (local_offset_secs / 3600);
+        for (name, tz_name) in TIMEZONES {
+            let Some(zone) = TimeZone::parse(tz_name) else {
+                continue;
+            };
+            let tz_time = zone.at(now_utc);
+            let offset = zone.offset_minutes(now_utc);
+            let diff = offset - local_offset;
 
             let hour = tz_time.hour();
             let minute = tz_time.minute();
@@ -421,19 +616,13 @@ impl CalendarModule {
                 format!("{} days", day_diff)
             };
 
-            let offset_str = if diff_hours == 0 {
+            let offset_str = if diff == 0 {
                 day_str
-            } else if diff_hours > 0 {
-                format!("+{}h, {}", diff_hours, day_str)
             } else {
-                format!("{}h, {}", diff_hours, day_str)
+                format!("{}h, {}", format_offset(diff), day_str)
             };
 
-            let gmt_str = if *offset_hours >= 0 {
-                format!("GMT+{}", offset_hours)
-            } else {
-                format!("GMT{}", offset_hours)
-            };
+            let gmt_str = format!("GMT{}", format_offset(offset));
 
             rows.push(
                 div()
@@ -558,7 +747,11 @@ impl CalendarModule {
                         dispatch_popup_action("calendar", PopupAction::SliderSet { value });
                         notify_popup_needs_render("calendar");
                     })
-                    .on_mouse_move(|event, _window, _cx| {
+                    .on_mouse_move(move |event, _window, _cx| {
+                        // Moving over the slider without dragging changes nothing
+                        if !is_dragging {
+                            return;
+                        }
                         let inner_width = CALENDAR_POPUP_WIDTH - (TIMEZONE_PADDING_X * 2.0);
                         let slider_left = TIMEZONE_PADDING_X + ((inner_width - SLIDER_WIDTH) / 2.0);
                         let event_x = f32::from(event.position.x);
@@ -662,6 +855,14 @@ impl GpuiModule for CalendarModule {
         changed
     }
 
+    fn next_update(&self) -> Option<std::time::Duration> {
+        Some(until_next_second())
+    }
+
+    fn display_text(&self) -> Option<String> {
+        Some(format!("{} {}", self.date_text, self.time_text))
+    }
+
     fn popup_spec(&self) -> Option<PopupSpec> {
         let height = self.calculate_height();
         log::debug!("CalendarModule::popup_spec height={}", height);
@@ -670,6 +871,7 @@ impl GpuiModule for CalendarModule {
             height,
             anchor: PopupAnchor::Right,
             popup_type: PopupType::Popup,
+            keyboard: true,
         })
     }
 
@@ -743,6 +945,27 @@ impl GpuiModule for CalendarModule {
                     Self::snap_offset_to_clock_boundary(self.offset_minutes + delta_minutes);
                 self.set_offset(snapped);
             }
+            PopupEvent::Key(key) => {
+                if key.command || key.control || key.alt {
+                    return;
+                }
+                match key.key.as_str() {
+                    "left" => self.move_selection(-1),
+                    "right" => self.move_selection(1),
+                    "up" => self.move_selection(-7),
+                    "down" => self.move_selection(7),
+                    "pageup" => self.prev_month(),
+                    "pagedown" => self.next_month(),
+                    "enter" => match self.selected_day {
+                        Some(day) => {
+                            Self::open_in_calendar(day);
+                            request_hide_popup();
+                        }
+                        None => self.reset(),
+                    },
+                    _ => {}
+                }
+            }
             _ => {}
         }
     }
@@ -762,6 +985,39 @@ impl GpuiModule for CalendarModule {
                 let minutes = Self::from_slider_value(value);
                 self.set_offset(minutes);
             }
+            PopupAction::Refresh | PopupAction::RunCommand { .. } | PopupAction::Select { .. } => {}
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn week_numbers_follow_iso_weeks() {
+        // Sunday Dec 28 2025 starts the row of ISO week 1 of 2026
+        assert_eq!(row_week_number(date(2025, 12, 28)), 1);
+        assert_eq!(row_week_number(date(2026, 3, 8)), 11);
+        // Row of Sunday Dec 27 2026 is ISO week 53
+        assert_eq!(row_week_number(date(2026, 12, 27)), 53);
+    }
+
+    #[test]
+    fn finds_yearly_and_one_off_holidays() {
+        let holidays: Vec<Holiday> = ["12-25 Christmas", "2026-05-25 Memorial Day"]
+            .iter()
+            .filter_map(|entry| Holiday::parse(entry))
+            .collect();
+        assert_eq!(
+            holiday_on(&holidays, date(2031, 12, 25)).map(|h| h.name.as_str()),
+            Some("Christmas")
+        );
+        assert!(holiday_on(&holidays, date(2026, 5, 25)).is_some());
+        assert!(holiday_on(&holidays, date(2027, 5, 25)).is_none());
+    }
+}
@@ -0,0 +1,609 @@
+//! CI/build status module.
+//!
+//! Polls pipelines on GitHub Actions, Buildkite and Jenkins every
+//! `interval` seconds and shows a colored dot per pipeline for its latest
+//! run, followed by a summary like "3/4". Clicking opens a popup listing
+//! recent runs across all pipelines; clicking a run opens it in the browser.
+//!
+//! `format` placeholders: `{passing}`, `{failing}`, `{running}` (running or
+//! queued) and `{total}`, counted by each pipeline's latest run.
+//!
+//! ```toml
+//! [[modules.right.left]]
+//! type = "ci"
+//! token = "secret:keychain/sinew/github"
+//! pipelines = [
+//!     { provider = "github", repo = "acme/web", branch = "main" },
+//!     { provider = "buildkite", org = "acme", pipeline = "api", token = "secret:keychain/sinew/buildkite" },
+//!     { provider = "jenkins", url = "https://ci.acme.dev/job/deploy", user = "me", token = "secret:keychain/sinew/jenkins" },
+//! ]
+//! ```
+
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use gpui::{div, prelude::*, px, AnyElement, MouseButton, Rgba, SharedString, Styled};
+
+use super::template::{Template, Value};
+use super::{
+    dispatch_popup_action, mark_dirty, sleep_unless_stopped, GpuiModule, PopupAction, PopupEvent,
+    PopupSpec,
+};
+use crate::gpui_app::popup_manager::{notify_popup_needs_render, request_hide_popup};
+use crate::gpui_app::primitives::skeleton::shimmer_skeleton;
+use crate::gpui_app::theme::Theme;
+use crate::system::ci::{Buildkite, CiProvider, GitHubActions, Jenkins, Run, Status};
+
+const DEFAULT_FORMAT: &str = "{passing}/{total}";
+const DOT_SIZE: f32 = 7.0;
+const POPUP_WIDTH: f64 = 400.0;
+const POPUP_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 24.0;
+const ROW_HEIGHT: f32 = 34.0;
+const MAX_ROWS: usize = 12;
+
+/// Parses one `pipelines` entry; `token` is the module-wide default.
+fn parse_pipeline(entry: &toml::Value, token: Option<&str>) -> Result<Box<dyn CiProvider>, String> {
+    let string = |key: &str| entry.get(key).and_then(|v| v.as_str()).map(String::from);
+    let token = string("token").or_else(|| token.map(String::from));
+    let required = |key: &str| string(key).ok_or_else(|| format!("missing '{}'", key));
+    match string("provider").as_deref() {
+        Some("github") => Ok(Box::new(GitHubActions {
+            repo: required("repo")?,
+            workflow: string("workflow"),
+            branch: string("branch"),
+            token,
+        })),
+        Some("buildkite") => Ok(Box::new(Buildkite {
+            org: required("org")?,
+            pipeline: required("pipeline")?,
+            branch: string("branch"),
+            token: token.ok_or("missing 'token'")?,
+        })),
+        Some("jenkins") => Ok(Box::new(Jenkins {
+            url: required("url")?,
+            credentials: string("user").zip(token),
+        })),
+        Some(other) => Err(format!(
+            "unknown provider '{}', expected github, buildkite or jenkins",
+            other
+        )),
+        None => Err("missing 'provider'".to_string()),
+    }
+}
+
+/// Parses `pipelines = [{ provider = "github", ... }]`, skipping (and
+/// logging) invalid entries.
+pub fn parse_pipelines(
+    id: &str,
+    value: Option<&toml::Value>,
+    token: Option<&str>,
+) -> Vec<Box<dyn CiProvider>> {
+    let Some(entries) = value.and_then(|v| v.as_array()) else {
+        return Vec::new();
+    };
+    entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, entry)| match parse_pipeline(entry, token) {
+            Ok(provider) => Some(provider),
+            Err(err) => {
+                log::warn!("ci: '{}' pipelines[{}]: {}", id, i, err);
+                None
+            }
+        })
+        .collect()
+}
+
+/// A pipeline's latest fetch.
+#[derive(Debug, Clone, Default)]
+struct PipelineState {
+    label: String,
+    /// Newest first
+    runs: Vec<Run>,
+    error: Option<String>,
+}
+
+impl PipelineState {
+    fn status(&self) -> Option<Status> {
+        self.runs.first().map(|run| run.status)
+    }
+}
+
+/// Pipelines by the status of their latest run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Summary {
+    passing: usize,
+    failing: usize,
+    running: usize,
+    total: usize,
+}
+
+fn summarize(pipelines: &[PipelineState]) -> Summary {
+    let mut summary = Summary {
+        total: pipelines.len(),
+        ..Default::default()
+    };
+    for pipeline in pipelines {
+        match pipeline.status() {
+            Some(Status::Passed) => summary.passing += 1,
+            Some(Status::Failed) => summary.failing += 1,
+            Some(Status::Running | Status::Pending) => summary.running += 1,
+            Some(Status::Canceled) | None => {}
+        }
+    }
+    summary
+}
+
+/// Runs of every pipeline with the pipeline's label, newest first.
+fn recent_runs(pipelines: &[PipelineState]) -> Vec<(&str, &Run)> {
+    let mut runs: Vec<(&str, &Run)> = pipelines
+        .iter()
+        .flat_map(|p| p.runs.iter().map(move |run| (p.label.as_str(), run)))
+        .collect();
+    // Runs without a start time (queued) sort first
+    runs.sort_by_key(|(_, run)| (run.started.is_some(), std::cmp::Reverse(run.started)));
+    runs
+}
+
+/// How long ago `started` was: "now", "5m ago", "3h ago" or "2d ago".
+fn ago(started: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let minutes = (now - started).num_minutes().max(0);
+    match minutes {
+        0 => "now".to_string(),
+        1..=59 => format!("{}m ago", minutes),
+        60..=1439 => format!("{}h ago", minutes / 60),
+        _ => format!("{}d ago", minutes / 1440),
+    }
+}
+
+fn status_color(theme: &Theme, status: Option<Status>) -> Rgba {
+    match status {
+        Some(Status::Passed) => theme.success,
+        Some(Status::Failed) => theme.destructive,
+        Some(Status::Running) => theme.info,
+        Some(Status::Pending) => theme.warning,
+        Some(Status::Canceled) | None => theme.foreground_subtle,
+    }
+}
+
+/// Latest fetch of every pipeline.
+#[derive(Debug, Clone, Default)]
+struct CiState {
+    pipelines: Vec<PipelineState>,
+    loaded: bool,
+}
+
+/// State shared between the bar and popup copies of the module.
+struct Shared {
+    providers: Vec<Box<dyn CiProvider>>,
+    /// Runs fetched per pipeline
+    runs: usize,
+    state: Mutex<CiState>,
+    dirty: AtomicBool,
+    stop: AtomicBool,
+}
+
+impl Shared {
+    /// Fetches every pipeline.
+    fn refresh(&self, id: &str) {
+        let pipelines = self
+            .providers
+            .iter()
+            .map(|provider| {
+                let label = provider.label();
+                match provider.runs(self.runs) {
+                    Ok(runs) => PipelineState {
+                        label,
+                        runs,
+                        error: None,
+                    },
+                    Err(err) => {
+                        log::warn!("ci: can't fetch '{}': {}", label, err);
+                        PipelineState {
+                            label,
+                            runs: Vec::new(),
+                            error: Some(err),
+                        }
+                    }
+                }
+            })
+            .collect();
+        if let Ok(mut guard) = self.state.lock() {
+            *guard = CiState {
+                pipelines,
+                loaded: true,
+            };
+        }
+        mark_dirty(&self.dirty);
+        notify_popup_needs_render(id);
+    }
+}
+
+/// CI status module.
+pub struct CiModule {
+    id: String,
+    format: Template,
+    shared: Arc<Shared>,
+}
+
+impl CiModule {
+    /// Creates a new CI module and starts polling its pipelines.
+    ///
+    /// @param id - Unique module identifier
+    /// @param providers - Pipelines to follow
+    /// @param runs - Recent runs fetched per pipeline
+    /// @param interval - Seconds between polls
+    /// @param format - Summary text after the dots (None = "{passing}/{total}")
+    pub fn new(
+        id: &str,
+        providers: Vec<Box<dyn CiProvider>>,
+        runs: usize,
+        interval: u64,
+        format: Option<&str>,
+    ) -> Self {
+        let shared = Arc::new(Shared {
+            providers,
+            runs,
+            state: Mutex::new(CiState::default()),
+            dirty: AtomicBool::new(true),
+            stop: AtomicBool::new(false),
+        });
+
+        let handle = Arc::clone(&shared);
+        let worker_id = id.to_string();
+        std::thread::spawn(move || {
+            while !handle.stop.load(Ordering::Relaxed) {
+                handle.refresh(&worker_id);
+                sleep_unless_stopped(Duration::from_secs(interval.max(30)), &handle.stop);
+            }
+        });
+
+        Self {
+            id: id.to_string(),
+            format: Template::parse_or_literal(format.unwrap_or(DEFAULT_FORMAT)),
+            shared,
+        }
+    }
+
+    /// Returns a second handle on the same state for the popup registry.
+    pub fn popup_handle(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            format: self.format.clone(),
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    fn state(&self) -> CiState {
+        self.shared
+            .state
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+
+    fn summary_text(&self, summary: Summary) -> String {
+        let count = |n: usize| Some(Value::from(n as f64));
+        self.format.render(&[
+            ("passing", count(summary.passing)),
+            ("failing", count(summary.failing)),
+            ("running", count(summary.running)),
+            ("total", count(summary.total)),
+        ])
+    }
+
+    fn render_run(&self, theme: &Theme, index: usize, label: &str, run: &Run) -> AnyElement {
+        let mut details = vec![label.to_string(), format!("#{}", run.number)];
+        details.extend(run.branch.clone());
+        details.extend(run.started.map(|started| ago(started, Utc::now())));
+
+        let id = self.id.clone();
+        let url = run.url.clone();
+        div()
+            .id(SharedString::from(format!("{}-run-{}", self.id, index)))
+            .h(px(ROW_HEIGHT))
+            .px(px(4.0))
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .rounded(px(4.0))
+            .cursor_pointer()
+            .hover(|s| s.bg(theme.surface_hover))
+            .on_mouse_down(MouseButton::Left, move |_event, _window, _cx| {
+                dispatch_popup_action(&id, PopupAction::Select { value: url.clone() });
+            })
+            .child(
+                div()
+                    .size(px(DOT_SIZE + 1.0))
+                    .rounded_full()
+                    .bg(status_color(theme, Some(run.status))),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .flex()
+                    .flex_col()
+                    .overflow_hidden()
+                    .child(SharedString::from(run.title.clone()))
+                    .child(
+                        div()
+                            .text_size(px(theme.font_size - 2.0))
+                            .text_color(theme.foreground_muted)
+                            .child(SharedString::from(details.join(" · "))),
+                    ),
+            )
+            .child(
+                div()
+                    .text_size(px(theme.font_size - 2.0))
+                    .text_color(status_color(theme, Some(run.status)))
+                    .child(run.status.label()),
+            )
+            .into_any_element()
+    }
+}
+
+impl GpuiModule for CiModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let state = self.state();
+        if !state.loaded {
+            return div()
+                .flex()
+                .items_center()
+                .child(shimmer_skeleton(40.0, 14.0).rounded(4.0).render(theme))
+                .into_any_element();
+        }
+
+        let text = self.summary_text(summarize(&state.pipelines));
+        let mut row = div()
+            .flex()
+            .items_center()
+            .gap(px(4.0))
+            .text_color(theme.foreground)
+            .text_size(px(theme.font_size));
+        for pipeline in &state.pipelines {
+            row = row.child(
+                div()
+                    .size(px(DOT_SIZE))
+                    .rounded_full()
+                    .bg(status_color(theme, pipeline.status())),
+            );
+        }
+        if !text.is_empty() {
+            row = row.child(div().ml(px(2.0)).child(SharedString::from(text)));
+        }
+        row.into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        self.shared.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn is_loading(&self) -> bool {
+        !self.state().loaded
+    }
+
+    fn value(&self) -> Option<u8> {
+        let summary = summarize(&self.state().pipelines);
+        (summary.total > 0).then(|| (summary.passing * 100 / summary.total) as u8)
+    }
+
+    fn display_text(&self) -> Option<String> {
+        let state = self.state();
+        let text = self.summary_text(summarize(&state.pipelines));
+        (state.loaded && !text.is_empty()).then_some(text)
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let state = self.state();
+        let errors = state.pipelines.iter().filter(|p| p.error.is_some()).count();
+        let rows = (recent_runs(&state.pipelines).len() + errors).clamp(1, MAX_ROWS);
+        let height = POPUP_PADDING * 2.0 + HEADER_HEIGHT + rows as f32 * ROW_HEIGHT;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let state = self.state();
+        let summary = summarize(&state.pipelines);
+
+        let header = div()
+            .h(px(HEADER_HEIGHT))
+            .flex()
+            .items_center()
+            .justify_between()
+            .child(div().font_weight(gpui::FontWeight::SEMIBOLD).child("CI"))
+            .child(
+                div()
+                    .text_color(theme.foreground_muted)
+                    .child(SharedString::from(format!(
+                        "{} of {} passing",
+                        summary.passing, summary.total
+                    ))),
+            );
+
+        let mut list = div().flex().flex_col();
+        let message_row = |message: String| {
+            div()
+                .h(px(ROW_HEIGHT))
+                .flex()
+                .items_center()
+                .text_color(theme.foreground_muted)
+                .child(SharedString::from(message))
+        };
+        let runs = recent_runs(&state.pipelines);
+        if !state.loaded {
+            list = list.child(message_row("Loading…".to_string()));
+        } else if runs.is_empty() && state.pipelines.iter().all(|p| p.error.is_none()) {
+            list = list.child(message_row("No runs yet".to_string()));
+        }
+        let mut rows = 0;
+        for pipeline in &state.pipelines {
+            if let Some(err) = &pipeline.error {
+                list = list.child(message_row(format!("{}: {}", pipeline.label, err)));
+                rows += 1;
+            }
+        }
+        for (index, (label, run)) in runs
+            .into_iter()
+            .take(MAX_ROWS.saturating_sub(rows))
+            .enumerate()
+        {
+            list = list.child(self.render_run(theme, index, label, run));
+        }
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(header)
+                .child(list)
+                .into_any_element(),
+        )
+    }
+
+    fn on_popup_action(&mut self, action: PopupAction) {
+        if let PopupAction::Select { value } = action {
+            if value.starts_with("https://") || value.starts_with("http://") {
+                if let Err(err) = Command::new("open").arg(&value).spawn() {
+                    log::warn!("ci: can't open {}: {}", value, err);
+                }
+                request_hide_popup();
+            }
+        }
+    }
+
+    fn on_popup_event(&mut self, event: PopupEvent) {
+        if matches!(event, PopupEvent::Opened) {
+            let shared = Arc::clone(&self.shared);
+            let id = self.id.clone();
+            std::thread::spawn(move || shared.refresh(&id));
+        }
+    }
+}
+
+impl Drop for CiModule {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn run(number: u64, status: Status, started: Option<i64>) -> Run {
+        Run {
+            number,
+            title: format!("run {}", number),
+            branch: None,
+            status,
+            url: format!("https://ci.example.com/{}", number),
+            started: started.and_then(|secs| Utc.timestamp_opt(secs, 0).single()),
+        }
+    }
+
+    fn pipeline(label: &str, runs: Vec<Run>) -> PipelineState {
+        PipelineState {
+            label: label.to_string(),
+            runs,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn parses_pipelines() {
+        let value: toml::Value = toml::from_str(
+            r#"pipelines = [
+                { provider = "github", repo = "acme/web", branch = "main" },
+                { provider = "buildkite", org = "acme", pipeline = "api" },
+                { provider = "jenkins", url = "https://ci.acme.dev/job/deploy" },
+                { provider = "travis", repo = "acme/old" },
+                { repo = "acme/none" },
+            ]"#,
+        )
+        .unwrap();
+        let labels = |token: Option<&str>| -> Vec<String> {
+            parse_pipelines("ci", value.get("pipelines"), token)
+                .iter()
+                .map(|p| p.label())
+                .collect()
+        };
+        // Buildkite needs a token
+        assert_eq!(labels(None), ["acme/web · main", "deploy"]);
+        assert_eq!(labels(Some("t0ken")), ["acme/web · main", "api", "deploy"]);
+        assert!(parse_pipelines("ci", None, None).is_empty());
+    }
+
+    #[test]
+    fn summarizes_latest_runs() {
+        let pipelines = [
+            pipeline(
+                "a",
+                vec![run(2, Status::Passed, None), run(1, Status::Failed, None)],
+            ),
+            pipeline("b", vec![run(5, Status::Failed, None)]),
+            pipeline("c", vec![run(9, Status::Pending, None)]),
+            pipeline("d", Vec::new()),
+        ];
+        assert_eq!(
+            summarize(&pipelines),
+            Summary {
+                passing: 1,
+                failing: 1,
+                running: 1,
+                total: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn lists_recent_runs_newest_first() {
+        let pipelines = [
+            pipeline(
+                "web",
+                vec![
+                    run(2, Status::Running, Some(300)),
+                    run(1, Status::Passed, Some(100)),
+                ],
+            ),
+            pipeline(
+                "api",
+                vec![
+                    run(8, Status::Pending, None),
+                    run(7, Status::Failed, Some(200)),
+                ],
+            ),
+        ];
+        let order: Vec<(&str, u64)> = recent_runs(&pipelines)
+            .into_iter()
+            .map(|(label, run)| (label, run.number))
+            .collect();
+        assert_eq!(order, [("api", 8), ("web", 2), ("api", 7), ("web", 1)]);
+    }
+
+    #[test]
+    fn formats_run_age() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 0).unwrap();
+        let minutes_ago = |m: i64| ago(now - chrono::Duration::minutes(m), now);
+        assert_eq!(minutes_ago(0), "now");
+        assert_eq!(minutes_ago(5), "5m ago");
+        assert_eq!(minutes_ago(180), "3h ago");
+        assert_eq!(minutes_ago(3000), "2d ago");
+        assert_eq!(ago(now + chrono::Duration::minutes(5), now), "now");
+    }
+}
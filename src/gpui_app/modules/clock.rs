@@ -1,27 +1,150 @@
 //! Clock module for displaying time.
+//!
+//! `zones` shows one clock per timezone in the same item, each prefixed
+//! with its city name unless `format` places `{zone}` itself. `blink`
+//! flashes the colons every second. With `toggle_hour12`, clicking switches
+//! between 24-hour and 12-hour time; `hour12` sets the starting mode.
+//!
+//! `sinew-msg set <id> time=<RFC 3339>` stops the clock at that time, and
+//! `hour12=true|false` switches the mode.
+//!
+//! ```toml
+//! { type = "clock", format = "%H:%M", zones = ["Local", "UTC", "Asia/Tokyo"], blink = true }
+//! ```
 
-use chrono::Local;
+use std::time::Duration;
+
+use chrono::{DateTime, Timelike, Utc};
 use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
 
-use super::GpuiModule;
+use super::template::{Template, Value};
+use super::timezone::TimeZone;
+use super::{until_next_second, GpuiModule, MouseEvent};
 use crate::gpui_app::theme::Theme;
 
-/// Clock module that displays the current time.
+/// Rewrites a strftime format for 12-hour time: `%H` becomes `%-I` and
+/// `%p` follows the minutes or seconds, unless the format already has it.
+pub fn twelve_hour(format: &str) -> String {
+    let has_hours = ["%H", "%k", "%R", "%T"].iter().any(|t| format.contains(t));
+    if !has_hours {
+        return format.to_string();
+    }
+    let mut out = format
+        .replace("%H", "%-I")
+        .replace("%k", "%-I")
+        .replace("%R", "%-I:%M")
+        .replace("%T", "%-I:%M:%S");
+    if !out.contains("%p") && !out.contains("%P") {
+        let end = ["%S", "%M", "%-I"]
+            .iter()
+            .find_map(|t| out.rfind(t).map(|i| i + t.len()));
+        if let Some(end) = end {
+            out.insert_str(end, " %p");
+        }
+    }
+    out
+}
+
+/// Clock module that displays the current time in one or more timezones.
 pub struct ClockModule {
     id: String,
-    format: String,
-    text: String,
+    /// `format` as configured, in 24-hour time
+    raw_format: String,
+    format: Template,
+    zones: Vec<TimeZone>,
+    /// Show city names before each clock
+    show_labels: bool,
+    blink: bool,
+    hour12: bool,
+    toggle_hour12: bool,
+    /// One rendered time per zone
+    texts: Vec<String>,
+    colons_hidden: bool,
+    /// Time set over IPC, shown instead of the current one
+    frozen: Option<DateTime<Utc>>,
 }
 
 impl ClockModule {
     /// Creates a new clock module.
-    pub fn new(id: &str, format: &str) -> Self {
-        let text = Local::now().format(format).to_string();
-        Self {
+    ///
+    /// @param id - Unique module identifier
+    /// @param format - strftime string, or a template using `{now:FORMAT}` and `{zone}`
+    /// @param zones - Timezones to show (empty = local time only)
+    /// @param blink - Flash the colons every second
+    /// @param hour12 - Start in 12-hour time
+    /// @param toggle_hour12 - Clicking switches between 12 and 24-hour time
+    pub fn new(
+        id: &str,
+        format: &str,
+        zones: Vec<TimeZone>,
+        blink: bool,
+        hour12: bool,
+        toggle_hour12: bool,
+    ) -> Self {
+        let zones = if zones.is_empty() {
+            vec![TimeZone::Local]
+        } else {
+            zones
+        };
+        let show_labels = zones != [TimeZone::Local] && !format.contains("{zone}");
+        let mut module = Self {
             id: id.to_string(),
-            format: format.to_string(),
-            text,
+            raw_format: format.to_string(),
+            format: Template::for_time(format),
+            zones,
+            show_labels,
+            blink,
+            hour12: false,
+            toggle_hour12,
+            texts: Vec::new(),
+            colons_hidden: false,
+            frozen: None,
+        };
+        module.set_hour12(hour12);
+        module.texts = module.render_texts();
+        module
+    }
+
+    fn set_hour12(&mut self, hour12: bool) {
+        self.hour12 = hour12;
+        self.format = if hour12 {
+            Template::for_time(&twelve_hour(&self.raw_format))
+        } else {
+            Template::for_time(&self.raw_format)
+        };
+    }
+
+    fn render_texts(&self) -> Vec<String> {
+        let utc = self.frozen.unwrap_or_else(Utc::now);
+        self.zones
+            .iter()
+            .map(|zone| {
+                self.format.render(&[
+                    ("now", Some(Value::Time(zone.at(utc)))),
+                    ("zone", Some(Value::from(zone.label()))),
+                ])
+            })
+            .collect()
+    }
+
+    /// Renders a time, hiding its colons while they're blinked off.
+    fn render_time(&self, text: &str) -> gpui::Div {
+        if !self.blink {
+            return div().child(SharedString::from(text.to_string()));
         }
+        let mut row = div().flex();
+        for (i, segment) in text.split(':').enumerate() {
+            if i > 0 {
+                let colon = div().child(":");
+                row = row.child(if self.colons_hidden {
+                    colon.opacity(0.0)
+                } else {
+                    colon
+                });
+            }
+            row = row.child(SharedString::from(segment.to_string()));
+        }
+        row
     }
 }
 
@@ -31,22 +154,84 @@ impl GpuiModule for ClockModule {
     }
 
     fn render(&self, theme: &Theme) -> AnyElement {
+        let clocks = self.zones.iter().zip(&self.texts).map(|(zone, text)| {
+            let mut clock = div().flex().items_center().gap(px(4.0));
+            if self.show_labels {
+                clock = clock.child(
+                    div()
+                        .text_color(theme.foreground_muted)
+                        .child(SharedString::from(zone.label())),
+                );
+            }
+            clock.child(self.render_time(text))
+        });
         div()
             .flex()
             .items_center()
+            .gap(px(10.0))
             .text_color(theme.foreground)
             .text_size(px(theme.font_size))
-            .child(SharedString::from(self.text.clone()))
+            .children(clocks)
             .into_any_element()
     }
 
     fn update(&mut self) -> bool {
-        let new_text = Local::now().format(&self.format).to_string();
-        if new_text != self.text {
-            self.text = new_text;
-            true
-        } else {
-            false
+        let mut changed = false;
+        if self.blink {
+            let hidden = Utc::now().second() % 2 == 1;
+            changed = hidden != self.colons_hidden;
+            self.colons_hidden = hidden;
         }
+        let texts = self.render_texts();
+        if texts != self.texts {
+            self.texts = texts;
+            changed = true;
+        }
+        changed
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        Some(until_next_second())
+    }
+
+    fn copy_text(&self) -> Option<String> {
+        Some(self.texts.join(" "))
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        match key {
+            "time" => match DateTime::parse_from_rfc3339(value) {
+                Ok(time) => self.frozen = Some(time.with_timezone(&Utc)),
+                Err(_) => return false,
+            },
+            "hour12" => self.set_hour12(matches!(value, "on" | "true" | "1")),
+            _ => return false,
+        }
+        self.texts = self.render_texts();
+        true
+    }
+
+    fn on_mouse_event(&mut self, event: MouseEvent) -> bool {
+        if !self.toggle_hour12 || !matches!(event, MouseEvent::Click) {
+            return false;
+        }
+        self.set_hour12(!self.hour12);
+        self.texts = self.render_texts();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn twelve_hour_rewrites_hours_and_adds_meridiem() {
+        assert_eq!(twelve_hour("%H:%M"), "%-I:%M %p");
+        assert_eq!(twelve_hour("%a %b %d  %H:%M:%S"), "%a %b %d  %-I:%M:%S %p");
+        assert_eq!(twelve_hour("%R"), "%-I:%M %p");
+        assert_eq!(twelve_hour("{now:%H:%M} UTC"), "{now:%-I:%M %p} UTC");
+        assert_eq!(twelve_hour("%H:%M %p"), "%-I:%M %p");
+        assert_eq!(twelve_hour("%a %d"), "%a %d");
     }
 }
@@ -0,0 +1,194 @@
+//! Badge shown in the bar while the config has errors.
+//!
+//! When config.toml (or a file it includes) fails to parse or validate,
+//! the bar keeps running on the previous config, or on the defaults at
+//! startup, and puts this badge first in the far-left zone. Its popup lists
+//! each error with its file, line and column and the offending line. The
+//! badge is gone after the next reload that succeeds.
+
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+
+use super::{register_popup_module, GpuiModule, PopupConfig, PopupSpec, PositionedModule};
+use crate::config::ConfigError;
+use crate::gpui_app::primitives::icons;
+use crate::gpui_app::theme::Theme;
+
+const ID: &str = "config-error";
+const POPUP_WIDTH: f64 = 460.0;
+const POPUP_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 40.0;
+const LINE_HEIGHT: f32 = 18.0;
+/// Characters of message per popup line, for sizing
+const CHARS_PER_LINE: usize = 60;
+const CODE_FONT: &str = "Menlo";
+
+/// Lists config errors in a popup behind a bar badge.
+pub struct ConfigErrorModule {
+    id: String,
+    errors: Vec<ConfigError>,
+}
+
+impl ConfigErrorModule {
+    fn new(errors: Vec<ConfigError>) -> Self {
+        Self {
+            id: ID.to_string(),
+            errors,
+        }
+    }
+
+    /// Height of one error in the popup: location, message and snippet.
+    fn error_height(error: &ConfigError) -> f32 {
+        let message_lines = error
+            .message
+            .chars()
+            .count()
+            .div_ceil(CHARS_PER_LINE)
+            .max(1);
+        let snippet_lines = match (&error.snippet, error.column) {
+            (Some(_), Some(_)) => 2,
+            (Some(_), None) => 1,
+            (None, _) => 0,
+        };
+        let code = if snippet_lines > 0 {
+            snippet_lines as f32 * LINE_HEIGHT + 12.0
+        } else {
+            0.0
+        };
+        LINE_HEIGHT * (1 + message_lines) as f32 + code + 10.0
+    }
+
+    fn render_error(error: &ConfigError, theme: &Theme) -> AnyElement {
+        let location = match (&error.location, error.column) {
+            (Some(location), Some(column)) => {
+                format!("{}:{}:{}", location.file, location.line, column)
+            }
+            (Some(location), None) => format!("{}:{}", location.file, location.line),
+            (None, _) => "config".to_string(),
+        };
+        let mut item = div()
+            .flex()
+            .flex_col()
+            .gap(px(2.0))
+            .pb(px(10.0))
+            .child(
+                div()
+                    .text_size(px(theme.font_size - 1.0))
+                    .text_color(theme.foreground_muted)
+                    .child(SharedString::from(location)),
+            )
+            .child(SharedString::from(error.message.clone()));
+
+        if let (Some(location), Some(snippet)) = (&error.location, &error.snippet) {
+            let gutter = location.line.to_string();
+            let mut code = div()
+                .mt(px(4.0))
+                .p(px(6.0))
+                .rounded(px(4.0))
+                .bg(theme.surface)
+                .font_family(CODE_FONT)
+                .text_size(px(theme.font_size - 1.0))
+                .whitespace_nowrap()
+                .overflow_hidden()
+                .child(SharedString::from(format!("{} | {}", gutter, snippet)));
+            if let Some(column) = error.column {
+                code = code.child(
+                    div()
+                        .text_color(theme.destructive)
+                        .child(SharedString::from(format!(
+                            "{} | {}^",
+                            " ".repeat(gutter.len()),
+                            " ".repeat(column.saturating_sub(1))
+                        ))),
+                );
+            }
+            item = item.child(code);
+        }
+        item.into_any_element()
+    }
+}
+
+impl GpuiModule for ConfigErrorModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        div()
+            .flex()
+            .items_center()
+            .gap(px(4.0))
+            .px(px(6.0))
+            .rounded(px(4.0))
+            .bg(theme.with_alpha(theme.destructive, 0.2))
+            .text_color(theme.destructive)
+            .text_size(px(theme.font_size))
+            .child(icons::system::ALERT)
+            .child("Config")
+            .into_any_element()
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let errors: f32 = self.errors.iter().map(Self::error_height).sum();
+        let height = POPUP_PADDING * 2.0 + HEADER_HEIGHT + errors;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let title = match self.errors.len() {
+            1 => "1 config error".to_string(),
+            count => format!("{} config errors", count),
+        };
+        let header = div()
+            .h(px(HEADER_HEIGHT))
+            .flex()
+            .flex_col()
+            .child(
+                div()
+                    .font_weight(gpui::FontWeight::SEMIBOLD)
+                    .text_color(theme.destructive)
+                    .child(SharedString::from(title)),
+            )
+            .child(
+                div()
+                    .text_size(px(theme.font_size - 1.0))
+                    .text_color(theme.foreground_muted)
+                    .child("Until the file is fixed, the bar uses the last config that loaded"),
+            );
+
+        let mut list = div().flex().flex_col();
+        for error in &self.errors {
+            list = list.child(Self::render_error(error, theme));
+        }
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(header)
+                .child(list)
+                .into_any_element(),
+        )
+    }
+}
+
+/// Returns the badge for the errors of the last config load, or None when
+/// it loaded cleanly.
+pub fn badge() -> Option<PositionedModule> {
+    let errors = crate::config::load_errors();
+    if errors.is_empty() {
+        return None;
+    }
+    register_popup_module(ConfigErrorModule::new(errors.clone()));
+    let popup = PopupConfig {
+        popup_type: Some(ID.to_string()),
+        max_height_percent: 80.0,
+        ..Default::default()
+    };
+    Some(PositionedModule::internal(
+        Box::new(ConfigErrorModule::new(errors)),
+        popup,
+    ))
+}
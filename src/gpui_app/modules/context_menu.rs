@@ -0,0 +1,324 @@
+//! Right-click context menu of bar modules.
+//!
+//! Right-clicking a module without a `right_click_command` opens a small
+//! popup with the module's own `menu` entries followed by the built-in
+//! actions: refresh it now, copy its value, open the config at its section,
+//! and snooze it for a while, until the next reload or until Sinew restarts.
+//!
+//! ```toml
+//! { type = "cpu", menu = [{ label = "Activity Monitor", command = "open -a 'Activity Monitor'" }] }
+//! ```
+
+use std::process::Command;
+use std::sync::RwLock;
+use std::time::Instant;
+
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+
+use super::emoji::copy_to_clipboard;
+use super::vpn::shell_quote;
+use super::{get_module, GpuiModule, PopupAction, PopupSpec};
+use crate::config::MenuItemConfig;
+use crate::gpui_app::popup_manager::{self, request_hide_popup};
+use crate::gpui_app::primitives::Button;
+use crate::gpui_app::snooze::{self, Until};
+use crate::gpui_app::theme::{ButtonVariant, Theme};
+
+const POPUP_WIDTH: f32 = 200.0;
+const POPUP_PADDING: f32 = 6.0;
+const ROW_HEIGHT: f32 = 26.0;
+const DIVIDER_HEIGHT: f32 = 9.0;
+/// Property the bar sets to the text "Copy value" copies
+const TEXT_PROPERTY: &str = "text";
+/// Snooze choices, as `snooze::Until::parse` reads them, and their labels
+const SNOOZES: [(&str, &str); 4] = [
+    ("15", "Snooze for 15 minutes"),
+    ("60", "Snooze for 1 hour"),
+    ("reload", "Hide until reload"),
+    ("restart", "Hide until restart"),
+];
+
+/// `bar.editor`
+static EDITOR: RwLock<Option<String>> = RwLock::new(None);
+
+/// One row of the menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Item {
+    /// A `menu` entry, by index
+    Entry(usize),
+    Refresh,
+    Copy,
+    OpenConfig,
+    /// A snooze choice, by index into `SNOOZES`
+    Snooze(usize),
+}
+
+impl Item {
+    /// Value passed back in `PopupAction::Select`.
+    fn value(self) -> String {
+        match self {
+            Self::Entry(index) => format!("entry:{}", index),
+            Self::Refresh => "refresh".to_string(),
+            Self::Copy => "copy".to_string(),
+            Self::OpenConfig => "config".to_string(),
+            Self::Snooze(index) => format!("snooze:{}", SNOOZES[index].0),
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "refresh" => Some(Self::Refresh),
+            "copy" => Some(Self::Copy),
+            "config" => Some(Self::OpenConfig),
+            _ => {
+                if let Some(snooze) = value.strip_prefix("snooze:") {
+                    let index = SNOOZES.iter().position(|(until, _)| *until == snooze)?;
+                    return Some(Self::Snooze(index));
+                }
+                value.strip_prefix("entry:")?.parse().ok().map(Self::Entry)
+            }
+        }
+    }
+}
+
+/// Rows for `entries` menu entries; "Copy value" only with something to copy.
+fn items(entries: usize, copyable: bool) -> Vec<Item> {
+    let mut items: Vec<Item> = (0..entries).map(Item::Entry).collect();
+    items.push(Item::Refresh);
+    if copyable {
+        items.push(Item::Copy);
+    }
+    items.push(Item::OpenConfig);
+    items.extend((0..SNOOZES.len()).map(Item::Snooze));
+    items
+}
+
+/// Command that opens config file `file` at `line`: `editor` with
+/// `{file}` and `{line}` replaced, or the default text editor.
+fn editor_command(editor: Option<&str>, file: &str, line: Option<usize>) -> String {
+    match editor {
+        Some(editor) => editor
+            .replace("{file}", &shell_quote(file))
+            .replace("{line}", &line.unwrap_or(1).to_string()),
+        None => format!("open -t {}", shell_quote(file)),
+    }
+}
+
+/// Applies `bar.editor`.
+pub fn set_editor(editor: Option<String>) {
+    if let Ok(mut current) = EDITOR.write() {
+        *current = editor;
+    }
+}
+
+/// Id the context menu of module `id` is registered under.
+pub fn menu_id(id: &str) -> String {
+    format!("{}.menu", id)
+}
+
+/// Opens (or closes) the context menu of module `id`, offering `text` to
+/// copy. The caller records the popup anchor.
+pub fn toggle(id: &str, text: Option<String>) {
+    let menu = menu_id(id);
+    if let Some(module) = get_module(&menu) {
+        if let Ok(mut guard) = module.write() {
+            guard.set_property(TEXT_PROPERTY, text.as_deref().unwrap_or_default());
+        }
+    }
+    popup_manager::record_popup_click(&menu);
+    popup_manager::toggle_popup(&menu);
+}
+
+/// Runs a shell command in the background.
+fn run(command: &str) {
+    if let Err(err) = Command::new("sh").args(["-c", command]).spawn() {
+        log::warn!("context menu: failed to run '{}': {}", command, err);
+    }
+}
+
+/// Context menu popup of one module.
+pub struct ContextMenuModule {
+    id: String,
+    /// The module the menu belongs to
+    target: String,
+    entries: Vec<MenuItemConfig>,
+    /// Where the module is set in the config, e.g. `modules.left.left[2]`
+    config_path: Option<String>,
+    /// What "Copy value" copies, as of the right-click
+    text: Option<String>,
+}
+
+impl ContextMenuModule {
+    /// Creates the menu of module `target` with its `menu` entries.
+    pub fn new(target: &str, entries: Vec<MenuItemConfig>, config_path: Option<String>) -> Self {
+        Self {
+            id: menu_id(target),
+            target: target.to_string(),
+            entries,
+            config_path,
+            text: None,
+        }
+    }
+
+    fn items(&self) -> Vec<Item> {
+        items(self.entries.len(), self.text.is_some())
+    }
+
+    fn label(&self, item: Item) -> String {
+        match item {
+            Item::Entry(index) => self.entries[index].label.clone(),
+            Item::Refresh => "Refresh now".to_string(),
+            Item::Copy => "Copy value".to_string(),
+            Item::OpenConfig => "Open config".to_string(),
+            Item::Snooze(index) => SNOOZES[index].1.to_string(),
+        }
+    }
+
+    fn open_config(&self) {
+        let file = crate::config::get_config_path();
+        let line = self
+            .config_path
+            .as_deref()
+            .and_then(crate::config::config_line);
+        let editor = EDITOR.read().ok().and_then(|editor| editor.clone());
+        run(&editor_command(
+            editor.as_deref(),
+            &file.display().to_string(),
+            line,
+        ));
+    }
+}
+
+impl GpuiModule for ContextMenuModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, _theme: &Theme) -> AnyElement {
+        div().into_any_element()
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        if key != TEXT_PROPERTY {
+            return false;
+        }
+        self.text = (!value.is_empty()).then(|| value.to_string());
+        true
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let divider = if self.entries.is_empty() {
+            0.0
+        } else {
+            DIVIDER_HEIGHT
+        };
+        let height = POPUP_PADDING * 2.0 + ROW_HEIGHT * self.items().len() as f32 + divider;
+        Some(PopupSpec::new(POPUP_WIDTH as f64, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let mut menu = div()
+            .flex()
+            .flex_col()
+            .p(px(POPUP_PADDING))
+            .text_size(px(theme.font_size))
+            .text_color(theme.foreground);
+        for item in self.items() {
+            // The built-in actions follow the entries after a divider
+            if item == Item::Refresh && !self.entries.is_empty() {
+                menu = menu.child(
+                    div()
+                        .h(px(DIVIDER_HEIGHT))
+                        .flex()
+                        .items_center()
+                        .child(div().w_full().h(px(1.0)).bg(theme.border)),
+                );
+            }
+            let value = item.value();
+            menu = menu.child(
+                Button::new(SharedString::from(format!("{}-{}", self.id, value)))
+                    .label(self.label(item))
+                    .variant(ButtonVariant::Ghost)
+                    .on_popup_action(self.id.clone(), PopupAction::Select { value })
+                    .render(theme)
+                    .h(px(ROW_HEIGHT))
+                    .justify_start(),
+            );
+        }
+        Some(menu.into_any_element())
+    }
+
+    fn on_popup_action(&mut self, action: PopupAction) {
+        let PopupAction::Select { value } = action else {
+            return;
+        };
+        match Item::parse(&value) {
+            Some(Item::Entry(index)) => {
+                if let Some(entry) = self.entries.get(index) {
+                    run(&entry.command);
+                }
+            }
+            Some(Item::Refresh) => crate::ipc::trigger_module(&self.target, "update"),
+            Some(Item::Copy) => {
+                if let Some(text) = self.text.clone() {
+                    std::thread::spawn(move || copy_to_clipboard(&text));
+                }
+            }
+            Some(Item::OpenConfig) => self.open_config(),
+            Some(Item::Snooze(index)) => {
+                if let Some(until) = Until::parse(SNOOZES[index].0, Instant::now()) {
+                    snooze::snooze(&self.target, until);
+                }
+            }
+            None => return,
+        }
+        request_hide_popup();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_entries_before_built_in_actions() {
+        assert_eq!(
+            items(2, false),
+            vec![
+                Item::Entry(0),
+                Item::Entry(1),
+                Item::Refresh,
+                Item::OpenConfig,
+                Item::Snooze(0),
+                Item::Snooze(1),
+                Item::Snooze(2),
+                Item::Snooze(3),
+            ]
+        );
+        assert!(items(0, true).contains(&Item::Copy));
+        for item in items(3, true) {
+            assert_eq!(Item::parse(&item.value()), Some(item));
+        }
+        assert_eq!(Item::parse("entry:x"), None);
+        assert_eq!(Item::parse("snooze:5"), None);
+        for (until, _) in SNOOZES {
+            assert!(Until::parse(until, Instant::now()).is_some());
+        }
+    }
+
+    #[test]
+    fn builds_editor_commands() {
+        assert_eq!(
+            editor_command(None, "/Users/me/config.toml", Some(4)),
+            "open -t '/Users/me/config.toml'"
+        );
+        assert_eq!(
+            editor_command(Some("zed {file}:{line}"), "/tmp/it's.toml", Some(12)),
+            r"zed '/tmp/it'\''s.toml':12"
+        );
+        assert_eq!(
+            editor_command(Some("code -g {file}:{line}"), "/c.toml", None),
+            "code -g '/c.toml':1"
+        );
+    }
+}
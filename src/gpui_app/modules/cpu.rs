@@ -1,67 +1,36 @@
-//! CPU module for displaying CPU usage via Mach host_statistics.
+//! CPU module for displaying CPU usage from the shared metrics service.
+//!
+//! Clicking opens per-core usage bars, grouped into efficiency and
+//! performance cores on Apple Silicon, with each cluster's top clock and
+//! the load averages. Per-core sampling only runs while the popup is open.
+//!
+//! `format` is a template (see `template.rs`) with `{value}`, the usage in
+//! percent. `sinew-msg set <id> value=<0-100>` shows a fixed usage instead of
+//! the sampled one until the module is recreated.
+//!
+//! ```toml
+//! { type = "cpu", format = "CPU {value:>3}%" }
+//! ```
 
 use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+use gpui::{div, prelude::*, px, AnyElement, Div, SharedString, Styled};
 
-use super::{GpuiModule, LabelAlign};
+use super::template::Template;
+use super::{mark_dirty, GpuiModule, LabelAlign, PopupEvent, PopupSpec, RuleColors};
+use crate::gpui_app::popup_manager::notify_popup_needs_render;
+use crate::gpui_app::primitives::{Chart, ChartKind};
 use crate::gpui_app::theme::Theme;
+use crate::system::metrics::{self, CpuCluster, CpuDetails, Metric, Subscription};
 
-/// Mach host_statistics FFI for CPU ticks (no process spawn needed).
-mod mach_cpu {
-    use std::ffi::c_uint;
-    use std::mem::MaybeUninit;
-
-    const HOST_CPU_LOAD_INFO: c_uint = 3;
-    const CPU_STATE_USER: usize = 0;
-    const CPU_STATE_SYSTEM: usize = 1;
-    const CPU_STATE_IDLE: usize = 2;
-    const CPU_STATE_NICE: usize = 3;
-    const CPU_STATE_MAX: usize = 4;
-
-    #[repr(C)]
-    struct HostCpuLoadInfo {
-        cpu_ticks: [u32; CPU_STATE_MAX],
-    }
-
-    extern "C" {
-        fn mach_host_self() -> c_uint;
-        fn host_statistics(
-            host: c_uint,
-            flavor: c_uint,
-            info: *mut HostCpuLoadInfo,
-            count: *mut c_uint,
-        ) -> c_uint;
-    }
-
-    /// Returns cumulative (active_ticks, total_ticks).
-    pub fn cpu_ticks() -> Option<(u64, u64)> {
-        unsafe {
-            let mut info = MaybeUninit::<HostCpuLoadInfo>::uninit();
-            let mut count =
-                (std::mem::size_of::<HostCpuLoadInfo>() / std::mem::size_of::<u32>()) as c_uint;
-            let status = host_statistics(
-                mach_host_self(),
-                HOST_CPU_LOAD_INFO,
-                info.as_mut_ptr(),
-                &mut count,
-            );
-            if status != 0 {
-                return None;
-            }
-            let info = info.assume_init();
-            let user = info.cpu_ticks[CPU_STATE_USER] as u64;
-            let system = info.cpu_ticks[CPU_STATE_SYSTEM] as u64;
-            let idle = info.cpu_ticks[CPU_STATE_IDLE] as u64;
-            let nice = info.cpu_ticks[CPU_STATE_NICE] as u64;
-            let total = user + system + idle + nice;
-            let active = user + system + nice;
-            Some((active, total))
-        }
-    }
-}
+const POPUP_WIDTH: f64 = 300.0;
+const POPUP_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 28.0;
+const ROW_HEIGHT: f32 = 22.0;
+const CHART_HEIGHT: f32 = 48.0;
+const SECTION_GAP: f32 = 8.0;
 
 /// CPU module that displays CPU usage percentage.
 pub struct CpuModule {
@@ -69,56 +38,159 @@ pub struct CpuModule {
     label: Option<String>,
     label_align: LabelAlign,
     fixed_width: bool,
+    format: Template,
     usage: Arc<AtomicU8>,
+    /// Usage set over IPC, shown instead of the sampled one
+    fixed_usage: Arc<Mutex<Option<u8>>>,
+    details: Arc<Mutex<Option<CpuDetails>>>,
+    /// Per-core subscription, held while the popup is open
+    cores: Arc<Mutex<Option<Subscription>>>,
     dirty: Arc<AtomicBool>,
-    stop: Arc<AtomicBool>,
+    /// Value color from `color_rules`
+    rule_colors: RuleColors,
+    /// Shared with the popup copy; dropping the last one unsubscribes
+    _metrics: Arc<Subscription>,
 }
 
 impl CpuModule {
     /// Creates a new CPU module.
-    pub fn new(id: &str, label: Option<&str>, label_align: LabelAlign, fixed_width: bool) -> Self {
+    ///
+    /// @param id - Unique module identifier
+    /// @param label - Optional label above the value
+    /// @param label_align - Alignment of the label and value
+    /// @param fixed_width - Reserve room for "100%" to avoid reflow
+    /// @param format - Value template (None = "{value}%")
+    pub fn new(
+        id: &str,
+        label: Option<&str>,
+        label_align: LabelAlign,
+        fixed_width: bool,
+        format: Option<&str>,
+    ) -> Self {
         let usage = Arc::new(AtomicU8::new(0));
         let dirty = Arc::new(AtomicBool::new(true));
-        let stop = Arc::new(AtomicBool::new(false));
 
         let usage_handle = Arc::clone(&usage);
         let dirty_handle = Arc::clone(&dirty);
-        let stop_handle = Arc::clone(&stop);
-        std::thread::spawn(move || {
-            let mut prev_ticks: Option<(u64, u64)> = None;
-            let mut last = 0u8;
-            while !stop_handle.load(Ordering::Relaxed) {
-                if let Some(current) = mach_cpu::cpu_ticks() {
-                    if let Some(prev) = prev_ticks {
-                        let d_active = current.0.saturating_sub(prev.0);
-                        let d_total = current.1.saturating_sub(prev.1);
-                        let pct = if d_total > 0 {
-                            ((d_active as f64 / d_total as f64) * 100.0).round() as u8
-                        } else {
-                            0
-                        };
-                        if pct != last {
-                            usage_handle.store(pct, Ordering::Relaxed);
-                            dirty_handle.store(true, Ordering::Relaxed);
-                            last = pct;
-                        }
+        let subscription =
+            metrics::subscribe(Metric::Cpu, Duration::from_secs(2), move |snapshot| {
+                if let Some(pct) = snapshot.cpu {
+                    if usage_handle.swap(pct, Ordering::Relaxed) != pct {
+                        mark_dirty(&dirty_handle);
                     }
-                    prev_ticks = Some(current);
                 }
-                std::thread::sleep(Duration::from_secs(2));
-            }
-        });
+            });
 
         Self {
             id: id.to_string(),
             label: label.map(|s| s.to_string()),
             label_align,
             fixed_width,
+            format: Template::parse_or_literal(format.unwrap_or("{value}%")),
             usage,
+            fixed_usage: Arc::new(Mutex::new(None)),
+            details: Arc::new(Mutex::new(None)),
+            cores: Arc::new(Mutex::new(None)),
             dirty,
-            stop,
+            rule_colors: RuleColors::default(),
+            _metrics: Arc::new(subscription),
+        }
+    }
+
+    /// Returns a second handle on the same state for the popup registry.
+    pub fn popup_handle(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            label: self.label.clone(),
+            label_align: self.label_align,
+            fixed_width: self.fixed_width,
+            format: self.format.clone(),
+            usage: Arc::clone(&self.usage),
+            fixed_usage: Arc::clone(&self.fixed_usage),
+            details: Arc::clone(&self.details),
+            cores: Arc::clone(&self.cores),
+            dirty: Arc::clone(&self.dirty),
+            rule_colors: self.rule_colors,
+            _metrics: Arc::clone(&self._metrics),
+        }
+    }
+
+    /// Samples per-core usage until the popup closes.
+    fn watch_cores(&self) {
+        if let Some(details) = metrics::latest().and_then(|s| s.cpu_details) {
+            if let Ok(mut guard) = self.details.lock() {
+                *guard = Some(details);
+            }
+        }
+        let details = Arc::clone(&self.details);
+        let id = self.id.clone();
+        let subscription =
+            metrics::subscribe(Metric::CpuCores, Duration::from_secs(1), move |snapshot| {
+                if let Ok(mut guard) = details.lock() {
+                    *guard = snapshot.cpu_details.clone();
+                }
+                notify_popup_needs_render(&id);
+            });
+        if let Ok(mut guard) = self.cores.lock() {
+            *guard = Some(subscription);
         }
     }
+
+    /// Cluster title with core count and top clock, over a bar per core.
+    fn render_cluster(theme: &Theme, cluster: Option<&CpuCluster>, cores: &[f32]) -> Div {
+        let title = match cluster {
+            Some(cluster) if metrics::cpu_clusters().len() > 1 => {
+                format!("{} · {} cores", cluster.name, cores.len())
+            }
+            _ => format!("{} cores", cores.len()),
+        };
+        let clock = cluster
+            .and_then(|c| c.max_frequency)
+            .map(|hz| format!("up to {:.2} GHz", hz as f64 / 1e9))
+            .unwrap_or_default();
+        let color = match cluster.map(|c| c.name.as_str()) {
+            Some("Efficiency") => theme.success,
+            _ => theme.accent,
+        };
+        div()
+            .flex()
+            .flex_col()
+            .child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(SharedString::from(title))
+                    .child(
+                        div()
+                            .text_color(theme.foreground_muted)
+                            .child(SharedString::from(clock)),
+                    ),
+            )
+            .child(
+                Chart::new(ChartKind::Bar, cores.to_vec())
+                    .size(POPUP_WIDTH as f32 - POPUP_PADDING * 2.0, CHART_HEIGHT)
+                    .range(Some(0.0), Some(100.0))
+                    .unit("%")
+                    .color(color)
+                    .render(theme),
+            )
+    }
+
+    /// Usage in percent, fixed over IPC or sampled.
+    fn usage(&self) -> u8 {
+        self.fixed_usage
+            .lock()
+            .ok()
+            .and_then(|fixed| *fixed)
+            .unwrap_or_else(|| self.usage.load(Ordering::Relaxed))
+    }
+
+    /// Usage rendered through `format`.
+    fn text(&self) -> String {
+        self.format.render(&[("value", Some(self.usage().into()))])
+    }
 }
 
 impl GpuiModule for CpuModule {
@@ -127,8 +199,8 @@ impl GpuiModule for CpuModule {
     }
 
     fn render(&self, theme: &Theme) -> AnyElement {
-        let usage = self.usage.load(Ordering::Relaxed);
-        let text = format!("{}%", usage);
+        let text = self.text();
+        let color = self.rule_colors.text.unwrap_or(theme.foreground);
 
         if let Some(ref label) = self.label {
             // Two-line layout with label - configurable alignment
@@ -157,7 +229,7 @@ impl GpuiModule for CpuModule {
                         .min_w(px(if self.fixed_width { value_width } else { 0.0 }))
                         .flex()
                         .justify_end()
-                        .text_color(theme.foreground)
+                        .text_color(color)
                         .text_size(px(theme.font_size * 0.85))
                         .line_height(px(theme.font_size * 0.9))
                         .child(SharedString::from(text)),
@@ -167,7 +239,7 @@ impl GpuiModule for CpuModule {
             div()
                 .flex()
                 .items_center()
-                .text_color(theme.foreground)
+                .text_color(color)
                 .text_size(px(theme.font_size * 0.85))
                 .child(SharedString::from(text))
                 .into_any_element()
@@ -178,14 +250,113 @@ impl GpuiModule for CpuModule {
         self.dirty.swap(false, Ordering::Relaxed)
     }
 
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
     fn value(&self) -> Option<u8> {
-        let usage = self.usage.load(Ordering::Relaxed);
-        Some(100 - usage) // Invert so low CPU is "good"
+        Some(self.usage())
+    }
+
+    fn display_text(&self) -> Option<String> {
+        Some(self.text())
     }
-}
 
-impl Drop for CpuModule {
-    fn drop(&mut self) {
-        self.stop.store(true, Ordering::Relaxed);
+    fn set_rule_colors(&mut self, colors: RuleColors) {
+        self.rule_colors = colors;
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        if key != "value" {
+            return false;
+        }
+        let Some(usage) = value.parse::<u8>().ok().filter(|usage| *usage <= 100) else {
+            return false;
+        };
+        let Ok(mut fixed) = self.fixed_usage.lock() else {
+            return false;
+        };
+        *fixed = Some(usage);
+        mark_dirty(&self.dirty);
+        true
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let sections = metrics::cpu_clusters().len().max(1) as f32;
+        let height = POPUP_PADDING * 2.0
+            + HEADER_HEIGHT
+            + sections * (ROW_HEIGHT + CHART_HEIGHT + SECTION_GAP);
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let details = self.details.lock().ok().and_then(|guard| guard.clone());
+        let load = details
+            .as_ref()
+            .and_then(|d| d.load)
+            .map(|[one, five, fifteen]| format!("Load {:.2} · {:.2} · {:.2}", one, five, fifteen))
+            .unwrap_or_default();
+
+        let mut content = div().flex().flex_col().gap(px(SECTION_GAP));
+        match details {
+            Some(details) => {
+                let clusters = metrics::cpu_clusters();
+                let groups = metrics::split_cores(&details.cores, clusters);
+                // A single group means the topology didn't match the cores
+                let matched = groups.len() == clusters.len();
+                for (i, cores) in groups.into_iter().enumerate() {
+                    let cluster = if matched { clusters.get(i) } else { None };
+                    content = content.child(Self::render_cluster(theme, cluster, cores));
+                }
+            }
+            None => {
+                content = content.child(
+                    div()
+                        .h(px(ROW_HEIGHT))
+                        .text_color(theme.foreground_muted)
+                        .child("Loading…"),
+                );
+            }
+        }
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(
+                    div()
+                        .h(px(HEADER_HEIGHT))
+                        .flex()
+                        .items_center()
+                        .justify_between()
+                        .child(
+                            div()
+                                .font_weight(gpui::FontWeight::SEMIBOLD)
+                                .child(SharedString::from(format!("CPU {}%", self.usage()))),
+                        )
+                        .child(
+                            div()
+                                .text_color(theme.foreground_muted)
+                                .child(SharedString::from(load)),
+                        ),
+                )
+                .child(content)
+                .into_any_element(),
+        )
+    }
+
+    fn on_popup_event(&mut self, event: PopupEvent) {
+        match event {
+            PopupEvent::Opened => self.watch_cores(),
+            PopupEvent::Closed => {
+                if let Ok(mut guard) = self.cores.lock() {
+                    guard.take();
+                }
+            }
+            _ => {}
+        }
     }
 }
@@ -1,30 +1,50 @@
 //! Date module for displaying the current date.
 
+use std::time::Duration;
+
 use chrono::Local;
 use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
 
-use super::GpuiModule;
+use super::template::{Template, Value};
+use super::{dispatch_popup_action, scroll_steps, until_next_second, GpuiModule, PopupAction};
+use crate::gpui_app::popup_manager;
 use crate::gpui_app::theme::Theme;
 
+/// Pixels of scrolling per month.
+const SCROLL_STEP_PX: f32 = 24.0;
+/// Registry id of the calendar popup.
+const CALENDAR_ID: &str = "calendar";
+
 /// Date module that displays the current date.
+/// Scrolling over it flips months in the open calendar popup.
 pub struct DateModule {
     id: String,
-    format: String,
+    format: Template,
     text: String,
+    scroll_accumulator: f32,
 }
 
 impl DateModule {
     /// Creates a new date module.
+    ///
+    /// @param id - Unique module identifier
+    /// @param format - strftime string, or a template using `{now:FORMAT}`
     pub fn new(id: &str, format: &str) -> Self {
-        let text = Local::now().format(format).to_string();
+        let format = Template::for_time(format);
+        let text = render_now(&format);
         Self {
             id: id.to_string(),
-            format: format.to_string(),
+            format,
             text,
+            scroll_accumulator: 0.0,
         }
     }
 }
 
+fn render_now(format: &Template) -> String {
+    format.render(&[("now", Some(Value::Time(Local::now().fixed_offset())))])
+}
+
 impl GpuiModule for DateModule {
     fn id(&self) -> &str {
         &self.id
@@ -41,7 +61,7 @@ impl GpuiModule for DateModule {
     }
 
     fn update(&mut self) -> bool {
-        let new_text = Local::now().format(&self.format).to_string();
+        let new_text = render_now(&self.format);
         if new_text != self.text {
             self.text = new_text;
             true
@@ -49,4 +69,49 @@ impl GpuiModule for DateModule {
             false
         }
     }
+
+    fn next_update(&self) -> Option<Duration> {
+        Some(until_next_second())
+    }
+
+    fn display_text(&self) -> Option<String> {
+        Some(self.text.clone())
+    }
+
+    fn on_scroll(&mut self, _delta_x: f32, delta_y: f32) -> bool {
+        if !popup_manager::is_popup_visible()
+            || popup_manager::get_current_module_id() != CALENDAR_ID
+        {
+            self.scroll_accumulator = 0.0;
+            return false;
+        }
+        let steps = scroll_steps(&mut self.scroll_accumulator, delta_y, SCROLL_STEP_PX);
+        let action = if steps > 0 {
+            PopupAction::Prev
+        } else {
+            PopupAction::Next
+        };
+        for _ in 0..steps.unsigned_abs() {
+            dispatch_popup_action(CALENDAR_ID, action.clone());
+        }
+        if steps != 0 {
+            popup_manager::notify_popup_needs_render(CALENDAR_ID);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_text_is_the_formatted_date() {
+        let module = DateModule::new("date", "%Y");
+        assert_eq!(
+            module.display_text(),
+            Some(Local::now().format("%Y").to_string())
+        );
+        assert_eq!(module.value(), None);
+    }
 }
@@ -2,10 +2,12 @@
 //!
 //! Displays date and time together as a single clickable widget.
 
+use std::time::Duration;
+
 use chrono::Local;
 use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
 
-use super::GpuiModule;
+use super::{until_next_second, GpuiModule};
 use crate::gpui_app::theme::Theme;
 
 /// Combined datetime module that displays date and time together.
@@ -74,4 +76,12 @@ impl GpuiModule for DateTimeModule {
         }
         changed
     }
+
+    fn next_update(&self) -> Option<Duration> {
+        Some(until_next_second())
+    }
+
+    fn display_text(&self) -> Option<String> {
+        Some(format!("{} {}", self.date_text, self.time_text))
+    }
 }
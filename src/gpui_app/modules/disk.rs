@@ -1,4 +1,13 @@
-//! Disk module for displaying disk usage.
+//! Disk module for displaying disk usage from the shared metrics service.
+//!
+//! With `io` on, the bar item also shows read/write throughput from the
+//! IOKit block storage statistics. Clicking opens a popup listing every
+//! mounted volume with a usage bar; volumes under `/Volumes` (external
+//! drives, disk images) get an eject button.
+//!
+//! ```toml
+//! { type = "disk", path = "/", io = true }
+//! ```
 
 use std::process::Command;
 use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
@@ -7,8 +16,127 @@ use std::time::Duration;
 
 use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
 
-use super::{GpuiModule, LabelAlign};
-use crate::gpui_app::theme::Theme;
+use super::{mark_dirty, GpuiModule, LabelAlign, PopupAction, PopupSpec, RuleColors};
+use crate::gpui_app::popup_manager::notify_popup_needs_render;
+use crate::gpui_app::primitives::{icons, Button};
+use crate::gpui_app::theme::{ButtonVariant, Theme};
+use crate::system::metrics::{self, DiskIo, Metric, Subscription};
+
+const POPUP_WIDTH: f64 = 300.0;
+const POPUP_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 24.0;
+const ROW_HEIGHT: f32 = 44.0;
+
+/// A mounted local volume.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Volume {
+    pub mount: String,
+    /// Sizes in bytes
+    pub total: u64,
+    pub used: u64,
+    pub percent: u8,
+}
+
+impl Volume {
+    /// Display name: the mount point's last component, or `/` for the root.
+    pub fn name(&self) -> &str {
+        self.mount
+            .rsplit('/')
+            .find(|part| !part.is_empty())
+            .unwrap_or("/")
+    }
+
+    /// Volumes under `/Volumes` are external drives or disk images.
+    pub fn ejectable(&self) -> bool {
+        self.mount.starts_with("/Volumes/")
+    }
+}
+
+/// Parses `df -kP` into local volumes, skipping the system's internal APFS
+/// volumes and pseudo filesystems.
+pub fn parse_volumes(output: &str) -> Vec<Volume> {
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            if cols.len() < 6 || !cols[0].starts_with("/dev/") {
+                return None;
+            }
+            // Mount points may contain spaces
+            let mount = cols[5..].join(" ");
+            if mount.starts_with("/System/Volumes/") {
+                return None;
+            }
+            Some(Volume {
+                mount,
+                total: cols[1].parse::<u64>().ok()? * 1024,
+                used: cols[2].parse::<u64>().ok()? * 1024,
+                percent: cols[4].trim_end_matches('%').parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Formats a byte count compactly for the bar, e.g. `1.2M`.
+pub fn format_compact(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+    let mut value = bytes.max(0.0);
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 || value >= 10.0 {
+        format!("{:.0}{}", value, UNITS[unit])
+    } else {
+        format!("{:.1}{}", value, UNITS[unit])
+    }
+}
+
+/// The volume holding `path`: the one with the longest mount point above it.
+fn volume_for<'a>(path: &str, volumes: &'a [Volume]) -> Option<&'a Volume> {
+    volumes
+        .iter()
+        .filter(|volume| {
+            volume.mount == "/"
+                || path == volume.mount
+                || path.starts_with(&format!("{}/", volume.mount))
+        })
+        .max_by_key(|volume| volume.mount.len())
+}
+
+/// Tooltip text: the path, its volume and the volume's sizes.
+fn describe(path: &str, volume: Option<&Volume>) -> String {
+    let Some(volume) = volume else {
+        return path.to_string();
+    };
+    let mut lines = vec![path.to_string()];
+    if volume.mount != path {
+        lines.push(format!("On {}", volume.mount));
+    }
+    lines.push(format!(
+        "{} of {} used ({}%)",
+        format_compact(volume.used as f64),
+        format_compact(volume.total as f64),
+        volume.percent
+    ));
+    lines.push(format!(
+        "{} free",
+        format_compact(volume.total.saturating_sub(volume.used) as f64)
+    ));
+    lines.join("\n")
+}
+
+fn list_volumes() -> Vec<Volume> {
+    Command::new("df")
+        .arg("-kP")
+        .output()
+        .ok()
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|out| parse_volumes(&out))
+        .unwrap_or_default()
+}
 
 /// Disk module that displays disk usage percentage.
 #[allow(dead_code)]
@@ -20,47 +148,80 @@ pub struct DiskModule {
     fixed_width: bool,
     usage: Arc<Mutex<String>>,
     usage_percent: Arc<AtomicU8>,
+    /// Throughput, when `io` is on
+    io: Option<Arc<Mutex<Option<DiskIo>>>>,
+    volumes: Arc<Mutex<Vec<Volume>>>,
     dirty: Arc<AtomicBool>,
-    stop: Arc<AtomicBool>,
+    /// Value color from `color_rules`
+    rule_colors: RuleColors,
+    /// Shared with the popup copy; dropping the last one unsubscribes
+    _metrics: Arc<Vec<Subscription>>,
 }
 
 impl DiskModule {
     /// Creates a new disk module.
+    ///
+    /// @param id - Unique module identifier
+    /// @param path - Mount point whose usage the bar shows
+    /// @param label - Optional label above the value
+    /// @param label_align - Alignment of the two-line layout
+    /// @param fixed_width - Reserve width for the value to prevent reflow
+    /// @param show_io - Show read/write throughput in the bar
     pub fn new(
         id: &str,
         path: &str,
         label: Option<&str>,
         label_align: LabelAlign,
         fixed_width: bool,
+        show_io: bool,
     ) -> Self {
         let usage = Arc::new(Mutex::new("0%".to_string()));
         let usage_percent = Arc::new(AtomicU8::new(0));
+        let volumes = Arc::new(Mutex::new(Vec::new()));
         let dirty = Arc::new(AtomicBool::new(true));
-        let stop = Arc::new(AtomicBool::new(false));
 
         let usage_handle = Arc::clone(&usage);
         let percent_handle = Arc::clone(&usage_percent);
+        let volumes_handle = Arc::clone(&volumes);
         let dirty_handle = Arc::clone(&dirty);
-        let stop_handle = Arc::clone(&stop);
-        let path = path.to_string();
-        let path_handle = path.clone();
-        std::thread::spawn(move || {
-            let mut last_usage = String::new();
-            let mut last_percent = 0;
-            while !stop_handle.load(Ordering::Relaxed) {
-                let (next_usage, next_percent) = Self::fetch_status(&path_handle);
-                if next_usage != last_usage || next_percent != last_percent {
-                    if let Ok(mut guard) = usage_handle.lock() {
-                        *guard = next_usage.clone();
-                    }
-                    percent_handle.store(next_percent, Ordering::Relaxed);
-                    dirty_handle.store(true, Ordering::Relaxed);
-                    last_usage = next_usage;
-                    last_percent = next_percent;
-                }
-                std::thread::sleep(Duration::from_secs(10));
+        let path_handle = path.to_string();
+        let metric = Metric::Disk(path.to_string());
+        let subscription = metrics::subscribe(metric, Duration::from_secs(10), move |snapshot| {
+            let (next_usage, next_percent) = snapshot
+                .disks
+                .get(&path_handle)
+                .map(|disk| (disk.text.clone(), disk.percent))
+                .unwrap_or_else(|| ("0%".to_string(), 0));
+            // Keeps the popup's list fresh without a poller of its own
+            if let Ok(mut volumes) = volumes_handle.lock() {
+                *volumes = list_volumes();
+            }
+            let Ok(mut guard) = usage_handle.lock() else {
+                return;
+            };
+            if *guard != next_usage || percent_handle.load(Ordering::Relaxed) != next_percent {
+                *guard = next_usage;
+                percent_handle.store(next_percent, Ordering::Relaxed);
+                mark_dirty(&dirty_handle);
             }
         });
+        let mut subscriptions = vec![subscription];
+
+        let io = show_io.then(|| Arc::new(Mutex::new(None)));
+        if let Some(io) = &io {
+            let io_handle = Arc::clone(io);
+            let dirty_handle = Arc::clone(&dirty);
+            subscriptions.push(metrics::subscribe(
+                Metric::DiskIo,
+                Duration::from_secs(2),
+                move |snapshot| {
+                    if let Ok(mut guard) = io_handle.lock() {
+                        *guard = snapshot.disk_io;
+                        mark_dirty(&dirty_handle);
+                    }
+                },
+            ));
+        }
 
         Self {
             id: id.to_string(),
@@ -70,34 +231,129 @@ impl DiskModule {
             fixed_width,
             usage,
             usage_percent,
+            io,
+            volumes,
             dirty,
-            stop,
+            rule_colors: RuleColors::default(),
+            _metrics: Arc::new(subscriptions),
         }
     }
 
-    fn fetch_status(path: &str) -> (String, u8) {
-        let mut usage = "0%".to_string();
-        let mut usage_percent = 0;
-        let output = Command::new("df")
-            .args(["-h", path])
-            .output()
-            .ok()
-            .and_then(|o| String::from_utf8(o.stdout).ok());
-
-        if let Some(out) = output {
-            if let Some(line) = out.lines().nth(1) {
-                if let Some(usage_str) = line.split_whitespace().nth(4) {
-                    usage = usage_str.to_string();
-                    // Parse percentage
-                    if let Some(pct) = usage.strip_suffix('%') {
-                        if let Ok(p) = pct.parse::<u8>() {
-                            usage_percent = p;
-                        }
-                    }
-                }
+    /// Returns a second handle on the same state for the popup registry.
+    pub fn popup_handle(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            path: self.path.clone(),
+            label: self.label.clone(),
+            label_align: self.label_align,
+            fixed_width: self.fixed_width,
+            usage: Arc::clone(&self.usage),
+            usage_percent: Arc::clone(&self.usage_percent),
+            io: self.io.clone(),
+            volumes: Arc::clone(&self.volumes),
+            dirty: Arc::clone(&self.dirty),
+            rule_colors: self.rule_colors,
+            _metrics: Arc::clone(&self._metrics),
+        }
+    }
+
+    /// Bar text: usage, plus throughput when `io` is on.
+    fn value_text(&self) -> String {
+        let usage = self.usage.lock().map(|v| v.clone()).unwrap_or_default();
+        let io = self
+            .io
+            .as_ref()
+            .and_then(|io| io.lock().ok().and_then(|guard| *guard));
+        match io {
+            Some(io) => format!(
+                "{} R {} W {}",
+                usage,
+                format_compact(io.read),
+                format_compact(io.write)
+            ),
+            None => usage,
+        }
+    }
+
+    /// Ejects the volume at `mount`, then refreshes the list.
+    fn eject(&self, mount: String) {
+        let volumes = Arc::clone(&self.volumes);
+        let id = self.id.clone();
+        std::thread::spawn(move || {
+            match Command::new("diskutil").args(["eject", &mount]).output() {
+                Ok(output) if output.status.success() => log::info!("disk: ejected {}", mount),
+                Ok(output) => log::warn!(
+                    "disk: failed to eject {}: {}",
+                    mount,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+                Err(err) => log::warn!("disk: failed to run diskutil: {}", err),
+            }
+            if let Ok(mut guard) = volumes.lock() {
+                *guard = list_volumes();
             }
+            notify_popup_needs_render(&id);
+        });
+    }
+
+    fn render_volume(&self, theme: &Theme, volume: &Volume) -> gpui::Div {
+        let color = match volume.percent {
+            0..=79 => theme.accent,
+            80..=89 => theme.warning,
+            _ => theme.destructive,
+        };
+        let detail = format!(
+            "{} of {}",
+            format_compact(volume.used as f64),
+            format_compact(volume.total as f64)
+        );
+        let mut title = div()
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .flex_1()
+                    .overflow_hidden()
+                    .child(SharedString::from(volume.name().to_string())),
+            )
+            .child(
+                div()
+                    .text_color(theme.foreground_muted)
+                    .child(SharedString::from(detail)),
+            );
+        if volume.ejectable() {
+            let value = format!("eject:{}", volume.mount);
+            title = title.child(
+                Button::new(SharedString::from(format!("{}-{}", self.id, value)))
+                    .icon(icons::system::EJECT)
+                    .variant(ButtonVariant::Ghost)
+                    .text_size(px(theme.font_size - 2.0))
+                    .on_popup_action(self.id.clone(), PopupAction::Select { value })
+                    .render(theme),
+            );
         }
-        (usage, usage_percent)
+        div()
+            .h(px(ROW_HEIGHT))
+            .flex()
+            .flex_col()
+            .justify_center()
+            .gap(px(6.0))
+            .child(title)
+            .child(
+                div()
+                    .h(px(4.0))
+                    .w_full()
+                    .rounded_full()
+                    .bg(theme.surface_hover)
+                    .child(
+                        div()
+                            .h_full()
+                            .w(gpui::relative(volume.percent as f32 / 100.0))
+                            .rounded_full()
+                            .bg(color),
+                    ),
+            )
     }
 }
 
@@ -107,7 +363,8 @@ impl GpuiModule for DiskModule {
     }
 
     fn render(&self, theme: &Theme) -> AnyElement {
-        let usage = self.usage.lock().map(|v| v.clone()).unwrap_or_default();
+        let usage = self.value_text();
+        let color = self.rule_colors.text.unwrap_or(theme.foreground);
         if let Some(ref label) = self.label {
             // Two-line layout with label - configurable alignment
             let mut container = div().flex().flex_col().gap(px(0.0));
@@ -134,7 +391,7 @@ impl GpuiModule for DiskModule {
                         .min_w(px(if self.fixed_width { value_width } else { 0.0 }))
                         .flex()
                         .justify_end()
-                        .text_color(theme.foreground)
+                        .text_color(color)
                         .text_size(px(theme.font_size * 0.85))
                         .line_height(px(theme.font_size * 0.9))
                         .child(SharedString::from(usage.clone())),
@@ -144,7 +401,7 @@ impl GpuiModule for DiskModule {
             div()
                 .flex()
                 .items_center()
-                .text_color(theme.foreground)
+                .text_color(color)
                 .text_size(px(theme.font_size * 0.85))
                 .child(SharedString::from(usage.clone()))
                 .into_any_element()
@@ -155,13 +412,134 @@ impl GpuiModule for DiskModule {
         self.dirty.swap(false, Ordering::Relaxed)
     }
 
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
     fn value(&self) -> Option<u8> {
-        Some(100 - self.usage_percent.load(Ordering::Relaxed)) // Invert so low disk usage is "good"
+        Some(self.usage_percent.load(Ordering::Relaxed))
+    }
+
+    fn display_text(&self) -> Option<String> {
+        Some(self.value_text())
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        let volumes = self.volumes.lock().ok()?;
+        Some(describe(&self.path, volume_for(&self.path, &volumes)))
+    }
+
+    fn set_rule_colors(&mut self, colors: RuleColors) {
+        self.rule_colors = colors;
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let rows = self.volumes.lock().map(|v| v.len()).unwrap_or(0).max(1) as f32;
+        let height = POPUP_PADDING * 2.0 + HEADER_HEIGHT + rows * ROW_HEIGHT;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let volumes = self.volumes.lock().map(|v| v.clone()).unwrap_or_default();
+        let mut list = div().flex().flex_col();
+        if volumes.is_empty() {
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .text_color(theme.foreground_muted)
+                    .child("Loading…"),
+            );
+        }
+        for volume in &volumes {
+            list = list.child(self.render_volume(theme, volume));
+        }
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(
+                    div()
+                        .h(px(HEADER_HEIGHT))
+                        .font_weight(gpui::FontWeight::SEMIBOLD)
+                        .child("Volumes"),
+                )
+                .child(list)
+                .into_any_element(),
+        )
+    }
+
+    fn on_popup_action(&mut self, action: PopupAction) {
+        if let PopupAction::Select { value } = action {
+            if let Some(mount) = value.strip_prefix("eject:") {
+                self.eject(mount.to_string());
+            }
+        }
     }
 }
 
-impl Drop for DiskModule {
-    fn drop(&mut self) {
-        self.stop.store(true, Ordering::Relaxed);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_local_volumes_from_df() {
+        let output = "\
+Filesystem     1024-blocks      Used Available Capacity  Mounted on
+/dev/disk3s1s1   482797652  10343488 232079480     5%    /
+devfs                  205       205         0   100%    /dev
+/dev/disk3s6     482797652   2097172 232079480     1%    /System/Volumes/VM
+/dev/disk5s1       1000000    250000    750000    25%    /Volumes/Backup Drive
+map auto_home            0         0         0   100%    /System/Volumes/Data/home";
+        let volumes = parse_volumes(output);
+        assert_eq!(volumes.len(), 2);
+        assert_eq!(volumes[0].name(), "/");
+        assert!(!volumes[0].ejectable());
+        assert_eq!(volumes[1].mount, "/Volumes/Backup Drive");
+        assert_eq!(volumes[1].name(), "Backup Drive");
+        assert_eq!(volumes[1].used, 250_000 * 1024);
+        assert_eq!(volumes[1].percent, 25);
+        assert!(volumes[1].ejectable());
+    }
+
+    #[test]
+    fn describes_the_volume_holding_the_path() {
+        let volume = |mount: &str| Volume {
+            mount: mount.to_string(),
+            total: 4 * 1024 * 1024 * 1024,
+            used: 1024 * 1024 * 1024,
+            percent: 25,
+        };
+        let volumes = vec![volume("/"), volume("/Volumes/Backup Drive")];
+        let backup = volume_for("/Volumes/Backup Drive/photos", &volumes);
+        assert_eq!(
+            backup.map(|v| v.mount.as_str()),
+            Some("/Volumes/Backup Drive")
+        );
+        assert_eq!(
+            volume_for("/Volumes/Backup", &volumes).map(|v| v.mount.as_str()),
+            Some("/")
+        );
+        assert_eq!(
+            describe("/", Some(&volumes[0])),
+            "/\n1.0G of 4.0G used (25%)\n3.0G free"
+        );
+        assert_eq!(
+            describe("/Volumes/Backup Drive/photos", backup),
+            "/Volumes/Backup Drive/photos\nOn /Volumes/Backup Drive\n1.0G of 4.0G used (25%)\n3.0G free"
+        );
+        assert_eq!(describe("/data", None), "/data");
+    }
+
+    #[test]
+    fn formats_compact_sizes() {
+        assert_eq!(format_compact(512.0), "512B");
+        assert_eq!(format_compact(1536.0), "1.5K");
+        assert_eq!(format_compact(20.0 * 1024.0 * 1024.0), "20M");
     }
 }
@@ -0,0 +1,545 @@
+//! Display info and resolution switcher.
+//!
+//! Shows the main display's resolution and refresh rate. Clicking it lists
+//! every display with its sizes and refresh rates to switch between, and
+//! toggles mirroring, Night Shift and True Tone where the Mac has them.
+//!
+//! ```toml
+//! { type = "display", format = "{icon} {count} · {width}×{height}" }
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+
+use super::template::{Template, Value};
+use super::{mark_dirty, sleep_unless_stopped, GpuiModule, PopupAction, PopupEvent, PopupSpec};
+use crate::gpui_app::popup_manager::notify_popup_needs_render;
+use crate::gpui_app::primitives::{icons, Button};
+use crate::gpui_app::theme::{ButtonVariant, Theme};
+use crate::system::display::{self, Display};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_FORMAT: &str = "{icon} {width}×{height}[ {refresh}Hz]";
+const POPUP_WIDTH: f64 = 340.0;
+const POPUP_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 24.0;
+const ROW_HEIGHT: f32 = 28.0;
+/// Resolution buttons per row in the popup
+const SIZE_COLUMNS: usize = 3;
+/// Most resolutions listed per display, largest first
+const MAX_SIZES: usize = 12;
+
+/// Bar text: `format` filled with the main display's mode.
+fn bar_text(format: &Template, displays: &[Display]) -> String {
+    let mode = displays.first().and_then(|display| display.mode);
+    format.render(&[
+        ("icon", Some(Value::from(icons::system::MONITOR))),
+        ("count", Some(Value::from(displays.len() as f64))),
+        ("width", mode.map(|mode| Value::from(f64::from(mode.width)))),
+        (
+            "height",
+            mode.map(|mode| Value::from(f64::from(mode.height))),
+        ),
+        (
+            "refresh",
+            mode.and_then(|mode| mode.refresh)
+                .map(|refresh| Value::from(refresh.round())),
+        ),
+    ])
+}
+
+/// A popup button press.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Choice {
+    Size {
+        display: u32,
+        width: u32,
+        height: u32,
+    },
+    Refresh {
+        display: u32,
+        rate: f64,
+    },
+    Mirror,
+    NightShift,
+    TrueTone,
+}
+
+impl Choice {
+    /// Value passed back in `PopupAction::Select`.
+    fn value(self) -> String {
+        match self {
+            Self::Size {
+                display,
+                width,
+                height,
+            } => format!("size:{}:{}x{}", display, width, height),
+            Self::Refresh { display, rate } => format!("refresh:{}:{}", display, rate),
+            Self::Mirror => "mirror".to_string(),
+            Self::NightShift => "nightshift".to_string(),
+            Self::TrueTone => "truetone".to_string(),
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "mirror" => return Some(Self::Mirror),
+            "nightshift" => return Some(Self::NightShift),
+            "truetone" => return Some(Self::TrueTone),
+            _ => {}
+        }
+        let mut parts = value.splitn(3, ':');
+        let (kind, display, rest) = (parts.next()?, parts.next()?, parts.next()?);
+        let display = display.parse().ok()?;
+        match kind {
+            "size" => {
+                let (width, height) = rest.split_once('x')?;
+                Some(Self::Size {
+                    display,
+                    width: width.parse().ok()?,
+                    height: height.parse().ok()?,
+                })
+            }
+            "refresh" => Some(Self::Refresh {
+                display,
+                rate: rest.parse().ok()?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct DisplayState {
+    /// Main display first
+    displays: Vec<Display>,
+    night_shift: Option<bool>,
+    true_tone: Option<bool>,
+    /// Why the last change failed
+    error: Option<String>,
+}
+
+impl DisplayState {
+    fn mirrored(&self) -> bool {
+        self.displays.iter().any(|display| display.mirrored)
+    }
+
+    fn display(&self, id: u32) -> Option<&Display> {
+        self.displays.iter().find(|display| display.id == id)
+    }
+}
+
+/// State shared between the bar and popup copies of the module.
+struct Shared {
+    state: Mutex<DisplayState>,
+    dirty: AtomicBool,
+    stop: AtomicBool,
+}
+
+impl Shared {
+    /// Re-reads the displays and color settings; returns true if they changed.
+    fn refresh(&self) -> bool {
+        let displays = display::displays();
+        let night_shift = display::night_shift();
+        let true_tone = display::true_tone();
+        let Ok(mut guard) = self.state.lock() else {
+            return false;
+        };
+        if guard.displays == displays
+            && guard.night_shift == night_shift
+            && guard.true_tone == true_tone
+        {
+            return false;
+        }
+        guard.displays = displays;
+        guard.night_shift = night_shift;
+        guard.true_tone = true_tone;
+        true
+    }
+}
+
+/// Shows display modes and switches them.
+pub struct DisplayModule {
+    id: String,
+    format: Template,
+    shared: Arc<Shared>,
+}
+
+impl DisplayModule {
+    pub fn new(id: &str, format: Option<&str>) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(DisplayState::default()),
+            dirty: AtomicBool::new(true),
+            stop: AtomicBool::new(false),
+        });
+
+        let thread_shared = Arc::clone(&shared);
+        let thread_id = id.to_string();
+        std::thread::spawn(move || {
+            while !thread_shared.stop.load(Ordering::Relaxed) {
+                if thread_shared.refresh() {
+                    mark_dirty(&thread_shared.dirty);
+                    notify_popup_needs_render(&thread_id);
+                }
+                sleep_unless_stopped(POLL_INTERVAL, &thread_shared.stop);
+            }
+        });
+
+        Self {
+            id: id.to_string(),
+            format: Template::parse_or_literal(format.unwrap_or(DEFAULT_FORMAT)),
+            shared,
+        }
+    }
+
+    /// Returns a copy that shares state, for the popup registry.
+    pub fn popup_handle(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            format: self.format.clone(),
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    fn state(&self) -> DisplayState {
+        self.shared
+            .state
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+
+    fn button(&self, theme: &Theme, label: String, choice: Choice, selected: bool) -> AnyElement {
+        let value = choice.value();
+        let variant = if selected {
+            ButtonVariant::Accent
+        } else {
+            ButtonVariant::Ghost
+        };
+        Button::new(SharedString::from(format!("{}-{}", self.id, value)))
+            .label(label)
+            .variant(variant)
+            .text_size(px(theme.font_size - 2.0))
+            .on_popup_action(self.id.clone(), PopupAction::Select { value })
+            .render(theme)
+            .into_any_element()
+    }
+
+    fn render_display(&self, theme: &Theme, display: &Display) -> gpui::Div {
+        let mut section = div().flex().flex_col().child(
+            div()
+                .h(px(HEADER_HEIGHT))
+                .flex()
+                .items_center()
+                .font_weight(gpui::FontWeight::SEMIBOLD)
+                .child(SharedString::from(display.name())),
+        );
+        let Some(current) = display.mode else {
+            return section;
+        };
+
+        let mut sizes = div().flex().flex_wrap();
+        for (width, height) in display::resolutions(&display.modes, current.hidpi())
+            .into_iter()
+            .take(MAX_SIZES)
+        {
+            let choice = Choice::Size {
+                display: display.id,
+                width,
+                height,
+            };
+            let selected = width == current.width && height == current.height;
+            sizes = sizes.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .w(gpui::relative(1.0 / SIZE_COLUMNS as f32))
+                    .child(self.button(theme, format!("{}×{}", width, height), choice, selected)),
+            );
+        }
+        section = section.child(sizes);
+
+        let rates = display::refresh_rates(&display.modes, &current);
+        if rates.len() > 1 {
+            let mut row = div().h(px(ROW_HEIGHT)).flex().items_center().gap(px(4.0));
+            for rate in rates {
+                let selected = current.refresh.is_some_and(|r| (r - rate).abs() < 0.01);
+                row = row.child(self.button(
+                    theme,
+                    format!("{}Hz", rate.round()),
+                    Choice::Refresh {
+                        display: display.id,
+                        rate,
+                    },
+                    selected,
+                ));
+            }
+            section = section.child(row);
+        }
+        section
+    }
+
+    fn render_toggle(
+        &self,
+        theme: &Theme,
+        label: &'static str,
+        on: bool,
+        choice: Choice,
+    ) -> gpui::Div {
+        let value = choice.value();
+        div()
+            .h(px(ROW_HEIGHT))
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .child(div().flex_1().child(label))
+            .child(
+                Button::new(SharedString::from(format!("{}-{}", self.id, value)))
+                    .label(if on { "Turn off" } else { "Turn on" })
+                    .variant(ButtonVariant::Outline)
+                    .text_size(px(theme.font_size - 2.0))
+                    .on_popup_action(self.id.clone(), PopupAction::Select { value })
+                    .render(theme),
+            )
+    }
+
+    /// Applies a popup choice; returns why it failed.
+    fn apply(&self, choice: Choice) -> Result<(), String> {
+        let state = self.state();
+        match choice {
+            Choice::Size {
+                display,
+                width,
+                height,
+            } => {
+                let refresh = state
+                    .display(display)
+                    .and_then(|display| display.mode)
+                    .and_then(|mode| mode.refresh);
+                display::set_mode(display, width, height, refresh)
+            }
+            Choice::Refresh { display, rate } => {
+                let mode = state
+                    .display(display)
+                    .and_then(|display| display.mode)
+                    .ok_or_else(|| "The display is gone".to_string())?;
+                display::set_mode(display, mode.width, mode.height, Some(rate))
+            }
+            Choice::Mirror => display::set_mirroring(!state.mirrored()),
+            Choice::NightShift => {
+                let on = !state.night_shift.unwrap_or(false);
+                display::set_night_shift(on)
+                    .then_some(())
+                    .ok_or_else(|| "Couldn't change Night Shift".to_string())
+            }
+            Choice::TrueTone => {
+                let on = !state.true_tone.unwrap_or(false);
+                display::set_true_tone(on)
+                    .then_some(())
+                    .ok_or_else(|| "Couldn't change True Tone".to_string())
+            }
+        }
+    }
+}
+
+impl GpuiModule for DisplayModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let text = bar_text(&self.format, &self.state().displays);
+        div()
+            .flex()
+            .items_center()
+            .text_color(theme.foreground)
+            .text_size(px(theme.font_size))
+            .child(SharedString::from(text))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        self.shared.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn value(&self) -> Option<u8> {
+        u8::try_from(self.state().displays.len()).ok()
+    }
+
+    fn display_text(&self) -> Option<String> {
+        Some(bar_text(&self.format, &self.state().displays))
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let state = self.state();
+        let mut height = POPUP_PADDING * 2.0;
+        for display in &state.displays {
+            height += HEADER_HEIGHT;
+            let Some(current) = display.mode else {
+                continue;
+            };
+            let sizes = display::resolutions(&display.modes, current.hidpi())
+                .len()
+                .min(MAX_SIZES);
+            height += sizes.div_ceil(SIZE_COLUMNS) as f32 * ROW_HEIGHT;
+            if display::refresh_rates(&display.modes, &current).len() > 1 {
+                height += ROW_HEIGHT;
+            }
+        }
+        let toggles = [
+            state.displays.len() > 1,
+            state.night_shift.is_some(),
+            state.true_tone.is_some(),
+            state.error.is_some(),
+        ];
+        let rows = toggles.iter().filter(|&&shown| shown).count();
+        if rows > 0 {
+            height += POPUP_PADDING + rows as f32 * ROW_HEIGHT;
+        }
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let state = self.state();
+        let mut popup = div()
+            .flex()
+            .flex_col()
+            .p(px(POPUP_PADDING))
+            .text_size(px(theme.font_size))
+            .text_color(theme.foreground);
+        for display in &state.displays {
+            popup = popup.child(self.render_display(theme, display));
+        }
+
+        let mut toggles = div().flex().flex_col().pt(px(POPUP_PADDING));
+        if state.displays.len() > 1 {
+            toggles = toggles.child(self.render_toggle(
+                theme,
+                "Mirror displays",
+                state.mirrored(),
+                Choice::Mirror,
+            ));
+        }
+        if let Some(on) = state.night_shift {
+            toggles =
+                toggles.child(self.render_toggle(theme, "Night Shift", on, Choice::NightShift));
+        }
+        if let Some(on) = state.true_tone {
+            toggles = toggles.child(self.render_toggle(theme, "True Tone", on, Choice::TrueTone));
+        }
+        if let Some(error) = state.error {
+            toggles = toggles.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .text_size(px(theme.font_size - 1.0))
+                    .text_color(theme.destructive)
+                    .child(SharedString::from(error)),
+            );
+        }
+        Some(popup.child(toggles).into_any_element())
+    }
+
+    fn on_popup_action(&mut self, action: PopupAction) {
+        let PopupAction::Select { value } = action else {
+            return;
+        };
+        let Some(choice) = Choice::parse(&value) else {
+            return;
+        };
+        let error = self.apply(choice).err();
+        if let Some(error) = &error {
+            log::warn!("display: can't apply {}: {}", value, error);
+        }
+        if let Ok(mut guard) = self.shared.state.lock() {
+            guard.error = error;
+        }
+        self.shared.refresh();
+        mark_dirty(&self.shared.dirty);
+        notify_popup_needs_render(&self.id);
+    }
+
+    fn on_popup_event(&mut self, event: PopupEvent) {
+        if matches!(event, PopupEvent::Opened) {
+            if let Ok(mut guard) = self.shared.state.lock() {
+                guard.error = None;
+            }
+        }
+    }
+}
+
+impl Drop for DisplayModule {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::display::DisplayMode;
+
+    fn display(id: u32, width: u32, height: u32, refresh: Option<f64>) -> Display {
+        let mode = DisplayMode {
+            width,
+            height,
+            pixel_width: width * 2,
+            refresh,
+        };
+        Display {
+            id,
+            builtin: id == 1,
+            main: id == 1,
+            mirrored: false,
+            mode: Some(mode),
+            modes: vec![mode],
+        }
+    }
+
+    #[test]
+    fn shows_the_main_display_mode() {
+        let displays = vec![
+            display(1, 1512, 982, Some(120.0)),
+            display(2, 2560, 1440, Some(59.94)),
+        ];
+        let format = Template::parse_or_literal("{count}: {width}x{height}[ @ {refresh}Hz]");
+        assert_eq!(bar_text(&format, &displays), "2: 1512x982 @ 120Hz");
+        let unknown_rate = vec![display(2, 2560, 1440, None)];
+        assert_eq!(bar_text(&format, &unknown_rate), "1: 2560x1440");
+        let default = Template::parse_or_literal(DEFAULT_FORMAT);
+        assert_eq!(
+            bar_text(&default, &displays[1..]),
+            format!("{} 2560×1440 60Hz", icons::system::MONITOR)
+        );
+    }
+
+    #[test]
+    fn round_trips_choices() {
+        let choices = [
+            Choice::Size {
+                display: 69734272,
+                width: 1800,
+                height: 1169,
+            },
+            Choice::Refresh {
+                display: 2,
+                rate: 59.94,
+            },
+            Choice::Mirror,
+            Choice::NightShift,
+            Choice::TrueTone,
+        ];
+        for choice in choices {
+            assert_eq!(Choice::parse(&choice.value()), Some(choice));
+        }
+        assert_eq!(Choice::parse("size:1:1800"), None);
+        assert_eq!(Choice::parse("refresh:x:60"), None);
+    }
+}
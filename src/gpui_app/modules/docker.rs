@@ -0,0 +1,591 @@
+//! Docker/Podman containers module.
+//!
+//! Talks to the engine's Unix socket directly (plain HTTP/1.0, no CLI) to
+//! show the number of running containers. Clicking opens a popup listing
+//! every container with its status and start/stop/restart buttons.
+//!
+//! The socket is taken from `socket`, then `DOCKER_HOST`, then the usual
+//! Docker Desktop, Colima, OrbStack and Podman machine locations.
+//!
+//! ```toml
+//! { type = "docker", socket = "~/.colima/default/docker.sock", interval = 5 }
+//! ```
+
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gpui::{div, prelude::*, px, AnyElement, Rgba, SharedString, Styled};
+use serde::Deserialize;
+
+use super::{mark_dirty, GpuiModule, PopupAction, PopupSpec};
+use crate::gpui_app::popup_manager::notify_popup_needs_render;
+use crate::gpui_app::primitives::{icons, Button};
+use crate::gpui_app::theme::{ButtonVariant, Theme};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const POPUP_WIDTH: f64 = 380.0;
+const POPUP_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 24.0;
+const ROW_HEIGHT: f32 = 30.0;
+const MAX_ROWS: usize = 12;
+
+/// Socket paths tried when none is configured, relative to the home
+/// directory unless absolute.
+const SOCKET_CANDIDATES: &[&str] = &[
+    ".docker/run/docker.sock",
+    "/var/run/docker.sock",
+    ".colima/default/docker.sock",
+    ".orbstack/run/docker.sock",
+    ".local/share/containers/podman/machine/podman.sock",
+    ".local/share/containers/podman/machine/podman-machine-default/podman.sock",
+];
+
+/// A container as returned by `GET /containers/json`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Container {
+    pub id: String,
+    #[serde(default)]
+    pub names: Vec<String>,
+    #[serde(default)]
+    pub image: String,
+    /// "running", "exited", "paused", "restarting", ...
+    #[serde(default)]
+    pub state: String,
+    /// Human-readable status, e.g. "Up 2 hours"
+    #[serde(default)]
+    pub status: String,
+}
+
+impl Container {
+    /// Display name without the leading slash Docker adds.
+    pub fn name(&self) -> &str {
+        self.names
+            .first()
+            .map(|n| n.trim_start_matches('/'))
+            .unwrap_or_else(|| &self.id[..self.id.len().min(12)])
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state == "running"
+    }
+}
+
+/// Container actions offered in the popup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerAction {
+    Start,
+    Stop,
+    Restart,
+}
+
+impl ContainerAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Stop => "stop",
+            Self::Restart => "restart",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Start => "Start",
+            Self::Stop => "Stop",
+            Self::Restart => "Restart",
+        }
+    }
+
+    /// Parses a `<action>:<container id>` popup selection.
+    pub fn parse_selection(value: &str) -> Option<(Self, &str)> {
+        let (action, id) = value.split_once(':')?;
+        let action = match action {
+            "start" => Self::Start,
+            "stop" => Self::Stop,
+            "restart" => Self::Restart,
+            _ => return None,
+        };
+        Some((action, id))
+    }
+}
+
+/// Latest poll result.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct DockerState {
+    containers: Vec<Container>,
+    /// Set when the engine couldn't be reached
+    error: Option<String>,
+    /// Container ids with an action in flight
+    pending: Vec<String>,
+}
+
+/// State shared between the bar and popup copies of the module.
+struct Shared {
+    socket: Option<PathBuf>,
+    state: Mutex<DockerState>,
+    dirty: AtomicBool,
+    stop: AtomicBool,
+}
+
+impl Shared {
+    /// Polls the engine. Returns true if anything changed.
+    fn refresh(&self) -> bool {
+        let result = match &self.socket {
+            Some(socket) => list_containers(socket),
+            None => Err("No Docker or Podman socket found".to_string()),
+        };
+        if let Ok(mut guard) = self.state.lock() {
+            let next = match result {
+                Ok(containers) => DockerState {
+                    containers,
+                    error: None,
+                    pending: guard.pending.clone(),
+                },
+                Err(err) => DockerState {
+                    containers: Vec::new(),
+                    error: Some(err),
+                    pending: guard.pending.clone(),
+                },
+            };
+            if *guard != next {
+                *guard = next;
+                mark_dirty(&self.dirty);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Returns the engine socket: the configured path, `DOCKER_HOST`, or the
+/// first well-known location that exists.
+pub fn resolve_socket(configured: Option<&str>) -> Option<PathBuf> {
+    let home = dirs::home_dir().unwrap_or_default();
+    if let Some(path) = configured {
+        return Some(expand_home(path, &home));
+    }
+    if let Some(path) = std::env::var("DOCKER_HOST")
+        .ok()
+        .as_deref()
+        .and_then(|host| host.strip_prefix("unix://"))
+    {
+        return Some(PathBuf::from(path));
+    }
+    SOCKET_CANDIDATES
+        .iter()
+        .map(|candidate| home.join(candidate))
+        .find(|path| path.exists())
+}
+
+fn expand_home(path: &str, home: &Path) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home.join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Sends one HTTP/1.0 request over the socket and returns the response body.
+/// HTTP/1.0 keeps the engine from using chunked encoding.
+fn request(socket: &Path, method: &str, path: &str) -> Result<String, String> {
+    let mut stream = UnixStream::connect(socket)
+        .map_err(|e| format!("Can't connect to {}: {}", socket.display(), e))?;
+    let _ = stream.set_read_timeout(Some(REQUEST_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(REQUEST_TIMEOUT));
+    let request = format!(
+        "{} {} HTTP/1.0\r\nHost: docker\r\nContent-Length: 0\r\n\r\n",
+        method, path
+    );
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| e.to_string())?;
+    let mut raw = String::new();
+    stream.read_to_string(&mut raw).map_err(|e| e.to_string())?;
+    let (status, body) = parse_response(&raw)?;
+    if (200..300).contains(&status) {
+        Ok(body.to_string())
+    } else {
+        Err(api_error(status, body))
+    }
+}
+
+/// Splits a raw HTTP response into status code and body.
+pub fn parse_response(raw: &str) -> Result<(u16, &str), String> {
+    let (head, body) = raw
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| "Malformed response".to_string())?;
+    let status = head
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| "Malformed status line".to_string())?;
+    Ok((status, body))
+}
+
+/// Engine errors come back as `{"message": "..."}`.
+fn api_error(status: u16, body: &str) -> String {
+    #[derive(Deserialize)]
+    struct ApiError {
+        message: String,
+    }
+    serde_json::from_str::<ApiError>(body)
+        .map(|e| e.message)
+        .unwrap_or_else(|_| format!("HTTP {}", status))
+}
+
+/// Parses the `GET /containers/json` body, running containers first.
+pub fn parse_containers(body: &str) -> Result<Vec<Container>, String> {
+    let mut containers: Vec<Container> = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    containers.sort_by(|a, b| {
+        b.is_running()
+            .cmp(&a.is_running())
+            .then_with(|| a.name().cmp(b.name()))
+    });
+    Ok(containers)
+}
+
+fn list_containers(socket: &Path) -> Result<Vec<Container>, String> {
+    parse_containers(&request(socket, "GET", "/containers/json?all=1")?)
+}
+
+/// Docker containers module.
+pub struct DockerModule {
+    id: String,
+    shared: Arc<Shared>,
+}
+
+impl DockerModule {
+    /// Creates a new Docker module and starts polling the engine.
+    ///
+    /// @param id - Unique module identifier
+    /// @param socket - Engine socket path (None = auto-detect)
+    /// @param interval - Seconds between polls
+    pub fn new(id: &str, socket: Option<&str>, interval: u64) -> Self {
+        let shared = Arc::new(Shared {
+            socket: resolve_socket(socket),
+            state: Mutex::new(DockerState::default()),
+            dirty: AtomicBool::new(true),
+            stop: AtomicBool::new(false),
+        });
+
+        let handle = Arc::clone(&shared);
+        let popup_id = id.to_string();
+        std::thread::spawn(move || {
+            while !handle.stop.load(Ordering::Relaxed) {
+                if handle.refresh() {
+                    notify_popup_needs_render(&popup_id);
+                }
+                std::thread::sleep(Duration::from_secs(interval.max(1)));
+            }
+        });
+
+        Self {
+            id: id.to_string(),
+            shared,
+        }
+    }
+
+    /// Returns a second handle on the same state for the popup registry.
+    pub fn popup_handle(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    /// Runs a container action in the background, then refreshes.
+    fn run_action(&self, action: ContainerAction, container_id: &str) {
+        let Some(socket) = self.shared.socket.clone() else {
+            return;
+        };
+        if let Ok(mut guard) = self.shared.state.lock() {
+            guard.pending.push(container_id.to_string());
+        }
+        let shared = Arc::clone(&self.shared);
+        let id = self.id.clone();
+        let container_id = container_id.to_string();
+        std::thread::spawn(move || {
+            let path = format!("/containers/{}/{}", container_id, action.as_str());
+            if let Err(err) = request(&socket, "POST", &path) {
+                log::warn!(
+                    "docker: {} {} failed: {}",
+                    action.as_str(),
+                    container_id,
+                    err
+                );
+            }
+            if let Ok(mut guard) = shared.state.lock() {
+                guard.pending.retain(|pending| pending != &container_id);
+            }
+            shared.refresh();
+            notify_popup_needs_render(&id);
+        });
+    }
+
+    fn state(&self) -> DockerState {
+        self.shared
+            .state
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+}
+
+fn state_color(theme: &Theme, state: &str) -> Rgba {
+    match state {
+        "running" => theme.success,
+        "paused" | "restarting" => theme.warning,
+        "dead" => theme.destructive,
+        _ => theme.foreground_subtle,
+    }
+}
+
+/// Icon and running container count, or a dash while Docker can't be reached.
+fn bar_text(state: &DockerState) -> String {
+    if state.error.is_some() {
+        return format!("{} –", icons::system::CONTAINER);
+    }
+    let running = state.containers.iter().filter(|c| c.is_running()).count();
+    format!("{} {}", icons::system::CONTAINER, running)
+}
+
+impl GpuiModule for DockerModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let state = self.state();
+        let running = state.containers.iter().filter(|c| c.is_running()).count();
+        let text = bar_text(&state);
+        let color = if state.error.is_none() && running > 0 {
+            theme.foreground
+        } else {
+            theme.foreground_muted
+        };
+
+        div()
+            .flex()
+            .items_center()
+            .text_color(color)
+            .text_size(px(theme.font_size))
+            .child(SharedString::from(text))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        self.shared.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn display_text(&self) -> Option<String> {
+        Some(bar_text(&self.state()))
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let rows = self.state().containers.len().clamp(1, MAX_ROWS);
+        let height = POPUP_PADDING * 2.0 + HEADER_HEIGHT + rows as f32 * ROW_HEIGHT;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let state = self.state();
+        let running = state.containers.iter().filter(|c| c.is_running()).count();
+
+        let header = div()
+            .h(px(HEADER_HEIGHT))
+            .flex()
+            .items_center()
+            .justify_between()
+            .child(
+                div()
+                    .font_weight(gpui::FontWeight::SEMIBOLD)
+                    .child("Containers"),
+            )
+            .child(
+                div()
+                    .text_color(theme.foreground_muted)
+                    .child(SharedString::from(format!(
+                        "{} of {} running",
+                        running,
+                        state.containers.len()
+                    ))),
+            );
+
+        let mut list = div().flex().flex_col();
+        if let Some(err) = &state.error {
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .text_color(theme.foreground_muted)
+                    .child(SharedString::from(err.clone())),
+            );
+        } else if state.containers.is_empty() {
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .text_color(theme.foreground_muted)
+                    .child("No containers"),
+            );
+        }
+
+        for container in state.containers.iter().take(MAX_ROWS) {
+            let actions: &[ContainerAction] = if container.is_running() {
+                &[ContainerAction::Restart, ContainerAction::Stop]
+            } else {
+                &[ContainerAction::Start]
+            };
+            let pending = state.pending.contains(&container.id);
+            let mut buttons = div().flex().gap(px(4.0));
+            for &action in actions {
+                let value = format!("{}:{}", action.as_str(), container.id);
+                let variant = if action == ContainerAction::Stop {
+                    ButtonVariant::Destructive
+                } else {
+                    ButtonVariant::Outline
+                };
+                buttons = buttons.child(
+                    Button::new(SharedString::from(format!("{}-{}", self.id, value)))
+                        .label(action.label())
+                        .variant(variant)
+                        .text_size(px(theme.font_size - 2.0))
+                        .disabled(pending)
+                        .on_popup_action(self.id.clone(), PopupAction::Select { value })
+                        .render(theme),
+                );
+            }
+
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .child(
+                        div()
+                            .size(px(8.0))
+                            .rounded_full()
+                            .bg(state_color(theme, &container.state)),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .flex()
+                            .flex_col()
+                            .overflow_hidden()
+                            .child(SharedString::from(container.name().to_string()))
+                            .child(
+                                div()
+                                    .text_size(px(theme.font_size - 2.0))
+                                    .text_color(theme.foreground_muted)
+                                    .child(SharedString::from(format!(
+                                        "{} · {}",
+                                        container.image, container.status
+                                    ))),
+                            ),
+                    )
+                    .child(buttons),
+            );
+        }
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(header)
+                .child(list)
+                .into_any_element(),
+        )
+    }
+
+    fn on_popup_action(&mut self, action: PopupAction) {
+        match action {
+            PopupAction::Select { value } => {
+                if let Some((action, container_id)) = ContainerAction::parse_selection(&value) {
+                    self.run_action(action, container_id);
+                }
+            }
+            PopupAction::Refresh => {
+                let shared = Arc::clone(&self.shared);
+                let id = self.id.clone();
+                std::thread::spawn(move || {
+                    shared.refresh();
+                    notify_popup_needs_render(&id);
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Drop for DockerModule {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTAINERS: &str = r#"[
+        {"Id": "aaa111", "Names": ["/web"], "Image": "nginx", "State": "exited", "Status": "Exited (0) 2 hours ago"},
+        {"Id": "bbb222", "Names": ["/db"], "Image": "postgres:16", "State": "running", "Status": "Up 3 hours", "Ports": []}
+    ]"#;
+
+    #[test]
+    fn parses_containers_running_first() {
+        let containers = parse_containers(CONTAINERS).unwrap();
+        assert_eq!(containers.len(), 2);
+        assert_eq!(containers[0].name(), "db");
+        assert!(containers[0].is_running());
+        assert_eq!(containers[1].name(), "web");
+        assert_eq!(containers[1].image, "nginx");
+    }
+
+    #[test]
+    fn parses_http_response() {
+        let raw = "HTTP/1.0 404 Not Found\r\nContent-Type: application/json\r\n\r\n{\"message\":\"No such container: x\"}";
+        let (status, body) = parse_response(raw).unwrap();
+        assert_eq!(status, 404);
+        assert_eq!(api_error(status, body), "No such container: x");
+        assert!(parse_response("garbage").is_err());
+    }
+
+    #[test]
+    fn parses_selection() {
+        assert_eq!(
+            ContainerAction::parse_selection("restart:bbb222"),
+            Some((ContainerAction::Restart, "bbb222"))
+        );
+        assert_eq!(ContainerAction::parse_selection("kill:bbb222"), None);
+        assert_eq!(ContainerAction::parse_selection("start"), None);
+    }
+
+    #[test]
+    fn expands_home_in_socket_path() {
+        let home = Path::new("/Users/me");
+        assert_eq!(
+            expand_home("~/.colima/default/docker.sock", home),
+            PathBuf::from("/Users/me/.colima/default/docker.sock")
+        );
+        assert_eq!(
+            expand_home("/var/run/docker.sock", home),
+            PathBuf::from("/var/run/docker.sock")
+        );
+    }
+}
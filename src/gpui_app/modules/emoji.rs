@@ -0,0 +1,639 @@
+//! Emoji picker popup.
+//!
+//! A searchable, categorized emoji grid. Picking an emoji copies it to the
+//! clipboard (or types it into the frontmost app with `action = "type"`) and
+//! records it in a recents list persisted under the state directory.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use gpui::{div, prelude::*, px, AnyElement, MouseButton, SharedString, Styled};
+
+use super::launcher::fuzzy_score;
+use super::{dispatch_popup_action, GpuiModule, PopupAction, PopupEvent, PopupSpec};
+use crate::gpui_app::popup_manager::request_hide_popup;
+use crate::gpui_app::primitives::icons;
+use crate::gpui_app::primitives::{InputResult, TextInput, TextInputState};
+use crate::gpui_app::theme::Theme;
+
+const COLUMNS: usize = 10;
+const CELL_SIZE: f32 = 32.0;
+const POPUP_PADDING: f32 = 12.0;
+const POPUP_HEIGHT: f64 = 360.0;
+const MAX_RECENTS: usize = 20;
+
+/// Emoji categories, in display order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmojiCategory {
+    Smileys,
+    People,
+    Nature,
+    Food,
+    Activities,
+    Travel,
+    Objects,
+    Symbols,
+}
+
+impl EmojiCategory {
+    const ALL: [EmojiCategory; 8] = [
+        Self::Smileys,
+        Self::People,
+        Self::Nature,
+        Self::Food,
+        Self::Activities,
+        Self::Travel,
+        Self::Objects,
+        Self::Symbols,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            Self::Smileys => "Smileys",
+            Self::People => "People",
+            Self::Nature => "Animals & Nature",
+            Self::Food => "Food & Drink",
+            Self::Activities => "Activities",
+            Self::Travel => "Travel & Places",
+            Self::Objects => "Objects",
+            Self::Symbols => "Symbols",
+        }
+    }
+}
+
+use EmojiCategory::*;
+
+/// Built-in emoji table: (glyph, search name, category).
+const EMOJI: &[(&str, &str, EmojiCategory)] = &[
+    ("😀", "grinning face smile happy", Smileys),
+    ("😃", "smiley big eyes happy", Smileys),
+    ("😄", "smile grin happy", Smileys),
+    ("😁", "beaming grin", Smileys),
+    ("😆", "laughing squint", Smileys),
+    ("😅", "sweat smile relief", Smileys),
+    ("🤣", "rofl rolling laughing", Smileys),
+    ("😂", "joy tears laughing", Smileys),
+    ("🙂", "slight smile", Smileys),
+    ("🙃", "upside down", Smileys),
+    ("😉", "wink", Smileys),
+    ("😊", "blush smiling eyes", Smileys),
+    ("😇", "innocent halo angel", Smileys),
+    ("🥰", "smiling hearts love", Smileys),
+    ("😍", "heart eyes love", Smileys),
+    ("😘", "kiss blowing", Smileys),
+    ("😋", "yum tongue delicious", Smileys),
+    ("😜", "winking tongue", Smileys),
+    ("🤔", "thinking hmm", Smileys),
+    ("🤨", "raised eyebrow skeptical", Smileys),
+    ("😐", "neutral face", Smileys),
+    ("😑", "expressionless", Smileys),
+    ("🙄", "eye roll", Smileys),
+    ("😏", "smirk", Smileys),
+    ("😬", "grimace awkward", Smileys),
+    ("😌", "relieved", Smileys),
+    ("😴", "sleeping zzz", Smileys),
+    ("😷", "mask sick", Smileys),
+    ("🤯", "mind blown exploding head", Smileys),
+    ("🥳", "party celebrate", Smileys),
+    ("😎", "cool sunglasses", Smileys),
+    ("🤓", "nerd glasses", Smileys),
+    ("😕", "confused", Smileys),
+    ("😢", "cry sad tear", Smileys),
+    ("😭", "sob crying loudly", Smileys),
+    ("😱", "scream fear", Smileys),
+    ("😤", "triumph huff", Smileys),
+    ("😡", "angry pout rage", Smileys),
+    ("💀", "skull dead", Smileys),
+    ("💩", "poop", Smileys),
+    ("👋", "wave hello bye hand", People),
+    ("👌", "ok hand", People),
+    ("✌️", "victory peace", People),
+    ("🤞", "fingers crossed luck", People),
+    ("👍", "thumbs up like yes", People),
+    ("👎", "thumbs down dislike no", People),
+    ("👏", "clap applause", People),
+    ("🙌", "raised hands hooray", People),
+    ("🙏", "pray please thanks", People),
+    ("🤝", "handshake deal", People),
+    ("💪", "muscle strong flex", People),
+    ("👀", "eyes look", People),
+    ("🧠", "brain smart", People),
+    ("🤷", "shrug dunno", People),
+    ("🤦", "facepalm", People),
+    ("🙋", "raising hand", People),
+    ("🐶", "dog puppy", Nature),
+    ("🐱", "cat kitten", Nature),
+    ("🐭", "mouse", Nature),
+    ("🦊", "fox", Nature),
+    ("🐻", "bear", Nature),
+    ("🐼", "panda", Nature),
+    ("🐨", "koala", Nature),
+    ("🦁", "lion", Nature),
+    ("🐸", "frog", Nature),
+    ("🐵", "monkey", Nature),
+    ("🐧", "penguin", Nature),
+    ("🐦", "bird", Nature),
+    ("🦀", "crab rust ferris", Nature),
+    ("🐍", "snake python", Nature),
+    ("🐢", "turtle slow", Nature),
+    ("🐙", "octopus", Nature),
+    ("🦄", "unicorn", Nature),
+    ("🐝", "bee", Nature),
+    ("🌵", "cactus", Nature),
+    ("🌲", "evergreen tree", Nature),
+    ("🌸", "cherry blossom flower", Nature),
+    ("🌻", "sunflower", Nature),
+    ("🍀", "four leaf clover luck", Nature),
+    ("🔥", "fire hot lit", Nature),
+    ("⭐", "star", Nature),
+    ("🌙", "moon crescent night", Nature),
+    ("☀️", "sun sunny", Nature),
+    ("🌈", "rainbow", Nature),
+    ("❄️", "snowflake cold", Nature),
+    ("⚡", "lightning zap high voltage", Nature),
+    ("🍎", "apple red", Food),
+    ("🍌", "banana", Food),
+    ("🍓", "strawberry", Food),
+    ("🍉", "watermelon", Food),
+    ("🥑", "avocado", Food),
+    ("🌶️", "hot pepper chili", Food),
+    ("🍞", "bread", Food),
+    ("🧀", "cheese", Food),
+    ("🍔", "hamburger burger", Food),
+    ("🍟", "fries", Food),
+    ("🍕", "pizza", Food),
+    ("🌮", "taco", Food),
+    ("🍣", "sushi", Food),
+    ("🍜", "ramen noodles", Food),
+    ("🍩", "doughnut donut", Food),
+    ("🍪", "cookie", Food),
+    ("🎂", "birthday cake", Food),
+    ("🍫", "chocolate", Food),
+    ("☕", "coffee hot beverage", Food),
+    ("🍵", "tea", Food),
+    ("🍺", "beer", Food),
+    ("🍷", "wine", Food),
+    ("🥂", "cheers clinking glasses", Food),
+    ("⚽", "soccer football", Activities),
+    ("🏀", "basketball", Activities),
+    ("🏈", "american football", Activities),
+    ("🎾", "tennis", Activities),
+    ("🏓", "ping pong table tennis", Activities),
+    ("🎯", "bullseye target dart", Activities),
+    ("🎮", "video game controller", Activities),
+    ("🎲", "dice game", Activities),
+    ("🧩", "puzzle piece", Activities),
+    ("🎨", "art palette", Activities),
+    ("🎸", "guitar", Activities),
+    ("🎧", "headphones music", Activities),
+    ("🏆", "trophy winner", Activities),
+    ("🥇", "gold medal first", Activities),
+    ("🎉", "tada party popper", Activities),
+    ("🎁", "gift present", Activities),
+    ("🚗", "car automobile", Travel),
+    ("🚕", "taxi", Travel),
+    ("🚌", "bus", Travel),
+    ("🚲", "bicycle bike", Travel),
+    ("🚂", "train locomotive", Travel),
+    ("✈️", "airplane flight", Travel),
+    ("🚀", "rocket launch ship", Travel),
+    ("⛵", "sailboat", Travel),
+    ("🏠", "house home", Travel),
+    ("🏢", "office building", Travel),
+    ("🏖️", "beach", Travel),
+    ("⛰️", "mountain", Travel),
+    ("🌍", "globe earth world", Travel),
+    ("🗺️", "map world", Travel),
+    ("💻", "laptop computer", Objects),
+    ("⌨️", "keyboard", Objects),
+    ("🖥️", "desktop computer", Objects),
+    ("📱", "phone mobile", Objects),
+    ("🔋", "battery", Objects),
+    ("🔌", "plug electric", Objects),
+    ("💡", "bulb idea light", Objects),
+    ("🔦", "flashlight", Objects),
+    ("📷", "camera", Objects),
+    ("📺", "television tv", Objects),
+    ("⏰", "alarm clock", Objects),
+    ("⏳", "hourglass timer", Objects),
+    ("📅", "calendar date", Objects),
+    ("📌", "pushpin pin", Objects),
+    ("📎", "paperclip", Objects),
+    ("✏️", "pencil", Objects),
+    ("📝", "memo note", Objects),
+    ("📦", "package box", Objects),
+    ("🔒", "lock locked", Objects),
+    ("🔑", "key", Objects),
+    ("🔨", "hammer", Objects),
+    ("🔧", "wrench tool", Objects),
+    ("⚙️", "gear settings", Objects),
+    ("🧪", "test tube experiment", Objects),
+    ("💊", "pill medicine", Objects),
+    ("💰", "money bag", Objects),
+    ("✉️", "envelope email mail", Objects),
+    ("🐛", "bug", Objects),
+    ("❤️", "red heart love", Symbols),
+    ("🧡", "orange heart", Symbols),
+    ("💛", "yellow heart", Symbols),
+    ("💚", "green heart", Symbols),
+    ("💙", "blue heart", Symbols),
+    ("💜", "purple heart", Symbols),
+    ("🖤", "black heart", Symbols),
+    ("💔", "broken heart", Symbols),
+    ("💯", "hundred points perfect", Symbols),
+    ("✅", "check mark done yes", Symbols),
+    ("❌", "cross mark no wrong", Symbols),
+    ("⚠️", "warning caution", Symbols),
+    ("🚫", "prohibited no entry", Symbols),
+    ("❓", "question mark", Symbols),
+    ("❗", "exclamation mark", Symbols),
+    ("➕", "plus add", Symbols),
+    ("➖", "minus", Symbols),
+    ("➡️", "right arrow", Symbols),
+    ("⬅️", "left arrow", Symbols),
+    ("🔄", "arrows refresh reload", Symbols),
+    ("🔴", "red circle", Symbols),
+    ("🟢", "green circle", Symbols),
+    ("🔵", "blue circle", Symbols),
+    ("✨", "sparkles", Symbols),
+    ("💤", "zzz sleep", Symbols),
+];
+
+/// Returns emoji matching `query`, best match first.
+pub fn search(query: &str) -> Vec<&'static str> {
+    let mut scored: Vec<(i32, &'static str)> = EMOJI
+        .iter()
+        .filter_map(|(glyph, name, _)| {
+            // Score each keyword separately so "heart" ranks "red heart" highly
+            name.split(' ')
+                .filter_map(|word| fuzzy_score(query, word))
+                .max()
+                .map(|score| (score, *glyph))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0));
+    scored.into_iter().map(|(_, glyph)| glyph).collect()
+}
+
+/// What happens to a picked emoji.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmojiAction {
+    /// Copy to the clipboard
+    #[default]
+    Copy,
+    /// Type into the frontmost app
+    Type,
+}
+
+impl EmojiAction {
+    /// Parses an action name, defaulting to copy.
+    pub fn parse(value: Option<&str>) -> Self {
+        match value {
+            Some("type") => Self::Type,
+            _ => Self::Copy,
+        }
+    }
+
+    /// Delivers the emoji off the main thread.
+    fn perform(self, glyph: &str) {
+        let glyph = glyph.to_string();
+        std::thread::spawn(move || match self {
+            Self::Copy => copy_to_clipboard(&glyph),
+            Self::Type => {
+                // Give the previous app a moment to regain focus
+                std::thread::sleep(Duration::from_millis(150));
+                type_text(&glyph);
+            }
+        });
+    }
+}
+
+/// Puts `text` on the clipboard.
+pub(super) fn copy_to_clipboard(text: &str) {
+    let child = Command::new("pbcopy").stdin(Stdio::piped()).spawn();
+    match child {
+        Ok(mut child) => {
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = stdin.write_all(text.as_bytes());
+            }
+            let _ = child.wait();
+        }
+        Err(err) => log::warn!("Failed to run pbcopy: {}", err),
+    }
+}
+
+/// Types `text` into the frontmost app with a synthetic key event.
+fn type_text(text: &str) {
+    use std::ffi::c_void;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventCreateKeyboardEvent(source: *const c_void, key: u16, down: bool) -> *mut c_void;
+        fn CGEventKeyboardSetUnicodeString(event: *mut c_void, length: usize, chars: *const u16);
+        fn CGEventPost(tap: u32, event: *mut c_void);
+    }
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFRelease(cf: *const c_void);
+    }
+    const HID_EVENT_TAP: u32 = 0;
+
+    let chars: Vec<u16> = text.encode_utf16().collect();
+    unsafe {
+        for down in [true, false] {
+            let event = CGEventCreateKeyboardEvent(std::ptr::null(), 0, down);
+            if event.is_null() {
+                log::warn!("Emoji: failed to create key event");
+                return;
+            }
+            CGEventKeyboardSetUnicodeString(event, chars.len(), chars.as_ptr());
+            CGEventPost(HID_EVENT_TAP, event);
+            CFRelease(event);
+        }
+    }
+}
+
+/// Most-recently-used emoji, newest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recents {
+    items: Vec<String>,
+}
+
+impl Recents {
+    /// Parses a recents file (one emoji per line).
+    pub fn parse(content: &str) -> Self {
+        let items = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .take(MAX_RECENTS)
+            .map(str::to_string)
+            .collect();
+        Self { items }
+    }
+
+    /// Moves `glyph` to the front, dropping the oldest past the limit.
+    pub fn push(&mut self, glyph: &str) {
+        self.items.retain(|item| item != glyph);
+        self.items.insert(0, glyph.to_string());
+        self.items.truncate(MAX_RECENTS);
+    }
+
+    fn path() -> PathBuf {
+        crate::config::get_state_dir().join("emoji-recent")
+    }
+
+    fn load() -> Self {
+        std::fs::read_to_string(Self::path())
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if let Err(err) = std::fs::write(&path, self.items.join("\n")) {
+            log::warn!("Emoji: failed to save recents: {}", err);
+        }
+    }
+}
+
+/// Emoji picker popup module.
+pub struct EmojiModule {
+    id: String,
+    action: EmojiAction,
+    input: TextInputState,
+    recents: Recents,
+    selected: usize,
+}
+
+impl EmojiModule {
+    /// Creates an emoji picker.
+    pub fn new(id: &str, action: EmojiAction) -> Self {
+        Self {
+            id: id.to_string(),
+            action,
+            input: TextInputState::new(),
+            recents: Recents::load(),
+            selected: 0,
+        }
+    }
+
+    /// Grid sections currently shown: search results, or recents followed by
+    /// every category.
+    fn sections(&self) -> Vec<(&'static str, Vec<String>)> {
+        let query = self.input.text();
+        if !query.is_empty() {
+            let results = search(query).into_iter().map(str::to_string).collect();
+            return vec![("Results", results)];
+        }
+        let mut sections = Vec::new();
+        if !self.recents.items.is_empty() {
+            sections.push(("Recent", self.recents.items.clone()));
+        }
+        for category in EmojiCategory::ALL {
+            let glyphs = EMOJI
+                .iter()
+                .filter(|(_, _, c)| *c == category)
+                .map(|(glyph, _, _)| glyph.to_string())
+                .collect();
+            sections.push((category.title(), glyphs));
+        }
+        sections
+    }
+
+    fn pick(&mut self, glyph: &str) {
+        self.action.perform(glyph);
+        self.recents.push(glyph);
+        self.recents.save();
+    }
+
+    fn render_cell(&self, index: usize, glyph: String, theme: &Theme) -> AnyElement {
+        let id = self.id.clone();
+        let hover_bg = theme.surface_hover;
+        let mut cell = div()
+            .id(SharedString::from(format!("emoji-{}", index)))
+            .flex()
+            .items_center()
+            .justify_center()
+            .size(px(CELL_SIZE))
+            .rounded(px(4.0))
+            .text_size(px(CELL_SIZE * 0.6))
+            .cursor_pointer()
+            .hover(move |s| s.bg(hover_bg))
+            .child(SharedString::from(glyph.clone()));
+        if index == self.selected {
+            cell = cell.bg(theme.surface_active);
+        }
+        cell.on_mouse_down(MouseButton::Left, move |_event, _window, _cx| {
+            dispatch_popup_action(
+                &id,
+                PopupAction::Select {
+                    value: glyph.clone(),
+                },
+            );
+        })
+        .into_any_element()
+    }
+}
+
+impl GpuiModule for EmojiModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, _theme: &Theme) -> AnyElement {
+        div().into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        false
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let width = CELL_SIZE * COLUMNS as f32 + POPUP_PADDING * 2.0;
+        Some(PopupSpec::new(width as f64, POPUP_HEIGHT).with_keyboard())
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let mut index = 0;
+        let mut grid = div()
+            .id("emoji-grid")
+            .flex()
+            .flex_col()
+            .flex_1()
+            .gap(px(6.0))
+            .overflow_y_scroll();
+        for (title, glyphs) in self.sections() {
+            let cells: Vec<AnyElement> = glyphs
+                .into_iter()
+                .map(|glyph| {
+                    let cell = self.render_cell(index, glyph, theme);
+                    index += 1;
+                    cell
+                })
+                .collect();
+            grid = grid
+                .child(
+                    div()
+                        .text_size(px((theme.font_size - 2.0).max(9.0)))
+                        .text_color(theme.foreground_muted)
+                        .child(title),
+                )
+                .child(div().flex().flex_row().flex_wrap().children(cells));
+        }
+        if index == 0 {
+            grid = grid.child(
+                div()
+                    .text_color(theme.foreground_muted)
+                    .child("No matching emoji"),
+            );
+        }
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .gap(px(8.0))
+                .size_full()
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(
+                    TextInput::new(&self.input)
+                        .placeholder("Search emoji")
+                        .icon(icons::system::SEARCH)
+                        .render(theme),
+                )
+                .child(grid)
+                .into_any_element(),
+        )
+    }
+
+    fn on_popup_event(&mut self, event: PopupEvent) {
+        match event {
+            PopupEvent::Opened => {
+                self.input.set_text("");
+                self.selected = 0;
+            }
+            PopupEvent::Key(key) => {
+                let count: usize = self.sections().iter().map(|(_, g)| g.len()).sum();
+                let last = count.saturating_sub(1);
+                match key.key.as_str() {
+                    "left" => self.selected = self.selected.saturating_sub(1),
+                    "right" => self.selected = (self.selected + 1).min(last),
+                    "up" => self.selected = self.selected.saturating_sub(COLUMNS),
+                    "down" => self.selected = (self.selected + COLUMNS).min(last),
+                    _ => match self.input.handle_key(&key) {
+                        InputResult::Edited => self.selected = 0,
+                        InputResult::Submitted => {
+                            let glyph = self
+                                .sections()
+                                .into_iter()
+                                .flat_map(|(_, glyphs)| glyphs)
+                                .nth(self.selected);
+                            if let Some(glyph) = glyph {
+                                self.pick(&glyph);
+                                request_hide_popup();
+                            }
+                        }
+                        InputResult::Moved | InputResult::Ignored => {}
+                    },
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_popup_action(&mut self, action: PopupAction) {
+        if let PopupAction::Select { value } = action {
+            self.pick(&value);
+            request_hide_popup();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_matches_keywords() {
+        let results = search("heart");
+        assert!(results.contains(&"❤️"));
+        assert!(results.contains(&"😍"));
+        assert!(!results.contains(&"🍕"));
+    }
+
+    #[test]
+    fn search_ranks_prefix_matches_first() {
+        assert_eq!(search("pizza").first(), Some(&"🍕"));
+    }
+
+    #[test]
+    fn recents_move_to_front_and_cap() {
+        let mut recents = Recents::parse("😀\n🔥\n");
+        recents.push("🔥");
+        assert_eq!(recents.items, vec!["🔥", "😀"]);
+        for i in 0..MAX_RECENTS {
+            recents.push(&i.to_string());
+        }
+        assert_eq!(recents.items.len(), MAX_RECENTS);
+        assert!(!recents.items.contains(&"😀".to_string()));
+    }
+
+    #[test]
+    fn every_category_has_emoji() {
+        for category in EmojiCategory::ALL {
+            assert!(EMOJI.iter().any(|(_, _, c)| *c == category));
+        }
+    }
+}
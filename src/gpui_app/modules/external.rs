@@ -5,6 +5,7 @@
 
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
 
 use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
 
@@ -117,6 +118,25 @@ impl GpuiModule for ExternalModule {
         false
     }
 
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn display_text(&self) -> Option<String> {
+        let guard = self.state.lock().ok()?;
+        if !guard.drawing {
+            return None;
+        }
+        let text = guard
+            .icon
+            .iter()
+            .chain(Some(&guard.label).filter(|label| !label.is_empty()))
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ");
+        (!text.is_empty()).then_some(text)
+    }
+
     fn set_property(&mut self, key: &str, value: &str) -> bool {
         let Ok(mut guard) = self.state.lock() else {
             return false;
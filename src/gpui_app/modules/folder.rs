@@ -0,0 +1,618 @@
+//! Folder watcher module.
+//!
+//! Watches a directory (Downloads by default, or the Trash) with FSEvents
+//! and shows how many items it holds and their total size. Clicking opens a
+//! popup listing the newest items; click one to reveal it in Finder. For the
+//! Trash, the popup also offers "Empty Trash" behind a confirmation.
+//!
+//! `format` placeholders: `{icon}`, `{count}` (top-level items), `{size}`
+//! (e.g. "1.5 GB") and `{name}` (the folder's name).
+//!
+//! ```toml
+//! { type = "folder", path = "trash", format = "{icon} {size}" }
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use gpui::{div, prelude::*, px, AnyElement, MouseButton, SharedString, Styled};
+use notify::{RecursiveMode, Watcher};
+
+use super::sysmon::format_bytes;
+use super::template::{Template, Value};
+use super::{dispatch_popup_action, mark_dirty, GpuiModule, PopupAction, PopupEvent, PopupSpec};
+use crate::gpui_app::popup_manager::{notify_popup_needs_render, request_hide_popup};
+use crate::gpui_app::primitives::{expand_tilde, icons, Button};
+use crate::gpui_app::theme::{ButtonVariant, Theme};
+
+const DEFAULT_FORMAT: &str = "{icon} {count}";
+/// Quiet time after a file event before rescanning, so a download or a
+/// Finder copy triggers one scan instead of hundreds.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+const POPUP_WIDTH: f64 = 340.0;
+const POPUP_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 24.0;
+const FOOTER_HEIGHT: f32 = 36.0;
+const ROW_HEIGHT: f32 = 28.0;
+const MAX_ROWS: usize = 10;
+
+/// Resolves `path`: `trash`, `downloads`, or a path with an optional `~/`.
+pub fn resolve_path(path: &str) -> PathBuf {
+    match path {
+        "trash" => expand_tilde("~/.Trash"),
+        "downloads" => expand_tilde("~/Downloads"),
+        other => expand_tilde(other),
+    }
+}
+
+/// Returns true if `path` is the user's Trash.
+pub fn is_trash(path: &Path) -> bool {
+    dirs::home_dir().is_some_and(|home| path == home.join(".Trash"))
+}
+
+/// A top-level item of the folder.
+#[derive(Debug, Clone, PartialEq)]
+struct Item {
+    name: String,
+    path: PathBuf,
+    bytes: u64,
+    modified: Option<SystemTime>,
+}
+
+/// Result of scanning the folder.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Listing {
+    /// Newest first
+    items: Vec<Item>,
+    bytes: u64,
+}
+
+/// Size of `path`, recursing into directories without following symlinks.
+/// Unreadable entries count as empty.
+fn disk_size(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| disk_size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Lists the visible top-level items of `dir` with their sizes.
+fn scan(dir: &Path) -> Result<Listing, String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| match e.kind() {
+        std::io::ErrorKind::PermissionDenied => {
+            "No access: grant sinew Full Disk Access".to_string()
+        }
+        _ => format!("Can't read {}: {}", dir.display(), e),
+    })?;
+    let mut items: Vec<Item> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // .DS_Store, .localized and friends
+            if name.starts_with('.') {
+                return None;
+            }
+            let path = entry.path();
+            let modified = std::fs::symlink_metadata(&path)
+                .and_then(|m| m.modified())
+                .ok();
+            Some(Item {
+                name,
+                bytes: disk_size(&path),
+                path,
+                modified,
+            })
+        })
+        .collect();
+    items.sort_by(|a, b| b.modified.cmp(&a.modified).then(a.name.cmp(&b.name)));
+    let bytes = items.iter().map(|item| item.bytes).sum();
+    Ok(Listing { items, bytes })
+}
+
+/// Latest scan of the folder.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct FolderState {
+    listing: Listing,
+    /// Set when the folder couldn't be read
+    error: Option<String>,
+    loaded: bool,
+}
+
+/// State shared between the bar and popup copies of the module.
+struct Shared {
+    path: PathBuf,
+    state: Mutex<FolderState>,
+    /// Set after the first "Empty Trash" click, until the popup closes
+    confirming: AtomicBool,
+    dirty: AtomicBool,
+    stop: AtomicBool,
+}
+
+impl Shared {
+    fn refresh(&self, id: &str) {
+        let next = match scan(&self.path) {
+            Ok(listing) => FolderState {
+                listing,
+                error: None,
+                loaded: true,
+            },
+            Err(err) => FolderState {
+                listing: Listing::default(),
+                error: Some(err),
+                loaded: true,
+            },
+        };
+        if let Ok(mut guard) = self.state.lock() {
+            if *guard != next {
+                if let Some(err) = &next.error {
+                    log::warn!("folder: {}", err);
+                }
+                *guard = next;
+                mark_dirty(&self.dirty);
+            }
+        }
+        notify_popup_needs_render(id);
+    }
+}
+
+/// Waits until something changes under the folder (then until it has been
+/// quiet for [`DEBOUNCE`]), `interval` passes, or the module stops.
+fn wait_for_change(events: Option<&Receiver<()>>, interval: Duration, stop: &AtomicBool) {
+    const STEP: Duration = Duration::from_millis(200);
+    let deadline = Instant::now() + interval;
+    while !stop.load(Ordering::Relaxed) && Instant::now() < deadline {
+        let Some(events) = events else {
+            std::thread::sleep(STEP);
+            continue;
+        };
+        match events.recv_timeout(STEP) {
+            Ok(()) => {
+                while events.recv_timeout(DEBOUNCE).is_ok() {}
+                return;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => std::thread::sleep(STEP),
+        }
+    }
+}
+
+/// Folder watcher module.
+pub struct FolderModule {
+    id: String,
+    format: Template,
+    /// Offer "Empty Trash" in the popup
+    empty_trash: bool,
+    shared: Arc<Shared>,
+}
+
+impl FolderModule {
+    /// Creates a new folder module and starts watching `path`.
+    ///
+    /// @param id - Unique module identifier
+    /// @param path - Folder to watch
+    /// @param empty_trash - Offer "Empty Trash" (only honored for the Trash)
+    /// @param interval - Seconds between rescans when no events arrive
+    /// @param format - Bar text (None = "{icon} {count}")
+    pub fn new(
+        id: &str,
+        path: PathBuf,
+        empty_trash: bool,
+        interval: u64,
+        format: Option<&str>,
+    ) -> Self {
+        let empty_trash = empty_trash && is_trash(&path);
+        let shared = Arc::new(Shared {
+            path,
+            state: Mutex::new(FolderState::default()),
+            confirming: AtomicBool::new(false),
+            dirty: AtomicBool::new(true),
+            stop: AtomicBool::new(false),
+        });
+
+        let handle = Arc::clone(&shared);
+        let worker_id = id.to_string();
+        std::thread::spawn(move || {
+            let (tx, rx) = channel();
+            // Kept alive for as long as the loop runs
+            let watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+                if res.is_ok() {
+                    let _ = tx.send(());
+                }
+            })
+            .and_then(|mut watcher| {
+                watcher.watch(&handle.path, RecursiveMode::Recursive)?;
+                Ok(watcher)
+            });
+            if let Err(err) = &watcher {
+                log::warn!(
+                    "folder: can't watch {}, polling instead: {}",
+                    handle.path.display(),
+                    err
+                );
+            }
+            let events = watcher.is_ok().then_some(&rx);
+            while !handle.stop.load(Ordering::Relaxed) {
+                handle.refresh(&worker_id);
+                wait_for_change(events, Duration::from_secs(interval.max(10)), &handle.stop);
+            }
+        });
+
+        Self {
+            id: id.to_string(),
+            format: Template::parse_or_literal(format.unwrap_or(DEFAULT_FORMAT)),
+            empty_trash,
+            shared,
+        }
+    }
+
+    /// Returns a second handle on the same state for the popup registry.
+    pub fn popup_handle(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            format: self.format.clone(),
+            empty_trash: self.empty_trash,
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    fn state(&self) -> FolderState {
+        self.shared
+            .state
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+
+    fn icon(&self) -> &'static str {
+        if is_trash(&self.shared.path) {
+            icons::system::TRASH
+        } else {
+            icons::system::FOLDER
+        }
+    }
+
+    fn name(&self) -> String {
+        if is_trash(&self.shared.path) {
+            return "Trash".to_string();
+        }
+        self.shared
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.shared.path.display().to_string())
+    }
+
+    /// Empties the Trash through Finder, then rescans.
+    fn empty_the_trash(&self) {
+        let shared = Arc::clone(&self.shared);
+        let id = self.id.clone();
+        std::thread::spawn(move || {
+            match Command::new("osascript")
+                .args(["-e", "tell application \"Finder\" to empty trash"])
+                .status()
+            {
+                Ok(status) if !status.success() => {
+                    log::warn!("folder: emptying the Trash failed: {}", status)
+                }
+                Err(err) => log::warn!("folder: can't run osascript: {}", err),
+                Ok(_) => {}
+            }
+            shared.refresh(&id);
+        });
+    }
+
+    fn footer(&self, theme: &Theme, state: &FolderState) -> impl IntoElement {
+        let button = |name: &str, label: &str, variant: ButtonVariant| {
+            Button::new(SharedString::from(format!("{}-{}", self.id, name)))
+                .label(label.to_string())
+                .variant(variant)
+                .text_size(px(theme.font_size - 2.0))
+                .on_popup_action(
+                    self.id.clone(),
+                    PopupAction::Select {
+                        value: name.to_string(),
+                    },
+                )
+                .render(theme)
+        };
+        let mut footer = div()
+            .h(px(FOOTER_HEIGHT))
+            .flex()
+            .items_end()
+            .justify_end()
+            .gap(px(6.0));
+        if !self.empty_trash {
+            return footer.child(button("open", "Open in Finder", ButtonVariant::Outline));
+        }
+        if self.shared.confirming.load(Ordering::Relaxed) {
+            let count = state.listing.items.len();
+            footer = footer
+                .child(
+                    div()
+                        .flex_1()
+                        .text_color(theme.foreground_muted)
+                        .text_size(px(theme.font_size - 2.0))
+                        .child(SharedString::from(format!(
+                            "Erase {} item{} permanently?",
+                            count,
+                            if count == 1 { "" } else { "s" }
+                        ))),
+                )
+                .child(button("cancel", "Cancel", ButtonVariant::Outline))
+                .child(button("confirm-empty", "Empty", ButtonVariant::Destructive));
+        } else {
+            footer = footer
+                .child(button("open", "Open in Finder", ButtonVariant::Outline))
+                .child(
+                    Button::new(SharedString::from(format!("{}-empty", self.id)))
+                        .label("Empty Trash")
+                        .variant(ButtonVariant::Destructive)
+                        .text_size(px(theme.font_size - 2.0))
+                        .disabled(state.listing.items.is_empty())
+                        .on_popup_action(
+                            self.id.clone(),
+                            PopupAction::Select {
+                                value: "empty".to_string(),
+                            },
+                        )
+                        .render(theme),
+                );
+        }
+        footer
+    }
+
+    /// The bar text for `state`, rendered through `format`.
+    fn text(&self, state: &FolderState) -> String {
+        let (count, size) = if state.loaded && state.error.is_none() {
+            (
+                Some(Value::from(state.listing.items.len() as f64)),
+                Some(Value::from(format_bytes(state.listing.bytes as f64))),
+            )
+        } else {
+            (None, None)
+        };
+        self.format.render(&[
+            ("icon", Some(Value::from(self.icon()))),
+            ("count", count),
+            ("size", size),
+            ("name", Some(Value::from(self.name()))),
+        ])
+    }
+}
+
+impl GpuiModule for FolderModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let state = self.state();
+        let empty = state.listing.items.is_empty();
+        let text = self.text(&state);
+
+        div()
+            .flex()
+            .items_center()
+            .text_color(if empty {
+                theme.foreground_muted
+            } else {
+                theme.foreground
+            })
+            .text_size(px(theme.font_size))
+            .child(SharedString::from(text))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        self.shared.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn is_loading(&self) -> bool {
+        !self.state().loaded
+    }
+
+    fn value(&self) -> Option<u8> {
+        let state = self.state();
+        state
+            .loaded
+            .then(|| state.listing.items.len().min(100) as u8)
+    }
+
+    fn display_text(&self) -> Option<String> {
+        Some(self.text(&self.state()))
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let rows = self.state().listing.items.len().clamp(1, MAX_ROWS);
+        let height = POPUP_PADDING * 2.0 + HEADER_HEIGHT + rows as f32 * ROW_HEIGHT + FOOTER_HEIGHT;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let state = self.state();
+        let count = state.listing.items.len();
+
+        let header = div()
+            .h(px(HEADER_HEIGHT))
+            .flex()
+            .items_center()
+            .justify_between()
+            .child(
+                div()
+                    .font_weight(gpui::FontWeight::SEMIBOLD)
+                    .child(SharedString::from(self.name())),
+            )
+            .child(
+                div()
+                    .text_color(theme.foreground_muted)
+                    .child(SharedString::from(format!(
+                        "{} item{} · {}",
+                        count,
+                        if count == 1 { "" } else { "s" },
+                        format_bytes(state.listing.bytes as f64)
+                    ))),
+            );
+
+        let message_row = |message: SharedString| {
+            div()
+                .h(px(ROW_HEIGHT))
+                .flex()
+                .items_center()
+                .text_color(theme.foreground_muted)
+                .child(message)
+        };
+        let mut list = div().flex().flex_col();
+        if let Some(err) = &state.error {
+            list = list.child(message_row(SharedString::from(err.clone())));
+        } else if state.loaded && count == 0 {
+            list = list.child(message_row("Empty".into()));
+        }
+
+        for item in state.listing.items.iter().take(MAX_ROWS) {
+            let id = self.id.clone();
+            let path = item.path.display().to_string();
+            list = list.child(
+                div()
+                    .id(SharedString::from(format!("{}-{}", self.id, item.name)))
+                    .h(px(ROW_HEIGHT))
+                    .px(px(4.0))
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .rounded(px(4.0))
+                    .cursor_pointer()
+                    .hover(|s| s.bg(theme.surface_hover))
+                    .on_mouse_down(MouseButton::Left, move |_event, _window, _cx| {
+                        dispatch_popup_action(
+                            &id,
+                            PopupAction::Select {
+                                value: format!("reveal:{}", path),
+                            },
+                        );
+                    })
+                    .child(
+                        div()
+                            .flex_1()
+                            .overflow_hidden()
+                            .child(SharedString::from(item.name.clone())),
+                    )
+                    .child(
+                        div()
+                            .text_size(px(theme.font_size - 2.0))
+                            .text_color(theme.foreground_muted)
+                            .child(SharedString::from(format_bytes(item.bytes as f64))),
+                    ),
+            );
+        }
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(header)
+                .child(list)
+                .child(self.footer(theme, &state))
+                .into_any_element(),
+        )
+    }
+
+    fn on_popup_action(&mut self, action: PopupAction) {
+        let PopupAction::Select { value } = action else {
+            return;
+        };
+        let open = |args: &[&str]| {
+            if let Err(err) = Command::new("open").args(args).spawn() {
+                log::warn!("folder: can't open Finder: {}", err);
+            }
+            request_hide_popup();
+        };
+        match value.as_str() {
+            "open" => open(&[&self.shared.path.display().to_string()]),
+            "empty" if self.empty_trash => {
+                self.shared.confirming.store(true, Ordering::Relaxed);
+            }
+            "cancel" => self.shared.confirming.store(false, Ordering::Relaxed),
+            "confirm-empty" if self.empty_trash => {
+                self.shared.confirming.store(false, Ordering::Relaxed);
+                self.empty_the_trash();
+            }
+            other => {
+                if let Some(path) = other.strip_prefix("reveal:") {
+                    open(&["-R", path]);
+                }
+            }
+        }
+    }
+
+    fn on_popup_event(&mut self, event: PopupEvent) {
+        if matches!(event, PopupEvent::Closed) {
+            self.shared.confirming.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for FolderModule {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_paths() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(resolve_path("trash"), home.join(".Trash"));
+        assert_eq!(resolve_path("~/Downloads"), home.join("Downloads"));
+        assert_eq!(resolve_path("/tmp/inbox"), PathBuf::from("/tmp/inbox"));
+        assert!(is_trash(&resolve_path("trash")));
+        assert!(!is_trash(&resolve_path("downloads")));
+    }
+
+    #[test]
+    fn scans_visible_items_with_sizes() {
+        let dir = std::env::temp_dir().join(format!("sinew-folder-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("album/disc 2")).unwrap();
+        std::fs::write(dir.join("report.pdf"), vec![0u8; 1000]).unwrap();
+        std::fs::write(dir.join("album/a.mp3"), vec![0u8; 300]).unwrap();
+        std::fs::write(dir.join("album/disc 2/b.mp3"), vec![0u8; 200]).unwrap();
+        std::fs::write(dir.join(".DS_Store"), vec![0u8; 50]).unwrap();
+
+        let listing = scan(&dir).unwrap();
+        let mut sizes: Vec<(&str, u64)> = listing
+            .items
+            .iter()
+            .map(|item| (item.name.as_str(), item.bytes))
+            .collect();
+        sizes.sort();
+        assert_eq!(sizes, [("album", 500), ("report.pdf", 1000)]);
+        assert_eq!(listing.bytes, 1500);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(scan(&dir).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! Popup graphing a module's recorded values over the last 24 hours.
+//!
+//! Shown for modules with `history = true` that have no popup of their own,
+//! or that set `popup = "history"`.
+
+use chrono::Utc;
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+
+use super::{GpuiModule, PopupSpec};
+use crate::gpui_app::history::{self, RETENTION_SECS};
+use crate::gpui_app::primitives::{Chart, ChartKind};
+use crate::gpui_app::theme::Theme;
+
+const POPUP_WIDTH: f32 = 320.0;
+const POPUP_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 24.0;
+const CHART_HEIGHT: f32 = 90.0;
+/// Ten-minute buckets
+const BUCKETS: usize = 144;
+
+/// History popup of one module.
+pub struct HistoryPopupModule {
+    id: String,
+    title: String,
+}
+
+impl HistoryPopupModule {
+    /// Creates the popup for module `id`, headed `title`.
+    pub fn new(id: &str, title: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            title: title.to_string(),
+        }
+    }
+}
+
+/// Formats a past offset as "24h ago" or "40m ago".
+fn ago(secs: i64) -> String {
+    if secs >= 3600 {
+        format!("{}h ago", secs / 3600)
+    } else {
+        format!("{}m ago", (secs / 60).max(1))
+    }
+}
+
+impl GpuiModule for HistoryPopupModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, _theme: &Theme) -> AnyElement {
+        div().into_any_element()
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let height = POPUP_PADDING * 2.0 + HEADER_HEIGHT + CHART_HEIGHT + 14.0;
+        Some(PopupSpec::new(POPUP_WIDTH as f64, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let points = history::last_day(&self.id);
+        let (values, offset) =
+            history::buckets(&points, Utc::now().timestamp(), RETENTION_SECS, BUCKETS);
+        let summary = match (
+            points.iter().map(|(_, v)| *v).min(),
+            points.iter().map(|(_, v)| *v).max(),
+        ) {
+            (Some(min), Some(max)) => format!("{} – {}", min, max),
+            _ => String::new(),
+        };
+
+        let body = if values.is_empty() {
+            div()
+                .h(px(CHART_HEIGHT))
+                .flex()
+                .items_center()
+                .justify_center()
+                .text_color(theme.foreground_muted)
+                .child("No history yet")
+        } else {
+            Chart::new(ChartKind::Line, values)
+                .size(POPUP_WIDTH - POPUP_PADDING * 2.0, CHART_HEIGHT)
+                .x_labels(ago(offset), "now")
+                .render(theme)
+        };
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(
+                    div()
+                        .h(px(HEADER_HEIGHT))
+                        .flex()
+                        .items_center()
+                        .justify_between()
+                        .child(
+                            div()
+                                .font_weight(gpui::FontWeight::SEMIBOLD)
+                                .child(SharedString::from(self.title.clone())),
+                        )
+                        .child(
+                            div()
+                                .text_color(theme.foreground_muted)
+                                .child(SharedString::from(summary)),
+                        ),
+                )
+                .child(body)
+                .into_any_element(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_offsets() {
+        assert_eq!(ago(RETENTION_SECS), "24h ago");
+        assert_eq!(ago(5400), "1h ago");
+        assert_eq!(ago(2400), "40m ago");
+        assert_eq!(ago(10), "1m ago");
+    }
+}
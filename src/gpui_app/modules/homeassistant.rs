@@ -0,0 +1,687 @@
+//! Home Assistant module.
+//!
+//! Talks to a Home Assistant instance over its WebSocket API: reads the
+//! current state of the configured entities once, then follows
+//! `state_changed` events, so the bar updates as soon as Home Assistant
+//! does. Clicking calls a service, e.g. to toggle a light.
+//!
+//! `entities` takes entity ids or patterns with `*` (`light.*`). The text
+//! shows the first matching entity's state, or counts how many are in
+//! `count_state`:
+//!
+//! ```toml
+//! [[modules.right.right]]
+//! type = "homeassistant"
+//! url = "http://homeassistant.local:8123"
+//! token = "secret:keychain/sinew/homeassistant"
+//! entities = ["light.*"]
+//! format = "󰌵 {count}"
+//! click_service = "light.turn_off"
+//! click_data = { entity_id = "all" }
+//! ```
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+use serde_json::json;
+
+use super::template::{Template, Value};
+use super::{mark_dirty, sleep_unless_stopped, GpuiModule, MouseEvent, RuleColors};
+use crate::gpui_app::primitives::skeleton::shimmer_skeleton;
+use crate::gpui_app::theme::{LoadingState, Theme};
+use crate::system::websocket::WebSocket;
+
+/// How often the worker checks for clicks to send and for shutdown.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+const RECONNECT_MIN: Duration = Duration::from_secs(2);
+const RECONNECT_MAX: Duration = Duration::from_secs(60);
+
+/// Message id of the initial `get_states` request.
+const GET_STATES_ID: u64 = 1;
+
+/// An entity's state as Home Assistant reports it.
+#[derive(Debug, Clone, PartialEq)]
+struct Entity {
+    state: String,
+    attributes: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Entity {
+    fn from_json(state: &serde_json::Value) -> Option<(String, Self)> {
+        let id = state.get("entity_id")?.as_str()?.to_string();
+        let entity = Self {
+            state: state.get("state")?.as_str()?.to_string(),
+            attributes: state
+                .get("attributes")
+                .and_then(|a| a.as_object())
+                .cloned()
+                .unwrap_or_default(),
+        };
+        Some((id, entity))
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name)?.as_str()
+    }
+}
+
+/// Matching entities by id.
+type Entities = BTreeMap<String, Entity>;
+
+/// Matches an entity id against a pattern where `*` stands for any text.
+fn matches(pattern: &str, id: &str) -> bool {
+    let Some((prefix, rest)) = pattern.split_once('*') else {
+        return pattern == id;
+    };
+    let Some(mut remaining) = id.strip_prefix(prefix) else {
+        return false;
+    };
+    let mut parts = rest.split('*').peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            return remaining.ends_with(part);
+        }
+        match remaining.find(part) {
+            Some(index) => remaining = &remaining[index + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Parses `http://host:port`, `ws://host:port` or `host:port` (with an
+/// optional path prefix) into the host, port and WebSocket API path.
+fn parse_url(url: &str) -> Result<(String, u16, String), String> {
+    if url.starts_with("https://") || url.starts_with("wss://") {
+        return Err("TLS isn't supported, use the local http:// address".to_string());
+    }
+    let address = url
+        .strip_prefix("http://")
+        .or_else(|| url.strip_prefix("ws://"))
+        .unwrap_or(url);
+    let (authority, path) = match address.find('/') {
+        Some(index) => address.split_at(index),
+        None => (address, ""),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (
+            host,
+            port.parse()
+                .map_err(|_| format!("invalid port in '{}'", url))?,
+        ),
+        None => (authority, 8123),
+    };
+    if host.is_empty() {
+        return Err(format!("no host in '{}'", url));
+    }
+    let path = path
+        .trim_end_matches('/')
+        .trim_end_matches("/api/websocket");
+    Ok((host.to_string(), port, format!("{}/api/websocket", path)))
+}
+
+/// A text or number placeholder value from a state or attribute;
+/// `unavailable` and `unknown` have none.
+fn state_value(text: &str) -> Option<Value> {
+    match text {
+        "" | "unavailable" | "unknown" => None,
+        _ => Some(match text.parse::<f64>() {
+            Ok(number) if number.is_finite() => Value::from(number),
+            _ => Value::from(text),
+        }),
+    }
+}
+
+/// What the module shows.
+#[derive(Debug, Clone)]
+struct Display {
+    patterns: Vec<String>,
+    /// Attribute shown as `{value}` instead of the state
+    attribute: Option<String>,
+    /// State counted by `{count}`
+    count_state: String,
+}
+
+impl Display {
+    /// The first entity matching the first pattern that matches any.
+    fn first<'a>(&self, entities: &'a Entities) -> Option<&'a Entity> {
+        self.patterns.iter().find_map(|pattern| {
+            entities
+                .iter()
+                .find(|(id, _)| matches(pattern, id))
+                .map(|(_, entity)| entity)
+        })
+    }
+
+    fn value(&self, entities: &Entities) -> Option<Value> {
+        let entity = self.first(entities)?;
+        match &self.attribute {
+            None => state_value(&entity.state),
+            Some(attribute) => match entity.attributes.get(attribute)? {
+                serde_json::Value::Number(number) => number.as_f64().map(Value::from),
+                serde_json::Value::String(text) => state_value(text),
+                serde_json::Value::Null => None,
+                other => Some(Value::from(other.to_string())),
+            },
+        }
+    }
+
+    fn placeholders(&self, entities: &Entities) -> Vec<(&'static str, Option<Value>)> {
+        let first = self.first(entities);
+        let count = entities
+            .values()
+            .filter(|entity| entity.state == self.count_state)
+            .count();
+        vec![
+            ("value", self.value(entities)),
+            (
+                "state",
+                first.map(|entity| Value::from(entity.state.as_str())),
+            ),
+            (
+                "unit",
+                first
+                    .and_then(|entity| entity.attribute("unit_of_measurement"))
+                    .map(Value::from),
+            ),
+            (
+                "name",
+                first
+                    .and_then(|entity| entity.attribute("friendly_name"))
+                    .map(Value::from),
+            ),
+            ("count", Some(Value::from(count as f64))),
+            ("total", Some(Value::from(entities.len() as f64))),
+        ]
+    }
+}
+
+/// A service call, e.g. `light.toggle` with its data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceCall {
+    domain: String,
+    service: String,
+    data: serde_json::Value,
+}
+
+impl ServiceCall {
+    /// Parses `domain.service`.
+    pub fn parse(service: &str, data: serde_json::Value) -> Option<Self> {
+        let (domain, service) = service.split_once('.')?;
+        Some(Self {
+            domain: domain.to_string(),
+            service: service.to_string(),
+            data,
+        })
+    }
+
+    fn message(&self, id: u64) -> String {
+        json!({
+            "id": id,
+            "type": "call_service",
+            "domain": self.domain,
+            "service": self.service,
+            "service_data": self.data,
+        })
+        .to_string()
+    }
+}
+
+/// Where to connect and what to follow.
+pub struct Connection {
+    host: String,
+    port: u16,
+    path: String,
+    token: String,
+}
+
+impl Connection {
+    pub fn parse(url: &str, token: &str) -> Result<Self, String> {
+        let (host, port, path) = parse_url(url)?;
+        Ok(Self {
+            host,
+            port,
+            path,
+            token: token.to_string(),
+        })
+    }
+}
+
+/// Shows Home Assistant entity states.
+pub struct HomeAssistantModule {
+    id: String,
+    display: Display,
+    format: Template,
+    click: Option<ServiceCall>,
+    state: Arc<Mutex<LoadingState<Entities>>>,
+    rule_colors: RuleColors,
+    dirty: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+    calls: Sender<ServiceCall>,
+}
+
+impl HomeAssistantModule {
+    /// Creates a new Home Assistant module.
+    ///
+    /// @param id - Unique module identifier
+    /// @param connection - Instance address and access token
+    /// @param patterns - Entity ids or `*` patterns to follow
+    /// @param attribute - Attribute shown as `{value}` (None = the state)
+    /// @param count_state - State `{count}` counts
+    /// @param format - Text template (None = "{value}[ {unit}]")
+    /// @param click - Service to call on click
+    pub fn new(
+        id: &str,
+        connection: Connection,
+        patterns: Vec<String>,
+        attribute: Option<String>,
+        count_state: &str,
+        format: Option<&str>,
+        click: Option<ServiceCall>,
+    ) -> Self {
+        let state = Arc::new(Mutex::new(LoadingState::Loading));
+        let dirty = Arc::new(AtomicBool::new(true));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (calls, calls_rx) = mpsc::channel();
+
+        let display = Display {
+            patterns,
+            attribute,
+            count_state: count_state.to_string(),
+        };
+        let patterns = display.patterns.clone();
+        let state_handle = Arc::clone(&state);
+        let dirty_handle = Arc::clone(&dirty);
+        let stop_handle = Arc::clone(&stop);
+        std::thread::spawn(move || {
+            run(
+                &connection,
+                &patterns,
+                &state_handle,
+                &dirty_handle,
+                &stop_handle,
+                &calls_rx,
+            )
+        });
+
+        Self {
+            id: id.to_string(),
+            display,
+            format: Template::parse_or_literal(format.unwrap_or("{value}[ {unit}]")),
+            click,
+            state,
+            rule_colors: RuleColors::default(),
+            dirty,
+            stop,
+            calls,
+        }
+    }
+}
+
+/// Keeps a connection to Home Assistant until `stop` is set.
+fn run(
+    connection: &Connection,
+    patterns: &[String],
+    state: &Mutex<LoadingState<Entities>>,
+    dirty: &AtomicBool,
+    stop: &AtomicBool,
+    calls: &Receiver<ServiceCall>,
+) {
+    let mut backoff = RECONNECT_MIN;
+    let mut warned = false;
+    while !stop.load(Ordering::Relaxed) {
+        let error = match connect(connection) {
+            Ok(mut socket) => {
+                log::info!(
+                    "homeassistant: connected to {}:{}",
+                    connection.host,
+                    connection.port
+                );
+                backoff = RECONNECT_MIN;
+                warned = false;
+                match session(&mut socket, patterns, state, dirty, stop, calls) {
+                    Ok(()) => return,
+                    Err(e) => e,
+                }
+            }
+            Err(e) => e,
+        };
+
+        if !warned {
+            log::warn!(
+                "homeassistant: connection to {}:{} failed: {}",
+                connection.host,
+                connection.port,
+                error
+            );
+            warned = true;
+        }
+        if let Ok(mut guard) = state.lock() {
+            if !guard.is_loaded() {
+                *guard = LoadingState::Error(error.to_string());
+                mark_dirty(dirty);
+            }
+        }
+        // Clicks made while offline would fire long after the fact
+        calls.try_iter().for_each(drop);
+        sleep_unless_stopped(backoff, stop);
+        backoff = (backoff * 2).min(RECONNECT_MAX);
+    }
+}
+
+/// Reads the next JSON message, waiting as long as the server takes.
+fn next_message(socket: &mut WebSocket) -> io::Result<serde_json::Value> {
+    loop {
+        if let Some(text) = socket.read_text(POLL_INTERVAL)? {
+            return serde_json::from_str(&text)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+        }
+    }
+}
+
+/// Connects and authenticates.
+fn connect(connection: &Connection) -> io::Result<WebSocket> {
+    let mut socket = WebSocket::connect(&connection.host, connection.port, &connection.path)?;
+    // The server asks for auth first
+    next_message(&mut socket)?;
+    socket.send_text(&json!({ "type": "auth", "access_token": connection.token }).to_string())?;
+    let reply = next_message(&mut socket)?;
+    match reply["type"].as_str() {
+        Some("auth_ok") => Ok(socket),
+        Some("auth_invalid") => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "invalid access token",
+        )),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unexpected reply to auth",
+        )),
+    }
+}
+
+/// Loads the current states and follows changes until `stop` is set (Ok)
+/// or the connection fails (Err).
+fn session(
+    socket: &mut WebSocket,
+    patterns: &[String],
+    state: &Mutex<LoadingState<Entities>>,
+    dirty: &AtomicBool,
+    stop: &AtomicBool,
+    calls: &Receiver<ServiceCall>,
+) -> io::Result<()> {
+    let matching = |id: &str| patterns.iter().any(|pattern| matches(pattern, id));
+    let publish = |update: &dyn Fn(&mut Entities)| {
+        if let Ok(mut guard) = state.lock() {
+            let mut entities = guard.as_loaded().cloned().unwrap_or_default();
+            update(&mut entities);
+            if guard.as_loaded() != Some(&entities) {
+                *guard = LoadingState::Loaded(entities);
+                mark_dirty(dirty);
+            }
+        }
+    };
+
+    socket.send_text(&json!({ "id": GET_STATES_ID, "type": "get_states" }).to_string())?;
+    socket.send_text(
+        &json!({ "id": GET_STATES_ID + 1, "type": "subscribe_events", "event_type": "state_changed" })
+            .to_string(),
+    )?;
+    let mut next_id = GET_STATES_ID + 2;
+
+    while !stop.load(Ordering::Relaxed) {
+        for call in calls.try_iter() {
+            socket.send_text(&call.message(next_id))?;
+            next_id += 1;
+        }
+        let Some(text) = socket.read_text(POLL_INTERVAL)? else {
+            continue;
+        };
+        let Ok(message) = serde_json::from_str::<serde_json::Value>(&text) else {
+            continue;
+        };
+        match message["type"].as_str() {
+            Some("result") if message["id"] == GET_STATES_ID => {
+                let states = message["result"].as_array().cloned().unwrap_or_default();
+                publish(&|entities| {
+                    entities.clear();
+                    entities.extend(
+                        states
+                            .iter()
+                            .filter_map(Entity::from_json)
+                            .filter(|(id, _)| matching(id)),
+                    );
+                });
+            }
+            Some("result") if message["success"] == false => {
+                log::warn!(
+                    "homeassistant: request failed: {}",
+                    message["error"]["message"]
+                        .as_str()
+                        .unwrap_or("unknown error")
+                );
+            }
+            Some("event") => {
+                let data = &message["event"]["data"];
+                let Some(id) = data["entity_id"].as_str().filter(|&id| matching(id)) else {
+                    continue;
+                };
+                let new_state = Entity::from_json(&data["new_state"]);
+                publish(&|entities| match &new_state {
+                    Some((_, entity)) => {
+                        entities.insert(id.to_string(), entity.clone());
+                    }
+                    // Removed entity
+                    None => {
+                        entities.remove(id);
+                    }
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+impl GpuiModule for HomeAssistantModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let state = self
+            .state
+            .lock()
+            .map(|s| s.clone())
+            .unwrap_or(LoadingState::Loading);
+        let (text, color) = match &state {
+            LoadingState::Loading => {
+                return div()
+                    .flex()
+                    .items_center()
+                    .child(shimmer_skeleton(40.0, 14.0).rounded(4.0).render(theme))
+                    .into_any_element()
+            }
+            LoadingState::Error(_) => ("--".to_string(), theme.foreground_muted),
+            LoadingState::Loaded(entities) => (
+                self.format.render(&self.display.placeholders(entities)),
+                self.rule_colors.text.unwrap_or(theme.foreground),
+            ),
+        };
+
+        div()
+            .flex()
+            .items_center()
+            .text_color(color)
+            .text_size(px(theme.font_size))
+            .child(SharedString::from(text))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        self.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn is_loading(&self) -> bool {
+        self.state.lock().map(|s| s.is_loading()).unwrap_or(true)
+    }
+
+    fn display_text(&self) -> Option<String> {
+        match &*self.state.lock().ok()? {
+            LoadingState::Loading => None,
+            LoadingState::Error(_) => Some("--".to_string()),
+            LoadingState::Loaded(entities) => {
+                Some(self.format.render(&self.display.placeholders(entities)))
+            }
+        }
+    }
+
+    fn value(&self) -> Option<u8> {
+        let state = self.state.lock().ok()?;
+        match self.display.value(state.as_loaded()?)? {
+            Value::Number(number) => Some(number.round().clamp(0.0, 100.0) as u8),
+            _ => None,
+        }
+    }
+
+    fn set_rule_colors(&mut self, colors: RuleColors) {
+        self.rule_colors = colors;
+    }
+
+    fn on_mouse_event(&mut self, event: MouseEvent) -> bool {
+        if event == MouseEvent::Click {
+            if let Some(call) = &self.click {
+                let _ = self.calls.send(call.clone());
+            }
+        }
+        false
+    }
+}
+
+impl Drop for HomeAssistantModule {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(state: &str, attributes: serde_json::Value) -> Entity {
+        Entity {
+            state: state.to_string(),
+            attributes: attributes.as_object().cloned().unwrap_or_default(),
+        }
+    }
+
+    #[test]
+    fn matches_entity_patterns() {
+        assert!(matches("light.kitchen", "light.kitchen"));
+        assert!(!matches("light.kitchen", "light.kitchen_2"));
+        assert!(matches("light.*", "light.kitchen"));
+        assert!(matches("*.kitchen", "switch.kitchen"));
+        assert!(matches("sensor.*_temperature", "sensor.attic_temperature"));
+        assert!(!matches("sensor.*_temperature", "sensor.attic_humidity"));
+        assert!(matches("*", "anything"));
+        assert!(!matches("light.*", "switch.light"));
+    }
+
+    #[test]
+    fn parses_urls() {
+        assert_eq!(
+            parse_url("http://homeassistant.local:8123"),
+            Ok((
+                "homeassistant.local".to_string(),
+                8123,
+                "/api/websocket".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_url("ws://10.0.0.2/ha/api/websocket"),
+            Ok((
+                "10.0.0.2".to_string(),
+                8123,
+                "/ha/api/websocket".to_string()
+            ))
+        );
+        assert!(parse_url("https://example.ui.nabu.casa").is_err());
+        assert!(parse_url("http://:8123").is_err());
+    }
+
+    #[test]
+    fn reads_entities_from_state_objects() {
+        let (id, entity) = Entity::from_json(&json!({
+            "entity_id": "sensor.office",
+            "state": "21.5",
+            "attributes": { "unit_of_measurement": "°C", "friendly_name": "Office" },
+        }))
+        .unwrap();
+        assert_eq!(id, "sensor.office");
+        assert_eq!(entity.state, "21.5");
+        assert_eq!(entity.attribute("unit_of_measurement"), Some("°C"));
+        assert!(Entity::from_json(&serde_json::Value::Null).is_none());
+    }
+
+    #[test]
+    fn fills_placeholders() {
+        let entities = Entities::from([
+            (
+                "light.hall".to_string(),
+                entity("off", json!({ "friendly_name": "Hall" })),
+            ),
+            (
+                "light.kitchen".to_string(),
+                entity("on", json!({ "brightness": 180 })),
+            ),
+            ("light.porch".to_string(), entity("on", json!({}))),
+        ]);
+        let display = Display {
+            patterns: vec!["light.kitchen".to_string(), "light.*".to_string()],
+            attribute: None,
+            count_state: "on".to_string(),
+        };
+        let format = Template::parse_or_literal("{value} {count}/{total}[ {name}]");
+        assert_eq!(format.render(&display.placeholders(&entities)), "on 2/3");
+
+        let brightness = Display {
+            attribute: Some("brightness".to_string()),
+            ..display.clone()
+        };
+        assert_eq!(brightness.value(&entities), Some(Value::Number(180.0)));
+
+        let unavailable = Entities::from([(
+            "sensor.t".to_string(),
+            entity("unavailable", json!({ "unit_of_measurement": "°C" })),
+        )]);
+        let sensor = Display {
+            patterns: vec!["sensor.t".to_string()],
+            ..display
+        };
+        assert_eq!(sensor.value(&unavailable), None);
+        let format = Template::parse_or_literal("[{value}{unit}]");
+        assert_eq!(format.render(&sensor.placeholders(&unavailable)), "");
+    }
+
+    #[test]
+    fn builds_service_calls() {
+        let call =
+            ServiceCall::parse("light.toggle", json!({ "entity_id": "light.hall" })).unwrap();
+        let message: serde_json::Value = serde_json::from_str(&call.message(7)).unwrap();
+        assert_eq!(message["id"], 7);
+        assert_eq!(message["type"], "call_service");
+        assert_eq!(message["domain"], "light");
+        assert_eq!(message["service"], "toggle");
+        assert_eq!(message["service_data"]["entity_id"], "light.hall");
+        assert!(ServiceCall::parse("toggle", json!({})).is_none());
+    }
+}
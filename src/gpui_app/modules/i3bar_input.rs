@@ -0,0 +1,485 @@
+//! i3bar input module: shows the blocks of an i3status or i3blocks command.
+//!
+//! The command speaks the i3bar protocol: a JSON header line
+//! (`{"version": 1, "click_events": true}`), then an endless JSON array
+//! with one array of blocks per update. Each block is drawn as its own item
+//! with its `color`, `background`, `border`, `urgent` state, `min_width`
+//! (in pixels) and separator. Commands that print no header are shown one
+//! plain text line at a time.
+//!
+//! When the header asks for click events, clicks on a block are written
+//! back to the command's stdin as JSON events with the block's `name` and
+//! `instance`, the button and the pointer position.
+//!
+//! ```toml
+//! [[modules.right.right]]
+//! type = "i3bar_input"
+//! command = "i3blocks -c ~/.config/i3blocks/config"
+//! ```
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gpui::{div, prelude::*, px, AnyElement, MouseButton, SharedString, Styled};
+use serde::Deserialize;
+
+use super::{mark_dirty, GpuiModule};
+use crate::config::parse_hex_color;
+use crate::gpui_app::theme::Theme;
+
+/// Gap after a block when it doesn't set `separator_block_width`.
+const DEFAULT_SEPARATOR_WIDTH: f32 = 9.0;
+
+/// The protocol header, the command's first line.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Header {
+    pub version: u32,
+    #[serde(default)]
+    pub click_events: bool,
+}
+
+impl Header {
+    /// Parses a header line, None when the line isn't one.
+    pub fn parse(line: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(line.trim())
+            .ok()
+            .filter(|header| header.version >= 1)
+    }
+}
+
+/// One block of a status line.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Block {
+    pub full_text: String,
+    pub color: Option<String>,
+    pub background: Option<String>,
+    pub border: Option<String>,
+    /// Pixels, or a string as wide as the block should be
+    pub min_width: Option<serde_json::Value>,
+    pub name: Option<String>,
+    pub instance: Option<String>,
+    pub urgent: bool,
+    pub separator: Option<bool>,
+    pub separator_block_width: Option<f32>,
+    pub markup: Option<String>,
+}
+
+impl Block {
+    /// A block showing a line of plain text.
+    fn plain(text: &str) -> Self {
+        Self {
+            full_text: text.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// The text to draw, without Pango tags.
+    pub fn text(&self) -> String {
+        if self.markup.as_deref() == Some("pango") {
+            strip_pango(&self.full_text)
+        } else {
+            self.full_text.clone()
+        }
+    }
+}
+
+/// Removes Pango markup tags and unescapes the entities it needs.
+fn strip_pango(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => plain.push(c),
+            _ => {}
+        }
+    }
+    plain
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Parses one line of the endless array: `[`, or an array of blocks that
+/// may start with the separating comma (or the opening bracket).
+pub fn parse_status_line(line: &str) -> Option<Vec<Block>> {
+    let line = line.trim().trim_start_matches(',').trim_start();
+    let line = match line.strip_prefix('[') {
+        Some(rest) if rest.trim_start().starts_with('[') => rest,
+        _ => line,
+    };
+    if line.is_empty() || line == "[" {
+        return None;
+    }
+    serde_json::from_str(line).ok()
+}
+
+/// A click on a block, as sent to the command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Click {
+    pub name: Option<String>,
+    pub instance: Option<String>,
+    /// 1 left, 2 middle, 3 right
+    pub button: u8,
+    pub x: f32,
+    pub y: f32,
+    /// X11 modifier names: Shift, Control, Mod1 (Option), Mod4 (Command)
+    pub modifiers: Vec<&'static str>,
+}
+
+impl Click {
+    /// The event as a line for the click stream, with the separating comma
+    /// unless it's the first.
+    pub fn encode(&self, first: bool) -> String {
+        let event = serde_json::json!({
+            "name": self.name,
+            "instance": self.instance,
+            "button": self.button,
+            "x": self.x.round() as i64,
+            "y": self.y.round() as i64,
+            "modifiers": self.modifiers,
+        });
+        if first {
+            format!("{}\n", event)
+        } else {
+            format!(",{}\n", event)
+        }
+    }
+}
+
+fn parse_color(hex: Option<&str>) -> Option<gpui::Rgba> {
+    let (r, g, b, a) = parse_hex_color(hex?)?;
+    Some(gpui::Rgba {
+        r: r as f32,
+        g: g as f32,
+        b: b as f32,
+        a: a as f32,
+    })
+}
+
+/// Module that shows the blocks of an i3bar protocol command.
+pub struct I3barInputModule {
+    id: String,
+    blocks: Arc<Mutex<Vec<Block>>>,
+    /// Clicks to write to the command; dropped when it doesn't want them
+    clicks: Sender<Click>,
+    dirty: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+    child: Arc<Mutex<Option<Child>>>,
+}
+
+impl I3barInputModule {
+    /// Creates a new i3bar input module running `command`.
+    pub fn new(id: &str, command: &str) -> Self {
+        let blocks = Arc::new(Mutex::new(Vec::new()));
+        let dirty = Arc::new(AtomicBool::new(true));
+        let stop = Arc::new(AtomicBool::new(false));
+        let child = Arc::new(Mutex::new(None));
+        let (clicks, clicks_rx) = mpsc::channel();
+
+        let command = command.to_string();
+        let blocks_handle = Arc::clone(&blocks);
+        let dirty_handle = Arc::clone(&dirty);
+        let stop_handle = Arc::clone(&stop);
+        let child_handle = Arc::clone(&child);
+        std::thread::spawn(move || {
+            let publish = |next: Vec<Block>| {
+                if let Ok(mut guard) = blocks_handle.lock() {
+                    if *guard != next {
+                        *guard = next;
+                        mark_dirty(&dirty_handle);
+                    }
+                }
+            };
+            run(&command, &child_handle, clicks_rx, &publish);
+            if !stop_handle.load(Ordering::Relaxed) {
+                log::warn!("i3bar_input: '{}' exited", command);
+            }
+        });
+
+        Self {
+            id: id.to_string(),
+            blocks,
+            clicks,
+            dirty,
+            stop,
+            child,
+        }
+    }
+}
+
+/// Runs the command and publishes its status lines until it exits.
+fn run(
+    command: &str,
+    child_slot: &Mutex<Option<Child>>,
+    clicks: Receiver<Click>,
+    publish: &dyn Fn(Vec<Block>),
+) {
+    let mut child = match Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            log::warn!("i3bar_input: failed to run '{}': {}", command, e);
+            return;
+        }
+    };
+    let (Some(stdout), Some(stdin)) = (child.stdout.take(), child.stdin.take()) else {
+        return;
+    };
+    if let Ok(mut slot) = child_slot.lock() {
+        *slot = Some(child);
+    }
+
+    let mut lines = BufReader::new(stdout).lines().map_while(Result::ok);
+    let Some(first) = lines.next() else {
+        return;
+    };
+    match Header::parse(&first) {
+        Some(header) => {
+            if header.click_events {
+                std::thread::spawn(move || write_clicks(stdin, clicks));
+            }
+            for line in lines {
+                if let Some(blocks) = parse_status_line(&line) {
+                    publish(blocks);
+                }
+            }
+        }
+        // No header: plain text, one status per line
+        None => {
+            for line in std::iter::once(first).chain(lines) {
+                publish(vec![Block::plain(&line)]);
+            }
+        }
+    }
+
+    if let Some(mut child) = child_slot.lock().ok().and_then(|mut slot| slot.take()) {
+        let _ = child.wait();
+    }
+}
+
+/// Writes clicks to the command as the endless array of click events.
+fn write_clicks(mut stdin: ChildStdin, clicks: Receiver<Click>) {
+    if stdin.write_all(b"[\n").is_err() {
+        return;
+    }
+    let mut first = true;
+    for click in clicks {
+        let line = click.encode(first);
+        if stdin
+            .write_all(line.as_bytes())
+            .and_then(|_| stdin.flush())
+            .is_err()
+        {
+            break;
+        }
+        first = false;
+    }
+}
+
+impl GpuiModule for I3barInputModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let blocks = self
+            .blocks
+            .lock()
+            .map(|blocks| blocks.clone())
+            .unwrap_or_default();
+        let count = blocks.len();
+
+        let mut row = div().flex().items_center().text_size(px(theme.font_size));
+        for (index, block) in blocks.into_iter().enumerate() {
+            let background = if block.urgent {
+                Some(theme.destructive)
+            } else {
+                parse_color(block.background.as_deref())
+            };
+            let color = if block.urgent {
+                theme.on_destructive
+            } else {
+                parse_color(block.color.as_deref()).unwrap_or(theme.foreground)
+            };
+
+            let mut item = div()
+                .flex()
+                .items_center()
+                .justify_center()
+                .text_color(color)
+                .child(SharedString::from(block.text()));
+            if let Some(background) = background {
+                item = item.bg(background).px(px(4.0)).rounded(px(3.0));
+            }
+            if let Some(border) = parse_color(block.border.as_deref()) {
+                item = item.border_1().border_color(border);
+            }
+            if let Some(width) = block.min_width.as_ref().and_then(|w| w.as_f64()) {
+                item = item.min_w(px(width as f32));
+            }
+            for (button, number) in [
+                (MouseButton::Left, 1),
+                (MouseButton::Middle, 2),
+                (MouseButton::Right, 3),
+            ] {
+                let clicks = self.clicks.clone();
+                let (name, instance) = (block.name.clone(), block.instance.clone());
+                item = item.on_mouse_down(button, move |event, _window, _cx| {
+                    let modifiers = [
+                        (event.modifiers.shift, "Shift"),
+                        (event.modifiers.control, "Control"),
+                        (event.modifiers.alt, "Mod1"),
+                        (event.modifiers.platform, "Mod4"),
+                    ];
+                    let _ = clicks.send(Click {
+                        name: name.clone(),
+                        instance: instance.clone(),
+                        button: number,
+                        x: f32::from(event.position.x),
+                        y: f32::from(event.position.y),
+                        modifiers: modifiers
+                            .into_iter()
+                            .filter(|(held, _)| *held)
+                            .map(|(_, name)| name)
+                            .collect(),
+                    });
+                });
+            }
+            row = row.child(item);
+
+            if index + 1 < count {
+                let gap = block
+                    .separator_block_width
+                    .unwrap_or(DEFAULT_SEPARATOR_WIDTH);
+                let mut spacer = div().w(px(gap)).flex().justify_center();
+                if block.separator != Some(false) {
+                    spacer = spacer.child(div().w(px(1.0)).h(px(theme.font_size)).bg(theme.border));
+                }
+                row = row.child(spacer);
+            }
+        }
+        row.into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        self.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn display_text(&self) -> Option<String> {
+        let blocks = self.blocks.lock().ok()?;
+        let texts: Vec<String> = blocks
+            .iter()
+            .map(Block::text)
+            .filter(|text| !text.is_empty())
+            .collect();
+        (!texts.is_empty()).then(|| texts.join(" "))
+    }
+}
+
+impl Drop for I3barInputModule {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(mut child) = self.child.lock().ok().and_then(|mut slot| slot.take()) {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_header() {
+        assert_eq!(
+            Header::parse(r#"{"version": 1, "click_events": true}"#),
+            Some(Header {
+                version: 1,
+                click_events: true,
+            })
+        );
+        assert_eq!(
+            Header::parse(r#"{ "version": 1 }"#).map(|h| h.click_events),
+            Some(false)
+        );
+        assert_eq!(Header::parse("load 0.42"), None);
+        assert_eq!(Header::parse(r#"{"version": 0}"#), None);
+    }
+
+    #[test]
+    fn parses_status_lines() {
+        assert_eq!(parse_status_line("["), None);
+        let blocks = parse_status_line(
+            r##"[{"name":"disk","instance":"/","full_text":"12G","color":"#00ff00","urgent":true,"separator":false}]"##,
+        )
+        .unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].name.as_deref(), Some("disk"));
+        assert_eq!(blocks[0].instance.as_deref(), Some("/"));
+        assert_eq!(blocks[0].color.as_deref(), Some("#00ff00"));
+        assert!(blocks[0].urgent);
+        assert_eq!(blocks[0].separator, Some(false));
+
+        let next =
+            parse_status_line(r#",[{"full_text":"a"},{"full_text":"b","min_width":80}]"#).unwrap();
+        assert_eq!(next.len(), 2);
+        assert_eq!(next[1].min_width, Some(serde_json::json!(80)));
+        // The opening bracket on the same line as the first update
+        let first = parse_status_line(r#"[[{"full_text":"a"}]"#).unwrap();
+        assert_eq!(first[0].full_text, "a");
+        assert_eq!(parse_status_line("garbage"), None);
+    }
+
+    #[test]
+    fn strips_pango_markup() {
+        let block = Block {
+            full_text: "<span color='red'>CPU</span> &lt;5%&gt; &amp; ok".to_string(),
+            markup: Some("pango".to_string()),
+            ..Block::default()
+        };
+        assert_eq!(block.text(), "CPU <5%> & ok");
+        let plain = Block::plain("<b>raw</b>");
+        assert_eq!(plain.text(), "<b>raw</b>");
+    }
+
+    #[test]
+    fn encodes_click_events() {
+        let click = Click {
+            name: Some("volume".to_string()),
+            instance: None,
+            button: 3,
+            x: 120.4,
+            y: 11.6,
+            modifiers: vec!["Shift"],
+        };
+        let first = click.encode(true);
+        assert!(first.ends_with('\n'));
+        let event: serde_json::Value = serde_json::from_str(&first).unwrap();
+        assert_eq!(event["name"], "volume");
+        assert_eq!(event["instance"], serde_json::Value::Null);
+        assert_eq!(event["button"], 3);
+        assert_eq!(event["x"], 120);
+        assert_eq!(event["y"], 12);
+        assert_eq!(event["modifiers"], serde_json::json!(["Shift"]));
+        assert!(click.encode(false).starts_with(",{"));
+    }
+}
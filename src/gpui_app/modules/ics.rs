@@ -0,0 +1,487 @@
+//! ICS calendar feed module.
+//!
+//! Fetches one or more iCalendar feeds (`https://`, `webcal://` or a local
+//! file) every `interval` seconds and shows the next event in the bar, e.g.
+//! "Standup in 12m". Repeating events are expanded, including exceptions and
+//! moved instances. The popup lists today's agenda, and days with events get
+//! a dot in the calendar popup.
+//!
+//! `format` placeholders: `{icon}`, `{title}`, `{relative}` ("in 12m",
+//! "now") and `{start}` (time, `{start:%H:%M}`). They're empty when nothing
+//! starts within the next day.
+//!
+//! ```toml
+//! { type = "ics", urls = ["webcal://example.com/work.ics", "~/Calendars/home.ics"], interval = 900 }
+//! ```
+
+use std::collections::HashSet;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+
+use super::calendar;
+use super::template::{Template, Value};
+use super::{mark_dirty, until_next_second, GpuiModule, PopupEvent, PopupSpec};
+use crate::gpui_app::popup_manager::notify_popup_needs_render;
+use crate::gpui_app::primitives::{expand_tilde, icons};
+use crate::gpui_app::theme::Theme;
+use crate::system::ics::{expand, parse_events, Event, Occurrence};
+
+const DEFAULT_FORMAT: &str = "{icon}[ {title} {relative}]";
+const FETCH_TIMEOUT_SECS: &str = "15";
+/// Days before and after today marked in the calendar popup.
+const DOT_DAYS_BEFORE: i64 = 60;
+const DOT_DAYS_AFTER: i64 = 120;
+/// How far ahead the bar looks for the next event.
+const LOOKAHEAD_HOURS: i64 = 24;
+const POPUP_WIDTH: f64 = 320.0;
+const POPUP_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 24.0;
+const ROW_HEIGHT: f32 = 28.0;
+const MAX_ROWS: usize = 12;
+
+/// Turns `webcal://` into `https://`; other sources are left alone.
+pub fn normalize_source(source: &str) -> String {
+    match source.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => source.to_string(),
+    }
+}
+
+/// Time until `occurrence` starts at `now`: "now" while it runs, otherwise
+/// "in 12m", "in 2h" or "in 1h 5m".
+pub fn relative_label(occurrence: &Occurrence, now: NaiveDateTime) -> String {
+    let minutes = ((occurrence.start - now).num_seconds().max(0) + 59) / 60;
+    if minutes == 0 {
+        return "now".to_string();
+    }
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("in {}m", m),
+        (h, 0) => format!("in {}h", h),
+        (h, m) => format!("in {}h {}m", h, m),
+    }
+}
+
+/// The timed event running at `now` or starting soonest within the
+/// lookahead. All-day events are skipped.
+pub fn next_event(occurrences: &[Occurrence], now: NaiveDateTime) -> Option<&Occurrence> {
+    let horizon = now + chrono::Duration::hours(LOOKAHEAD_HOURS);
+    occurrences
+        .iter()
+        .filter(|o| !o.all_day && o.start < horizon)
+        .find(|o| o.end > now || o.start >= now)
+}
+
+/// Time column for the agenda: "09:30–10:00", or "All day".
+fn time_range(occurrence: &Occurrence) -> String {
+    if occurrence.all_day {
+        return "All day".to_string();
+    }
+    if occurrence.end <= occurrence.start {
+        return occurrence.start.format("%H:%M").to_string();
+    }
+    format!(
+        "{}–{}",
+        occurrence.start.format("%H:%M"),
+        occurrence.end.format("%H:%M")
+    )
+}
+
+/// Reads one feed: fetched with curl for URLs, read from disk otherwise.
+fn fetch(source: &str) -> Result<String, String> {
+    if !source.contains("://") {
+        return std::fs::read_to_string(expand_tilde(source)).map_err(|e| e.to_string());
+    }
+    let output = Command::new("curl")
+        .args(["-sfL", "-m", FETCH_TIMEOUT_SECS, source])
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!("curl exited with {}", output.status));
+    }
+    String::from_utf8(output.stdout).map_err(|e| e.to_string())
+}
+
+/// Latest fetch result.
+#[derive(Debug, Clone, Default)]
+struct FeedState {
+    events: Vec<Event>,
+    /// Occurrences from the start of `day` through the lookahead
+    upcoming: Vec<Occurrence>,
+    day: Option<NaiveDate>,
+    /// Set when no feed could be read
+    error: Option<String>,
+}
+
+impl FeedState {
+    /// Re-expands the cached occurrences when the day has changed.
+    fn expand_for(&mut self, today: NaiveDate) {
+        if self.day == Some(today) {
+            return;
+        }
+        let from = today.and_hms_opt(0, 0, 0).unwrap_or_default();
+        let to = from + chrono::Duration::days(1) + chrono::Duration::hours(LOOKAHEAD_HOURS);
+        self.upcoming = expand(&self.events, from, to);
+        self.day = Some(today);
+    }
+}
+
+/// State shared between the bar and popup copies of the module.
+struct Shared {
+    sources: Vec<String>,
+    state: Mutex<FeedState>,
+    dirty: AtomicBool,
+    stop: AtomicBool,
+}
+
+impl Shared {
+    /// Fetches every feed and marks event days in the calendar popup.
+    fn refresh(&self, id: &str) {
+        let mut events = Vec::new();
+        let mut errors = Vec::new();
+        for source in &self.sources {
+            match fetch(source) {
+                Ok(text) => events.extend(parse_events(&text)),
+                Err(err) => {
+                    log::warn!("ics: can't fetch '{}': {}", source, err);
+                    errors.push(err);
+                }
+            }
+        }
+
+        let today = Local::now().date_naive();
+        let from = today.and_hms_opt(0, 0, 0).unwrap_or_default();
+        let mut days = HashSet::new();
+        for occurrence in expand(
+            &events,
+            from - chrono::Duration::days(DOT_DAYS_BEFORE),
+            from + chrono::Duration::days(DOT_DAYS_AFTER),
+        ) {
+            // Exclusive end, so an event ending at midnight stays on its day
+            let last = (occurrence.end - chrono::Duration::seconds(1)).max(occurrence.start);
+            days.extend(
+                occurrence
+                    .start
+                    .date()
+                    .iter_days()
+                    .take_while(|day| *day <= last.date()),
+            );
+        }
+        calendar::set_event_days(id, days);
+
+        if let Ok(mut guard) = self.state.lock() {
+            let error = (errors.len() == self.sources.len()).then(|| {
+                errors
+                    .pop()
+                    .unwrap_or_else(|| "No calendar feeds configured".to_string())
+            });
+            *guard = FeedState {
+                events,
+                error,
+                ..Default::default()
+            };
+            guard.expand_for(today);
+        }
+        mark_dirty(&self.dirty);
+        notify_popup_needs_render(id);
+    }
+}
+
+/// ICS calendar feed module showing the next event.
+pub struct IcsModule {
+    id: String,
+    format: Template,
+    shared: Arc<Shared>,
+    text: String,
+}
+
+impl IcsModule {
+    /// Creates a new ICS module and starts fetching its feeds.
+    ///
+    /// @param id - Unique module identifier
+    /// @param sources - Feed URLs (`https://`, `webcal://`) or file paths
+    /// @param interval - Seconds between fetches
+    /// @param format - Bar text template (None = icon, title and time until start)
+    pub fn new(id: &str, sources: Vec<String>, interval: u64, format: Option<&str>) -> Self {
+        let shared = Arc::new(Shared {
+            sources: sources.iter().map(|s| normalize_source(s)).collect(),
+            state: Mutex::new(FeedState::default()),
+            dirty: AtomicBool::new(true),
+            stop: AtomicBool::new(false),
+        });
+
+        let handle = Arc::clone(&shared);
+        let worker_id = id.to_string();
+        std::thread::spawn(move || {
+            while !handle.stop.load(Ordering::Relaxed) {
+                handle.refresh(&worker_id);
+                std::thread::sleep(Duration::from_secs(interval.max(60)));
+            }
+        });
+
+        Self {
+            id: id.to_string(),
+            format: Template::parse_or_literal(format.unwrap_or(DEFAULT_FORMAT)),
+            shared,
+            text: String::new(),
+        }
+    }
+
+    /// Returns a second handle on the same state for the popup registry.
+    pub fn popup_handle(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            format: self.format.clone(),
+            shared: Arc::clone(&self.shared),
+            text: String::new(),
+        }
+    }
+
+    fn state(&self, now: NaiveDateTime) -> FeedState {
+        self.shared
+            .state
+            .lock()
+            .map(|mut guard| {
+                guard.expand_for(now.date());
+                guard.clone()
+            })
+            .unwrap_or_default()
+    }
+
+    fn render_text(&self, now: NaiveDateTime) -> String {
+        let state = self.state(now);
+        let next = next_event(&state.upcoming, now);
+        let start = next.and_then(|o| Local.from_local_datetime(&o.start).earliest());
+        self.format.render(&[
+            ("icon", Some(Value::from(icons::system::CALENDAR))),
+            ("title", next.map(|o| Value::from(o.summary.as_str()))),
+            (
+                "relative",
+                next.map(|o| Value::from(relative_label(o, now))),
+            ),
+            ("start", start.map(|t| Value::Time(t.fixed_offset()))),
+        ])
+    }
+}
+
+impl GpuiModule for IcsModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let color = if self.shared.state.lock().is_ok_and(|s| s.error.is_some()) {
+            theme.foreground_muted
+        } else {
+            theme.foreground
+        };
+        div()
+            .flex()
+            .items_center()
+            .text_color(color)
+            .text_size(px(theme.font_size))
+            .child(SharedString::from(self.text.clone()))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        let dirty = self.shared.dirty.swap(false, Ordering::Relaxed);
+        let text = self.render_text(Local::now().naive_local());
+        if text == self.text && !dirty {
+            return false;
+        }
+        self.text = text;
+        true
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        Some(until_next_second())
+    }
+
+    fn display_text(&self) -> Option<String> {
+        (!self.text.is_empty()).then(|| self.text.clone())
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let now = Local::now().naive_local();
+        let rows = self
+            .state(now)
+            .upcoming
+            .iter()
+            .filter(|o| o.start.date() <= now.date())
+            .count()
+            .clamp(1, MAX_ROWS);
+        let height = POPUP_PADDING * 2.0 + HEADER_HEIGHT + rows as f32 * ROW_HEIGHT;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let now = Local::now().naive_local();
+        let state = self.state(now);
+        let agenda: Vec<&Occurrence> = state
+            .upcoming
+            .iter()
+            .filter(|o| o.start.date() <= now.date())
+            .collect();
+
+        let header = div()
+            .h(px(HEADER_HEIGHT))
+            .flex()
+            .items_center()
+            .justify_between()
+            .child(div().font_weight(gpui::FontWeight::SEMIBOLD).child("Today"))
+            .child(
+                div()
+                    .text_color(theme.foreground_muted)
+                    .child(SharedString::from(now.format("%A, %B %-d").to_string())),
+            );
+
+        let mut list = div().flex().flex_col();
+        let empty = state
+            .error
+            .clone()
+            .or_else(|| agenda.is_empty().then(|| "No events today".to_string()));
+        if let Some(message) = empty {
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .text_color(theme.foreground_muted)
+                    .child(SharedString::from(message)),
+            );
+        }
+
+        for occurrence in agenda.iter().take(MAX_ROWS) {
+            let past = !occurrence.all_day && occurrence.end.max(occurrence.start) < now;
+            let color = if past {
+                theme.foreground_subtle
+            } else {
+                theme.foreground
+            };
+            let mut title = div()
+                .flex_1()
+                .flex()
+                .gap(px(6.0))
+                .overflow_hidden()
+                .child(SharedString::from(occurrence.summary.clone()));
+            if let Some(location) = &occurrence.location {
+                title = title.child(
+                    div()
+                        .text_size(px(theme.font_size - 2.0))
+                        .text_color(theme.foreground_subtle)
+                        .child(SharedString::from(location.clone())),
+                );
+            }
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .text_color(color)
+                    .child(
+                        div()
+                            .w(px(84.0))
+                            .text_size(px(theme.font_size - 1.0))
+                            .text_color(theme.foreground_muted)
+                            .child(SharedString::from(time_range(occurrence))),
+                    )
+                    .child(title),
+            );
+        }
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(header)
+                .child(list)
+                .into_any_element(),
+        )
+    }
+
+    fn on_popup_event(&mut self, event: PopupEvent) {
+        if matches!(event, PopupEvent::Opened) {
+            let shared = Arc::clone(&self.shared);
+            let id = self.id.clone();
+            std::thread::spawn(move || shared.refresh(&id));
+        }
+    }
+}
+
+impl Drop for IcsModule {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, 10)
+            .unwrap()
+            .and_hms_opt(h, m, 0)
+            .unwrap()
+    }
+
+    fn occurrence(summary: &str, start: NaiveDateTime, minutes: i64, all_day: bool) -> Occurrence {
+        Occurrence {
+            summary: summary.to_string(),
+            location: None,
+            start,
+            end: start + chrono::Duration::minutes(minutes),
+            all_day,
+        }
+    }
+
+    #[test]
+    fn normalizes_webcal_urls() {
+        assert_eq!(
+            normalize_source("webcal://example.com/a.ics"),
+            "https://example.com/a.ics"
+        );
+        assert_eq!(normalize_source("~/cal.ics"), "~/cal.ics");
+    }
+
+    #[test]
+    fn labels_time_until_start() {
+        let standup = occurrence("Standup", at(9, 30), 15, false);
+        assert_eq!(relative_label(&standup, at(9, 18)), "in 12m");
+        assert_eq!(relative_label(&standup, at(7, 30)), "in 2h");
+        assert_eq!(relative_label(&standup, at(8, 25)), "in 1h 5m");
+        assert_eq!(relative_label(&standup, at(9, 35)), "now");
+    }
+
+    #[test]
+    fn next_event_skips_finished_and_all_day() {
+        let events = [
+            occurrence("Holiday", at(0, 0), 24 * 60, true),
+            occurrence("Standup", at(9, 30), 15, false),
+            occurrence("Review", at(14, 0), 60, false),
+        ];
+        assert_eq!(next_event(&events, at(9, 0)).unwrap().summary, "Standup");
+        assert_eq!(next_event(&events, at(9, 40)).unwrap().summary, "Standup");
+        assert_eq!(next_event(&events, at(10, 0)).unwrap().summary, "Review");
+        assert!(next_event(&events, at(16, 0)).is_none());
+    }
+
+    #[test]
+    fn formats_agenda_times() {
+        assert_eq!(
+            time_range(&occurrence("a", at(9, 30), 30, false)),
+            "09:30–10:00"
+        );
+        assert_eq!(time_range(&occurrence("b", at(9, 30), 0, false)), "09:30");
+        assert_eq!(time_range(&occurrence("c", at(0, 0), 0, true)), "All day");
+    }
+}
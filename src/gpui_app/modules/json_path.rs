@@ -0,0 +1,111 @@
+//! JSONPath subset for picking a value out of a JSON payload.
+//!
+//! Supports the root `$`, child keys (`.name` or `['name']`) and array
+//! indexes (`[0]`, or `[-1]` from the end), e.g. `$.sensors[0].temperature`.
+//! The leading `$` is optional.
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Key(String),
+    Index(i64),
+}
+
+/// A parsed path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath(Vec<Step>);
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Result<Self, String> {
+        let trimmed = path.trim();
+        let mut rest = trimmed.strip_prefix('$').unwrap_or(trimmed);
+        let rooted = rest.len() < trimmed.len();
+        let mut steps = Vec::new();
+        let mut first = true;
+        while !rest.is_empty() {
+            if let Some(bracketed) = rest.strip_prefix('[') {
+                let end = bracketed
+                    .find(']')
+                    .ok_or_else(|| format!("unclosed '[' in '{}'", path))?;
+                let inner = bracketed[..end].trim();
+                let quoted = inner
+                    .strip_prefix('\'')
+                    .and_then(|s| s.strip_suffix('\''))
+                    .or_else(|| inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+                steps.push(match quoted {
+                    Some(key) => Step::Key(key.to_string()),
+                    None => Step::Index(
+                        inner
+                            .parse()
+                            .map_err(|_| format!("invalid index '{}' in '{}'", inner, path))?,
+                    ),
+                });
+                rest = &bracketed[end + 1..];
+            } else {
+                // A path without `$` may start with a bare key
+                let key_start = match rest.strip_prefix('.') {
+                    Some(after) => after,
+                    None if first && !rooted => rest,
+                    None => return Err(format!("expected '.' or '[' in '{}'", path)),
+                };
+                let end = key_start.find(['.', '[']).unwrap_or(key_start.len());
+                if end == 0 {
+                    return Err(format!("empty key in '{}'", path));
+                }
+                steps.push(Step::Key(key_start[..end].to_string()));
+                rest = &key_start[end..];
+            }
+            first = false;
+        }
+        Ok(Self(steps))
+    }
+
+    /// The value at the path, None if any step is missing.
+    pub fn select<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.0.iter().try_fold(value, |value, step| match step {
+            Step::Key(key) => value.get(key),
+            Step::Index(index) => {
+                let items = value.as_array()?;
+                let index = if *index < 0 {
+                    items.len().checked_sub(index.unsigned_abs() as usize)?
+                } else {
+                    *index as usize
+                };
+                items.get(index)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn selects_keys_and_indexes() {
+        let value = json!({
+            "sensors": [{ "temperature": 21.5 }, { "temperature": 19.0 }],
+            "state": { "power": "ON", "odd key": 1 },
+        });
+        let select = |path: &str| JsonPath::parse(path).unwrap().select(&value).cloned();
+
+        assert_eq!(select("$.sensors[0].temperature"), Some(json!(21.5)));
+        assert_eq!(select("$.sensors[-1].temperature"), Some(json!(19.0)));
+        assert_eq!(select("state.power"), Some(json!("ON")));
+        assert_eq!(select("$['state']['odd key']"), Some(json!(1)));
+        assert_eq!(select("$"), Some(value.clone()));
+        assert_eq!(select("$.sensors[5]"), None);
+        assert_eq!(select("$.sensors[-3]"), None);
+        assert_eq!(select("$.missing.key"), None);
+    }
+
+    #[test]
+    fn rejects_malformed_paths() {
+        assert!(JsonPath::parse("$.a[0").is_err());
+        assert!(JsonPath::parse("$.a[x]").is_err());
+        assert!(JsonPath::parse("$..a").is_err());
+        assert!(JsonPath::parse("$a").is_err());
+    }
+}
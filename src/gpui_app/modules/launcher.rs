@@ -0,0 +1,463 @@
+//! App launcher / command palette popup.
+//!
+//! The popup is a search field over installed applications and user-defined
+//! commands. It opens on click or via `sinew-msg trigger <id> popup`, takes
+//! keyboard focus, and launches the selected entry on Enter.
+//!
+//! ```toml
+//! { type = "launcher", id = "launcher", commands = [
+//!   { name = "Lock Screen", command = "pmset displaysleepnow" },
+//! ] }
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gpui::{div, prelude::*, px, AnyElement, MouseButton, SharedString, Styled};
+
+use super::{GpuiModule, PopupEvent, PopupSpec};
+use crate::gpui_app::popup_manager::{hide_popup, notify_popup_needs_render, request_hide_popup};
+use crate::gpui_app::primitives::icons;
+use crate::gpui_app::primitives::{InputResult, TextInput, TextInputState};
+use crate::gpui_app::theme::Theme;
+
+const POPUP_WIDTH: f64 = 420.0;
+const POPUP_PADDING: f32 = 12.0;
+const INPUT_HEIGHT: f32 = 32.0;
+const ROW_HEIGHT: f32 = 28.0;
+const MAX_RESULTS: usize = 8;
+
+/// Directories scanned for `.app` bundles (one level of subfolders).
+const APP_DIRS: &[&str] = &["/Applications", "/System/Applications"];
+
+/// What a launcher entry opens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LaunchTarget {
+    /// Application bundle, opened via NSWorkspace
+    App(PathBuf),
+    /// Shell command from config
+    Command(String),
+}
+
+/// A searchable launcher entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LauncherItem {
+    pub name: String,
+    pub target: LaunchTarget,
+}
+
+impl LauncherItem {
+    /// Opens the entry.
+    fn launch(&self) {
+        match &self.target {
+            LaunchTarget::App(path) => open_app(path),
+            LaunchTarget::Command(command) => {
+                let command = command.clone();
+                std::thread::spawn(move || {
+                    if let Err(err) = Command::new("sh").args(["-c", &command]).status() {
+                        log::warn!("Launcher command '{}' failed: {}", command, err);
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Opens an application bundle via NSWorkspace.
+fn open_app(path: &Path) {
+    use objc2_app_kit::NSWorkspace;
+    use objc2_foundation::{NSString, NSURL};
+
+    unsafe {
+        let path_str = NSString::from_str(&path.to_string_lossy());
+        let url = NSURL::fileURLWithPath(&path_str);
+        if !NSWorkspace::sharedWorkspace().openURL(&url) {
+            log::warn!("Launcher failed to open {}", path.display());
+        }
+    }
+}
+
+/// Parses `commands = [{ name, command }]` from module config extras.
+pub fn parse_commands(value: Option<&toml::Value>) -> Vec<LauncherItem> {
+    let Some(entries) = value.and_then(|v| v.as_array()) else {
+        return Vec::new();
+    };
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let name = entry.get("name")?.as_str()?;
+            let command = entry.get("command")?.as_str()?;
+            Some(LauncherItem {
+                name: name.to_string(),
+                target: LaunchTarget::Command(command.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Returns the display name of an `.app` bundle path.
+fn app_name(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    name.strip_suffix(".app").map(str::to_string)
+}
+
+/// Lists `.app` bundles in `dirs` and their immediate subfolders
+/// (e.g. /Applications/Utilities), sorted by name.
+fn scan_applications(dirs: &[PathBuf]) -> Vec<LauncherItem> {
+    let mut apps = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|e| e.path()) {
+            if let Some(name) = app_name(&path) {
+                apps.push((name, path));
+            } else if path.is_dir() {
+                let Ok(nested) = std::fs::read_dir(&path) else {
+                    continue;
+                };
+                for path in nested.flatten().map(|e| e.path()) {
+                    if let Some(name) = app_name(&path) {
+                        apps.push((name, path));
+                    }
+                }
+            }
+        }
+    }
+    apps.sort_by_key(|(name, _)| name.to_lowercase());
+    apps.dedup_by(|a, b| a.0 == b.0);
+    apps.into_iter()
+        .map(|(name, path)| LauncherItem {
+            name,
+            target: LaunchTarget::App(path),
+        })
+        .collect()
+}
+
+/// Scores `candidate` against `query` as a case-insensitive subsequence.
+///
+/// Returns `None` when the query doesn't match. Consecutive matches, word
+/// starts, and prefix matches score higher. An empty query matches everything.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let chars: Vec<char> = candidate.chars().collect();
+    let mut score = 0;
+    let mut qi = 0;
+    let mut last_match: Option<usize> = None;
+    for (i, c) in chars.iter().enumerate() {
+        if qi == query.len() {
+            break;
+        }
+        if !c.to_lowercase().eq(query[qi].to_lowercase()) {
+            continue;
+        }
+        score += 1;
+        if last_match == Some(i.wrapping_sub(1)) {
+            score += 5;
+        }
+        let word_start = i == 0
+            || !chars[i - 1].is_alphanumeric()
+            || (c.is_uppercase() && chars[i - 1].is_lowercase());
+        if word_start {
+            score += 8;
+        }
+        if let Some(last) = last_match {
+            score -= (i - last - 1).min(3) as i32;
+        }
+        last_match = Some(i);
+        qi += 1;
+    }
+    if qi < query.len() {
+        return None;
+    }
+    if last_match == Some(query.len() - 1) {
+        score += 10;
+    }
+    Some(score)
+}
+
+/// Returns the best matches for `query`, highest score first.
+pub fn rank(items: &[LauncherItem], query: &str, limit: usize) -> Vec<LauncherItem> {
+    let mut scored: Vec<(i32, &LauncherItem)> = items
+        .iter()
+        .filter_map(|item| fuzzy_score(query, &item.name).map(|s| (s, item)))
+        .collect();
+    // Stable sort keeps commands ahead of apps on ties
+    scored.sort_by(|a, b| b.0.cmp(&a.0));
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, item)| item.clone())
+        .collect()
+}
+
+/// Launcher popup module.
+pub struct LauncherModule {
+    id: String,
+    commands: Vec<LauncherItem>,
+    apps: Arc<Mutex<Vec<LauncherItem>>>,
+    scanning: Arc<AtomicBool>,
+    input: TextInputState,
+    selected: usize,
+}
+
+impl LauncherModule {
+    /// Creates a launcher with the given user-defined commands.
+    pub fn new(id: &str, commands: Vec<LauncherItem>) -> Self {
+        Self {
+            id: id.to_string(),
+            commands,
+            apps: Arc::new(Mutex::new(Vec::new())),
+            scanning: Arc::new(AtomicBool::new(false)),
+            input: TextInputState::new(),
+            selected: 0,
+        }
+    }
+
+    /// Rescans installed applications in the background.
+    fn rescan(&self) {
+        if self.scanning.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let id = self.id.clone();
+        let apps = Arc::clone(&self.apps);
+        let scanning = Arc::clone(&self.scanning);
+        std::thread::spawn(move || {
+            let mut dirs: Vec<PathBuf> = APP_DIRS.iter().map(PathBuf::from).collect();
+            if let Some(home) = dirs::home_dir() {
+                dirs.push(home.join("Applications"));
+            }
+            let found = scan_applications(&dirs);
+            if let Ok(mut guard) = apps.lock() {
+                *guard = found;
+            }
+            scanning.store(false, Ordering::SeqCst);
+            notify_popup_needs_render(&id);
+        });
+    }
+
+    /// Current matches for the search field.
+    fn results(&self) -> Vec<LauncherItem> {
+        let mut items = self.commands.clone();
+        if let Ok(apps) = self.apps.lock() {
+            items.extend(apps.iter().cloned());
+        }
+        rank(&items, self.input.text(), MAX_RESULTS)
+    }
+
+    fn render_row(&self, index: usize, item: LauncherItem, theme: &Theme) -> AnyElement {
+        let (glyph, kind) = match item.target {
+            LaunchTarget::App(_) => (icons::system::APPS, "Application"),
+            LaunchTarget::Command(_) => (icons::system::CONSOLE, "Command"),
+        };
+        let hover_bg = theme.surface_hover;
+        let mut row = div()
+            .id(SharedString::from(format!("launcher-row-{}", index)))
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap(px(8.0))
+            .h(px(ROW_HEIGHT))
+            .px(px(8.0))
+            .rounded(px(4.0))
+            .cursor_pointer()
+            .hover(move |s| s.bg(hover_bg))
+            .child(div().text_color(theme.foreground_muted).child(glyph))
+            .child(
+                div()
+                    .flex_1()
+                    .overflow_hidden()
+                    .whitespace_nowrap()
+                    .child(SharedString::from(item.name.clone())),
+            )
+            .child(
+                div()
+                    .text_size(px((theme.font_size - 2.0).max(9.0)))
+                    .text_color(theme.foreground_subtle)
+                    .child(kind),
+            );
+        if index == self.selected {
+            row = row.bg(theme.surface_active);
+        }
+        row.on_mouse_down(MouseButton::Left, move |_event, _window, _cx| {
+            item.launch();
+            hide_popup();
+        })
+        .into_any_element()
+    }
+}
+
+impl GpuiModule for LauncherModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, _theme: &Theme) -> AnyElement {
+        div().into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        false
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        // Fixed height: the window only resizes when the popup reopens
+        let rows = ROW_HEIGHT * MAX_RESULTS as f32;
+        let height = POPUP_PADDING * 2.0 + INPUT_HEIGHT + 8.0 + rows;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64).with_keyboard())
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let results = self.results();
+        let list = if results.is_empty() {
+            let message = if self.scanning.load(Ordering::SeqCst) {
+                "Loading applications…"
+            } else {
+                "No matches"
+            };
+            div()
+                .px(px(8.0))
+                .text_color(theme.foreground_muted)
+                .child(message)
+                .into_any_element()
+        } else {
+            div()
+                .flex()
+                .flex_col()
+                .children(
+                    results
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, item)| self.render_row(i, item, theme)),
+                )
+                .into_any_element()
+        };
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .gap(px(8.0))
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(
+                    TextInput::new(&self.input)
+                        .placeholder("Search apps and commands")
+                        .icon(icons::system::SEARCH)
+                        .render(theme)
+                        .h(px(INPUT_HEIGHT)),
+                )
+                .child(list)
+                .into_any_element(),
+        )
+    }
+
+    fn on_popup_event(&mut self, event: PopupEvent) {
+        match event {
+            PopupEvent::Opened => {
+                self.input.set_text("");
+                self.selected = 0;
+                self.rescan();
+            }
+            PopupEvent::Key(key) => {
+                let last = self.results().len().saturating_sub(1);
+                let down = key.key == "down" || (key.control && key.key == "n");
+                let up = key.key == "up" || (key.control && key.key == "p");
+                if down {
+                    self.selected = (self.selected + 1).min(last);
+                    return;
+                }
+                if up {
+                    self.selected = self.selected.saturating_sub(1);
+                    return;
+                }
+                match self.input.handle_key(&key) {
+                    InputResult::Edited => self.selected = 0,
+                    InputResult::Submitted => {
+                        if let Some(item) = self.results().get(self.selected) {
+                            item.launch();
+                            request_hide_popup();
+                        }
+                    }
+                    InputResult::Moved | InputResult::Ignored => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(name: &str) -> LauncherItem {
+        LauncherItem {
+            name: name.to_string(),
+            target: LaunchTarget::App(PathBuf::from(format!("/Applications/{}.app", name))),
+        }
+    }
+
+    #[test]
+    fn fuzzy_score_requires_subsequence() {
+        assert!(fuzzy_score("sfr", "Safari").is_some());
+        assert!(fuzzy_score("rfs", "Safari").is_none());
+        assert_eq!(fuzzy_score("", "Safari"), Some(0));
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_prefix_and_word_starts() {
+        let prefix = fuzzy_score("term", "Terminal").unwrap();
+        let inner = fuzzy_score("term", "Intermission").unwrap();
+        assert!(prefix > inner);
+
+        let initials = fuzzy_score("vsc", "Visual Studio Code").unwrap();
+        let scattered = fuzzy_score("vsc", "Vision Scanner").unwrap();
+        assert!(initials > scattered);
+    }
+
+    #[test]
+    fn rank_orders_by_score_and_limits() {
+        let items = vec![app("Messages"), app("Safari"), app("System Settings")];
+        let ranked = rank(&items, "s", 2);
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].name, "Safari");
+    }
+
+    #[test]
+    fn parse_commands_skips_incomplete_entries() {
+        let value: toml::Value = toml::from_str(
+            r#"commands = [
+                { name = "Lock", command = "pmset displaysleepnow" },
+                { name = "Missing command" },
+            ]"#,
+        )
+        .unwrap();
+        let commands = parse_commands(value.get("commands"));
+        assert_eq!(commands.len(), 1);
+        assert_eq!(
+            commands[0].target,
+            LaunchTarget::Command("pmset displaysleepnow".to_string())
+        );
+    }
+
+    #[test]
+    fn app_name_strips_bundle_suffix() {
+        assert_eq!(
+            app_name(Path::new("/Applications/Safari.app")),
+            Some("Safari".to_string())
+        );
+        assert_eq!(app_name(Path::new("/Applications/Utilities")), None);
+    }
+}
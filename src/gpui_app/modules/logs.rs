@@ -0,0 +1,304 @@
+//! Log viewer: recent warnings and errors in a popup.
+//!
+//! The bar shows how many warnings and errors sinew has logged, colored by
+//! the worst of them. The popup lists them newest first and can narrow the
+//! list to one source, e.g. a script or weather module (see
+//! [`crate::logging`] for how entries get a source).
+
+use gpui::{div, prelude::*, px, AnyElement, Rgba, SharedString, Styled};
+use tracing::Level;
+
+use super::{GpuiModule, PopupAction, PopupEvent, PopupSpec};
+use crate::gpui_app::popup_manager::notify_popup_needs_render;
+use crate::gpui_app::primitives::{icons, Button};
+use crate::gpui_app::theme::{ButtonVariant, Theme};
+use crate::logging::{self, LogEntry};
+
+const POPUP_WIDTH: f64 = 560.0;
+const POPUP_HEIGHT: f64 = 400.0;
+const POPUP_PADDING: f32 = 12.0;
+/// Rows rendered in the popup; the buffer can hold more
+const MAX_ROWS: usize = 200;
+
+/// Sources in the entries with their entry counts, sorted by name.
+fn sources(entries: &[LogEntry]) -> Vec<(String, usize)> {
+    let mut sources: Vec<(String, usize)> = Vec::new();
+    for entry in entries {
+        match sources.iter_mut().find(|(name, _)| *name == entry.source) {
+            Some((_, count)) => *count += 1,
+            None => sources.push((entry.source.clone(), 1)),
+        }
+    }
+    sources.sort();
+    sources
+}
+
+/// Entries from `source` (all when None), newest first.
+fn filtered<'a>(entries: &'a [LogEntry], source: Option<&str>) -> Vec<&'a LogEntry> {
+    entries
+        .iter()
+        .rev()
+        .filter(|entry| source.is_none_or(|source| entry.source == source))
+        .collect()
+}
+
+fn level_color(theme: &Theme, level: Level) -> Rgba {
+    if level == Level::ERROR {
+        theme.destructive
+    } else {
+        theme.warning
+    }
+}
+
+/// Recent warnings and errors, with a per-source filter.
+pub struct LogsModule {
+    id: String,
+    /// Source the popup is narrowed to
+    filter: Option<String>,
+    generation: u64,
+}
+
+impl LogsModule {
+    /// Creates a new log viewer module.
+    ///
+    /// @param id - Unique module identifier
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            filter: None,
+            generation: logging::generation(),
+        }
+    }
+
+    fn render_entry(entry: &LogEntry, theme: &Theme) -> AnyElement {
+        let small = px(theme.font_size - 2.0);
+        div()
+            .flex()
+            .flex_col()
+            .py(px(4.0))
+            .border_b_1()
+            .border_color(theme.border)
+            .child(
+                div()
+                    .flex()
+                    .gap(px(8.0))
+                    .text_size(small)
+                    .child(
+                        div()
+                            .text_color(theme.foreground_muted)
+                            .child(SharedString::from(
+                                entry.time.format("%H:%M:%S").to_string(),
+                            )),
+                    )
+                    .child(
+                        div()
+                            .font_weight(gpui::FontWeight::SEMIBOLD)
+                            .text_color(level_color(theme, entry.level))
+                            .child(SharedString::from(entry.level.to_string())),
+                    )
+                    .child(
+                        div()
+                            .text_color(theme.foreground_muted)
+                            .child(SharedString::from(entry.source.clone())),
+                    ),
+            )
+            .child(SharedString::from(entry.message.clone()))
+            .into_any_element()
+    }
+}
+
+/// Logs icon, followed by the number of entries when there are any.
+fn bar_text(count: usize) -> String {
+    if count == 0 {
+        icons::system::LOGS.to_string()
+    } else {
+        format!("{} {}", icons::system::LOGS, count)
+    }
+}
+
+impl GpuiModule for LogsModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let entries = logging::recent();
+        let color = if entries.iter().any(|e| e.level == Level::ERROR) {
+            theme.destructive
+        } else if entries.is_empty() {
+            theme.foreground_muted
+        } else {
+            theme.warning
+        };
+        let text = bar_text(entries.len());
+        div()
+            .flex()
+            .items_center()
+            .text_color(color)
+            .text_size(px(theme.font_size))
+            .child(SharedString::from(text))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        let generation = logging::generation();
+        if generation == self.generation {
+            return false;
+        }
+        self.generation = generation;
+        notify_popup_needs_render(&self.id);
+        true
+    }
+
+    fn display_text(&self) -> Option<String> {
+        Some(bar_text(logging::recent().len()))
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        Some(PopupSpec::new(POPUP_WIDTH, POPUP_HEIGHT))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let entries = logging::recent();
+        let sources = sources(&entries);
+        // A source that's gone (e.g. after Clear) shows everything
+        let filter = self
+            .filter
+            .as_deref()
+            .filter(|filter| sources.iter().any(|(name, _)| name == filter));
+        let shown = filtered(&entries, filter);
+
+        let header = div()
+            .flex()
+            .items_center()
+            .justify_between()
+            .child(
+                div()
+                    .font_weight(gpui::FontWeight::SEMIBOLD)
+                    .child("Warnings and errors"),
+            )
+            .child(
+                Button::new(SharedString::from(format!("{}-clear", self.id)))
+                    .label("Clear")
+                    .variant(ButtonVariant::Outline)
+                    .text_size(px(theme.font_size - 2.0))
+                    .disabled(entries.is_empty())
+                    .on_popup_action(self.id.clone(), PopupAction::Reset)
+                    .render(theme),
+            );
+
+        let chip = |key: &str, label: String, selected: bool| {
+            let variant = if selected {
+                ButtonVariant::Accent
+            } else {
+                ButtonVariant::Ghost
+            };
+            Button::new(SharedString::from(format!("{}-source-{}", self.id, key)))
+                .label(label)
+                .variant(variant)
+                .text_size(px(theme.font_size - 2.0))
+                .on_popup_action(
+                    self.id.clone(),
+                    PopupAction::Select {
+                        value: key.to_string(),
+                    },
+                )
+                .render(theme)
+        };
+        let mut chips = div().flex().flex_wrap().gap(px(4.0)).child(chip(
+            "",
+            format!("All {}", entries.len()),
+            filter.is_none(),
+        ));
+        for (name, count) in &sources {
+            chips = chips.child(chip(
+                name,
+                format!("{} {}", name, count),
+                filter == Some(name.as_str()),
+            ));
+        }
+
+        let mut list = div()
+            .id("logs-list")
+            .flex()
+            .flex_col()
+            .flex_1()
+            .overflow_y_scroll();
+        if shown.is_empty() {
+            list = list.child(
+                div()
+                    .text_color(theme.foreground_muted)
+                    .child("No warnings or errors"),
+            );
+        }
+        for entry in shown.into_iter().take(MAX_ROWS) {
+            list = list.child(Self::render_entry(entry, theme));
+        }
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .gap(px(8.0))
+                .size_full()
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(header)
+                .child(chips)
+                .child(list)
+                .into_any_element(),
+        )
+    }
+
+    fn on_popup_event(&mut self, event: PopupEvent) {
+        if let PopupEvent::Opened = event {
+            self.filter = None;
+        }
+    }
+
+    fn on_popup_action(&mut self, action: PopupAction) {
+        match action {
+            PopupAction::Select { value } => {
+                self.filter = (!value.is_empty()).then_some(value);
+            }
+            PopupAction::Reset => logging::clear(),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(source: &str, message: &str) -> LogEntry {
+        LogEntry {
+            time: chrono::Local::now(),
+            level: Level::WARN,
+            source: source.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn filters_by_source_newest_first() {
+        let entries = vec![
+            entry("weather", "first"),
+            entry("script", "second"),
+            entry("weather", "third"),
+        ];
+        assert_eq!(
+            sources(&entries),
+            [("script".to_string(), 1), ("weather".to_string(), 2)]
+        );
+        let messages = |source| -> Vec<&str> {
+            filtered(&entries, source)
+                .iter()
+                .map(|e| e.message.as_str())
+                .collect()
+        };
+        assert_eq!(messages(Some("weather")), ["third", "first"]);
+        assert_eq!(messages(None), ["third", "second", "first"]);
+    }
+}
@@ -1,14 +1,27 @@
-//! Memory module for displaying RAM usage.
+//! Memory module for displaying RAM usage from the shared metrics service.
+//!
+//! The value is colored by the kernel's memory pressure level (green,
+//! yellow, red), which says more about whether the Mac is short on memory
+//! than the usage percentage. Clicking opens a breakdown of app, wired,
+//! compressed and cached memory plus swap.
 
-use std::process::Command;
 use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+use gpui::{div, prelude::*, px, AnyElement, Rgba, SharedString, Styled};
 
-use super::{GpuiModule, LabelAlign};
+use super::sysmon::format_bytes;
+use super::{mark_dirty, GpuiModule, LabelAlign, PopupSpec, RuleColors};
 use crate::gpui_app::theme::Theme;
+use crate::system::metrics::{self, MemoryDetails, MemoryPressure, Metric, Subscription};
+
+const POPUP_WIDTH: f64 = 260.0;
+const POPUP_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 28.0;
+const ROW_HEIGHT: f32 = 24.0;
+/// Pressure, app, wired, compressed, cached, swap
+const ROWS: usize = 6;
 
 /// Memory module that displays RAM usage percentage.
 pub struct MemoryModule {
@@ -17,32 +30,37 @@ pub struct MemoryModule {
     label_align: LabelAlign,
     fixed_width: bool,
     usage: Arc<AtomicU8>,
+    details: Arc<Mutex<Option<MemoryDetails>>>,
     dirty: Arc<AtomicBool>,
-    stop: Arc<AtomicBool>,
+    /// Value color from `color_rules`
+    rule_colors: RuleColors,
+    /// Shared with the popup copy; dropping the last one unsubscribes
+    _metrics: Arc<Subscription>,
 }
 
 impl MemoryModule {
     /// Creates a new memory module.
     pub fn new(id: &str, label: Option<&str>, label_align: LabelAlign, fixed_width: bool) -> Self {
         let usage = Arc::new(AtomicU8::new(0));
+        let details = Arc::new(Mutex::new(None));
         let dirty = Arc::new(AtomicBool::new(true));
-        let stop = Arc::new(AtomicBool::new(false));
 
         let usage_handle = Arc::clone(&usage);
+        let details_handle = Arc::clone(&details);
         let dirty_handle = Arc::clone(&dirty);
-        let stop_handle = Arc::clone(&stop);
-        std::thread::spawn(move || {
-            let mut last = 0;
-            while !stop_handle.load(Ordering::Relaxed) {
-                let next = Self::fetch_status();
-                if next != last {
-                    usage_handle.store(next, Ordering::Relaxed);
-                    dirty_handle.store(true, Ordering::Relaxed);
-                    last = next;
+        let subscription =
+            metrics::subscribe(Metric::Memory, Duration::from_secs(5), move |snapshot| {
+                let next = snapshot.memory.unwrap_or(0);
+                let mut changed = usage_handle.swap(next, Ordering::Relaxed) != next;
+                if let Ok(mut guard) = details_handle.lock() {
+                    let pressure = |d: &Option<MemoryDetails>| d.map(|d| d.pressure);
+                    changed |= pressure(&guard) != pressure(&snapshot.memory_details);
+                    *guard = snapshot.memory_details;
                 }
-                std::thread::sleep(Duration::from_secs(5));
-            }
-        });
+                if changed {
+                    mark_dirty(&dirty_handle);
+                }
+            });
 
         Self {
             id: id.to_string(),
@@ -50,23 +68,60 @@ impl MemoryModule {
             label_align,
             fixed_width,
             usage,
+            details,
             dirty,
-            stop,
+            rule_colors: RuleColors::default(),
+            _metrics: Arc::new(subscription),
+        }
+    }
+
+    /// Returns a second handle on the same state for the popup registry.
+    pub fn popup_handle(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            label: self.label.clone(),
+            label_align: self.label_align,
+            fixed_width: self.fixed_width,
+            usage: Arc::clone(&self.usage),
+            details: Arc::clone(&self.details),
+            dirty: Arc::clone(&self.dirty),
+            rule_colors: self.rule_colors,
+            _metrics: Arc::clone(&self._metrics),
         }
     }
 
-    fn fetch_status() -> u8 {
-        let mut usage = 0;
-        let output = Command::new("sh")
-            .args(["-c", "memory_pressure | grep 'System-wide memory free percentage' | awk '{print $5}' | tr -d '%'"])
-            .output()
-            .ok()
-            .and_then(|o| String::from_utf8(o.stdout).ok());
+    fn details(&self) -> Option<MemoryDetails> {
+        self.details.lock().ok().and_then(|guard| *guard)
+    }
 
-        if let Some(free) = output.and_then(|s| s.trim().parse::<f32>().ok()) {
-            usage = (100.0 - free).round() as u8;
+    fn pressure_color(theme: &Theme, pressure: MemoryPressure) -> Rgba {
+        match pressure {
+            MemoryPressure::Normal => theme.success,
+            MemoryPressure::Warning => theme.warning,
+            MemoryPressure::Critical => theme.destructive,
         }
-        usage
+    }
+
+    /// Value color: the pressure level once known, plain text before.
+    fn value_color(&self, theme: &Theme) -> Rgba {
+        self.details()
+            .map(|d| Self::pressure_color(theme, d.pressure))
+            .unwrap_or(theme.foreground)
+    }
+
+    fn detail_row(theme: &Theme, name: &'static str, value: String, color: Rgba) -> gpui::Div {
+        div()
+            .h(px(ROW_HEIGHT))
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .child(div().size(px(8.0)).rounded_full().bg(color))
+            .child(div().flex_1().child(name))
+            .child(
+                div()
+                    .text_color(theme.foreground_muted)
+                    .child(SharedString::from(value)),
+            )
     }
 }
 
@@ -76,8 +131,11 @@ impl GpuiModule for MemoryModule {
     }
 
     fn render(&self, theme: &Theme) -> AnyElement {
-        let usage = self.usage.load(Ordering::Relaxed);
-        let text = format!("{}%", usage);
+        let text = format!("{}%", self.usage.load(Ordering::Relaxed));
+        let color = self
+            .rule_colors
+            .text
+            .unwrap_or_else(|| self.value_color(theme));
 
         if let Some(ref label) = self.label {
             // Two-line layout with label - configurable alignment
@@ -105,7 +163,7 @@ impl GpuiModule for MemoryModule {
                         .min_w(px(if self.fixed_width { value_width } else { 0.0 }))
                         .flex()
                         .justify_end()
-                        .text_color(theme.foreground)
+                        .text_color(color)
                         .text_size(px(theme.font_size * 0.85))
                         .line_height(px(theme.font_size * 0.9))
                         .child(SharedString::from(text)),
@@ -115,7 +173,7 @@ impl GpuiModule for MemoryModule {
             div()
                 .flex()
                 .items_center()
-                .text_color(theme.foreground)
+                .text_color(color)
                 .text_size(px(theme.font_size * 0.85))
                 .child(SharedString::from(text))
                 .into_any_element()
@@ -126,13 +184,118 @@ impl GpuiModule for MemoryModule {
         self.dirty.swap(false, Ordering::Relaxed)
     }
 
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
     fn value(&self) -> Option<u8> {
-        Some(100 - self.usage.load(Ordering::Relaxed)) // Invert so low memory usage is "good"
+        Some(self.usage.load(Ordering::Relaxed))
     }
-}
 
-impl Drop for MemoryModule {
-    fn drop(&mut self) {
-        self.stop.store(true, Ordering::Relaxed);
+    fn display_text(&self) -> Option<String> {
+        Some(format!("{}%", self.usage.load(Ordering::Relaxed)))
+    }
+
+    fn set_rule_colors(&mut self, colors: RuleColors) {
+        self.rule_colors = colors;
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let height = POPUP_PADDING * 2.0 + HEADER_HEIGHT + ROWS as f32 * ROW_HEIGHT;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let content = match self.details() {
+            Some(details) => {
+                let size = |bytes: u64| format_bytes(bytes as f64);
+                let (pressure, pressure_text) = match details.pressure {
+                    MemoryPressure::Normal => (theme.success, "Normal"),
+                    MemoryPressure::Warning => (theme.warning, "Warning"),
+                    MemoryPressure::Critical => (theme.destructive, "Critical"),
+                };
+                div()
+                    .flex()
+                    .flex_col()
+                    .child(Self::detail_row(
+                        theme,
+                        "Pressure",
+                        pressure_text.to_string(),
+                        pressure,
+                    ))
+                    .child(Self::detail_row(
+                        theme,
+                        "App memory",
+                        size(details.app),
+                        theme.accent,
+                    ))
+                    .child(Self::detail_row(
+                        theme,
+                        "Wired",
+                        size(details.wired),
+                        theme.warning,
+                    ))
+                    .child(Self::detail_row(
+                        theme,
+                        "Compressed",
+                        size(details.compressed),
+                        theme.info,
+                    ))
+                    .child(Self::detail_row(
+                        theme,
+                        "Cached files",
+                        size(details.cached),
+                        theme.foreground_subtle,
+                    ))
+                    .child(Self::detail_row(
+                        theme,
+                        "Swap used",
+                        format!(
+                            "{} of {}",
+                            size(details.swap_used),
+                            size(details.swap_total)
+                        ),
+                        theme.destructive,
+                    ))
+            }
+            None => div()
+                .h(px(ROW_HEIGHT))
+                .text_color(theme.foreground_muted)
+                .child("Loading…"),
+        };
+        let total = self
+            .details()
+            .filter(|d| d.total > 0)
+            .map(|d| format!("{} total", format_bytes(d.total as f64)))
+            .unwrap_or_default();
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(
+                    div()
+                        .h(px(HEADER_HEIGHT))
+                        .flex()
+                        .items_center()
+                        .justify_between()
+                        .child(div().font_weight(gpui::FontWeight::SEMIBOLD).child(
+                            SharedString::from(format!(
+                                "Memory {}%",
+                                self.usage.load(Ordering::Relaxed)
+                            )),
+                        ))
+                        .child(
+                            div()
+                                .text_color(theme.foreground_muted)
+                                .child(SharedString::from(total)),
+                        ),
+                )
+                .child(content)
+                .into_any_element(),
+        )
     }
 }
@@ -5,58 +5,132 @@
 //! Modules may optionally provide popup content.
 
 mod app_name;
+mod appearance;
+mod applescript;
+mod audio_devices;
 mod battery;
 pub mod calendar;
+mod ci;
 mod clock;
+pub mod config_error;
+pub mod context_menu;
 mod cpu;
 mod date;
 mod datetime;
 mod demo;
 mod disk;
+mod display;
+mod docker;
+mod emoji;
 pub mod external;
+mod folder;
+mod history_popup;
+mod homeassistant;
+mod i3bar_input;
+mod ics;
+mod json_path;
+mod launcher;
+mod logs;
 mod memory;
+mod mqtt;
+pub mod notch;
 mod now_playing;
+mod peripherals;
+mod plugin;
 mod popup_host;
+mod power;
+pub mod privacy;
+mod reminders;
+mod screenshare;
 mod script;
+mod script_popup;
 mod separator;
+mod shortcuts;
 mod skeleton_demo;
+#[cfg(test)]
+mod snapshot;
+pub mod snoozed;
 mod static_text;
+mod sysmon;
 mod temperature;
+mod template;
+mod timetrack;
+mod timezone;
+mod updates;
 mod volume;
+mod vpn;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod waybar;
 mod weather;
 mod wifi;
 mod window_title;
 
 pub use app_name::AppNameModule;
+pub use appearance::AppearanceModule;
+pub use applescript::AppleScriptModule;
+pub use audio_devices::AudioDevicesModule;
 pub use battery::BatteryModule;
 pub use calendar::CalendarModule;
+pub use ci::CiModule;
 pub use clock::ClockModule;
+pub use context_menu::ContextMenuModule;
 pub use cpu::CpuModule;
 pub use date::DateModule;
 pub use datetime::DateTimeModule;
 pub use demo::DemoModule;
 pub use disk::DiskModule;
+pub use display::DisplayModule;
+pub use docker::DockerModule;
+pub use emoji::EmojiModule;
 pub use external::ExternalModule;
+pub use folder::FolderModule;
+pub use history_popup::HistoryPopupModule;
+pub use homeassistant::HomeAssistantModule;
+pub use i3bar_input::I3barInputModule;
+pub use ics::IcsModule;
+pub use launcher::LauncherModule;
+pub use logs::LogsModule;
 pub use memory::MemoryModule;
+pub use mqtt::MqttModule;
 pub use now_playing::NowPlayingModule;
+pub use peripherals::PeripheralsModule;
 pub use popup_host::PopupHostView;
+pub use power::PowerModule;
+pub use privacy::PrivacyModule;
+pub use reminders::RemindersModule;
+pub use screenshare::ScreenShareModule;
 pub use script::ScriptModule;
+pub use script_popup::ScriptPopupModule;
 pub use separator::SeparatorModule;
+pub use shortcuts::ShortcutsModule;
 pub use skeleton_demo::SkeletonDemoModule;
 pub use static_text::StaticTextModule;
+pub use sysmon::SysmonModule;
 pub use temperature::TemperatureModule;
+pub use timetrack::TimeTrackModule;
+pub use updates::UpdatesModule;
 pub use volume::VolumeModule;
+pub use vpn::VpnModule;
+#[cfg(feature = "wasm")]
+pub use wasm::WasmModule;
+pub use waybar::WaybarModule;
 pub use weather::WeatherModule;
 pub use wifi::WifiModule;
 pub use window_title::WindowTitleModule;
 
-use gpui::AnyElement;
+use gpui::{div, prelude::*, px, AnyElement};
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
 
-use crate::config::{parse_hex_color, ModuleConfig};
+use crate::config::{parse_hex_color, Condition, Config, ModuleConfig};
+use crate::gpui_app::layout::Zone;
+use crate::gpui_app::primitives::{expand_tilde, icons, MarqueeSettings};
 use crate::gpui_app::theme::Theme;
+use crate::system::osa::Language;
+use crate::system::providers;
 
 type ModuleFactory = fn(&str, &ModuleConfig) -> Option<Box<dyn GpuiModule>>;
 
@@ -84,7 +158,39 @@ fn ensure_builtin_factories() {
     INIT.get_or_init(|| {
         register_module_factory("clock", |id, config| {
             let format = config.format.as_deref().unwrap_or("%a %b %d  %H:%M:%S");
-            Some(Box::new(ClockModule::new(id, format)))
+            let zones = config
+                .extras
+                .get("zones")
+                .and_then(|v| v.as_array())
+                .map(|names| {
+                    names
+                        .iter()
+                        .filter_map(|v| v.as_str())
+                        .filter_map(|name| {
+                            let zone = timezone::TimeZone::parse(name);
+                            if zone.is_none() {
+                                log::warn!("clock: unknown timezone '{}'", name);
+                            }
+                            zone
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let flag = |key: &str| {
+                config
+                    .extras
+                    .get(key)
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false)
+            };
+            Some(Box::new(ClockModule::new(
+                id,
+                format,
+                zones,
+                flag("blink"),
+                flag("hour12"),
+                flag("toggle_hour12"),
+            )))
         });
         register_module_factory("date", |id, config| {
             let format = config.format.as_deref().unwrap_or("%a %b %d");
@@ -96,17 +202,56 @@ fn ensure_builtin_factories() {
             Some(Box::new(DateTimeModule::new(id, date_format, time_format)))
         });
         register_module_factory("battery", |id, config| {
-            Some(Box::new(BatteryModule::new(id, config.label.as_deref())))
+            let display = battery::BatteryDisplay::parse(config.display.as_deref());
+            let emergency = battery::Emergency::parse(config.extras.get("emergency"));
+            let alerts = battery::Alert::parse_list(config.extras.get("alerts"));
+            let format = |key: &str| config.extras.get(key).and_then(|v| v.as_str());
+            let state_formats = battery::StateFormats::new(
+                format("format_charging"),
+                format("format_full"),
+                format("format_low"),
+            );
+            Some(Box::new(
+                BatteryModule::new(
+                    id,
+                    config.label.as_deref(),
+                    display,
+                    config.format.as_deref(),
+                    emergency,
+                    alerts,
+                    providers::battery(),
+                )
+                .with_state_formats(state_formats),
+            ))
         });
         register_module_factory("cpu", |id, config| {
             let label_align = parse_label_align(config.label_align.as_deref());
             let fixed_width = config.value_fixed_width.unwrap_or(true);
-            Some(Box::new(CpuModule::new(
+            let module = CpuModule::new(
                 id,
                 config.label.as_deref(),
                 label_align,
                 fixed_width,
-            )))
+                config.format.as_deref(),
+            );
+            register_popup_module(module.popup_handle());
+            Some(Box::new(module))
+        });
+        register_module_factory("power", |id, config| {
+            let interval = config.interval.map(|v| v as u64).unwrap_or(5);
+            let warning = config.warning_threshold.unwrap_or(15.0);
+            let critical = config.critical_threshold.unwrap_or(25.0);
+            let module = PowerModule::new(id, interval, warning, critical);
+            register_popup_module(module.popup_handle());
+            Some(Box::new(module))
+        });
+        register_module_factory("peripherals", |id, config| {
+            let interval = config.interval.map(|v| v as u64).unwrap_or(60);
+            let warning = config.warning_threshold.unwrap_or(20.0);
+            let critical = config.critical_threshold.unwrap_or(10.0);
+            let module = PeripheralsModule::new(id, interval, warning, critical);
+            register_popup_module(module.popup_handle());
+            Some(Box::new(module))
         });
         register_module_factory("temperature", |id, config| {
             let label_align = parse_label_align(config.label_align.as_deref());
@@ -135,29 +280,75 @@ fn ensure_builtin_factories() {
         register_module_factory("memory", |id, config| {
             let label_align = parse_label_align(config.label_align.as_deref());
             let fixed_width = config.value_fixed_width.unwrap_or(true);
-            Some(Box::new(MemoryModule::new(
-                id,
-                config.label.as_deref(),
-                label_align,
-                fixed_width,
-            )))
+            let module = MemoryModule::new(id, config.label.as_deref(), label_align, fixed_width);
+            register_popup_module(module.popup_handle());
+            Some(Box::new(module))
         });
         register_module_factory("disk", |id, config| {
             let path = config.path.as_deref().unwrap_or("/");
             let label_align = parse_label_align(config.label_align.as_deref());
             let fixed_width = config.value_fixed_width.unwrap_or(false);
-            Some(Box::new(DiskModule::new(
+            let show_io = config
+                .extras
+                .get("io")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            let module = DiskModule::new(
                 id,
                 path,
                 config.label.as_deref(),
                 label_align,
                 fixed_width,
-            )))
+                show_io,
+            );
+            register_popup_module(module.popup_handle());
+            Some(Box::new(module))
+        });
+        register_module_factory("network", |id, config| {
+            let module = WifiModule::new(id, providers::network(), config.format.as_deref());
+            register_popup_module(module.popup_handle());
+            Some(Box::new(module))
+        });
+        register_module_factory("wifi", |id, config| {
+            let module = WifiModule::new(id, providers::network(), config.format.as_deref());
+            register_popup_module(module.popup_handle());
+            Some(Box::new(module))
+        });
+        register_module_factory("audio_devices", |id, config| {
+            let module = AudioDevicesModule::new(id, config.format.as_deref());
+            register_popup_module(module.popup_handle());
+            Some(Box::new(module))
+        });
+        register_module_factory("display", |id, config| {
+            let module = DisplayModule::new(id, config.format.as_deref());
+            register_popup_module(module.popup_handle());
+            Some(Box::new(module))
+        });
+        register_module_factory("vpn", |id, config| {
+            let name = config.extras.get("name").and_then(|v| v.as_str());
+            Some(Box::new(VpnModule::new(id, name)))
+        });
+        register_module_factory("shortcuts", |id, config| {
+            let shortcuts = config
+                .extras
+                .get("shortcuts")
+                .and_then(|v| v.as_array())
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|v| v.as_str())
+                        .map(str::to_string)
+                        .collect()
+                });
+            let module = ShortcutsModule::new(id, shortcuts);
+            register_popup_module(module.popup_handle());
+            Some(Box::new(module))
+        });
+        register_module_factory("appearance", |id, _config| {
+            Some(Box::new(AppearanceModule::new(id)))
         });
-        register_module_factory("network", |id, _config| Some(Box::new(WifiModule::new(id))));
-        register_module_factory("wifi", |id, _config| Some(Box::new(WifiModule::new(id))));
         register_module_factory("volume", |id, _config| {
-            Some(Box::new(VolumeModule::new(id)))
+            Some(Box::new(VolumeModule::new(id, providers::audio())))
         });
         register_module_factory("app_name", |id, config| {
             let max_len = config.max_length.map(|v| v as usize).unwrap_or(30);
@@ -165,17 +356,71 @@ fn ensure_builtin_factories() {
         });
         register_module_factory("window_title", |id, config| {
             let max_len = config.max_length.map(|v| v as usize).unwrap_or(50);
-            Some(Box::new(WindowTitleModule::new(id, max_len)))
+            let rules = window_title::parse_rules(config.extras.get("rewrite"));
+            let marquee = MarqueeSettings::parse(config.extras.get("marquee"));
+            Some(Box::new(
+                WindowTitleModule::new(id, max_len, rules).with_marquee(marquee),
+            ))
         });
         register_module_factory("now_playing", |id, config| {
             let max_len = config.max_length.map(|v| v as usize).unwrap_or(40);
-            Some(Box::new(NowPlayingModule::new(id, max_len)))
+            let sources = config
+                .extras
+                .get("sources")
+                .and_then(|v| v.as_array())
+                .map(|names| {
+                    names
+                        .iter()
+                        .filter_map(|v| v.as_str())
+                        .filter_map(|name| {
+                            let source = now_playing::Source::parse(name);
+                            if source.is_none() {
+                                log::warn!("now_playing: unknown source '{}'", name);
+                            }
+                            source
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let marquee = MarqueeSettings::parse(config.extras.get("marquee"));
+            Some(Box::new(
+                NowPlayingModule::new(id, max_len, sources, providers::media())
+                    .with_marquee(marquee),
+            ))
         });
         register_module_factory("script", |id, config| {
             let command = config.command.as_deref().unwrap_or("echo 'no command'");
             let interval = config.interval.map(|v| v as u64);
             let icon = config.icon.as_deref();
-            Some(Box::new(ScriptModule::new(id, command, interval, icon)))
+            let format = config.format.as_deref();
+            let waybar_compat = config.extras.get("waybar_compat");
+            if waybar_compat.and_then(|v| v.as_bool()).unwrap_or(false) {
+                let seconds =
+                    |value: Option<f64>| value.filter(|s| *s > 0.0).map(Duration::from_secs_f64);
+                let restart = config
+                    .extras
+                    .get("restart_interval")
+                    .and_then(|v| v.as_float().or_else(|| v.as_integer().map(|i| i as f64)));
+                return Some(Box::new(WaybarModule::new(
+                    id,
+                    command,
+                    seconds(config.interval),
+                    seconds(restart),
+                    format,
+                    waybar::FormatIcons::parse(config.extras.get("format_icons")),
+                    waybar::parse_class_colors(config.extras.get("class_colors")),
+                )));
+            }
+            Some(Box::new(ScriptModule::new(
+                id, command, interval, icon, format,
+            )))
+        });
+        register_module_factory("i3bar_input", |id, config| {
+            let Some(command) = config.command.as_deref() else {
+                log::warn!("i3bar_input: '{}' has no command", id);
+                return None;
+            };
+            Some(Box::new(I3barInputModule::new(id, command)))
         });
         register_module_factory("weather", |id, config| {
             let location = config.location.as_deref().unwrap_or("auto");
@@ -185,7 +430,10 @@ fn ensure_builtin_factories() {
         register_module_factory("static", |id, config| {
             let text = config.text.as_deref().unwrap_or("");
             let icon = config.icon.as_deref();
-            Some(Box::new(StaticTextModule::new(id, text, icon)))
+            let image = config.extras.get("image").and_then(|v| v.as_str());
+            Some(Box::new(
+                StaticTextModule::new(id, text, icon).with_image(image),
+            ))
         });
         register_module_factory("separator", |id, config| {
             let sep_type = config.separator_type.as_deref().unwrap_or("space");
@@ -205,11 +453,331 @@ fn ensure_builtin_factories() {
             let icon = config.icon.as_deref();
             Some(Box::new(ExternalModule::new(id, label, icon)))
         });
+        register_module_factory("launcher", |id, config| {
+            let commands = launcher::parse_commands(config.extras.get("commands"));
+            register_popup_module(LauncherModule::new(id, commands));
+            let text = config.text.as_deref().unwrap_or("");
+            let icon = config.icon.as_deref().unwrap_or(icons::system::APPS);
+            Some(Box::new(StaticTextModule::new(id, text, Some(icon))))
+        });
+        register_module_factory("logs", |id, _config| {
+            register_popup_module(LogsModule::new(id));
+            Some(Box::new(LogsModule::new(id)))
+        });
+        register_module_factory("emoji", |id, config| {
+            let action = config.extras.get("action").and_then(|v| v.as_str());
+            register_popup_module(EmojiModule::new(id, emoji::EmojiAction::parse(action)));
+            let text = config.text.as_deref().unwrap_or("");
+            let icon = config.icon.as_deref().unwrap_or("😀");
+            Some(Box::new(StaticTextModule::new(id, text, Some(icon))))
+        });
+        register_module_factory("privacy", |id, config| {
+            let flag = |key: &str| {
+                config
+                    .extras
+                    .get(key)
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(true)
+            };
+            let (camera, microphone, screen) = (flag("camera"), flag("microphone"), flag("screen"));
+            let flash = flag("flash").then(|| {
+                let ms = config
+                    .extras
+                    .get("flash_ms")
+                    .and_then(|v| v.as_integer())
+                    .map(|v| v.clamp(200, 10_000) as u64)
+                    .unwrap_or(privacy::DEFAULT_FLASH_MS);
+                std::time::Duration::from_millis(ms)
+            });
+            register_popup_module(PrivacyModule::new(id, camera, microphone, screen, flash));
+            Some(Box::new(PrivacyModule::new(
+                id, camera, microphone, screen, flash,
+            )))
+        });
+        register_module_factory("docker", |id, config| {
+            let socket = config.extras.get("socket").and_then(|v| v.as_str());
+            let interval = config.interval.map(|v| v as u64).unwrap_or(5);
+            let module = DockerModule::new(id, socket, interval);
+            register_popup_module(module.popup_handle());
+            Some(Box::new(module))
+        });
+        register_module_factory("ics", |id, config| {
+            let mut sources: Vec<String> = config
+                .extras
+                .get("urls")
+                .and_then(|v| v.as_array())
+                .map(|urls| {
+                    urls.iter()
+                        .filter_map(|v| v.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default();
+            if let Some(url) = config.extras.get("url").and_then(|v| v.as_str()) {
+                sources.push(url.to_string());
+            }
+            let interval = config.interval.map(|v| v as u64).unwrap_or(900);
+            let module = IcsModule::new(id, sources, interval, config.format.as_deref());
+            register_popup_module(module.popup_handle());
+            Some(Box::new(module))
+        });
+        register_module_factory("sysmon", |id, config| {
+            let interval = config.interval.map(|v| v as u64).unwrap_or(2);
+            let module = SysmonModule::new(id, interval);
+            register_popup_module(module.popup_handle());
+            Some(Box::new(module))
+        });
+        register_module_factory("reminders", |id, _config| {
+            register_popup_module(RemindersModule::new(id));
+            Some(Box::new(RemindersModule::new(id)))
+        });
+        register_module_factory("timetrack", |id, config| {
+            let project = config.extras.get("project").and_then(|v| v.as_str());
+            let token = config.extras.get("toggl_token").and_then(|v| v.as_str());
+            let workspace = config
+                .extras
+                .get("toggl_workspace")
+                .and_then(|v| v.as_integer());
+            let toggl = match (token, workspace) {
+                (Some(token), Some(workspace)) if !token.is_empty() => {
+                    Some(timetrack::TogglConfig {
+                        token: token.to_string(),
+                        workspace: workspace as u64,
+                    })
+                }
+                _ => None,
+            };
+            let idle_stop = config
+                .extras
+                .get("idle_stop")
+                .and_then(|v| v.as_float().or_else(|| v.as_integer().map(|i| i as f64)))
+                .filter(|secs| *secs > 0.0)
+                .map(Duration::from_secs_f64);
+            Some(Box::new(TimeTrackModule::new(
+                id,
+                project.unwrap_or("sinew"),
+                toggl,
+                idle_stop,
+            )))
+        });
+        register_module_factory("screenshare", |id, config| {
+            let extra = screenshare::parse_processes(config.extras.get("processes"));
+            register_popup_module(ScreenShareModule::new(id, extra.clone()));
+            Some(Box::new(ScreenShareModule::new(id, extra)))
+        });
+        register_module_factory("mqtt", |id, config| {
+            let string = |key: &str| config.extras.get(key).and_then(|v| v.as_str());
+            let Some(broker) = string("broker") else {
+                log::warn!("mqtt: '{}' has no broker", id);
+                return None;
+            };
+            let (host, port) = match crate::system::mqtt::parse_broker(broker) {
+                Ok(address) => address,
+                Err(e) => {
+                    log::warn!("mqtt: '{}': {}", id, e);
+                    return None;
+                }
+            };
+            let mut topics: Vec<String> = config
+                .extras
+                .get("topics")
+                .and_then(|v| v.as_array())
+                .map(|topics| {
+                    topics
+                        .iter()
+                        .filter_map(|v| v.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default();
+            if let Some(topic) = string("topic") {
+                topics.push(topic.to_string());
+            }
+            if topics.is_empty() {
+                log::warn!("mqtt: '{}' has no topics", id);
+                return None;
+            }
+            let json_path = match string("json_path").map(json_path::JsonPath::parse) {
+                Some(Ok(path)) => Some(path),
+                Some(Err(e)) => {
+                    log::warn!("mqtt: '{}': {}", id, e);
+                    None
+                }
+                None => None,
+            };
+            let click = string("click_topic").map(|topic| {
+                let payload = string("click_payload").unwrap_or("");
+                (topic.to_string(), payload.to_string())
+            });
+            let options = crate::system::mqtt::Options {
+                host,
+                port,
+                client_id: crate::system::mqtt::client_id(),
+                username: string("username").map(String::from),
+                password: string("password").map(String::from),
+                keep_alive: Duration::from_secs(60),
+            };
+            Some(Box::new(MqttModule::new(
+                id,
+                options,
+                topics,
+                json_path,
+                config.format.as_deref(),
+                click,
+            )))
+        });
+        register_module_factory("homeassistant", |id, config| {
+            let string = |key: &str| config.extras.get(key).and_then(|v| v.as_str());
+            let (Some(url), Some(token)) = (string("url"), string("token")) else {
+                log::warn!("homeassistant: '{}' needs a url and a token", id);
+                return None;
+            };
+            let connection = match homeassistant::Connection::parse(url, token) {
+                Ok(connection) => connection,
+                Err(e) => {
+                    log::warn!("homeassistant: '{}': {}", id, e);
+                    return None;
+                }
+            };
+            let mut entities: Vec<String> = config
+                .extras
+                .get("entities")
+                .and_then(|v| v.as_array())
+                .map(|entities| {
+                    entities
+                        .iter()
+                        .filter_map(|v| v.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default();
+            if let Some(entity) = string("entity") {
+                entities.push(entity.to_string());
+            }
+            if entities.is_empty() {
+                log::warn!("homeassistant: '{}' has no entities", id);
+                return None;
+            }
+            let click = string("click_service").and_then(|service| {
+                let data = match config.extras.get("click_data") {
+                    Some(data) => serde_json::to_value(data).unwrap_or_default(),
+                    // A single entity is the obvious target
+                    None if entities.len() == 1 && !entities[0].contains('*') => {
+                        serde_json::json!({ "entity_id": entities[0] })
+                    }
+                    None => serde_json::json!({}),
+                };
+                let call = homeassistant::ServiceCall::parse(service, data);
+                if call.is_none() {
+                    log::warn!(
+                        "homeassistant: '{}': click_service '{}' isn't domain.service",
+                        id,
+                        service
+                    );
+                }
+                call
+            });
+            Some(Box::new(HomeAssistantModule::new(
+                id,
+                connection,
+                entities,
+                string("attribute").map(String::from),
+                string("count_state").unwrap_or("on"),
+                config.format.as_deref(),
+                click,
+            )))
+        });
+        register_module_factory("ci", |id, config| {
+            let token = config.extras.get("token").and_then(|v| v.as_str());
+            let providers = ci::parse_pipelines(id, config.extras.get("pipelines"), token);
+            if providers.is_empty() {
+                log::warn!("ci: '{}' has no pipelines", id);
+                return None;
+            }
+            let runs = config
+                .extras
+                .get("runs")
+                .and_then(|v| v.as_integer())
+                .map(|v| v.clamp(1, 20) as usize)
+                .unwrap_or(5);
+            let interval = config.interval.map(|v| v as u64).unwrap_or(120);
+            let module = CiModule::new(id, providers, runs, interval, config.format.as_deref());
+            register_popup_module(module.popup_handle());
+            Some(Box::new(module))
+        });
+        register_module_factory("updates", |id, config| {
+            let flag = |key: &str| config.extras.get(key).and_then(|v| v.as_bool());
+            let mas = flag("mas").unwrap_or(false);
+            let default_command = if mas {
+                "brew upgrade && mas upgrade"
+            } else {
+                "brew upgrade"
+            };
+            let update_command = config
+                .extras
+                .get("update_command")
+                .and_then(|v| v.as_str())
+                .unwrap_or(default_command);
+            let interval = config.interval.map(|v| v as u64).unwrap_or(3600);
+            let module = UpdatesModule::new(
+                id,
+                mas,
+                flag("brew_update").unwrap_or(true),
+                interval,
+                update_command,
+            );
+            register_popup_module(module.popup_handle());
+            Some(Box::new(module))
+        });
+        register_module_factory("folder", |id, config| {
+            let path = folder::resolve_path(config.path.as_deref().unwrap_or("downloads"));
+            let empty_trash = config
+                .extras
+                .get("empty_trash")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true);
+            let interval = config.interval.map(|v| v as u64).unwrap_or(300);
+            let module =
+                FolderModule::new(id, path, empty_trash, interval, config.format.as_deref());
+            register_popup_module(module.popup_handle());
+            Some(Box::new(module))
+        });
+        register_module_factory("applescript", |id, config| {
+            let option = |key: &str| config.extras.get(key).and_then(|v| v.as_str());
+            let source = match (option("script"), option("file")) {
+                (Some(script), _) => applescript::Source::Inline(script.to_string()),
+                (None, Some(file)) => applescript::Source::File(expand_tilde(file)),
+                (None, None) => applescript::Source::Inline(String::new()),
+            };
+            let language = option("language")
+                .and_then(Language::parse)
+                .unwrap_or_default();
+            let interval = Duration::from_secs_f64(config.interval.unwrap_or(10.0).max(1.0));
+            Some(Box::new(AppleScriptModule::new(
+                id,
+                source,
+                language,
+                interval,
+                config.icon.as_deref(),
+                config.format.as_deref(),
+            )))
+        });
+        #[cfg(feature = "wasm")]
+        register_module_factory("wasm", |id, config| {
+            let path = config.path.as_deref()?;
+            let config_json = config
+                .extras
+                .get("config")
+                .and_then(|v| serde_json::to_string(v).ok())
+                .unwrap_or_else(|| "{}".to_string());
+            let interval = Duration::from_secs_f64(config.interval.unwrap_or(1.0).max(0.1));
+            let module = WasmModule::new(id, expand_tilde(path), config_json, interval);
+            register_popup_module(module.popup_handle());
+            Some(Box::new(module))
+        });
     });
 }
 
 pub fn init_module_factories() {
     ensure_builtin_factories();
+    plugin::load_plugins();
 }
 
 fn popup_config_map() -> &'static RwLock<HashMap<String, PopupConfig>> {
@@ -251,17 +819,19 @@ pub struct PopupSpec {
     pub anchor: PopupAnchor,
     /// Type of popup (popup vs full-width panel)
     pub popup_type: PopupType,
+    /// Whether the popup takes keyboard focus (for text inputs)
+    pub keyboard: bool,
 }
 
 impl PopupSpec {
     /// Creates a new popup spec.
-    #[allow(dead_code)]
     pub fn new(width: f64, height: f64) -> Self {
         Self {
             width,
             height,
             anchor: PopupAnchor::Center,
             popup_type: PopupType::Popup,
+            keyboard: false,
         }
     }
 
@@ -272,9 +842,16 @@ impl PopupSpec {
             height,
             anchor: PopupAnchor::Left,
             popup_type: PopupType::Panel,
+            keyboard: false,
         }
     }
 
+    /// Makes the popup window key so it receives keyboard input.
+    pub fn with_keyboard(mut self) -> Self {
+        self.keyboard = true;
+        self
+    }
+
     /// Sets the anchor position.
     #[allow(dead_code)]
     pub fn with_anchor(mut self, anchor: PopupAnchor) -> Self {
@@ -298,6 +875,46 @@ pub enum PopupEvent {
     MouseLeft,
     /// Scroll event with delta
     Scroll { delta_x: f32, delta_y: f32 },
+    /// Key pressed while the popup has keyboard focus
+    Key(PopupKey),
+}
+
+/// A key press routed to a popup with keyboard focus.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PopupKey {
+    /// GPUI key name ("a", "backspace", "left", "enter", ...)
+    pub key: String,
+    /// Text the key produces, if any (respects shift/option)
+    pub text: Option<String>,
+    pub shift: bool,
+    pub alt: bool,
+    pub control: bool,
+    /// Command key
+    pub command: bool,
+}
+
+impl PopupKey {
+    /// Converts a GPUI keystroke.
+    pub fn from_keystroke(keystroke: &gpui::Keystroke) -> Self {
+        Self {
+            key: keystroke.key.clone(),
+            text: keystroke.key_char.clone(),
+            shift: keystroke.modifiers.shift,
+            alt: keystroke.modifiers.alt,
+            control: keystroke.modifiers.control,
+            command: keystroke.modifiers.platform,
+        }
+    }
+}
+
+/// Clicks on a module's bar item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseEvent {
+    Click,
+    RightClick,
+    MiddleClick,
+    /// Second press of a double click (the first arrives as `Click`)
+    DoubleClick,
 }
 
 /// Actions triggered from popup UI controls.
@@ -310,6 +927,9 @@ pub enum PopupAction {
     DragStart,
     DragEnd,
     SliderSet { value: f32 },
+    Refresh,
+    RunCommand { command: String },
+    Select { value: String },
 }
 
 /// Trait for GPUI-based bar modules.
@@ -328,19 +948,55 @@ pub trait GpuiModule: Send + Sync {
         false
     }
 
-    /// Returns the current value (0-100) for threshold-based coloring.
+    /// How long until `update()` should run again. Asked after every
+    /// update, so modules can time it to when their text will change.
+    ///
+    /// Returns None for modules that only change when something wakes the
+    /// bar: a background thread calling [`mark_dirty`], an IPC command or a
+    /// system notification. The default polls every second.
+    fn next_update(&self) -> Option<Duration> {
+        Some(Duration::from_secs(1))
+    }
+
+    /// Returns the current value for `color_rules`: a percentage for most
+    /// modules, degrees for temperature.
     /// Returns None if the module doesn't support value-based colors.
-    #[allow(dead_code)]
     fn value(&self) -> Option<u8> {
         None
     }
 
+    /// Text the context menu's "Copy value" puts on the clipboard.
+    /// Defaults to the value.
+    fn copy_text(&self) -> Option<String> {
+        self.value().map(|value| value.to_string())
+    }
+
+    /// Text the module shows in the bar, for mirroring it elsewhere with
+    /// `watch`. Defaults to the copy text; modules that draw more than
+    /// their value override it.
+    fn display_text(&self) -> Option<String> {
+        self.copy_text()
+    }
+
+    /// Text shown in a tooltip while the pointer rests on the module, for
+    /// details that don't fit in the bar. None shows no tooltip.
+    fn tooltip(&self) -> Option<String> {
+        None
+    }
+
     /// Returns true if the module is currently loading.
     #[allow(dead_code)]
     fn is_loading(&self) -> bool {
         false
     }
 
+    /// Returns true while the module draws an animation that changes every
+    /// frame (e.g. scrolling text). The bar keeps requesting frames while
+    /// any visible module does.
+    fn is_animating(&self) -> bool {
+        false
+    }
+
     /// Returns the popup specification (if any).
     /// The module calculates its own dimensions.
     fn popup_spec(&self) -> Option<PopupSpec> {
@@ -364,10 +1020,41 @@ pub trait GpuiModule: Send + Sync {
     /// Called before the module is removed/replaced in the registry.
     fn on_module_stop(&mut self) {}
 
+    /// Receives the `color_rules` colors the module draws itself: its value
+    /// text and icon. None restores the default color.
+    fn set_rule_colors(&mut self, _colors: RuleColors) {}
+
     /// Sets a property by key/value from IPC. Returns true if the property was accepted.
     fn set_property(&mut self, _key: &str, _value: &str) -> bool {
         false
     }
+
+    /// On/off state for modules that toggle by nature (e.g. a timer).
+    /// Returning Some makes the module toggleable without `toggle = true`.
+    fn toggle_state(&self) -> Option<bool> {
+        None
+    }
+
+    /// Called when the module is toggled by a click or IPC.
+    fn on_toggle(&mut self, _active: bool) {}
+
+    /// Called by `sinew-msg trigger <id> update` before `update()`. Modules
+    /// that fetch on a timer can fetch right away.
+    fn refresh(&mut self) {}
+
+    /// Handles a scroll over the module's bar item, in pixels as reported
+    /// by macOS (positive `delta_y` scrolls up).
+    /// Returns true if the module needs to be re-rendered.
+    fn on_scroll(&mut self, _delta_x: f32, _delta_y: f32) -> bool {
+        false
+    }
+
+    /// Handles a click on the module's bar item. Runs alongside any
+    /// configured click command, popup or toggle.
+    /// Returns true if the module needs to be re-rendered.
+    fn on_mouse_event(&mut self, _event: MouseEvent) -> bool {
+        false
+    }
 }
 
 /// Module styling options.
@@ -384,14 +1071,8 @@ pub struct ModuleStyle {
     pub corner_radius: f32,
     /// Padding
     pub padding: f32,
-    /// Critical color (for values below critical_threshold)
-    pub critical_color: Option<gpui::Rgba>,
-    /// Warning color (for values below warning_threshold)
-    pub warning_color: Option<gpui::Rgba>,
-    /// Threshold for critical state
-    pub critical_threshold: f32,
-    /// Threshold for warning state
-    pub warning_threshold: f32,
+    /// Value-based colors, checked in order
+    pub color_rules: Vec<ColorRule>,
     /// Background color when toggle is active
     pub active_background: Option<gpui::Rgba>,
     /// Border color when toggle is active
@@ -400,6 +1081,53 @@ pub struct ModuleStyle {
     pub active_text_color: Option<gpui::Rgba>,
 }
 
+/// What a color rule colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorTarget {
+    Text,
+    Background,
+    Icon,
+}
+
+impl ColorTarget {
+    /// Parses a `color_rules` target name.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "text" => Some(Self::Text),
+            "background" => Some(Self::Background),
+            "icon" => Some(Self::Icon),
+            _ => None,
+        }
+    }
+}
+
+/// A parsed `color_rules` entry.
+#[derive(Debug, Clone)]
+pub struct ColorRule {
+    pub condition: Condition,
+    pub color: gpui::Rgba,
+    pub target: ColorTarget,
+}
+
+impl ModuleStyle {
+    /// Returns the color of the first rule for `target` that matches
+    /// `value`.
+    pub fn rule_color(&self, target: ColorTarget, value: Option<u8>) -> Option<gpui::Rgba> {
+        let value = f64::from(value?);
+        self.color_rules
+            .iter()
+            .find(|rule| rule.target == target && rule.condition.matches(value))
+            .map(|rule| rule.color)
+    }
+}
+
+/// Text and icon colors from matching `color_rules`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RuleColors {
+    pub text: Option<gpui::Rgba>,
+    pub icon: Option<gpui::Rgba>,
+}
+
 /// Popup configuration for a module.
 #[allow(dead_code)]
 #[derive(Debug, Clone, Default)]
@@ -414,8 +1142,11 @@ pub struct PopupConfig {
     pub max_height_percent: f32,
     /// Command for script-type popup
     pub command: Option<String>,
-    /// Anchor position
-    pub anchor: PopupAnchor,
+    /// Anchor position; None keeps the one the module's popup asks for
+    pub anchor: Option<PopupAnchor>,
+    /// How long the pointer rests on the module before the popup opens;
+    /// None opens it on click only
+    pub hover_delay: Option<Duration>,
 }
 
 /// Popup anchor position.
@@ -449,6 +1180,10 @@ pub struct PositionedModule {
     pub click_command: Option<String>,
     /// Command to run when right-clicked
     pub right_click_command: Option<String>,
+    /// Command to run when middle-clicked
+    pub middle_click_command: Option<String>,
+    /// Command to run when double-clicked
+    pub double_click_command: Option<String>,
     /// Group ID for shared backgrounds
     pub group: Option<String>,
     /// Popup configuration
@@ -469,6 +1204,104 @@ pub struct PositionedModule {
     pub margin_left: Option<f32>,
     /// Right margin in pixels
     pub margin_right: Option<f32>,
+    /// Whether the module may move to the other half of the bar when its
+    /// own is full
+    pub spill: bool,
+    /// Colors last handed to the module from `color_rules`
+    pub rule_colors: RuleColors,
+    /// When `update()` is next due on a timer; None for modules that wait
+    /// to be woken
+    pub update_due: Option<Instant>,
+    /// Whether power saving slows this module down
+    pub power_saving: bool,
+    /// Whether the module's value is recorded for its history
+    pub history: bool,
+    /// Whether right-clicking opens the module's context menu
+    pub context_menu: bool,
+}
+
+impl PositionedModule {
+    /// Wraps a module the bar adds itself rather than one from the config.
+    pub fn internal(module: Box<dyn GpuiModule>, popup: PopupConfig) -> Self {
+        Self {
+            module,
+            style: ModuleStyle::default(),
+            text_color: None,
+            click_command: None,
+            right_click_command: None,
+            middle_click_command: None,
+            double_click_command: None,
+            group: None,
+            popup: Some(popup),
+            toggle_enabled: false,
+            toggle_active: false,
+            toggle_group: None,
+            flex: false,
+            min_width: None,
+            max_width: None,
+            margin_left: None,
+            margin_right: None,
+            spill: false,
+            rule_colors: RuleColors::default(),
+            update_due: Some(Instant::now()),
+            power_saving: true,
+            history: false,
+            context_menu: false,
+        }
+    }
+
+    /// Surrounds a rendered module with its `margin_left`/`margin_right`.
+    ///
+    /// Margins are padding on a wrapper so they count toward the module's
+    /// measured width.
+    pub fn with_margins(&self, element: impl IntoElement) -> AnyElement {
+        if self.margin_left.is_none() && self.margin_right.is_none() {
+            return element.into_any_element();
+        }
+        div()
+            .flex()
+            .items_center()
+            .pl(px(self.margin_left.unwrap_or(0.0)))
+            .pr(px(self.margin_right.unwrap_or(0.0)))
+            .child(element)
+            .into_any_element()
+    }
+}
+
+/// Adds a scroll delta to `accumulator` and returns how many whole `step_px`
+/// steps it now holds (negative when scrolling down), keeping the remainder
+/// so slow trackpad scrolls still add up.
+pub fn scroll_steps(accumulator: &mut f32, delta: f32, step_px: f32) -> i32 {
+    *accumulator += delta;
+    let steps = (*accumulator / step_px).trunc() as i32;
+    *accumulator -= steps as f32 * step_px;
+    steps
+}
+
+/// Marks a module's state as changed from a background thread and wakes the
+/// bar, which then runs `update()` instead of waiting for a timer.
+pub fn mark_dirty(dirty: &AtomicBool) {
+    dirty.store(true, Ordering::Relaxed);
+    crate::gpui_app::request_immediate_refresh();
+}
+
+/// Sleeps for `duration` on a background thread, returning early once
+/// `stop` is set so a dropped module's thread doesn't linger.
+pub fn sleep_unless_stopped(duration: Duration, stop: &AtomicBool) {
+    const STEP: Duration = Duration::from_millis(200);
+    let mut slept = Duration::ZERO;
+    while slept < duration && !stop.load(Ordering::Relaxed) {
+        std::thread::sleep(STEP.min(duration - slept));
+        slept += STEP;
+    }
+}
+
+/// Time until the wall clock reaches the next second, for modules that show
+/// the time so they change when the clock does.
+pub fn until_next_second() -> Duration {
+    let nanos = u64::from(chrono::Utc::now().timestamp_subsec_nanos());
+    // Leap seconds report more than a second of nanoseconds
+    Duration::from_nanos(1_000_000_000u64.saturating_sub(nanos).max(1_000_000))
 }
 
 /// Truncates text to a maximum number of characters, adding an ellipsis if truncated.
@@ -499,13 +1332,64 @@ fn parse_temp_unit(unit: Option<&str>) -> temperature::TemperatureUnit {
     }
 }
 
+/// Click command a module type provides when none is configured.
+fn default_click_command(config: &ModuleConfig) -> Option<String> {
+    match config.module_type.as_str() {
+        "vpn" => vpn::toggle_command(config),
+        _ => None,
+    }
+}
+
+/// Every configured bar module with its zone and creation index. Each zone
+/// numbers its modules from its own base, so default ids stay the same when
+/// modules are added to another zone.
+pub fn configured_modules(config: &Config) -> Vec<(Zone, usize, &ModuleConfig)> {
+    let zones = [
+        (Zone::LeftOuter, &config.modules.left.outer, 0),
+        (Zone::LeftInner, &config.modules.left.inner, 1000),
+        (Zone::RightOuter, &config.modules.right.outer, 2000),
+        (Zone::RightInner, &config.modules.right.inner, 3000),
+        (Zone::LeftCenter, &config.modules.left.center, 4000),
+        (Zone::RightCenter, &config.modules.right.center, 5000),
+    ];
+    zones
+        .into_iter()
+        .flat_map(|(zone, modules, base)| {
+            modules
+                .iter()
+                .enumerate()
+                .map(move |(i, module)| (zone, base + i, module))
+        })
+        .collect()
+}
+
+/// Returns where the module at `index` (as numbered by
+/// [`configured_modules`]) is set in the config, e.g. `modules.left.left[2]`.
+fn config_path(index: usize) -> Option<String> {
+    let section = [
+        "modules.left.left",
+        "modules.left.right",
+        "modules.right.left",
+        "modules.right.right",
+        "modules.left.center",
+        "modules.right.center",
+    ]
+    .get(index / 1000)?;
+    Some(format!("{}[{}]", section, index % 1000))
+}
+
+/// Returns a module's configured `id`, or `{type}-{index}` without one.
+pub fn module_id(config: &ModuleConfig, index: usize) -> String {
+    config
+        .id
+        .clone()
+        .unwrap_or_else(|| format!("{}-{}", config.module_type, index))
+}
+
 /// Creates a module from configuration.
 pub fn create_module(config: &ModuleConfig, index: usize) -> Option<PositionedModule> {
     ensure_builtin_factories();
-    let id = config
-        .id
-        .clone()
-        .unwrap_or_else(|| format!("{}-{}", config.module_type, index));
+    let id = module_id(config, index);
 
     let module = {
         let factories = module_factories().lock().unwrap();
@@ -529,48 +1413,109 @@ pub fn create_module(config: &ModuleConfig, index: usize) -> Option<PositionedMo
     }
     let text_color = config.color.as_ref().and_then(|c| to_rgba(c));
 
-    // Parse popup config
-    let popup = config.popup.as_ref().map(|popup_type| {
-        let anchor = match config.popup_anchor.as_deref() {
-            Some("left") => PopupAnchor::Left,
-            Some("right") => PopupAnchor::Right,
-            _ => PopupAnchor::Center,
+    // Modules that record history graph it in a popup, unless they have
+    // a popup of their own and didn't ask for `popup = "history"`
+    let history_popup = config.history
+        && match config.popup.as_deref() {
+            Some("history") => true,
+            None => get_module(&id).is_none(),
+            Some(_) => false,
         };
+    if history_popup {
+        let title = config.label.as_deref().unwrap_or(&config.module_type);
+        register_popup_module(HistoryPopupModule::new(&id, title));
+    }
+
+    // Parse popup config. Modules that register their own popup (e.g. the
+    // launcher) open it on click without a `popup` key.
+    let popup_type = if history_popup {
+        Some(id.clone())
+    } else {
+        config
+            .popup
+            .clone()
+            .or_else(|| get_module(&id).map(|_| id.clone()))
+    };
+    let popup = popup_type.map(|popup_type| {
+        let anchor = config.popup_anchor.as_deref().map(|anchor| match anchor {
+            "left" => PopupAnchor::Left,
+            "right" => PopupAnchor::Right,
+            _ => PopupAnchor::Center,
+        });
         PopupConfig {
-            popup_type: Some(popup_type.clone()),
+            popup_type: Some(popup_type),
             width: config.popup_width.unwrap_or(0.0) as f32,
             height: config.popup_height.unwrap_or(0.0) as f32,
             max_height_percent: config.popup_max_height.unwrap_or(50.0).clamp(0.0, 100.0) as f32,
             command: config.popup_command.clone(),
             anchor,
+            hover_delay: config
+                .popup_on_hover
+                .then(|| Duration::from_millis(config.popup_hover_delay_ms)),
         }
     });
     if let Some(ref popup_cfg) = popup {
+        let target_id = popup_cfg.popup_type.clone().unwrap_or_else(|| id.clone());
+        // A popup command makes the popup script-driven
+        if let Some(ref command) = popup_cfg.command {
+            register_popup_module(ScriptPopupModule::new(&target_id, command));
+        }
         if let Ok(mut map) = popup_config_map().write() {
-            let target_id = popup_cfg.popup_type.clone().unwrap_or_else(|| id.clone());
             map.insert(target_id, popup_cfg.clone());
         }
     }
 
-    module.map(|module| {
+    // Right-clicking opens a context menu unless a command replaces it
+    let context_menu = config.context_menu && config.right_click_command.is_none();
+    if context_menu {
+        register_popup_module(ContextMenuModule::new(
+            &id,
+            config.menu.clone(),
+            config_path(index),
+        ));
+    }
+
+    // Some modules have a built-in click action (e.g. toggling a VPN)
+    let click_command = config
+        .click_command
+        .clone()
+        .or_else(|| default_click_command(config));
+
+    module.map(|mut module| {
         // Register id/type for IPC `list` command
         crate::ipc::register_module_id(module.id(), &config.module_type);
+        let toggle_state = module.toggle_state();
+        // Config toggles come back the way they were left
+        let restored = toggle_state.is_none()
+            && config.toggle
+            && crate::gpui_app::ui_state::is_toggled(module.id());
+        if restored {
+            module.on_toggle(true);
+        }
         PositionedModule {
             module,
             style,
             text_color,
-            click_command: config.click_command.clone(),
+            click_command,
             right_click_command: config.right_click_command.clone(),
+            middle_click_command: config.middle_click_command.clone(),
+            double_click_command: config.double_click_command.clone(),
             group: config.group.clone(),
             popup,
-            toggle_enabled: config.toggle,
-            toggle_active: false,
+            toggle_enabled: config.toggle || toggle_state.is_some(),
+            toggle_active: toggle_state.unwrap_or(restored),
             toggle_group: config.toggle_group.clone(),
             flex: config.flex,
             min_width: config.min_width.map(|v| v as f32),
             max_width: config.max_width.map(|v| v as f32),
             margin_left: config.margin_left.map(|v| v as f32),
             margin_right: config.margin_right.map(|v| v as f32),
+            spill: config.spill,
+            rule_colors: RuleColors::default(),
+            update_due: Some(Instant::now()),
+            power_saving: config.power_saving,
+            history: config.history,
+            context_menu,
         }
     })
 }
@@ -593,16 +1538,55 @@ fn parse_module_style(config: &ModuleConfig) -> ModuleStyle {
         border_width: config.border_width.unwrap_or(0.0) as f32,
         corner_radius: config.corner_radius.unwrap_or(0.0) as f32,
         padding: config.padding.unwrap_or(0.0) as f32,
-        critical_color: config.critical_color.as_ref().and_then(|c| to_rgba(c)),
-        warning_color: config.warning_color.as_ref().and_then(|c| to_rgba(c)),
-        critical_threshold: config.critical_threshold.unwrap_or(20.0) as f32,
-        warning_threshold: config.warning_threshold.unwrap_or(40.0) as f32,
+        color_rules: parse_color_rules(config),
         active_background: config.active_background.as_ref().and_then(|c| to_rgba(c)),
         active_border_color: config.active_border_color.as_ref().and_then(|c| to_rgba(c)),
         active_text_color: config.active_color.as_ref().and_then(|c| to_rgba(c)),
     }
 }
 
+/// Parses `color_rules`, falling back to the deprecated
+/// `critical_color`/`warning_color` pair (at or below their thresholds).
+/// Invalid rules are skipped; config validation reports them.
+fn parse_color_rules(config: &ModuleConfig) -> Vec<ColorRule> {
+    let to_rgba = |hex: &str| {
+        parse_hex_color(hex).map(|(r, g, b, a)| gpui::Rgba {
+            r: r as f32,
+            g: g as f32,
+            b: b as f32,
+            a: a as f32,
+        })
+    };
+
+    if !config.color_rules.is_empty() {
+        return config
+            .color_rules
+            .iter()
+            .filter_map(|rule| {
+                Some(ColorRule {
+                    condition: Condition::parse(&rule.when)?,
+                    color: to_rgba(&rule.color)?,
+                    target: ColorTarget::parse(&rule.apply)?,
+                })
+            })
+            .collect();
+    }
+
+    [
+        (&config.critical_color, config.critical_threshold, 20.0),
+        (&config.warning_color, config.warning_threshold, 40.0),
+    ]
+    .into_iter()
+    .filter_map(|(color, threshold, default)| {
+        Some(ColorRule {
+            condition: Condition::parse(&format!("<= {}", threshold.unwrap_or(default)))?,
+            color: to_rgba(color.as_deref()?)?,
+            target: ColorTarget::Text,
+        })
+    })
+    .collect()
+}
+
 /// Registry for managing popup-capable modules.
 pub struct ModuleRegistry {
     modules: HashMap<String, Arc<RwLock<dyn GpuiModule>>>,
@@ -688,6 +1672,27 @@ pub fn init_modules(theme: &Theme) {
     log::info!("Module registry initialized");
 }
 
+/// Adds (or replaces) a popup-capable module in the global registry.
+pub fn register_popup_module<M: GpuiModule + 'static>(module: M) {
+    let Ok(mut global) = MODULE_REGISTRY.write() else {
+        return;
+    };
+    let registry = global.get_or_insert_with(ModuleRegistry::new);
+    if let Some(prev) = registry.get(module.id()) {
+        if let Ok(mut guard) = prev.write() {
+            guard.on_module_stop();
+        }
+    }
+    let id = module.id().to_string();
+    registry.register(module);
+    if let Some(module) = registry.get(&id) {
+        if let Ok(mut guard) = module.write() {
+            guard.on_module_start();
+        }
+    }
+    log::info!("Module registry: registered popup '{}'", id);
+}
+
 /// Gets a module from the global registry.
 pub fn get_module(id: &str) -> Option<Arc<RwLock<dyn GpuiModule>>> {
     let result = MODULE_REGISTRY
@@ -704,6 +1709,7 @@ pub fn dispatch_popup_action(module_id: &str, action: PopupAction) {
             guard.on_popup_action(action);
         }
     }
+    crate::gpui_app::popup_manager::apply_hide_request();
 }
 
 pub fn dispatch_popup_event(module_id: &str, event: PopupEvent) {
@@ -712,6 +1718,7 @@ pub fn dispatch_popup_event(module_id: &str, event: PopupEvent) {
             guard.on_popup_event(event);
         }
     }
+    crate::gpui_app::popup_manager::apply_hide_request();
 }
 
 /// Gets the popup spec for a module.
@@ -724,7 +1731,9 @@ pub fn get_popup_spec(id: &str) -> Option<PopupSpec> {
         if cfg.height > 0.0 {
             spec.height = cfg.height as f64;
         }
-        spec.anchor = cfg.anchor;
+        if let Some(anchor) = cfg.anchor {
+            spec.anchor = anchor;
+        }
     }
     Some(spec)
 }
@@ -0,0 +1,369 @@
+//! MQTT module.
+//!
+//! Subscribes to topics on a broker and shows the latest payload, or the
+//! value `json_path` picks out of it. Clicking publishes `click_payload` to
+//! `click_topic`, e.g. to toggle a light. The connection is kept open and
+//! re-established with backoff when it drops.
+//!
+//! ```toml
+//! [[modules.right.right]]
+//! type = "mqtt"
+//! broker = "mqtt://homeassistant.local:1883"
+//! topics = ["zigbee2mqtt/living_room"]
+//! json_path = "$.temperature"
+//! format = "{value:.1}°"
+//! click_topic = "zigbee2mqtt/lamp/set"
+//! click_payload = '{"state": "TOGGLE"}'
+//! ```
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+
+use super::json_path::JsonPath;
+use super::template::{Template, Value};
+use super::{mark_dirty, sleep_unless_stopped, GpuiModule, MouseEvent, RuleColors};
+use crate::gpui_app::primitives::skeleton::shimmer_skeleton;
+use crate::gpui_app::theme::{LoadingState, Theme};
+use crate::system::mqtt::{Client, Options};
+
+/// How often the worker checks for clicks to publish and for shutdown.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+const RECONNECT_MIN: Duration = Duration::from_secs(2);
+const RECONNECT_MAX: Duration = Duration::from_secs(60);
+
+/// The last message received.
+#[derive(Debug, Clone, PartialEq)]
+struct Latest {
+    topic: String,
+    payload: String,
+}
+
+/// The value to show for a payload: what `path` selects from it as JSON,
+/// or the whole payload. Numbers (also in strings) become numbers so
+/// `format` can round them.
+fn extract(payload: &str, path: Option<&JsonPath>) -> Option<Value> {
+    let text = match path {
+        None => payload.trim().to_string(),
+        Some(path) => {
+            let json: serde_json::Value = serde_json::from_str(payload).ok()?;
+            match path.select(&json)? {
+                serde_json::Value::Null => return None,
+                serde_json::Value::Number(number) => return number.as_f64().map(Value::from),
+                serde_json::Value::String(text) => text.clone(),
+                other => other.to_string(),
+            }
+        }
+    };
+    if text.is_empty() {
+        return None;
+    }
+    Some(match text.parse::<f64>() {
+        Ok(number) if number.is_finite() => Value::from(number),
+        _ => Value::from(text),
+    })
+}
+
+/// Shows the latest message on MQTT topics.
+pub struct MqttModule {
+    id: String,
+    json_path: Option<JsonPath>,
+    format: Template,
+    /// Topic and payload published on click
+    click: Option<(String, String)>,
+    state: Arc<Mutex<LoadingState<Latest>>>,
+    rule_colors: RuleColors,
+    dirty: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+    outgoing: Sender<(String, String)>,
+}
+
+impl MqttModule {
+    /// Creates a new MQTT module.
+    ///
+    /// @param id - Unique module identifier
+    /// @param options - Broker address and credentials
+    /// @param topics - Topic filters to subscribe to (wildcards allowed)
+    /// @param json_path - Value to pick out of JSON payloads
+    /// @param format - Text template (None = "{value}")
+    /// @param click - Topic and payload to publish on click
+    pub fn new(
+        id: &str,
+        options: Options,
+        topics: Vec<String>,
+        json_path: Option<JsonPath>,
+        format: Option<&str>,
+        click: Option<(String, String)>,
+    ) -> Self {
+        let state = Arc::new(Mutex::new(LoadingState::Loading));
+        let dirty = Arc::new(AtomicBool::new(true));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (outgoing, outgoing_rx) = mpsc::channel();
+
+        let state_handle = Arc::clone(&state);
+        let dirty_handle = Arc::clone(&dirty);
+        let stop_handle = Arc::clone(&stop);
+        std::thread::spawn(move || {
+            run(
+                &options,
+                &topics,
+                &state_handle,
+                &dirty_handle,
+                &stop_handle,
+                &outgoing_rx,
+            )
+        });
+
+        Self {
+            id: id.to_string(),
+            json_path,
+            format: Template::parse_or_literal(format.unwrap_or("{value}")),
+            click,
+            state,
+            rule_colors: RuleColors::default(),
+            dirty,
+            stop,
+            outgoing,
+        }
+    }
+
+    fn value_of(&self, latest: &Latest) -> Option<Value> {
+        extract(&latest.payload, self.json_path.as_ref())
+    }
+
+    /// The latest message rendered through `format`.
+    fn text(&self, latest: &Latest) -> String {
+        self.format.render(&[
+            ("value", self.value_of(latest)),
+            ("topic", Some(Value::from(latest.topic.as_str()))),
+            ("payload", Some(Value::from(latest.payload.as_str()))),
+        ])
+    }
+}
+
+/// Keeps a connection to the broker until `stop` is set.
+fn run(
+    options: &Options,
+    topics: &[String],
+    state: &Mutex<LoadingState<Latest>>,
+    dirty: &AtomicBool,
+    stop: &AtomicBool,
+    outgoing: &Receiver<(String, String)>,
+) {
+    let mut backoff = RECONNECT_MIN;
+    let mut warned = false;
+    while !stop.load(Ordering::Relaxed) {
+        let connected = Client::connect(options).and_then(|mut client| {
+            client.subscribe(topics)?;
+            Ok(client)
+        });
+        let error = match connected {
+            Ok(mut client) => {
+                log::info!("mqtt: connected to {}:{}", options.host, options.port);
+                backoff = RECONNECT_MIN;
+                warned = false;
+                match session(&mut client, state, dirty, stop, outgoing) {
+                    Ok(()) => {
+                        client.disconnect();
+                        return;
+                    }
+                    Err(e) => e,
+                }
+            }
+            Err(e) => e,
+        };
+
+        if !warned {
+            log::warn!(
+                "mqtt: connection to {}:{} failed: {}",
+                options.host,
+                options.port,
+                error
+            );
+            warned = true;
+        }
+        if let Ok(mut guard) = state.lock() {
+            if !guard.is_loaded() {
+                *guard = LoadingState::Error(error.to_string());
+                mark_dirty(dirty);
+            }
+        }
+        // Clicks made while offline would fire long after the fact
+        outgoing.try_iter().for_each(drop);
+        sleep_unless_stopped(backoff, stop);
+        backoff = (backoff * 2).min(RECONNECT_MAX);
+    }
+}
+
+/// Passes messages to the module and clicks to the broker until `stop` is
+/// set (Ok) or the connection fails (Err).
+fn session(
+    client: &mut Client,
+    state: &Mutex<LoadingState<Latest>>,
+    dirty: &AtomicBool,
+    stop: &AtomicBool,
+    outgoing: &Receiver<(String, String)>,
+) -> io::Result<()> {
+    while !stop.load(Ordering::Relaxed) {
+        for (topic, payload) in outgoing.try_iter() {
+            client.publish(&topic, payload.as_bytes(), false)?;
+        }
+        let Some(message) = client.poll(POLL_INTERVAL)? else {
+            continue;
+        };
+        let latest = Latest {
+            topic: message.topic,
+            payload: String::from_utf8_lossy(&message.payload).into_owned(),
+        };
+        if let Ok(mut guard) = state.lock() {
+            if guard.as_loaded() != Some(&latest) {
+                *guard = LoadingState::Loaded(latest);
+                mark_dirty(dirty);
+            }
+        }
+    }
+    Ok(())
+}
+
+impl GpuiModule for MqttModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let state = self
+            .state
+            .lock()
+            .map(|s| s.clone())
+            .unwrap_or(LoadingState::Loading);
+        let (text, color) = match &state {
+            LoadingState::Loading => {
+                return div()
+                    .flex()
+                    .items_center()
+                    .child(shimmer_skeleton(40.0, 14.0).rounded(4.0).render(theme))
+                    .into_any_element()
+            }
+            LoadingState::Error(_) => ("--".to_string(), theme.foreground_muted),
+            LoadingState::Loaded(latest) => (
+                self.text(latest),
+                self.rule_colors.text.unwrap_or(theme.foreground),
+            ),
+        };
+
+        div()
+            .flex()
+            .items_center()
+            .text_color(color)
+            .text_size(px(theme.font_size))
+            .child(SharedString::from(text))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        self.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn is_loading(&self) -> bool {
+        self.state.lock().map(|s| s.is_loading()).unwrap_or(true)
+    }
+
+    fn display_text(&self) -> Option<String> {
+        match &*self.state.lock().ok()? {
+            LoadingState::Loading => None,
+            LoadingState::Error(_) => Some("--".to_string()),
+            LoadingState::Loaded(latest) => Some(self.text(latest)),
+        }
+    }
+
+    fn value(&self) -> Option<u8> {
+        let state = self.state.lock().ok()?;
+        match self.value_of(state.as_loaded()?)? {
+            Value::Number(number) => Some(number.round().clamp(0.0, 100.0) as u8),
+            _ => None,
+        }
+    }
+
+    fn set_rule_colors(&mut self, colors: RuleColors) {
+        self.rule_colors = colors;
+    }
+
+    fn on_mouse_event(&mut self, event: MouseEvent) -> bool {
+        if event == MouseEvent::Click {
+            if let Some(click) = &self.click {
+                let _ = self.outgoing.send(click.clone());
+            }
+        }
+        false
+    }
+}
+
+impl Drop for MqttModule {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_values_from_payloads() {
+        let path = JsonPath::parse("$.temperature").unwrap();
+        assert_eq!(
+            extract(r#"{"temperature": 21.5}"#, Some(&path)),
+            Some(Value::Number(21.5))
+        );
+        assert_eq!(
+            extract(r#"{"temperature": "19"}"#, Some(&path)),
+            Some(Value::Number(19.0))
+        );
+        assert_eq!(extract(r#"{"humidity": 40}"#, Some(&path)), None);
+        assert_eq!(extract(r#"{"temperature": null}"#, Some(&path)), None);
+        assert_eq!(extract("not json", Some(&path)), None);
+
+        let state = JsonPath::parse("$.state").unwrap();
+        assert_eq!(
+            extract(r#"{"state": "ON"}"#, Some(&state)),
+            Some(Value::from("ON"))
+        );
+        assert_eq!(
+            extract(r#"{"state": true}"#, Some(&state)),
+            Some(Value::from("true"))
+        );
+
+        assert_eq!(extract(" 42\n", None), Some(Value::Number(42.0)));
+        assert_eq!(extract("online", None), Some(Value::from("online")));
+        assert_eq!(extract("", None), None);
+    }
+
+    #[test]
+    fn reports_numeric_values_for_color_rules() {
+        let (outgoing, _) = mpsc::channel();
+        let module = MqttModule {
+            id: "mqtt".to_string(),
+            json_path: Some(JsonPath::parse("$.level").unwrap()),
+            format: Template::parse_or_literal("{value:.0}%"),
+            click: None,
+            state: Arc::new(Mutex::new(LoadingState::Loaded(Latest {
+                topic: "home/tank".to_string(),
+                payload: r#"{"level": 67.4}"#.to_string(),
+            }))),
+            rule_colors: RuleColors::default(),
+            dirty: Arc::new(AtomicBool::new(false)),
+            stop: Arc::new(AtomicBool::new(false)),
+            outgoing,
+        };
+        assert_eq!(module.value(), Some(67));
+    }
+}
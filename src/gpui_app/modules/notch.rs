@@ -0,0 +1,525 @@
+//! Notch expansion panel.
+//!
+//! Turns the notch into an interactive zone: hovering (or clicking) it drops
+//! a small panel from under the notch with a calendar peek, the current
+//! track, and any extra widgets configured under `[bar.notch]`:
+//!
+//! ```toml
+//! [bar.notch]
+//! enabled = true
+//! trigger = "hover"
+//!
+//! [[bar.notch.widgets]]
+//! type = "battery"
+//! ```
+//!
+//! In hover mode the panel collapses once the pointer has left both the
+//! notch and the panel for a short grace period. The pointer is followed
+//! with mouse-moved event monitors while the panel is open, not by polling
+//! its position. Collapsing plays the
+//! expand animation in reverse, then hides the popup from the bar's render
+//! pass (AppKit window changes must happen on the main thread).
+
+use std::cell::RefCell;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use block2::RcBlock;
+use chrono::{Datelike, Local, NaiveDate};
+use gpui::{div, prelude::*, px, Animation, AnimationExt, AnyElement, SharedString, Styled};
+use objc2::rc::Retained;
+use objc2::runtime::AnyObject;
+use objc2_app_kit::{NSEvent, NSEventMask};
+
+use super::{create_module, get_module, GpuiModule, NowPlayingModule, PopupSpec, PositionedModule};
+use crate::config::NotchConfig;
+use crate::gpui_app::popup_manager;
+use crate::gpui_app::primitives::icons;
+use crate::gpui_app::theme::Theme;
+use crate::system::providers;
+
+/// Popup module id of the notch panel.
+pub const NOTCH_ID: &str = "notch";
+
+const EXPAND_DURATION: Duration = Duration::from_millis(180);
+/// How often widgets refresh while the panel is open.
+const WIDGET_INTERVAL: Duration = Duration::from_secs(1);
+/// How long the pointer may be outside before the panel collapses.
+const COLLAPSE_GRACE: Duration = Duration::from_millis(250);
+
+const PANEL_PADDING: f32 = 12.0;
+const SECTION_GAP: f32 = 10.0;
+const CALENDAR_HEIGHT: f32 = 64.0;
+const ROW_HEIGHT: f32 = 24.0;
+const DAY_SIZE: f32 = 26.0;
+
+/// Set while the collapse animation plays.
+static COLLAPSING: AtomicBool = AtomicBool::new(false);
+/// Set once the collapse animation finished; applied by the bar.
+static COLLAPSE_REQUESTED: AtomicBool = AtomicBool::new(false);
+/// Whether the widget refresh thread is running.
+static WIDGETS_RUNNING: AtomicBool = AtomicBool::new(false);
+/// Whether the pointer was last seen outside the notch and panel.
+static POINTER_OUTSIDE: AtomicBool = AtomicBool::new(false);
+/// Bumped whenever the pointer leaves or comes back; a pending collapse
+/// only goes ahead if it hasn't changed since.
+static POINTER_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// Mouse-moved monitors installed while the panel is open (hover trigger)
+    static POINTER_MONITORS: RefCell<Vec<Retained<AnyObject>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// What expands the panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotchTrigger {
+    Hover,
+    Click,
+}
+
+impl NotchTrigger {
+    /// Parses a trigger name, defaulting to hover.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "click" => Self::Click,
+            _ => Self::Hover,
+        }
+    }
+}
+
+/// Screen area (AppKit coordinates) that keeps the panel open.
+#[derive(Debug, Clone, Copy)]
+pub struct NotchGeometry {
+    pub center_x: f64,
+    /// Top edge of the screen
+    pub top: f64,
+    pub bar_height: f64,
+    pub zone_width: f64,
+    pub panel_width: f64,
+    pub panel_height: f64,
+}
+
+/// Returns true if the pointer is over the notch zone or the panel below it.
+pub fn pointer_inside(geometry: &NotchGeometry, x: f64, y: f64) -> bool {
+    let dx = (x - geometry.center_x).abs();
+    let depth = geometry.top - y;
+    let in_zone = dx <= geometry.zone_width / 2.0 && depth <= geometry.bar_height;
+    let in_panel =
+        dx <= geometry.panel_width / 2.0 && depth <= geometry.bar_height + geometry.panel_height;
+    in_zone || in_panel
+}
+
+/// Returns the Monday-to-Sunday week containing `day`.
+pub fn week_of(day: NaiveDate) -> [NaiveDate; 7] {
+    let monday = day - chrono::Duration::days(day.weekday().num_days_from_monday() as i64);
+    std::array::from_fn(|i| monday + chrono::Duration::days(i as i64))
+}
+
+fn is_expanded() -> bool {
+    popup_manager::is_popup_visible() && popup_manager::get_current_module_id() == NOTCH_ID
+}
+
+/// Expands the panel, or cancels a pending collapse if it's already open.
+pub fn expand() {
+    if is_expanded() {
+        if COLLAPSING.swap(false, Ordering::SeqCst) {
+            popup_manager::notify_popup_needs_render(NOTCH_ID);
+        }
+        return;
+    }
+    COLLAPSING.store(false, Ordering::SeqCst);
+    COLLAPSE_REQUESTED.store(false, Ordering::SeqCst);
+    let center_x = popup_manager::panel_width() / 2.0;
+    popup_manager::record_popup_anchor(center_x, center_x);
+    popup_manager::record_popup_click(NOTCH_ID);
+    popup_manager::toggle_popup(NOTCH_ID);
+}
+
+/// Expands or collapses the panel (click trigger).
+pub fn toggle() {
+    if is_expanded() && !COLLAPSING.load(Ordering::SeqCst) {
+        collapse();
+    } else {
+        expand();
+    }
+}
+
+/// Plays the collapse animation, then asks the bar to hide the popup.
+pub fn collapse() {
+    if COLLAPSING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    popup_manager::notify_popup_needs_render(NOTCH_ID);
+    std::thread::spawn(|| {
+        std::thread::sleep(EXPAND_DURATION);
+        COLLAPSE_REQUESTED.store(true, Ordering::SeqCst);
+        crate::gpui_app::request_immediate_refresh();
+    });
+}
+
+/// Hides the panel if a collapse finished. Called from the bar on the main
+/// thread; a collapse cancelled by `expand()` is ignored.
+pub fn apply_collapse_request() {
+    if !COLLAPSE_REQUESTED.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    if COLLAPSING.swap(false, Ordering::SeqCst) && is_expanded() {
+        popup_manager::hide_popup();
+    }
+}
+
+/// Refreshes widgets while the panel is open. Exits when the panel closes.
+fn start_widget_refresh() {
+    if WIDGETS_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(|| {
+        loop {
+            std::thread::sleep(WIDGET_INTERVAL);
+            if !is_expanded() {
+                break;
+            }
+            let changed = get_module(NOTCH_ID)
+                .and_then(|m| m.write().ok().map(|mut m| m.update()))
+                .unwrap_or(false);
+            if changed {
+                popup_manager::notify_popup_needs_render(NOTCH_ID);
+            }
+        }
+        WIDGETS_RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Checks the pointer after it moved, and collapses the panel once it has
+/// been outside for `COLLAPSE_GRACE`.
+fn pointer_moved(zone_width: f64) {
+    if !is_expanded() || COLLAPSING.load(Ordering::SeqCst) {
+        return;
+    }
+    let spec = super::get_popup_spec(NOTCH_ID);
+    let geometry = NotchGeometry {
+        center_x: popup_manager::panel_width() / 2.0,
+        top: popup_manager::screen_height(),
+        bar_height: popup_manager::bar_height(),
+        zone_width,
+        panel_width: spec.as_ref().map(|s| s.width).unwrap_or(0.0),
+        panel_height: spec.as_ref().map(|s| s.height).unwrap_or(0.0),
+    };
+    let mouse = NSEvent::mouseLocation();
+    let outside = !pointer_inside(&geometry, mouse.x, mouse.y);
+    if POINTER_OUTSIDE.swap(outside, Ordering::SeqCst) == outside {
+        return;
+    }
+    let generation = POINTER_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    if outside {
+        std::thread::spawn(move || {
+            std::thread::sleep(COLLAPSE_GRACE);
+            if POINTER_GENERATION.load(Ordering::SeqCst) == generation && is_expanded() {
+                collapse();
+            }
+        });
+    }
+}
+
+/// Follows the pointer (hover trigger): a global monitor sees it move over
+/// other apps, a local one over sinew's own windows. Must be called on the
+/// main thread.
+fn start_pointer_tracking(zone_width: f64) {
+    POINTER_MONITORS.with(|monitors| {
+        let mut monitors = monitors.borrow_mut();
+        if !monitors.is_empty() {
+            return;
+        }
+        POINTER_OUTSIDE.store(false, Ordering::SeqCst);
+        let global = RcBlock::new(move |_event: NonNull<NSEvent>| pointer_moved(zone_width));
+        let local = RcBlock::new(move |event: NonNull<NSEvent>| -> *mut NSEvent {
+            pointer_moved(zone_width);
+            event.as_ptr()
+        });
+        monitors.extend(NSEvent::addGlobalMonitorForEventsMatchingMask_handler(
+            NSEventMask::MouseMoved,
+            &global,
+        ));
+        monitors.extend(unsafe {
+            NSEvent::addLocalMonitorForEventsMatchingMask_handler(NSEventMask::MouseMoved, &local)
+        });
+    });
+}
+
+/// Removes the pointer monitors. Must be called on the main thread.
+fn stop_pointer_tracking() {
+    POINTER_MONITORS.with(|monitors| {
+        for monitor in monitors.borrow_mut().drain(..) {
+            unsafe {
+                NSEvent::removeMonitor(&monitor);
+            }
+        }
+    });
+    // Cancels a pending collapse
+    POINTER_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Registers the notch panel popup when enabled on a notched display.
+///
+/// Returns the trigger the bar should wire to its notch zone.
+pub fn register(config: &NotchConfig, has_notch: bool, zone_width: f32) -> Option<NotchTrigger> {
+    if !config.enabled || !has_notch {
+        return None;
+    }
+    super::register_popup_module(NotchModule::new(config, zone_width));
+    Some(NotchTrigger::parse(&config.trigger))
+}
+
+/// Popup-only module rendering the notch panel.
+pub struct NotchModule {
+    id: String,
+    trigger: NotchTrigger,
+    width: f64,
+    zone_width: f32,
+    calendar: bool,
+    now_playing: Option<NowPlayingModule>,
+    widgets: Vec<PositionedModule>,
+}
+
+impl NotchModule {
+    /// Creates the notch panel from `[bar.notch]`.
+    ///
+    /// @param config - Notch configuration
+    /// @param zone_width - Width of the notch zone in the bar
+    pub fn new(config: &NotchConfig, zone_width: f32) -> Self {
+        let widgets = config
+            .widgets
+            .iter()
+            .enumerate()
+            .filter_map(|(i, cfg)| create_module(cfg, 9000 + i))
+            .collect();
+        Self {
+            id: NOTCH_ID.to_string(),
+            trigger: NotchTrigger::parse(&config.trigger),
+            width: config.width.clamp(220.0, 480.0),
+            zone_width,
+            calendar: config.calendar,
+            now_playing: config.now_playing.then(|| {
+                NowPlayingModule::new("notch-now-playing", 48, Vec::new(), providers::media())
+            }),
+            widgets,
+        }
+    }
+
+    fn render_calendar(&self, theme: &Theme) -> AnyElement {
+        let today = Local::now().date_naive();
+        let mut week = div().flex().flex_row().justify_between();
+        for day in week_of(today) {
+            let is_today = day == today;
+            let weekday = day.format("%a").to_string();
+            let mut number = div()
+                .w(px(DAY_SIZE))
+                .h(px(DAY_SIZE))
+                .flex()
+                .items_center()
+                .justify_center()
+                .rounded_full()
+                .child(SharedString::from(day.day().to_string()));
+            if is_today {
+                number = number.bg(theme.accent).text_color(theme.on_accent);
+            }
+            week = week.child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .items_center()
+                    .gap(px(2.0))
+                    .child(
+                        div()
+                            .text_size(px(theme.font_size - 2.0))
+                            .text_color(theme.foreground_muted)
+                            .child(SharedString::from(weekday)),
+                    )
+                    .child(number),
+            );
+        }
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .h(px(CALENDAR_HEIGHT))
+            .child(
+                div()
+                    .font_weight(gpui::FontWeight::SEMIBOLD)
+                    .child(SharedString::from(today.format("%A, %B %-d").to_string())),
+            )
+            .child(week)
+            .into_any_element()
+    }
+
+    fn render_now_playing(&self, module: &NowPlayingModule, theme: &Theme) -> AnyElement {
+        let row = div().h(px(ROW_HEIGHT)).flex().items_center();
+        if module.is_playing() {
+            row.child(module.render(theme)).into_any_element()
+        } else {
+            row.gap(px(6.0))
+                .text_color(theme.foreground_muted)
+                .child(SharedString::from(icons::music::NOTE))
+                .child("Nothing playing")
+                .into_any_element()
+        }
+    }
+}
+
+impl GpuiModule for NotchModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, _theme: &Theme) -> AnyElement {
+        div().into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        let mut changed = false;
+        if let Some(module) = self.now_playing.as_mut() {
+            changed |= module.update();
+        }
+        for widget in &mut self.widgets {
+            changed |= widget.module.update();
+        }
+        changed
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let mut sections: Vec<f32> = Vec::new();
+        if self.calendar {
+            sections.push(CALENDAR_HEIGHT);
+        }
+        if self.now_playing.is_some() {
+            sections.push(ROW_HEIGHT);
+        }
+        if !self.widgets.is_empty() {
+            sections.push(ROW_HEIGHT);
+        }
+        let gaps = sections.len().saturating_sub(1) as f32 * SECTION_GAP;
+        let height = PANEL_PADDING * 2.0 + sections.iter().sum::<f32>() + gaps;
+        Some(PopupSpec::new(self.width, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let mut content = div()
+            .flex()
+            .flex_col()
+            .gap(px(SECTION_GAP))
+            .p(px(PANEL_PADDING))
+            .text_size(px(theme.font_size))
+            .text_color(theme.foreground);
+
+        if self.calendar {
+            content = content.child(self.render_calendar(theme));
+        }
+        if let Some(module) = self.now_playing.as_ref() {
+            content = content.child(self.render_now_playing(module, theme));
+        }
+        if !self.widgets.is_empty() {
+            let mut row = div()
+                .h(px(ROW_HEIGHT))
+                .flex()
+                .flex_row()
+                .items_center()
+                .justify_center()
+                .gap(px(12.0));
+            for widget in &self.widgets {
+                row = row.child(widget.with_margins(widget.module.render(theme)));
+            }
+            content = content.child(row);
+        }
+
+        // Separate ids so each direction starts its animation from scratch
+        let collapsing = COLLAPSING.load(Ordering::SeqCst);
+        let id = if collapsing {
+            "notch-collapse"
+        } else {
+            "notch-expand"
+        };
+        Some(
+            content
+                .with_animation(
+                    id,
+                    Animation::new(EXPAND_DURATION).with_easing(ease_out_cubic),
+                    move |el, delta| {
+                        let t = if collapsing { 1.0 - delta } else { delta };
+                        el.opacity(t).mt(px(-(1.0 - t) * 16.0))
+                    },
+                )
+                .into_any_element(),
+        )
+    }
+
+    fn on_popup_event(&mut self, event: super::PopupEvent) {
+        match event {
+            super::PopupEvent::Opened => {
+                COLLAPSING.store(false, Ordering::SeqCst);
+                self.update();
+                start_widget_refresh();
+                if self.trigger == NotchTrigger::Hover {
+                    start_pointer_tracking(self.zone_width as f64);
+                }
+            }
+            super::PopupEvent::Closed => {
+                COLLAPSING.store(false, Ordering::SeqCst);
+                stop_pointer_tracking();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Cubic ease-out so the panel decelerates as it settles.
+fn ease_out_cubic(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geometry() -> NotchGeometry {
+        NotchGeometry {
+            center_x: 756.0,
+            top: 982.0,
+            bar_height: 32.0,
+            zone_width: 200.0,
+            panel_width: 360.0,
+            panel_height: 150.0,
+        }
+    }
+
+    #[test]
+    fn pointer_inside_covers_zone_and_panel() {
+        let g = geometry();
+        // In the notch zone
+        assert!(pointer_inside(&g, 756.0, 970.0));
+        // Beside the notch, within panel width but still in the bar
+        assert!(pointer_inside(&g, 900.0, 970.0));
+        // Inside the panel
+        assert!(pointer_inside(&g, 600.0, 850.0));
+        // Below the panel
+        assert!(!pointer_inside(&g, 756.0, 700.0));
+        // Off to the side
+        assert!(!pointer_inside(&g, 1000.0, 970.0));
+    }
+
+    #[test]
+    fn week_starts_on_monday() {
+        let friday = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let week = week_of(friday);
+        assert_eq!(week[0], NaiveDate::from_ymd_opt(2026, 10, 12).unwrap());
+        assert_eq!(week[6], NaiveDate::from_ymd_opt(2026, 10, 18).unwrap());
+        assert_eq!(week_of(week[0]), week);
+    }
+
+    #[test]
+    fn trigger_defaults_to_hover() {
+        assert_eq!(NotchTrigger::parse("click"), NotchTrigger::Click);
+        assert_eq!(NotchTrigger::parse("tap"), NotchTrigger::Hover);
+    }
+}
@@ -1,22 +1,139 @@
 //! Now playing module for displaying current music.
+//!
+//! Reads the current track from several sources: `system` (MediaRemote,
+//! whatever app macOS shows in Control Center), `spotify` and `music`
+//! (AppleScript). Sources are checked in the order of `sources`; the one
+//! already shown keeps the bar while it plays, otherwise the first playing
+//! source takes over. The bar shows the source's icon before the track.
+//!
+//! With `marquee`, long tracks scroll instead of being truncated.
+//!
+//! ```toml
+//! { type = "now_playing", sources = ["spotify", "music"], marquee = { width = 180 } }
+//! ```
 
-use std::process::Command;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
 
-use super::{truncate_text, GpuiModule};
+use super::{mark_dirty, truncate_text, GpuiModule};
 use crate::gpui_app::primitives::icons::music;
+use crate::gpui_app::primitives::{Marquee, MarqueeSettings};
 use crate::gpui_app::theme::Theme;
+use crate::system::providers::MediaProvider;
+
+/// Where a track comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// System-wide now playing info (MediaRemote)
+    System,
+    Spotify,
+    Music,
+}
+
+impl Source {
+    /// Checked in this order when `sources` isn't set.
+    pub const DEFAULT_ORDER: [Source; 3] = [Source::Spotify, Source::Music, Source::System];
+
+    /// Parses a source name, returning None for unknown names.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "system" => Some(Self::System),
+            "spotify" => Some(Self::Spotify),
+            "music" | "apple_music" => Some(Self::Music),
+            _ => None,
+        }
+    }
+
+    /// Name shown in the tooltip.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::System => "System",
+            Self::Spotify => "Spotify",
+            Self::Music => "Music",
+        }
+    }
+
+    pub fn icon(self) -> &'static str {
+        match self {
+            Self::System => music::NOTE,
+            Self::Spotify => music::SPOTIFY,
+            Self::Music => music::APPLE_MUSIC,
+        }
+    }
+
+    /// Asks `media` for this source's track.
+    fn fetch(self, media: &dyn MediaProvider) -> Option<Track> {
+        let track = match self {
+            Self::System => media.system_track(),
+            Self::Spotify => media.app_track("Spotify"),
+            Self::Music => media.app_track("Music"),
+        }?;
+        Some(Track {
+            title: track.title,
+            artist: track.artist,
+            playing: track.playing,
+            source: self,
+        })
+    }
+}
+
+/// A track reported by a source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Track {
+    pub title: String,
+    pub artist: String,
+    pub playing: bool,
+    pub source: Source,
+}
+
+impl Track {
+    /// "Title - Artist", or just the title when the artist is unknown.
+    pub fn display(&self) -> String {
+        if self.artist.is_empty() {
+            self.title.clone()
+        } else {
+            format!("{} - {}", self.title, self.artist)
+        }
+    }
+
+    /// Full title, artist and source on their own lines, for the tooltip.
+    pub fn details(&self) -> String {
+        let mut lines = vec![self.title.as_str()];
+        if !self.artist.is_empty() {
+            lines.push(&self.artist);
+        }
+        lines.push(self.source.name());
+        lines.join("\n")
+    }
+}
+
+/// Returns the track to show: the current source's while it plays,
+/// otherwise the first playing track in priority order.
+pub fn pick_track(
+    sources: &[Source],
+    current: Option<Source>,
+    mut fetch: impl FnMut(Source) -> Option<Track>,
+) -> Option<Track> {
+    let playing = |track: &Track| track.playing;
+    if let Some(track) = current.and_then(&mut fetch).filter(playing) {
+        return Some(track);
+    }
+    sources
+        .iter()
+        .filter(|source| Some(**source) != current)
+        .find_map(|source| fetch(*source).filter(playing))
+}
 
 /// Now playing module that displays the current track.
 #[allow(dead_code)]
 pub struct NowPlayingModule {
     id: String,
     max_length: usize,
-    text: Arc<Mutex<String>>,
+    marquee: Option<Marquee>,
+    track: Arc<Mutex<Option<Track>>>,
     is_playing: Arc<AtomicBool>,
     dirty: Arc<AtomicBool>,
     stop: Arc<AtomicBool>,
@@ -24,29 +141,43 @@ pub struct NowPlayingModule {
 
 impl NowPlayingModule {
     /// Creates a new now playing module.
-    pub fn new(id: &str, max_length: usize) -> Self {
-        let text = Arc::new(Mutex::new(String::new()));
+    ///
+    /// @param id - Unique module identifier
+    /// @param max_length - Longest track text before it's truncated
+    /// @param sources - Sources in priority order (empty = spotify, music, system)
+    /// @param media - Where tracks come from
+    pub fn new(
+        id: &str,
+        max_length: usize,
+        sources: Vec<Source>,
+        media: Arc<dyn MediaProvider>,
+    ) -> Self {
+        let sources = if sources.is_empty() {
+            Source::DEFAULT_ORDER.to_vec()
+        } else {
+            sources
+        };
+        let track = Arc::new(Mutex::new(None));
         let is_playing = Arc::new(AtomicBool::new(false));
         let dirty = Arc::new(AtomicBool::new(true));
         let stop = Arc::new(AtomicBool::new(false));
 
-        let text_handle = Arc::clone(&text);
+        let track_handle = Arc::clone(&track);
         let playing_handle = Arc::clone(&is_playing);
         let dirty_handle = Arc::clone(&dirty);
         let stop_handle = Arc::clone(&stop);
         std::thread::spawn(move || {
-            let mut last_text = String::new();
-            let mut last_playing = false;
+            let mut last: Option<Track> = None;
             while !stop_handle.load(Ordering::Relaxed) {
-                let (next_text, next_playing) = Self::fetch_status(max_length);
-                if next_text != last_text || next_playing != last_playing {
-                    if let Ok(mut guard) = text_handle.lock() {
-                        *guard = next_text.clone();
+                let current = last.as_ref().map(|t| t.source);
+                let next = pick_track(&sources, current, |source| source.fetch(&*media));
+                if next != last {
+                    if let Ok(mut guard) = track_handle.lock() {
+                        *guard = next.clone();
                     }
-                    playing_handle.store(next_playing, Ordering::Relaxed);
-                    dirty_handle.store(true, Ordering::Relaxed);
-                    last_text = next_text;
-                    last_playing = next_playing;
+                    playing_handle.store(next.is_some(), Ordering::Relaxed);
+                    mark_dirty(&dirty_handle);
+                    last = next;
                 }
                 std::thread::sleep(Duration::from_secs(1));
             }
@@ -55,29 +186,23 @@ impl NowPlayingModule {
         Self {
             id: id.to_string(),
             max_length,
-            text,
+            marquee: None,
+            track,
             is_playing,
             dirty,
             stop,
         }
     }
 
-    fn fetch_status(max_length: usize) -> (String, bool) {
-        let output = Command::new("osascript")
-            .args(["-e", r#"tell application "Music" to if player state is playing then get name of current track & " - " & artist of current track"#])
-            .output()
-            .ok()
-            .and_then(|o| String::from_utf8(o.stdout).ok());
+    /// Scrolls long tracks instead of truncating them.
+    pub fn with_marquee(mut self, settings: Option<MarqueeSettings>) -> Self {
+        self.marquee = settings.map(|settings| Marquee::new(&self.id, settings));
+        self
+    }
 
-        if let Some(text) = output {
-            let text = text.trim();
-            if text.is_empty() {
-                return (String::new(), false);
-            } else {
-                return (truncate_text(text, max_length), true);
-            }
-        }
-        (String::new(), false)
+    /// Returns true while a track is playing.
+    pub fn is_playing(&self) -> bool {
+        self.is_playing.load(Ordering::Relaxed)
     }
 }
 
@@ -87,25 +212,53 @@ impl GpuiModule for NowPlayingModule {
     }
 
     fn render(&self, theme: &Theme) -> AnyElement {
-        let text = self.text.lock().map(|t| t.clone()).unwrap_or_default();
-        if text.is_empty() {
+        let track = self.track.lock().ok().and_then(|t| t.clone());
+        let Some(track) = track else {
             // Return empty div when not playing
-            div().into_any_element()
-        } else {
-            let display = format!("{} {}", music::NOTE, text);
-            div()
-                .flex()
-                .items_center()
-                .text_color(theme.foreground)
-                .text_size(px(theme.font_size))
-                .child(SharedString::from(display))
-                .into_any_element()
-        }
+            return div().into_any_element();
+        };
+        let row = div()
+            .flex()
+            .items_center()
+            .text_color(theme.foreground)
+            .text_size(px(theme.font_size));
+        let row = match &self.marquee {
+            Some(marquee) => row
+                .gap(px(4.0))
+                .child(SharedString::from(track.source.icon()))
+                .child(marquee.render(&track.display())),
+            None => row.child(SharedString::from(format!(
+                "{} {}",
+                track.source.icon(),
+                truncate_text(&track.display(), self.max_length)
+            ))),
+        };
+        row.into_any_element()
+    }
+
+    fn is_animating(&self) -> bool {
+        self.is_playing() && self.marquee.as_ref().is_some_and(|m| m.is_animating())
     }
 
     fn update(&mut self) -> bool {
         self.dirty.swap(false, Ordering::Relaxed)
     }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        let track = self.track.lock().ok()?;
+        track.as_ref().map(Track::details)
+    }
+
+    fn display_text(&self) -> Option<String> {
+        let track = self.track.lock().ok()?;
+        track
+            .as_ref()
+            .map(|track| format!("{} {}", track.source.icon(), track.display()))
+    }
 }
 
 impl Drop for NowPlayingModule {
@@ -113,3 +266,61 @@ impl Drop for NowPlayingModule {
         self.stop.store(true, Ordering::Relaxed);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::media::PlayerTrack;
+    use crate::system::providers::mock::MockMedia;
+
+    #[test]
+    fn parses_sources() {
+        assert_eq!(Source::parse("Spotify"), Some(Source::Spotify));
+        assert_eq!(Source::parse("apple_music"), Some(Source::Music));
+        assert_eq!(Source::parse("system"), Some(Source::System));
+        assert_eq!(Source::parse("winamp"), None);
+    }
+
+    #[test]
+    fn fetches_tracks_from_the_provider() {
+        let media = MockMedia {
+            system: None,
+            apps: vec![(
+                "Spotify",
+                PlayerTrack {
+                    title: "Song".to_string(),
+                    artist: "Band".to_string(),
+                    playing: true,
+                },
+            )],
+        };
+        let track = Source::Spotify.fetch(&media).unwrap();
+        assert_eq!(track.source, Source::Spotify);
+        assert_eq!(track.display(), "Song - Band");
+        assert_eq!(track.details(), "Song\nBand\nSpotify");
+        assert!(track.playing);
+        assert_eq!(Source::Music.fetch(&media), None);
+        assert_eq!(Source::System.fetch(&media), None);
+    }
+
+    #[test]
+    fn keeps_the_current_source_while_it_plays() {
+        let track = |source, playing| Track {
+            title: "Song".to_string(),
+            artist: String::new(),
+            playing,
+            source,
+        };
+        let fetch = |source| match source {
+            Source::Spotify => Some(track(Source::Spotify, true)),
+            Source::Music => Some(track(Source::Music, false)),
+            Source::System => Some(track(Source::System, true)),
+        };
+        let order = Source::DEFAULT_ORDER;
+        let pick = |current| pick_track(&order, current, fetch).map(|t| t.source);
+        assert_eq!(pick(None), Some(Source::Spotify));
+        assert_eq!(pick(Some(Source::System)), Some(Source::System));
+        assert_eq!(pick(Some(Source::Music)), Some(Source::Spotify));
+        assert_eq!(pick_track(&[Source::Music], None, fetch), None);
+    }
+}
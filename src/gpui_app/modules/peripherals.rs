@@ -0,0 +1,290 @@
+//! Peripherals module showing the batteries of Bluetooth input devices.
+//!
+//! Shows the charge of each connected keyboard, mouse and trackpad that
+//! reports one over HID (Apple's do), yellow at the warning threshold and
+//! red at the critical one. Clicking lists each device with a charge bar.
+//!
+//! ```toml
+//! { type = "peripherals", warning_threshold = 20, critical_threshold = 10 }
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gpui::{div, prelude::*, px, AnyElement, Rgba, SharedString, Styled};
+
+use super::{mark_dirty, GpuiModule, PopupSpec};
+use crate::gpui_app::primitives::icons::peripherals as peripheral_icons;
+use crate::gpui_app::theme::Theme;
+use crate::system::metrics::{self, Metric, PeripheralBattery, PeripheralKind, Subscription};
+
+const POPUP_WIDTH: f64 = 260.0;
+const POPUP_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 24.0;
+const ROW_HEIGHT: f32 = 36.0;
+
+fn kind_icon(kind: PeripheralKind) -> &'static str {
+    match kind {
+        PeripheralKind::Keyboard => peripheral_icons::KEYBOARD,
+        PeripheralKind::Mouse => peripheral_icons::MOUSE,
+        PeripheralKind::Trackpad | PeripheralKind::Other => peripheral_icons::BLUETOOTH,
+    }
+}
+
+/// How low a battery is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Charge {
+    Normal,
+    Warning,
+    Critical,
+}
+
+impl Charge {
+    /// Levels at or below a threshold reach it.
+    fn new(level: u8, warning: f64, critical: f64) -> Self {
+        let level = f64::from(level);
+        if level <= critical {
+            Self::Critical
+        } else if level <= warning {
+            Self::Warning
+        } else {
+            Self::Normal
+        }
+    }
+
+    fn color(self, theme: &Theme, normal: Rgba) -> Rgba {
+        match self {
+            Self::Normal => normal,
+            Self::Warning => theme.warning,
+            Self::Critical => theme.destructive,
+        }
+    }
+}
+
+/// Peripheral battery module.
+pub struct PeripheralsModule {
+    id: String,
+    warning: f64,
+    critical: f64,
+    peripherals: Arc<Mutex<Vec<PeripheralBattery>>>,
+    dirty: Arc<AtomicBool>,
+    /// Shared with the popup copy; dropping the last one unsubscribes
+    _metrics: Arc<Subscription>,
+}
+
+impl PeripheralsModule {
+    /// Creates a new peripherals module.
+    ///
+    /// @param id - Unique module identifier
+    /// @param interval - Seconds between readings
+    /// @param warning - Charge in percent at which a device turns yellow
+    /// @param critical - Charge in percent at which a device turns red
+    pub fn new(id: &str, interval: u64, warning: f64, critical: f64) -> Self {
+        let peripherals = Arc::new(Mutex::new(Vec::new()));
+        let dirty = Arc::new(AtomicBool::new(true));
+
+        let peripherals_handle = Arc::clone(&peripherals);
+        let dirty_handle = Arc::clone(&dirty);
+        let subscription = metrics::subscribe(
+            Metric::Peripherals,
+            Duration::from_secs(interval.max(1)),
+            move |snapshot| {
+                if let Ok(mut guard) = peripherals_handle.lock() {
+                    if *guard != snapshot.peripherals {
+                        *guard = snapshot.peripherals.clone();
+                        mark_dirty(&dirty_handle);
+                    }
+                }
+            },
+        );
+
+        Self {
+            id: id.to_string(),
+            warning,
+            critical,
+            peripherals,
+            dirty,
+            _metrics: Arc::new(subscription),
+        }
+    }
+
+    /// Returns a second handle on the same state for the popup registry.
+    pub fn popup_handle(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            warning: self.warning,
+            critical: self.critical,
+            peripherals: Arc::clone(&self.peripherals),
+            dirty: Arc::clone(&self.dirty),
+            _metrics: Arc::clone(&self._metrics),
+        }
+    }
+
+    fn peripherals(&self) -> Vec<PeripheralBattery> {
+        self.peripherals
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+
+    fn charge(&self, peripheral: &PeripheralBattery) -> Charge {
+        Charge::new(peripheral.level, self.warning, self.critical)
+    }
+
+    fn render_row(&self, theme: &Theme, peripheral: &PeripheralBattery) -> gpui::Div {
+        let bar_color = self.charge(peripheral).color(theme, theme.accent);
+        let title = div()
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .text_color(theme.foreground_muted)
+                    .child(kind_icon(peripheral.kind)),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .overflow_hidden()
+                    .child(SharedString::from(peripheral.name.clone())),
+            )
+            .child(
+                div()
+                    .text_color(self.charge(peripheral).color(theme, theme.foreground_muted))
+                    .child(SharedString::from(format!("{}%", peripheral.level))),
+            );
+        div()
+            .h(px(ROW_HEIGHT))
+            .flex()
+            .flex_col()
+            .justify_center()
+            .gap(px(6.0))
+            .child(title)
+            .child(
+                div()
+                    .h(px(4.0))
+                    .w_full()
+                    .rounded_full()
+                    .bg(theme.surface_hover)
+                    .child(
+                        div()
+                            .h_full()
+                            .w(gpui::relative(f32::from(peripheral.level) / 100.0))
+                            .rounded_full()
+                            .bg(bar_color),
+                    ),
+            )
+    }
+}
+
+impl GpuiModule for PeripheralsModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let peripherals = self.peripherals();
+        let mut row = div()
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .text_size(px(theme.font_size))
+            .text_color(theme.foreground);
+        if peripherals.is_empty() {
+            row = row.child(
+                div()
+                    .text_color(theme.foreground_muted)
+                    .child(peripheral_icons::BLUETOOTH),
+            );
+        }
+        for peripheral in &peripherals {
+            row = row.child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(3.0))
+                    .text_color(self.charge(peripheral).color(theme, theme.foreground))
+                    .child(kind_icon(peripheral.kind))
+                    .child(SharedString::from(format!("{}%", peripheral.level))),
+            );
+        }
+        row.into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        self.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn value(&self) -> Option<u8> {
+        self.peripherals().iter().map(|p| p.level).min()
+    }
+
+    fn display_text(&self) -> Option<String> {
+        let levels: Vec<String> = self
+            .peripherals()
+            .iter()
+            .map(|peripheral| format!("{}%", peripheral.level))
+            .collect();
+        (!levels.is_empty()).then(|| levels.join(" "))
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let rows = self.peripherals().len().max(1) as f32;
+        let height = POPUP_PADDING * 2.0 + HEADER_HEIGHT + rows * ROW_HEIGHT;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let peripherals = self.peripherals();
+        let mut list = div().flex().flex_col();
+        if peripherals.is_empty() {
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .text_color(theme.foreground_muted)
+                    .child("No devices report a battery"),
+            );
+        }
+        for peripheral in &peripherals {
+            list = list.child(self.render_row(theme, peripheral));
+        }
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(
+                    div()
+                        .h(px(HEADER_HEIGHT))
+                        .font_weight(gpui::FontWeight::SEMIBOLD)
+                        .child("Peripherals"),
+                )
+                .child(list)
+                .into_any_element(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thresholds_include_their_level() {
+        assert_eq!(Charge::new(50, 20.0, 10.0), Charge::Normal);
+        assert_eq!(Charge::new(20, 20.0, 10.0), Charge::Warning);
+        assert_eq!(Charge::new(11, 20.0, 10.0), Charge::Warning);
+        assert_eq!(Charge::new(10, 20.0, 10.0), Charge::Critical);
+        assert_eq!(Charge::new(0, 20.0, 10.0), Charge::Critical);
+    }
+}
@@ -0,0 +1,460 @@
+//! Native plugins: modules compiled into a `.dylib`.
+//!
+//! At startup every `.dylib` in `~/.config/sinew/plugins` is loaded and its
+//! exported `sinew_plugin` function asked for a [`PluginVTable`]. The
+//! module type it names can then be used in the config like a built-in
+//! one; the module's table reaches `create` as JSON. Anything that exports
+//! a C function works: Swift with `@_cdecl`, Objective-C, C or Rust.
+//!
+//! Calls into one module never overlap, but needn't come from the main
+//! thread. A plugin that changes from a thread of its own calls the
+//! `request_update` function it got in `create`, and the bar asks it for
+//! `update` right away instead of waiting for `interval`.
+//!
+//! ```toml
+//! { type = "pomodoro", minutes = 25 }   # from ~/.config/sinew/plugins/pomodoro.dylib
+//! ```
+
+use std::collections::HashMap;
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+
+use super::{register_module_factory, registered_module_types, GpuiModule, MouseEvent};
+use crate::config::{parse_hex_color, ModuleConfig};
+use crate::gpui_app::theme::Theme;
+
+/// Plugin ABI version; plugins built for another one aren't loaded.
+pub const ABI_VERSION: u32 = 1;
+
+/// `button` values passed to `on_click`
+pub const BUTTON_LEFT: u32 = 0;
+pub const BUTTON_RIGHT: u32 = 1;
+pub const BUTTON_MIDDLE: u32 = 2;
+pub const BUTTON_DOUBLE: u32 = 3;
+
+/// What a plugin module shows. Every field may be null; the strings must
+/// stay valid until the next call into the module.
+#[repr(C)]
+pub struct RenderSpec {
+    /// Text after the icon
+    pub text: *const c_char,
+    /// Icon before the text
+    pub icon: *const c_char,
+    /// Text color as `#RRGGBB` or `#RRGGBBAA`
+    pub color: *const c_char,
+    /// Hover tooltip
+    pub tooltip: *const c_char,
+}
+
+/// Entry points a plugin provides, returned by its `sinew_plugin` export.
+/// Strings are NUL-terminated UTF-8.
+#[repr(C)]
+pub struct PluginVTable {
+    /// Must be [`ABI_VERSION`]
+    pub abi_version: u32,
+    /// Module type the plugin provides, e.g. `pomodoro`
+    pub type_name: *const c_char,
+    /// Creates a module from its id and config table (JSON). Returns null
+    /// if the config is unusable.
+    pub create: unsafe extern "C" fn(
+        id: *const c_char,
+        config_json: *const c_char,
+        request_update: extern "C" fn(),
+    ) -> *mut c_void,
+    /// Frees a module when it's removed, e.g. on config reload
+    pub destroy: unsafe extern "C" fn(module: *mut c_void),
+    /// Returns true if the module changed since the last call
+    pub update: unsafe extern "C" fn(module: *mut c_void) -> bool,
+    /// Returns what the module shows, asked after it changed
+    pub render: unsafe extern "C" fn(module: *mut c_void) -> RenderSpec,
+    /// Handles a click; may be null. Returns true if the module changed.
+    pub on_click: Option<unsafe extern "C" fn(module: *mut c_void, button: u32) -> bool>,
+}
+
+type EntryFn = unsafe extern "C" fn() -> *const PluginVTable;
+
+extern "C" {
+    fn dlopen(path: *const c_char, mode: i32) -> *mut c_void;
+    fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    fn dlerror() -> *const c_char;
+}
+
+/// A loaded plugin's vtable.
+#[derive(Clone, Copy)]
+struct Plugin(&'static PluginVTable);
+
+// SAFETY: the vtable is never written to, and plugin libraries are never
+// unloaded, so its pointers stay valid for the life of the app.
+unsafe impl Send for Plugin {}
+unsafe impl Sync for Plugin {}
+
+/// Loaded plugins by module type.
+static PLUGINS: OnceLock<HashMap<String, Plugin>> = OnceLock::new();
+
+/// Directory plugins are loaded from, next to the config file.
+fn plugin_dir() -> PathBuf {
+    crate::config::get_config_path()
+        .parent()
+        .map(|dir| dir.join("plugins"))
+        .unwrap_or_else(|| PathBuf::from("plugins"))
+}
+
+/// Copies a C string a plugin handed over. None for null.
+unsafe fn plugin_string(ptr: *const c_char) -> Option<String> {
+    (!ptr.is_null()).then(|| CStr::from_ptr(ptr).to_string_lossy().into_owned())
+}
+
+/// Checks a plugin's vtable, returning the module type it provides.
+fn check_vtable(vtable: &PluginVTable, taken: &[String]) -> Result<String, String> {
+    if vtable.abi_version != ABI_VERSION {
+        return Err(format!(
+            "built for plugin ABI {}, sinew uses {}",
+            vtable.abi_version, ABI_VERSION
+        ));
+    }
+    let type_name = unsafe { plugin_string(vtable.type_name) }
+        .filter(|name| !name.is_empty())
+        .ok_or("has no module type name")?;
+    if taken.contains(&type_name) {
+        return Err(format!("module type '{}' is already taken", type_name));
+    }
+    Ok(type_name)
+}
+
+/// Loads the library at `path` and returns its vtable. The library stays
+/// loaded even if the vtable is rejected later.
+fn open(path: &Path) -> Result<&'static PluginVTable, String> {
+    const RTLD_NOW: i32 = 2;
+    const RTLD_LOCAL: i32 = 4;
+    let c_path = CString::new(path.as_os_str().as_bytes()).map_err(|e| e.to_string())?;
+    unsafe {
+        let handle = dlopen(c_path.as_ptr(), RTLD_NOW | RTLD_LOCAL);
+        if handle.is_null() {
+            return Err(plugin_string(dlerror()).unwrap_or_else(|| "can't be loaded".to_string()));
+        }
+        let entry = dlsym(handle, c"sinew_plugin".as_ptr());
+        if entry.is_null() {
+            return Err("doesn't export sinew_plugin".to_string());
+        }
+        let entry = std::mem::transmute::<*mut c_void, EntryFn>(entry);
+        entry()
+            .as_ref()
+            .ok_or_else(|| "sinew_plugin returned null".to_string())
+    }
+}
+
+/// Loads the plugins and registers their module types. Later calls do
+/// nothing, so a changed plugin needs a restart.
+pub fn load_plugins() {
+    PLUGINS.get_or_init(|| {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(plugin_dir())
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "dylib"))
+            .collect();
+        paths.sort();
+
+        let mut plugins = HashMap::new();
+        let mut taken = registered_module_types();
+        for path in paths {
+            let loaded = open(&path)
+                .and_then(|vtable| check_vtable(vtable, &taken).map(|name| (name, vtable)));
+            match loaded {
+                Ok((type_name, vtable)) => {
+                    log::info!("Loaded plugin '{}' from {}", type_name, path.display());
+                    register_module_factory(&type_name, create_module);
+                    taken.push(type_name.clone());
+                    plugins.insert(type_name, Plugin(vtable));
+                }
+                Err(err) => log::warn!("Plugin {}: {}", path.display(), err),
+            }
+        }
+        plugins
+    });
+}
+
+/// Factory for every plugin module type.
+fn create_module(id: &str, config: &ModuleConfig) -> Option<Box<dyn GpuiModule>> {
+    let plugin = *PLUGINS.get()?.get(&config.module_type)?;
+    let config_json = serde_json::to_string(&config.extras).unwrap_or_else(|_| "{}".to_string());
+    let interval = config
+        .interval
+        .filter(|secs| *secs > 0.0)
+        .map(Duration::from_secs_f64)
+        .unwrap_or(Duration::from_secs(1));
+    let module = PluginModule::new(id, plugin.0, &config_json, interval)?;
+    Some(Box::new(module))
+}
+
+extern "C" fn request_update() {
+    crate::gpui_app::request_immediate_refresh();
+}
+
+/// Owned copy of a plugin's [`RenderSpec`].
+#[derive(Debug, Clone, Default)]
+struct Spec {
+    text: String,
+    icon: Option<String>,
+    color: Option<String>,
+    tooltip: Option<String>,
+}
+
+impl Spec {
+    unsafe fn copy(spec: &RenderSpec) -> Self {
+        Self {
+            text: plugin_string(spec.text).unwrap_or_default(),
+            icon: plugin_string(spec.icon),
+            color: plugin_string(spec.color),
+            tooltip: plugin_string(spec.tooltip),
+        }
+    }
+}
+
+/// A module provided by a plugin.
+pub struct PluginModule {
+    id: String,
+    vtable: &'static PluginVTable,
+    instance: *mut c_void,
+    interval: Duration,
+    spec: Spec,
+}
+
+// SAFETY: the instance is only used through `&mut self` (and in `new`),
+// so calls into it never overlap; plugins are told they may come from
+// any thread.
+unsafe impl Send for PluginModule {}
+unsafe impl Sync for PluginModule {}
+
+impl PluginModule {
+    /// Creates a module through the plugin. None if the plugin refuses.
+    ///
+    /// @param id - Unique module identifier
+    /// @param vtable - The plugin's entry points
+    /// @param config_json - The module's config table as JSON
+    /// @param interval - Time between `update` calls
+    fn new(
+        id: &str,
+        vtable: &'static PluginVTable,
+        config_json: &str,
+        interval: Duration,
+    ) -> Option<Self> {
+        let c_id = CString::new(id).ok()?;
+        let c_config = CString::new(config_json).ok()?;
+        let instance = unsafe { (vtable.create)(c_id.as_ptr(), c_config.as_ptr(), request_update) };
+        if instance.is_null() {
+            log::warn!("{}: plugin couldn't create the module", id);
+            return None;
+        }
+        let mut module = Self {
+            id: id.to_string(),
+            vtable,
+            instance,
+            interval,
+            spec: Spec::default(),
+        };
+        module.read_spec();
+        Some(module)
+    }
+
+    fn read_spec(&mut self) {
+        let spec = unsafe { (self.vtable.render)(self.instance) };
+        self.spec = unsafe { Spec::copy(&spec) };
+    }
+}
+
+impl GpuiModule for PluginModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let color = self
+            .spec
+            .color
+            .as_deref()
+            .and_then(parse_hex_color)
+            .map(|(r, g, b, a)| gpui::Rgba {
+                r: r as f32,
+                g: g as f32,
+                b: b as f32,
+                a: a as f32,
+            })
+            .unwrap_or(theme.foreground);
+        let mut row = div()
+            .flex()
+            .items_center()
+            .gap(px(4.0))
+            .text_color(color)
+            .text_size(px(theme.font_size));
+        if let Some(icon) = &self.spec.icon {
+            row = row.child(SharedString::from(icon.clone()));
+        }
+        if !self.spec.text.is_empty() {
+            row = row.child(SharedString::from(self.spec.text.clone()));
+        }
+        row.into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        let changed = unsafe { (self.vtable.update)(self.instance) };
+        if changed {
+            self.read_spec();
+        }
+        changed
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        Some(self.interval)
+    }
+
+    fn copy_text(&self) -> Option<String> {
+        (!self.spec.text.is_empty()).then(|| self.spec.text.clone())
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        self.spec.tooltip.clone()
+    }
+
+    fn on_mouse_event(&mut self, event: MouseEvent) -> bool {
+        let Some(on_click) = self.vtable.on_click else {
+            return false;
+        };
+        let button = match event {
+            MouseEvent::Click => BUTTON_LEFT,
+            MouseEvent::RightClick => BUTTON_RIGHT,
+            MouseEvent::MiddleClick => BUTTON_MIDDLE,
+            MouseEvent::DoubleClick => BUTTON_DOUBLE,
+        };
+        let changed = unsafe { on_click(self.instance, button) };
+        if changed {
+            self.read_spec();
+        }
+        changed
+    }
+}
+
+impl Drop for PluginModule {
+    fn drop(&mut self) {
+        unsafe { (self.vtable.destroy)(self.instance) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A counter plugin, written against the ABI as a plugin would be.
+    struct Counter {
+        count: u32,
+        step: u32,
+        text: CString,
+    }
+
+    unsafe extern "C" fn counter_create(
+        _id: *const c_char,
+        config_json: *const c_char,
+        _request_update: extern "C" fn(),
+    ) -> *mut c_void {
+        let config: serde_json::Value =
+            serde_json::from_slice(CStr::from_ptr(config_json).to_bytes()).unwrap();
+        let Some(step) = config.get("step").and_then(|v| v.as_u64()) else {
+            return std::ptr::null_mut();
+        };
+        Box::into_raw(Box::new(Counter {
+            count: 0,
+            step: step as u32,
+            text: CString::new("0").unwrap(),
+        }))
+        .cast()
+    }
+
+    unsafe extern "C" fn counter_destroy(module: *mut c_void) {
+        drop(Box::from_raw(module.cast::<Counter>()));
+    }
+
+    unsafe extern "C" fn counter_update(module: *mut c_void) -> bool {
+        let counter = &mut *module.cast::<Counter>();
+        counter.count += counter.step;
+        counter.text = CString::new(counter.count.to_string()).unwrap();
+        true
+    }
+
+    unsafe extern "C" fn counter_render(module: *mut c_void) -> RenderSpec {
+        let counter = &*module.cast::<Counter>();
+        RenderSpec {
+            text: counter.text.as_ptr(),
+            icon: c"#".as_ptr(),
+            color: std::ptr::null(),
+            tooltip: std::ptr::null(),
+        }
+    }
+
+    unsafe extern "C" fn counter_click(module: *mut c_void, button: u32) -> bool {
+        let counter = &mut *module.cast::<Counter>();
+        if button != BUTTON_RIGHT {
+            return false;
+        }
+        counter.count = 0;
+        counter.text = CString::new("0").unwrap();
+        true
+    }
+
+    fn counter() -> &'static PluginVTable {
+        Box::leak(Box::new(PluginVTable {
+            abi_version: ABI_VERSION,
+            type_name: c"counter".as_ptr(),
+            create: counter_create,
+            destroy: counter_destroy,
+            update: counter_update,
+            render: counter_render,
+            on_click: Some(counter_click),
+        }))
+    }
+
+    #[test]
+    fn module_goes_through_the_plugin() {
+        assert!(PluginModule::new("counter", counter(), "{}", Duration::from_secs(1)).is_none());
+
+        let mut module = PluginModule::new(
+            "counter",
+            counter(),
+            r#"{"step":2}"#,
+            Duration::from_secs(1),
+        )
+        .unwrap();
+        assert_eq!(module.spec.text, "0");
+        assert_eq!(module.spec.icon.as_deref(), Some("#"));
+
+        assert!(module.update());
+        assert!(module.update());
+        assert_eq!(module.copy_text().as_deref(), Some("4"));
+
+        assert!(!module.on_mouse_event(MouseEvent::Click));
+        assert!(module.on_mouse_event(MouseEvent::RightClick));
+        assert_eq!(module.spec.text, "0");
+    }
+
+    #[test]
+    fn rejects_other_abi_versions_and_taken_types() {
+        let taken = vec!["clock".to_string()];
+        assert_eq!(check_vtable(counter(), &taken), Ok("counter".to_string()));
+        assert!(check_vtable(counter(), &["counter".to_string()]).is_err());
+
+        let old = PluginVTable {
+            abi_version: 0,
+            type_name: c"counter".as_ptr(),
+            on_click: None,
+            ..*counter()
+        };
+        assert!(check_vtable(&old, &taken)
+            .unwrap_err()
+            .contains("plugin ABI 0"));
+    }
+}
@@ -6,16 +6,21 @@
 use std::sync::{Arc, RwLock};
 use std::time::Instant;
 
-use gpui::{div, prelude::*, px, Context, ElementId, ParentElement, Styled, Window};
+use gpui::{
+    div, prelude::*, px, Context, ElementId, FocusHandle, KeyDownEvent, ParentElement, Styled,
+    Window,
+};
 
-use super::{dispatch_popup_event, get_module, get_popup_spec, GpuiModule, PopupEvent, PopupType};
-use crate::gpui_app::theme::Theme;
+use super::{dispatch_popup_event, get_module, GpuiModule, PopupEvent, PopupKey, PopupType};
+use crate::gpui_app::primitives::{icons, Button};
+use crate::gpui_app::theme::{ButtonVariant, Theme};
 
 /// View that hosts a module's popup content.
 ///
 /// This is a generic GPUI view that:
 /// 1. Polls for the current module ID from popup_manager
-/// 2. Renders that module's popup content
+/// 2. Renders that module's popup content, or while hidden the popup
+///    prepared on hover, so opening it shows content already laid out
 /// 3. Handles window resizing based on module's popup_spec
 pub struct PopupHostView {
     theme: Theme,
@@ -25,6 +30,11 @@ pub struct PopupHostView {
     popup_type: PopupType,
     /// Debug timing for module changes
     last_change_at: Option<Instant>,
+    /// Focus target for popups that take keyboard input
+    focus_handle: FocusHandle,
+    /// Shows a fixed module in its own window instead of following the
+    /// current popup
+    detached: bool,
 }
 
 impl PopupHostView {
@@ -38,41 +48,25 @@ impl PopupHostView {
                 }
                 let should_notify = this
                     .update(cx, |view, _cx| {
-                        if view.module_id != current_id {
+                        if view.detached {
+                            return view.module_id == current_id;
+                        }
+                        // Renders of a detached popup aren't for this host
+                        if crate::gpui_app::popup_manager::is_detached(&current_id) {
+                            return false;
+                        }
+                        let hosted_id =
+                            crate::gpui_app::popup_manager::hosted_module_id(view.popup_type);
+                        if view.module_id != hosted_id {
                             log::info!(
                                 "PopupHost[{:?}] module changed: '{}' -> '{}' (notified)",
                                 view.popup_type,
                                 view.module_id,
-                                current_id
+                                hosted_id
                             );
-
-                            let new_spec = get_popup_spec(&current_id);
-                            let matches_type = new_spec
-                                .as_ref()
-                                .map(|s| s.popup_type == view.popup_type)
-                                .unwrap_or(false);
-                            let old_height = get_popup_spec(&view.module_id)
-                                .filter(|s| s.popup_type == view.popup_type)
-                                .map(|s| s.height);
-                            let new_height = new_spec
-                                .filter(|s| s.popup_type == view.popup_type)
-                                .map(|s| s.height);
-
-                            let needs_resize = match (old_height, new_height) {
-                                (Some(old), Some(new)) => (old - new).abs() > 1.0,
-                                _ => false,
-                            };
-
-                            view.module_id = if matches_type {
-                                current_id
-                            } else {
-                                String::new()
-                            };
+                            // Window resize handled by popup_manager on toggle.
+                            view.module_id = hosted_id;
                             view.last_change_at = Some(Instant::now());
-
-                            if needs_resize {
-                                // Window resize handled by popup_manager on toggle.
-                            }
                             true
                         } else {
                             false
@@ -90,20 +84,13 @@ impl PopupHostView {
         })
         .detach();
 
-        let current_id = crate::gpui_app::popup_manager::get_current_module_id();
-        let initial_matches = get_popup_spec(&current_id)
-            .map(|s| s.popup_type == popup_type)
-            .unwrap_or(false);
-
         Self {
             theme,
-            module_id: if initial_matches {
-                current_id
-            } else {
-                String::new()
-            },
+            module_id: crate::gpui_app::popup_manager::hosted_module_id(popup_type),
             popup_type,
             last_change_at: None,
+            focus_handle: cx.focus_handle(),
+            detached: false,
         }
     }
 
@@ -116,6 +103,47 @@ impl PopupHostView {
     pub fn panel(theme: Theme, cx: &mut Context<Self>) -> Self {
         Self::new(theme, PopupType::Panel, cx)
     }
+
+    /// Creates a host that keeps showing a pinned module's popup in its own
+    /// window while other popups open.
+    pub fn detached(
+        theme: Theme,
+        popup_type: PopupType,
+        module_id: String,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let mut view = Self::new(theme, popup_type, cx);
+        view.module_id = module_id;
+        view.detached = true;
+        view
+    }
+
+    /// Renders the pin toggle in the bottom-right corner.
+    fn render_pin_button(&self) -> gpui::Div {
+        let pinned = self.detached || crate::gpui_app::popup_manager::is_popup_pinned();
+        let variant = if pinned {
+            ButtonVariant::Accent
+        } else {
+            ButtonVariant::Ghost
+        };
+        let detached = self.detached.then(|| self.module_id.clone());
+        div().absolute().bottom(px(2.0)).right(px(4.0)).child(
+            Button::new("popup-pin")
+                .icon(icons::system::PIN)
+                .square(px(14.0))
+                .text_size(px(10.0))
+                .variant(variant)
+                .on_click(move || {
+                    // Unpinning a detached popup closes it
+                    if let Some(id) = detached.as_deref() {
+                        crate::gpui_app::popup_manager::close_detached(id);
+                    } else {
+                        crate::gpui_app::popup_manager::set_popup_pinned(!pinned);
+                    }
+                })
+                .render(&self.theme),
+        )
+    }
 }
 
 fn clamp_popup_height(spec_height: f64, max_height: f64) -> f64 {
@@ -123,20 +151,14 @@ fn clamp_popup_height(spec_height: f64, max_height: f64) -> f64 {
 }
 
 impl Render for PopupHostView {
-    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
         let render_start = Instant::now();
-        let current_id = crate::gpui_app::popup_manager::get_current_module_id();
-        let current_matches = get_popup_spec(&current_id)
-            .map(|s| s.popup_type == self.popup_type)
-            .unwrap_or(false);
-        let next_id = if current_matches {
-            current_id
-        } else {
-            String::new()
-        };
-        if self.module_id != next_id {
-            self.module_id = next_id;
-            self.last_change_at = Some(Instant::now());
+        if !self.detached {
+            let next_id = crate::gpui_app::popup_manager::hosted_module_id(self.popup_type);
+            if self.module_id != next_id {
+                self.module_id = next_id;
+                self.last_change_at = Some(Instant::now());
+            }
         }
         // Get the current module
         let module: Option<Arc<RwLock<dyn GpuiModule>>> = if self.module_id.is_empty() {
@@ -168,7 +190,7 @@ impl Render for PopupHostView {
             .as_ref()
             .map(|s| s.popup_type == self.popup_type)
             .unwrap_or(false);
-        if type_matches && !self.module_id.is_empty() {
+        if type_matches && !self.module_id.is_empty() && !self.detached {
             crate::gpui_app::popup_manager::mark_popup_content_rendered(
                 self.popup_type,
                 &self.module_id,
@@ -213,6 +235,34 @@ impl Render for PopupHostView {
             });
         }
 
+        // Route key presses to modules that asked for keyboard focus, once
+        // their popup is open rather than just prepared
+        let is_open = crate::gpui_app::popup_manager::is_popup_visible()
+            && crate::gpui_app::popup_manager::get_current_module_id() == self.module_id;
+        let wants_keyboard = type_matches
+            && is_open
+            && !self.detached
+            && spec.as_ref().map(|s| s.keyboard).unwrap_or(false);
+        if wants_keyboard && !self.module_id.is_empty() {
+            if !self.focus_handle.is_focused(window) {
+                window.focus(&self.focus_handle);
+            }
+            let module_id = self.module_id.clone();
+            container = container.track_focus(&self.focus_handle).on_key_down(
+                move |event: &KeyDownEvent, _window, _cx| {
+                    if event.keystroke.key == "escape" {
+                        crate::gpui_app::popup_manager::hide_popup();
+                        return;
+                    }
+                    dispatch_popup_event(
+                        &module_id,
+                        PopupEvent::Key(PopupKey::from_keystroke(&event.keystroke)),
+                    );
+                    crate::gpui_app::popup_manager::notify_popup_needs_render(&module_id);
+                },
+            );
+        }
+
         if let Some(ref spec) = spec {
             if spec.popup_type == self.popup_type {
                 let max_height = match self.popup_type {
@@ -220,7 +270,7 @@ impl Render for PopupHostView {
                     PopupType::Popup => crate::gpui_app::popup_manager::max_popup_height(),
                 };
                 let height_value = clamp_popup_height(spec.height, max_height);
-                let window_bounds = _window.bounds();
+                let window_bounds = window.bounds();
                 log::debug!(
                     "PopupHost[{:?}] container height id='{}' spec_h={:.1} max_h={:.1} final_h={:.1} win_h={:.1}",
                     self.popup_type,
@@ -236,7 +286,11 @@ impl Render for PopupHostView {
         }
 
         if let Some(content) = content {
-            container.child(content).into_any_element()
+            container
+                .relative()
+                .child(content)
+                .child(self.render_pin_button())
+                .into_any_element()
         } else {
             container.into_any_element()
         }
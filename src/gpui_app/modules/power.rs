@@ -0,0 +1,253 @@
+//! Power module for displaying battery wattage from the shared metrics
+//! service.
+//!
+//! Shows the charging wattage while plugged in and the discharge rate on
+//! battery, colored yellow and red when the battery drains faster than the
+//! warning and critical thresholds (in watts). Clicking shows the adapter
+//! and how much the system draws from it.
+//!
+//! ```toml
+//! { type = "power", warning_threshold = 15, critical_threshold = 25 }
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gpui::{div, prelude::*, px, AnyElement, Div, Rgba, SharedString, Styled};
+
+use super::{mark_dirty, GpuiModule, PopupSpec};
+use crate::gpui_app::primitives::icons::battery as battery_icons;
+use crate::gpui_app::theme::Theme;
+use crate::system::metrics::{self, Metric, PowerState, PowerStatus, Subscription};
+
+const POPUP_WIDTH: f64 = 280.0;
+const POPUP_PADDING: f32 = 12.0;
+const ROW_HEIGHT: f32 = 24.0;
+/// Status, battery, system input, adapter
+const ROWS: usize = 4;
+
+/// Formats watts with one decimal below 10 W, e.g. `8.4 W` or `45 W`.
+fn format_watts(watts: f64) -> String {
+    let watts = watts.abs();
+    if watts < 10.0 {
+        format!("{:.1} W", watts)
+    } else {
+        format!("{:.0} W", watts)
+    }
+}
+
+/// Power module that displays charge or discharge wattage.
+pub struct PowerModule {
+    id: String,
+    warning: f64,
+    critical: f64,
+    status: Arc<Mutex<Option<PowerStatus>>>,
+    dirty: Arc<AtomicBool>,
+    /// Shared with the popup copy; dropping the last one unsubscribes
+    _metrics: Arc<Subscription>,
+}
+
+impl PowerModule {
+    /// Creates a new power module.
+    ///
+    /// @param id - Unique module identifier
+    /// @param interval - Seconds between readings
+    /// @param warning - Discharge watts at which the value turns yellow
+    /// @param critical - Discharge watts at which the value turns red
+    pub fn new(id: &str, interval: u64, warning: f64, critical: f64) -> Self {
+        let status = Arc::new(Mutex::new(None));
+        let dirty = Arc::new(AtomicBool::new(true));
+
+        let status_handle = Arc::clone(&status);
+        let dirty_handle = Arc::clone(&dirty);
+        let subscription = metrics::subscribe(
+            Metric::Power,
+            Duration::from_secs(interval.max(1)),
+            move |snapshot| {
+                if let Ok(mut guard) = status_handle.lock() {
+                    if *guard != snapshot.power {
+                        *guard = snapshot.power.clone();
+                        mark_dirty(&dirty_handle);
+                    }
+                }
+            },
+        );
+
+        Self {
+            id: id.to_string(),
+            warning,
+            critical,
+            status,
+            dirty,
+            _metrics: Arc::new(subscription),
+        }
+    }
+
+    /// Returns a second handle on the same state for the popup registry.
+    pub fn popup_handle(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            warning: self.warning,
+            critical: self.critical,
+            status: Arc::clone(&self.status),
+            dirty: Arc::clone(&self.dirty),
+            _metrics: Arc::clone(&self._metrics),
+        }
+    }
+
+    fn status(&self) -> Option<PowerStatus> {
+        self.status.lock().ok().and_then(|guard| guard.clone())
+    }
+
+    /// Discharge rate colored by threshold; charging reads as success.
+    fn value_color(&self, theme: &Theme, status: &PowerStatus) -> Rgba {
+        let drain = -status.battery_watts;
+        match status.state {
+            PowerState::Charging => theme.success,
+            PowerState::OnAdapter => theme.foreground,
+            PowerState::Discharging if drain >= self.critical => theme.destructive,
+            PowerState::Discharging if drain >= self.warning => theme.warning,
+            PowerState::Discharging => theme.foreground,
+        }
+    }
+
+    fn detail_row(theme: &Theme, name: &'static str, value: String) -> Div {
+        div()
+            .h(px(ROW_HEIGHT))
+            .flex()
+            .items_center()
+            .justify_between()
+            .gap(px(8.0))
+            .child(name)
+            .child(
+                div()
+                    .text_color(theme.foreground_muted)
+                    .child(SharedString::from(value)),
+            )
+    }
+}
+
+/// Signed watts flowing in or out of the battery, or a dash before the
+/// first reading.
+fn bar_text(status: Option<&PowerStatus>) -> String {
+    let Some(status) = status else {
+        return "—".to_string();
+    };
+    // Plugged in and idle: show what the system draws instead
+    let watts = match status.state {
+        PowerState::OnAdapter => status.input_watts.unwrap_or(0.0),
+        _ => status.battery_watts,
+    };
+    let sign = match status.state {
+        PowerState::Charging => "+",
+        PowerState::Discharging => "−",
+        PowerState::OnAdapter => "",
+    };
+    format!("{}{}", sign, format_watts(watts))
+}
+
+impl GpuiModule for PowerModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let status = self.status();
+        let text = bar_text(status.as_ref());
+        let color = match &status {
+            Some(status) => self.value_color(theme, status),
+            None => theme.foreground_muted,
+        };
+
+        div()
+            .flex()
+            .items_center()
+            .gap(px(4.0))
+            .text_size(px(theme.font_size * 0.85))
+            .text_color(color)
+            .child(battery_icons::BOLT)
+            .child(SharedString::from(text))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        self.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn display_text(&self) -> Option<String> {
+        Some(bar_text(self.status().as_ref()))
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let height = POPUP_PADDING * 2.0 + ROWS as f32 * ROW_HEIGHT;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let content = match self.status() {
+            Some(status) => {
+                let state = match status.state {
+                    PowerState::Charging => "Charging",
+                    PowerState::OnAdapter => "On adapter",
+                    PowerState::Discharging => "On battery",
+                };
+                let battery = match status.state {
+                    PowerState::Charging => format!("+{}", format_watts(status.battery_watts)),
+                    PowerState::Discharging => format!("−{}", format_watts(status.battery_watts)),
+                    PowerState::OnAdapter => "Idle".to_string(),
+                };
+                let adapter = match &status.adapter {
+                    Some(adapter) => match &adapter.name {
+                        Some(name) => format!("{} ({} W)", name, adapter.watts),
+                        None => format!("{} W", adapter.watts),
+                    },
+                    None => "Not connected".to_string(),
+                };
+                div()
+                    .flex()
+                    .flex_col()
+                    .child(Self::detail_row(theme, "Status", state.to_string()))
+                    .child(Self::detail_row(theme, "Battery", battery))
+                    .child(Self::detail_row(
+                        theme,
+                        "System input",
+                        status
+                            .input_watts
+                            .map(format_watts)
+                            .unwrap_or_else(|| "—".to_string()),
+                    ))
+                    .child(Self::detail_row(theme, "Adapter", adapter))
+            }
+            None => div()
+                .h(px(ROW_HEIGHT))
+                .text_color(theme.foreground_muted)
+                .child("No battery found"),
+        };
+
+        Some(
+            div()
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(content)
+                .into_any_element(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_watts() {
+        assert_eq!(format_watts(8.44), "8.4 W");
+        assert_eq!(format_watts(-12.3), "12 W");
+        assert_eq!(format_watts(96.0), "96 W");
+    }
+}
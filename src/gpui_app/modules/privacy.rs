@@ -0,0 +1,378 @@
+//! Camera, microphone and screen capture privacy indicator.
+//!
+//! Shows a camera and/or microphone icon while either is in use, like the
+//! dots macOS draws in its own menu bar, but persistent and clickable: the
+//! popup lists what's active and, for the microphone, which processes are
+//! recording (macOS 14+; CoreMediaIO doesn't report camera owners).
+//!
+//! Screen capture (sharing or recording) is guessed with the screenshare
+//! module's process heuristic and shown as a flashing icon, so presenters and
+//! streamers get a reminder that's hard to miss.
+//!
+//! With `border = true` (default) the bar also gets a colored bottom border
+//! while anything is active.
+//!
+//! ```toml
+//! { type = "privacy", camera = true, microphone = true, screen = true, flash = true, border = true }
+//! ```
+
+use std::time::{Duration, Instant};
+
+use gpui::{div, prelude::*, px, Animation, AnimationExt, AnyElement, Rgba, SharedString, Styled};
+
+use super::{screenshare, GpuiModule, PopupSpec};
+use crate::config::{ModuleConfig, ModulesConfig};
+use crate::gpui_app::primitives::icons;
+use crate::gpui_app::theme::Theme;
+use crate::gpui_app::{camera, microphone};
+
+/// While the microphone is active, re-read its process list this often
+/// (listeners don't fire when another app joins a running device).
+const PROCESS_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+const POPUP_WIDTH: f64 = 280.0;
+const POPUP_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 24.0;
+const ROW_HEIGHT: f32 = 26.0;
+/// Default duration of one flash cycle of the screen capture icon.
+pub const DEFAULT_FLASH_MS: u64 = 1200;
+
+/// What's currently in use.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrivacyState {
+    pub camera: bool,
+    pub microphone: bool,
+    /// Processes recording audio
+    pub processes: Vec<String>,
+    /// Apps capturing the screen
+    pub screen: Vec<String>,
+}
+
+impl PrivacyState {
+    fn current() -> Self {
+        Self {
+            camera: camera::is_camera_active(),
+            microphone: microphone::is_microphone_active(),
+            processes: microphone::microphone_processes(),
+            screen: screenshare::capturing_apps(),
+        }
+    }
+}
+
+/// Color for the bar border: screen capture first, then camera over
+/// microphone, like macOS's green (camera) vs orange (microphone) dots.
+pub fn indicator_color(
+    theme: &Theme,
+    screen: bool,
+    camera: bool,
+    microphone: bool,
+) -> Option<Rgba> {
+    if screen {
+        Some(theme.destructive)
+    } else if camera {
+        Some(theme.success)
+    } else if microphone {
+        Some(theme.warning)
+    } else {
+        None
+    }
+}
+
+fn shows_border(config: &ModuleConfig) -> bool {
+    config.module_type == "privacy"
+        && config
+            .extras
+            .get("border")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true)
+}
+
+/// Returns true if any configured privacy module wants the bar border.
+pub fn border_enabled(modules: &ModulesConfig) -> bool {
+    [&modules.left, &modules.right].iter().any(|half| {
+        half.outer
+            .iter()
+            .chain(&half.inner)
+            .chain(&half.center)
+            .any(shows_border)
+    })
+}
+
+/// Bar border color while the camera or microphone is in use.
+pub fn border_color(theme: &Theme) -> Option<Rgba> {
+    indicator_color(
+        theme,
+        !screenshare::capturing_apps().is_empty(),
+        camera::is_camera_active(),
+        microphone::is_microphone_active(),
+    )
+}
+
+/// Privacy indicator showing camera, microphone and screen capture.
+pub struct PrivacyModule {
+    id: String,
+    show_camera: bool,
+    show_microphone: bool,
+    show_screen: bool,
+    /// Flash cycle of the screen capture icon (None = steady)
+    flash: Option<Duration>,
+    state: PrivacyState,
+    last_process_refresh: Instant,
+}
+
+impl PrivacyModule {
+    /// Creates a new privacy module.
+    ///
+    /// @param id - Unique module identifier
+    /// @param show_camera - Show the camera indicator
+    /// @param show_microphone - Show the microphone indicator
+    /// @param show_screen - Show the screen capture indicator
+    /// @param flash - Flash cycle of the screen capture icon (None = steady)
+    pub fn new(
+        id: &str,
+        show_camera: bool,
+        show_microphone: bool,
+        show_screen: bool,
+        flash: Option<Duration>,
+    ) -> Self {
+        if show_microphone {
+            microphone::start_monitoring();
+        }
+        if show_screen {
+            screenshare::ensure_monitor();
+        }
+        Self {
+            id: id.to_string(),
+            show_camera,
+            show_microphone,
+            show_screen,
+            flash,
+            state: PrivacyState::current(),
+            last_process_refresh: Instant::now(),
+        }
+    }
+
+    fn camera_active(&self) -> bool {
+        self.show_camera && self.state.camera
+    }
+
+    fn microphone_active(&self) -> bool {
+        self.show_microphone && self.state.microphone
+    }
+
+    fn screen_active(&self) -> bool {
+        self.show_screen && !self.state.screen.is_empty()
+    }
+}
+
+impl GpuiModule for PrivacyModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let mut row = div().flex().items_center().gap(px(4.0));
+        if self.screen_active() {
+            let icon = div()
+                .text_color(theme.destructive)
+                .child(SharedString::from(icons::system::SCREEN_SHARE));
+            row = match self.flash {
+                Some(cycle) => row.child(icon.with_animation(
+                    "privacy-screen-flash",
+                    Animation::new(cycle).repeat(),
+                    |el, delta| el.opacity(flash_opacity(delta)),
+                )),
+                None => row.child(icon),
+            };
+        }
+        if self.camera_active() {
+            row = row.child(
+                div()
+                    .text_color(theme.success)
+                    .child(SharedString::from(icons::privacy::CAMERA)),
+            );
+        }
+        if self.microphone_active() {
+            row = row.child(
+                div()
+                    .text_color(theme.warning)
+                    .child(SharedString::from(icons::privacy::MICROPHONE)),
+            );
+        }
+        row.text_size(px(theme.font_size)).into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        if self.show_microphone
+            && microphone::is_microphone_active()
+            && self.last_process_refresh.elapsed() >= PROCESS_REFRESH_INTERVAL
+        {
+            microphone::refresh();
+            self.last_process_refresh = Instant::now();
+        }
+        let next = PrivacyState::current();
+        if next == self.state {
+            return false;
+        }
+        self.state = next;
+        true
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        // Device listeners wake the bar, but not when another process joins
+        // a microphone that's already running
+        (self.show_microphone && self.state.microphone).then_some(PROCESS_REFRESH_INTERVAL)
+    }
+
+    // The popup copy isn't polled via `update()`, so it reads live state.
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let state = PrivacyState::current();
+        let rows = 3 + state.processes.len() + state.screen.len();
+        let height = POPUP_PADDING * 2.0 + HEADER_HEIGHT + rows as f32 * ROW_HEIGHT;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let state = PrivacyState::current();
+        let status_row = |icon: &'static str, label: &'static str, active: bool, color: Rgba| {
+            let (status, status_color) = if active {
+                ("In use", color)
+            } else {
+                ("Off", theme.foreground_muted)
+            };
+            div()
+                .h(px(ROW_HEIGHT))
+                .flex()
+                .items_center()
+                .gap(px(8.0))
+                .child(
+                    div()
+                        .w(px(16.0))
+                        .text_color(status_color)
+                        .child(SharedString::from(icon)),
+                )
+                .child(div().flex_1().child(label))
+                .child(div().text_color(status_color).child(status))
+        };
+
+        let mut list = div().flex().flex_col().child(status_row(
+            icons::system::SCREEN_SHARE,
+            "Screen capture",
+            !state.screen.is_empty(),
+            theme.destructive,
+        ));
+        for app in state.screen {
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .pl(px(24.0))
+                    .text_color(theme.foreground_muted)
+                    .child(SharedString::from(app)),
+            );
+        }
+        list = list
+            .child(status_row(
+                icons::privacy::CAMERA,
+                "Camera",
+                state.camera,
+                theme.success,
+            ))
+            .child(status_row(
+                icons::privacy::MICROPHONE,
+                "Microphone",
+                state.microphone,
+                theme.warning,
+            ));
+        for process in state.processes {
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .pl(px(24.0))
+                    .text_color(theme.foreground_muted)
+                    .child(SharedString::from(process)),
+            );
+        }
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(
+                    div()
+                        .h(px(HEADER_HEIGHT))
+                        .font_weight(gpui::FontWeight::SEMIBOLD)
+                        .child("Privacy"),
+                )
+                .child(list)
+                .into_any_element(),
+        )
+    }
+}
+
+/// Opacity over one flash cycle: fully visible, fading to dim and back.
+pub fn flash_opacity(delta: f32) -> f32 {
+    let wave = (std::f32::consts::TAU * delta).cos() * 0.5 + 0.5;
+    0.25 + 0.75 * wave
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn screen_then_camera_take_precedence() {
+        let theme = Theme::from_config(&crate::config::BarConfig::default());
+        assert_eq!(
+            indicator_color(&theme, true, true, true),
+            Some(theme.destructive)
+        );
+        assert_eq!(
+            indicator_color(&theme, false, true, true),
+            Some(theme.success)
+        );
+        assert_eq!(
+            indicator_color(&theme, false, false, true),
+            Some(theme.warning)
+        );
+        assert_eq!(indicator_color(&theme, false, false, false), None);
+    }
+
+    #[test]
+    fn flash_starts_and_ends_visible() {
+        assert!((flash_opacity(0.0) - 1.0).abs() < 1e-6);
+        assert!((flash_opacity(1.0) - 1.0).abs() < 1e-5);
+        assert!((flash_opacity(0.5) - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn border_follows_privacy_modules() {
+        let config: Config = toml::from_str(
+            r#"
+            [[modules.right.left]]
+            type = "privacy"
+            "#,
+        )
+        .unwrap();
+        assert!(border_enabled(&config.modules));
+
+        let config: Config = toml::from_str(
+            r#"
+            [[modules.right.left]]
+            type = "privacy"
+            border = false
+
+            [[modules.left.left]]
+            type = "clock"
+            "#,
+        )
+        .unwrap();
+        assert!(!border_enabled(&config.modules));
+    }
+}
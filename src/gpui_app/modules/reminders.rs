@@ -0,0 +1,612 @@
+//! Reminders module showing due and overdue reminders.
+//!
+//! Reads incomplete reminders due by the end of today from EventKit and
+//! shows their count, in the warning color while any are overdue. The popup
+//! lists them with a checkbox that completes the reminder in place.
+//!
+//! EventKit is driven from a single worker thread that owns the event store;
+//! the first use asks for Reminders access (granted to the app or terminal
+//! that launched sinew).
+//!
+//! ```toml
+//! { type = "reminders" }
+//! ```
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Mutex, OnceLock};
+use std::time::Duration;
+
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+
+use super::{GpuiModule, PopupAction, PopupEvent, PopupSpec};
+use crate::gpui_app::popup_manager::notify_popup_needs_render;
+use crate::gpui_app::primitives::{icons, Button};
+use crate::gpui_app::request_immediate_refresh;
+use crate::gpui_app::theme::Theme;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+const POPUP_WIDTH: f64 = 300.0;
+const POPUP_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 24.0;
+const ROW_HEIGHT: f32 = 28.0;
+const MAX_ROWS: usize = 12;
+
+/// An incomplete reminder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReminderItem {
+    /// EventKit calendar item identifier
+    pub id: String,
+    pub title: String,
+    /// Name of the reminder list
+    pub list: String,
+    pub due: Option<NaiveDateTime>,
+    /// Due on a day without a time
+    pub all_day: bool,
+}
+
+impl ReminderItem {
+    /// Returns true if the reminder is past due at `now`. All-day reminders
+    /// become overdue the day after.
+    pub fn is_overdue(&self, now: NaiveDateTime) -> bool {
+        match self.due {
+            Some(due) if self.all_day => due.date() < now.date(),
+            Some(due) => due < now,
+            None => false,
+        }
+    }
+
+    /// Short due label: the time for today, the date for other days.
+    pub fn due_label(&self, now: NaiveDateTime) -> String {
+        let Some(due) = self.due else {
+            return String::new();
+        };
+        let today = due.date() == now.date();
+        match (self.all_day, today) {
+            (true, true) => "Today".to_string(),
+            (true, false) => due.format("%b %-d").to_string(),
+            (false, true) => due.format("%H:%M").to_string(),
+            (false, false) => due.format("%b %-d %H:%M").to_string(),
+        }
+    }
+}
+
+/// Sorts reminders by due date, undated last.
+pub fn sort_reminders(items: &mut [ReminderItem]) {
+    items.sort_by(|a, b| match (a.due, b.due) {
+        (Some(a_due), Some(b_due)) => a_due.cmp(&b_due),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.title.cmp(&b.title),
+    });
+}
+
+/// End of the given day, the upper bound for "due today".
+fn end_of_day(date: NaiveDate) -> NaiveDateTime {
+    date.and_hms_opt(23, 59, 59).unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Default)]
+struct RemindersState {
+    items: Vec<ReminderItem>,
+    error: Option<String>,
+}
+
+enum Command {
+    Refresh,
+    Complete(String),
+}
+
+/// Latest reminders, written by the worker thread.
+static STATE: Mutex<RemindersState> = Mutex::new(RemindersState {
+    items: Vec::new(),
+    error: None,
+});
+
+/// Bumped whenever `STATE` changes.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn set_state(next: RemindersState) {
+    if let Ok(mut guard) = STATE.lock() {
+        if guard.items != next.items || guard.error != next.error {
+            *guard = next;
+            GENERATION.fetch_add(1, Ordering::Relaxed);
+            request_immediate_refresh();
+        }
+    }
+}
+
+fn snapshot() -> RemindersState {
+    STATE.lock().map(|s| s.clone()).unwrap_or_default()
+}
+
+/// Sends a command to the EventKit worker, starting it on first use.
+fn send(command: Command) {
+    static WORKER: OnceLock<Mutex<mpsc::Sender<Command>>> = OnceLock::new();
+    let sender = WORKER.get_or_init(|| {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || run_worker(rx));
+        Mutex::new(tx)
+    });
+    if let Ok(sender) = sender.lock() {
+        let _ = sender.send(command);
+    }
+}
+
+fn run_worker(rx: mpsc::Receiver<Command>) {
+    let Some(store) = eventkit::Store::new() else {
+        set_state(RemindersState {
+            items: Vec::new(),
+            error: Some("EventKit is unavailable".to_string()),
+        });
+        return;
+    };
+    if !store.request_access() {
+        set_state(RemindersState {
+            items: Vec::new(),
+            error: Some("No access to Reminders (System Settings › Privacy)".to_string()),
+        });
+        return;
+    }
+
+    loop {
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(Command::Complete(id)) => {
+                if !store.complete(&id) {
+                    log::warn!("reminders: failed to complete {}", id);
+                }
+            }
+            Ok(Command::Refresh) | Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+        }
+        let end = end_of_day(Local::now().date_naive());
+        match store.fetch_due(end) {
+            Some(mut items) => {
+                sort_reminders(&mut items);
+                set_state(RemindersState { items, error: None });
+            }
+            None => log::warn!("reminders: fetch timed out"),
+        }
+    }
+}
+
+/// Reminders module showing the number of due reminders.
+pub struct RemindersModule {
+    id: String,
+    generation: u64,
+}
+
+impl RemindersModule {
+    /// Creates a new reminders module.
+    ///
+    /// @param id - Unique module identifier
+    pub fn new(id: &str) -> Self {
+        send(Command::Refresh);
+        Self {
+            id: id.to_string(),
+            generation: u64::MAX,
+        }
+    }
+}
+
+/// List icon and the number of reminders.
+fn bar_text(count: usize) -> String {
+    format!("{} {}", icons::reminders::LIST, count)
+}
+
+impl GpuiModule for RemindersModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let state = snapshot();
+        let now = Local::now().naive_local();
+        let count = state.items.len();
+        let color = if state.error.is_some() || count == 0 {
+            theme.foreground_muted
+        } else if state.items.iter().any(|item| item.is_overdue(now)) {
+            theme.warning
+        } else {
+            theme.foreground
+        };
+
+        div()
+            .flex()
+            .items_center()
+            .text_color(color)
+            .text_size(px(theme.font_size))
+            .child(SharedString::from(bar_text(count)))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        let generation = GENERATION.load(Ordering::Relaxed);
+        if generation == self.generation {
+            return false;
+        }
+        self.generation = generation;
+        true
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn display_text(&self) -> Option<String> {
+        Some(bar_text(snapshot().items.len()))
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let rows = snapshot().items.len().clamp(1, MAX_ROWS);
+        let height = POPUP_PADDING * 2.0 + HEADER_HEIGHT + rows as f32 * ROW_HEIGHT;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let state = snapshot();
+        let now = Local::now().naive_local();
+        let overdue = state.items.iter().filter(|i| i.is_overdue(now)).count();
+
+        let summary = if overdue > 0 {
+            format!("{} due · {} overdue", state.items.len(), overdue)
+        } else {
+            format!("{} due", state.items.len())
+        };
+        let header = div()
+            .h(px(HEADER_HEIGHT))
+            .flex()
+            .items_center()
+            .justify_between()
+            .child(
+                div()
+                    .font_weight(gpui::FontWeight::SEMIBOLD)
+                    .child("Reminders"),
+            )
+            .child(
+                div()
+                    .text_color(theme.foreground_muted)
+                    .child(SharedString::from(summary)),
+            );
+
+        let mut list = div().flex().flex_col();
+        let empty = state.error.clone().or_else(|| {
+            state
+                .items
+                .is_empty()
+                .then(|| "Nothing due today".to_string())
+        });
+        if let Some(message) = empty {
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .text_color(theme.foreground_muted)
+                    .child(SharedString::from(message)),
+            );
+        }
+
+        for item in state.items.iter().take(MAX_ROWS) {
+            let due_color = if item.is_overdue(now) {
+                theme.destructive
+            } else {
+                theme.foreground_muted
+            };
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .child(
+                        Button::new(SharedString::from(format!("{}-{}", self.id, item.id)))
+                            .icon(icons::reminders::UNCHECKED)
+                            .square(px(20.0))
+                            .on_popup_action(
+                                self.id.clone(),
+                                PopupAction::Select {
+                                    value: item.id.clone(),
+                                },
+                            )
+                            .render(theme),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .flex()
+                            .gap(px(6.0))
+                            .overflow_hidden()
+                            .child(SharedString::from(item.title.clone()))
+                            .child(
+                                div()
+                                    .text_size(px(theme.font_size - 2.0))
+                                    .text_color(theme.foreground_subtle)
+                                    .child(SharedString::from(item.list.clone())),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .text_size(px(theme.font_size - 1.0))
+                            .text_color(due_color)
+                            .child(SharedString::from(item.due_label(now))),
+                    ),
+            );
+        }
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(header)
+                .child(list)
+                .into_any_element(),
+        )
+    }
+
+    fn on_popup_event(&mut self, event: PopupEvent) {
+        if matches!(event, PopupEvent::Opened) {
+            send(Command::Refresh);
+        }
+    }
+
+    fn on_popup_action(&mut self, action: PopupAction) {
+        if let PopupAction::Select { value } = action {
+            // Drop it from the list right away; the worker saves and refetches.
+            if let Ok(mut guard) = STATE.lock() {
+                guard.items.retain(|item| item.id != value);
+                GENERATION.fetch_add(1, Ordering::Relaxed);
+                request_immediate_refresh();
+            }
+            send(Command::Complete(value));
+            notify_popup_needs_render(&self.id);
+        }
+    }
+}
+
+// EventKit bindings via the Objective-C runtime
+mod eventkit {
+    use std::ffi::{c_char, CStr};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use block2::RcBlock;
+    use chrono::{Local, NaiveDate, NaiveDateTime};
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyClass, AnyObject, Bool};
+    use objc2::{msg_send, sel};
+    use objc2_foundation::NSString;
+
+    use super::ReminderItem;
+
+    /// EKEntityTypeReminder
+    const ENTITY_TYPE_REMINDER: usize = 1;
+    /// NSDateComponentUndefined
+    const UNDEFINED_COMPONENT: isize = isize::MAX;
+    /// How long to wait for the user to answer the access prompt.
+    const ACCESS_TIMEOUT: Duration = Duration::from_secs(120);
+    const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[link(name = "EventKit", kind = "framework")]
+    extern "C" {}
+
+    /// An `EKEventStore`. Only used from the worker thread that created it.
+    pub struct Store(Retained<AnyObject>);
+
+    impl Store {
+        pub fn new() -> Option<Self> {
+            let class = AnyClass::get(c"EKEventStore")?;
+            let store: Option<Retained<AnyObject>> = unsafe { msg_send![class, new] };
+            store.map(Self)
+        }
+
+        /// Requests Reminders access, blocking until the user answers.
+        pub fn request_access(&self) -> bool {
+            let (tx, rx) = mpsc::channel();
+            let block = RcBlock::new(move |granted: Bool, _error: *mut AnyObject| {
+                let _ = tx.send(granted.as_bool());
+            });
+            unsafe {
+                // macOS 14 split reminders access out of the generic request
+                let full_access: bool = msg_send![
+                    &*self.0,
+                    respondsToSelector: sel!(requestFullAccessToRemindersWithCompletion:)
+                ];
+                if full_access {
+                    let _: () =
+                        msg_send![&*self.0, requestFullAccessToRemindersWithCompletion: &*block];
+                } else {
+                    let _: () = msg_send![
+                        &*self.0,
+                        requestAccessToEntityType: ENTITY_TYPE_REMINDER,
+                        completion: &*block
+                    ];
+                }
+            }
+            rx.recv_timeout(ACCESS_TIMEOUT).unwrap_or(false)
+        }
+
+        /// Fetches incomplete reminders due before `end`. Returns None if
+        /// EventKit didn't answer in time.
+        pub fn fetch_due(&self, end: NaiveDateTime) -> Option<Vec<ReminderItem>> {
+            let end = end.and_local_timezone(Local).earliest()?.timestamp() as f64;
+            let date_class = AnyClass::get(c"NSDate")?;
+            let (tx, rx) = mpsc::channel();
+            let block = RcBlock::new(move |reminders: *mut AnyObject| {
+                let _ = tx.send(unsafe { read_reminders(reminders) });
+            });
+            unsafe {
+                let end_date: Retained<AnyObject> =
+                    msg_send![date_class, dateWithTimeIntervalSince1970: end];
+                let nil: *mut AnyObject = std::ptr::null_mut();
+                let predicate: Retained<AnyObject> = msg_send![
+                    &*self.0,
+                    predicateForIncompleteRemindersWithDueDateStarting: nil,
+                    ending: &*end_date,
+                    calendars: nil
+                ];
+                let _: *mut AnyObject = msg_send![
+                    &*self.0,
+                    fetchRemindersMatchingPredicate: &*predicate,
+                    completion: &*block
+                ];
+            }
+            rx.recv_timeout(FETCH_TIMEOUT).ok()
+        }
+
+        /// Marks a reminder completed and saves it.
+        pub fn complete(&self, id: &str) -> bool {
+            unsafe {
+                let id = NSString::from_str(id);
+                let item: Option<Retained<AnyObject>> =
+                    msg_send![&*self.0, calendarItemWithIdentifier: &*id];
+                let Some(item) = item else {
+                    return false;
+                };
+                let _: () = msg_send![&*item, setCompleted: Bool::YES];
+                let mut error: *mut AnyObject = std::ptr::null_mut();
+                let saved: bool = msg_send![
+                    &*self.0,
+                    saveReminder: &*item,
+                    commit: Bool::YES,
+                    error: &mut error as *mut *mut AnyObject
+                ];
+                saved
+            }
+        }
+    }
+
+    unsafe fn ns_string(obj: *mut AnyObject) -> String {
+        let Some(obj) = obj.as_ref() else {
+            return String::new();
+        };
+        let utf8: *const c_char = msg_send![obj, UTF8String];
+        if utf8.is_null() {
+            return String::new();
+        }
+        CStr::from_ptr(utf8).to_string_lossy().into_owned()
+    }
+
+    /// Reads an `NSDateComponents`; hour is undefined for all-day reminders.
+    unsafe fn read_due(components: &AnyObject) -> (Option<NaiveDateTime>, bool) {
+        let year: isize = msg_send![components, year];
+        let month: isize = msg_send![components, month];
+        let day: isize = msg_send![components, day];
+        let hour: isize = msg_send![components, hour];
+        let minute: isize = msg_send![components, minute];
+        let Some(date) = NaiveDate::from_ymd_opt(year as i32, month as u32, day as u32) else {
+            return (None, false);
+        };
+        if hour == UNDEFINED_COMPONENT {
+            return (date.and_hms_opt(0, 0, 0), true);
+        }
+        let minute = if minute == UNDEFINED_COMPONENT {
+            0
+        } else {
+            minute
+        };
+        (date.and_hms_opt(hour as u32, minute as u32, 0), false)
+    }
+
+    unsafe fn read_reminders(array: *mut AnyObject) -> Vec<ReminderItem> {
+        let Some(array) = array.as_ref() else {
+            return Vec::new();
+        };
+        let count: usize = msg_send![array, count];
+        let mut items = Vec::with_capacity(count);
+        for i in 0..count {
+            let reminder: *mut AnyObject = msg_send![array, objectAtIndex: i];
+            let Some(reminder) = reminder.as_ref() else {
+                continue;
+            };
+            let id: *mut AnyObject = msg_send![reminder, calendarItemIdentifier];
+            let title: *mut AnyObject = msg_send![reminder, title];
+            let calendar: *mut AnyObject = msg_send![reminder, calendar];
+            let list = match calendar.as_ref() {
+                Some(calendar) => {
+                    let name: *mut AnyObject = msg_send![calendar, title];
+                    ns_string(name)
+                }
+                None => String::new(),
+            };
+            let components: *mut AnyObject = msg_send![reminder, dueDateComponents];
+            let (due, all_day) = match components.as_ref() {
+                Some(components) => read_due(components),
+                None => (None, false),
+            };
+            items.push(ReminderItem {
+                id: ns_string(id),
+                title: ns_string(title),
+                list,
+                due,
+                all_day,
+            });
+        }
+        items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn item(title: &str, due: Option<&str>, all_day: bool) -> ReminderItem {
+        ReminderItem {
+            id: title.to_string(),
+            title: title.to_string(),
+            list: "Inbox".to_string(),
+            due: due.map(at),
+            all_day,
+        }
+    }
+
+    #[test]
+    fn all_day_reminders_are_overdue_the_next_day() {
+        let now = at("2026-03-10 15:00");
+        assert!(!item("today", Some("2026-03-10 00:00"), true).is_overdue(now));
+        assert!(item("yesterday", Some("2026-03-09 00:00"), true).is_overdue(now));
+        assert!(item("morning", Some("2026-03-10 09:00"), false).is_overdue(now));
+        assert!(!item("evening", Some("2026-03-10 18:00"), false).is_overdue(now));
+        assert!(!item("undated", None, false).is_overdue(now));
+    }
+
+    #[test]
+    fn due_labels() {
+        let now = at("2026-03-10 15:00");
+        assert_eq!(
+            item("a", Some("2026-03-10 00:00"), true).due_label(now),
+            "Today"
+        );
+        assert_eq!(
+            item("b", Some("2026-03-10 18:30"), false).due_label(now),
+            "18:30"
+        );
+        assert_eq!(
+            item("c", Some("2026-03-08 09:00"), false).due_label(now),
+            "Mar 8 09:00"
+        );
+        assert_eq!(
+            item("d", Some("2026-03-08 00:00"), true).due_label(now),
+            "Mar 8"
+        );
+    }
+
+    #[test]
+    fn sorts_by_due_date_undated_last() {
+        let mut items = vec![
+            item("undated", None, false),
+            item("late", Some("2026-03-10 18:00"), false),
+            item("early", Some("2026-03-09 08:00"), false),
+        ];
+        sort_reminders(&mut items);
+        let titles: Vec<_> = items.iter().map(|i| i.title.as_str()).collect();
+        assert_eq!(titles, ["early", "late", "undated"]);
+    }
+}
@@ -0,0 +1,311 @@
+//! Screen sharing indicator.
+//!
+//! macOS has no public API that says whether the screen is being captured or
+//! which app owns the purple capture indicator, so this is a heuristic: it
+//! looks for helper processes some capture tools spawn while a session is
+//! live (Zoom's `CptHost`, `screencaptureui` during a recording,
+//! `screensharingd` while someone is viewing this Mac, ...). Apps that capture
+//! from their main process, such as browsers sharing a tab or QuickTime, go
+//! unnoticed unless they're added with `processes`. Because of that the guess
+//! only feeds indicators (this module and the privacy module's screen icon),
+//! never anything that changes how the bar behaves.
+//!
+//! The bar item is empty while no helper is running and turns into a colored
+//! pill naming the app when one starts; clicking it lists every match.
+//!
+//! ```toml
+//! { type = "screenshare", processes = { "obs" = "OBS" } }
+//! ```
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+
+use super::{GpuiModule, PopupSpec};
+use crate::gpui_app::primitives::icons;
+use crate::gpui_app::request_immediate_refresh;
+use crate::gpui_app::theme::Theme;
+use crate::system::processes;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const POPUP_WIDTH: f64 = 260.0;
+const POPUP_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 24.0;
+const ROW_HEIGHT: f32 = 26.0;
+
+/// Helper processes that only run while their app is capturing the screen,
+/// and the app they belong to.
+const CAPTURE_HELPERS: &[(&str, &str)] = &[
+    ("CptHost", "Zoom"),
+    ("screencaptureui", "Screenshot"),
+    ("screensharingd", "Screen Sharing"),
+    ("TeamViewer_Desktop", "TeamViewer"),
+    ("AnyDesk_Capture", "AnyDesk"),
+];
+
+/// Running process names, refreshed by the process sampler.
+static PROCESSES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Bumped whenever the process list changes.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Subscribes to the shared process sampler (once).
+pub fn ensure_monitor() {
+    static SUBSCRIPTION: OnceLock<processes::Subscription> = OnceLock::new();
+    SUBSCRIPTION.get_or_init(|| {
+        processes::subscribe(POLL_INTERVAL, |snapshot| {
+            let next = snapshot.names();
+            if let Ok(mut guard) = PROCESSES.lock() {
+                if *guard != next {
+                    *guard = next;
+                    GENERATION.fetch_add(1, Ordering::Relaxed);
+                    request_immediate_refresh();
+                }
+            }
+        })
+    });
+}
+
+/// Guesses the apps capturing the screen from the running `processes`, in
+/// table order without duplicates. `extra` entries are checked after the
+/// built-in table.
+pub fn guess_capturing_apps(processes: &[String], extra: &[(String, String)]) -> Vec<String> {
+    let builtin = CAPTURE_HELPERS
+        .iter()
+        .map(|(process, app)| (*process, *app));
+    let extra = extra
+        .iter()
+        .map(|(process, app)| (process.as_str(), app.as_str()));
+    let mut apps: Vec<String> = Vec::new();
+    for (process, app) in builtin.chain(extra) {
+        if processes.iter().any(|p| p == process) && !apps.iter().any(|a| a == app) {
+            apps.push(app.to_string());
+        }
+    }
+    apps
+}
+
+/// Returns the apps that look like they're capturing the screen (see
+/// `guess_capturing_apps`), or nothing if the monitor hasn't been started.
+pub fn capturing_apps() -> Vec<String> {
+    PROCESSES
+        .lock()
+        .map(|processes| guess_capturing_apps(&processes, &[]))
+        .unwrap_or_default()
+}
+
+/// Parses the `processes` table (process name → app name) from config.
+pub fn parse_processes(value: Option<&toml::Value>) -> Vec<(String, String)> {
+    let Some(table) = value.and_then(|v| v.as_table()) else {
+        return Vec::new();
+    };
+    let sorted: BTreeMap<&String, &toml::Value> = table.iter().collect();
+    sorted
+        .into_iter()
+        .filter_map(|(process, app)| Some((process.clone(), app.as_str()?.to_string())))
+        .collect()
+}
+
+/// Screen sharing indicator with a popup listing the capturing apps.
+pub struct ScreenShareModule {
+    id: String,
+    extra: Vec<(String, String)>,
+    apps: Vec<String>,
+    generation: u64,
+}
+
+impl ScreenShareModule {
+    /// Creates a new screen sharing module.
+    ///
+    /// @param id - Unique module identifier
+    /// @param extra - Additional (process, app) pairs to look for
+    pub fn new(id: &str, extra: Vec<(String, String)>) -> Self {
+        ensure_monitor();
+        let mut module = Self {
+            id: id.to_string(),
+            extra,
+            apps: Vec::new(),
+            generation: u64::MAX,
+        };
+        module.refresh();
+        module
+    }
+
+    /// Re-runs detection if the process list changed. Returns true if the
+    /// set of capturing apps changed.
+    fn refresh(&mut self) -> bool {
+        let generation = GENERATION.load(Ordering::Relaxed);
+        if generation == self.generation {
+            return false;
+        }
+        self.generation = generation;
+        let next = self.current_apps();
+        if next == self.apps {
+            return false;
+        }
+        if !next.is_empty() && self.apps.is_empty() {
+            log::info!("Screen capture started: {}", next.join(", "));
+        } else if next.is_empty() {
+            log::info!("Screen capture stopped");
+        }
+        self.apps = next;
+        true
+    }
+
+    /// Guesses the capturing apps from the latest process list.
+    fn current_apps(&self) -> Vec<String> {
+        PROCESSES
+            .lock()
+            .map(|processes| guess_capturing_apps(&processes, &self.extra))
+            .unwrap_or_default()
+    }
+
+    /// Bar label: first app, plus a count of the rest.
+    fn label(&self) -> String {
+        match self.apps.as_slice() {
+            [] => String::new(),
+            [app] => app.clone(),
+            [app, rest @ ..] => format!("{} +{}", app, rest.len()),
+        }
+    }
+}
+
+impl GpuiModule for ScreenShareModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        if self.apps.is_empty() {
+            return div().into_any_element();
+        }
+        div()
+            .flex()
+            .items_center()
+            .gap(px(4.0))
+            .px(px(6.0))
+            .rounded(px(4.0))
+            .bg(theme.destructive)
+            .text_color(theme.on_destructive)
+            .text_size(px(theme.font_size))
+            .child(SharedString::from(icons::system::SCREEN_SHARE))
+            .child(SharedString::from(self.label()))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        self.refresh()
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn display_text(&self) -> Option<String> {
+        (!self.apps.is_empty()).then(|| format!("{} {}", icons::system::SCREEN_SHARE, self.label()))
+    }
+
+    // The popup copy isn't polled via `update()`, so it reads the live list.
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let rows = self.current_apps().len().max(1) as f32;
+        let height = POPUP_PADDING * 2.0 + HEADER_HEIGHT + rows * ROW_HEIGHT;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let apps = self.current_apps();
+        let header = if apps.is_empty() {
+            "Screen not shared"
+        } else {
+            "Sharing screen"
+        };
+        let mut list = div().flex().flex_col();
+        if apps.is_empty() {
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .text_color(theme.foreground_muted)
+                    .child("No app is capturing the screen"),
+            );
+        }
+        for app in apps {
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .child(
+                        div()
+                            .text_color(theme.destructive)
+                            .child(SharedString::from(icons::system::SCREEN_SHARE)),
+                    )
+                    .child(SharedString::from(app)),
+            );
+        }
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(
+                    div()
+                        .h(px(HEADER_HEIGHT))
+                        .font_weight(gpui::FontWeight::SEMIBOLD)
+                        .child(header),
+                )
+                .child(list)
+                .into_any_element(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn guesses_apps_from_helper_processes() {
+        let running = names(&["Finder", "CptHost", "zoom.us", "screencaptureui"]);
+        assert_eq!(
+            guess_capturing_apps(&running, &[]),
+            vec!["Zoom", "Screenshot"]
+        );
+        assert!(guess_capturing_apps(&names(&["Finder", "zoom.us"]), &[]).is_empty());
+    }
+
+    #[test]
+    fn guesses_extra_processes_without_duplicates() {
+        let extra = vec![
+            ("obs".to_string(), "OBS".to_string()),
+            ("CptHost".to_string(), "Zoom".to_string()),
+        ];
+        let running = names(&["obs", "CptHost"]);
+        assert_eq!(guess_capturing_apps(&running, &extra), vec!["Zoom", "OBS"]);
+    }
+
+    #[test]
+    fn parse_processes_reads_string_entries() {
+        let value: toml::Value = toml::from_str("obs = \"OBS\"\nbad = 1\nLoom = \"Loom\"").unwrap();
+        assert_eq!(
+            parse_processes(Some(&value)),
+            vec![
+                ("Loom".to_string(), "Loom".to_string()),
+                ("obs".to_string(), "OBS".to_string()),
+            ]
+        );
+        assert!(parse_processes(None).is_empty());
+    }
+}
@@ -1,4 +1,13 @@
 //! Script module for running custom commands.
+//!
+//! `format` is a template (see `template.rs`) with `{output}` (the text or
+//! JSON `label`), `{icon}`, and any other string or number field of JSON
+//! output by name. The hover tooltip shows the JSON `tooltip` field, or the
+//! whole output, with the time of the last run.
+//!
+//! ```toml
+//! { type = "script", command = "~/bin/mail.sh", format = "{icon}[ {unread}]" }
+//! ```
 
 use std::io::Read;
 use std::process::{Command, Stdio};
@@ -6,9 +15,11 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Local};
 use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
 
-use super::GpuiModule;
+use super::template::{Template, Value};
+use super::{mark_dirty, GpuiModule};
 use crate::gpui_app::theme::Theme;
 
 /// Parsed script output — plain text or structured JSON.
@@ -16,6 +27,12 @@ struct ScriptOutput {
     text: String,
     icon: Option<String>,
     color: Option<String>,
+    /// JSON `tooltip`, shown instead of the text on hover
+    tooltip: Option<String>,
+    /// Other string and number fields of JSON output, for `format`
+    fields: Vec<(String, Value)>,
+    /// When the command last finished
+    ran_at: Option<DateTime<Local>>,
 }
 
 impl ScriptOutput {
@@ -32,6 +49,24 @@ impl ScriptOutput {
                         .to_string(),
                     icon: val.get("icon").and_then(|v| v.as_str()).map(String::from),
                     color: val.get("color").and_then(|v| v.as_str()).map(String::from),
+                    tooltip: val
+                        .get("tooltip")
+                        .and_then(|v| v.as_str())
+                        .map(String::from),
+                    fields: val
+                        .as_object()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|(key, value)| {
+                            let value = match value {
+                                serde_json::Value::String(text) => Value::from(text.as_str()),
+                                serde_json::Value::Number(n) => Value::Number(n.as_f64()?),
+                                _ => return None,
+                            };
+                            Some((key.clone(), value))
+                        })
+                        .collect(),
+                    ran_at: None,
                 };
             }
         }
@@ -39,8 +74,20 @@ impl ScriptOutput {
             text: raw.to_string(),
             icon: None,
             color: None,
+            tooltip: None,
+            fields: Vec::new(),
+            ran_at: None,
         }
     }
+
+    /// Tooltip text: the JSON `tooltip` or the output, then the time of the
+    /// last run. None before the first run.
+    fn tooltip_text(&self) -> Option<String> {
+        let ran_at = self.ran_at?;
+        let body = self.tooltip.as_deref().unwrap_or(&self.text);
+        let body = if body.is_empty() { "(no output)" } else { body };
+        Some(format!("{}\nLast run {}", body, ran_at.format("%H:%M:%S")))
+    }
 }
 
 /// Script module that runs custom shell commands.
@@ -50,6 +97,7 @@ pub struct ScriptModule {
     command: String,
     interval: Duration,
     icon: Option<String>,
+    format: Option<Template>,
     output: Arc<Mutex<ScriptOutput>>,
     dirty: Arc<AtomicBool>,
     stop: Arc<AtomicBool>,
@@ -57,13 +105,21 @@ pub struct ScriptModule {
 
 impl ScriptModule {
     /// Creates a new script module.
-    pub fn new(id: &str, command: &str, interval_secs: Option<u64>, icon: Option<&str>) -> Self {
+    ///
+    /// @param id - Unique module identifier
+    /// @param command - Shell command to run
+    /// @param interval_secs - Seconds between runs (None = 60)
+    /// @param icon - Icon shown before the output
+    /// @param format - Text template (None = icon and output)
+    pub fn new(
+        id: &str,
+        command: &str,
+        interval_secs: Option<u64>,
+        icon: Option<&str>,
+        format: Option<&str>,
+    ) -> Self {
         let interval = Duration::from_secs(interval_secs.unwrap_or(60));
-        let output = Arc::new(Mutex::new(ScriptOutput {
-            text: String::new(),
-            icon: None,
-            color: None,
-        }));
+        let output = Arc::new(Mutex::new(ScriptOutput::parse("")));
         let dirty = Arc::new(AtomicBool::new(true));
         let stop = Arc::new(AtomicBool::new(false));
 
@@ -77,11 +133,12 @@ impl ScriptModule {
                 break;
             }
             let raw = Self::run_command_with_timeout(&command_handle, Duration::from_secs(10));
-            let parsed = ScriptOutput::parse(&raw);
+            let mut parsed = ScriptOutput::parse(&raw);
+            parsed.ran_at = Some(Local::now());
             if let Ok(mut guard) = output_handle.lock() {
                 *guard = parsed;
             }
-            dirty_handle.store(true, Ordering::Relaxed);
+            mark_dirty(&dirty_handle);
             std::thread::sleep(interval);
         });
 
@@ -90,13 +147,15 @@ impl ScriptModule {
             command,
             interval,
             icon: icon.map(|s| s.to_string()),
+            format: format.map(Template::parse_or_literal),
             output,
             dirty,
             stop,
         }
     }
 
-    fn run_command_with_timeout(command: &str, timeout: Duration) -> String {
+    /// Runs a shell command, returning its trimmed stdout (empty on failure or timeout).
+    pub(super) fn run_command_with_timeout(command: &str, timeout: Duration) -> String {
         let mut child = match Command::new("sh")
             .args(["-c", command])
             .stdout(Stdio::piped())
@@ -130,6 +189,35 @@ impl ScriptModule {
     }
 }
 
+impl ScriptModule {
+    /// Text shown in the bar for the script's output.
+    fn display(&self, out: &ScriptOutput) -> String {
+        // JSON icon overrides config icon
+        let effective_icon = out.icon.as_deref().or(self.icon.as_deref());
+
+        if let Some(format) = &self.format {
+            let mut values = vec![
+                ("output", Some(Value::from(out.text.as_str()))),
+                ("icon", effective_icon.map(Value::from)),
+            ];
+            values.extend(
+                out.fields
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), Some(value.clone()))),
+            );
+            format.render(&values)
+        } else if let Some(icon) = effective_icon {
+            if out.text.is_empty() {
+                icon.to_string()
+            } else {
+                format!("{} {}", icon, out.text)
+            }
+        } else {
+            out.text.clone()
+        }
+    }
+}
+
 impl GpuiModule for ScriptModule {
     fn id(&self) -> &str {
         &self.id
@@ -137,26 +225,13 @@ impl GpuiModule for ScriptModule {
 
     fn render(&self, theme: &Theme) -> AnyElement {
         let guard = self.output.lock().ok();
-        let (text, json_icon, json_color) = match guard.as_ref() {
-            Some(out) => (out.text.clone(), out.icon.clone(), out.color.clone()),
-            None => (String::new(), None, None),
+        let (display, json_color) = match guard.as_ref() {
+            Some(out) => (self.display(out), out.color.clone()),
+            None => (self.display(&ScriptOutput::parse("")), None),
         };
         // Drop the guard before building the element tree
         drop(guard);
 
-        // JSON icon overrides config icon
-        let effective_icon = json_icon.as_deref().or(self.icon.as_deref());
-
-        let display = if let Some(icon) = effective_icon {
-            if text.is_empty() {
-                icon.to_string()
-            } else {
-                format!("{} {}", icon, text)
-            }
-        } else {
-            text
-        };
-
         // JSON color overrides theme foreground
         let fg = json_color
             .as_deref()
@@ -183,6 +258,25 @@ impl GpuiModule for ScriptModule {
     fn update(&mut self) -> bool {
         self.dirty.swap(false, Ordering::Relaxed)
     }
+
+    fn copy_text(&self) -> Option<String> {
+        let output = self.output.lock().ok()?;
+        (!output.text.is_empty()).then(|| output.text.clone())
+    }
+
+    fn display_text(&self) -> Option<String> {
+        let output = self.output.lock().ok()?;
+        let text = self.display(&output);
+        (!text.is_empty()).then_some(text)
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        self.output.lock().ok()?.tooltip_text()
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
 }
 
 impl Drop for ScriptModule {
@@ -266,6 +360,50 @@ mod tests {
         assert_eq!(out.text, "ok");
     }
 
+    #[test]
+    fn parse_json_keeps_fields_for_format() {
+        let out =
+            ScriptOutput::parse(r#"{"label": "Mail", "unread": 3, "from": "Ana", "ok": true}"#);
+        let format = Template::parse("{output}[ {unread}] {from}{ok}").unwrap();
+        let values: Vec<_> = out
+            .fields
+            .iter()
+            .map(|(key, value)| (key.as_str(), Some(value.clone())))
+            .chain([("output", Some(Value::from(out.text.as_str())))])
+            .collect();
+        assert_eq!(format.render(&values), "Mail 3 Ana");
+    }
+
+    // -- ScriptOutput::tooltip_text ------------------------------------------
+
+    #[test]
+    fn tooltip_shows_output_and_last_run() {
+        use chrono::TimeZone;
+
+        let ran_at = Local.with_ymd_and_hms(2024, 5, 1, 14, 3, 9).unwrap();
+        let mut out = ScriptOutput::parse("3 unread");
+        assert_eq!(out.tooltip_text(), None);
+        out.ran_at = Some(ran_at);
+        assert_eq!(
+            out.tooltip_text().as_deref(),
+            Some("3 unread\nLast run 14:03:09")
+        );
+
+        let mut out = ScriptOutput::parse(r#"{"label": "3", "tooltip": "From Ana\nRe: lunch"}"#);
+        out.ran_at = Some(ran_at);
+        assert_eq!(
+            out.tooltip_text().as_deref(),
+            Some("From Ana\nRe: lunch\nLast run 14:03:09")
+        );
+
+        let mut out = ScriptOutput::parse("");
+        out.ran_at = Some(ran_at);
+        assert_eq!(
+            out.tooltip_text().as_deref(),
+            Some("(no output)\nLast run 14:03:09")
+        );
+    }
+
     #[test]
     fn parse_json_label_empty_string() {
         let out = ScriptOutput::parse(r#"{"label": ""}"#);
@@ -0,0 +1,817 @@
+//! Script-driven popup with a JSON layout DSL.
+//!
+//! A module configured with `popup_command` gets its popup content from that
+//! command. The command prints a JSON document describing the layout:
+//!
+//! ```json
+//! {"width": 300, "children": [
+//!   {"type": "row", "children": [
+//!     {"type": "text", "text": "Containers", "bold": true},
+//!     {"type": "badge", "text": "3 up", "color": "#a6e3a1"}
+//!   ]},
+//!   {"type": "progress", "value": 42, "label": "CPU"},
+//!   {"type": "button", "label": "Restart", "command": "docker restart web"}
+//! ]}
+//! ```
+//!
+//! The command runs off the main thread each time the popup opens, after a
+//! button command finishes, and when a button with `"action": "refresh"` is
+//! clicked.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gpui::{div, prelude::*, px, AnyElement, Rgba, SharedString, Styled};
+use serde::Deserialize;
+
+use super::{
+    dispatch_popup_action, get_popup_config, GpuiModule, PopupAction, PopupAnchor, PopupEvent,
+    PopupSpec, PopupType, ScriptModule,
+};
+use crate::gpui_app::popup_manager::{notify_popup_needs_render, request_hide_popup};
+use crate::gpui_app::primitives::{
+    Button, Chart, ChartKind, Column, ColumnAlign, FocusKind, FocusManager, FocusResult, Table,
+};
+use crate::gpui_app::theme::{ButtonVariant, Theme};
+
+const DEFAULT_POPUP_WIDTH: f64 = 280.0;
+const POPUP_PADDING: f32 = 12.0;
+const DEFAULT_GAP: f32 = 8.0;
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Top-level popup document emitted by a popup command.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+pub struct PopupLayout {
+    /// Popup width in pixels (overridden by `popup_width`)
+    #[serde(default)]
+    pub width: Option<f64>,
+    /// Popup height in pixels (estimated from content when omitted)
+    #[serde(default)]
+    pub height: Option<f64>,
+    /// Nodes stacked vertically
+    #[serde(default)]
+    pub children: Vec<LayoutNode>,
+}
+
+/// A node in the popup layout tree.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LayoutNode {
+    /// Children laid out horizontally
+    Row {
+        #[serde(default)]
+        children: Vec<LayoutNode>,
+        #[serde(default)]
+        gap: Option<f32>,
+    },
+    /// Children laid out vertically
+    Column {
+        #[serde(default)]
+        children: Vec<LayoutNode>,
+        #[serde(default)]
+        gap: Option<f32>,
+    },
+    /// A line of text
+    Text {
+        text: String,
+        #[serde(default)]
+        color: Option<String>,
+        #[serde(default)]
+        size: Option<f32>,
+        #[serde(default)]
+        bold: bool,
+        #[serde(default)]
+        muted: bool,
+    },
+    /// Small pill-shaped label
+    Badge {
+        text: String,
+        #[serde(default)]
+        color: Option<String>,
+    },
+    /// Horizontal progress bar, `value` is 0-100
+    Progress {
+        value: f32,
+        #[serde(default)]
+        label: Option<String>,
+        #[serde(default)]
+        color: Option<String>,
+    },
+    /// Clickable button that runs a command and/or a popup action
+    Button {
+        label: String,
+        #[serde(default)]
+        command: Option<String>,
+        #[serde(default)]
+        action: Option<ButtonAction>,
+    },
+    /// Table with a header row; `rows` are lists of cell strings
+    Table {
+        columns: Vec<TableColumn>,
+        #[serde(default)]
+        rows: Vec<Vec<String>>,
+        #[serde(default = "default_true")]
+        header: bool,
+        #[serde(default = "default_true")]
+        striped: bool,
+    },
+    /// Line or bar chart of `values` (oldest first)
+    Chart {
+        #[serde(default)]
+        style: ChartStyle,
+        values: Vec<f32>,
+        #[serde(default)]
+        min: Option<f32>,
+        #[serde(default)]
+        max: Option<f32>,
+        #[serde(default)]
+        height: Option<f32>,
+        #[serde(default)]
+        color: Option<String>,
+        #[serde(default)]
+        unit: Option<String>,
+        /// Labels for the start and end of the x-axis
+        #[serde(default)]
+        labels: Option<(String, String)>,
+    },
+    /// Thin horizontal rule
+    Divider,
+    /// Flexible space that pushes siblings apart
+    Spacer,
+}
+
+/// Column definition for a table node.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct TableColumn {
+    pub title: String,
+    /// "left", "center" or "right"
+    #[serde(default)]
+    pub align: Option<String>,
+    /// Fixed width in pixels (flex when omitted)
+    #[serde(default)]
+    pub width: Option<f32>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Chart style for a chart node.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartStyle {
+    #[default]
+    Line,
+    Bar,
+}
+
+/// Popup-level actions a button can trigger.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ButtonAction {
+    /// Re-run the popup command
+    Refresh,
+    /// Close the popup
+    Close,
+}
+
+impl PopupLayout {
+    /// Parses command output into a layout.
+    ///
+    /// Output that isn't a layout document is shown as plain text lines so
+    /// simple scripts still produce something readable.
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let raw = raw.trim();
+        if raw.starts_with('{') {
+            return serde_json::from_str(raw).map_err(|e| format!("Invalid popup layout: {}", e));
+        }
+        Ok(Self {
+            children: raw
+                .lines()
+                .map(|line| LayoutNode::Text {
+                    text: line.to_string(),
+                    color: None,
+                    size: None,
+                    bold: false,
+                    muted: false,
+                })
+                .collect(),
+            ..Self::default()
+        })
+    }
+
+    /// Returns the popup height, estimating it from the content if not set.
+    pub fn estimated_height(&self) -> f64 {
+        if let Some(height) = self.height {
+            return height;
+        }
+        let content = column_height(&self.children, DEFAULT_GAP);
+        (content + POPUP_PADDING * 2.0) as f64
+    }
+}
+
+impl PopupLayout {
+    /// Button nodes in tab order (depth-first, as rendered).
+    fn buttons(&self) -> Vec<&LayoutNode> {
+        fn collect<'a>(nodes: &'a [LayoutNode], out: &mut Vec<&'a LayoutNode>) {
+            for node in nodes {
+                match node {
+                    LayoutNode::Row { children, .. } | LayoutNode::Column { children, .. } => {
+                        collect(children, out)
+                    }
+                    LayoutNode::Button { .. } => out.push(node),
+                    _ => {}
+                }
+            }
+        }
+        let mut out = Vec::new();
+        collect(&self.children, &mut out);
+        out
+    }
+}
+
+impl LayoutNode {
+    /// Approximate rendered height of the node in pixels.
+    fn estimated_height(&self) -> f32 {
+        match self {
+            Self::Row { children, .. } => children
+                .iter()
+                .map(Self::estimated_height)
+                .fold(0.0, f32::max),
+            Self::Column { children, gap } => column_height(children, gap.unwrap_or(DEFAULT_GAP)),
+            Self::Text { size, .. } => size.unwrap_or(13.0) + 7.0,
+            Self::Badge { .. } => 20.0,
+            Self::Progress { label, .. } => {
+                if label.is_some() {
+                    26.0
+                } else {
+                    6.0
+                }
+            }
+            Self::Button { .. } => 28.0,
+            Self::Table { .. } => self.to_table().map(|t| t.height()).unwrap_or(0.0),
+            Self::Chart { .. } => self.to_chart(0.0).map(|c| c.height()).unwrap_or(0.0),
+            Self::Divider => 1.0,
+            Self::Spacer => 0.0,
+        }
+    }
+
+    /// Builds the chart primitive for a chart node.
+    fn to_chart(&self, width: f32) -> Option<Chart> {
+        let Self::Chart {
+            style,
+            values,
+            min,
+            max,
+            height,
+            color,
+            unit,
+            labels,
+        } = self
+        else {
+            return None;
+        };
+        let kind = match style {
+            ChartStyle::Line => ChartKind::Line,
+            ChartStyle::Bar => ChartKind::Bar,
+        };
+        let mut chart = Chart::new(kind, values.clone())
+            .size(width, height.unwrap_or(60.0))
+            .range(*min, *max);
+        if let Some(color) = color.as_deref().and_then(parse_color) {
+            chart = chart.color(color);
+        }
+        if let Some(unit) = unit {
+            chart = chart.unit(unit.clone());
+        }
+        if let Some((start, end)) = labels {
+            chart = chart.x_labels(start.clone(), end.clone());
+        }
+        Some(chart)
+    }
+
+    /// Builds the table primitive for a table node.
+    fn to_table(&self) -> Option<Table> {
+        let Self::Table {
+            columns,
+            rows,
+            header,
+            striped,
+        } = self
+        else {
+            return None;
+        };
+        let columns = columns
+            .iter()
+            .map(|c| {
+                let column = Column::new(c.title.clone())
+                    .align(ColumnAlign::parse(c.align.as_deref().unwrap_or("left")));
+                match c.width {
+                    Some(width) => column.fixed(width),
+                    None => column,
+                }
+            })
+            .collect();
+        Some(
+            Table::new(columns)
+                .rows(rows.iter().map(|r| r.iter().cloned()))
+                .header(*header)
+                .striped(*striped),
+        )
+    }
+}
+
+fn column_height(children: &[LayoutNode], gap: f32) -> f32 {
+    let heights: f32 = children.iter().map(LayoutNode::estimated_height).sum();
+    heights + gap * children.len().saturating_sub(1) as f32
+}
+
+fn parse_color(hex: &str) -> Option<Rgba> {
+    let (r, g, b, a) = crate::config::parse_hex_color(hex)?;
+    Some(Rgba {
+        r: r as f32,
+        g: g as f32,
+        b: b as f32,
+        a: a as f32,
+    })
+}
+
+/// Latest popup content produced by the command.
+enum PopupContent {
+    Loading,
+    Ready(PopupLayout),
+    Error(String),
+}
+
+/// Popup module whose content is rendered from a command's JSON output.
+pub struct ScriptPopupModule {
+    id: String,
+    command: String,
+    content: Arc<Mutex<PopupContent>>,
+    running: Arc<AtomicBool>,
+    focus: FocusManager,
+}
+
+impl ScriptPopupModule {
+    /// Creates a popup module that renders the output of `command`.
+    pub fn new(id: &str, command: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            command: command.to_string(),
+            content: Arc::new(Mutex::new(PopupContent::Loading)),
+            running: Arc::new(AtomicBool::new(false)),
+            focus: FocusManager::new(),
+        }
+    }
+
+    /// Runs the popup command in the background and re-renders when done.
+    fn refresh(&self) {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let id = self.id.clone();
+        let command = self.command.clone();
+        let content = Arc::clone(&self.content);
+        let running = Arc::clone(&self.running);
+        std::thread::spawn(move || {
+            let raw = ScriptModule::run_command_with_timeout(&command, COMMAND_TIMEOUT);
+            let parsed = match PopupLayout::parse(&raw) {
+                Ok(layout) => PopupContent::Ready(layout),
+                Err(err) => {
+                    log::warn!("Popup '{}': {}", id, err);
+                    PopupContent::Error(err)
+                }
+            };
+            if let Ok(mut guard) = content.lock() {
+                *guard = parsed;
+            }
+            running.store(false, Ordering::SeqCst);
+            notify_popup_needs_render(&id);
+        });
+    }
+
+    /// Runs a button command, then refreshes the popup.
+    fn run_button_command(&self, command: &str) {
+        let id = self.id.clone();
+        let command = command.to_string();
+        std::thread::spawn(move || {
+            let _ = ScriptModule::run_command_with_timeout(&command, COMMAND_TIMEOUT);
+            dispatch_popup_action(&id, PopupAction::Refresh);
+        });
+    }
+
+    /// Element id of the `index`th button in tab order; also its focus id.
+    /// Labels can repeat, so the id uses the position.
+    fn button_id(&self, index: usize) -> String {
+        format!("{}-button-{}", self.id, index)
+    }
+
+    /// Command and action of the button with the given id.
+    fn button(&self, button_id: &str) -> Option<(Option<String>, Option<ButtonAction>)> {
+        match self.content.lock().ok().as_deref() {
+            Some(PopupContent::Ready(layout)) => {
+                let mut buttons = layout.buttons().into_iter().enumerate();
+                buttons.find_map(|(index, node)| match node {
+                    LayoutNode::Button {
+                        command, action, ..
+                    } if self.button_id(index) == button_id => Some((command.clone(), *action)),
+                    _ => None,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Runs the command and/or action of the button with the given id.
+    fn activate_button(&self, button_id: &str) {
+        let Some((command, action)) = self.button(button_id) else {
+            return;
+        };
+        if let Some(command) = command {
+            self.run_button_command(&command);
+        }
+        match action {
+            Some(ButtonAction::Refresh) => self.refresh(),
+            Some(ButtonAction::Close) => request_hide_popup(),
+            None => {}
+        }
+    }
+
+    /// Width available to popup content (charts need a fixed pixel width).
+    fn content_width(&self, layout: &PopupLayout) -> f32 {
+        let width = get_popup_config(&self.id)
+            .map(|cfg| cfg.width as f64)
+            .filter(|w| *w > 0.0)
+            .or(layout.width)
+            .unwrap_or(DEFAULT_POPUP_WIDTH);
+        (width as f32 - POPUP_PADDING * 2.0).max(0.0)
+    }
+
+    /// Renders a node; `buttons` counts the buttons rendered before it.
+    fn render_node(
+        &self,
+        node: &LayoutNode,
+        theme: &Theme,
+        width: f32,
+        buttons: &mut usize,
+    ) -> AnyElement {
+        match node {
+            LayoutNode::Row { children, gap } => div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap(px(gap.unwrap_or(DEFAULT_GAP)))
+                .children(
+                    children
+                        .iter()
+                        .map(|c| self.render_node(c, theme, width, buttons)),
+                )
+                .into_any_element(),
+            LayoutNode::Column { children, gap } => div()
+                .flex()
+                .flex_col()
+                .gap(px(gap.unwrap_or(DEFAULT_GAP)))
+                .children(
+                    children
+                        .iter()
+                        .map(|c| self.render_node(c, theme, width, buttons)),
+                )
+                .into_any_element(),
+            LayoutNode::Text {
+                text,
+                color,
+                size,
+                bold,
+                muted,
+            } => {
+                let default_color = if *muted {
+                    theme.foreground_muted
+                } else {
+                    theme.foreground
+                };
+                let mut el = div()
+                    .text_size(px(size.unwrap_or(theme.font_size)))
+                    .text_color(
+                        color
+                            .as_deref()
+                            .and_then(parse_color)
+                            .unwrap_or(default_color),
+                    )
+                    .child(SharedString::from(text.clone()));
+                if *bold {
+                    el = el.font_weight(gpui::FontWeight::BOLD);
+                }
+                el.into_any_element()
+            }
+            LayoutNode::Badge { text, color } => {
+                let color = color
+                    .as_deref()
+                    .and_then(parse_color)
+                    .unwrap_or(theme.accent);
+                div()
+                    .px(px(6.0))
+                    .py(px(2.0))
+                    .rounded(px(9999.0))
+                    .bg(theme.with_alpha(color, 0.2))
+                    .text_color(color)
+                    .text_size(px(theme.font_size - 2.0))
+                    .child(SharedString::from(text.clone()))
+                    .into_any_element()
+            }
+            LayoutNode::Progress {
+                value,
+                label,
+                color,
+            } => {
+                let fraction = (value / 100.0).clamp(0.0, 1.0);
+                let color = color
+                    .as_deref()
+                    .and_then(parse_color)
+                    .unwrap_or(theme.accent);
+                let track = div()
+                    .w_full()
+                    .h(px(6.0))
+                    .rounded(px(3.0))
+                    .bg(theme.surface)
+                    .child(
+                        div()
+                            .h_full()
+                            .w(gpui::relative(fraction))
+                            .rounded(px(3.0))
+                            .bg(color),
+                    );
+                let mut el = div().flex().flex_col().gap(px(4.0)).w_full();
+                if let Some(label) = label {
+                    el = el.child(
+                        div()
+                            .flex()
+                            .flex_row()
+                            .justify_between()
+                            .text_size(px(theme.font_size - 1.0))
+                            .text_color(theme.foreground_muted)
+                            .child(SharedString::from(label.clone()))
+                            .child(SharedString::from(format!("{:.0}%", fraction * 100.0))),
+                    );
+                }
+                el.child(track).into_any_element()
+            }
+            LayoutNode::Button { label, .. } => {
+                let id = self.id.clone();
+                let button_id = self.button_id(*buttons);
+                *buttons += 1;
+                Button::new(SharedString::from(button_id.clone()))
+                    .label(label.clone())
+                    .variant(ButtonVariant::Default)
+                    .focused(self.focus.is_focused(&button_id))
+                    .on_popup_action(id, PopupAction::Select { value: button_id })
+                    .render(theme)
+                    .into_any_element()
+            }
+            LayoutNode::Table { .. } => match node.to_table() {
+                Some(table) => table.render(theme).into_any_element(),
+                None => div().into_any_element(),
+            },
+            LayoutNode::Chart { .. } => match node.to_chart(width) {
+                Some(chart) => chart.render(theme).into_any_element(),
+                None => div().into_any_element(),
+            },
+            LayoutNode::Divider => div()
+                .w_full()
+                .h(px(1.0))
+                .bg(theme.border_subtle)
+                .into_any_element(),
+            LayoutNode::Spacer => div().flex_grow().into_any_element(),
+        }
+    }
+}
+
+impl GpuiModule for ScriptPopupModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, _theme: &Theme) -> AnyElement {
+        div().into_any_element()
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let (width, height, keyboard) = match self.content.lock().ok().as_deref() {
+            Some(PopupContent::Ready(layout)) => (
+                layout.width.unwrap_or(DEFAULT_POPUP_WIDTH),
+                layout.estimated_height(),
+                // Buttons are reachable with Tab
+                !layout.buttons().is_empty(),
+            ),
+            _ => (DEFAULT_POPUP_WIDTH, 48.0, false),
+        };
+        Some(PopupSpec {
+            width,
+            height,
+            anchor: PopupAnchor::Center,
+            popup_type: PopupType::Popup,
+            keyboard,
+        })
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let guard = self.content.lock().ok()?;
+        let body = match &*guard {
+            PopupContent::Loading => div()
+                .text_color(theme.foreground_muted)
+                .text_size(px(theme.font_size))
+                .child("Loading…")
+                .into_any_element(),
+            PopupContent::Error(err) => div()
+                .text_color(theme.destructive)
+                .text_size(px(theme.font_size))
+                .child(SharedString::from(err.clone()))
+                .into_any_element(),
+            PopupContent::Ready(layout) => {
+                let width = self.content_width(layout);
+                let mut buttons = 0;
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(DEFAULT_GAP))
+                    .children(
+                        layout
+                            .children
+                            .iter()
+                            .map(|n| self.render_node(n, theme, width, &mut buttons)),
+                    )
+                    .into_any_element()
+            }
+        };
+        Some(div().p(px(POPUP_PADDING)).child(body).into_any_element())
+    }
+
+    fn on_popup_event(&mut self, event: PopupEvent) {
+        match event {
+            PopupEvent::Opened => {
+                self.focus.blur();
+                self.refresh();
+            }
+            PopupEvent::Key(key) => {
+                let order = match self.content.lock().ok().as_deref() {
+                    Some(PopupContent::Ready(layout)) => (0..layout.buttons().len())
+                        .map(|index| (self.button_id(index).into(), FocusKind::Button))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                self.focus.set_order(order);
+                if let FocusResult::Activate(id) = self.focus.handle_key(&key) {
+                    self.activate_button(&id);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_popup_action(&mut self, action: PopupAction) {
+        match action {
+            PopupAction::Refresh => self.refresh(),
+            PopupAction::RunCommand { command } => self.run_button_command(&command),
+            PopupAction::Select { value } => self.activate_button(&value),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_plain_text_becomes_text_lines() {
+        let layout = PopupLayout::parse("one\ntwo").unwrap();
+        assert_eq!(layout.children.len(), 2);
+        assert!(matches!(&layout.children[1], LayoutNode::Text { text, .. } if text == "two"));
+    }
+
+    #[test]
+    fn parse_nested_layout() {
+        let raw = r##"{"width": 320, "children": [
+            {"type": "row", "children": [
+                {"type": "text", "text": "Docker", "bold": true},
+                {"type": "spacer"},
+                {"type": "badge", "text": "3 up", "color": "#a6e3a1"}
+            ]},
+            {"type": "divider"},
+            {"type": "progress", "value": 42, "label": "CPU"},
+            {"type": "button", "label": "Reload", "action": "refresh"}
+        ]}"##;
+        let layout = PopupLayout::parse(raw).unwrap();
+        assert_eq!(layout.width, Some(320.0));
+        assert_eq!(layout.children.len(), 4);
+        assert!(matches!(
+            &layout.children[3],
+            LayoutNode::Button {
+                action: Some(ButtonAction::Refresh),
+                command: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn buttons_with_the_same_label_keep_their_own_action() {
+        let raw = r#"{"children": [
+            {"type": "button", "label": "Run", "command": "make"},
+            {"type": "row", "children": [
+                {"type": "button", "label": "Run", "action": "close"}
+            ]}
+        ]}"#;
+        let module = ScriptPopupModule::new("popup", "true");
+        *module.content.lock().unwrap() = PopupContent::Ready(PopupLayout::parse(raw).unwrap());
+
+        let (first, second) = (module.button_id(0), module.button_id(1));
+        assert_ne!(first, second);
+        assert_eq!(
+            module.button(&first),
+            Some((Some("make".to_string()), None))
+        );
+        assert_eq!(
+            module.button(&second),
+            Some((None, Some(ButtonAction::Close)))
+        );
+        assert_eq!(module.button("popup-button-2"), None);
+    }
+
+    #[test]
+    fn parse_rejects_unknown_node_type() {
+        let err = PopupLayout::parse(r#"{"children": [{"type": "video"}]}"#).unwrap_err();
+        assert!(err.starts_with("Invalid popup layout"));
+    }
+
+    #[test]
+    fn estimated_height_sums_column_and_takes_row_max() {
+        let layout = PopupLayout {
+            children: vec![
+                LayoutNode::Row {
+                    children: vec![
+                        LayoutNode::Badge {
+                            text: "a".into(),
+                            color: None,
+                        },
+                        LayoutNode::Button {
+                            label: "b".into(),
+                            command: None,
+                            action: None,
+                        },
+                    ],
+                    gap: None,
+                },
+                LayoutNode::Divider,
+            ],
+            ..PopupLayout::default()
+        };
+        // 28 (row) + 8 (gap) + 1 (divider) + 24 (padding)
+        assert_eq!(layout.estimated_height(), 61.0);
+    }
+
+    #[test]
+    fn parse_table_and_estimate_its_height() {
+        let raw = r#"{"children": [{"type": "table",
+            "columns": [{"title": "Name"}, {"title": "CPU", "align": "right", "width": 48}],
+            "rows": [["sinew", "1.2"], ["WindowServer", "8.4"]]}]}"#;
+        let layout = PopupLayout::parse(raw).unwrap();
+        let LayoutNode::Table {
+            columns,
+            rows,
+            header,
+            ..
+        } = &layout.children[0]
+        else {
+            panic!("expected a table node");
+        };
+        assert_eq!(columns[1].width, Some(48.0));
+        assert_eq!(rows.len(), 2);
+        assert!(header);
+        // header + 2 rows at 22px, plus padding
+        assert_eq!(layout.estimated_height(), 66.0 + 24.0);
+    }
+
+    #[test]
+    fn parse_chart_defaults_to_line() {
+        let raw =
+            r#"{"children": [{"type": "chart", "values": [1, 2, 3], "labels": ["1h", "now"]}]}"#;
+        let layout = PopupLayout::parse(raw).unwrap();
+        assert!(matches!(
+            &layout.children[0],
+            LayoutNode::Chart {
+                style: ChartStyle::Line,
+                labels: Some(_),
+                ..
+            }
+        ));
+        // 60px plot + 14px x-axis labels + padding
+        assert_eq!(layout.estimated_height(), 74.0 + 24.0);
+    }
+
+    #[test]
+    fn explicit_height_wins_over_estimate() {
+        let layout = PopupLayout::parse(r#"{"height": 400, "children": []}"#).unwrap();
+        assert_eq!(layout.estimated_height(), 400.0);
+    }
+}
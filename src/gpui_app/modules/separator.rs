@@ -1,5 +1,7 @@
 //! Separator module for visual spacing/dividers.
 
+use std::time::Duration;
+
 use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
 
 use super::GpuiModule;
@@ -90,4 +92,8 @@ impl GpuiModule for SeparatorModule {
     fn update(&mut self) -> bool {
         false // Separators never change
     }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
 }
@@ -0,0 +1,337 @@
+//! Shortcuts module for running macOS Shortcuts from the bar.
+//!
+//! Clicking opens a popup listing the configured shortcuts, or every
+//! shortcut in the Shortcuts app when `shortcuts` is omitted. Picking one
+//! runs it with `shortcuts run`; the bar item shows a spinner while it runs,
+//! then the shortcut's text output (or whether it failed) for a few seconds.
+//!
+//! ```toml
+//! { type = "shortcuts", shortcuts = ["Log Water", "Start Focus"] }
+//! ```
+
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use gpui::{div, prelude::*, px, Animation, AnimationExt, AnyElement, SharedString, Styled};
+
+use super::{mark_dirty, GpuiModule, PopupAction, PopupEvent, PopupSpec};
+use crate::gpui_app::popup_manager::{notify_popup_needs_render, request_hide_popup};
+use crate::gpui_app::primitives::{icons, Button};
+use crate::gpui_app::theme::{ButtonVariant, Theme};
+
+const POPUP_WIDTH: f64 = 260.0;
+const POPUP_PADDING: f32 = 8.0;
+const ROW_HEIGHT: f32 = 28.0;
+const MAX_ROWS: usize = 12;
+/// How long a shortcut's result stays in the bar.
+const MESSAGE_DURATION: Duration = Duration::from_secs(5);
+/// Longest result shown in the bar, in characters.
+const MAX_MESSAGE_LEN: usize = 40;
+const SPINNER_FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Parses `shortcuts list`, one name per line.
+pub fn parse_list(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Bar text for a finished run: the last line of output, shortened, or a
+/// plain done/failed note when the shortcut printed nothing.
+pub fn result_message(name: &str, success: bool, output: &str) -> String {
+    let line = output.lines().map(str::trim).rfind(|line| !line.is_empty());
+    match line {
+        Some(line) if line.chars().count() > MAX_MESSAGE_LEN => {
+            let short: String = line.chars().take(MAX_MESSAGE_LEN - 1).collect();
+            format!("{}…", short)
+        }
+        Some(line) => line.to_string(),
+        None if success => format!("{} done", name),
+        None => format!("{} failed", name),
+    }
+}
+
+/// A finished run's message.
+#[derive(Debug, Clone)]
+struct Message {
+    text: String,
+    success: bool,
+    at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct ShortcutsState {
+    shortcuts: Vec<String>,
+    /// Shortcut currently running
+    running: Option<String>,
+    message: Option<Message>,
+}
+
+/// Shortcuts module that runs macOS Shortcuts from a popup.
+pub struct ShortcutsModule {
+    id: String,
+    /// Configured shortcuts (None = everything in the Shortcuts app)
+    configured: Option<Vec<String>>,
+    state: Arc<Mutex<ShortcutsState>>,
+    dirty: Arc<AtomicBool>,
+}
+
+impl ShortcutsModule {
+    /// Creates a new shortcuts module.
+    ///
+    /// @param id - Unique module identifier
+    /// @param shortcuts - Shortcut names to list (None = all of them)
+    pub fn new(id: &str, shortcuts: Option<Vec<String>>) -> Self {
+        let state = ShortcutsState {
+            shortcuts: shortcuts.clone().unwrap_or_default(),
+            ..ShortcutsState::default()
+        };
+        let module = Self {
+            id: id.to_string(),
+            configured: shortcuts,
+            state: Arc::new(Mutex::new(state)),
+            dirty: Arc::new(AtomicBool::new(true)),
+        };
+        // Load early so the popup is sized right the first time it opens
+        module.refresh_list();
+        module
+    }
+
+    /// Returns a second handle on the same state for the popup registry.
+    pub fn popup_handle(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            configured: self.configured.clone(),
+            state: Arc::clone(&self.state),
+            dirty: Arc::clone(&self.dirty),
+        }
+    }
+
+    /// Reloads the shortcut list from the Shortcuts app.
+    fn refresh_list(&self) {
+        if self.configured.is_some() {
+            return;
+        }
+        let state = Arc::clone(&self.state);
+        let id = self.id.clone();
+        std::thread::spawn(move || {
+            let output = Command::new("shortcuts")
+                .arg("list")
+                .output()
+                .ok()
+                .and_then(|o| String::from_utf8(o.stdout).ok())
+                .unwrap_or_default();
+            if let Ok(mut state) = state.lock() {
+                state.shortcuts = parse_list(&output);
+            }
+            notify_popup_needs_render(&id);
+        });
+    }
+
+    /// Runs `name` in the background unless a shortcut is already running.
+    fn run(&self, name: String) {
+        if let Ok(mut state) = self.state.lock() {
+            if state.running.is_some() {
+                return;
+            }
+            state.running = Some(name.clone());
+            state.message = None;
+        }
+        mark_dirty(&self.dirty);
+
+        let state = Arc::clone(&self.state);
+        let dirty = Arc::clone(&self.dirty);
+        std::thread::spawn(move || {
+            log::info!("shortcuts: running '{}'", name);
+            let (success, output) = match Command::new("shortcuts").args(["run", &name]).output() {
+                Ok(out) => (
+                    out.status.success(),
+                    String::from_utf8_lossy(&out.stdout).into_owned(),
+                ),
+                Err(err) => {
+                    log::warn!("shortcuts: failed to run '{}': {}", name, err);
+                    (false, String::new())
+                }
+            };
+            if let Ok(mut state) = state.lock() {
+                state.running = None;
+                state.message = Some(Message {
+                    text: result_message(&name, success, &output),
+                    success,
+                    at: Instant::now(),
+                });
+            }
+            mark_dirty(&dirty);
+        });
+    }
+}
+
+impl GpuiModule for ShortcutsModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let (running, message) = match self.state.lock() {
+            Ok(state) => (state.running.clone(), state.message.clone()),
+            Err(_) => (None, None),
+        };
+        let row = div()
+            .flex()
+            .items_center()
+            .gap(px(4.0))
+            .text_size(px(theme.font_size))
+            .text_color(theme.foreground);
+
+        if let Some(name) = running {
+            let frames = SPINNER_FRAMES.len();
+            return row
+                .child(div().with_animation(
+                    SharedString::from(format!("{}-spinner", self.id)),
+                    Animation::new(Duration::from_millis(800)).repeat(),
+                    move |el, delta| {
+                        el.child(SPINNER_FRAMES[(delta * frames as f32) as usize % frames])
+                    },
+                ))
+                .child(SharedString::from(name))
+                .into_any_element();
+        }
+        if let Some(message) = message {
+            let color = if message.success {
+                theme.foreground
+            } else {
+                theme.destructive
+            };
+            return row
+                .text_color(color)
+                .child(SharedString::from(message.text))
+                .into_any_element();
+        }
+        row.child(icons::system::SHORTCUTS).into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        // Clear the result once it has been shown long enough
+        if let Ok(mut state) = self.state.lock() {
+            let expired = state
+                .message
+                .as_ref()
+                .is_some_and(|m| m.at.elapsed() >= MESSAGE_DURATION);
+            if expired {
+                state.message = None;
+                return true;
+            }
+        }
+        self.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn display_text(&self) -> Option<String> {
+        let state = self.state.lock().ok()?;
+        state
+            .running
+            .clone()
+            .or_else(|| state.message.as_ref().map(|message| message.text.clone()))
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        // Only the result message's expiry needs a timer
+        let state = self.state.lock().ok()?;
+        let shown = state.message.as_ref()?.at.elapsed();
+        Some(MESSAGE_DURATION.saturating_sub(shown))
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let rows = self
+            .state
+            .lock()
+            .map(|state| state.shortcuts.len())
+            .unwrap_or(0)
+            .clamp(1, MAX_ROWS);
+        let height = POPUP_PADDING * 2.0 + rows as f32 * ROW_HEIGHT;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let (shortcuts, busy) = match self.state.lock() {
+            Ok(state) => (state.shortcuts.clone(), state.running.is_some()),
+            Err(_) => (Vec::new(), false),
+        };
+
+        let mut list = div()
+            .flex()
+            .flex_col()
+            .p(px(POPUP_PADDING))
+            .text_size(px(theme.font_size))
+            .text_color(theme.foreground);
+        if shortcuts.is_empty() {
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .text_color(theme.foreground_muted)
+                    .child("No shortcuts"),
+            );
+        }
+        for (i, name) in shortcuts.into_iter().take(MAX_ROWS).enumerate() {
+            list = list.child(
+                Button::new(SharedString::from(format!("{}-{}", self.id, i)))
+                    .label(name.clone())
+                    .variant(ButtonVariant::Ghost)
+                    .disabled(busy)
+                    .on_popup_action(self.id.clone(), PopupAction::Select { value: name })
+                    .render(theme)
+                    .h(px(ROW_HEIGHT))
+                    .w_full()
+                    .justify_start(),
+            );
+        }
+        Some(list.into_any_element())
+    }
+
+    fn on_popup_event(&mut self, event: PopupEvent) {
+        if let PopupEvent::Opened = event {
+            self.refresh_list();
+        }
+    }
+
+    fn on_popup_action(&mut self, action: PopupAction) {
+        if let PopupAction::Select { value } = action {
+            self.run(value);
+            request_hide_popup();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_shortcut_list() {
+        assert_eq!(
+            parse_list("Log Water\n\n  Start Focus \n"),
+            vec!["Log Water", "Start Focus"]
+        );
+    }
+
+    #[test]
+    fn result_message_uses_last_output_line() {
+        assert_eq!(
+            result_message("Log", true, "a\n250 ml logged\n\n"),
+            "250 ml logged"
+        );
+        assert_eq!(result_message("Log", true, ""), "Log done");
+        assert_eq!(result_message("Log", false, ""), "Log failed");
+        let long = "x".repeat(60);
+        assert_eq!(
+            result_message("Log", true, &long).chars().count(),
+            MAX_MESSAGE_LEN
+        );
+    }
+}
@@ -3,6 +3,8 @@
 //! This module permanently returns is_loading() = true, which causes
 //! the bar to render it as a skeleton container with shimmer animation.
 
+use std::time::Duration;
+
 use gpui::{div, prelude::*, AnyElement};
 
 use super::GpuiModule;
@@ -36,6 +38,10 @@ impl GpuiModule for SkeletonDemoModule {
         false
     }
 
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
     fn is_loading(&self) -> bool {
         true // Permanently loading
     }
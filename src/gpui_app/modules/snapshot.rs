@@ -0,0 +1,277 @@
+//! Snapshot tests for modules.
+//!
+//! Each fixture builds a module from a config snippet through the same
+//! factory path as the bar, feeds it fake data through `set_property` (what
+//! `sinew-msg set` does) and renders it and its popup with the default theme.
+//! GPUI elements can't be inspected once built, so the snapshot records the
+//! structure the bar renders around the module instead: its style, colors,
+//! popup geometry, toggle state, value, copy text and update timing.
+//! Snapshots live in `snapshots/<fixture>.snap` next to this file.
+//!
+//! A fixture without a snapshot file fails. Write new ones, or rewrite them
+//! all after an intended change, with
+//! `SINEW_UPDATE_SNAPSHOTS=1 cargo test snapshot` and review the diff.
+
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use super::{create_module, PositionedModule};
+use crate::config::ModuleConfig;
+use crate::gpui_app::theme::Theme;
+
+/// Writes snapshots instead of comparing against them.
+const UPDATE_ENV: &str = "SINEW_UPDATE_SNAPSHOTS";
+
+/// A module config and the fake data it gets before rendering.
+struct Fixture {
+    name: &'static str,
+    config: &'static str,
+    /// Properties set on the module, as `sinew-msg set` would
+    set: &'static [(&'static str, &'static str)],
+}
+
+/// Fixtures for modules whose construction runs no commands and uses no
+/// network. Modules that read the machine get fixed readings through `set`.
+const FIXTURES: &[Fixture] = &[
+    Fixture {
+        name: "static",
+        config: r##"
+            type = "static"
+            text = "Sinew"
+            icon = "★"
+            background = "#2C3135"
+            padding = 8.0
+            corner_radius = 6.0
+            click_command = "open -a Finder"
+        "##,
+        set: &[],
+    },
+    Fixture {
+        name: "separator",
+        config: r##"
+            type = "separator"
+            separator_type = "line"
+            separator_width = 12.0
+            margin_left = 4.0
+            margin_right = 4.0
+        "##,
+        set: &[],
+    },
+    Fixture {
+        name: "external",
+        config: r##"
+            id = "deploy"
+            type = "external"
+            label = "idle"
+            toggle = true
+            active_background = "#5f656a"
+            color_rules = [{ when = "< 20", color = "#ff0000" }]
+        "##,
+        set: &[("label", "deploying"), ("icon", "🚀"), ("color", "#ffcc00")],
+    },
+    Fixture {
+        name: "logs",
+        config: r##"
+            type = "logs"
+            group = "status"
+            flex = true
+            min_width = 40.0
+            max_width = 120.0
+        "##,
+        set: &[],
+    },
+    Fixture {
+        name: "battery",
+        config: r##"
+            type = "battery"
+            color_rules = [{ when = "< 20", color = "#ff0000" }]
+        "##,
+        set: &[("level", "15"), ("charging", "false"), ("plugged", "false")],
+    },
+    Fixture {
+        name: "cpu",
+        config: r##"
+            type = "cpu"
+            label = "CPU"
+            format = "{value:>3}%"
+        "##,
+        set: &[("value", "42")],
+    },
+    Fixture {
+        name: "clock",
+        config: r##"
+            type = "clock"
+            format = "%H:%M"
+            zones = ["UTC"]
+            toggle_hour12 = true
+        "##,
+        set: &[("time", "2026-03-14T15:26:53Z"), ("hour12", "true")],
+    },
+];
+
+/// Formats a color as `#rrggbbaa`.
+fn hex(color: Option<gpui::Rgba>) -> String {
+    let Some(color) = color else {
+        return "-".to_string();
+    };
+    let channel = |value: f32| (value * 255.0).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}{:02x}",
+        channel(color.r),
+        channel(color.g),
+        channel(color.b),
+        channel(color.a)
+    )
+}
+
+/// Formats an optional value, `-` when absent.
+fn opt<T: std::fmt::Display>(value: Option<T>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| value.to_string())
+}
+
+/// Renders a module and its popup, and describes what the bar draws.
+fn snapshot(positioned: &PositionedModule, theme: &Theme) -> String {
+    let module = &positioned.module;
+    // Rendering must work with fixed inputs; the elements themselves are opaque
+    let _ = module.render(theme);
+    let popup_content = module.render_popup(theme).is_some();
+
+    let style = &positioned.style;
+    let mut out = String::new();
+    let mut line = |key: &str, value: String| {
+        let _ = writeln!(out, "{}: {}", key, value);
+    };
+    line("id", module.id().to_string());
+    line("value", opt(module.value()));
+    line("copy", opt(module.copy_text()));
+    line("toggle", opt(module.toggle_state()));
+    line("animating", module.is_animating().to_string());
+    line(
+        "updates",
+        match module.next_update() {
+            Some(_) => "timer".to_string(),
+            None => "on wake".to_string(),
+        },
+    );
+    line(
+        "style",
+        format!(
+            "background={} border={} {}px radius={} padding={}",
+            hex(style.background),
+            hex(style.border_color),
+            style.border_width,
+            style.corner_radius,
+            style.padding
+        ),
+    );
+    line(
+        "active",
+        format!(
+            "background={} border={} text={}",
+            hex(style.active_background),
+            hex(style.active_border_color),
+            hex(style.active_text_color)
+        ),
+    );
+    line("text_color", hex(positioned.text_color));
+    for rule in &style.color_rules {
+        line(
+            "color_rule",
+            format!(
+                "{:?} {:?} {}",
+                rule.condition,
+                rule.target,
+                hex(Some(rule.color))
+            ),
+        );
+    }
+    line("click", opt(positioned.click_command.as_deref()));
+    line("group", opt(positioned.group.as_deref()));
+    line(
+        "toggle_enabled",
+        format!(
+            "{} group={}",
+            positioned.toggle_enabled,
+            opt(positioned.toggle_group.as_deref())
+        ),
+    );
+    line(
+        "width",
+        format!(
+            "flex={} min={} max={} margin={}/{}",
+            positioned.flex,
+            opt(positioned.min_width),
+            opt(positioned.max_width),
+            opt(positioned.margin_left),
+            opt(positioned.margin_right)
+        ),
+    );
+    line(
+        "popup",
+        match module.popup_spec() {
+            Some(spec) => format!(
+                "{:?} {}x{} anchor={:?} keyboard={} content={}",
+                spec.popup_type, spec.width, spec.height, spec.anchor, spec.keyboard, popup_content
+            ),
+            None => "-".to_string(),
+        },
+    );
+    out
+}
+
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/gpui_app/modules/snapshots")
+        .join(format!("{}.snap", name))
+}
+
+#[test]
+fn modules_match_snapshots() {
+    let theme = Theme::default();
+    let update = std::env::var_os(UPDATE_ENV).is_some();
+    let mut mismatches = Vec::new();
+
+    for (index, fixture) in FIXTURES.iter().enumerate() {
+        let config: ModuleConfig = toml::from_str(fixture.config)
+            .unwrap_or_else(|e| panic!("fixture '{}': {}", fixture.name, e));
+        let mut positioned = create_module(&config, index)
+            .unwrap_or_else(|| panic!("fixture '{}': module not created", fixture.name));
+        for (key, value) in fixture.set {
+            assert!(
+                positioned.module.set_property(key, value),
+                "fixture '{}': property '{}' not accepted",
+                fixture.name,
+                key
+            );
+        }
+        let actual = snapshot(&positioned, &theme);
+
+        let path = snapshot_path(fixture.name);
+        if update {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, &actual).unwrap();
+            continue;
+        }
+        match std::fs::read_to_string(&path) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => mismatches.push(format!(
+                "{}\n--- expected\n{}--- actual\n{}",
+                path.display(),
+                expected,
+                actual
+            )),
+            Err(_) => mismatches.push(format!(
+                "{}: missing snapshot, run with {}=1",
+                path.display(),
+                UPDATE_ENV
+            )),
+        }
+    }
+
+    assert!(
+        mismatches.is_empty(),
+        "snapshots don't match (rerun with {}=1 to accept):\n{}",
+        UPDATE_ENV,
+        mismatches.join("\n")
+    );
+}
@@ -0,0 +1,152 @@
+//! Chevron at the far right of the bar while modules are snoozed.
+//!
+//! It shows how many modules are hidden; its popup lists them with the
+//! time they have left and brings one back on click. The bar leaves the
+//! chevron out while nothing is snoozed.
+
+use std::time::{Duration, Instant};
+
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+
+use super::{
+    register_popup_module, GpuiModule, PopupAction, PopupConfig, PopupSpec, PositionedModule,
+};
+use crate::gpui_app::popup_manager::request_hide_popup;
+use crate::gpui_app::primitives::{icons, Button};
+use crate::gpui_app::snooze;
+use crate::gpui_app::theme::{ButtonVariant, Theme};
+
+pub const ID: &str = "snoozed";
+const POPUP_WIDTH: f64 = 260.0;
+const POPUP_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 24.0;
+const ROW_HEIGHT: f32 = 30.0;
+
+/// Lists snoozed modules behind a bar chevron.
+pub struct SnoozedModule {
+    id: String,
+}
+
+impl SnoozedModule {
+    fn new() -> Self {
+        Self { id: ID.to_string() }
+    }
+}
+
+impl GpuiModule for SnoozedModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        div()
+            .flex()
+            .items_center()
+            .gap(px(2.0))
+            .text_color(theme.foreground_muted)
+            .text_size(px(theme.font_size - 1.0))
+            .child(icons::system::CHEVRON)
+            .child(SharedString::from(snooze::snoozed().len().to_string()))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        // The bar redraws when snoozes change
+        false
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let rows = snooze::snoozed().len().max(1) as f32;
+        let height = POPUP_PADDING * 2.0 + HEADER_HEIGHT + ROW_HEIGHT * rows;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let now = Instant::now();
+        let snoozed = snooze::snoozed();
+        let mut list = div().flex().flex_col();
+        if snoozed.is_empty() {
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .text_color(theme.foreground_muted)
+                    .child("Nothing is snoozed"),
+            );
+        }
+        for (module_id, until) in snoozed {
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .child(
+                        div()
+                            .flex_1()
+                            .overflow_hidden()
+                            .child(SharedString::from(module_id.clone())),
+                    )
+                    .child(
+                        div()
+                            .text_size(px(theme.font_size - 2.0))
+                            .text_color(theme.foreground_muted)
+                            .child(SharedString::from(until.describe(now))),
+                    )
+                    .child(
+                        Button::new(SharedString::from(format!("{}-{}", self.id, module_id)))
+                            .label("Show")
+                            .variant(ButtonVariant::Outline)
+                            .text_size(px(theme.font_size - 2.0))
+                            .on_popup_action(
+                                self.id.clone(),
+                                PopupAction::Select { value: module_id },
+                            )
+                            .render(theme),
+                    ),
+            );
+        }
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(
+                    div()
+                        .h(px(HEADER_HEIGHT))
+                        .font_weight(gpui::FontWeight::SEMIBOLD)
+                        .child("Snoozed modules"),
+                )
+                .child(list)
+                .into_any_element(),
+        )
+    }
+
+    fn on_popup_action(&mut self, action: PopupAction) {
+        let PopupAction::Select { value } = action else {
+            return;
+        };
+        snooze::restore(&value);
+        if snooze::snoozed().is_empty() {
+            request_hide_popup();
+        }
+    }
+}
+
+/// Returns the chevron, which the bar shows only while something is snoozed.
+pub fn indicator() -> PositionedModule {
+    register_popup_module(SnoozedModule::new());
+    let popup = PopupConfig {
+        popup_type: Some(ID.to_string()),
+        ..Default::default()
+    };
+    PositionedModule::internal(Box::new(SnoozedModule::new()), popup)
+}
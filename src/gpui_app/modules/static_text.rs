@@ -1,15 +1,19 @@
 //! Static text module for displaying fixed text.
 
+use std::time::Duration;
+
 use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
 
 use super::GpuiModule;
+use crate::gpui_app::primitives::Image;
 use crate::gpui_app::theme::Theme;
 
-/// Static text module that displays fixed text and/or icon.
+/// Static text module that displays fixed text, an icon, and/or an image.
 pub struct StaticTextModule {
     id: String,
     text: String,
     icon: Option<String>,
+    image: Option<String>,
 }
 
 impl StaticTextModule {
@@ -19,8 +23,15 @@ impl StaticTextModule {
             id: id.to_string(),
             text: text.to_string(),
             icon: icon.map(|s| s.to_string()),
+            image: None,
         }
     }
+
+    /// Shows an image (file path or URL) before the text.
+    pub fn with_image(mut self, image: Option<&str>) -> Self {
+        self.image = image.map(|s| s.to_string());
+        self
+    }
 }
 
 impl GpuiModule for StaticTextModule {
@@ -35,16 +46,35 @@ impl GpuiModule for StaticTextModule {
             (None, _) => self.text.clone(),
         };
 
-        div()
+        let mut el = div()
             .flex()
             .items_center()
+            .gap(px(6.0))
             .text_color(theme.foreground)
-            .text_size(px(theme.font_size))
-            .child(SharedString::from(display))
-            .into_any_element()
+            .text_size(px(theme.font_size));
+        if let Some(image) = &self.image {
+            el = el.child(
+                Image::new(image.clone())
+                    .size(theme.font_size + 2.0)
+                    .render(theme),
+            );
+        }
+        if !display.is_empty() {
+            el = el.child(SharedString::from(display));
+        }
+        el.into_any_element()
     }
 
     fn update(&mut self) -> bool {
-        false // Static content never changes
+        // Static content never changes; downloaded images refresh the bar
+        false
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn copy_text(&self) -> Option<String> {
+        (!self.text.is_empty()).then(|| self.text.clone())
     }
 }
@@ -0,0 +1,635 @@
+//! System monitor module.
+//!
+//! Shows overall CPU usage in the bar. Clicking opens a full-width panel
+//! with per-core CPU bars, memory pressure, disk and network throughput
+//! graphs, and the top five processes by CPU or memory with kill buttons.
+//!
+//! Per-core CPU, memory pressure and disk I/O come from the shared metrics
+//! service, and network counters from `netstat`. The
+//! process list comes from the shared process sampler, subscribed to only
+//! while the panel is open.
+//!
+//! ```toml
+//! { type = "sysmon", interval = 2 }
+//! ```
+
+use std::collections::VecDeque;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use gpui::{div, prelude::*, px, AnyElement, Div, Rgba, SharedString, Styled};
+
+use super::{mark_dirty, GpuiModule, PopupAction, PopupEvent, PopupSpec};
+use crate::gpui_app::popup_manager::{notify_popup_needs_render, panel_width};
+use crate::gpui_app::primitives::{icons, Button, Chart, ChartKind};
+use crate::gpui_app::theme::{ButtonVariant, Theme};
+use crate::system::metrics::{self, Metric};
+use crate::system::processes::{self, ProcessInfo, ProcessSort, Snapshot, Subscription};
+
+/// Throughput samples kept for the graphs.
+const HISTORY: usize = 60;
+const TOP_PROCESSES: usize = 5;
+const PANEL_HEIGHT: f64 = 360.0;
+const PANEL_PADDING: f32 = 16.0;
+const CARD_GAP: f32 = 12.0;
+const CHART_HEIGHT: f32 = 70.0;
+const ROW_HEIGHT: f32 = 26.0;
+
+/// Sums received and sent bytes over all non-loopback interfaces from
+/// `netstat -ibn`. Only the `<Link#N>` rows are counted, since the address
+/// rows repeat the same totals.
+pub fn parse_netstat(output: &str) -> (u64, u64) {
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            if cols.len() < 8 || cols[0].starts_with("lo") || !cols[2].starts_with("<Link#") {
+                return None;
+            }
+            // The address column is empty for some interfaces, so count
+            // from the end: ... Ibytes Opkts Oerrs Obytes Coll
+            let n = cols.len();
+            let received = cols[n - 5].parse::<u64>().ok()?;
+            let sent = cols[n - 2].parse::<u64>().ok()?;
+            Some((received, sent))
+        })
+        .fold((0, 0), |(r, s), (dr, ds)| (r + dr, s + ds))
+}
+
+/// Formats a byte count with a binary unit, e.g. `1.5 GB`.
+pub fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes.max(0.0);
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 || value >= 100.0 {
+        format!("{:.0} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+fn command_output(program: &str, args: &[&str]) -> String {
+    Command::new(program)
+        .args(args)
+        .output()
+        .ok()
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .unwrap_or_default()
+}
+
+fn push_sample(history: &mut VecDeque<f32>, value: f32) {
+    if history.len() == HISTORY {
+        history.pop_front();
+    }
+    history.push_back(value);
+}
+
+/// Latest readings and throughput history.
+#[derive(Debug, Clone, Default)]
+struct SysmonState {
+    /// Per-core usage in percent
+    cores: Vec<f32>,
+    /// Memory in use by the kernel's reckoning, in percent
+    memory_pressure: Option<u8>,
+    /// Bytes per second, oldest first
+    disk_read: VecDeque<f32>,
+    disk_write: VecDeque<f32>,
+    net_in: VecDeque<f32>,
+    net_out: VecDeque<f32>,
+    processes: Vec<ProcessInfo>,
+    sort: ProcessSort,
+}
+
+impl SysmonState {
+    fn cpu_total(&self) -> f32 {
+        metrics::mean(&self.cores)
+    }
+}
+
+/// Cumulative counters from the previous sample.
+struct Counters {
+    at: Instant,
+    net: (u64, u64),
+}
+
+/// State shared between the bar and popup copies of the module.
+struct Shared {
+    state: Mutex<SysmonState>,
+    interval: Duration,
+    /// Process sampler subscription, held while the panel is open
+    processes: Mutex<Option<Subscription>>,
+    dirty: AtomicBool,
+    stop: AtomicBool,
+}
+
+impl Shared {
+    /// Takes one sample, turning counter deltas into rates.
+    fn sample(&self, prev: Option<&Counters>) -> Counters {
+        let now = Counters {
+            at: Instant::now(),
+            net: parse_netstat(&command_output("netstat", &["-ibn"])),
+        };
+
+        if let Ok(mut state) = self.state.lock() {
+            if let Some(prev) = prev {
+                let secs = now.at.duration_since(prev.at).as_secs_f32().max(0.001);
+                let rate = |a: u64, b: u64| b.saturating_sub(a) as f32 / secs;
+                push_sample(&mut state.net_in, rate(prev.net.0, now.net.0));
+                push_sample(&mut state.net_out, rate(prev.net.1, now.net.1));
+            }
+        }
+        mark_dirty(&self.dirty);
+        now
+    }
+
+    fn set_processes(&self, snapshot: &Snapshot) {
+        if let Ok(mut state) = self.state.lock() {
+            state.processes = snapshot.top(state.sort, TOP_PROCESSES);
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.processes
+            .lock()
+            .map(|guard| guard.is_some())
+            .unwrap_or(false)
+    }
+}
+
+/// System monitor module.
+pub struct SysmonModule {
+    id: String,
+    shared: Arc<Shared>,
+}
+
+impl SysmonModule {
+    /// Creates a new system monitor and starts sampling.
+    ///
+    /// @param id - Unique module identifier
+    /// @param interval - Seconds between samples
+    pub fn new(id: &str, interval: u64) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(SysmonState::default()),
+            interval: Duration::from_secs(interval.max(1)),
+            processes: Mutex::new(None),
+            dirty: AtomicBool::new(true),
+            stop: AtomicBool::new(false),
+        });
+
+        let handle = Arc::clone(&shared);
+        let popup_id = id.to_string();
+        std::thread::spawn(move || {
+            let cpu = Arc::clone(&handle);
+            let _cores = metrics::subscribe(Metric::CpuCores, handle.interval, move |snapshot| {
+                if let (Some(details), Ok(mut state)) = (&snapshot.cpu_details, cpu.state.lock()) {
+                    state.cores = details.cores.clone();
+                }
+            });
+            let memory = Arc::clone(&handle);
+            let _memory = metrics::subscribe(Metric::Memory, handle.interval, move |snapshot| {
+                if let Ok(mut state) = memory.state.lock() {
+                    state.memory_pressure = snapshot.memory;
+                }
+            });
+            let disk = Arc::clone(&handle);
+            let _disk_io = metrics::subscribe(Metric::DiskIo, handle.interval, move |snapshot| {
+                if let (Some(io), Ok(mut state)) = (snapshot.disk_io, disk.state.lock()) {
+                    push_sample(&mut state.disk_read, io.read as f32);
+                    push_sample(&mut state.disk_write, io.write as f32);
+                }
+            });
+            let mut prev: Option<Counters> = None;
+            while !handle.stop.load(Ordering::Relaxed) {
+                prev = Some(handle.sample(prev.as_ref()));
+                if handle.is_open() {
+                    notify_popup_needs_render(&popup_id);
+                }
+                std::thread::sleep(handle.interval);
+            }
+        });
+
+        Self {
+            id: id.to_string(),
+            shared,
+        }
+    }
+
+    /// Returns a second handle on the same state for the popup registry.
+    pub fn popup_handle(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    fn state(&self) -> SysmonState {
+        self.shared
+            .state
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+
+    /// Subscribes to the process sampler until the panel closes.
+    fn watch_processes(&self) {
+        if let Some(snapshot) = processes::latest() {
+            self.shared.set_processes(&snapshot);
+        }
+        // Weak, so the subscription stored in `shared` doesn't keep it alive
+        let shared = Arc::downgrade(&self.shared);
+        let id = self.id.clone();
+        let subscription = processes::subscribe(self.shared.interval, move |snapshot| {
+            if let Some(shared) = shared.upgrade() {
+                shared.set_processes(snapshot);
+                notify_popup_needs_render(&id);
+            }
+        });
+        if let Ok(mut guard) = self.shared.processes.lock() {
+            *guard = Some(subscription);
+        }
+    }
+
+    /// Sends SIGTERM to `pid`. The next snapshot drops it from the list.
+    fn kill(&self, pid: u32) {
+        // Never signal launchd or the kernel
+        if pid <= 1 {
+            return;
+        }
+        std::thread::spawn(move || {
+            let status = Command::new("kill").arg(pid.to_string()).status();
+            if status.map(|s| s.success()).unwrap_or(false) {
+                log::info!("sysmon: sent SIGTERM to {}", pid);
+            } else {
+                log::warn!("sysmon: failed to kill {}", pid);
+            }
+        });
+    }
+
+    /// A titled dashboard card.
+    fn card(theme: &Theme, title: &str, detail: String, body: impl IntoElement) -> Div {
+        div()
+            .flex_1()
+            .flex()
+            .flex_col()
+            .gap(px(6.0))
+            .p(px(10.0))
+            .rounded(px(6.0))
+            .bg(theme.surface)
+            .child(
+                div()
+                    .flex()
+                    .justify_between()
+                    .child(
+                        div()
+                            .font_weight(gpui::FontWeight::SEMIBOLD)
+                            .child(SharedString::from(title.to_string())),
+                    )
+                    .child(
+                        div()
+                            .text_color(theme.foreground_muted)
+                            .child(SharedString::from(detail)),
+                    ),
+            )
+            .child(body)
+    }
+
+    /// Line chart of a throughput history.
+    fn throughput_chart(values: &VecDeque<f32>, width: f32, color: Rgba) -> Chart {
+        let values: Vec<f32> = values.iter().copied().collect();
+        Chart::new(ChartKind::Line, values)
+            .size(width, CHART_HEIGHT / 2.0)
+            .range(Some(0.0), None)
+            .color(color)
+    }
+
+    fn render_memory(theme: &Theme, pressure: Option<u8>) -> Div {
+        let Some(pressure) = pressure else {
+            return div()
+                .text_color(theme.foreground_muted)
+                .child("Unavailable");
+        };
+        let color = match pressure {
+            0..=59 => theme.success,
+            60..=84 => theme.warning,
+            _ => theme.destructive,
+        };
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .text_size(px(28.0))
+                    .text_color(color)
+                    .child(SharedString::from(format!("{}%", pressure))),
+            )
+            .child(
+                div()
+                    .h(px(6.0))
+                    .w_full()
+                    .rounded_full()
+                    .bg(theme.surface_hover)
+                    .child(
+                        div()
+                            .h_full()
+                            .w(gpui::relative(pressure as f32 / 100.0))
+                            .rounded_full()
+                            .bg(color),
+                    ),
+            )
+    }
+
+    fn render_processes(&self, theme: &Theme, state: &SysmonState) -> Div {
+        let sort_button = |sort: ProcessSort, label: &'static str, value: &str| {
+            let variant = if state.sort == sort {
+                ButtonVariant::Accent
+            } else {
+                ButtonVariant::Ghost
+            };
+            Button::new(SharedString::from(format!("{}-{}", self.id, value)))
+                .label(label)
+                .variant(variant)
+                .text_size(px(theme.font_size - 2.0))
+                .on_popup_action(
+                    self.id.clone(),
+                    PopupAction::Select {
+                        value: value.to_string(),
+                    },
+                )
+                .render(theme)
+        };
+
+        let header = div()
+            .h(px(ROW_HEIGHT))
+            .flex()
+            .items_center()
+            .justify_between()
+            .child(
+                div()
+                    .font_weight(gpui::FontWeight::SEMIBOLD)
+                    .child("Top processes"),
+            )
+            .child(
+                div()
+                    .flex()
+                    .gap(px(4.0))
+                    .child(sort_button(ProcessSort::Cpu, "CPU", "sort:cpu"))
+                    .child(sort_button(ProcessSort::Memory, "Memory", "sort:memory")),
+            );
+
+        let mut list = div().flex().flex_col().child(header);
+        if state.processes.is_empty() {
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .text_color(theme.foreground_muted)
+                    .child("Loading…"),
+            );
+        }
+        for process in &state.processes {
+            let value = format!("kill:{}", process.pid);
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .gap(px(12.0))
+                    .child(
+                        div()
+                            .flex_1()
+                            .overflow_hidden()
+                            .child(SharedString::from(process.name.clone())),
+                    )
+                    .child(
+                        div()
+                            .w(px(60.0))
+                            .text_color(theme.foreground_muted)
+                            .child(SharedString::from(process.pid.to_string())),
+                    )
+                    .child(
+                        div()
+                            .w(px(60.0))
+                            .flex()
+                            .justify_end()
+                            .child(SharedString::from(format!("{:.1}%", process.cpu))),
+                    )
+                    .child(
+                        div()
+                            .w(px(80.0))
+                            .flex()
+                            .justify_end()
+                            .child(SharedString::from(format_bytes(process.memory as f64))),
+                    )
+                    .child(
+                        Button::new(SharedString::from(format!("{}-{}", self.id, value)))
+                            .label("Kill")
+                            .variant(ButtonVariant::Destructive)
+                            .text_size(px(theme.font_size - 2.0))
+                            .on_popup_action(self.id.clone(), PopupAction::Select { value })
+                            .render(theme),
+                    ),
+            );
+        }
+        list
+    }
+}
+
+/// CPU icon and total usage.
+fn bar_text(total: f32) -> String {
+    format!("{} {:.0}%", icons::system::CPU, total)
+}
+
+impl GpuiModule for SysmonModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let total = self.state().cpu_total();
+        div()
+            .flex()
+            .items_center()
+            .text_color(theme.foreground)
+            .text_size(px(theme.font_size))
+            .child(SharedString::from(bar_text(total)))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        self.shared.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn display_text(&self) -> Option<String> {
+        Some(bar_text(self.state().cpu_total()))
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        Some(PopupSpec::panel(PANEL_HEIGHT))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let state = self.state();
+        let card_width =
+            ((panel_width() as f32 - PANEL_PADDING * 2.0 - CARD_GAP * 3.0) / 4.0).max(120.0);
+        let chart_width = card_width - 20.0;
+
+        let last = |values: &VecDeque<f32>| values.back().copied().unwrap_or(0.0) as f64;
+        let rate = |value: f64| format!("{}/s", format_bytes(value));
+
+        let cpu = Self::card(
+            theme,
+            "CPU",
+            format!("{:.0}%", state.cpu_total()),
+            Chart::new(ChartKind::Bar, state.cores.clone())
+                .size(chart_width, CHART_HEIGHT)
+                .range(Some(0.0), Some(100.0))
+                .unit("%")
+                .render(theme),
+        );
+        let memory = Self::card(
+            theme,
+            "Memory pressure",
+            String::new(),
+            Self::render_memory(theme, state.memory_pressure),
+        );
+        let disk = Self::card(
+            theme,
+            "Disk",
+            format!(
+                "R {} · W {}",
+                rate(last(&state.disk_read)),
+                rate(last(&state.disk_write))
+            ),
+            div()
+                .flex()
+                .flex_col()
+                .gap(px(4.0))
+                .child(
+                    Self::throughput_chart(&state.disk_read, chart_width, theme.accent)
+                        .render(theme),
+                )
+                .child(
+                    Self::throughput_chart(&state.disk_write, chart_width, theme.warning)
+                        .render(theme),
+                ),
+        );
+        let network = Self::card(
+            theme,
+            "Network",
+            format!(
+                "{} {} · {} {}",
+                icons::system::DOWNLOAD,
+                rate(last(&state.net_in)),
+                icons::system::UPLOAD,
+                rate(last(&state.net_out))
+            ),
+            div()
+                .flex()
+                .flex_col()
+                .gap(px(4.0))
+                .child(
+                    Self::throughput_chart(&state.net_in, chart_width, theme.success).render(theme),
+                )
+                .child(
+                    Self::throughput_chart(&state.net_out, chart_width, theme.info).render(theme),
+                ),
+        );
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .gap(px(CARD_GAP))
+                .p(px(PANEL_PADDING))
+                .size_full()
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(
+                    div()
+                        .flex()
+                        .gap(px(CARD_GAP))
+                        .child(cpu)
+                        .child(memory)
+                        .child(disk)
+                        .child(network),
+                )
+                .child(self.render_processes(theme, &state))
+                .into_any_element(),
+        )
+    }
+
+    fn on_popup_event(&mut self, event: PopupEvent) {
+        match event {
+            PopupEvent::Opened => self.watch_processes(),
+            PopupEvent::Closed => {
+                if let Ok(mut guard) = self.shared.processes.lock() {
+                    guard.take();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_popup_action(&mut self, action: PopupAction) {
+        let PopupAction::Select { value } = action else {
+            return;
+        };
+        if let Some(pid) = value.strip_prefix("kill:").and_then(|p| p.parse().ok()) {
+            self.kill(pid);
+            return;
+        }
+        let sort = match value.as_str() {
+            "sort:cpu" => ProcessSort::Cpu,
+            "sort:memory" => ProcessSort::Memory,
+            _ => return,
+        };
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.sort = sort;
+        }
+        if let Some(snapshot) = processes::latest() {
+            self.shared.set_processes(&snapshot);
+        }
+    }
+}
+
+impl Drop for SysmonModule {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sums_link_rows_from_netstat() {
+        let output = "\
+Name       Mtu   Network       Address            Ipkts Ierrs     Ibytes    Opkts Oerrs     Obytes  Coll
+lo0        16384 <Link#1>                        100     0      5000      100     0      5000     0
+en0        1500  <Link#6>    aa:bb:cc:dd:ee:ff    900     0    120000      500     0     40000     0
+en0        1500  192.168.1     192.168.1.20       900     -    120000      500     -     40000     -
+utun0      1380  <Link#12>                        10     0       300       12     0       700     0";
+        assert_eq!(parse_netstat(output), (120_300, 40_700));
+    }
+
+    #[test]
+    fn formats_bytes() {
+        assert_eq!(format_bytes(512.0), "512 B");
+        assert_eq!(format_bytes(1536.0), "1.5 KB");
+        assert_eq!(format_bytes(250.0 * 1024.0 * 1024.0), "250 MB");
+    }
+}
@@ -1,14 +1,15 @@
-//! Temperature module for displaying CPU temperature.
+//! Temperature module for displaying CPU temperature from the shared metrics
+//! service.
 
-use std::process::Command;
 use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
 use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
 
-use super::{GpuiModule, LabelAlign};
+use super::{mark_dirty, GpuiModule, LabelAlign, RuleColors};
 use crate::gpui_app::theme::Theme;
+use crate::system::metrics::{self, Metric, Subscription};
 
 #[derive(Clone, Copy, Debug)]
 pub enum TemperatureUnit {
@@ -25,7 +26,10 @@ pub struct TemperatureModule {
     fixed_width: bool,
     temp_celsius: Arc<AtomicU8>,
     dirty: Arc<AtomicBool>,
-    stop: Arc<AtomicBool>,
+    /// Value color from `color_rules`
+    rule_colors: RuleColors,
+    /// Dropping the module unsubscribes
+    _metrics: Subscription,
 }
 
 impl TemperatureModule {
@@ -37,26 +41,22 @@ impl TemperatureModule {
         unit: TemperatureUnit,
         fixed_width: bool,
     ) -> Self {
-        let initial = Self::fetch_temperature();
-        let temp_celsius = Arc::new(AtomicU8::new(initial));
+        let temp_celsius = Arc::new(AtomicU8::new(0));
         let dirty = Arc::new(AtomicBool::new(true));
-        let stop = Arc::new(AtomicBool::new(false));
 
         let temp_handle = Arc::clone(&temp_celsius);
         let dirty_handle = Arc::clone(&dirty);
-        let stop_handle = Arc::clone(&stop);
-        std::thread::spawn(move || {
-            let mut last = temp_handle.load(Ordering::Relaxed);
-            while !stop_handle.load(Ordering::Relaxed) {
-                let next = Self::fetch_temperature();
-                if next != last {
-                    temp_handle.store(next, Ordering::Relaxed);
-                    dirty_handle.store(true, Ordering::Relaxed);
-                    last = next;
+        let subscription = metrics::subscribe(
+            Metric::Temperature,
+            Duration::from_secs(1),
+            move |snapshot| {
+                // 0 renders as "no reading"
+                let next = snapshot.temperature.unwrap_or(0);
+                if temp_handle.swap(next, Ordering::Relaxed) != next {
+                    mark_dirty(&dirty_handle);
                 }
-                std::thread::sleep(Duration::from_secs(1));
-            }
-        });
+            },
+        );
 
         Self {
             id: id.to_string(),
@@ -66,84 +66,24 @@ impl TemperatureModule {
             fixed_width,
             temp_celsius,
             dirty,
-            stop,
+            rule_colors: RuleColors::default(),
+            _metrics: subscription,
         }
     }
 
-    fn fetch_temperature() -> u8 {
-        // Try multiple methods to get CPU temperature on macOS
-        if let Some(temp) = Self::try_smctemp() {
-            return temp;
-        }
-
-        if let Some(temp) = Self::try_osx_cpu_temp() {
-            return temp;
+    /// Temperature in the configured unit, or a dash before the first reading.
+    fn text(&self) -> String {
+        let temp = self.temp_celsius.load(Ordering::Relaxed);
+        if temp == 0 {
+            return "—".to_string();
         }
-
-        0
-    }
-
-    fn try_smctemp() -> Option<u8> {
-        // smctemp -l lists all sensor keys with values.
-        // TCMb is the main CPU temperature on Apple Silicon.
-        // Try common Homebrew paths since launchd has a minimal PATH.
-        let binary = [
-            "/opt/homebrew/bin/smctemp",
-            "/usr/local/bin/smctemp",
-            "smctemp",
-        ]
-        .iter()
-        .find(|p| std::path::Path::new(p).exists())
-        .copied()
-        .unwrap_or("smctemp");
-        let output = Command::new(binary)
-            .arg("-l")
-            .output()
-            .ok()
-            .and_then(|o| String::from_utf8(o.stdout).ok())?;
-
-        // Look for "TCMb" line - main CPU temperature
-        // Format: "  TCMb  [flt ]  60.0 (bytes: ...)"
-        for line in output.lines() {
-            let trimmed = line.trim();
-            if trimmed.starts_with("TCMb") {
-                // Split on whitespace and find the float value
-                let parts: Vec<&str> = trimmed.split_whitespace().collect();
-                // parts: ["TCMb", "[flt", "]", "60.0", "(bytes:", ...]
-                if let Some(temp_str) = parts.get(3) {
-                    if let Ok(temp) = temp_str.parse::<f32>() {
-                        return Some(temp.round() as u8);
-                    }
-                }
+        match self.unit {
+            TemperatureUnit::Celsius => format!("{}°", temp),
+            TemperatureUnit::Fahrenheit => {
+                let fahrenheit = ((temp as f32 * 9.0 / 5.0) + 32.0).round() as i32;
+                format!("{}°F", fahrenheit)
             }
         }
-        None
-    }
-
-    fn try_osx_cpu_temp() -> Option<u8> {
-        let binary = [
-            "/opt/homebrew/bin/osx-cpu-temp",
-            "/usr/local/bin/osx-cpu-temp",
-            "osx-cpu-temp",
-        ]
-        .iter()
-        .find(|p| std::path::Path::new(p).exists())
-        .copied()
-        .unwrap_or("osx-cpu-temp");
-        let output = Command::new(binary)
-            .output()
-            .ok()
-            .and_then(|o| String::from_utf8(o.stdout).ok())?;
-
-        // Output is like "63.0°C"
-        let temp_str = output.trim().trim_end_matches("°C");
-        let temp = temp_str.parse::<f32>().ok()?;
-        // Only return if we got a non-zero value (osx-cpu-temp returns 0.0 on Apple Silicon)
-        if temp > 0.0 {
-            Some(temp.round() as u8)
-        } else {
-            None
-        }
     }
 }
 
@@ -153,18 +93,8 @@ impl GpuiModule for TemperatureModule {
     }
 
     fn render(&self, theme: &Theme) -> AnyElement {
-        let temp = self.temp_celsius.load(Ordering::Relaxed);
-        let text = if temp > 0 {
-            match self.unit {
-                TemperatureUnit::Celsius => format!("{}°", temp),
-                TemperatureUnit::Fahrenheit => {
-                    let fahrenheit = ((temp as f32 * 9.0 / 5.0) + 32.0).round() as i32;
-                    format!("{}°F", fahrenheit)
-                }
-            }
-        } else {
-            "—".to_string()
-        };
+        let text = self.text();
+        let color = self.rule_colors.text.unwrap_or(theme.foreground);
 
         if let Some(ref label) = self.label {
             // Two-line layout with label - configurable alignment
@@ -193,7 +123,7 @@ impl GpuiModule for TemperatureModule {
                         .min_w(px(if self.fixed_width { value_width } else { 0.0 }))
                         .flex()
                         .justify_end()
-                        .text_color(theme.foreground)
+                        .text_color(color)
                         .text_size(px(theme.font_size * 0.85))
                         .line_height(px(theme.font_size * 0.9))
                         .child(SharedString::from(text)),
@@ -203,7 +133,7 @@ impl GpuiModule for TemperatureModule {
             div()
                 .flex()
                 .items_center()
-                .text_color(theme.foreground)
+                .text_color(color)
                 .text_size(px(theme.font_size * 0.85))
                 .child(SharedString::from(text))
                 .into_any_element()
@@ -214,21 +144,21 @@ impl GpuiModule for TemperatureModule {
         self.dirty.swap(false, Ordering::Relaxed)
     }
 
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
     fn value(&self) -> Option<u8> {
-        // Return inverted value for threshold coloring
-        // Lower temp is "good" (high value), higher temp is "bad" (low value)
-        // Map 30-100°C range to 100-0 value
+        // Rules compare against Celsius regardless of the display unit
         let temp = self.temp_celsius.load(Ordering::Relaxed);
-        if temp == 0 {
-            return None;
-        }
-        let normalized = ((100.0 - temp as f32) / 70.0 * 100.0).clamp(0.0, 100.0);
-        Some(normalized as u8)
+        (temp != 0).then_some(temp)
+    }
+
+    fn display_text(&self) -> Option<String> {
+        Some(self.text())
     }
-}
 
-impl Drop for TemperatureModule {
-    fn drop(&mut self) {
-        self.stop.store(true, Ordering::Relaxed);
+    fn set_rule_colors(&mut self, colors: RuleColors) {
+        self.rule_colors = colors;
     }
 }
@@ -0,0 +1,400 @@
+//! Format templates for module text.
+//!
+//! Modules that accept `format` render it through [`Template`], filling
+//! module-specific placeholders:
+//!
+//! - `{name}` inserts a value; `{{` and `}}` are literal braces.
+//! - `{name:spec}` pads and rounds it. The spec is `[<>^][0][width][.precision]`,
+//!   e.g. `{value:>3}` right-aligns in three columns, `{value:03}` pads with
+//!   zeros and `{watts:.1}` keeps one decimal. Times take a strftime string
+//!   instead, e.g. `{now:%H:%M}`.
+//! - `[...]` is a conditional section, dropped unless every placeholder in it
+//!   has a value; `\[` and `\]` are literal brackets.
+//!
+//! ```toml
+//! { type = "battery", format = "{icon} {value}%[ ({time_left})]" }
+//! ```
+
+use chrono::{DateTime, FixedOffset};
+
+/// A placeholder value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Text(String),
+    Number(f64),
+    Time(DateTime<FixedOffset>),
+}
+
+impl From<&str> for Value {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<u8> for Value {
+    fn from(number: u8) -> Self {
+        Self::Number(f64::from(number))
+    }
+}
+
+impl From<f64> for Value {
+    fn from(number: f64) -> Self {
+        Self::Number(number)
+    }
+}
+
+/// Placeholder values by name. A missing or empty value hides the
+/// conditional section it's in.
+pub type Values<'a> = [(&'a str, Option<Value>)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Align {
+    #[default]
+    Left,
+    Right,
+    Center,
+}
+
+/// Formatting options after the `:` of a placeholder.
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Spec {
+    /// The spec as written (strftime format for times)
+    raw: String,
+    /// Explicit alignment; numbers default to the right
+    align: Option<Align>,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl Spec {
+    fn parse(raw: &str) -> Self {
+        let mut spec = Self {
+            raw: raw.to_string(),
+            ..Self::default()
+        };
+        let mut rest = raw;
+        spec.align = match rest.chars().next() {
+            Some('<') => Some(Align::Left),
+            Some('>') => Some(Align::Right),
+            Some('^') => Some(Align::Center),
+            _ => None,
+        };
+        if spec.align.is_some() {
+            rest = &rest[1..];
+        }
+        if let Some(stripped) = rest.strip_prefix('0') {
+            spec.zero = true;
+            rest = stripped;
+        }
+        let (width, precision) = match rest.split_once('.') {
+            Some((width, precision)) => (width, precision.parse().ok()),
+            None => (rest, None),
+        };
+        spec.width = width.parse().unwrap_or(0);
+        spec.precision = precision;
+        spec
+    }
+
+    fn apply(&self, value: &Value) -> String {
+        let (text, numeric) = match value {
+            Value::Text(text) => (text.clone(), false),
+            Value::Number(number) => (format_number(*number, self.precision), true),
+            Value::Time(time) => {
+                let format = if self.raw.is_empty() {
+                    "%H:%M"
+                } else {
+                    &self.raw
+                };
+                return time.format(format).to_string();
+            }
+        };
+        let len = text.chars().count();
+        if len >= self.width {
+            return text;
+        }
+        let pad = self.width - len;
+        let align = self
+            .align
+            .unwrap_or(if numeric { Align::Right } else { Align::Left });
+        if self.zero && numeric && align == Align::Right {
+            // Zeros go after the sign
+            return match text.strip_prefix('-') {
+                Some(digits) => format!("-{}{}", "0".repeat(pad), digits),
+                None => format!("{}{}", "0".repeat(pad), text),
+            };
+        }
+        match align {
+            Align::Left => format!("{}{}", text, " ".repeat(pad)),
+            Align::Right => format!("{}{}", " ".repeat(pad), text),
+            Align::Center => format!(
+                "{}{}{}",
+                " ".repeat(pad / 2),
+                text,
+                " ".repeat(pad - pad / 2)
+            ),
+        }
+    }
+}
+
+/// Whole numbers print without decimals unless a precision is given.
+fn format_number(number: f64, precision: Option<usize>) -> String {
+    match precision {
+        Some(precision) => format!("{:.*}", precision, number),
+        None if number.fract() == 0.0 => format!("{:.0}", number),
+        None => format!("{}", number),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Field { name: String, spec: Spec },
+    Section(Vec<Part>),
+}
+
+/// A parsed format string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Parses a format string.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut chars = input.chars().peekable();
+        let parts = parse_parts(&mut chars, false)?;
+        Ok(Self { parts })
+    }
+
+    /// Parses `input`, falling back to showing it verbatim if it's invalid.
+    pub fn parse_or_literal(input: &str) -> Self {
+        Self::parse(input).unwrap_or_else(|err| {
+            log::warn!("Invalid format '{}': {}", input, err);
+            Self {
+                parts: vec![Part::Literal(input.to_string())],
+            }
+        })
+    }
+
+    /// Parses a clock or date format. Plain strftime strings (no `{`) keep
+    /// working and are treated as `{now:FORMAT}`.
+    pub fn for_time(format: &str) -> Self {
+        if format.contains('{') {
+            Self::parse_or_literal(format)
+        } else {
+            Self {
+                parts: vec![Part::Field {
+                    name: "now".to_string(),
+                    spec: Spec::parse(format),
+                }],
+            }
+        }
+    }
+
+    /// Returns true if the template uses the placeholder `name`.
+    pub fn uses(&self, name: &str) -> bool {
+        fn walk(parts: &[Part], name: &str) -> bool {
+            parts.iter().any(|part| match part {
+                Part::Field { name: field, .. } => field == name,
+                Part::Section(parts) => walk(parts, name),
+                Part::Literal(_) => false,
+            })
+        }
+        walk(&self.parts, name)
+    }
+
+    /// Renders the template. Unknown placeholders render empty.
+    pub fn render(&self, values: &Values) -> String {
+        let mut out = String::new();
+        render_parts(&self.parts, values, &mut out);
+        out
+    }
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+fn parse_parts(chars: &mut Chars, in_section: bool) -> Result<Vec<Part>, String> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let flush = |literal: &mut String, parts: &mut Vec<Part>| {
+        if !literal.is_empty() {
+            parts.push(Part::Literal(std::mem::take(literal)));
+        }
+    };
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '}' => return Err("unmatched '}'".to_string()),
+            '{' => {
+                flush(&mut literal, &mut parts);
+                let mut field = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => field.push(c),
+                        None => return Err("unclosed '{'".to_string()),
+                    }
+                }
+                let (name, spec) = field.split_once(':').unwrap_or((&field, ""));
+                let name = name.trim();
+                if name.is_empty() {
+                    return Err("empty placeholder".to_string());
+                }
+                parts.push(Part::Field {
+                    name: name.to_string(),
+                    spec: Spec::parse(spec),
+                });
+            }
+            '\\' if matches!(chars.peek(), Some('[' | ']' | '\\')) => {
+                literal.push(chars.next().unwrap_or('\\'));
+            }
+            '[' => {
+                flush(&mut literal, &mut parts);
+                parts.push(Part::Section(parse_parts(chars, true)?));
+            }
+            ']' if in_section => {
+                flush(&mut literal, &mut parts);
+                return Ok(parts);
+            }
+            ']' => return Err("unmatched ']'".to_string()),
+            c => literal.push(c),
+        }
+    }
+    if in_section {
+        return Err("unclosed '['".to_string());
+    }
+    flush(&mut literal, &mut parts);
+    Ok(parts)
+}
+
+fn lookup<'v>(values: &'v Values, name: &str) -> Option<&'v Value> {
+    values
+        .iter()
+        .find(|(key, _)| *key == name)
+        .and_then(|(_, value)| value.as_ref())
+        .filter(|value| !matches!(value, Value::Text(text) if text.is_empty()))
+}
+
+/// Returns false if any placeholder in `parts` (including nested sections)
+/// has no value.
+fn all_present(parts: &[Part], values: &Values) -> bool {
+    parts.iter().all(|part| match part {
+        Part::Field { name, .. } => lookup(values, name).is_some(),
+        Part::Section(_) | Part::Literal(_) => true,
+    })
+}
+
+fn render_parts(parts: &[Part], values: &Values, out: &mut String) {
+    for part in parts {
+        match part {
+            Part::Literal(text) => out.push_str(text),
+            Part::Field { name, spec } => {
+                if let Some(value) = lookup(values, name) {
+                    out.push_str(&spec.apply(value));
+                }
+            }
+            Part::Section(inner) => {
+                if all_present(inner, values) {
+                    render_parts(inner, values, out);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    fn render(format: &str, values: &Values) -> String {
+        Template::parse(format).unwrap().render(values)
+    }
+
+    #[test]
+    fn fills_placeholders_and_escapes() {
+        let values = [("icon", Some("⚡".into())), ("value", Some(87u8.into()))];
+        assert_eq!(render("{icon} {value}%", &values), "⚡ 87%");
+        assert_eq!(render("{{{value}}}", &values), "{87}");
+        assert_eq!(render("{missing}|", &values), "|");
+    }
+
+    #[test]
+    fn applies_width_and_precision() {
+        let values = [
+            ("n", Some(7u8.into())),
+            ("w", Some(8.44.into())),
+            ("s", Some("ab".into())),
+        ];
+        assert_eq!(render("{n:3}", &values), "  7");
+        assert_eq!(render("{n:03}", &values), "007");
+        assert_eq!(render("{n:<3}|", &values), "7  |");
+        assert_eq!(render("{w:.1}", &values), "8.4");
+        assert_eq!(render("{w:>6.2}", &values), "  8.44");
+        assert_eq!(render("{s:^6}|", &values), "  ab  |");
+        assert_eq!(render("{s:4}|", &values), "ab  |");
+    }
+
+    #[test]
+    fn formats_times_with_strftime() {
+        let time = chrono::TimeZone::with_ymd_and_hms(&Local, 2026, 3, 9, 14, 5, 0)
+            .unwrap()
+            .fixed_offset();
+        let values = [("now", Some(Value::Time(time)))];
+        assert_eq!(render("{now:%a %H:%M}", &values), "Mon 14:05");
+        assert_eq!(render("{now}", &values), "14:05");
+    }
+
+    #[test]
+    fn drops_sections_with_missing_values() {
+        let format = "{value}%[ ({time_left})]";
+        let with = [
+            ("value", Some(50u8.into())),
+            ("time_left", Some("2:10".into())),
+        ];
+        let without = [("value", Some(50u8.into())), ("time_left", None)];
+        let empty = [("value", Some(50u8.into())), ("time_left", Some("".into()))];
+        assert_eq!(render(format, &with), "50% (2:10)");
+        assert_eq!(render(format, &without), "50%");
+        assert_eq!(render(format, &empty), "50%");
+        assert_eq!(render("\\[{value}\\]", &with), "[50]");
+    }
+
+    #[test]
+    fn rejects_malformed_templates() {
+        assert!(Template::parse("{value").is_err());
+        assert!(Template::parse("value}").is_err());
+        assert!(Template::parse("[{value}").is_err());
+        assert!(Template::parse("{}").is_err());
+        assert_eq!(Template::parse_or_literal("{oops").render(&[]), "{oops");
+        assert!(Template::parse("{a} [{b}]").unwrap().uses("b"));
+    }
+
+    #[test]
+    fn plain_strftime_formats_still_work() {
+        let time = chrono::TimeZone::with_ymd_and_hms(&Local, 2026, 3, 9, 14, 5, 0)
+            .unwrap()
+            .fixed_offset();
+        let values = [("now", Some(Value::Time(time)))];
+        assert_eq!(Template::for_time("[%H:%M]").render(&values), "[14:05]");
+        assert_eq!(
+            Template::for_time("{now:%d}/{now:%m}").render(&values),
+            "09/03"
+        );
+    }
+}
@@ -0,0 +1,521 @@
+//! Time tracking module.
+//!
+//! A toggle module: click to start a timer, click again to stop it. While
+//! running the bar shows the elapsed time; when stopped it shows today's
+//! total. Sessions are kept in `~/.local/state/sinew/timetrack.json`, so a
+//! running timer survives restarts.
+//!
+//! With `toggl_token` and `toggl_workspace` set, each session is also started
+//! and stopped as a Toggl Track time entry (the token can be a
+//! `secret:keychain/...` reference).
+//!
+//! With `idle_stop` set, a running timer stops by itself once there's been
+//! no input for that many seconds, ending the session at the last input.
+//!
+//! ```toml
+//! { type = "timetrack", project = "Client work", toggl_token = "secret:keychain/sinew/toggl", toggl_workspace = 123456 }
+//! ```
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Local, SecondsFormat, TimeZone, Utc};
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+use serde::{Deserialize, Serialize};
+
+use super::{until_next_second, GpuiModule};
+use crate::gpui_app::primitives::icons;
+use crate::gpui_app::scheduler;
+use crate::gpui_app::theme::Theme;
+use crate::system::idle;
+
+/// Sessions older than this are dropped when saving.
+const MAX_AGE_SECS: i64 = 90 * 24 * 60 * 60;
+const TOGGL_API: &str = "https://api.track.toggl.com/api/v9";
+/// How often a running timer checks for idle time.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// A finished session (Unix timestamps, seconds).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub start: i64,
+    pub end: i64,
+    pub project: String,
+}
+
+/// The running timer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Running {
+    pub start: i64,
+    pub project: String,
+    /// Toggl time entry id, once Toggl has answered
+    #[serde(default)]
+    pub toggl_id: Option<u64>,
+}
+
+/// Persisted timer state.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeStore {
+    #[serde(default)]
+    pub running: Option<Running>,
+    #[serde(default)]
+    pub sessions: Vec<Session>,
+}
+
+impl TimeStore {
+    /// Starts the timer unless it's already running.
+    pub fn start(&mut self, now: i64, project: &str) -> bool {
+        if self.running.is_some() {
+            return false;
+        }
+        self.running = Some(Running {
+            start: now,
+            project: project.to_string(),
+            toggl_id: None,
+        });
+        true
+    }
+
+    /// Stops the timer, recording the session.
+    pub fn stop(&mut self, now: i64) -> Option<Running> {
+        let running = self.running.take()?;
+        self.sessions.push(Session {
+            start: running.start,
+            end: now.max(running.start),
+            project: running.project.clone(),
+        });
+        Some(running)
+    }
+
+    /// Seconds tracked within `[from, to)`, counting the running timer up
+    /// to `now`.
+    pub fn total_between(&self, from: i64, to: i64, now: i64) -> i64 {
+        let running = self.running.as_ref().map(|r| (r.start, now));
+        self.sessions
+            .iter()
+            .map(|s| (s.start, s.end))
+            .chain(running)
+            .map(|(start, end)| (end.min(to) - start.max(from)).max(0))
+            .sum()
+    }
+
+    /// Drops sessions that ended before `cutoff`.
+    pub fn prune(&mut self, cutoff: i64) {
+        self.sessions.retain(|s| s.end >= cutoff);
+    }
+
+    fn path() -> PathBuf {
+        crate::config::get_state_dir().join("timetrack.json")
+    }
+
+    fn load() -> Self {
+        std::fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&mut self, now: i64) {
+        self.prune(now - MAX_AGE_SECS);
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let result = serde_json::to_string_pretty(self)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+        if let Err(err) = result {
+            log::warn!("timetrack: failed to save sessions: {}", err);
+        }
+    }
+}
+
+/// Formats seconds as `H:MM`, or `H:MM:SS` with `seconds`.
+pub fn format_duration(secs: i64, seconds: bool) -> String {
+    let secs = secs.max(0);
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if seconds {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", h, m)
+    }
+}
+
+/// When a timer should stop after `idle` seconds without input: at the
+/// last input, once `idle` reaches `limit`.
+pub fn idle_stop_end(now: i64, idle: i64, limit: i64) -> Option<i64> {
+    (idle >= limit).then_some(now - idle)
+}
+
+/// Toggl Track credentials.
+#[derive(Debug, Clone)]
+pub struct TogglConfig {
+    pub token: String,
+    pub workspace: u64,
+}
+
+impl TogglConfig {
+    /// Sends a request with the token passed on stdin, so it doesn't show
+    /// up in the process list.
+    fn request(&self, method: &str, path: &str, body: Option<&str>) -> Option<serde_json::Value> {
+        let url = format!("{}/workspaces/{}{}", TOGGL_API, self.workspace, path);
+        let mut args = vec![
+            "-s",
+            "-m",
+            "10",
+            "-K",
+            "-",
+            "-X",
+            method,
+            "-H",
+            "Content-Type: application/json",
+        ];
+        if let Some(body) = body {
+            args.extend(["-d", body]);
+        }
+        args.push(&url);
+        let mut child = Command::new("curl")
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .ok()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = writeln!(stdin, "user = \"{}:api_token\"", self.token);
+        }
+        let output = child.wait_with_output().ok()?;
+        serde_json::from_slice(&output.stdout).ok()
+    }
+
+    /// Starts a running time entry and returns its id.
+    fn start(&self, start: i64, description: &str) -> Option<u64> {
+        let start = DateTime::<Utc>::from_timestamp(start, 0)?;
+        let body = serde_json::json!({
+            "created_with": "sinew",
+            "description": description,
+            "workspace_id": self.workspace,
+            "start": start.to_rfc3339_opts(SecondsFormat::Secs, true),
+            "duration": -1,
+        });
+        let response = self.request("POST", "/time_entries", Some(&body.to_string()))?;
+        response.get("id").and_then(|id| id.as_u64())
+    }
+
+    /// Stops a running entry at `end`.
+    fn stop(&self, entry: u64, end: i64) {
+        let stopped = DateTime::<Utc>::from_timestamp(end, 0).and_then(|end| {
+            let body = serde_json::json!({
+                "stop": end.to_rfc3339_opts(SecondsFormat::Secs, true),
+            });
+            self.request(
+                "PUT",
+                &format!("/time_entries/{}", entry),
+                Some(&body.to_string()),
+            )
+        });
+        if stopped.is_none() {
+            log::warn!("timetrack: failed to stop Toggl entry {}", entry);
+        }
+    }
+}
+
+/// Stops the running timer at `end`, saving the session and stopping its
+/// Toggl entry. Returns false if no timer was running.
+fn stop_timer(store: &Mutex<TimeStore>, toggl: Option<&TogglConfig>, end: i64) -> bool {
+    let Ok(mut store) = store.lock() else {
+        return false;
+    };
+    let Some(running) = store.stop(end) else {
+        return false;
+    };
+    store.save(Local::now().timestamp());
+    if let (Some(toggl), Some(entry)) = (toggl.cloned(), running.toggl_id) {
+        let end = end.max(running.start);
+        std::thread::spawn(move || toggl.stop(entry, end));
+    }
+    true
+}
+
+/// Time tracking toggle module.
+pub struct TimeTrackModule {
+    id: String,
+    project: String,
+    toggl: Option<TogglConfig>,
+    store: Arc<Mutex<TimeStore>>,
+    last_text: String,
+    /// Stops the idle check
+    stop_idle_check: Arc<AtomicBool>,
+}
+
+impl TimeTrackModule {
+    /// Creates a new time tracking module.
+    ///
+    /// @param id - Unique module identifier
+    /// @param project - Description recorded for new sessions
+    /// @param toggl - Toggl credentials to sync sessions (None = local only)
+    /// @param idle_stop - Idle time after which a running timer stops
+    pub fn new(
+        id: &str,
+        project: &str,
+        toggl: Option<TogglConfig>,
+        idle_stop: Option<Duration>,
+    ) -> Self {
+        let store = Arc::new(Mutex::new(TimeStore::load()));
+        let stop_idle_check = Arc::new(AtomicBool::new(false));
+        if let Some(limit) = idle_stop {
+            let store = Arc::clone(&store);
+            let toggl = toggl.clone();
+            let id = id.to_string();
+            scheduler::schedule(
+                IDLE_CHECK_INTERVAL,
+                Arc::clone(&stop_idle_check),
+                move || {
+                    let running = store.lock().is_ok_and(|store| store.running.is_some());
+                    if !running {
+                        return;
+                    }
+                    let now = Local::now().timestamp();
+                    let idle = idle::idle_time().as_secs() as i64;
+                    let Some(end) = idle_stop_end(now, idle, limit.as_secs() as i64) else {
+                        return;
+                    };
+                    if stop_timer(&store, toggl.as_ref(), end) {
+                        log::info!("timetrack '{}': stopped after {}s idle", id, idle);
+                        crate::gpui_app::request_immediate_refresh();
+                    }
+                },
+            );
+        }
+        Self {
+            id: id.to_string(),
+            project: project.to_string(),
+            toggl,
+            store,
+            last_text: String::new(),
+            stop_idle_check,
+        }
+    }
+
+    /// Bar text: elapsed time while running, otherwise today's total.
+    fn text(&self) -> (String, bool) {
+        let now = Local::now();
+        let Ok(store) = self.store.lock() else {
+            return (String::new(), false);
+        };
+        match &store.running {
+            Some(running) => (format_duration(now.timestamp() - running.start, true), true),
+            None => {
+                let midnight = now
+                    .date_naive()
+                    .and_hms_opt(0, 0, 0)
+                    .and_then(|t| Local.from_local_datetime(&t).earliest())
+                    .map(|t| t.timestamp())
+                    .unwrap_or(now.timestamp());
+                let total = store.total_between(midnight, now.timestamp(), now.timestamp());
+                (format_duration(total, false), false)
+            }
+        }
+    }
+
+    fn start(&self) {
+        let now = Local::now().timestamp();
+        let Ok(mut store) = self.store.lock() else {
+            return;
+        };
+        if !store.start(now, &self.project) {
+            return;
+        }
+        store.save(now);
+        let Some(toggl) = self.toggl.clone() else {
+            return;
+        };
+        let shared = Arc::clone(&self.store);
+        let project = self.project.clone();
+        std::thread::spawn(move || {
+            let Some(entry) = toggl.start(now, &project) else {
+                log::warn!("timetrack: failed to start Toggl entry");
+                return;
+            };
+            let attached = shared
+                .lock()
+                .is_ok_and(|mut store| match store.running.as_mut() {
+                    Some(running) if running.start == now => {
+                        running.toggl_id = Some(entry);
+                        store.save(Local::now().timestamp());
+                        true
+                    }
+                    _ => false,
+                });
+            // Stopped before Toggl answered
+            if !attached {
+                toggl.stop(entry, Local::now().timestamp());
+            }
+        });
+    }
+
+    fn stop(&self) {
+        stop_timer(&self.store, self.toggl.as_ref(), Local::now().timestamp());
+    }
+}
+
+impl Drop for TimeTrackModule {
+    fn drop(&mut self) {
+        self.stop_idle_check.store(true, Ordering::SeqCst);
+    }
+}
+
+impl GpuiModule for TimeTrackModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let (text, running) = self.text();
+        let color = if running {
+            theme.foreground
+        } else {
+            theme.foreground_muted
+        };
+
+        div()
+            .flex()
+            .items_center()
+            .text_color(color)
+            .text_size(px(theme.font_size))
+            .child(SharedString::from(format!(
+                "{} {}",
+                icons::system::TIMER,
+                text
+            )))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        let (text, _) = self.text();
+        if text == self.last_text {
+            return false;
+        }
+        self.last_text = text;
+        true
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        Some(until_next_second())
+    }
+
+    fn display_text(&self) -> Option<String> {
+        Some(format!("{} {}", icons::system::TIMER, self.text().0))
+    }
+
+    fn toggle_state(&self) -> Option<bool> {
+        Some(
+            self.store
+                .lock()
+                .map(|store| store.running.is_some())
+                .unwrap_or(false),
+        )
+    }
+
+    fn on_toggle(&mut self, active: bool) {
+        if active {
+            self.start();
+        } else {
+            self.stop();
+        }
+        self.last_text.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_and_stop_record_a_session() {
+        let mut store = TimeStore::default();
+        assert!(store.start(100, "Work"));
+        assert!(!store.start(150, "Other"));
+        let running = store.stop(400).unwrap();
+        assert_eq!(running.project, "Work");
+        assert_eq!(
+            store.sessions,
+            vec![Session {
+                start: 100,
+                end: 400,
+                project: "Work".into(),
+            }]
+        );
+        assert!(store.stop(500).is_none());
+    }
+
+    #[test]
+    fn totals_clip_to_range_and_include_running() {
+        let mut store = TimeStore::default();
+        store.sessions.push(Session {
+            start: 0,
+            end: 200,
+            project: "a".into(),
+        });
+        store.sessions.push(Session {
+            start: 300,
+            end: 400,
+            project: "b".into(),
+        });
+        store.start(900, "c");
+        // 100..200 + 300..400 + 900..1000
+        assert_eq!(store.total_between(100, 2000, 1000), 300);
+    }
+
+    #[test]
+    fn prunes_old_sessions() {
+        let mut store = TimeStore::default();
+        store.sessions.push(Session {
+            start: 0,
+            end: 10,
+            project: "old".into(),
+        });
+        store.sessions.push(Session {
+            start: 100,
+            end: 200,
+            project: "new".into(),
+        });
+        store.prune(50);
+        assert_eq!(store.sessions.len(), 1);
+        assert_eq!(store.sessions[0].project, "new");
+    }
+
+    #[test]
+    fn store_round_trips_through_json() {
+        let mut store = TimeStore::default();
+        store.start(100, "Work");
+        store.running.as_mut().unwrap().toggl_id = Some(42);
+        let json = serde_json::to_string(&store).unwrap();
+        assert_eq!(serde_json::from_str::<TimeStore>(&json).unwrap(), store);
+        assert_eq!(
+            serde_json::from_str::<TimeStore>("{}").unwrap(),
+            TimeStore::default()
+        );
+    }
+
+    #[test]
+    fn idle_stop_ends_at_last_input() {
+        assert_eq!(idle_stop_end(1000, 299, 300), None);
+        assert_eq!(idle_stop_end(1000, 300, 300), Some(700));
+        // Stopping before the start records an empty session
+        let mut store = TimeStore::default();
+        store.start(800, "Work");
+        store.stop(idle_stop_end(1000, 600, 300).unwrap());
+        assert_eq!(store.sessions[0].end, 800);
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(3725, true), "1:02:05");
+        assert_eq!(format_duration(3725, false), "1:02");
+        assert_eq!(format_duration(-5, false), "0:00");
+    }
+}
@@ -0,0 +1,105 @@
+//! Named timezones shared by the clock module and the calendar popup.
+//!
+//! Zones are IANA names (`"Asia/Tokyo"`, `"UTC"`) resolved with chrono-tz,
+//! so offsets follow daylight saving time, or `"Local"` for the system zone.
+
+use chrono::{DateTime, FixedOffset, Local, Offset, Utc};
+use chrono_tz::Tz;
+
+/// A timezone to show times in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeZone {
+    /// The system timezone
+    Local,
+    Named(Tz),
+}
+
+impl TimeZone {
+    /// Parses `"Local"` or an IANA name, returning None for unknown zones.
+    pub fn parse(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("local") {
+            return Some(Self::Local);
+        }
+        name.parse::<Tz>().ok().map(Self::Named)
+    }
+
+    /// Short name for the bar: the city part of the IANA name
+    /// ("America/New_York" → "New York"), or "UTC".
+    pub fn label(&self) -> String {
+        match self {
+            Self::Local => "Local".to_string(),
+            Self::Named(tz) => {
+                let name = tz.name();
+                name.rsplit('/').next().unwrap_or(name).replace('_', " ")
+            }
+        }
+    }
+
+    /// The time at `utc` in this zone.
+    pub fn at(&self, utc: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self {
+            Self::Local => utc.with_timezone(&Local).fixed_offset(),
+            Self::Named(tz) => {
+                let time = utc.with_timezone(tz);
+                time.with_timezone(&time.offset().fix())
+            }
+        }
+    }
+
+    /// The current time in this zone.
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        self.at(Utc::now())
+    }
+
+    /// Offset from UTC at `utc`, in minutes.
+    pub fn offset_minutes(&self, utc: DateTime<Utc>) -> i32 {
+        self.at(utc).offset().local_minus_utc() / 60
+    }
+}
+
+/// Formats an offset in minutes as hours: "+9", "-5", "+5:30".
+pub fn format_offset(minutes: i32) -> String {
+    let sign = if minutes < 0 { '-' } else { '+' };
+    let (hours, rest) = (minutes.abs() / 60, minutes.abs() % 60);
+    if rest == 0 {
+        format!("{}{}", sign, hours)
+    } else {
+        format!("{}{}:{:02}", sign, hours, rest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone as _;
+
+    #[test]
+    fn parses_local_and_iana_names() {
+        assert_eq!(TimeZone::parse("Local"), Some(TimeZone::Local));
+        assert_eq!(TimeZone::parse("UTC"), Some(TimeZone::Named(Tz::UTC)));
+        assert_eq!(TimeZone::parse("Mars/Olympus"), None);
+        assert_eq!(
+            TimeZone::parse("America/New_York").unwrap().label(),
+            "New York"
+        );
+        assert_eq!(TimeZone::parse("UTC").unwrap().label(), "UTC");
+    }
+
+    #[test]
+    fn named_offsets_follow_daylight_saving() {
+        let new_york = TimeZone::parse("America/New_York").unwrap();
+        let winter = Utc.with_ymd_and_hms(2026, 1, 15, 12, 0, 0).unwrap();
+        let summer = Utc.with_ymd_and_hms(2026, 7, 15, 12, 0, 0).unwrap();
+        assert_eq!(new_york.offset_minutes(winter), -300);
+        assert_eq!(new_york.offset_minutes(summer), -240);
+        assert_eq!(new_york.at(summer).format("%H:%M").to_string(), "08:00");
+    }
+
+    #[test]
+    fn formats_offsets_in_hours() {
+        assert_eq!(format_offset(540), "+9");
+        assert_eq!(format_offset(-300), "-5");
+        assert_eq!(format_offset(330), "+5:30");
+        assert_eq!(format_offset(0), "+0");
+    }
+}
@@ -0,0 +1,557 @@
+//! Package updates module.
+//!
+//! Runs `brew outdated` (and `mas outdated` with `mas = true`) in the
+//! background every `interval` seconds and shows the number of pending
+//! updates. Clicking opens a popup listing the outdated packages with an
+//! "Update all" button that runs `update_command` in Terminal.
+//!
+//! ```toml
+//! { type = "updates", mas = true, interval = 3600 }
+//! ```
+
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+use serde::Deserialize;
+
+use super::{mark_dirty, sleep_unless_stopped, GpuiModule, PopupAction, PopupSpec};
+use crate::gpui_app::popup_manager::{notify_popup_needs_render, request_hide_popup};
+use crate::gpui_app::primitives::{icons, Button};
+use crate::gpui_app::theme::{ButtonVariant, Theme};
+use crate::system::metrics::homebrew_binary;
+
+const POPUP_WIDTH: f64 = 360.0;
+const POPUP_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 24.0;
+const FOOTER_HEIGHT: f32 = 36.0;
+const ROW_HEIGHT: f32 = 28.0;
+const MAX_ROWS: usize = 12;
+
+/// Where an outdated package comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Formula,
+    Cask,
+    AppStore,
+}
+
+impl Source {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Formula => "formula",
+            Self::Cask => "cask",
+            Self::AppStore => "App Store",
+        }
+    }
+}
+
+/// An outdated package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    pub name: String,
+    pub source: Source,
+    pub installed: String,
+    pub latest: String,
+    /// Pinned formulae are listed but skipped by `brew upgrade`
+    pub pinned: bool,
+}
+
+/// `installed_versions` is a list for formulae and, in older Homebrew
+/// releases, a single string for casks.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Versions {
+    List(Vec<String>),
+    One(String),
+}
+
+impl Versions {
+    fn newest(&self) -> String {
+        match self {
+            Self::List(versions) => versions.last().cloned().unwrap_or_default(),
+            Self::One(version) => version.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct BrewPackage {
+    name: String,
+    installed_versions: Versions,
+    current_version: String,
+    #[serde(default)]
+    pinned: bool,
+}
+
+#[derive(Deserialize)]
+struct BrewOutdated {
+    #[serde(default)]
+    formulae: Vec<BrewPackage>,
+    #[serde(default)]
+    casks: Vec<BrewPackage>,
+}
+
+/// Parses `brew outdated --json=v2`.
+pub fn parse_brew(json: &str) -> Result<Vec<Package>, String> {
+    let outdated: BrewOutdated =
+        serde_json::from_str(json).map_err(|e| format!("Unexpected brew output: {}", e))?;
+    let packages = |list: Vec<BrewPackage>, source: Source| {
+        list.into_iter().map(move |p| Package {
+            name: p.name,
+            source,
+            installed: p.installed_versions.newest(),
+            latest: p.current_version,
+            pinned: p.pinned,
+        })
+    };
+    Ok(packages(outdated.formulae, Source::Formula)
+        .chain(packages(outdated.casks, Source::Cask))
+        .collect())
+}
+
+/// Parses `mas outdated` lines like `497799835 Xcode (15.0 -> 15.1)`.
+pub fn parse_mas(output: &str) -> Vec<Package> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (_, rest) = line.trim().split_once(' ')?;
+            let (name, versions) = rest.rsplit_once(" (")?;
+            let versions = versions.strip_suffix(')')?;
+            let (installed, latest) = versions.split_once(" -> ").unwrap_or(("", versions));
+            Some(Package {
+                name: name.trim().to_string(),
+                source: Source::AppStore,
+                installed: installed.to_string(),
+                latest: latest.to_string(),
+                pinned: false,
+            })
+        })
+        .collect()
+}
+
+/// Quotes `text` as an AppleScript string literal.
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn command_stdout(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .env("HOMEBREW_NO_AUTO_UPDATE", "1")
+        .output()
+        .map_err(|e| format!("Can't run {}: {}", program, e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.lines().last().unwrap_or("").trim();
+        return Err(format!("{} failed: {}", program, message));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Checks Homebrew (and the App Store) for outdated packages.
+fn check(mas: bool, brew_update: bool) -> Result<Vec<Package>, String> {
+    let brew = homebrew_binary("brew");
+    if brew_update {
+        // Stale tap metadata only means fewer updates, so keep going
+        if let Err(err) = command_stdout(&brew, &["update", "--quiet"]) {
+            log::warn!("updates: {}", err);
+        }
+    }
+    let mut packages = parse_brew(&command_stdout(&brew, &["outdated", "--json=v2"])?)?;
+    if mas {
+        match command_stdout(&homebrew_binary("mas"), &["outdated"]) {
+            Ok(output) => packages.extend(parse_mas(&output)),
+            Err(err) => log::warn!("updates: {}", err),
+        }
+    }
+    Ok(packages)
+}
+
+/// Latest check result.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct UpdatesState {
+    packages: Vec<Package>,
+    /// Set when brew couldn't be run
+    error: Option<String>,
+    loaded: bool,
+}
+
+impl UpdatesState {
+    /// Updates `brew upgrade` would install.
+    fn pending(&self) -> usize {
+        self.packages.iter().filter(|p| !p.pinned).count()
+    }
+}
+
+/// State shared between the bar and popup copies of the module.
+struct Shared {
+    mas: bool,
+    brew_update: bool,
+    state: Mutex<UpdatesState>,
+    dirty: AtomicBool,
+    stop: AtomicBool,
+}
+
+impl Shared {
+    fn refresh(&self, id: &str) {
+        let next = match check(self.mas, self.brew_update) {
+            Ok(packages) => UpdatesState {
+                packages,
+                error: None,
+                loaded: true,
+            },
+            Err(err) => {
+                log::warn!("updates: {}", err);
+                UpdatesState {
+                    packages: Vec::new(),
+                    error: Some(err),
+                    loaded: true,
+                }
+            }
+        };
+        if let Ok(mut guard) = self.state.lock() {
+            if *guard != next {
+                *guard = next;
+                mark_dirty(&self.dirty);
+            }
+        }
+        notify_popup_needs_render(id);
+    }
+}
+
+/// Package updates module.
+pub struct UpdatesModule {
+    id: String,
+    update_command: String,
+    shared: Arc<Shared>,
+}
+
+impl UpdatesModule {
+    /// Creates a new updates module and starts checking in the background.
+    ///
+    /// @param id - Unique module identifier
+    /// @param mas - Also check App Store apps with `mas`
+    /// @param brew_update - Run `brew update` before each check
+    /// @param interval - Seconds between checks
+    /// @param update_command - Shell command "Update all" runs in Terminal
+    pub fn new(
+        id: &str,
+        mas: bool,
+        brew_update: bool,
+        interval: u64,
+        update_command: &str,
+    ) -> Self {
+        let shared = Arc::new(Shared {
+            mas,
+            brew_update,
+            state: Mutex::new(UpdatesState::default()),
+            dirty: AtomicBool::new(true),
+            stop: AtomicBool::new(false),
+        });
+
+        let handle = Arc::clone(&shared);
+        let worker_id = id.to_string();
+        std::thread::spawn(move || {
+            while !handle.stop.load(Ordering::Relaxed) {
+                handle.refresh(&worker_id);
+                sleep_unless_stopped(Duration::from_secs(interval.max(60)), &handle.stop);
+            }
+        });
+
+        Self {
+            id: id.to_string(),
+            update_command: update_command.to_string(),
+            shared,
+        }
+    }
+
+    /// Returns a second handle on the same state for the popup registry.
+    pub fn popup_handle(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            update_command: self.update_command.clone(),
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    fn state(&self) -> UpdatesState {
+        self.shared
+            .state
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+
+    /// Runs `update_command` in a new Terminal window.
+    fn update_all(&self) {
+        let script = format!(
+            "tell application \"Terminal\"\n\
+             activate\n\
+             do script {}\n\
+             end tell",
+            applescript_string(&self.update_command)
+        );
+        std::thread::spawn(move || {
+            if let Err(err) = Command::new("osascript").args(["-e", &script]).status() {
+                log::warn!("updates: can't open Terminal: {}", err);
+            }
+        });
+    }
+}
+
+/// Icon and pending count, or a dash until the first successful check.
+fn bar_text(state: &UpdatesState) -> String {
+    if !state.loaded || state.error.is_some() {
+        format!("{} –", icons::system::UPDATE)
+    } else {
+        format!("{} {}", icons::system::UPDATE, state.pending())
+    }
+}
+
+impl GpuiModule for UpdatesModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let state = self.state();
+        let text = bar_text(&state);
+        let color = if state.loaded && state.error.is_none() && state.pending() > 0 {
+            theme.foreground
+        } else {
+            theme.foreground_muted
+        };
+
+        div()
+            .flex()
+            .items_center()
+            .text_color(color)
+            .text_size(px(theme.font_size))
+            .child(SharedString::from(text))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        self.shared.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn is_loading(&self) -> bool {
+        !self.state().loaded
+    }
+
+    fn value(&self) -> Option<u8> {
+        let state = self.state();
+        state.loaded.then(|| state.pending().min(100) as u8)
+    }
+
+    fn display_text(&self) -> Option<String> {
+        Some(bar_text(&self.state()))
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let rows = self.state().packages.len().clamp(1, MAX_ROWS);
+        let height = POPUP_PADDING * 2.0 + HEADER_HEIGHT + rows as f32 * ROW_HEIGHT + FOOTER_HEIGHT;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let state = self.state();
+        let pending = state.pending();
+
+        let header = div()
+            .h(px(HEADER_HEIGHT))
+            .flex()
+            .items_center()
+            .justify_between()
+            .child(
+                div()
+                    .font_weight(gpui::FontWeight::SEMIBOLD)
+                    .child("Updates"),
+            )
+            .child(
+                div()
+                    .text_color(theme.foreground_muted)
+                    .child(SharedString::from(format!("{} outdated", pending))),
+            );
+
+        let message_row = |message: SharedString| {
+            div()
+                .h(px(ROW_HEIGHT))
+                .flex()
+                .items_center()
+                .text_color(theme.foreground_muted)
+                .child(message)
+        };
+        let mut list = div().flex().flex_col();
+        if !state.loaded {
+            list = list.child(message_row("Checking…".into()));
+        } else if let Some(err) = &state.error {
+            list = list.child(message_row(SharedString::from(err.clone())));
+        } else if state.packages.is_empty() {
+            list = list.child(message_row("Everything is up to date".into()));
+        }
+
+        for package in state.packages.iter().take(MAX_ROWS) {
+            let versions = if package.installed.is_empty() {
+                package.latest.clone()
+            } else {
+                format!("{} → {}", package.installed, package.latest)
+            };
+            let tag = if package.pinned {
+                "pinned"
+            } else {
+                package.source.label()
+            };
+            list = list.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .child(
+                        div()
+                            .flex_1()
+                            .overflow_hidden()
+                            .child(SharedString::from(package.name.clone())),
+                    )
+                    .child(
+                        div()
+                            .text_size(px(theme.font_size - 2.0))
+                            .text_color(theme.foreground_muted)
+                            .child(SharedString::from(versions)),
+                    )
+                    .child(
+                        div()
+                            .w(px(64.0))
+                            .flex()
+                            .justify_end()
+                            .text_size(px(theme.font_size - 2.0))
+                            .text_color(theme.foreground_subtle)
+                            .child(tag),
+                    ),
+            );
+        }
+
+        let footer = div()
+            .h(px(FOOTER_HEIGHT))
+            .flex()
+            .items_end()
+            .justify_end()
+            .child(
+                Button::new(SharedString::from(format!("{}-update-all", self.id)))
+                    .label("Update all")
+                    .variant(ButtonVariant::Accent)
+                    .text_size(px(theme.font_size - 2.0))
+                    .disabled(pending == 0)
+                    .on_popup_action(
+                        self.id.clone(),
+                        PopupAction::Select {
+                            value: "update-all".to_string(),
+                        },
+                    )
+                    .render(theme),
+            );
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .p(px(POPUP_PADDING))
+                .text_size(px(theme.font_size))
+                .text_color(theme.foreground)
+                .child(header)
+                .child(list)
+                .child(footer)
+                .into_any_element(),
+        )
+    }
+
+    fn on_popup_action(&mut self, action: PopupAction) {
+        match action {
+            PopupAction::Select { value } if value == "update-all" => {
+                self.update_all();
+                request_hide_popup();
+            }
+            PopupAction::Refresh => {
+                let shared = Arc::clone(&self.shared);
+                let id = self.id.clone();
+                std::thread::spawn(move || shared.refresh(&id));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Drop for UpdatesModule {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_brew_outdated() {
+        let json = r#"{
+            "formulae": [
+                {"name": "git", "installed_versions": ["2.42.0", "2.42.1"], "current_version": "2.43.0", "pinned": false, "pinned_version": null},
+                {"name": "node", "installed_versions": ["20.1.0"], "current_version": "21.0.0", "pinned": true, "pinned_version": "20.1.0"}
+            ],
+            "casks": [
+                {"name": "firefox", "installed_versions": "119.0", "current_version": "120.0"}
+            ]
+        }"#;
+        let packages = parse_brew(json).unwrap();
+        assert_eq!(packages.len(), 3);
+        assert_eq!(packages[0].name, "git");
+        assert_eq!(packages[0].installed, "2.42.1");
+        assert_eq!(packages[0].latest, "2.43.0");
+        assert!(packages[1].pinned);
+        assert_eq!(packages[2].source, Source::Cask);
+        assert_eq!(packages[2].installed, "119.0");
+
+        let state = UpdatesState {
+            packages,
+            error: None,
+            loaded: true,
+        };
+        assert_eq!(state.pending(), 2);
+        assert!(parse_brew("Error: not json").is_err());
+        assert!(parse_brew("{}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parses_mas_outdated() {
+        let output = "497799835 Xcode (15.0 -> 15.1)\n\
+                      1295203466 Microsoft Remote Desktop (10.7.6 -> 10.7.7)\n\
+                      409183694 Keynote (13.2)\n\
+                      garbage\n";
+        let packages = parse_mas(output);
+        assert_eq!(packages.len(), 3);
+        assert_eq!(packages[0].name, "Xcode");
+        assert_eq!(packages[0].installed, "15.0");
+        assert_eq!(packages[0].latest, "15.1");
+        assert_eq!(packages[1].name, "Microsoft Remote Desktop");
+        assert_eq!(packages[2].installed, "");
+        assert_eq!(packages[2].latest, "13.2");
+        assert_eq!(packages[2].source, Source::AppStore);
+    }
+
+    #[test]
+    fn quotes_applescript_strings() {
+        assert_eq!(applescript_string("brew upgrade"), "\"brew upgrade\"");
+        assert_eq!(
+            applescript_string(r#"echo "hi" \ bye"#),
+            r#""echo \"hi\" \\ bye""#
+        );
+    }
+}
@@ -1,47 +1,68 @@
 //! Volume module for displaying audio volume.
 
-use std::process::Command;
 use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
 use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
 
-use super::GpuiModule;
+use super::{mark_dirty, scroll_steps, GpuiModule, RuleColors};
 use crate::gpui_app::primitives::icons::volume as volume_icons;
 use crate::gpui_app::theme::Theme;
+use crate::system::providers::AudioProvider;
+
+/// Pixels of scrolling per volume step.
+const SCROLL_STEP_PX: f32 = 12.0;
+/// Volume change per scroll step, in percent.
+const SCROLL_STEP_PERCENT: i32 = 2;
 
 /// Volume module that displays the current audio volume.
+/// Scrolling over it raises or lowers the volume.
 pub struct VolumeModule {
     id: String,
+    audio: Arc<dyn AudioProvider>,
     level: Arc<AtomicU8>,
     muted: Arc<AtomicBool>,
     dirty: Arc<AtomicBool>,
     stop: Arc<AtomicBool>,
+    /// True while a volume change is being applied
+    setting: Arc<AtomicBool>,
+    scroll_accumulator: f32,
+    rule_colors: RuleColors,
 }
 
 impl VolumeModule {
-    /// Creates a new volume module.
-    pub fn new(id: &str) -> Self {
-        let (initial_level, initial_muted) = Self::fetch_status();
-        let level = Arc::new(AtomicU8::new(initial_level));
-        let muted = Arc::new(AtomicBool::new(initial_muted));
+    /// Creates a new volume module reading and setting the volume through
+    /// `audio`.
+    pub fn new(id: &str, audio: Arc<dyn AudioProvider>) -> Self {
+        let initial = audio.status();
+        let level = Arc::new(AtomicU8::new(initial.level));
+        let muted = Arc::new(AtomicBool::new(initial.muted));
         let dirty = Arc::new(AtomicBool::new(true));
         let stop = Arc::new(AtomicBool::new(false));
+        let setting = Arc::new(AtomicBool::new(false));
 
         let level_handle = Arc::clone(&level);
         let muted_handle = Arc::clone(&muted);
         let dirty_handle = Arc::clone(&dirty);
         let stop_handle = Arc::clone(&stop);
+        let setting_handle = Arc::clone(&setting);
+        let audio_handle = Arc::clone(&audio);
         std::thread::spawn(move || {
             let mut last_level = level_handle.load(Ordering::Relaxed);
             let mut last_muted = muted_handle.load(Ordering::Relaxed);
             while !stop_handle.load(Ordering::Relaxed) {
-                let (next_level, next_muted) = Self::fetch_status();
+                // Don't clobber a scroll change that's still being applied
+                if setting_handle.load(Ordering::Relaxed) {
+                    std::thread::sleep(Duration::from_millis(750));
+                    continue;
+                }
+                let status = audio_handle.status();
+                let (next_level, next_muted) = (status.level, status.muted);
                 if next_level != last_level || next_muted != last_muted {
                     level_handle.store(next_level, Ordering::Relaxed);
                     muted_handle.store(next_muted, Ordering::Relaxed);
-                    dirty_handle.store(true, Ordering::Relaxed);
+                    mark_dirty(&dirty_handle);
                     last_level = next_level;
                     last_muted = next_muted;
                 }
@@ -51,41 +72,50 @@ impl VolumeModule {
 
         Self {
             id: id.to_string(),
+            audio,
             level,
             muted,
             dirty,
             stop,
+            setting,
+            scroll_accumulator: 0.0,
+            rule_colors: RuleColors::default(),
         }
     }
 
-    fn fetch_status() -> (u8, bool) {
-        // Get volume level
-        let output = Command::new("osascript")
-            .args(["-e", "output volume of (get volume settings)"])
-            .output()
-            .ok()
-            .and_then(|o| String::from_utf8(o.stdout).ok());
-
-        let mut level = 0;
-        if let Some(vol) = output {
-            if let Ok(parsed) = vol.trim().parse::<u8>() {
-                level = parsed;
-            }
+    /// Sets the system volume to the current level in the background,
+    /// following further changes until it catches up.
+    fn apply_level(&self) {
+        if self.setting.swap(true, Ordering::Relaxed) {
+            return;
         }
+        let audio = Arc::clone(&self.audio);
+        let level = Arc::clone(&self.level);
+        let setting = Arc::clone(&self.setting);
+        std::thread::spawn(move || {
+            let mut applied = None;
+            loop {
+                let target = level.load(Ordering::Relaxed);
+                if applied == Some(target) {
+                    break;
+                }
+                if let Err(err) = audio.set_level(target) {
+                    log::warn!("Failed to set volume: {}", err);
+                    break;
+                }
+                applied = Some(target);
+            }
+            setting.store(false, Ordering::Relaxed);
+        });
+    }
 
-        // Check if muted
-        let muted_output = Command::new("osascript")
-            .args(["-e", "output muted of (get volume settings)"])
-            .output()
-            .ok()
-            .and_then(|o| String::from_utf8(o.stdout).ok());
-
-        let mut muted = false;
-        if let Some(muted_str) = muted_output {
-            muted = muted_str.trim() == "true";
+    /// Level, or "muted".
+    fn text(&self) -> String {
+        if self.muted.load(Ordering::Relaxed) {
+            "muted".to_string()
+        } else {
+            format!("{}%", self.level.load(Ordering::Relaxed))
         }
-
-        (level, muted)
     }
 }
 
@@ -98,19 +128,20 @@ impl GpuiModule for VolumeModule {
         let level = self.level.load(Ordering::Relaxed);
         let muted = self.muted.load(Ordering::Relaxed);
         let icon = volume_icons::for_level(level, muted);
-        let text = if muted {
-            "muted".to_string()
-        } else {
-            format!("{}%", level)
-        };
+        let text = self.text();
+
+        let mut glyph = div().child(SharedString::from(icon.to_string()));
+        if let Some(color) = self.rule_colors.icon {
+            glyph = glyph.text_color(color);
+        }
 
         div()
             .flex()
             .items_center()
             .gap(px(6.0)) // Gap between icon and text
-            .text_color(theme.foreground)
+            .text_color(self.rule_colors.text.unwrap_or(theme.foreground))
             .text_size(px(theme.font_size))
-            .child(SharedString::from(icon.to_string()))
+            .child(glyph)
             .child(SharedString::from(text))
             .into_any_element()
     }
@@ -119,9 +150,34 @@ impl GpuiModule for VolumeModule {
         self.dirty.swap(false, Ordering::Relaxed)
     }
 
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
     fn value(&self) -> Option<u8> {
         Some(self.level.load(Ordering::Relaxed))
     }
+
+    fn display_text(&self) -> Option<String> {
+        Some(self.text())
+    }
+
+    fn set_rule_colors(&mut self, colors: RuleColors) {
+        self.rule_colors = colors;
+    }
+
+    fn on_scroll(&mut self, _delta_x: f32, delta_y: f32) -> bool {
+        let steps = scroll_steps(&mut self.scroll_accumulator, delta_y, SCROLL_STEP_PX);
+        if steps == 0 {
+            return false;
+        }
+        let current = self.level.load(Ordering::Relaxed) as i32;
+        let level = (current + steps * SCROLL_STEP_PERCENT).clamp(0, 100) as u8;
+        self.level.store(level, Ordering::Relaxed);
+        self.muted.store(false, Ordering::Relaxed);
+        self.apply_level();
+        true
+    }
 }
 
 impl Drop for VolumeModule {
@@ -129,3 +185,35 @@ impl Drop for VolumeModule {
         self.stop.store(true, Ordering::Relaxed);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::providers::mock::MockAudio;
+
+    #[test]
+    fn starts_at_the_current_volume() {
+        let module = VolumeModule::new("volume", Arc::new(MockAudio::new(30, true)));
+        assert_eq!(module.value(), Some(30));
+        assert!(module.muted.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn scrolling_sets_the_volume_and_unmutes() {
+        let audio = Arc::new(MockAudio::new(30, true));
+        let mut module = VolumeModule::new("volume", audio.clone());
+        assert!(module.on_scroll(0.0, SCROLL_STEP_PX * 2.0));
+        assert_eq!(module.value(), Some(34));
+        assert!(!module.muted.load(Ordering::Relaxed));
+
+        // The change is applied on a background thread
+        for _ in 0..100 {
+            if audio.status().level == 34 {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(audio.status().level, 34);
+        assert!(!audio.status().muted);
+    }
+}
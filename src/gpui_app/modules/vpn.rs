@@ -0,0 +1,354 @@
+//! VPN module for displaying tunnel connection status.
+//!
+//! Configured VPN services (IPSec, L2TP, IKEv2 and app-provided ones like
+//! WireGuard) are read from SystemConfiguration via `scutil --nc list`.
+//! Tunnels that aren't managed by a network service (e.g. Tailscale or a
+//! CLI OpenVPN) are picked up from `utun`/`ipsec`/`ppp` interfaces with an
+//! IPv4 address.
+//!
+//! With `name` set, the module tracks that service and clicking it connects
+//! or disconnects it (unless `click_command` overrides the click).
+//!
+//! ```toml
+//! { type = "vpn", name = "Work VPN" }
+//! ```
+
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+
+use super::{mark_dirty, GpuiModule};
+use crate::config::ModuleConfig;
+use crate::gpui_app::primitives::icons::vpn as vpn_icons;
+use crate::gpui_app::theme::Theme;
+
+/// Connection status of a VPN service as reported by `scutil --nc`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VpnStatus {
+    Connected,
+    Connecting,
+    Disconnecting,
+    Disconnected,
+}
+
+impl VpnStatus {
+    fn parse(status: &str) -> Self {
+        match status.trim() {
+            "Connected" => Self::Connected,
+            "Connecting" => Self::Connecting,
+            "Disconnecting" => Self::Disconnecting,
+            _ => Self::Disconnected,
+        }
+    }
+}
+
+/// A VPN service from the current network set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VpnService {
+    pub name: String,
+    pub status: VpnStatus,
+}
+
+/// Snapshot of VPN services and active tunnel interfaces.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VpnState {
+    pub services: Vec<VpnService>,
+    /// Tunnel interfaces with an IPv4 address
+    pub tunnels: Vec<String>,
+}
+
+impl VpnState {
+    /// Resolves what to display: the named service if configured, otherwise
+    /// the first active service or tunnel.
+    ///
+    /// @param name - Service to track (None = any)
+    /// @returns Label and status, or None if no VPN is known
+    pub fn display(&self, name: Option<&str>) -> Option<(String, VpnStatus)> {
+        if let Some(name) = name {
+            return self
+                .services
+                .iter()
+                .find(|s| s.name == name)
+                .map(|s| (s.name.clone(), s.status));
+        }
+        self.services
+            .iter()
+            .find(|s| s.status != VpnStatus::Disconnected)
+            .map(|s| (s.name.clone(), s.status))
+            .or_else(|| {
+                self.tunnels
+                    .first()
+                    .map(|t| (t.clone(), VpnStatus::Connected))
+            })
+    }
+}
+
+/// Parses `scutil --nc list` output.
+///
+/// Lines look like:
+/// `* (Connected)   8F3A...  IPSec   "Work VPN"   [IPSec]`
+pub fn parse_nc_list(output: &str) -> Vec<VpnService> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let open = line.find('(')?;
+            let close = open + line[open..].find(')')?;
+            let status = VpnStatus::parse(&line[open + 1..close]);
+            let rest = &line[close..];
+            let start = rest.find('"')? + 1;
+            let end = start + rest[start..].find('"')?;
+            Some(VpnService {
+                name: rest[start..end].to_string(),
+                status,
+            })
+        })
+        .collect()
+}
+
+/// Parses `ifconfig` output for tunnel interfaces carrying an IPv4 address.
+///
+/// macOS keeps several `utun` interfaces up for system services with only
+/// link-local IPv6, so an `inet` address is what marks a live VPN tunnel.
+pub fn parse_tunnels(output: &str) -> Vec<String> {
+    let mut tunnels = Vec::new();
+    let mut current: Option<&str> = None;
+    for line in output.lines() {
+        if !line.starts_with(char::is_whitespace) {
+            current = line.split(':').next().filter(|name| {
+                ["utun", "ipsec", "ppp"]
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix))
+            });
+        } else if let Some(name) = current {
+            if line.trim_start().starts_with("inet ") && !tunnels.iter().any(|t| t == name) {
+                tunnels.push(name.to_string());
+            }
+        }
+    }
+    tunnels
+}
+
+/// Quotes a string for `sh -c`.
+pub(super) fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Default click command: connects the configured service, or disconnects
+/// it if it's connected.
+pub fn toggle_command(config: &ModuleConfig) -> Option<String> {
+    let name = config.extras.get("name").and_then(|v| v.as_str())?;
+    let name = shell_quote(name);
+    Some(format!(
+        "if scutil --nc status {name} | head -n 1 | grep -qx Connected; \
+         then scutil --nc stop {name}; else scutil --nc start {name}; fi"
+    ))
+}
+
+/// VPN module that displays the current VPN connection.
+pub struct VpnModule {
+    id: String,
+    name: Option<String>,
+    state: Arc<Mutex<VpnState>>,
+    dirty: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+}
+
+impl VpnModule {
+    /// Creates a new VPN module.
+    ///
+    /// @param id - Unique module identifier
+    /// @param name - VPN service to track (None = any active VPN)
+    pub fn new(id: &str, name: Option<&str>) -> Self {
+        let state = Arc::new(Mutex::new(VpnState::default()));
+        let dirty = Arc::new(AtomicBool::new(true));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let state_handle = Arc::clone(&state);
+        let dirty_handle = Arc::clone(&dirty);
+        let stop_handle = Arc::clone(&stop);
+        std::thread::spawn(move || {
+            let mut last = VpnState::default();
+            while !stop_handle.load(Ordering::Relaxed) {
+                let next = Self::fetch_state();
+                if next != last {
+                    if let Ok(mut guard) = state_handle.lock() {
+                        *guard = next.clone();
+                    }
+                    mark_dirty(&dirty_handle);
+                    last = next;
+                }
+                std::thread::sleep(Duration::from_secs(3));
+            }
+        });
+
+        Self {
+            id: id.to_string(),
+            name: name.map(str::to_string),
+            state,
+            dirty,
+            stop,
+        }
+    }
+
+    fn fetch_state() -> VpnState {
+        let run = |program: &str, args: &[&str]| {
+            Command::new(program)
+                .args(args)
+                .output()
+                .ok()
+                .and_then(|o| String::from_utf8(o.stdout).ok())
+                .unwrap_or_default()
+        };
+        VpnState {
+            services: parse_nc_list(&run("scutil", &["--nc", "list"])),
+            tunnels: parse_tunnels(&run("ifconfig", &[])),
+        }
+    }
+
+    /// Label and status of the service to show, if there is one.
+    fn display(&self) -> Option<(String, VpnStatus)> {
+        self.state
+            .lock()
+            .ok()
+            .and_then(|state| state.display(self.name.as_deref()))
+    }
+}
+
+/// Status icon and label of the shown service, or "Off" without one.
+fn bar_text(display: Option<(String, VpnStatus)>) -> String {
+    match display {
+        Some((label, VpnStatus::Connected)) => format!("{} {}", vpn_icons::CONNECTED, label),
+        Some((label, VpnStatus::Connecting | VpnStatus::Disconnecting)) => {
+            format!("{} {}…", vpn_icons::CONNECTED, label)
+        }
+        Some((label, VpnStatus::Disconnected)) => {
+            format!("{} {}", vpn_icons::DISCONNECTED, label)
+        }
+        None => format!("{} Off", vpn_icons::DISCONNECTED),
+    }
+}
+
+impl GpuiModule for VpnModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let display = self.display();
+        let color = match display {
+            Some((_, VpnStatus::Connected)) => theme.foreground,
+            Some((_, VpnStatus::Connecting | VpnStatus::Disconnecting)) => theme.foreground_muted,
+            Some((_, VpnStatus::Disconnected)) | None => theme.warning,
+        };
+        let text = bar_text(display);
+
+        div()
+            .flex()
+            .items_center()
+            .text_color(color)
+            .text_size(px(theme.font_size))
+            .child(SharedString::from(text))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        self.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn display_text(&self) -> Option<String> {
+        Some(bar_text(self.display()))
+    }
+}
+
+impl Drop for VpnModule {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NC_LIST: &str = r#"Available network connection services in the current set (*=enabled):
+* (Connected)      8F3A1C2D-0000-4000-8000-000000000001 IPSec              "Work VPN"                       [IPSec]
+* (Disconnected)   8F3A1C2D-0000-4000-8000-000000000002 VPN (com.wireguard.macos) "Home (WG)"        [VPN:com.wireguard.macos]
+"#;
+
+    const IFCONFIG: &str = "lo0: flags=8049<UP,LOOPBACK,RUNNING,MULTICAST> mtu 16384
+\tinet 127.0.0.1 netmask 0xff000000
+utun0: flags=8051<UP,POINTOPOINT,RUNNING,MULTICAST> mtu 1380
+\tinet6 fe80::1%utun0 prefixlen 64 scopeid 0x10
+utun4: flags=8051<UP,POINTOPOINT,RUNNING,MULTICAST> mtu 1280
+\tinet 100.64.0.3 --> 100.64.0.3 netmask 0xffffffff
+";
+
+    #[test]
+    fn parses_services() {
+        let services = parse_nc_list(NC_LIST);
+        assert_eq!(
+            services,
+            vec![
+                VpnService {
+                    name: "Work VPN".into(),
+                    status: VpnStatus::Connected,
+                },
+                VpnService {
+                    name: "Home (WG)".into(),
+                    status: VpnStatus::Disconnected,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn only_ipv4_tunnels_count() {
+        assert_eq!(parse_tunnels(IFCONFIG), vec!["utun4".to_string()]);
+    }
+
+    #[test]
+    fn display_prefers_named_service() {
+        let state = VpnState {
+            services: parse_nc_list(NC_LIST),
+            tunnels: vec!["utun4".into()],
+        };
+        assert_eq!(
+            state.display(Some("Home (WG)")),
+            Some(("Home (WG)".into(), VpnStatus::Disconnected))
+        );
+        assert_eq!(
+            state.display(None),
+            Some(("Work VPN".into(), VpnStatus::Connected))
+        );
+        assert_eq!(state.display(Some("Missing")), None);
+
+        let tunnel_only = VpnState {
+            services: Vec::new(),
+            tunnels: vec!["utun4".into()],
+        };
+        assert_eq!(
+            tunnel_only.display(None),
+            Some(("utun4".into(), VpnStatus::Connected))
+        );
+    }
+
+    #[test]
+    fn toggle_command_quotes_name() {
+        let config: ModuleConfig = toml::from_str(
+            r#"
+            type = "vpn"
+            name = "Bob's VPN"
+            "#,
+        )
+        .unwrap();
+        let command = toggle_command(&config).unwrap();
+        assert!(command.contains(r"scutil --nc start 'Bob'\''s VPN'"));
+    }
+}
@@ -0,0 +1,530 @@
+//! WASM module running a sandboxed WebAssembly plugin.
+//!
+//! Loads a component implementing the `bar-module` world in
+//! `wit/bar-module.wit` and runs it with wasmtime on a thread of its own,
+//! so a slow or broken plugin can't block or crash the bar. Each call gets
+//! a fuel budget and the plugin a memory limit; one that runs out or traps
+//! is shown as failed and started afresh on the next interval. WASI is
+//! available without files, network or environment.
+//!
+//! The module's `config` table reaches the plugin's `init` as JSON.
+//!
+//! ```toml
+//! { type = "wasm", path = "~/.config/sinew/plugins/pomodoro.wasm", config = { minutes = 25 } }
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+use wasmtime::component::{Component, Linker, ResourceTable};
+use wasmtime::{Engine, Store, StoreLimits, StoreLimitsBuilder, Trap};
+use wasmtime_wasi::{WasiCtx, WasiCtxBuilder, WasiView};
+
+use super::{mark_dirty, GpuiModule, MouseEvent, PopupSpec};
+use crate::config::parse_hex_color;
+use crate::gpui_app::popup_manager::notify_popup_needs_render;
+use crate::gpui_app::theme::Theme;
+
+mod bindings {
+    wasmtime::component::bindgen!({ world: "bar-module", path: "wit" });
+}
+
+use bindings::{BarModule, BarModuleImports, Button};
+
+/// Fuel for one call into the plugin, roughly a few hundred milliseconds
+/// of work.
+const FUEL_PER_CALL: u64 = 500_000_000;
+/// Most memory a plugin may grow to.
+const MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+const POPUP_WIDTH: f64 = 280.0;
+const POPUP_PADDING: f32 = 12.0;
+const ROW_HEIGHT: f32 = 28.0;
+const MAX_ROWS: usize = 15;
+
+/// Engine shared by all WASM modules; compiling is the expensive part.
+fn engine() -> Result<&'static Engine, String> {
+    static ENGINE: OnceLock<Result<Engine, String>> = OnceLock::new();
+    ENGINE
+        .get_or_init(|| {
+            let mut config = wasmtime::Config::new();
+            config.wasm_component_model(true);
+            config.consume_fuel(true);
+            Engine::new(&config).map_err(|e| format!("Can't start wasmtime: {}", e))
+        })
+        .as_ref()
+        .map_err(Clone::clone)
+}
+
+/// Describes a failed call, naming the sandbox limits plainly.
+fn call_error(err: wasmtime::Error) -> String {
+    match err.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => "plugin took too long and was stopped".to_string(),
+        _ => format!("{:#}", err),
+    }
+}
+
+/// State of a plugin instance.
+struct Host {
+    id: String,
+    wasi: WasiCtx,
+    table: ResourceTable,
+    limits: StoreLimits,
+}
+
+impl WasiView for Host {
+    fn table(&mut self) -> &mut ResourceTable {
+        &mut self.table
+    }
+
+    fn ctx(&mut self) -> &mut WasiCtx {
+        &mut self.wasi
+    }
+}
+
+impl BarModuleImports for Host {
+    fn log(&mut self, message: String) {
+        log::info!("{}: {}", self.id, message);
+    }
+}
+
+/// An instantiated plugin.
+struct Instance {
+    store: Store<Host>,
+    bindings: BarModule,
+}
+
+impl Instance {
+    /// Instantiates the component and calls its `init`.
+    fn new(component: &Component, id: &str, config_json: &str) -> Result<Self, String> {
+        let engine = engine()?;
+        let mut linker = Linker::new(engine);
+        wasmtime_wasi::add_to_linker_sync(&mut linker).map_err(call_error)?;
+        BarModule::add_to_linker(&mut linker, |host: &mut Host| host).map_err(call_error)?;
+
+        let host = Host {
+            id: id.to_string(),
+            wasi: WasiCtxBuilder::new().build(),
+            table: ResourceTable::new(),
+            limits: StoreLimitsBuilder::new().memory_size(MEMORY_LIMIT).build(),
+        };
+        let mut store = Store::new(engine, host);
+        store.limiter(|host| &mut host.limits);
+        store.set_fuel(FUEL_PER_CALL).map_err(call_error)?;
+        let bindings =
+            BarModule::instantiate(&mut store, component, &linker).map_err(call_error)?;
+
+        let mut instance = Self { store, bindings };
+        instance
+            .call(|bindings, store| bindings.call_init(store, config_json))?
+            .map_err(|e| format!("init failed: {}", e))?;
+        Ok(instance)
+    }
+
+    /// Calls into the plugin with a fresh fuel budget.
+    fn call<R>(
+        &mut self,
+        f: impl FnOnce(&BarModule, &mut Store<Host>) -> wasmtime::Result<R>,
+    ) -> Result<R, String> {
+        self.store.set_fuel(FUEL_PER_CALL).map_err(call_error)?;
+        f(&self.bindings, &mut self.store).map_err(call_error)
+    }
+
+    /// Passes on a click, if any, then asks what to show.
+    fn refresh(&mut self, click: Option<Button>) -> Result<Output, String> {
+        if let Some(button) = click {
+            self.call(|bindings, store| bindings.call_handle_click(store, button))?;
+        }
+        let render = self.call(|bindings, store| bindings.call_update(store))?;
+        let rows = self.call(|bindings, store| bindings.call_popup_rows(store))?;
+        Ok(Output {
+            text: render.text,
+            icon: render.icon,
+            color: render.color,
+            tooltip: render.tooltip,
+            rows: rows
+                .into_iter()
+                .map(|row| (row.label, row.detail))
+                .collect(),
+            error: None,
+        })
+    }
+}
+
+fn load_component(path: &Path) -> Result<Component, String> {
+    Component::from_file(engine()?, path)
+        .map_err(|e| format!("Can't load {}: {:#}", path.display(), e))
+}
+
+/// What the plugin last returned.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Output {
+    text: String,
+    icon: Option<String>,
+    color: Option<String>,
+    tooltip: Option<String>,
+    /// Popup rows: label and detail
+    rows: Vec<(String, Option<String>)>,
+    /// Set when loading or the last call failed
+    error: Option<String>,
+}
+
+/// State shared between the bar and popup copies of the module.
+struct Shared {
+    id: String,
+    output: Mutex<Output>,
+    dirty: AtomicBool,
+}
+
+impl Shared {
+    fn set(&self, next: Output) {
+        if let Ok(mut guard) = self.output.lock() {
+            if *guard != next {
+                *guard = next;
+                mark_dirty(&self.dirty);
+                notify_popup_needs_render(&self.id);
+            }
+        }
+    }
+
+    /// Keeps the last output but shows the error, logging it when it changes.
+    fn fail(&self, error: String) {
+        let mut next = self
+            .output
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default();
+        if next.error.as_ref() != Some(&error) {
+            log::warn!("{}: {}", self.id, error);
+        }
+        next.error = Some(error);
+        self.set(next);
+    }
+}
+
+/// Runs the plugin until both copies of the module are gone.
+fn run(
+    shared: &Shared,
+    path: &Path,
+    config_json: &str,
+    interval: Duration,
+    clicks: &Receiver<Button>,
+) {
+    let component = match load_component(path) {
+        Ok(component) => component,
+        Err(err) => {
+            shared.fail(err);
+            return;
+        }
+    };
+    let mut instance: Option<Instance> = None;
+    let mut click = None;
+    loop {
+        if instance.is_none() {
+            match Instance::new(&component, &shared.id, config_json) {
+                Ok(started) => instance = Some(started),
+                Err(err) => shared.fail(err),
+            }
+        }
+        if let Some(plugin) = instance.as_mut() {
+            match plugin.refresh(click.take()) {
+                Ok(output) => shared.set(output),
+                Err(err) => {
+                    shared.fail(err);
+                    // A trapped instance can't be called again
+                    instance = None;
+                }
+            }
+        }
+        click = match clicks.recv_timeout(interval) {
+            Ok(button) => Some(button),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+    }
+}
+
+/// Module backed by a WebAssembly plugin.
+pub struct WasmModule {
+    id: String,
+    shared: Arc<Shared>,
+    /// Clicks for the plugin thread; it ends once every sender is dropped
+    clicks: Sender<Button>,
+}
+
+impl WasmModule {
+    /// Creates a new WASM module and starts the plugin.
+    ///
+    /// @param id - Unique module identifier
+    /// @param path - Component file
+    /// @param config_json - The `config` table as JSON, passed to `init`
+    /// @param interval - Time between `update` calls
+    pub fn new(id: &str, path: PathBuf, config_json: String, interval: Duration) -> Self {
+        let shared = Arc::new(Shared {
+            id: id.to_string(),
+            output: Mutex::new(Output::default()),
+            dirty: AtomicBool::new(true),
+        });
+        let (clicks, receiver) = mpsc::channel();
+
+        let handle = Arc::clone(&shared);
+        std::thread::spawn(move || run(&handle, &path, &config_json, interval, &receiver));
+
+        Self {
+            id: id.to_string(),
+            shared,
+            clicks,
+        }
+    }
+
+    /// Returns a second handle on the same plugin for the popup registry.
+    pub fn popup_handle(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            shared: Arc::clone(&self.shared),
+            clicks: self.clicks.clone(),
+        }
+    }
+
+    fn output(&self) -> Output {
+        self.shared
+            .output
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl GpuiModule for WasmModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let output = self.output();
+        let color = if output.error.is_some() {
+            theme.warning
+        } else {
+            output
+                .color
+                .as_deref()
+                .and_then(parse_hex_color)
+                .map(|(r, g, b, a)| gpui::Rgba {
+                    r: r as f32,
+                    g: g as f32,
+                    b: b as f32,
+                    a: a as f32,
+                })
+                .unwrap_or(theme.foreground)
+        };
+        let mut row = div()
+            .flex()
+            .items_center()
+            .gap(px(4.0))
+            .text_color(color)
+            .text_size(px(theme.font_size));
+        if let Some(icon) = output.icon {
+            row = row.child(SharedString::from(icon));
+        }
+        if !output.text.is_empty() {
+            row = row.child(SharedString::from(output.text));
+        }
+        row.into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        self.shared.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn copy_text(&self) -> Option<String> {
+        let output = self.output();
+        (!output.text.is_empty()).then_some(output.text)
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        let output = self.output();
+        output.error.or(output.tooltip)
+    }
+
+    fn on_mouse_event(&mut self, event: MouseEvent) -> bool {
+        let button = match event {
+            MouseEvent::Click => Button::Left,
+            MouseEvent::RightClick => Button::Right,
+            MouseEvent::MiddleClick => Button::Middle,
+            MouseEvent::DoubleClick => Button::Double,
+        };
+        // The plugin thread updates the module once it has handled it
+        let _ = self.clicks.send(button);
+        false
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let rows = self.output().rows.len().min(MAX_ROWS);
+        if rows == 0 {
+            return None;
+        }
+        let height = POPUP_PADDING * 2.0 + rows as f32 * ROW_HEIGHT;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let output = self.output();
+        if output.rows.is_empty() {
+            return None;
+        }
+        let mut list = div()
+            .flex()
+            .flex_col()
+            .p(px(POPUP_PADDING))
+            .text_size(px(theme.font_size))
+            .text_color(theme.foreground);
+        for (label, detail) in output.rows.into_iter().take(MAX_ROWS) {
+            let mut row = div()
+                .h(px(ROW_HEIGHT))
+                .flex()
+                .items_center()
+                .gap(px(8.0))
+                .child(
+                    div()
+                        .flex_1()
+                        .overflow_hidden()
+                        .child(SharedString::from(label)),
+                );
+            if let Some(detail) = detail {
+                row = row.child(
+                    div()
+                        .text_color(theme.foreground_muted)
+                        .child(SharedString::from(detail)),
+                );
+            }
+            list = list.child(row);
+        }
+        Some(list.into_any_element())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn out_of_fuel_reads_as_too_slow() {
+        let err = wasmtime::Error::new(Trap::OutOfFuel);
+        assert_eq!(call_error(err), "plugin took too long and was stopped");
+        let err = wasmtime::Error::msg("unknown import");
+        assert_eq!(call_error(err), "unknown import");
+    }
+
+    /// A minimal `bar-module` component whose `update` runs `update_body`,
+    /// which leaves the address of a render record for "hi" (1024) on the
+    /// stack. 512 is zeroed memory: an `ok` from `init`, an empty row list.
+    fn component_wat(update_body: &str) -> String {
+        format!(
+            r#"(component
+  (core module $m
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 4096))
+    (data (i32.const 1024) "\00\08\00\00\02\00\00\00")
+    (data (i32.const 2048) "hi")
+    (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      (local.set $ptr
+        (i32.and (i32.add (global.get $heap) (i32.const 7)) (i32.const -8)))
+      (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+      (local.get $ptr))
+    (func (export "init") (param i32 i32) (result i32) (i32.const 512))
+    (func (export "update") (result i32) {update_body})
+    (func (export "handle-click") (param i32))
+    (func (export "popup-rows") (result i32) (i32.const 512)))
+  (core instance $i (instantiate $m))
+  (type $render' (record
+    (field "text" string)
+    (field "icon" (option string))
+    (field "color" (option string))
+    (field "tooltip" (option string))))
+  (export $render "render" (type $render'))
+  (type $row' (record (field "label" string) (field "detail" (option string))))
+  (export $row "popup-row" (type $row'))
+  (type $button' (enum "left" "right" "middle" "double"))
+  (export $button "button" (type $button'))
+  (func (export "init") (param "config" string) (result (result (error string)))
+    (canon lift (core func $i "init") (memory $i "memory")
+      (realloc (func $i "cabi_realloc"))))
+  (func (export "update") (result $render)
+    (canon lift (core func $i "update") (memory $i "memory")))
+  (func (export "handle-click") (param "button" $button)
+    (canon lift (core func $i "handle-click")))
+  (func (export "popup-rows") (result (list $row))
+    (canon lift (core func $i "popup-rows") (memory $i "memory"))))"#
+        )
+    }
+
+    fn instance(update_body: &str) -> Instance {
+        let component = Component::new(engine().unwrap(), component_wat(update_body)).unwrap();
+        Instance::new(&component, "plugin", "{}").unwrap()
+    }
+
+    #[test]
+    fn renders_what_the_plugin_returns() {
+        let output = instance("(i32.const 1024)").refresh(None).unwrap();
+        assert_eq!(output.text, "hi");
+        assert!(output.rows.is_empty());
+    }
+
+    #[test]
+    fn stops_a_plugin_that_never_returns() {
+        let mut plugin = instance("(loop $spin (br $spin)) (i32.const 1024)");
+        assert_eq!(
+            plugin.refresh(None),
+            Err("plugin took too long and was stopped".to_string())
+        );
+    }
+
+    #[test]
+    fn contains_a_trap() {
+        let mut plugin = instance("unreachable");
+        let err = plugin.refresh(None).unwrap_err();
+        assert!(err.contains("unreachable"), "{}", err);
+        assert!(plugin.refresh(Some(Button::Left)).is_err());
+    }
+
+    #[test]
+    fn a_stuck_plugin_leaves_the_bar_alone() {
+        let path = std::env::temp_dir().join(format!("sinew-wasm-{}.wat", std::process::id()));
+        std::fs::write(
+            &path,
+            component_wat("(loop $spin (br $spin)) (i32.const 1024)"),
+        )
+        .unwrap();
+        let started = std::time::Instant::now();
+        let mut module = WasmModule::new(
+            "plugin",
+            path.clone(),
+            "{}".to_string(),
+            Duration::from_secs(60),
+        );
+        // What the bar calls doesn't wait for the plugin
+        assert!(module.update());
+        let _ = module.tooltip();
+        assert!(started.elapsed() < Duration::from_millis(100));
+
+        let deadline = started + Duration::from_secs(30);
+        while module.tooltip().is_none() && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            module.tooltip().as_deref(),
+            Some("plugin took too long and was stopped")
+        );
+        assert!(module.update());
+    }
+}
@@ -0,0 +1,497 @@
+//! Waybar compatibility mode for script modules.
+//!
+//! With `waybar_compat = true` a script module follows the contract of
+//! Waybar's custom modules, so existing Waybar scripts work unmodified:
+//!
+//! - Output is JSON with `text`, `alt`, `tooltip`, `class` (a string or a
+//!   list) and `percentage`, or plain text lines: text, tooltip, class.
+//! - With `interval` the command runs every `interval` seconds. Without it
+//!   the command keeps running and every line it prints is an update. A
+//!   command that exits is restarted after `restart_interval` seconds, or
+//!   on the next trigger without one.
+//! - Where a Waybar setup sends `pkill -RTMIN+<signal> waybar`, send
+//!   `sinew-msg trigger <id> update` to run the command right away.
+//!
+//! `format` takes `{}` (or `{text}`), `{alt}`, `{tooltip}`, `{class}`,
+//! `{percentage}` and `{icon}`, picked from `format_icons`: a table keyed by
+//! `alt` (falling back to `default`), or a list spread over `percentage`.
+//! `class_colors` colors the text by class, and `percentage` is the value
+//! `color_rules` check. An empty `text` hides the module.
+//!
+//! ```toml
+//! [[modules.right.right]]
+//! type = "script"
+//! waybar_compat = true
+//! command = "~/.config/waybar/scripts/vpn.sh"
+//! interval = 5
+//! format = "{icon} {}"
+//! format_icons = { connected = "󰖂", default = "󰖃" }
+//! class_colors = { disconnected = "#f38ba8" }
+//! ```
+
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+
+use super::template::{Template, Value};
+use super::{mark_dirty, GpuiModule, RuleColors, ScriptModule};
+use crate::config::parse_hex_color;
+use crate::gpui_app::theme::Theme;
+
+/// How long one run of an interval command may take.
+const RUN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One update from a Waybar script.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WaybarOutput {
+    pub text: String,
+    pub alt: String,
+    pub tooltip: String,
+    pub classes: Vec<String>,
+    /// 0–100
+    pub percentage: Option<u8>,
+}
+
+impl WaybarOutput {
+    /// Parses JSON output, or plain text lines (text, tooltip, class) when
+    /// it isn't JSON.
+    pub fn parse(raw: &str) -> Self {
+        let raw = raw.trim();
+        if raw.starts_with('{') {
+            if let Ok(serde_json::Value::Object(json)) = serde_json::from_str(raw) {
+                let string = |key: &str| {
+                    json.get(key)
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("")
+                        .to_string()
+                };
+                let classes = match json.get("class") {
+                    Some(serde_json::Value::String(class)) => {
+                        class.split_whitespace().map(String::from).collect()
+                    }
+                    Some(serde_json::Value::Array(classes)) => classes
+                        .iter()
+                        .filter_map(|v| v.as_str())
+                        .map(String::from)
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                return Self {
+                    text: string("text"),
+                    alt: string("alt"),
+                    tooltip: string("tooltip"),
+                    classes,
+                    percentage: json
+                        .get("percentage")
+                        .and_then(|v| v.as_f64())
+                        .map(|p| p.round().clamp(0.0, 100.0) as u8),
+                };
+            }
+        }
+        let mut lines = raw.lines();
+        Self {
+            text: lines.next().unwrap_or("").to_string(),
+            tooltip: lines.next().unwrap_or("").to_string(),
+            classes: lines
+                .next()
+                .unwrap_or("")
+                .split_whitespace()
+                .map(String::from)
+                .collect(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Icons for `{icon}`, from `format_icons`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum FormatIcons {
+    #[default]
+    None,
+    /// By `alt`, with `default` for other values
+    ByAlt(Vec<(String, String)>),
+    /// Spread evenly over `percentage`, lowest first
+    ByPercentage(Vec<String>),
+}
+
+impl FormatIcons {
+    /// Parses `format_icons`: a table keyed by `alt` or a list of icons.
+    pub fn parse(value: Option<&toml::Value>) -> Self {
+        match value {
+            Some(toml::Value::Table(table)) => Self::ByAlt(
+                table
+                    .iter()
+                    .filter_map(|(alt, icon)| Some((alt.clone(), icon.as_str()?.to_string())))
+                    .collect(),
+            ),
+            Some(toml::Value::Array(icons)) => Self::ByPercentage(
+                icons
+                    .iter()
+                    .filter_map(|icon| icon.as_str())
+                    .map(String::from)
+                    .collect(),
+            ),
+            _ => Self::None,
+        }
+    }
+
+    /// The icon for an update, None when nothing matches.
+    pub fn pick(&self, output: &WaybarOutput) -> Option<&str> {
+        match self {
+            Self::None => None,
+            Self::ByAlt(icons) => {
+                let find = |key: &str| {
+                    icons
+                        .iter()
+                        .find(|(alt, _)| alt == key)
+                        .map(|(_, icon)| icon.as_str())
+                };
+                find(&output.alt).or_else(|| find("default"))
+            }
+            Self::ByPercentage(icons) if icons.is_empty() => None,
+            Self::ByPercentage(icons) => {
+                let percentage = usize::from(output.percentage?);
+                let index = (percentage * icons.len() / 100).min(icons.len() - 1);
+                Some(icons[index].as_str())
+            }
+        }
+    }
+}
+
+/// Parses `class_colors`: text colors by class name.
+pub fn parse_class_colors(value: Option<&toml::Value>) -> Vec<(String, gpui::Rgba)> {
+    let Some(table) = value.and_then(|v| v.as_table()) else {
+        return Vec::new();
+    };
+    table
+        .iter()
+        .filter_map(|(class, color)| {
+            let hex = color.as_str()?;
+            let Some((r, g, b, a)) = parse_hex_color(hex) else {
+                log::warn!("waybar: invalid color '{}' for class '{}'", hex, class);
+                return None;
+            };
+            let color = gpui::Rgba {
+                r: r as f32,
+                g: g as f32,
+                b: b as f32,
+                a: a as f32,
+            };
+            Some((class.clone(), color))
+        })
+        .collect()
+}
+
+/// Turns Waybar's `{}` placeholder into `{text}`.
+fn parse_format(format: Option<&str>) -> Template {
+    Template::parse_or_literal(&format.unwrap_or("{}").replace("{}", "{text}"))
+}
+
+/// Script module that runs a Waybar custom-module script.
+pub struct WaybarModule {
+    id: String,
+    format: Template,
+    icons: FormatIcons,
+    class_colors: Vec<(String, gpui::Rgba)>,
+    output: Arc<Mutex<WaybarOutput>>,
+    rule_colors: RuleColors,
+    dirty: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+    /// Runs the command now; dropping it ends the worker thread
+    wake: Sender<()>,
+    /// The running command of a continuous script
+    child: Arc<Mutex<Option<Child>>>,
+}
+
+impl WaybarModule {
+    /// Creates a new Waybar script module.
+    ///
+    /// @param id - Unique module identifier
+    /// @param command - Shell command to run
+    /// @param interval - Time between runs (None = the command keeps running)
+    /// @param restart_interval - Delay before restarting a continuous command
+    ///   that exited (None = wait for a trigger)
+    /// @param format - Text template (None = "{}")
+    /// @param icons - Icons for `{icon}`
+    /// @param class_colors - Text colors by class
+    pub fn new(
+        id: &str,
+        command: &str,
+        interval: Option<Duration>,
+        restart_interval: Option<Duration>,
+        format: Option<&str>,
+        icons: FormatIcons,
+        class_colors: Vec<(String, gpui::Rgba)>,
+    ) -> Self {
+        let output = Arc::new(Mutex::new(WaybarOutput::default()));
+        let dirty = Arc::new(AtomicBool::new(true));
+        let stop = Arc::new(AtomicBool::new(false));
+        let child = Arc::new(Mutex::new(None));
+        let (wake, wake_rx) = mpsc::channel();
+
+        let command = command.to_string();
+        let output_handle = Arc::clone(&output);
+        let dirty_handle = Arc::clone(&dirty);
+        let stop_handle = Arc::clone(&stop);
+        let child_handle = Arc::clone(&child);
+        std::thread::spawn(move || {
+            let publish = |next: WaybarOutput| {
+                if let Ok(mut guard) = output_handle.lock() {
+                    if *guard != next {
+                        *guard = next;
+                        mark_dirty(&dirty_handle);
+                    }
+                }
+            };
+            while !stop_handle.load(Ordering::Relaxed) {
+                let woken = match interval {
+                    Some(interval) => {
+                        let raw = ScriptModule::run_command_with_timeout(&command, RUN_TIMEOUT);
+                        publish(WaybarOutput::parse(&raw));
+                        !matches!(
+                            wake_rx.recv_timeout(interval),
+                            Err(RecvTimeoutError::Disconnected)
+                        )
+                    }
+                    None => {
+                        stream(&command, &child_handle, &publish);
+                        match restart_interval {
+                            Some(delay) => !matches!(
+                                wake_rx.recv_timeout(delay),
+                                Err(RecvTimeoutError::Disconnected)
+                            ),
+                            None => wake_rx.recv().is_ok(),
+                        }
+                    }
+                };
+                if !woken {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            id: id.to_string(),
+            format: parse_format(format),
+            icons,
+            class_colors,
+            output,
+            rule_colors: RuleColors::default(),
+            dirty,
+            stop,
+            wake,
+            child,
+        }
+    }
+
+    /// The script's output rendered through `format`.
+    fn text(&self, output: &WaybarOutput) -> String {
+        let class = output.classes.join(" ");
+        self.format.render(&[
+            ("text", Some(Value::from(output.text.as_str()))),
+            ("alt", Some(Value::from(output.alt.as_str()))),
+            ("tooltip", Some(Value::from(output.tooltip.as_str()))),
+            ("class", Some(Value::from(class))),
+            ("percentage", output.percentage.map(Value::from)),
+            ("icon", self.icons.pick(output).map(Value::from)),
+        ])
+    }
+}
+
+/// Runs a continuous command, publishing every line it prints until it
+/// exits or is killed.
+fn stream(command: &str, child_slot: &Mutex<Option<Child>>, publish: &dyn Fn(WaybarOutput)) {
+    let mut child = match Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            log::warn!("waybar: failed to run '{}': {}", command, e);
+            return;
+        }
+    };
+    let Some(stdout) = child.stdout.take() else {
+        return;
+    };
+    if let Ok(mut slot) = child_slot.lock() {
+        *slot = Some(child);
+    }
+    for line in BufReader::new(stdout).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !line.trim().is_empty() {
+            publish(WaybarOutput::parse(&line));
+        }
+    }
+    if let Some(mut child) = child_slot.lock().ok().and_then(|mut slot| slot.take()) {
+        let _ = child.wait();
+    }
+}
+
+impl GpuiModule for WaybarModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn render(&self, theme: &Theme) -> AnyElement {
+        let output = self
+            .output
+            .lock()
+            .map(|output| output.clone())
+            .unwrap_or_default();
+        if output.text.is_empty() {
+            return div().into_any_element();
+        }
+
+        let text = self.text(&output);
+        let class_color = output.classes.iter().find_map(|class| {
+            self.class_colors
+                .iter()
+                .find(|(name, _)| name == class)
+                .map(|(_, color)| *color)
+        });
+
+        div()
+            .flex()
+            .items_center()
+            .text_color(
+                self.rule_colors
+                    .text
+                    .or(class_color)
+                    .unwrap_or(theme.foreground),
+            )
+            .text_size(px(theme.font_size))
+            .child(SharedString::from(text))
+            .into_any_element()
+    }
+
+    fn update(&mut self) -> bool {
+        self.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn refresh(&mut self) {
+        let _ = self.wake.send(());
+    }
+
+    fn value(&self) -> Option<u8> {
+        self.output.lock().ok()?.percentage
+    }
+
+    fn display_text(&self) -> Option<String> {
+        let output = self.output.lock().ok()?;
+        (!output.text.is_empty()).then(|| self.text(&output))
+    }
+
+    fn set_rule_colors(&mut self, colors: RuleColors) {
+        self.rule_colors = colors;
+    }
+}
+
+impl Drop for WaybarModule {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(mut child) = self.child.lock().ok().and_then(|mut slot| slot.take()) {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_json_output() {
+        let output = WaybarOutput::parse(
+            r#"{"text": "VPN", "alt": "connected", "tooltip": "wg0", "class": ["on", "ok"], "percentage": 42.6}"#,
+        );
+        assert_eq!(output.text, "VPN");
+        assert_eq!(output.alt, "connected");
+        assert_eq!(output.tooltip, "wg0");
+        assert_eq!(output.classes, ["on", "ok"]);
+        assert_eq!(output.percentage, Some(43));
+
+        let output = WaybarOutput::parse(r#"{"text": "x", "class": "warning", "percentage": 140}"#);
+        assert_eq!(output.classes, ["warning"]);
+        assert_eq!(output.percentage, Some(100));
+    }
+
+    #[test]
+    fn parses_plain_text_lines() {
+        let output = WaybarOutput::parse("3 updates\npacman: 3\nwarning\n");
+        assert_eq!(output.text, "3 updates");
+        assert_eq!(output.tooltip, "pacman: 3");
+        assert_eq!(output.classes, ["warning"]);
+        assert_eq!(output.percentage, None);
+
+        let output = WaybarOutput::parse("{not json}");
+        assert_eq!(output.text, "{not json}");
+    }
+
+    #[test]
+    fn picks_icons_by_alt_or_percentage() {
+        let value: toml::Value = toml::from_str(
+            r#"by_alt = { muted = "M", default = "D" }
+by_percentage = ["low", "mid", "high"]"#,
+        )
+        .unwrap();
+        let by_alt = FormatIcons::parse(value.get("by_alt"));
+        let by_percentage = FormatIcons::parse(value.get("by_percentage"));
+        let output = |alt: &str, percentage| WaybarOutput {
+            alt: alt.to_string(),
+            percentage,
+            ..WaybarOutput::default()
+        };
+
+        assert_eq!(by_alt.pick(&output("muted", None)), Some("M"));
+        assert_eq!(by_alt.pick(&output("loud", None)), Some("D"));
+        assert_eq!(by_percentage.pick(&output("", Some(0))), Some("low"));
+        assert_eq!(by_percentage.pick(&output("", Some(50))), Some("mid"));
+        assert_eq!(by_percentage.pick(&output("", Some(100))), Some("high"));
+        assert_eq!(by_percentage.pick(&output("", None)), None);
+        assert_eq!(FormatIcons::parse(None).pick(&output("", Some(5))), None);
+    }
+
+    #[test]
+    fn formats_with_waybar_placeholders() {
+        let format = parse_format(Some("{icon} {}[ {percentage}%]"));
+        let render = |percentage: Option<u8>| {
+            format.render(&[
+                ("text", Some(Value::from("vol"))),
+                ("icon", Some(Value::from("♪"))),
+                ("percentage", percentage.map(Value::from)),
+            ])
+        };
+        assert_eq!(render(Some(30)), "♪ vol 30%");
+        assert_eq!(render(None), "♪ vol");
+        assert_eq!(
+            parse_format(None).render(&[("text", Some(Value::from("hi")))]),
+            "hi"
+        );
+    }
+
+    #[test]
+    fn parses_class_colors() {
+        let value: toml::Value =
+            toml::from_str(r##"colors = { critical = "#ff0000", bad = "nope" }"##).unwrap();
+        let colors = parse_class_colors(value.get("colors"));
+        assert_eq!(colors.len(), 1);
+        assert_eq!(colors[0].0, "critical");
+        assert_eq!(colors[0].1.r, 1.0);
+    }
+}
@@ -7,7 +7,7 @@ use std::time::Duration;
 
 use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
 
-use super::GpuiModule;
+use super::{mark_dirty, GpuiModule};
 use crate::gpui_app::primitives::icons::weather as weather_icons;
 use crate::gpui_app::primitives::skeleton::shimmer_skeleton;
 use crate::gpui_app::theme::{LoadingState, Theme};
@@ -65,7 +65,7 @@ impl WeatherModule {
             if let Ok(mut guard) = state_handle.lock() {
                 *guard = next;
             }
-            dirty_handle.store(true, Ordering::Relaxed);
+            mark_dirty(&dirty_handle);
             std::thread::sleep(interval);
         });
 
@@ -192,9 +192,21 @@ impl GpuiModule for WeatherModule {
         self.dirty.swap(false, Ordering::Relaxed)
     }
 
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
     fn is_loading(&self) -> bool {
         self.state.lock().map(|s| s.is_loading()).unwrap_or(true)
     }
+
+    fn display_text(&self) -> Option<String> {
+        match &*self.state.lock().ok()? {
+            LoadingState::Loading => None,
+            LoadingState::Loaded(data) => Some(format!("{} {}", data.icon, data.temp)),
+            LoadingState::Error(_) => Some("--".to_string()),
+        }
+    }
 }
 
 impl Drop for WeatherModule {
@@ -1,4 +1,13 @@
-//! WiFi module for displaying network status.
+//! WiFi module showing the network and its signal strength.
+//!
+//! Shows the network name behind an icon with one to four signal bars; the
+//! `format` template can add the signal strength, noise and link speed.
+//! Clicking it shows the link details, the known networks in range (one
+//! click switches to one) and a button that turns Wi-Fi on or off.
+//!
+//! ```toml
+//! { type = "wifi", format = "{icon} {ssid}[ {rssi} dBm][ {rate} Mbps]" }
+//! ```
 
 use std::process::Command;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -7,66 +16,253 @@ use std::time::Duration;
 
 use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
 
-use super::GpuiModule;
+use super::template::{Template, Value};
+use super::{mark_dirty, sleep_unless_stopped, GpuiModule, PopupAction, PopupEvent, PopupSpec};
+use crate::gpui_app::popup_manager::notify_popup_needs_render;
 use crate::gpui_app::primitives::icons::wifi as wifi_icons;
-use crate::gpui_app::theme::Theme;
+use crate::gpui_app::primitives::Button;
+use crate::gpui_app::theme::{ButtonVariant, Theme};
+use crate::system::providers::NetworkProvider;
+use crate::system::wifi::{signal_bars, WifiNetwork, WifiStatus};
 
-/// WiFi module that displays the current WiFi network.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_FORMAT: &str = "{icon} {ssid}";
+/// Shown for `{ssid}` while macOS hides the network name
+const HIDDEN_SSID: &str = "Wi-Fi";
+const LOCATION_SETTINGS: &str =
+    "x-apple.systempreferences:com.apple.preference.security?Privacy_LocationServices";
+const POPUP_WIDTH: f64 = 280.0;
+const POPUP_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 28.0;
+const LINE_HEIGHT: f32 = 20.0;
+const ROW_HEIGHT: f32 = 28.0;
+const MAX_NETWORKS: usize = 8;
+
+/// Icon with `bars` (0–4) signal bars.
+fn signal_icon(bars: u8) -> &'static str {
+    match bars {
+        4 => wifi_icons::CONNECTED,
+        3 => wifi_icons::STRONG,
+        2 => wifi_icons::MEDIUM,
+        _ => wifi_icons::WEAK,
+    }
+}
+
+/// Bar text for `status`: `format` while connected, "Off" or "No network"
+/// otherwise.
+fn bar_text(format: &Template, status: Option<&WifiStatus>) -> String {
+    let Some(status) = status.filter(|s| s.connected()) else {
+        let state = match status {
+            Some(status) if status.power => "No network",
+            _ => "Off",
+        };
+        return format!("{} {}", wifi_icons::DISCONNECTED, state);
+    };
+    let bars = status.rssi.map(signal_bars).unwrap_or(0);
+    format.render(&[
+        ("icon", Some(Value::from(signal_icon(bars)))),
+        (
+            "ssid",
+            Some(Value::from(status.ssid.as_deref().unwrap_or(HIDDEN_SSID))),
+        ),
+        ("bars", Some(Value::from(bars))),
+        ("rssi", status.rssi.map(|v| Value::from(f64::from(v)))),
+        ("noise", status.noise.map(|v| Value::from(f64::from(v)))),
+        ("rate", status.rate.map(Value::from)),
+        ("interface", Some(Value::from(status.interface.as_str()))),
+    ])
+}
+
+/// Signal and link lines for the popup and tooltip.
+fn details(status: &WifiStatus) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(rssi) = status.rssi {
+        let mut signal = format!("Signal {} dBm ({} of 4 bars)", rssi, signal_bars(rssi));
+        if let Some(noise) = status.noise {
+            signal.push_str(&format!(", noise {} dBm", noise));
+        }
+        lines.push(signal);
+    }
+    match status.rate {
+        Some(rate) => lines.push(format!("Link {:.0} Mbps on {}", rate, status.interface)),
+        None => lines.push(format!("Interface {}", status.interface)),
+    }
+    lines
+}
+
+/// Last reading and scan.
+#[derive(Debug, Clone, Default)]
+struct WifiState {
+    status: Option<WifiStatus>,
+    /// Known networks in range, None while scanning
+    networks: Option<Vec<WifiNetwork>>,
+    /// Why the last switch or power change failed
+    error: Option<String>,
+}
+
+/// State shared between the bar and popup copies of the module.
+struct Shared {
+    network: Arc<dyn NetworkProvider>,
+    state: Mutex<WifiState>,
+    dirty: AtomicBool,
+    stop: AtomicBool,
+}
+
+impl Shared {
+    /// Reads the interface; returns true if anything changed.
+    fn poll(&self) -> bool {
+        let next = self.network.status();
+        let Ok(mut guard) = self.state.lock() else {
+            return false;
+        };
+        if guard.status == next {
+            return false;
+        }
+        guard.status = next;
+        true
+    }
+
+    /// Scans for known networks.
+    fn scan(&self, id: &str) {
+        if let Ok(mut guard) = self.state.lock() {
+            guard.networks = None;
+        }
+        notify_popup_needs_render(id);
+        let networks = self.network.known_networks();
+        if let Ok(mut guard) = self.state.lock() {
+            guard.networks = Some(networks);
+        }
+        notify_popup_needs_render(id);
+    }
+
+    fn powered(&self) -> bool {
+        self.state
+            .lock()
+            .is_ok_and(|guard| guard.status.as_ref().is_some_and(|s| s.power))
+    }
+
+    /// Shows the outcome of a switch or power change and re-reads the
+    /// interface, scanning again once Wi-Fi is back on.
+    fn apply(&self, id: &str, result: std::io::Result<()>) {
+        let error = result.err().map(|err| err.to_string());
+        if let Some(error) = &error {
+            log::warn!("wifi: {}", error);
+        }
+        if let Ok(mut guard) = self.state.lock() {
+            guard.error = error;
+        }
+        let was_on = self.powered();
+        self.poll();
+        mark_dirty(&self.dirty);
+        notify_popup_needs_render(id);
+        if !was_on && self.powered() {
+            self.scan(id);
+        }
+    }
+}
+
+/// WiFi module that displays the current network.
 pub struct WifiModule {
     id: String,
-    ssid: Arc<Mutex<Option<String>>>,
-    dirty: Arc<AtomicBool>,
-    stop: Arc<AtomicBool>,
+    format: Template,
+    shared: Arc<Shared>,
 }
 
 impl WifiModule {
-    /// Creates a new WiFi module.
-    pub fn new(id: &str) -> Self {
-        let ssid = Arc::new(Mutex::new(None));
-        let dirty = Arc::new(AtomicBool::new(true));
-        let stop = Arc::new(AtomicBool::new(false));
-
-        let ssid_handle = Arc::clone(&ssid);
-        let dirty_handle = Arc::clone(&dirty);
-        let stop_handle = Arc::clone(&stop);
+    /// Creates a new WiFi module that checks `network` every few seconds.
+    pub fn new(id: &str, network: Arc<dyn NetworkProvider>, format: Option<&str>) -> Self {
+        let shared = Arc::new(Shared {
+            network,
+            state: Mutex::new(WifiState::default()),
+            dirty: AtomicBool::new(true),
+            stop: AtomicBool::new(false),
+        });
+
+        let thread_shared = Arc::clone(&shared);
+        let thread_id = id.to_string();
         std::thread::spawn(move || {
-            let mut last: Option<String> = None;
-            while !stop_handle.load(Ordering::Relaxed) {
-                let next = Self::fetch_status();
-                if next != last {
-                    if let Ok(mut guard) = ssid_handle.lock() {
-                        *guard = next.clone();
-                    }
-                    dirty_handle.store(true, Ordering::Relaxed);
-                    last = next;
+            while !thread_shared.stop.load(Ordering::Relaxed) {
+                if thread_shared.poll() {
+                    mark_dirty(&thread_shared.dirty);
+                    notify_popup_needs_render(&thread_id);
                 }
-                std::thread::sleep(Duration::from_secs(5));
+                sleep_unless_stopped(POLL_INTERVAL, &thread_shared.stop);
             }
         });
 
         Self {
             id: id.to_string(),
-            ssid,
-            dirty,
-            stop,
+            format: Template::parse_or_literal(format.unwrap_or(DEFAULT_FORMAT)),
+            shared,
+        }
+    }
+
+    /// Returns a copy that shares state, for the popup registry.
+    pub fn popup_handle(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            format: self.format.clone(),
+            shared: Arc::clone(&self.shared),
         }
     }
 
-    fn fetch_status() -> Option<String> {
-        let output = Command::new("sh")
-            .args(["-c", "/System/Library/PrivateFrameworks/Apple80211.framework/Versions/Current/Resources/airport -I | grep ' SSID' | cut -d ':' -f 2 | tr -d ' '"])
-            .output()
-            .ok()
-            .and_then(|o| String::from_utf8(o.stdout).ok());
+    fn state(&self) -> WifiState {
+        self.shared
+            .state
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
 
-        if let Some(ssid) = output {
-            let ssid = ssid.trim();
-            if ssid.is_empty() {
-                return None;
-            }
-            return Some(ssid.to_string());
+    /// Runs `action` on a background thread and shows its outcome.
+    fn run(
+        &self,
+        action: impl FnOnce(&dyn NetworkProvider) -> std::io::Result<()> + Send + 'static,
+    ) {
+        let shared = Arc::clone(&self.shared);
+        let id = self.id.clone();
+        std::thread::spawn(move || {
+            let result = action(shared.network.as_ref());
+            shared.apply(&id, result);
+        });
+    }
+
+    fn render_network(&self, theme: &Theme, network: &WifiNetwork, current: bool) -> gpui::Div {
+        let mut row = div()
+            .h(px(ROW_HEIGHT))
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .text_color(theme.foreground_muted)
+                    .child(signal_icon(signal_bars(network.rssi))),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .overflow_hidden()
+                    .child(SharedString::from(network.ssid.clone())),
+            );
+        if current {
+            row = row.child(
+                div()
+                    .text_size(px(theme.font_size - 2.0))
+                    .text_color(theme.foreground_muted)
+                    .child("Connected"),
+            );
+        } else {
+            let value = format!("join:{}", network.ssid);
+            row = row.child(
+                Button::new(SharedString::from(format!("{}-{}", self.id, value)))
+                    .label("Join")
+                    .variant(ButtonVariant::Outline)
+                    .text_size(px(theme.font_size - 2.0))
+                    .on_popup_action(self.id.clone(), PopupAction::Select { value })
+                    .render(theme),
+            );
         }
-        None
+        row
     }
 }
 
@@ -76,18 +272,7 @@ impl GpuiModule for WifiModule {
     }
 
     fn render(&self, theme: &Theme) -> AnyElement {
-        let ssid = self.ssid.lock().ok().and_then(|s| s.clone());
-        let (_icon, text) = match ssid {
-            Some(ssid) => (
-                wifi_icons::CONNECTED,
-                format!("{} {}", wifi_icons::CONNECTED, ssid),
-            ),
-            None => (
-                wifi_icons::DISCONNECTED,
-                format!("{} Off", wifi_icons::DISCONNECTED),
-            ),
-        };
-
+        let text = bar_text(&self.format, self.state().status.as_ref());
         div()
             .flex()
             .items_center()
@@ -98,12 +283,301 @@ impl GpuiModule for WifiModule {
     }
 
     fn update(&mut self) -> bool {
-        self.dirty.swap(false, Ordering::Relaxed)
+        self.shared.dirty.swap(false, Ordering::Relaxed)
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn display_text(&self) -> Option<String> {
+        Some(bar_text(&self.format, self.state().status.as_ref()))
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        let status = self.state().status.filter(|s| s.connected())?;
+        let mut lines = vec![status.ssid.clone().unwrap_or_else(|| HIDDEN_SSID.into())];
+        lines.extend(details(&status));
+        Some(lines.join("\n"))
+    }
+
+    fn popup_spec(&self) -> Option<PopupSpec> {
+        let state = self.state();
+        let status = state.status.unwrap_or_default();
+        let mut lines = if status.connected() {
+            details(&status).len()
+        } else {
+            0
+        };
+        if status.ssid_hidden() {
+            lines += 2;
+        }
+        if state.error.is_some() {
+            lines += 1;
+        }
+        let rows = if status.power {
+            // Subheader plus the networks, or a line saying why there are none
+            let networks = state.networks.map_or(0, |n| n.len().min(MAX_NETWORKS));
+            1 + networks.max(1)
+        } else {
+            0
+        };
+        let height = POPUP_PADDING * 2.0
+            + HEADER_HEIGHT
+            + LINE_HEIGHT * lines as f32
+            + ROW_HEIGHT * rows as f32;
+        Some(PopupSpec::new(POPUP_WIDTH, height as f64))
+    }
+
+    fn render_popup(&self, theme: &Theme) -> Option<AnyElement> {
+        let state = self.state();
+        let status = state.status.clone().unwrap_or_default();
+        let title = match &status {
+            s if s.connected() => s.ssid.clone().unwrap_or_else(|| HIDDEN_SSID.into()),
+            s if s.power => "Not connected".to_string(),
+            _ => "Wi-Fi is off".to_string(),
+        };
+        let (power_label, power_value) = if status.power {
+            ("Turn off", "power:off")
+        } else {
+            ("Turn on", "power:on")
+        };
+        let header = div()
+            .h(px(HEADER_HEIGHT))
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .flex_1()
+                    .overflow_hidden()
+                    .font_weight(gpui::FontWeight::SEMIBOLD)
+                    .child(SharedString::from(title)),
+            )
+            .child(
+                Button::new(SharedString::from(format!("{}-power", self.id)))
+                    .label(power_label)
+                    .variant(ButtonVariant::Outline)
+                    .text_size(px(theme.font_size - 2.0))
+                    .on_popup_action(
+                        self.id.clone(),
+                        PopupAction::Select {
+                            value: power_value.to_string(),
+                        },
+                    )
+                    .render(theme),
+            );
+
+        let line = |text: String| {
+            div()
+                .h(px(LINE_HEIGHT))
+                .flex()
+                .items_center()
+                .text_size(px(theme.font_size - 1.0))
+                .text_color(theme.foreground_muted)
+                .child(SharedString::from(text))
+        };
+        let mut popup = div()
+            .flex()
+            .flex_col()
+            .p(px(POPUP_PADDING))
+            .text_size(px(theme.font_size))
+            .text_color(theme.foreground)
+            .child(header);
+        if status.connected() {
+            for text in details(&status) {
+                popup = popup.child(line(text));
+            }
+        }
+        if status.ssid_hidden() {
+            popup = popup.child(line(
+                "Network names need Location Services access".to_string(),
+            ));
+            popup = popup.child(
+                Button::new(SharedString::from(format!("{}-location", self.id)))
+                    .label("Open Location Services settings")
+                    .variant(ButtonVariant::Ghost)
+                    .text_size(px(theme.font_size - 1.0))
+                    .on_popup_action(
+                        self.id.clone(),
+                        PopupAction::Select {
+                            value: "location".to_string(),
+                        },
+                    )
+                    .render(theme)
+                    .h(px(LINE_HEIGHT))
+                    .justify_start(),
+            );
+        }
+        if let Some(error) = state.error {
+            popup = popup.child(line(error).text_color(theme.destructive));
+        }
+        if !status.power {
+            return Some(popup.into_any_element());
+        }
+
+        popup = popup.child(
+            div()
+                .h(px(ROW_HEIGHT))
+                .flex()
+                .items_end()
+                .pb(px(4.0))
+                .font_weight(gpui::FontWeight::SEMIBOLD)
+                .child("Known networks"),
+        );
+        let networks = state.networks.as_deref().unwrap_or_default();
+        let empty = match state.networks {
+            None => Some("Scanning…"),
+            Some(ref networks) if networks.is_empty() => Some("No known networks in range"),
+            Some(_) => None,
+        };
+        if let Some(empty) = empty {
+            popup = popup.child(
+                div()
+                    .h(px(ROW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .text_color(theme.foreground_muted)
+                    .child(empty),
+            );
+        }
+        for network in networks.iter().take(MAX_NETWORKS) {
+            let current = status.ssid.as_deref() == Some(network.ssid.as_str());
+            popup = popup.child(self.render_network(theme, network, current));
+        }
+        Some(popup.into_any_element())
+    }
+
+    fn on_popup_action(&mut self, action: PopupAction) {
+        let PopupAction::Select { value } = action else {
+            return;
+        };
+        match value.as_str() {
+            "power:on" => self.run(|network| network.set_power(true)),
+            "power:off" => self.run(|network| network.set_power(false)),
+            "location" => {
+                if let Err(err) = Command::new("open").arg(LOCATION_SETTINGS).spawn() {
+                    log::warn!("wifi: failed to open settings: {}", err);
+                }
+            }
+            _ => {
+                if let Some(ssid) = value.strip_prefix("join:") {
+                    let ssid = ssid.to_string();
+                    self.run(move |network| network.join(&ssid));
+                }
+            }
+        }
+    }
+
+    fn on_popup_event(&mut self, event: PopupEvent) {
+        if matches!(event, PopupEvent::Opened) {
+            if let Ok(mut guard) = self.shared.state.lock() {
+                guard.error = None;
+            }
+            let shared = Arc::clone(&self.shared);
+            let id = self.id.clone();
+            std::thread::spawn(move || shared.scan(&id));
+        }
     }
 }
 
 impl Drop for WifiModule {
     fn drop(&mut self) {
-        self.stop.store(true, Ordering::Relaxed);
+        self.shared.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::providers::mock::MockNetwork;
+
+    fn connected(ssid: Option<&str>) -> WifiStatus {
+        WifiStatus {
+            interface: "en0".to_string(),
+            power: true,
+            ssid: ssid.map(str::to_string),
+            rssi: Some(-60),
+            noise: Some(-92),
+            rate: Some(866.0),
+        }
+    }
+
+    #[test]
+    fn shows_the_network_name() {
+        let network = MockNetwork::default();
+        *network.status.lock().unwrap() = Some(connected(Some("Home")));
+        let module = WifiModule::new("wifi", Arc::new(network), None);
+        // The first check runs on a background thread
+        for _ in 0..100 {
+            if module.state().status.is_some() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(
+            module.state().status.and_then(|s| s.ssid).as_deref(),
+            Some("Home")
+        );
+    }
+
+    #[test]
+    fn formats_signal_and_link_speed() {
+        let format = Template::parse_or_literal("{ssid}[ {rssi} dBm][ {rate} Mbps] {bars}");
+        let status = connected(Some("Home"));
+        assert_eq!(bar_text(&format, Some(&status)), "Home -60 dBm 866 Mbps 3");
+        let default = Template::parse_or_literal(DEFAULT_FORMAT);
+        assert_eq!(
+            bar_text(&default, Some(&connected(None))),
+            format!("{} Wi-Fi", wifi_icons::STRONG)
+        );
+        let on = WifiStatus {
+            power: true,
+            ..Default::default()
+        };
+        assert!(bar_text(&default, Some(&on)).ends_with("No network"));
+        assert!(bar_text(&default, None).ends_with("Off"));
+        assert_eq!(
+            details(&status),
+            vec![
+                "Signal -60 dBm (3 of 4 bars), noise -92 dBm",
+                "Link 866 Mbps on en0"
+            ]
+        );
+    }
+
+    #[test]
+    fn joins_and_turns_wifi_off() {
+        let network = Arc::new(MockNetwork {
+            status: Mutex::new(Some(connected(Some("Home")))),
+            networks: vec![WifiNetwork {
+                ssid: "Office".to_string(),
+                rssi: -50,
+            }],
+        });
+        let shared = Shared {
+            network: network.clone(),
+            state: Mutex::new(WifiState::default()),
+            dirty: AtomicBool::new(false),
+            stop: AtomicBool::new(false),
+        };
+        shared.apply("wifi", network.join("Office"));
+        let state = shared.state.lock().unwrap().clone();
+        assert_eq!(state.status.and_then(|s| s.ssid).as_deref(), Some("Office"));
+        assert_eq!(state.error, None);
+
+        shared.apply("wifi", network.join("Cafe"));
+        let state = shared.state.lock().unwrap().clone();
+        assert_eq!(state.error.as_deref(), Some("Could not find network Cafe."));
+
+        shared.apply("wifi", network.set_power(false));
+        let status = shared.state.lock().unwrap().status.clone().unwrap();
+        assert!(!status.power);
+        assert!(!status.connected());
+
+        // Turning it back on scans again
+        shared.apply("wifi", network.set_power(true));
+        let networks = shared.state.lock().unwrap().networks.clone();
+        assert_eq!(networks.map(|n| n.len()), Some(1));
     }
 }
@@ -1,70 +1,382 @@
-//! Window title module for displaying the active window title.
+//! Window title module driven by Accessibility (AX) notifications.
+//!
+//! An `AXObserver` on the frontmost app reports focused/main window changes
+//! and title changes of the focused window, so titles update as soon as they
+//! change instead of on a polling interval. The observer is attached to the
+//! main run loop and re-created whenever the frontmost app changes (checked
+//! in `update()`, which runs on the main thread).
+//!
+//! Titles can be rewritten per app with regex rules:
+//!
+//! ```toml
+//! { type = "window_title", rewrite = [
+//!   { app = "Google Chrome", pattern = " - Google Chrome$", replace = "" },
+//!   { pattern = "^(.+) — (.+)$", replace = "$2: $1" },
+//! ] }
+//! ```
+//!
+//! With `marquee`, long titles scroll instead of being truncated.
+//!
+//! Requires the Accessibility permission (System Settings → Privacy &
+//! Security → Accessibility); the module stays empty until it's granted.
 
-use std::process::Command;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
+use std::cell::RefCell;
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
 use std::time::Duration;
 
+use core_foundation::base::{CFType, CFTypeRef, TCFType};
+use core_foundation::runloop::{kCFRunLoopDefaultMode, CFRunLoop, CFRunLoopSource};
+use core_foundation::string::{CFString, CFStringRef};
 use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+use regex::Regex;
 
 use super::{truncate_text, GpuiModule};
+use crate::gpui_app::primitives::{Marquee, MarqueeSettings};
+use crate::gpui_app::request_immediate_refresh;
 use crate::gpui_app::theme::Theme;
 
-/// Window title module that displays the current window title.
-#[allow(dead_code)]
+// Accessibility FFI bindings
+mod ffi {
+    use std::ffi::c_void;
+
+    use core_foundation::base::CFTypeRef;
+    use core_foundation::runloop::CFRunLoopSourceRef;
+    use core_foundation::string::CFStringRef;
+
+    pub type AXError = i32;
+    pub type AXUIElementRef = *const c_void;
+    pub type AXObserverRef = *const c_void;
+
+    pub const K_AX_ERROR_SUCCESS: AXError = 0;
+
+    pub type AXObserverCallback = extern "C" fn(
+        observer: AXObserverRef,
+        element: AXUIElementRef,
+        notification: CFStringRef,
+        refcon: *mut c_void,
+    );
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        pub fn AXIsProcessTrusted() -> bool;
+
+        pub fn AXUIElementCreateApplication(pid: i32) -> AXUIElementRef;
+
+        pub fn AXUIElementCopyAttributeValue(
+            element: AXUIElementRef,
+            attribute: CFStringRef,
+            value: *mut CFTypeRef,
+        ) -> AXError;
+
+        pub fn AXObserverCreate(
+            pid: i32,
+            callback: AXObserverCallback,
+            observer: *mut AXObserverRef,
+        ) -> AXError;
+
+        pub fn AXObserverAddNotification(
+            observer: AXObserverRef,
+            element: AXUIElementRef,
+            notification: CFStringRef,
+            refcon: *mut c_void,
+        ) -> AXError;
+
+        pub fn AXObserverRemoveNotification(
+            observer: AXObserverRef,
+            element: AXUIElementRef,
+            notification: CFStringRef,
+        ) -> AXError;
+
+        pub fn AXObserverGetRunLoopSource(observer: AXObserverRef) -> CFRunLoopSourceRef;
+    }
+}
+
+/// App-level notifications: the focused window changed.
+const APP_NOTIFICATIONS: &[&str] = &["AXFocusedWindowChanged", "AXMainWindowChanged"];
+/// Window-level notification: the focused window's title changed.
+const TITLE_CHANGED: &str = "AXTitleChanged";
+
+/// Set by the AX callback when the title may have changed.
+static TITLE_DIRTY: AtomicBool = AtomicBool::new(true);
+
+/// Latest (app name, window title), shared by all window title modules.
+static CURRENT: Mutex<(String, String)> = Mutex::new((String::new(), String::new()));
+
+/// Bumped whenever `CURRENT` changes.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Whether the missing-permission warning has been logged.
+static WARNED_UNTRUSTED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// Observer for the frontmost app; only touched on the main thread.
+    static WATCH: RefCell<Option<Watch>> = const { RefCell::new(None) };
+}
+
+extern "C" fn ax_notification(
+    _observer: ffi::AXObserverRef,
+    _element: ffi::AXUIElementRef,
+    _notification: CFStringRef,
+    _refcon: *mut c_void,
+) {
+    TITLE_DIRTY.store(true, Ordering::Relaxed);
+    request_immediate_refresh();
+}
+
+/// Copies an AX attribute, returning None on error or if unset.
+fn copy_attribute(element: &CFType, attribute: &str) -> Option<CFType> {
+    let attribute = CFString::new(attribute);
+    let mut value: CFTypeRef = std::ptr::null();
+    let status = unsafe {
+        ffi::AXUIElementCopyAttributeValue(
+            element.as_CFTypeRef(),
+            attribute.as_concrete_TypeRef(),
+            &mut value,
+        )
+    };
+    if status != ffi::K_AX_ERROR_SUCCESS || value.is_null() {
+        return None;
+    }
+    Some(unsafe { CFType::wrap_under_create_rule(value) })
+}
+
+/// An AX observer attached to one app's process.
+struct Watch {
+    pid: i32,
+    observer: CFType,
+    app: CFType,
+    /// Focused window currently registered for title changes
+    window: Option<CFType>,
+    source: CFRunLoopSource,
+}
+
+impl Watch {
+    /// Creates an observer for `pid` and adds it to the main run loop.
+    fn new(pid: i32) -> Option<Self> {
+        let mut observer: ffi::AXObserverRef = std::ptr::null();
+        let status = unsafe { ffi::AXObserverCreate(pid, ax_notification, &mut observer) };
+        if status != ffi::K_AX_ERROR_SUCCESS || observer.is_null() {
+            log::debug!("AXObserverCreate failed for pid {}: {}", pid, status);
+            return None;
+        }
+        let observer = unsafe { CFType::wrap_under_create_rule(observer) };
+        let app = unsafe { CFType::wrap_under_create_rule(ffi::AXUIElementCreateApplication(pid)) };
+
+        for name in APP_NOTIFICATIONS {
+            let name = CFString::new(name);
+            unsafe {
+                ffi::AXObserverAddNotification(
+                    observer.as_CFTypeRef(),
+                    app.as_CFTypeRef(),
+                    name.as_concrete_TypeRef(),
+                    std::ptr::null_mut(),
+                );
+            }
+        }
+
+        let source = unsafe {
+            CFRunLoopSource::wrap_under_get_rule(ffi::AXObserverGetRunLoopSource(
+                observer.as_CFTypeRef(),
+            ))
+        };
+        CFRunLoop::get_main().add_source(&source, unsafe { kCFRunLoopDefaultMode });
+
+        Some(Self {
+            pid,
+            observer,
+            app,
+            window: None,
+            source,
+        })
+    }
+
+    /// Re-reads the focused window, moving the title-change registration to
+    /// it if focus moved, and returns its title.
+    fn refresh(&mut self) -> String {
+        let window = copy_attribute(&self.app, "AXFocusedWindow");
+        let same = match (&window, &self.window) {
+            (Some(a), Some(b)) => a == b,
+            (None, None) => true,
+            _ => false,
+        };
+        if !same {
+            let name = CFString::new(TITLE_CHANGED);
+            unsafe {
+                if let Some(old) = &self.window {
+                    ffi::AXObserverRemoveNotification(
+                        self.observer.as_CFTypeRef(),
+                        old.as_CFTypeRef(),
+                        name.as_concrete_TypeRef(),
+                    );
+                }
+                if let Some(new) = &window {
+                    ffi::AXObserverAddNotification(
+                        self.observer.as_CFTypeRef(),
+                        new.as_CFTypeRef(),
+                        name.as_concrete_TypeRef(),
+                        std::ptr::null_mut(),
+                    );
+                }
+            }
+            self.window = window;
+        }
+
+        self.window
+            .as_ref()
+            .and_then(|window| copy_attribute(window, "AXTitle"))
+            .and_then(|title| title.downcast::<CFString>())
+            .map(|title| title.to_string())
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        CFRunLoop::get_main().remove_source(&self.source, unsafe { kCFRunLoopDefaultMode });
+    }
+}
+
+/// Follows the frontmost app and refreshes `CURRENT` when its focused
+/// window or title changed. Must be called on the main thread.
+fn poll() {
+    use objc2_app_kit::NSWorkspace;
+    use objc2_foundation::MainThreadMarker;
+
+    let Some(_mtm) = MainThreadMarker::new() else {
+        log::warn!("window_title poll called off main thread");
+        return;
+    };
+    if !unsafe { ffi::AXIsProcessTrusted() } {
+        if !WARNED_UNTRUSTED.swap(true, Ordering::Relaxed) {
+            log::warn!("window_title needs the Accessibility permission");
+        }
+        return;
+    }
+
+    let Some(front) = NSWorkspace::sharedWorkspace().frontmostApplication() else {
+        return;
+    };
+    let pid = front.processIdentifier();
+    let app_name = front
+        .localizedName()
+        .map(|n| n.to_string())
+        .unwrap_or_default();
+
+    WATCH.with(|watch| {
+        let mut watch = watch.borrow_mut();
+        if watch.as_ref().map(|w| w.pid) != Some(pid) {
+            *watch = Watch::new(pid);
+            TITLE_DIRTY.store(true, Ordering::Relaxed);
+        }
+        if !TITLE_DIRTY.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        let title = watch.as_mut().map(Watch::refresh).unwrap_or_default();
+        if let Ok(mut current) = CURRENT.lock() {
+            let next = (app_name, title);
+            if *current != next {
+                *current = next;
+                GENERATION.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    });
+}
+
+/// A regex replacement applied to window titles, optionally scoped to an app.
+#[derive(Debug, Clone)]
+pub struct RewriteRule {
+    app: Option<String>,
+    pattern: Regex,
+    replace: String,
+}
+
+/// Parses the `rewrite` array from config. Invalid entries are logged and
+/// skipped.
+pub fn parse_rules(value: Option<&toml::Value>) -> Vec<RewriteRule> {
+    let Some(entries) = value.and_then(|v| v.as_array()) else {
+        return Vec::new();
+    };
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let pattern = entry.get("pattern").and_then(|v| v.as_str())?;
+            let pattern = match Regex::new(pattern) {
+                Ok(pattern) => pattern,
+                Err(err) => {
+                    log::warn!(
+                        "window_title: invalid rewrite pattern '{}': {}",
+                        pattern,
+                        err
+                    );
+                    return None;
+                }
+            };
+            Some(RewriteRule {
+                app: entry
+                    .get("app")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string),
+                pattern,
+                replace: entry
+                    .get("replace")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Applies every rule matching `app` to `title`, in order.
+pub fn rewrite(rules: &[RewriteRule], app: &str, title: &str) -> String {
+    rules
+        .iter()
+        .filter(|rule| rule.app.as_deref().is_none_or(|a| a == app))
+        .fold(title.to_string(), |title, rule| {
+            rule.pattern
+                .replace_all(&title, rule.replace.as_str())
+                .into_owned()
+        })
+}
+
+/// Window title module that displays the focused window's title.
 pub struct WindowTitleModule {
     id: String,
     max_length: usize,
-    title: Arc<Mutex<String>>,
-    dirty: Arc<AtomicBool>,
-    stop: Arc<AtomicBool>,
+    rules: Vec<RewriteRule>,
+    marquee: Option<Marquee>,
+    title: String,
+    generation: u64,
 }
 
 impl WindowTitleModule {
     /// Creates a new window title module.
-    pub fn new(id: &str, max_length: usize) -> Self {
-        let title = Arc::new(Mutex::new(String::new()));
-        let dirty = Arc::new(AtomicBool::new(true));
-        let stop = Arc::new(AtomicBool::new(false));
-
-        let title_handle = Arc::clone(&title);
-        let dirty_handle = Arc::clone(&dirty);
-        let stop_handle = Arc::clone(&stop);
-        std::thread::spawn(move || {
-            let mut last = String::new();
-            while !stop_handle.load(Ordering::Relaxed) {
-                let next = Self::fetch_status(max_length);
-                if next != last {
-                    if let Ok(mut guard) = title_handle.lock() {
-                        *guard = next.clone();
-                    }
-                    dirty_handle.store(true, Ordering::Relaxed);
-                    last = next;
-                }
-                std::thread::sleep(Duration::from_secs(1));
-            }
-        });
-
-        Self {
+    ///
+    /// @param id - Unique module identifier
+    /// @param max_length - Maximum display length before truncation
+    /// @param rules - Title rewrite rules, applied before truncation
+    pub fn new(id: &str, max_length: usize, rules: Vec<RewriteRule>) -> Self {
+        let mut module = Self {
             id: id.to_string(),
             max_length,
-            title,
-            dirty,
-            stop,
-        }
+            rules,
+            marquee: None,
+            title: String::new(),
+            generation: u64::MAX,
+        };
+        module.update();
+        module
     }
 
-    fn fetch_status(max_length: usize) -> String {
-        let output = Command::new("osascript")
-            .args(["-e", "tell application \"System Events\" to get title of front window of first application process whose frontmost is true"])
-            .output()
-            .ok()
-            .and_then(|o| String::from_utf8(o.stdout).ok());
-
-        if let Some(title) = output {
-            return truncate_text(title.trim(), max_length);
-        }
-        String::new()
+    /// Scrolls long titles instead of truncating them.
+    pub fn with_marquee(mut self, settings: Option<MarqueeSettings>) -> Self {
+        self.marquee = settings.map(|settings| Marquee::new(&self.id, settings));
+        // Re-read the title without truncation
+        self.generation = u64::MAX;
+        self.update();
+        self
     }
 }
 
@@ -74,23 +386,90 @@ impl GpuiModule for WindowTitleModule {
     }
 
     fn render(&self, theme: &Theme) -> AnyElement {
-        let title = self.title.lock().map(|t| t.clone()).unwrap_or_default();
         div()
             .flex()
             .items_center()
             .text_color(theme.foreground)
             .text_size(px(theme.font_size))
-            .child(SharedString::from(title))
+            .child(match &self.marquee {
+                Some(marquee) => marquee.render(&self.title),
+                None => SharedString::from(self.title.clone()).into_any_element(),
+            })
             .into_any_element()
     }
 
+    fn is_animating(&self) -> bool {
+        self.marquee.as_ref().is_some_and(|m| m.is_animating())
+    }
+
     fn update(&mut self) -> bool {
-        self.dirty.swap(false, Ordering::Relaxed)
+        poll();
+        let generation = GENERATION.load(Ordering::Relaxed);
+        if generation == self.generation {
+            return false;
+        }
+        self.generation = generation;
+        let next = CURRENT
+            .lock()
+            .map(|current| {
+                let (app, title) = &*current;
+                let title = rewrite(&self.rules, app, title);
+                if self.marquee.is_some() {
+                    title
+                } else {
+                    truncate_text(&title, self.max_length)
+                }
+            })
+            .unwrap_or_default();
+        if next != self.title {
+            self.title = next;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn next_update(&self) -> Option<Duration> {
+        None
+    }
+
+    fn display_text(&self) -> Option<String> {
+        (!self.title.is_empty()).then(|| self.title.clone())
     }
 }
 
-impl Drop for WindowTitleModule {
-    fn drop(&mut self) {
-        self.stop.store(true, Ordering::Relaxed);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(source: &str) -> Vec<RewriteRule> {
+        let value: toml::Value = toml::from_str(source).unwrap();
+        parse_rules(value.get("rewrite"))
+    }
+
+    #[test]
+    fn rules_apply_in_order_and_respect_app_scope() {
+        let rules = rules(
+            r#"rewrite = [
+                { app = "Google Chrome", pattern = " - Google Chrome$" },
+                { pattern = "^(.+) — (.+)$", replace = "$2: $1" },
+            ]"#,
+        );
+        assert_eq!(
+            rewrite(&rules, "Google Chrome", "Inbox - Google Chrome"),
+            "Inbox"
+        );
+        assert_eq!(
+            rewrite(&rules, "Safari", "Inbox - Google Chrome"),
+            "Inbox - Google Chrome"
+        );
+        assert_eq!(rewrite(&rules, "Code", "main.rs — sinew"), "sinew: main.rs");
+    }
+
+    #[test]
+    fn invalid_patterns_are_skipped() {
+        let rules = rules(r#"rewrite = [{ pattern = "(" }, { pattern = "x" }, { app = "y" }]"#);
+        assert_eq!(rules.len(), 1);
+        assert!(parse_rules(None).is_empty());
     }
 }
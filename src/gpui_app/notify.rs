@@ -0,0 +1,199 @@
+//! Notify items pushed by external services.
+//!
+//! `sinew-msg notify ci "main passing" --color success --ttl 60`, or
+//! `POST /notify/ci` on the HTTP API, adds a short text item named `ci` to
+//! the zone set by `bar.notify.zone`, or replaces an existing one. Like
+//! progress items they need no config: build bots, CI webhooks and alerting
+//! tools create them on first use. Items disappear after their ttl
+//! (`bar.notify.ttl` when not given, 0 = until removed) or right away with
+//! `sinew-msg notify ci remove`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+
+use crate::config::parse_hex_color;
+use crate::gpui_app::request_immediate_refresh;
+use crate::gpui_app::theme::Theme;
+use crate::gpui_app::toast::ToastStyle;
+
+/// Items on screen, in creation order.
+static ITEMS: Mutex<Vec<NotifyItem>> = Mutex::new(Vec::new());
+/// `bar.notify.ttl` in milliseconds, 0 = items stay until removed.
+static DEFAULT_TTL_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Text color of an item: a theme accent or a hex color.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ItemColor {
+    Style(ToastStyle),
+    Hex(gpui::Rgba),
+}
+
+impl ItemColor {
+    /// Parses `info`, `success`, `warning`, `error` or a hex color.
+    pub fn parse(value: &str) -> Option<Self> {
+        if let Some(style) = ToastStyle::parse(value) {
+            return Some(Self::Style(style));
+        }
+        let (r, g, b, a) = parse_hex_color(value)?;
+        Some(Self::Hex(gpui::Rgba {
+            r: r as f32,
+            g: g as f32,
+            b: b as f32,
+            a: a as f32,
+        }))
+    }
+
+    fn resolve(self, theme: &Theme) -> gpui::Rgba {
+        match self {
+            Self::Style(ToastStyle::Info) => theme.info,
+            Self::Style(ToastStyle::Success) => theme.success,
+            Self::Style(ToastStyle::Warning) => theme.warning,
+            Self::Style(ToastStyle::Error) => theme.destructive,
+            Self::Hex(color) => color,
+        }
+    }
+}
+
+/// A named text item.
+#[derive(Debug, Clone)]
+struct NotifyItem {
+    name: String,
+    text: String,
+    color: Option<ItemColor>,
+    /// When the item disappears (None = until removed)
+    expires_at: Option<Instant>,
+}
+
+/// Sets the ttl of items that don't give one.
+pub fn set_default_ttl(ttl: Duration) {
+    DEFAULT_TTL_MS.store(ttl.as_millis() as u64, Ordering::Relaxed);
+}
+
+/// Creates or replaces the item `name`.
+///
+/// @param name - Item name, used to update or remove it
+/// @param text - Text shown in the bar
+/// @param color - Text color (None = theme foreground)
+/// @param ttl - How long the item stays (None = `bar.notify.ttl`)
+pub fn set(name: &str, text: &str, color: Option<ItemColor>, ttl: Option<Duration>) {
+    let ttl = ttl.unwrap_or_else(|| Duration::from_millis(DEFAULT_TTL_MS.load(Ordering::Relaxed)));
+    let expires_at = (!ttl.is_zero()).then(|| Instant::now() + ttl);
+    let item = NotifyItem {
+        name: name.to_string(),
+        text: text.to_string(),
+        color,
+        expires_at,
+    };
+    if let Ok(mut items) = ITEMS.lock() {
+        match items.iter_mut().find(|item| item.name == name) {
+            Some(existing) => *existing = item,
+            None => items.push(item),
+        }
+    }
+    request_immediate_refresh();
+
+    if expires_at.is_some() {
+        // Redraw once the item is due to disappear
+        std::thread::spawn(move || {
+            std::thread::sleep(ttl);
+            request_immediate_refresh();
+        });
+    }
+}
+
+/// Removes the item `name`, returning false if there was none.
+pub fn remove(name: &str) -> bool {
+    let removed = ITEMS
+        .lock()
+        .map(|mut items| {
+            let before = items.len();
+            items.retain(|item| item.name != name);
+            items.len() != before
+        })
+        .unwrap_or(false);
+    if removed {
+        request_immediate_refresh();
+    }
+    removed
+}
+
+/// Renders the current items, dropping expired ones.
+pub fn render(theme: &Theme) -> Vec<AnyElement> {
+    let items = match ITEMS.lock() {
+        Ok(mut items) => {
+            let now = Instant::now();
+            items.retain(|item| item.expires_at.is_none_or(|at| at > now));
+            items.clone()
+        }
+        Err(_) => return Vec::new(),
+    };
+
+    items
+        .into_iter()
+        .map(|item| {
+            let color = item
+                .color
+                .map(|color| color.resolve(theme))
+                .unwrap_or(theme.foreground);
+            div()
+                .flex()
+                .items_center()
+                .text_size(px(theme.font_size))
+                .text_color(color)
+                .child(SharedString::from(item.text))
+                .into_any_element()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(
+            ItemColor::parse("success"),
+            Some(ItemColor::Style(ToastStyle::Success))
+        );
+        assert_eq!(
+            ItemColor::parse("error"),
+            Some(ItemColor::Style(ToastStyle::Error))
+        );
+        match ItemColor::parse("#ff0000") {
+            Some(ItemColor::Hex(color)) => {
+                assert!((color.r - 1.0).abs() < 0.01);
+                assert!(color.g.abs() < 0.01);
+            }
+            other => panic!("expected a hex color, got {:?}", other),
+        }
+        assert_eq!(ItemColor::parse("loud"), None);
+    }
+
+    #[test]
+    fn replaces_and_removes_items() {
+        set("test-notify-ci", "running", None, Some(Duration::ZERO));
+        set(
+            "test-notify-ci",
+            "passed",
+            ItemColor::parse("success"),
+            Some(Duration::ZERO),
+        );
+        let texts = |name: &str| -> Vec<String> {
+            ITEMS
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|item| item.name == name)
+                .map(|item| item.text.clone())
+                .collect()
+        };
+        assert_eq!(texts("test-notify-ci"), vec!["passed"]);
+        assert!(remove("test-notify-ci"));
+        assert!(texts("test-notify-ci").is_empty());
+        assert!(!remove("test-notify-ci"));
+    }
+}
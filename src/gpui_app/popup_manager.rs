@@ -2,8 +2,12 @@
 //!
 //! Provides generic infrastructure for showing/hiding popup windows:
 //! - Global visibility state tracking
-//! - Mutual exclusion between popups
+//! - Mutual exclusion between popups, except pinned ones: a pinned popup
+//!   moves into a window of its own (keyed by module id) when another
+//!   popup opens, so several can be open at once
 //! - Click-outside-to-close monitoring
+//! - Pre-rendering: hovering a module lays its popup out in the hidden
+//!   window, so opening it only reveals the window
 //! - Window-level manipulation
 
 use async_channel::{Receiver, Sender};
@@ -16,20 +20,48 @@ use objc2_foundation::{NSNotification, NSNotificationCenter, NSNotificationName,
 use std::cell::RefCell;
 use std::ptr::NonNull;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::atomic::{AtomicI64, Ordering as AtomicIOrdering};
 use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
 use std::sync::OnceLock;
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
 
-use crate::gpui_app::modules::{get_module, get_popup_spec, PopupEvent, PopupType};
+use crate::gpui_app::modules::{get_module, get_popup_spec, PopupAnchor, PopupEvent, PopupType};
+use crate::gpui_app::window_registry::{self, WindowRole};
 
 /// Current module ID being displayed in a popup.
 static CURRENT_MODULE_ID: RwLock<String> = RwLock::new(String::new());
 
+/// Module whose popup the hidden windows lay out ahead of a click.
+static PREPARED_MODULE_ID: RwLock<String> = RwLock::new(String::new());
+
 /// Global visibility state for the popup/panel.
 static POPUP_VISIBLE: AtomicBool = AtomicBool::new(false);
 
+/// Whether the visible popup takes keyboard focus (its window is made key).
+static POPUP_WANTS_KEYBOARD: AtomicBool = AtomicBool::new(false);
+
+/// Whether the visible popup is pinned open (clicks outside don't close it).
+static POPUP_PINNED: AtomicBool = AtomicBool::new(false);
+
+/// Module ids of detached (pinned) popups. Their windows are tagged
+/// `WindowRole::Detached` in the window registry.
+static DETACHED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Detached popups waiting for the bar to create their window.
+static DETACH_QUEUE: Mutex<Vec<DetachRequest>> = Mutex::new(Vec::new());
+
+/// A pinned popup that needs a window of its own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetachRequest {
+    pub module_id: String,
+    pub popup_type: PopupType,
+    /// AppKit frame (x, y, width, height) the popup had when detached
+    pub frame: (f64, f64, f64, f64),
+}
+
+/// Set by a module callback that wants the popup closed once it returns.
+static POPUP_HIDE_REQUESTED: AtomicBool = AtomicBool::new(false);
+
 /// Pending panel show - set when we need to show panel after content renders.
 /// Format: (popup_type as u8, height). Panel=0, Popup=1.
 static PENDING_SHOW: Mutex<Option<(PopupType, f64)>> = Mutex::new(None);
@@ -52,8 +84,6 @@ static LAST_GLOBAL_CLICK_MS: AtomicU64 = AtomicU64::new(0);
 static SCREEN_HEIGHT: OnceLock<Mutex<f64>> = OnceLock::new();
 static SCREEN_WIDTH: OnceLock<Mutex<f64>> = OnceLock::new();
 static SCREEN_BAR_HEIGHT: OnceLock<Mutex<f64>> = OnceLock::new();
-static PANEL_WINDOW_NUMBER: AtomicI64 = AtomicI64::new(0);
-static POPUP_WINDOW_NUMBER: AtomicI64 = AtomicI64::new(0);
 
 struct ModuleChangeBus {
     subscribers: Mutex<Vec<Sender<String>>>,
@@ -115,49 +145,54 @@ pub fn set_bar_height(height: f64) {
     }
 }
 
-pub(crate) fn set_window_number(popup_type: PopupType, number: i64) {
-    match popup_type {
-        PopupType::Panel => {
-            PANEL_WINDOW_NUMBER.store(number, AtomicIOrdering::SeqCst);
-        }
-        PopupType::Popup => {
-            POPUP_WINDOW_NUMBER.store(number, AtomicIOrdering::SeqCst);
-        }
-    }
-}
-
-fn stored_window_number(popup_type: PopupType) -> i64 {
+/// Registry role of the shared window for `popup_type`.
+pub fn window_role(popup_type: PopupType) -> WindowRole {
     match popup_type {
-        PopupType::Panel => PANEL_WINDOW_NUMBER.load(AtomicIOrdering::SeqCst),
-        PopupType::Popup => POPUP_WINDOW_NUMBER.load(AtomicIOrdering::SeqCst),
+        PopupType::Panel => WindowRole::Panel,
+        PopupType::Popup => WindowRole::Popup,
     }
 }
 
-fn width_matches_popup_type(popup_type: PopupType, width: f64) -> bool {
-    match popup_type {
-        PopupType::Panel => width > 500.0,
-        PopupType::Popup => width > 200.0 && width < 500.0,
-    }
+/// Left edge, bottom edge and width of the bar window, which popups hang
+/// from. The bar spans its screen, so these bound the popup on any display.
+fn bar_anchor(mtm: MainThreadMarker) -> (f64, f64, f64) {
+    window_registry::window(mtm, &WindowRole::Bar)
+        .map(|w| {
+            let frame = w.frame();
+            (frame.origin.x, frame.origin.y, frame.size.width)
+        })
+        .unwrap_or((0.0, 0.0, 1512.0))
 }
 
-#[cfg(test)]
-fn matches_window_for_test(
-    popup_type: PopupType,
-    stored: i64,
-    window_number: i64,
+/// Returns the x of a `width` wide popup anchored to the module spanning
+/// `left..right`, kept on the screen spanning
+/// `screen_x..screen_x + screen_width`.
+pub fn anchored_x(
+    anchor: PopupAnchor,
+    (left, right): (f64, f64),
     width: f64,
-) -> bool {
-    if stored > 0 {
-        return stored == window_number;
-    }
-    width_matches_popup_type(popup_type, width)
+    screen_x: f64,
+    screen_width: f64,
+) -> f64 {
+    let x = match anchor {
+        PopupAnchor::Left => left,
+        PopupAnchor::Center => (left + right - width) / 2.0,
+        PopupAnchor::Right => right - width,
+    };
+    // A popup wider than the screen starts at its left edge
+    x.min(screen_x + screen_width - width).max(screen_x)
 }
 
-fn bar_height() -> f64 {
+pub fn bar_height() -> f64 {
     let lock = SCREEN_BAR_HEIGHT.get_or_init(|| Mutex::new(32.0));
     lock.lock().map(|v| *v).unwrap_or(32.0)
 }
 
+pub fn screen_height() -> f64 {
+    let lock = SCREEN_HEIGHT.get_or_init(|| Mutex::new(900.0));
+    lock.lock().map(|v| *v).unwrap_or(900.0)
+}
+
 pub fn max_panel_height() -> f64 {
     let lock = SCREEN_HEIGHT.get_or_init(|| Mutex::new(900.0));
     let height = lock.lock().map(|v| *v).unwrap_or(900.0);
@@ -168,7 +203,6 @@ pub fn max_popup_height() -> f64 {
     max_panel_height() * 0.8
 }
 
-#[allow(dead_code)]
 pub fn panel_width() -> f64 {
     let lock = SCREEN_WIDTH.get_or_init(|| Mutex::new(1440.0));
     lock.lock().map(|v| *v).unwrap_or(1440.0)
@@ -223,6 +257,10 @@ mod popup_height_tests {
 pub(crate) trait WindowOps: Send + Sync {
     fn show_popup_window(&self, popup_type: PopupType, height: f64) -> bool;
     fn hide_all_popup_windows(&self);
+    /// Frame (x, y, width, height) of the shared window for `popup_type`.
+    fn popup_frame(&self, popup_type: PopupType) -> Option<(f64, f64, f64, f64)>;
+    /// Resizes the hidden shared window for `popup_type` to `height`.
+    fn size_hidden_popup_window(&self, popup_type: PopupType, height: f64);
 }
 
 struct AppKitWindowOps;
@@ -235,6 +273,14 @@ impl WindowOps for AppKitWindowOps {
     fn hide_all_popup_windows(&self) {
         hide_all_popup_windows_appkit();
     }
+
+    fn popup_frame(&self, popup_type: PopupType) -> Option<(f64, f64, f64, f64)> {
+        popup_frame_appkit(popup_type)
+    }
+
+    fn size_hidden_popup_window(&self, popup_type: PopupType, height: f64) {
+        size_hidden_popup_window_appkit(popup_type, height);
+    }
 }
 
 fn window_ops() -> Arc<dyn WindowOps> {
@@ -273,11 +319,16 @@ pub fn record_popup_click(module_id: &str) {
     ));
 }
 
-pub fn record_popup_anchor(x: f64, y: f64) {
+/// Records the screen x edges of what the next popup opens from: the
+/// clicked module, or a point when `left == right`.
+pub fn record_popup_anchor(left: f64, right: f64) {
     if let Ok(mut guard) = LAST_ANCHOR.lock() {
-        *guard = Some((x, y));
+        *guard = Some((left, right));
     }
-    trace_popup(&format!("record_popup_anchor x={:.1} y={:.1}", x, y));
+    trace_popup(&format!(
+        "record_popup_anchor left={:.1} right={:.1}",
+        left, right
+    ));
 }
 
 fn take_popup_anchor() -> Option<(f64, f64)> {
@@ -330,33 +381,20 @@ fn log_popup_window_state_later(popup_type: PopupType, label: &'static str) {
                 trace_popup("window_state: not on main thread");
                 return;
             };
-            let app = NSApplication::sharedApplication(mtm);
-            let windows = app.windows();
-            for i in 0..windows.len() {
-                let ns_window = windows.objectAtIndex(i);
+            if let Some(ns_window) = window_registry::window(mtm, &window_role(popup_type)) {
                 let frame = ns_window.frame();
-
-                let is_panel = frame.size.width > 500.0;
-                let is_popup = frame.size.width > 200.0 && frame.size.width < 500.0;
-                let matches = match popup_type {
-                    PopupType::Panel => is_panel,
-                    PopupType::Popup => is_popup,
-                };
-
-                if matches {
-                    trace_popup(&format!(
-                        "window_state {} type={:?} frame=({:.1},{:.1}) {:.1}x{:.1} visible={} alpha={:.2}",
-                        label,
-                        popup_type,
-                        frame.origin.x,
-                        frame.origin.y,
-                        frame.size.width,
-                        frame.size.height,
-                        ns_window.isVisible(),
-                        ns_window.alphaValue()
-                    ));
-                    return;
-                }
+                trace_popup(&format!(
+                    "window_state {} type={:?} frame=({:.1},{:.1}) {:.1}x{:.1} visible={} alpha={:.2}",
+                    label,
+                    popup_type,
+                    frame.origin.x,
+                    frame.origin.y,
+                    frame.size.width,
+                    frame.size.height,
+                    ns_window.isVisible(),
+                    ns_window.alphaValue()
+                ));
+                return;
             }
             trace_popup(&format!(
                 "window_state {} type={:?} not_found",
@@ -490,6 +528,67 @@ pub fn mark_popup_content_rendered(
     }
 }
 
+/// Frame (x, y, width, height) of the shared window for `popup_type`.
+pub fn popup_window_frame(popup_type: PopupType) -> Option<(f64, f64, f64, f64)> {
+    window_ops().popup_frame(popup_type)
+}
+
+/// Lays out the popup of `module_id` in its hidden window ahead of a click,
+/// so `toggle_popup` only has to reveal it. Called on hover intent.
+///
+/// Returns true if the popup windows need a render for it.
+pub fn prepare_popup(module_id: &str) -> bool {
+    if is_detached(module_id) {
+        return false;
+    }
+    let Some(spec) = get_popup_spec(module_id) else {
+        return false;
+    };
+    if let Ok(mut prepared) = PREPARED_MODULE_ID.write() {
+        if *prepared == module_id {
+            return false;
+        }
+        *prepared = module_id.to_string();
+    }
+    trace_popup(&format!("prepare_popup module_id='{}'", module_id));
+
+    // An open popup of the same type keeps its window; the prepared one
+    // takes over when it closes
+    let open_type = POPUP_VISIBLE
+        .load(Ordering::SeqCst)
+        .then(|| get_popup_spec(&get_current_module_id()))
+        .flatten()
+        .map(|s| s.popup_type);
+    if open_type != Some(spec.popup_type) {
+        window_ops().size_hidden_popup_window(spec.popup_type, spec.height);
+    }
+    true
+}
+
+/// Module whose popup the shared window for `popup_type` shows: the open
+/// popup's, or else the one prepared on hover.
+pub fn hosted_module_id(popup_type: PopupType) -> String {
+    let matches = |id: &str| {
+        !id.is_empty()
+            && get_popup_spec(id)
+                .map(|s| s.popup_type == popup_type)
+                .unwrap_or(false)
+    };
+    let current = get_current_module_id();
+    if matches(&current) {
+        return current;
+    }
+    let prepared = PREPARED_MODULE_ID
+        .read()
+        .map(|id| id.clone())
+        .unwrap_or_default();
+    if matches(&prepared) && !is_detached(&prepared) {
+        prepared
+    } else {
+        String::new()
+    }
+}
+
 /// Check if there's a pending show and execute it.
 /// Call this from PopupHostView after rendering with the correct module_id.
 pub fn execute_pending_show() {
@@ -522,70 +621,10 @@ pub fn reposition_popup_window(popup_type: PopupType, height: f64) {
         return;
     };
 
-    let app = NSApplication::sharedApplication(mtm);
-    let windows = app.windows();
-    let stored_number = stored_window_number(popup_type);
-    let mut fallback_index: Option<usize> = None;
-    let mut match_index: Option<usize> = None;
-    // Find bar window to get screen info
-    let mut bar_y = 0.0;
-    let mut screen_width = 1512.0;
-    for i in 0..windows.len() {
-        let ns_window = windows.objectAtIndex(i);
-        let frame = ns_window.frame();
-        if frame.size.height <= 40.0 && frame.size.height > 20.0 {
-            bar_y = frame.origin.y;
-            screen_width = frame.size.width;
-            break;
-        }
-    }
+    let (screen_x, bar_y, screen_width) = bar_anchor(mtm);
 
-    for i in 0..windows.len() {
-        let ns_window = windows.objectAtIndex(i);
+    if let Some(ns_window) = window_registry::window(mtm, &window_role(popup_type)) {
         let frame = ns_window.frame();
-        let window_number = ns_window.windowNumber() as i64;
-
-        // Skip the bar window (height ~32px)
-        if frame.size.height <= 40.0 && frame.size.height > 20.0 {
-            continue;
-        }
-
-        if stored_number > 0 {
-            if window_number == stored_number {
-                match_index = Some(i);
-                break;
-            }
-            if fallback_index.is_none() && width_matches_popup_type(popup_type, frame.size.width) {
-                fallback_index = Some(i);
-            }
-            continue;
-        }
-
-        if width_matches_popup_type(popup_type, frame.size.width) {
-            match_index = Some(i);
-            break;
-        }
-    }
-
-    let selected_index = if match_index.is_none() && stored_number > 0 {
-        fallback_index
-    } else {
-        match_index
-    };
-
-    if let Some(i) = selected_index {
-        let ns_window = windows.objectAtIndex(i);
-        let frame = ns_window.frame();
-        let window_number = ns_window.windowNumber() as i64;
-        if stored_number == 0 || (match_index.is_none() && fallback_index.is_some()) {
-            set_window_number(popup_type, window_number);
-        } else if match_index.is_none() && fallback_index.is_some() {
-            log::warn!(
-                "Popup window number mismatch for {:?}; using fallback window {}",
-                popup_type,
-                window_number
-            );
-        }
 
         let max_height = match popup_type {
             PopupType::Panel => max_panel_height(),
@@ -598,11 +637,7 @@ pub fn reposition_popup_window(popup_type: PopupType, height: f64) {
 
         if popup_type == PopupType::Popup {
             // Keep popup on screen after height change.
-            if new_x < 0.0 {
-                new_x = 0.0;
-            } else if new_x + new_width > screen_width {
-                new_x = screen_width - new_width;
-            }
+            new_x = new_x.min(screen_x + screen_width - new_width).max(screen_x);
         }
 
         let new_frame = objc2_foundation::NSRect::new(
@@ -634,6 +669,24 @@ pub fn is_popup_visible() -> bool {
     POPUP_VISIBLE.load(Ordering::SeqCst)
 }
 
+/// Returns whether the visible popup is pinned open.
+pub fn is_popup_pinned() -> bool {
+    POPUP_PINNED.load(Ordering::SeqCst)
+}
+
+/// Pins or unpins the visible popup. A pinned popup stays open on clicks
+/// outside it; Esc or clicking its module again still close it. Returns
+/// false if no popup is open.
+pub fn set_popup_pinned(pinned: bool) -> bool {
+    if !POPUP_VISIBLE.load(Ordering::SeqCst) {
+        return false;
+    }
+    POPUP_PINNED.store(pinned, Ordering::SeqCst);
+    log::info!("Popup {}", if pinned { "pinned" } else { "unpinned" });
+    notify_popup_needs_render(&get_current_module_id());
+    true
+}
+
 /// Toggles a popup for the given module ID.
 ///
 /// If a popup is visible with the same module, it closes.
@@ -654,6 +707,12 @@ pub fn toggle_popup(module_id: &str) -> bool {
         return false;
     }
 
+    // Clicking the module of a detached popup closes it
+    if is_detached(module_id) {
+        close_detached(module_id);
+        return false;
+    }
+
     // Notify new module of open early so popup_spec can reflect updated state.
     if let Some(m) = get_module(module_id) {
         if let Ok(mut e) = m.write() {
@@ -670,8 +729,15 @@ pub fn toggle_popup(module_id: &str) -> bool {
         }
     };
 
+    // A pinned popup keeps showing in a window of its own instead of
+    // being replaced.
+    let detached = was_visible
+        && !current_id.is_empty()
+        && POPUP_PINNED.swap(false, Ordering::SeqCst)
+        && detach_popup(&current_id);
+
     // Notify old module of close without holding the ID lock.
-    if !current_id.is_empty() && current_id != module_id {
+    if !detached && !current_id.is_empty() && current_id != module_id {
         if let Some(m) = get_module(&current_id) {
             if let Ok(mut e) = m.write() {
                 e.on_popup_event(PopupEvent::Closed);
@@ -682,6 +748,7 @@ pub fn toggle_popup(module_id: &str) -> bool {
         *id = module_id.to_string();
     }
     POPUP_VISIBLE.store(true, Ordering::SeqCst);
+    POPUP_WANTS_KEYBOARD.store(spec.keyboard, Ordering::SeqCst);
     module_change_bus().notify(module_id);
     start_popup_open_trace(module_id, spec.popup_type);
 
@@ -729,11 +796,108 @@ pub fn toggle_popup(module_id: &str) -> bool {
     true
 }
 
+/// Queues the pinned popup of `module_id` for a window of its own.
+fn detach_popup(module_id: &str) -> bool {
+    let Some(spec) = get_popup_spec(module_id) else {
+        return false;
+    };
+    let Some(frame) = window_ops().popup_frame(spec.popup_type) else {
+        return false;
+    };
+    if let Ok(mut detached) = DETACHED.lock() {
+        detached.push(module_id.to_string());
+    }
+    if let Ok(mut queue) = DETACH_QUEUE.lock() {
+        queue.push(DetachRequest {
+            module_id: module_id.to_string(),
+            popup_type: spec.popup_type,
+            frame,
+        });
+    }
+    log::info!("Detaching pinned popup '{}'", module_id);
+    // The bar creates the window on its next render
+    crate::gpui_app::request_immediate_refresh();
+    true
+}
+
+/// Takes the popups waiting for a detached window.
+pub fn take_detach_requests() -> Vec<DetachRequest> {
+    DETACH_QUEUE
+        .lock()
+        .map(|mut queue| std::mem::take(&mut *queue))
+        .unwrap_or_default()
+}
+
+/// Module ids of the detached popups, oldest first.
+pub fn detached_ids() -> Vec<String> {
+    DETACHED.lock().map(|d| d.clone()).unwrap_or_default()
+}
+
+/// Detaches the popups of `module_ids` again, skipping modules that no
+/// longer have a popup.
+pub fn restore_detached(module_ids: &[String]) {
+    for module_id in module_ids {
+        if !is_detached(module_id) && get_module(module_id).is_some() {
+            detach_popup(module_id);
+        }
+    }
+}
+
+/// Returns whether `module_id` is showing in a detached popup window.
+pub fn is_detached(module_id: &str) -> bool {
+    DETACHED
+        .lock()
+        .map(|d| d.iter().any(|id| id == module_id))
+        .unwrap_or(false)
+}
+
+/// Closes the detached popup window of `module_id`.
+pub fn close_detached(module_id: &str) {
+    let removed = DETACHED.lock().is_ok_and(|mut d| {
+        let before = d.len();
+        d.retain(|id| id != module_id);
+        d.len() != before
+    });
+    if !removed {
+        return;
+    }
+    // A request that never got its window is dropped with it
+    if let Ok(mut queue) = DETACH_QUEUE.lock() {
+        queue.retain(|request| request.module_id != module_id);
+    }
+    if let Some(m) = get_module(module_id) {
+        if let Ok(mut e) = m.write() {
+            e.on_popup_event(PopupEvent::Closed);
+        }
+    }
+    log::info!("Closing detached popup '{}'", module_id);
+    let role = WindowRole::Detached(module_id.to_string());
+    let window = MainThreadMarker::new().and_then(|mtm| window_registry::window(mtm, &role));
+    window_registry::untag(&role);
+    if let Some(ns_window) = window {
+        // Close on the next run-loop turn, outside GPUI event dispatch
+        let block = RcBlock::new(move || ns_window.close());
+        unsafe {
+            NSRunLoop::mainRunLoop().performBlock(&block);
+        }
+    }
+}
+
 /// Hides all popups.
 pub fn hide_popup() {
     let current_id = get_current_module_id();
 
     if POPUP_VISIBLE.swap(false, Ordering::SeqCst) {
+        POPUP_PINNED.store(false, Ordering::SeqCst);
+
+        // Keyboard popups activated the app; hand focus back to the
+        // previously active app.
+        if POPUP_WANTS_KEYBOARD.swap(false, Ordering::SeqCst) {
+            if let Some(mtm) = MainThreadMarker::new() {
+                NSApplication::sharedApplication(mtm).deactivate();
+            }
+        }
+
         // Notify module of close
         if !current_id.is_empty() {
             if let Some(m) = get_module(&current_id) {
@@ -763,6 +927,21 @@ pub fn hide_popup() {
     }
 }
 
+/// Asks for the popup to close after the current module callback returns.
+///
+/// Popup callbacks run with the module locked, and `hide_popup` notifies the
+/// module of the close, so modules request the hide instead of calling it.
+pub fn request_hide_popup() {
+    POPUP_HIDE_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Hides the popup if a module requested it.
+pub fn apply_hide_request() {
+    if POPUP_HIDE_REQUESTED.swap(false, Ordering::SeqCst) {
+        hide_popup();
+    }
+}
+
 /// Warm up popup rendering to avoid first-open latency.
 pub fn warmup_popups() {
     let popup_height = get_popup_spec("calendar")
@@ -836,80 +1015,22 @@ fn show_popup_window_appkit(popup_type: PopupType, height: f64) -> bool {
         return false;
     };
 
-    let app = NSApplication::sharedApplication(mtm);
-    let windows = app.windows();
-    let stored_number = stored_window_number(popup_type);
-    let mut fallback_index: Option<usize> = None;
-    let mut match_index: Option<usize> = None;
-
-    // Find bar window to get screen info
-    let mut bar_y = 0.0;
-    let mut screen_width = 1512.0;
-    for i in 0..windows.len() {
-        let ns_window = windows.objectAtIndex(i);
-        let frame = ns_window.frame();
-        if frame.size.height <= 40.0 && frame.size.height > 20.0 {
-            bar_y = frame.origin.y;
-            screen_width = frame.size.width;
-            trace_popup(&format!(
-                "bar_window frame=({:.1},{:.1}) {:.1}x{:.1}",
-                frame.origin.x, frame.origin.y, frame.size.width, frame.size.height
-            ));
-            break;
-        }
-    }
-
-    for i in 0..windows.len() {
-        let ns_window = windows.objectAtIndex(i);
-        let frame = ns_window.frame();
-        let window_number = ns_window.windowNumber() as i64;
-
-        // Skip the bar window (height ~32px)
-        if frame.size.height <= 40.0 && frame.size.height > 20.0 {
-            continue;
-        }
-
-        if stored_number > 0 {
-            if window_number == stored_number {
-                match_index = Some(i);
-                break;
-            }
-            if fallback_index.is_none() && width_matches_popup_type(popup_type, frame.size.width) {
-                fallback_index = Some(i);
-            }
-            continue;
-        }
-
-        if width_matches_popup_type(popup_type, frame.size.width) {
-            match_index = Some(i);
-            break;
-        }
-    }
-
-    let selected_index = if match_index.is_none() && stored_number > 0 {
-        fallback_index
-    } else {
-        match_index
-    };
+    // The bar window gives the anchor y and the screen's extent
+    let (screen_x, bar_y, screen_width) = bar_anchor(mtm);
+    trace_popup(&format!(
+        "bar_anchor x={:.1} y={:.1} width={:.1}",
+        screen_x, bar_y, screen_width
+    ));
 
-    if let Some(i) = selected_index {
-        let ns_window = windows.objectAtIndex(i);
+    if let Some(ns_window) = window_registry::window(mtm, &window_role(popup_type)) {
         let frame = ns_window.frame();
-        let window_number = ns_window.windowNumber() as i64;
-        if stored_number > 0 && match_index.is_none() && fallback_index.is_some() {
-            log::warn!(
-                "Popup window number mismatch for {:?}; updating stored number to {}",
-                popup_type,
-                window_number
-            );
-        }
-        if stored_number == 0 || (match_index.is_none() && fallback_index.is_some()) {
-            set_window_number(popup_type, window_number);
-        }
-
         trace_popup(&format!(
-            "show_popup_window_appkit match idx={} frame=({:.1},{:.1}) {:.1}x{:.1}",
-            i, frame.origin.x, frame.origin.y, frame.size.width, frame.size.height
+            "show_popup_window_appkit match number={} frame=({:.1},{:.1}) {:.1}x{:.1}",
+            ns_window.windowNumber(),
+            frame.origin.x,
+            frame.origin.y,
+            frame.size.width,
+            frame.size.height
         ));
         // Position the window and apply requested height.
         let new_width = frame.size.width;
@@ -922,35 +1043,27 @@ fn show_popup_window_appkit(popup_type: PopupType, height: f64) -> bool {
         let new_y = bar_y - desired_height;
 
         let new_frame = if popup_type == PopupType::Popup {
-            // Get mouse position as trigger location
-            let (trigger_x, trigger_y, source) = if let Some((x, y)) = take_popup_anchor() {
-                (x, y, "anchor")
+            // Anchor to the module that opened the popup, or to the pointer
+            let (span, source) = if let Some(span) = take_popup_anchor() {
+                (span, "anchor")
             } else {
                 let mouse_pos = NSEvent::mouseLocation();
-                (mouse_pos.x, mouse_pos.y, "mouse")
+                ((mouse_pos.x, mouse_pos.x), "mouse")
             };
-
-            // Center popup on trigger, with screen edge detection
-            let mut popup_x = trigger_x - (new_width / 2.0);
-
-            let mut clamped = false;
-            // Keep popup on screen
-            if popup_x < 0.0 {
-                popup_x = 0.0;
-                clamped = true;
-            } else if popup_x + new_width > screen_width {
-                popup_x = screen_width - new_width;
-                clamped = true;
-            }
+            let anchor = get_popup_spec(&get_current_module_id())
+                .map(|spec| spec.anchor)
+                .unwrap_or_default();
+            let popup_x = anchored_x(anchor, span, new_width, screen_x, screen_width);
 
             trace_popup(&format!(
-                "show_popup_window_appkit trigger_source={} trigger=({:.1},{:.1}) popup_x={:.1} screen_width={:.1} clamped={}",
+                "show_popup_window_appkit trigger_source={} span=({:.1},{:.1}) anchor={:?} popup_x={:.1} screen=({:.1},{:.1})",
                 source,
-                trigger_x,
-                trigger_y,
+                span.0,
+                span.1,
+                anchor,
                 popup_x,
-                screen_width,
-                clamped
+                screen_x,
+                screen_width
             ));
 
             log::info!("Repositioned popup to ({}, {})", popup_x, new_y);
@@ -991,7 +1104,8 @@ fn show_popup_window_appkit(popup_type: PopupType, height: f64) -> bool {
             // Show window just above bar level (-20) but below normal windows (0).
             // This keeps popups visible over the bar without floating above other apps.
             unsafe {
-                let _: () = objc2::msg_send![&ns_window, setLevel: -19_i64];
+                let level = crate::gpui_app::screenshot::window_level(-19);
+                let _: () = objc2::msg_send![&ns_window, setLevel: level];
             }
             ns_window.setAlphaValue(1.0);
             ns_window.setOpaque(true);
@@ -1005,8 +1119,16 @@ fn show_popup_window_appkit(popup_type: PopupType, height: f64) -> bool {
             // Don't override it here — that would ignore the user's config.
 
             ns_window.setAcceptsMouseMovedEvents(true);
-            // Order front without activating the window.
+            // Order front without activating the window, unless the popup
+            // takes keyboard input and needs to become key.
             ns_window.orderFrontRegardless();
+            if POPUP_WANTS_KEYBOARD.load(Ordering::SeqCst) {
+                if let Some(mtm) = MainThreadMarker::new() {
+                    let app = NSApplication::sharedApplication(mtm);
+                    let _: () = unsafe { objc2::msg_send![&app, activateIgnoringOtherApps: true] };
+                }
+                ns_window.makeKeyWindow();
+            }
             trace_popup(&format!(
                 "show_popup_window_appkit visible={} alpha={:.2} key={} ignores_mouse={}",
                 ns_window.isVisible(),
@@ -1056,6 +1178,53 @@ fn show_popup_window_appkit(popup_type: PopupType, height: f64) -> bool {
     false
 }
 
+/// Returns the frame of the shared window for `popup_type`.
+fn popup_frame_appkit(popup_type: PopupType) -> Option<(f64, f64, f64, f64)> {
+    let mtm = MainThreadMarker::new()?;
+    window_registry::window(mtm, &window_role(popup_type)).map(|w| {
+        let frame = w.frame();
+        (
+            frame.origin.x,
+            frame.origin.y,
+            frame.size.width,
+            frame.size.height,
+        )
+    })
+}
+
+/// Gives a hidden popup window the height its prepared content opens at,
+/// keeping the top edge in place.
+fn size_hidden_popup_window_appkit(popup_type: PopupType, height: f64) {
+    let Some(mtm) = MainThreadMarker::new() else {
+        return;
+    };
+    let Some(ns_window) = window_registry::window(mtm, &window_role(popup_type)) else {
+        return;
+    };
+    let max_height = match popup_type {
+        PopupType::Panel => max_panel_height(),
+        PopupType::Popup => max_popup_height(),
+    };
+    let height = height.min(max_height);
+    let frame = ns_window.frame();
+    if height <= 0.0 || (frame.size.height - height).abs() < 1.0 {
+        return;
+    }
+    let new_frame = objc2_foundation::NSRect::new(
+        objc2_foundation::NSPoint::new(frame.origin.x, frame.origin.y + frame.size.height - height),
+        objc2_foundation::NSSize::new(frame.size.width, height),
+    );
+
+    // Deferred like showing: resizing during GPUI event dispatch re-enters
+    // its window callbacks
+    let block = RcBlock::new(move || {
+        ns_window.setFrame_display(new_frame, true);
+    });
+    unsafe {
+        NSRunLoop::mainRunLoop().performBlock(&block);
+    }
+}
+
 /// Hides all popup windows.
 fn hide_all_popup_windows() {
     window_ops().hide_all_popup_windows();
@@ -1066,44 +1235,28 @@ fn hide_all_popup_windows_appkit() {
         return;
     };
 
-    let app = NSApplication::sharedApplication(mtm);
-    let windows = app.windows();
-
+    // Only the shared windows; detached popups stay open until closed on
+    // their own
     let mut hidden_count = 0;
-    for i in 0..windows.len() {
-        let ns_window = windows.objectAtIndex(i);
-        let frame = ns_window.frame();
-
-        // Skip the bar window (height ~32px, full screen width)
-        let is_bar = frame.size.height <= 40.0 && frame.size.height > 20.0;
-        if is_bar {
+    for role in [WindowRole::Panel, WindowRole::Popup] {
+        let Some(ns_window) = window_registry::window(mtm, &role) else {
             continue;
+        };
+        unsafe {
+            let _: () = objc2::msg_send![&ns_window, setLevel: -20_i64];
         }
-
-        // Hide both panel windows (full width) and popup windows (narrow width)
-        // This covers any non-bar window that could be a popup or panel
-        let is_panel = frame.size.width > 500.0;
-        let is_popup = frame.size.width > 200.0 && frame.size.width < 500.0;
-
-        if is_panel || is_popup {
-            unsafe {
-                let _: () = objc2::msg_send![&ns_window, setLevel: -20_i64];
-            }
-            // Keep hidden windows non-visible and non-interactive.
-            // We use close+show=false on creation, so alpha-only hiding is enough.
-            ns_window.setAlphaValue(0.0);
-            ns_window.setIgnoresMouseEvents(true);
-            use objc2_app_kit::NSWindowAnimationBehavior;
-            ns_window.setAnimationBehavior(NSWindowAnimationBehavior::None);
-            hidden_count += 1;
-            log::debug!(
-                "hide_all_popup_windows: hiding {} window {} ({}x{})",
-                if is_panel { "panel" } else { "popup" },
-                i,
-                frame.size.width,
-                frame.size.height
-            );
-        }
+        // Keep hidden windows non-visible and non-interactive.
+        // We use close+show=false on creation, so alpha-only hiding is enough.
+        ns_window.setAlphaValue(0.0);
+        ns_window.setIgnoresMouseEvents(true);
+        use objc2_app_kit::NSWindowAnimationBehavior;
+        ns_window.setAnimationBehavior(NSWindowAnimationBehavior::None);
+        hidden_count += 1;
+        log::debug!(
+            "hide_all_popup_windows: hiding {:?} window {}",
+            role,
+            ns_window.windowNumber()
+        );
     }
     log::debug!("hide_all_popup_windows: hid {} windows", hidden_count);
 }
@@ -1150,6 +1303,7 @@ mod tests {
         show_calls: AtomicUsize,
         hide_calls: AtomicUsize,
         show_args: Mutex<Vec<(PopupType, f64)>>,
+        size_args: Mutex<Vec<(PopupType, f64)>>,
     }
 
     impl TestWindowOps {
@@ -1159,6 +1313,7 @@ mod tests {
                 show_calls: AtomicUsize::new(0),
                 hide_calls: AtomicUsize::new(0),
                 show_args: Mutex::new(Vec::new()),
+                size_args: Mutex::new(Vec::new()),
             }
         }
     }
@@ -1177,7 +1332,13 @@ mod tests {
             self.hide_calls.fetch_add(1, Ordering::SeqCst);
         }
 
-        // no-op
+        fn popup_frame(&self, _popup_type: PopupType) -> Option<(f64, f64, f64, f64)> {
+            Some((0.0, 500.0, 900.0, 200.0))
+        }
+
+        fn size_hidden_popup_window(&self, popup_type: PopupType, height: f64) {
+            self.size_args.lock().unwrap().push((popup_type, height));
+        }
     }
 
     fn reset_popup_state() {
@@ -1185,9 +1346,17 @@ mod tests {
         if let Ok(mut id) = CURRENT_MODULE_ID.write() {
             id.clear();
         }
+        if let Ok(mut id) = PREPARED_MODULE_ID.write() {
+            id.clear();
+        }
         if let Ok(mut pending) = PENDING_SHOW.lock() {
             *pending = None;
         }
+        POPUP_PINNED.store(false, Ordering::SeqCst);
+        if let Ok(mut detached) = DETACHED.lock() {
+            detached.clear();
+        }
+        let _ = take_detach_requests();
         reset_module_change_bus_for_test();
     }
 
@@ -1314,23 +1483,137 @@ mod tests {
     }
 
     #[test]
-    fn window_match_prefers_registered_number() {
-        let stored = 42;
-        assert!(matches_window_for_test(PopupType::Panel, stored, 42, 100.0));
-        assert!(!matches_window_for_test(
-            PopupType::Panel,
-            stored,
-            43,
-            900.0
-        ));
+    fn pinned_popup_detaches_when_another_opens() {
+        with_test_lock(|| {
+            reset_popup_state();
+            install_two_panel_registry();
+            let ops = Arc::new(TestWindowOps::new(vec![true, true]));
+            set_window_ops_for_test(ops);
+
+            let _ = toggle_popup("first");
+            assert!(set_popup_pinned(true));
+            assert!(toggle_popup("second"));
+
+            assert_eq!(get_current_module_id(), "second");
+            assert!(!is_popup_pinned());
+            assert!(is_detached("first"));
+            assert_eq!(
+                take_detach_requests(),
+                vec![DetachRequest {
+                    module_id: "first".to_string(),
+                    popup_type: PopupType::Panel,
+                    frame: (0.0, 500.0, 900.0, 200.0),
+                }]
+            );
+
+            // Clicking the detached popup's module closes it
+            assert!(!toggle_popup("first"));
+            assert!(!is_detached("first"));
+            assert_eq!(get_current_module_id(), "second");
+        });
+    }
+
+    #[test]
+    fn unpinned_popup_is_replaced() {
+        with_test_lock(|| {
+            reset_popup_state();
+            install_two_panel_registry();
+            let ops = Arc::new(TestWindowOps::new(vec![true, true]));
+            set_window_ops_for_test(ops);
+
+            let _ = toggle_popup("first");
+            let _ = toggle_popup("second");
+            assert!(!is_detached("first"));
+            assert!(take_detach_requests().is_empty());
+        });
+    }
+
+    #[test]
+    fn anchored_x_aligns_popup_with_module() {
+        let span = (500.0, 540.0);
+        assert_eq!(
+            anchored_x(PopupAnchor::Left, span, 200.0, 0.0, 1440.0),
+            500.0
+        );
+        assert_eq!(
+            anchored_x(PopupAnchor::Center, span, 200.0, 0.0, 1440.0),
+            420.0
+        );
+        assert_eq!(
+            anchored_x(PopupAnchor::Right, span, 200.0, 0.0, 1440.0),
+            340.0
+        );
+    }
+
+    #[test]
+    fn anchored_x_stays_on_the_module_screen() {
+        // A display right of a 1440pt wide one
+        let (screen_x, screen_width) = (1440.0, 1920.0);
+        assert_eq!(
+            anchored_x(
+                PopupAnchor::Center,
+                (1450.0, 1470.0),
+                300.0,
+                screen_x,
+                screen_width
+            ),
+            1440.0
+        );
+        assert_eq!(
+            anchored_x(
+                PopupAnchor::Left,
+                (3300.0, 3350.0),
+                300.0,
+                screen_x,
+                screen_width
+            ),
+            3060.0
+        );
+        // Wider than the screen: starts at its left edge
+        assert_eq!(
+            anchored_x(PopupAnchor::Right, (100.0, 120.0), 500.0, 0.0, 400.0),
+            0.0
+        );
+    }
+
+    #[test]
+    fn prepared_popup_is_sized_once_and_hosted_while_closed() {
+        with_test_lock(|| {
+            reset_popup_state();
+            install_dummy_registry();
+            let ops = Arc::new(TestWindowOps::new(vec![]));
+            set_window_ops_for_test(ops.clone());
+
+            assert!(prepare_popup("dummy"));
+            assert!(!prepare_popup("dummy"));
+            assert!(!prepare_popup("missing"));
+
+            assert_eq!(
+                *ops.size_args.lock().unwrap(),
+                vec![(PopupType::Panel, 123.0)]
+            );
+            assert_eq!(hosted_module_id(PopupType::Panel), "dummy");
+            assert_eq!(hosted_module_id(PopupType::Popup), "");
+        });
     }
 
     #[test]
-    fn window_match_falls_back_to_width_when_unregistered() {
-        let stored = 0;
-        assert!(matches_window_for_test(PopupType::Panel, stored, 1, 900.0));
-        assert!(!matches_window_for_test(PopupType::Popup, stored, 1, 900.0));
-        assert!(matches_window_for_test(PopupType::Popup, stored, 1, 320.0));
+    fn open_popup_is_hosted_over_prepared_one() {
+        with_test_lock(|| {
+            reset_popup_state();
+            install_two_panel_registry();
+            let ops = Arc::new(TestWindowOps::new(vec![true]));
+            set_window_ops_for_test(ops.clone());
+
+            let _ = toggle_popup("first");
+            assert!(prepare_popup("second"));
+            // The open panel keeps its window size
+            assert!(ops.size_args.lock().unwrap().is_empty());
+            assert_eq!(hosted_module_id(PopupType::Panel), "first");
+
+            hide_popup();
+            assert_eq!(hosted_module_id(PopupType::Panel), "second");
+        });
     }
 }
 
@@ -1421,6 +1704,10 @@ fn handle_global_key(event: &NSEvent) {
 
 /// Handles a global click event.
 fn handle_global_click(event: &NSEvent) {
+    if POPUP_PINNED.load(Ordering::SeqCst) {
+        return;
+    }
+
     let location = event.locationInWindow();
     let screen_x = location.x;
     let screen_y = location.y;
@@ -1434,39 +1721,29 @@ fn handle_global_click(event: &NSEvent) {
     let app = NSApplication::sharedApplication(mtm);
     let windows = app.windows();
 
-    // Check if click is inside any popup window
     for i in 0..windows.len() {
         let ns_window = windows.objectAtIndex(i);
+        let Some(role) = window_registry::role(ns_window.windowNumber() as i64) else {
+            continue;
+        };
         let frame = ns_window.frame();
-
-        // Only check popup windows (height > 100 and visible)
-        if frame.size.height > 100.0
-            && ns_window.alphaValue() > 0.5
-            && screen_x >= frame.origin.x
+        let inside = screen_x >= frame.origin.x
             && screen_x <= frame.origin.x + frame.size.width
             && screen_y >= frame.origin.y
-            && screen_y <= frame.origin.y + frame.size.height
-        {
-            log::debug!("Click inside popup, ignoring");
-            return;
+            && screen_y <= frame.origin.y + frame.size.height;
+        if !inside {
+            continue;
         }
-    }
 
-    // Check if click is on the bar (don't close for bar clicks)
-    for i in 0..windows.len() {
-        let ns_window = windows.objectAtIndex(i);
-        let frame = ns_window.frame();
-
-        if frame.size.height <= 40.0
-            && frame.size.height > 20.0
-            && screen_x >= frame.origin.x
-            && screen_x <= frame.origin.x + frame.size.width
-            && screen_y >= frame.origin.y
-            && screen_y <= frame.origin.y + frame.size.height
-        {
+        // Clicks on the bar are left to its handlers
+        if role == WindowRole::Bar {
             log::debug!("Click on bar, letting handler deal with it");
             return;
         }
+        if role.is_popup() && ns_window.alphaValue() > 0.5 {
+            log::debug!("Click inside popup, ignoring");
+            return;
+        }
     }
 
     // Click is outside, hide popup
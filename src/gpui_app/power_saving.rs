@@ -0,0 +1,143 @@
+//! Power saving: slower updates and no animations on a low battery.
+//!
+//! While the Mac runs on battery below `bar.power_saving.battery_below`
+//! percent, or Low Power Mode is on, the bar stretches timed module updates
+//! by `slowdown` and stops animations such as scrolling text. Modules with
+//! `power_saving = false` keep their full speed.
+//!
+//! The battery is read through the shared metrics sampler; Low Power Mode
+//! is checked whenever the bar renders.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use objc2_foundation::NSProcessInfo;
+
+use crate::config::PowerSavingConfig;
+use crate::system::metrics::{self, BatteryStatus, Metric, Subscription};
+
+/// How often the battery level is sampled.
+const BATTERY_INTERVAL: Duration = Duration::from_secs(60);
+
+static SAVER: Mutex<Option<Saver>> = Mutex::new(None);
+/// On battery below the threshold, as of the last sample
+static LOW_BATTERY: AtomicBool = AtomicBool::new(false);
+
+struct Saver {
+    config: PowerSavingConfig,
+    /// Keeps the battery sampled
+    _battery: Option<Subscription>,
+}
+
+/// How the bar saves power right now.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Policy {
+    slowdown: f64,
+    pub pause_animations: bool,
+}
+
+impl Policy {
+    /// Stretches a module's delay until its next update.
+    pub fn stretch(&self, delay: Duration) -> Duration {
+        delay.mul_f64(self.slowdown.max(1.0))
+    }
+}
+
+/// True when `battery` runs on battery below `threshold` percent.
+fn is_low(battery: &BatteryStatus, threshold: f64) -> bool {
+    !battery.plugged && f64::from(battery.level) < threshold
+}
+
+/// Whether macOS Low Power Mode is on.
+fn low_power_mode() -> bool {
+    unsafe { objc2::msg_send![&NSProcessInfo::processInfo(), isLowPowerModeEnabled] }
+}
+
+/// Applies `[bar.power_saving]`.
+pub fn sync(config: &PowerSavingConfig) {
+    let Ok(mut saver) = SAVER.lock() else {
+        return;
+    };
+    if saver.as_ref().is_some_and(|s| s.config == *config) {
+        return;
+    }
+    // Drop the old subscription before its flag is reset
+    *saver = None;
+    LOW_BATTERY.store(false, Ordering::Relaxed);
+
+    let threshold = config.battery_below;
+    let battery = (threshold > 0.0).then(|| {
+        metrics::subscribe(Metric::Battery, BATTERY_INTERVAL, move |snapshot| {
+            let low = snapshot
+                .battery
+                .as_ref()
+                .is_some_and(|battery| is_low(battery, threshold));
+            if LOW_BATTERY.swap(low, Ordering::Relaxed) != low {
+                log::info!(
+                    "power saving: battery {}",
+                    if low { "low, slowing down" } else { "ok" }
+                );
+                crate::gpui_app::request_immediate_refresh();
+            }
+        })
+    });
+    *saver = Some(Saver {
+        config: config.clone(),
+        _battery: battery,
+    });
+}
+
+/// The current policy, None while power isn't being saved.
+pub fn current() -> Option<Policy> {
+    let saver = SAVER.lock().ok()?;
+    let config = &saver.as_ref()?.config;
+    let saving = LOW_BATTERY.load(Ordering::Relaxed) || (config.low_power_mode && low_power_mode());
+    saving.then_some(Policy {
+        slowdown: config.slowdown,
+        pause_animations: config.pause_animations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn battery(level: u8, plugged: bool) -> BatteryStatus {
+        BatteryStatus {
+            level,
+            charging: false,
+            plugged,
+            minutes_left: None,
+            capacity: None,
+        }
+    }
+
+    #[test]
+    fn low_only_on_battery_below_threshold() {
+        assert!(is_low(&battery(15, false), 20.0));
+        assert!(!is_low(&battery(20, false), 20.0));
+        assert!(!is_low(&battery(15, true), 20.0));
+        assert!(!is_low(&battery(0, false), 0.0));
+    }
+
+    #[test]
+    fn stretches_delays() {
+        let policy = Policy {
+            slowdown: 3.0,
+            pause_animations: true,
+        };
+        assert_eq!(
+            policy.stretch(Duration::from_secs(2)),
+            Duration::from_secs(6)
+        );
+        let policy = Policy {
+            slowdown: 0.5,
+            pause_animations: true,
+        };
+        assert_eq!(
+            policy.stretch(Duration::from_secs(2)),
+            Duration::from_secs(2)
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! Button primitive with action binding.
+//!
+//! Buttons dispatch a [`PopupAction`] to a module or run a callback when
+//! clicked, so popups don't need hand-rolled clickable divs.
+
+use gpui::{div, prelude::*, px, MouseButton, Pixels, SharedString, Stateful, Styled};
+
+use super::focus::focus_ring;
+use crate::gpui_app::modules::{dispatch_popup_action, PopupAction};
+use crate::gpui_app::popup_manager::notify_popup_needs_render;
+use crate::gpui_app::theme::{ButtonVariant, Theme};
+
+/// What a button does when clicked.
+enum ButtonBinding {
+    /// Dispatch a popup action to a module, then re-render its popup
+    Popup {
+        module_id: String,
+        action: PopupAction,
+    },
+    /// Run an arbitrary callback
+    Callback(Box<dyn Fn() + 'static>),
+}
+
+/// Clickable button with a label and/or icon.
+pub struct Button {
+    id: SharedString,
+    label: Option<SharedString>,
+    icon: Option<SharedString>,
+    variant: ButtonVariant,
+    disabled: bool,
+    focused: bool,
+    text_size: Option<Pixels>,
+    square: Option<Pixels>,
+    binding: Option<ButtonBinding>,
+}
+
+impl Button {
+    /// Creates a new button. The id must be unique within the popup.
+    pub fn new(id: impl Into<SharedString>) -> Self {
+        Self {
+            id: id.into(),
+            label: None,
+            icon: None,
+            variant: ButtonVariant::default(),
+            disabled: false,
+            focused: false,
+            text_size: None,
+            square: None,
+            binding: None,
+        }
+    }
+
+    /// Sets the button label.
+    pub fn label(mut self, label: impl Into<SharedString>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Sets the icon glyph shown before the label.
+    pub fn icon(mut self, glyph: impl Into<SharedString>) -> Self {
+        self.icon = Some(glyph.into());
+        self
+    }
+
+    /// Sets the visual variant.
+    pub fn variant(mut self, variant: ButtonVariant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// Sets whether the button is disabled (dimmed, ignores clicks).
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+
+    /// Draws the focus ring (see [`FocusManager`](super::FocusManager)).
+    pub fn focused(mut self, focused: bool) -> Self {
+        self.focused = focused;
+        self
+    }
+
+    /// Sets the label font size.
+    pub fn text_size(mut self, size: impl Into<Pixels>) -> Self {
+        self.text_size = Some(size.into());
+        self
+    }
+
+    /// Renders as a fixed-size square (icon buttons).
+    pub fn square(mut self, size: impl Into<Pixels>) -> Self {
+        self.square = Some(size.into());
+        self
+    }
+
+    /// Dispatches a popup action to `module_id` when clicked.
+    pub fn on_popup_action(mut self, module_id: impl Into<String>, action: PopupAction) -> Self {
+        self.binding = Some(ButtonBinding::Popup {
+            module_id: module_id.into(),
+            action,
+        });
+        self
+    }
+
+    /// Runs a callback when clicked.
+    pub fn on_click(mut self, handler: impl Fn() + 'static) -> Self {
+        self.binding = Some(ButtonBinding::Callback(Box::new(handler)));
+        self
+    }
+
+    /// Renders the button with the given theme.
+    pub fn render(self, theme: &Theme) -> Stateful<gpui::Div> {
+        let (bg, border, fg, hover_bg) = self.variant.colors(theme);
+        let text_size = self.text_size.unwrap_or(px(theme.font_size));
+
+        let mut el = div()
+            .id(self.id)
+            .flex()
+            .flex_row()
+            .items_center()
+            .justify_center()
+            .gap(px(4.0))
+            .rounded(px(4.0))
+            .bg(bg)
+            .border_1()
+            .border_color(border)
+            .text_color(fg)
+            .text_size(text_size);
+        el = focus_ring(el, theme, self.focused);
+
+        el = match self.square {
+            Some(size) => el.w(size).h(size),
+            None => el.px(px(8.0)).py(px(4.0)),
+        };
+
+        if let Some(icon) = self.icon {
+            el = el.child(icon);
+        }
+        if let Some(label) = self.label {
+            el = el.child(label);
+        }
+
+        if self.disabled {
+            return el.opacity(0.5);
+        }
+
+        el = el.cursor_pointer().hover(move |s| s.bg(hover_bg));
+        match self.binding {
+            Some(ButtonBinding::Popup { module_id, action }) => {
+                el.on_mouse_down(MouseButton::Left, move |_event, _window, _cx| {
+                    dispatch_popup_action(&module_id, action.clone());
+                    notify_popup_needs_render(&module_id);
+                })
+            }
+            Some(ButtonBinding::Callback(handler)) => {
+                el.on_mouse_down(MouseButton::Left, move |_event, _window, _cx| {
+                    handler();
+                })
+            }
+            None => el,
+        }
+    }
+}
@@ -0,0 +1,293 @@
+//! Line and bar chart primitives for popup dashboards.
+//!
+//! Charts take a plain slice of samples (oldest first). Long series are
+//! downsampled to roughly one point per two pixels before rendering so an
+//! hour of per-second samples stays cheap to draw.
+
+use gpui::{
+    canvas, div, point, prelude::*, px, Bounds, Div, PathBuilder, Pixels, Rgba, SharedString,
+    Styled,
+};
+
+use crate::gpui_app::theme::Theme;
+
+/// Width reserved for the y-axis labels.
+const AXIS_WIDTH: f32 = 32.0;
+
+/// Averages `values` into at most `max_points` evenly sized buckets.
+pub fn downsample(values: &[f32], max_points: usize) -> Vec<f32> {
+    if max_points == 0 || values.len() <= max_points {
+        return values.to_vec();
+    }
+    let bucket = values.len() as f32 / max_points as f32;
+    (0..max_points)
+        .map(|i| {
+            let start = (i as f32 * bucket) as usize;
+            let end = (((i + 1) as f32 * bucket) as usize).clamp(start + 1, values.len());
+            let slice = &values[start..end];
+            slice.iter().sum::<f32>() / slice.len() as f32
+        })
+        .collect()
+}
+
+/// Returns the (min, max) range to plot, honoring fixed bounds when set.
+///
+/// A flat series gets a unit range so it renders as a line instead of
+/// dividing by zero.
+pub fn value_range(values: &[f32], min: Option<f32>, max: Option<f32>) -> (f32, f32) {
+    let lo = min.unwrap_or_else(|| values.iter().copied().fold(f32::INFINITY, f32::min));
+    let hi = max.unwrap_or_else(|| values.iter().copied().fold(f32::NEG_INFINITY, f32::max));
+    if !lo.is_finite() || !hi.is_finite() {
+        return (0.0, 1.0);
+    }
+    if hi - lo < f32::EPSILON {
+        return (lo, lo + 1.0);
+    }
+    (lo, hi)
+}
+
+/// How a chart draws its series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartKind {
+    /// Stroked line through every sample
+    Line,
+    /// One bar per sample, growing up from the baseline
+    Bar,
+}
+
+/// Line or bar chart with y-axis labels.
+pub struct Chart {
+    kind: ChartKind,
+    values: Vec<f32>,
+    width: f32,
+    height: f32,
+    min: Option<f32>,
+    max: Option<f32>,
+    color: Option<Rgba>,
+    x_labels: Option<(SharedString, SharedString)>,
+    unit: SharedString,
+}
+
+impl Chart {
+    /// Creates a chart of the given kind from samples, oldest first.
+    pub fn new(kind: ChartKind, values: impl Into<Vec<f32>>) -> Self {
+        Self {
+            kind,
+            values: values.into(),
+            width: 240.0,
+            height: 60.0,
+            min: None,
+            max: None,
+            color: None,
+            x_labels: None,
+            unit: SharedString::default(),
+        }
+    }
+
+    /// Creates a line chart.
+    #[allow(dead_code)]
+    pub fn line(values: impl Into<Vec<f32>>) -> Self {
+        Self::new(ChartKind::Line, values)
+    }
+
+    /// Creates a bar chart.
+    #[allow(dead_code)]
+    pub fn bar(values: impl Into<Vec<f32>>) -> Self {
+        Self::new(ChartKind::Bar, values)
+    }
+
+    /// Sets the total chart size, including axis labels.
+    pub fn size(mut self, width: f32, height: f32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Fixes the y-axis range instead of fitting it to the data.
+    pub fn range(mut self, min: Option<f32>, max: Option<f32>) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    /// Sets the series color (defaults to the theme accent).
+    pub fn color(mut self, color: Rgba) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Sets labels for the start and end of the x-axis.
+    pub fn x_labels(
+        mut self,
+        start: impl Into<SharedString>,
+        end: impl Into<SharedString>,
+    ) -> Self {
+        self.x_labels = Some((start.into(), end.into()));
+        self
+    }
+
+    /// Sets the unit suffix for y-axis labels (e.g. "%").
+    pub fn unit(mut self, unit: impl Into<SharedString>) -> Self {
+        self.unit = unit.into();
+        self
+    }
+
+    /// Returns the rendered height, including x-axis labels.
+    pub fn height(&self) -> f32 {
+        let labels = if self.x_labels.is_some() { 14.0 } else { 0.0 };
+        self.height + labels
+    }
+
+    /// Width of the plot area (excluding the axis).
+    fn plot_width(&self) -> f32 {
+        (self.width - AXIS_WIDTH).max(1.0)
+    }
+
+    /// Renders the chart with the given theme.
+    pub fn render(self, theme: &Theme) -> Div {
+        let color = self.color.unwrap_or(theme.accent);
+        match self.kind {
+            ChartKind::Line => {
+                let values = downsample(&self.values, (self.plot_width() / 2.0) as usize);
+                let (lo, hi) = value_range(&values, self.min, self.max);
+                let plot = canvas(
+                    |_bounds, _window, _cx| {},
+                    move |bounds: Bounds<Pixels>, _, window, _cx| {
+                        if values.len() < 2 {
+                            return;
+                        }
+                        let width = f32::from(bounds.size.width);
+                        let height = f32::from(bounds.size.height);
+                        let step = width / (values.len() - 1) as f32;
+                        let mut path = PathBuilder::stroke(px(1.5));
+                        for (i, value) in values.iter().enumerate() {
+                            let t = ((value - lo) / (hi - lo)).clamp(0.0, 1.0);
+                            let p = point(
+                                bounds.origin.x + px(i as f32 * step),
+                                bounds.origin.y + px(height - t * height),
+                            );
+                            if i == 0 {
+                                path.move_to(p);
+                            } else {
+                                path.line_to(p);
+                            }
+                        }
+                        if let Ok(path) = path.build() {
+                            window.paint_path(path, color);
+                        }
+                    },
+                )
+                .size_full();
+                self.frame(plot, lo, hi, theme)
+            }
+            ChartKind::Bar => {
+                // Bars need a few pixels each to stay readable
+                let values = downsample(&self.values, (self.plot_width() / 4.0) as usize);
+                let (lo, hi) = value_range(&values, self.min.or(Some(0.0)), self.max);
+                let bars = values.iter().map(|value| {
+                    let t = ((value - lo) / (hi - lo)).clamp(0.0, 1.0);
+                    div()
+                        .flex_1()
+                        .h(gpui::relative(t))
+                        .rounded_t(px(1.0))
+                        .bg(color)
+                });
+                let plot = div()
+                    .flex()
+                    .flex_row()
+                    .items_end()
+                    .gap(px(1.0))
+                    .size_full()
+                    .children(bars);
+                self.frame(plot, lo, hi, theme)
+            }
+        }
+    }
+
+    /// Wraps a plot element with y-axis labels and optional x-axis labels.
+    fn frame(&self, plot: impl IntoElement, lo: f32, hi: f32, theme: &Theme) -> Div {
+        let label_size = px((theme.font_size - 3.0).max(8.0));
+        let axis = div()
+            .flex()
+            .flex_col()
+            .justify_between()
+            .w(px(AXIS_WIDTH))
+            .h(px(self.height))
+            .pr(px(4.0))
+            .text_size(label_size)
+            .text_color(theme.foreground_subtle)
+            .child(SharedString::from(format!("{:.0}{}", hi, self.unit)))
+            .child(SharedString::from(format!("{:.0}{}", lo, self.unit)));
+
+        let mut chart = div()
+            .flex()
+            .flex_col()
+            .gap(px(2.0))
+            .w(px(self.width))
+            .child(
+                div().flex().flex_row().child(axis).child(
+                    div()
+                        .w(px(self.plot_width()))
+                        .h(px(self.height))
+                        .border_b_1()
+                        .border_l_1()
+                        .border_color(theme.border_subtle)
+                        .child(plot),
+                ),
+            );
+
+        if let Some((start, end)) = self.x_labels.clone() {
+            chart = chart.child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .justify_between()
+                    .pl(px(AXIS_WIDTH))
+                    .text_size(label_size)
+                    .text_color(theme.foreground_subtle)
+                    .child(start)
+                    .child(end),
+            );
+        }
+        chart
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downsample_keeps_short_series() {
+        assert_eq!(downsample(&[1.0, 2.0, 3.0], 10), vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn downsample_averages_buckets() {
+        let values = [1.0, 3.0, 5.0, 7.0, 9.0, 11.0];
+        assert_eq!(downsample(&values, 3), vec![2.0, 6.0, 10.0]);
+    }
+
+    #[test]
+    fn downsample_handles_uneven_buckets() {
+        let values: Vec<f32> = (0..10).map(|v| v as f32).collect();
+        let out = downsample(&values, 4);
+        assert_eq!(out.len(), 4);
+        assert_eq!(out, vec![0.5, 3.0, 5.5, 8.0]);
+    }
+
+    #[test]
+    fn value_range_fits_data_and_honors_bounds() {
+        assert_eq!(value_range(&[3.0, 9.0, 5.0], None, None), (3.0, 9.0));
+        assert_eq!(
+            value_range(&[3.0, 9.0], Some(0.0), Some(100.0)),
+            (0.0, 100.0)
+        );
+    }
+
+    #[test]
+    fn value_range_widens_flat_and_empty_series() {
+        assert_eq!(value_range(&[4.0, 4.0], None, None), (4.0, 5.0));
+        assert_eq!(value_range(&[], None, None), (0.0, 1.0));
+    }
+}
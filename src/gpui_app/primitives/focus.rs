@@ -0,0 +1,203 @@
+//! Keyboard focus management for popup content.
+//!
+//! Popups are rebuilt from module state on every render, so focus lives in
+//! the module as a [`FocusManager`]. The module feeds it `PopupEvent::Key`
+//! presses (Tab / Shift-Tab move focus, Enter / Space activate) and passes
+//! `is_focused(id)` to components like `Button::focused` and
+//! `TextInput::focused` when rendering.
+
+use gpui::{SharedString, Styled};
+
+use crate::gpui_app::modules::PopupKey;
+use crate::gpui_app::theme::Theme;
+
+/// What kind of component a focus stop is; decides which keys activate it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusKind {
+    /// Activated by Enter or Space
+    Button,
+    /// Takes typed text; only Enter activates (submits)
+    Input,
+    /// Moves its own selection with arrows; Enter activates the selection
+    List,
+}
+
+/// Result of routing a key through the focus manager.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FocusResult {
+    /// Key not handled; pass it on to the focused component
+    Ignored,
+    /// Focus moved to another component
+    Moved,
+    /// The focused component was activated
+    Activate(SharedString),
+}
+
+/// Tab order and current focus for a popup.
+#[derive(Debug, Clone, Default)]
+pub struct FocusManager {
+    stops: Vec<(SharedString, FocusKind)>,
+    index: Option<usize>,
+}
+
+impl FocusManager {
+    /// Creates an empty manager with nothing focused.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a focus stop in tab order.
+    #[allow(dead_code)]
+    pub fn with(mut self, id: impl Into<SharedString>, kind: FocusKind) -> Self {
+        self.stops.push((id.into(), kind));
+        self
+    }
+
+    /// Replaces the tab order, keeping focus on the same id if it remains.
+    pub fn set_order(&mut self, stops: Vec<(SharedString, FocusKind)>) {
+        let focused = self.focused().cloned();
+        self.stops = stops;
+        self.index = focused.and_then(|id| self.position(&id));
+    }
+
+    fn position(&self, id: &str) -> Option<usize> {
+        self.stops.iter().position(|(stop, _)| stop.as_ref() == id)
+    }
+
+    /// Returns the focused id, if any.
+    pub fn focused(&self) -> Option<&SharedString> {
+        self.index.map(|i| &self.stops[i].0)
+    }
+
+    /// Returns true if `id` has focus.
+    pub fn is_focused(&self, id: &str) -> bool {
+        self.focused().is_some_and(|focused| focused.as_ref() == id)
+    }
+
+    /// Focuses `id` if it's a registered stop.
+    #[allow(dead_code)]
+    pub fn focus(&mut self, id: &str) {
+        if let Some(index) = self.position(id) {
+            self.index = Some(index);
+        }
+    }
+
+    /// Clears focus.
+    pub fn blur(&mut self) {
+        self.index = None;
+    }
+
+    /// Moves focus forward (or backward), wrapping at the ends.
+    fn cycle(&mut self, backward: bool) {
+        let len = self.stops.len();
+        if len == 0 {
+            return;
+        }
+        self.index = Some(match (self.index, backward) {
+            (None, false) => 0,
+            (None, true) => len - 1,
+            (Some(i), false) => (i + 1) % len,
+            (Some(i), true) => (i + len - 1) % len,
+        });
+    }
+
+    /// Routes a key press: Tab / Shift-Tab move focus, Enter activates the
+    /// focused stop, and Space activates buttons.
+    pub fn handle_key(&mut self, key: &PopupKey) -> FocusResult {
+        if key.command || key.control || key.alt {
+            return FocusResult::Ignored;
+        }
+        match key.key.as_str() {
+            "tab" => {
+                self.cycle(key.shift);
+                FocusResult::Moved
+            }
+            "enter" | "space" => {
+                let Some(index) = self.index else {
+                    return FocusResult::Ignored;
+                };
+                let (id, kind) = &self.stops[index];
+                if key.key == "space" && *kind != FocusKind::Button {
+                    return FocusResult::Ignored;
+                }
+                FocusResult::Activate(id.clone())
+            }
+            _ => FocusResult::Ignored,
+        }
+    }
+}
+
+/// Draws the focus ring on a bordered element when `focused` is set.
+pub fn focus_ring<E: Styled>(el: E, theme: &Theme, focused: bool) -> E {
+    if focused {
+        el.border_color(theme.accent)
+    } else {
+        el
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str, shift: bool) -> PopupKey {
+        PopupKey {
+            key: name.to_string(),
+            shift,
+            ..PopupKey::default()
+        }
+    }
+
+    fn manager() -> FocusManager {
+        FocusManager::new()
+            .with("search", FocusKind::Input)
+            .with("ok", FocusKind::Button)
+            .with("cancel", FocusKind::Button)
+    }
+
+    #[test]
+    fn tab_cycles_forward_and_back_with_wrap() {
+        let mut focus = manager();
+        assert_eq!(focus.handle_key(&key("tab", false)), FocusResult::Moved);
+        assert!(focus.is_focused("search"));
+        focus.handle_key(&key("tab", true));
+        assert!(focus.is_focused("cancel"));
+        focus.handle_key(&key("tab", false));
+        assert!(focus.is_focused("search"));
+    }
+
+    #[test]
+    fn space_activates_buttons_only() {
+        let mut focus = manager();
+        focus.focus("search");
+        assert_eq!(focus.handle_key(&key("space", false)), FocusResult::Ignored);
+        assert_eq!(
+            focus.handle_key(&key("enter", false)),
+            FocusResult::Activate("search".into())
+        );
+        focus.focus("ok");
+        assert_eq!(
+            focus.handle_key(&key("space", false)),
+            FocusResult::Activate("ok".into())
+        );
+    }
+
+    #[test]
+    fn enter_without_focus_is_ignored() {
+        let mut focus = manager();
+        assert_eq!(focus.handle_key(&key("enter", false)), FocusResult::Ignored);
+    }
+
+    #[test]
+    fn set_order_keeps_focus_by_id() {
+        let mut focus = manager();
+        focus.focus("cancel");
+        focus.set_order(vec![
+            ("cancel".into(), FocusKind::Button),
+            ("ok".into(), FocusKind::Button),
+        ]);
+        assert!(focus.is_focused("cancel"));
+        focus.set_order(vec![("ok".into(), FocusKind::Button)]);
+        assert_eq!(focus.focused(), None);
+    }
+}
@@ -58,6 +58,8 @@ pub mod icons {
         pub const QUARTER: &str = "󰁻"; // U+F007B nf-md-battery_20
         pub const EMPTY: &str = "󰂎"; // U+F008E nf-md-battery_outline
         pub const CHARGING: &str = "󰂄"; // U+F0084 nf-md-battery_charging
+        pub const BOLT: &str = "󱐋"; // U+F140B nf-md-lightning_bolt
+        pub const PLUGGED: &str = "󰚥"; // U+F06A5 nf-md-power_plug
 
         /// Returns the appropriate battery icon for a charge level.
         pub fn for_level(level: u8, charging: bool) -> &'static str {
@@ -77,6 +79,19 @@ pub mod icons {
         }
     }
 
+    /// Audio device icons (Material Design Icons).
+    pub mod audio {
+        pub const SPEAKER: &str = "󰓃"; // U+F04C3 nf-md-speaker
+        pub const HEADPHONES: &str = "󰋋"; // U+F02CB nf-md-headphones
+    }
+
+    /// Peripheral icons (Material Design Icons).
+    pub mod peripherals {
+        pub const KEYBOARD: &str = "󰌌"; // U+F030C nf-md-keyboard
+        pub const MOUSE: &str = "󰍽"; // U+F037D nf-md-mouse
+        pub const BLUETOOTH: &str = "󰂯"; // U+F00AF nf-md-bluetooth
+    }
+
     /// Volume icons.
     pub mod volume {
         pub const HIGH: &str = "󰕾";
@@ -127,6 +142,8 @@ pub mod icons {
         pub const NEXT: &str = "󰒭";
         pub const PREV: &str = "󰒮";
         pub const NOTE: &str = "\u{f001}";
+        pub const SPOTIFY: &str = "\u{f1bc}";
+        pub const APPLE_MUSIC: &str = "\u{f179}";
     }
 
     /// System icons (Material Design Icons).
@@ -138,6 +155,47 @@ pub mod icons {
         pub const DOWNLOAD: &str = "󰇚"; // U+F01DA nf-md-download
         pub const UPLOAD: &str = "󰕒"; // U+F0552 nf-md-upload
         pub const CALENDAR: &str = "󰃭"; // U+F00ED nf-md-calendar
+        pub const APPS: &str = "󰀻"; // U+F003B nf-md-apps
+        pub const CONSOLE: &str = "󰆍"; // U+F018D nf-md-console
+        pub const SEARCH: &str = "󰍉"; // U+F0349 nf-md-magnify
+        pub const SCREEN_SHARE: &str = "󱒃"; // U+F1483 nf-md-monitor_share
+        pub const MONITOR: &str = "󰍹"; // U+F0379 nf-md-monitor
+        pub const BRIGHTNESS: &str = "󰃠"; // U+F00E0 nf-md-brightness_7
+        pub const CONTAINER: &str = "󰡨"; // U+F0868 nf-md-docker
+        pub const TIMER: &str = "󰔛"; // U+F051B nf-md-timer_outline
+        pub const PIN: &str = "󰐃"; // U+F0403 nf-md-pin
+        pub const EJECT: &str = "󰇪"; // U+F01EA nf-md-eject
+        pub const SHORTCUTS: &str = "󰌨"; // U+F0328 nf-md-layers
+        pub const ALERT: &str = "󰀦"; // U+F0026 nf-md-alert
+        pub const LOGS: &str = "󰉹"; // U+F0279 nf-md-format_list_bulleted
+        pub const UPDATE: &str = "󰚰"; // U+F06B0 nf-md-update
+        pub const FOLDER: &str = "󰉋"; // U+F024B nf-md-folder
+        pub const TRASH: &str = "󰩹"; // U+F0A79 nf-md-trash_can
+        pub const CHEVRON: &str = "󰅂"; // U+F0142 nf-md-chevron_right
+    }
+
+    /// Privacy indicator icons (Material Design Icons).
+    pub mod privacy {
+        pub const CAMERA: &str = "󰄀"; // U+F0100 nf-md-camera
+        pub const MICROPHONE: &str = "󰍬"; // U+F036C nf-md-microphone
+    }
+
+    /// Reminders icons (Material Design Icons).
+    pub mod reminders {
+        pub const LIST: &str = "󰝖"; // U+F0756 nf-md-format_list_checks
+        pub const UNCHECKED: &str = "󰄱"; // U+F0131 nf-md-checkbox_blank_outline
+    }
+
+    /// Light/dark appearance icons (Material Design Icons).
+    pub mod appearance {
+        pub const LIGHT: &str = "󰖨"; // U+F05A8 nf-md-white_balance_sunny
+        pub const DARK: &str = "󰖔"; // U+F0594 nf-md-weather_night
+    }
+
+    /// VPN status icons (Material Design Icons).
+    pub mod vpn {
+        pub const CONNECTED: &str = "󰖂"; // U+F0582 nf-md-vpn
+        pub const DISCONNECTED: &str = "󰦜"; // U+F099C nf-md-shield_off_outline
     }
 }
 
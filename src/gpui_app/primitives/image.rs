@@ -0,0 +1,262 @@
+//! Image primitive for bar items and popups.
+//!
+//! Sources are local paths (`~` is expanded) or `http(s)` URLs. URLs are
+//! downloaded once in the background into `~/.cache/sinew/images` and a
+//! skeleton is shown until the file lands; the bar (and an open popup) is
+//! refreshed when it does. Decoding and drawing are left to GPUI's `img`.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+
+use gpui::{div, img, prelude::*, px, Div, ObjectFit, Styled};
+
+use super::Skeleton;
+use crate::gpui_app::theme::Theme;
+
+/// Where an image source currently stands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageState {
+    /// URL download in progress
+    Loading,
+    /// File ready to draw
+    Ready(PathBuf),
+    /// Missing file or failed download
+    Failed,
+}
+
+/// Download state per URL, so each URL is fetched at most once per run.
+fn downloads() -> &'static Mutex<HashMap<String, ImageState>> {
+    static DOWNLOADS: OnceLock<Mutex<HashMap<String, ImageState>>> = OnceLock::new();
+    DOWNLOADS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Returns true for `http://` and `https://` sources.
+pub fn is_url(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+/// Expands a leading `~/` to the home directory.
+pub fn expand_tilde(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Cache file name for a URL: a hash of the URL plus its extension (if any),
+/// so GPUI can pick the decoder from the name.
+pub fn cache_file_name(url: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    let hash = hasher.finish();
+
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let ext = path
+        .rsplit('/')
+        .next()
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .filter(|ext| {
+            !ext.is_empty() && ext.len() <= 5 && ext.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+    match ext {
+        Some(ext) => format!("{:016x}.{}", hash, ext),
+        None => format!("{:016x}", hash),
+    }
+}
+
+fn cache_dir() -> PathBuf {
+    crate::config::get_cache_dir().join("images")
+}
+
+/// Resolves a source to its current state, starting a download for
+/// uncached URLs.
+pub fn resolve(source: &str) -> ImageState {
+    if !is_url(source) {
+        let path = expand_tilde(source);
+        return if path.is_file() {
+            ImageState::Ready(path)
+        } else {
+            ImageState::Failed
+        };
+    }
+
+    let path = cache_dir().join(cache_file_name(source));
+    if path.is_file() {
+        return ImageState::Ready(path);
+    }
+
+    let Ok(mut downloads) = downloads().lock() else {
+        return ImageState::Failed;
+    };
+    if let Some(state) = downloads.get(source) {
+        return state.clone();
+    }
+    downloads.insert(source.to_string(), ImageState::Loading);
+    let url = source.to_string();
+    std::thread::spawn(move || {
+        let state = match download(&url, &path) {
+            Ok(()) => ImageState::Ready(path),
+            Err(err) => {
+                log::warn!("Image download failed for {}: {}", url, err);
+                ImageState::Failed
+            }
+        };
+        if let Ok(mut downloads) = downloads().lock() {
+            downloads.insert(url, state);
+        }
+        crate::gpui_app::request_immediate_refresh();
+        if crate::gpui_app::popup_manager::is_popup_visible() {
+            let id = crate::gpui_app::popup_manager::get_current_module_id();
+            crate::gpui_app::popup_manager::notify_popup_needs_render(&id);
+        }
+    });
+    ImageState::Loading
+}
+
+/// Downloads `url` to `path` via a temp file so partial downloads are never
+/// picked up from the cache.
+fn download(url: &str, path: &Path) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let tmp = path.with_extension("part");
+    let status = Command::new("curl")
+        .args(["-fsSL", "--max-time", "20", "-o"])
+        .arg(&tmp)
+        .arg(url)
+        .status()
+        .map_err(|e| e.to_string())?;
+    if !status.success() {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(format!("curl exited with {}", status));
+    }
+    std::fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
+
+/// Image loaded from a path or URL.
+pub struct Image {
+    source: String,
+    width: f32,
+    height: f32,
+    corner_radius: f32,
+    cover: bool,
+}
+
+impl Image {
+    /// Creates an image for a file path or URL.
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            width: 16.0,
+            height: 16.0,
+            corner_radius: 0.0,
+            cover: false,
+        }
+    }
+
+    /// Sets width and height to the same size in pixels.
+    pub fn size(mut self, size: f32) -> Self {
+        self.width = size;
+        self.height = size;
+        self
+    }
+
+    /// Sets the width in pixels.
+    #[allow(dead_code)]
+    pub fn width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Sets the height in pixels.
+    #[allow(dead_code)]
+    pub fn height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// Sets the corner radius.
+    pub fn rounded(mut self, radius: f32) -> Self {
+        self.corner_radius = radius;
+        self
+    }
+
+    /// Fills the box and crops (default is to fit inside it).
+    #[allow(dead_code)]
+    pub fn cover(mut self) -> Self {
+        self.cover = true;
+        self
+    }
+
+    /// Renders the image, a loading skeleton, or an empty box on failure.
+    pub fn render(self, theme: &Theme) -> Div {
+        let frame = div()
+            .flex()
+            .flex_none()
+            .items_center()
+            .justify_center()
+            .w(px(self.width))
+            .h(px(self.height))
+            .rounded(px(self.corner_radius))
+            .overflow_hidden();
+
+        match resolve(&self.source) {
+            ImageState::Ready(path) => {
+                let fit = if self.cover {
+                    ObjectFit::Cover
+                } else {
+                    ObjectFit::Contain
+                };
+                frame.child(img(path).size_full().object_fit(fit))
+            }
+            ImageState::Loading => frame.child(
+                Skeleton::new()
+                    .fill_w()
+                    .fill_h()
+                    .rounded(self.corner_radius)
+                    .render(theme),
+            ),
+            ImageState::Failed => frame,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_urls() {
+        assert!(is_url("https://example.com/a.png"));
+        assert!(is_url("http://example.com/a.png"));
+        assert!(!is_url("~/pics/logo.png"));
+        assert!(!is_url("/tmp/https://x"));
+    }
+
+    #[test]
+    fn expands_home_prefix_only() {
+        let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+        assert_eq!(expand_tilde("~/pics/a.png"), home.join("pics/a.png"));
+        assert_eq!(expand_tilde("/tmp/a.png"), PathBuf::from("/tmp/a.png"));
+    }
+
+    #[test]
+    fn cache_name_keeps_extension_and_is_stable() {
+        let a = cache_file_name("https://example.com/art/cover.JPG?size=large");
+        assert!(a.ends_with(".jpg"));
+        assert_eq!(
+            a,
+            cache_file_name("https://example.com/art/cover.JPG?size=large")
+        );
+        assert_ne!(a, cache_file_name("https://example.com/art/other.JPG"));
+        assert!(!cache_file_name("https://example.com/image").contains('.'));
+        assert!(!cache_file_name("https://example.com/v1.2/image").contains('.'));
+    }
+}
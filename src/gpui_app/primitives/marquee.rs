@@ -0,0 +1,220 @@
+//! Marquee primitive: scrolls text that's wider than its box.
+//!
+//! Text that fits is drawn as is. Wider text is drawn twice, `gap` pixels
+//! apart, and slid left at `speed` pixels per second so it wraps around
+//! seamlessly; hovering pauses it. The text is measured after layout, so a
+//! new text starts scrolling one frame after it first appears.
+//!
+//! Owners report [`Marquee::is_animating`] from `GpuiModule::is_animating`
+//! so the bar keeps drawing frames while the text moves.
+
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+
+/// Marquee options, from `marquee = true` or a `marquee = { ... }` table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarqueeSettings {
+    /// Box width in pixels; longer text scrolls
+    pub width: f32,
+    /// Pixels per second
+    pub speed: f32,
+    /// Space between the end of the text and its repeat
+    pub gap: f32,
+    pub pause_on_hover: bool,
+}
+
+impl Default for MarqueeSettings {
+    fn default() -> Self {
+        Self {
+            width: 200.0,
+            speed: 30.0,
+            gap: 40.0,
+            pause_on_hover: true,
+        }
+    }
+}
+
+impl MarqueeSettings {
+    /// Reads the `marquee` option. Returns None when it's absent or false.
+    pub fn parse(value: Option<&toml::Value>) -> Option<Self> {
+        match value? {
+            toml::Value::Boolean(enabled) => enabled.then(Self::default),
+            toml::Value::Table(table) => {
+                let defaults = Self::default();
+                let number = |key: &str, default: f32| {
+                    table
+                        .get(key)
+                        .and_then(|v| v.as_float().or_else(|| v.as_integer().map(|i| i as f64)))
+                        .map(|v| v as f32)
+                        .filter(|v| *v >= 0.0)
+                        .unwrap_or(default)
+                };
+                Some(Self {
+                    width: number("width", defaults.width),
+                    speed: number("speed", defaults.speed),
+                    gap: number("gap", defaults.gap),
+                    pause_on_hover: table
+                        .get("pause_on_hover")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(defaults.pause_on_hover),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Moves `offset` forward by `distance`, wrapping every `period` pixels.
+pub fn advance(offset: f32, distance: f32, period: f32) -> f32 {
+    if period <= 0.0 {
+        return 0.0;
+    }
+    (offset + distance).rem_euclid(period)
+}
+
+#[derive(Debug, Default)]
+struct MarqueeState {
+    text: String,
+    /// Width of one copy of the text, once measured
+    text_width: Option<f32>,
+    offset: f32,
+    last_frame: Option<Instant>,
+    hovered: bool,
+}
+
+/// Scrolling text box.
+pub struct Marquee {
+    id: SharedString,
+    settings: MarqueeSettings,
+    state: Arc<Mutex<MarqueeState>>,
+}
+
+impl Marquee {
+    /// Creates a marquee. `id` must be unique among the bar's elements.
+    pub fn new(id: &str, settings: MarqueeSettings) -> Self {
+        Self {
+            id: SharedString::from(format!("marquee-{}", id)),
+            settings,
+            state: Arc::new(Mutex::new(MarqueeState::default())),
+        }
+    }
+
+    /// Returns true while the text moves or still needs measuring.
+    pub fn is_animating(&self) -> bool {
+        self.state.lock().is_ok_and(|state| {
+            let paused = state.hovered && self.settings.pause_on_hover;
+            match state.text_width {
+                Some(width) => width > self.settings.width && !paused,
+                None => !state.text.is_empty(),
+            }
+        })
+    }
+
+    /// Advances the scroll position and renders `text`.
+    pub fn render(&self, text: &str) -> AnyElement {
+        let Ok(mut state) = self.state.lock() else {
+            return div()
+                .child(SharedString::from(text.to_string()))
+                .into_any_element();
+        };
+        if state.text != text {
+            *state = MarqueeState {
+                text: text.to_string(),
+                hovered: state.hovered,
+                ..Default::default()
+            };
+        }
+        let now = Instant::now();
+        let scrolling = state
+            .text_width
+            .filter(|width| *width > self.settings.width);
+        if let (Some(width), Some(last)) = (scrolling, state.last_frame) {
+            if !(state.hovered && self.settings.pause_on_hover) {
+                let distance = (now - last).as_secs_f32() * self.settings.speed;
+                state.offset = advance(state.offset, distance, width + self.settings.gap);
+            }
+        }
+        state.last_frame = Some(now);
+        let offset = state.offset;
+        drop(state);
+
+        let copy = || {
+            div()
+                .flex_none()
+                .whitespace_nowrap()
+                .child(SharedString::from(text.to_string()))
+        };
+        let measured = Arc::clone(&self.state);
+        let mut strip = div()
+            .flex_none()
+            .flex()
+            .flex_row()
+            .gap(px(self.settings.gap))
+            .ml(px(-offset))
+            .child(copy())
+            .on_children_prepainted(move |bounds, _window, _cx| {
+                if let (Some(first), Ok(mut state)) = (bounds.first(), measured.lock()) {
+                    state.text_width = Some(f32::from(first.size.width));
+                }
+            });
+        if scrolling.is_some() {
+            strip = strip.child(copy());
+        }
+
+        let hovered = Arc::clone(&self.state);
+        // Hovering only changes what's drawn when it pauses scrolling text
+        let pauses = scrolling.is_some() && self.settings.pause_on_hover;
+        let mut frame = div()
+            .id(self.id.clone())
+            .flex()
+            .items_center()
+            .overflow_hidden()
+            .on_hover(move |is_hovered, window, _cx| {
+                if let Ok(mut state) = hovered.lock() {
+                    state.hovered = *is_hovered;
+                }
+                // Resume frames after a hover pause
+                if pauses {
+                    window.refresh();
+                }
+            });
+        frame = if scrolling.is_some() {
+            frame.w(px(self.settings.width))
+        } else {
+            frame.max_w(px(self.settings.width))
+        };
+        frame.child(strip).into_any_element()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Option<MarqueeSettings> {
+        let value: toml::Value = toml::from_str(source).unwrap();
+        MarqueeSettings::parse(value.get("marquee"))
+    }
+
+    #[test]
+    fn parses_bool_and_table() {
+        assert_eq!(parse("marquee = true"), Some(MarqueeSettings::default()));
+        assert_eq!(parse("marquee = false"), None);
+        assert_eq!(parse("other = 1"), None);
+        let settings =
+            parse("marquee = { width = 150, speed = 45.5, pause_on_hover = false }").unwrap();
+        assert_eq!(settings.width, 150.0);
+        assert_eq!(settings.speed, 45.5);
+        assert_eq!(settings.gap, MarqueeSettings::default().gap);
+        assert!(!settings.pause_on_hover);
+    }
+
+    #[test]
+    fn advance_wraps_around() {
+        assert_eq!(advance(10.0, 5.0, 100.0), 15.0);
+        assert_eq!(advance(98.0, 5.0, 100.0), 3.0);
+        assert_eq!(advance(50.0, 5.0, 0.0), 0.0);
+    }
+}
@@ -3,30 +3,49 @@
 //! Primitives are the lowest-level building blocks that compose into higher-level components.
 //! They provide a consistent API for common UI patterns.
 
+mod button;
+mod chart;
 mod container;
 mod flex;
+mod focus;
 pub mod icon;
+mod image;
 mod interactive;
+mod marquee;
+mod progress_bar;
+mod progress_ring;
 pub mod skeleton;
 pub mod slider;
 mod spacer;
+mod table;
 mod text;
+mod text_input;
 
 // Re-export primitives for external use (some not yet used internally)
+pub use button::Button;
+pub use chart::{Chart, ChartKind};
 #[allow(unused)]
 pub use container::Container;
 #[allow(unused)]
 pub use flex::{Flex, FlexDirection};
+pub use focus::{FocusKind, FocusManager, FocusResult};
 pub use icon::icons;
+pub use image::{expand_tilde, Image};
 #[allow(unused)]
 pub use interactive::Interactive;
+pub use marquee::{Marquee, MarqueeSettings};
+pub use progress_bar::ProgressBar;
+pub use progress_ring::ProgressRing;
 #[allow(unused)]
 pub use skeleton::Skeleton;
 pub use slider::{render_slider, SliderStyle};
 #[allow(unused)]
 pub use spacer::Spacer;
+pub use table::{Column, ColumnAlign, Table};
 #[allow(unused)]
 pub use text::Text;
+#[allow(unused)]
+pub use text_input::{InputResult, TextInput, TextInputState};
 
 /// Common spacing values.
 pub mod spacing {
@@ -0,0 +1,69 @@
+//! Horizontal progress bar primitive.
+//!
+//! A rounded track with a fill from the left edge, sized for use inline in
+//! the bar or in popups.
+
+use gpui::{div, prelude::*, px, relative, Div, Rgba, Styled};
+
+use crate::gpui_app::theme::Theme;
+
+/// Bar showing a 0–1 value.
+pub struct ProgressBar {
+    value: f32,
+    width: f32,
+    height: f32,
+    track_color: Option<Rgba>,
+    fill_color: Option<Rgba>,
+}
+
+impl ProgressBar {
+    /// Creates a bar for `value` in 0.0–1.0 (clamped).
+    pub fn new(value: f32) -> Self {
+        Self {
+            value: value.clamp(0.0, 1.0),
+            width: 60.0,
+            height: 4.0,
+            track_color: None,
+            fill_color: None,
+        }
+    }
+
+    /// Sets the width and height in pixels.
+    pub fn size(mut self, width: f32, height: f32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Sets the track color (defaults to the theme border color).
+    #[allow(dead_code)]
+    pub fn track_color(mut self, color: Rgba) -> Self {
+        self.track_color = Some(color);
+        self
+    }
+
+    /// Sets the fill color (defaults to the theme accent).
+    pub fn fill_color(mut self, color: Rgba) -> Self {
+        self.fill_color = Some(color);
+        self
+    }
+
+    /// Renders the bar with the given theme.
+    pub fn render(self, theme: &Theme) -> Div {
+        let radius = px(self.height / 2.0);
+        div()
+            .flex_none()
+            .w(px(self.width))
+            .h(px(self.height))
+            .rounded(radius)
+            .overflow_hidden()
+            .bg(self.track_color.unwrap_or(theme.border))
+            .child(
+                div()
+                    .h_full()
+                    .w(relative(self.value))
+                    .rounded(radius)
+                    .bg(self.fill_color.unwrap_or(theme.accent)),
+            )
+    }
+}
@@ -0,0 +1,191 @@
+//! Circular progress ring primitive.
+//!
+//! Draws a full-circle track with a clockwise fill arc starting at
+//! 12 o'clock. Arcs are approximated with short line segments so the path
+//! stays a plain stroked polyline.
+
+use gpui::{
+    canvas, div, point, prelude::*, px, Bounds, Div, PathBuilder, Pixels, Rgba, SharedString,
+    Styled, Window,
+};
+
+use crate::gpui_app::theme::Theme;
+
+/// Segments used for a full circle.
+const SEGMENTS: usize = 64;
+
+/// Returns points along a clockwise arc from 12 o'clock covering `fraction`
+/// of the circle (0.0–1.0). Offsets are relative to the circle's center.
+pub fn arc_points(radius: f32, fraction: f32) -> Vec<(f32, f32)> {
+    let fraction = fraction.clamp(0.0, 1.0);
+    let steps = ((SEGMENTS as f32 * fraction).ceil() as usize).max(1);
+    let sweep = std::f32::consts::TAU * fraction;
+    (0..=steps)
+        .map(|i| {
+            let angle = sweep * i as f32 / steps as f32;
+            (radius * angle.sin(), -radius * angle.cos())
+        })
+        .collect()
+}
+
+/// Strokes an arc inside `bounds`.
+fn paint_arc(
+    bounds: Bounds<Pixels>,
+    thickness: f32,
+    fraction: f32,
+    color: Rgba,
+    window: &mut Window,
+) {
+    if fraction <= 0.0 {
+        return;
+    }
+    let size = f32::from(bounds.size.width).min(f32::from(bounds.size.height));
+    let radius = (size - thickness) / 2.0;
+    let center = bounds.center();
+    let mut path = PathBuilder::stroke(px(thickness));
+    for (i, (x, y)) in arc_points(radius, fraction).into_iter().enumerate() {
+        let p = point(center.x + px(x), center.y + px(y));
+        if i == 0 {
+            path.move_to(p);
+        } else {
+            path.line_to(p);
+        }
+    }
+    if let Ok(path) = path.build() {
+        window.paint_path(path, color);
+    }
+}
+
+/// Ring showing a 0–1 value, with an optional label in the center.
+pub struct ProgressRing {
+    value: f32,
+    size: f32,
+    thickness: f32,
+    track_color: Option<Rgba>,
+    fill_color: Option<Rgba>,
+    label: Option<SharedString>,
+    label_size: Option<Pixels>,
+}
+
+impl ProgressRing {
+    /// Creates a ring for `value` in 0.0–1.0 (clamped).
+    pub fn new(value: f32) -> Self {
+        Self {
+            value: value.clamp(0.0, 1.0),
+            size: 16.0,
+            thickness: 2.0,
+            track_color: None,
+            fill_color: None,
+            label: None,
+            label_size: None,
+        }
+    }
+
+    /// Sets the outer diameter in pixels.
+    pub fn size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Sets the stroke width in pixels.
+    pub fn thickness(mut self, thickness: f32) -> Self {
+        self.thickness = thickness;
+        self
+    }
+
+    /// Sets the track color (defaults to the theme border color).
+    #[allow(dead_code)]
+    pub fn track_color(mut self, color: Rgba) -> Self {
+        self.track_color = Some(color);
+        self
+    }
+
+    /// Sets the fill color (defaults to the theme accent).
+    pub fn fill_color(mut self, color: Rgba) -> Self {
+        self.fill_color = Some(color);
+        self
+    }
+
+    /// Sets text shown in the center of the ring.
+    pub fn label(mut self, label: impl Into<SharedString>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Sets the center label font size.
+    #[allow(dead_code)]
+    pub fn label_size(mut self, size: impl Into<Pixels>) -> Self {
+        self.label_size = Some(size.into());
+        self
+    }
+
+    /// Renders the ring with the given theme.
+    pub fn render(self, theme: &Theme) -> Div {
+        let track = self.track_color.unwrap_or(theme.border);
+        let fill = self.fill_color.unwrap_or(theme.accent);
+        let thickness = self.thickness;
+        let value = self.value;
+
+        let ring = canvas(
+            |_bounds, _window, _cx| {},
+            move |bounds: Bounds<Pixels>, _, window, _cx| {
+                paint_arc(bounds, thickness, 1.0, track, window);
+                paint_arc(bounds, thickness, value, fill, window);
+            },
+        )
+        .absolute()
+        .size_full();
+
+        let mut el = div()
+            .relative()
+            .flex()
+            .items_center()
+            .justify_center()
+            .flex_none()
+            .size(px(self.size))
+            .child(ring);
+
+        if let Some(label) = self.label {
+            let size = self.label_size.unwrap_or(px((self.size * 0.4).max(7.0)));
+            el = el.child(
+                div()
+                    .text_size(size)
+                    .line_height(size)
+                    .text_color(theme.foreground)
+                    .child(label),
+            );
+        }
+        el
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: (f32, f32), b: (f32, f32)) -> bool {
+        (a.0 - b.0).abs() < 1e-3 && (a.1 - b.1).abs() < 1e-3
+    }
+
+    #[test]
+    fn arc_starts_at_twelve_oclock() {
+        let points = arc_points(10.0, 0.5);
+        assert!(approx(points[0], (0.0, -10.0)));
+    }
+
+    #[test]
+    fn arc_runs_clockwise() {
+        let quarter = arc_points(10.0, 0.25);
+        assert!(approx(*quarter.last().unwrap(), (10.0, 0.0)));
+        let half = arc_points(10.0, 0.5);
+        assert!(approx(*half.last().unwrap(), (0.0, 10.0)));
+    }
+
+    #[test]
+    fn arc_clamps_fraction() {
+        let full = arc_points(10.0, 2.0);
+        assert_eq!(full.len(), SEGMENTS + 1);
+        assert!(approx(*full.last().unwrap(), (0.0, -10.0)));
+        assert_eq!(arc_points(10.0, -1.0).len(), 2);
+    }
+}
@@ -0,0 +1,225 @@
+//! Table primitive for tabular popup content.
+//!
+//! Columns are either a fixed pixel width or a flex share of the remaining
+//! space, so rows line up without the caller measuring text.
+
+use gpui::{div, prelude::*, px, Div, Pixels, SharedString, Styled};
+
+use crate::gpui_app::theme::Theme;
+
+/// Horizontal alignment of a column's cells.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColumnAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl ColumnAlign {
+    /// Parses an alignment name, defaulting to left.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "center" => Self::Center,
+            "right" => Self::Right,
+            _ => Self::Left,
+        }
+    }
+}
+
+/// How wide a column is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnWidth {
+    /// Fixed width in pixels
+    Fixed(f32),
+    /// Share of the space left after fixed columns
+    Flex(f32),
+}
+
+impl Default for ColumnWidth {
+    fn default() -> Self {
+        Self::Flex(1.0)
+    }
+}
+
+/// Column definition.
+#[derive(Debug, Clone, Default)]
+pub struct Column {
+    title: SharedString,
+    align: ColumnAlign,
+    width: ColumnWidth,
+}
+
+impl Column {
+    /// Creates a flex column with the given header title.
+    pub fn new(title: impl Into<SharedString>) -> Self {
+        Self {
+            title: title.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the cell alignment.
+    pub fn align(mut self, align: ColumnAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Sets a fixed width in pixels.
+    pub fn fixed(mut self, width: f32) -> Self {
+        self.width = ColumnWidth::Fixed(width);
+        self
+    }
+
+    /// Sets a flex share of the remaining width.
+    #[allow(dead_code)]
+    pub fn flex(mut self, grow: f32) -> Self {
+        self.width = ColumnWidth::Flex(grow);
+        self
+    }
+}
+
+/// Table with a header row and striped body rows.
+pub struct Table {
+    columns: Vec<Column>,
+    rows: Vec<Vec<SharedString>>,
+    show_header: bool,
+    striped: bool,
+    row_height: Pixels,
+    text_size: Option<Pixels>,
+}
+
+impl Table {
+    /// Creates a table with the given columns.
+    pub fn new(columns: Vec<Column>) -> Self {
+        Self {
+            columns,
+            rows: Vec::new(),
+            show_header: true,
+            striped: true,
+            row_height: px(22.0),
+            text_size: None,
+        }
+    }
+
+    /// Appends a row. Missing cells render empty; extra cells are ignored.
+    pub fn row<S: Into<SharedString>>(mut self, cells: impl IntoIterator<Item = S>) -> Self {
+        self.rows.push(cells.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Appends several rows.
+    pub fn rows<R, S>(mut self, rows: impl IntoIterator<Item = R>) -> Self
+    where
+        R: IntoIterator<Item = S>,
+        S: Into<SharedString>,
+    {
+        for row in rows {
+            self = self.row(row);
+        }
+        self
+    }
+
+    /// Sets whether the header row is shown.
+    pub fn header(mut self, show: bool) -> Self {
+        self.show_header = show;
+        self
+    }
+
+    /// Sets whether alternate rows get a tinted background.
+    pub fn striped(mut self, striped: bool) -> Self {
+        self.striped = striped;
+        self
+    }
+
+    /// Sets the height of each row.
+    #[allow(dead_code)]
+    pub fn row_height(mut self, height: impl Into<Pixels>) -> Self {
+        self.row_height = height.into();
+        self
+    }
+
+    /// Sets the cell font size.
+    #[allow(dead_code)]
+    pub fn text_size(mut self, size: impl Into<Pixels>) -> Self {
+        self.text_size = Some(size.into());
+        self
+    }
+
+    /// Returns the rendered height of the table.
+    pub fn height(&self) -> f32 {
+        let rows = self.rows.len() + usize::from(self.show_header);
+        f32::from(self.row_height) * rows as f32
+    }
+
+    fn render_cell(column: &Column, text: SharedString) -> Div {
+        let mut cell = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .overflow_hidden()
+            .whitespace_nowrap()
+            .child(text);
+        cell = match column.width {
+            ColumnWidth::Fixed(width) => cell.w(px(width)).flex_none(),
+            ColumnWidth::Flex(grow) => {
+                let mut cell = cell.flex_basis(px(0.0)).min_w(px(0.0));
+                cell.style().flex_grow = Some(grow);
+                cell
+            }
+        };
+        match column.align {
+            ColumnAlign::Left => cell.justify_start(),
+            ColumnAlign::Center => cell.justify_center(),
+            ColumnAlign::Right => cell.justify_end(),
+        }
+    }
+
+    fn render_row(&self, cells: Vec<SharedString>) -> Div {
+        let mut cells = cells.into_iter();
+        div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap(px(8.0))
+            .px(px(8.0))
+            .h(self.row_height)
+            .children(
+                self.columns
+                    .iter()
+                    .map(|column| Self::render_cell(column, cells.next().unwrap_or_default())),
+            )
+    }
+
+    /// Renders the table with the given theme.
+    pub fn render(self, theme: &Theme) -> Div {
+        let text_size = self.text_size.unwrap_or(px(theme.font_size));
+        let mut table = div()
+            .flex()
+            .flex_col()
+            .w_full()
+            .text_size(text_size)
+            .text_color(theme.foreground);
+
+        if self.show_header {
+            let titles = self.columns.iter().map(|c| c.title.clone()).collect();
+            table = table.child(
+                self.render_row(titles)
+                    .text_color(theme.foreground_muted)
+                    .font_weight(gpui::FontWeight::MEDIUM)
+                    .border_b_1()
+                    .border_color(theme.border_subtle),
+            );
+        }
+
+        for (i, cells) in self.rows.iter().enumerate() {
+            let mut row = self.render_row(cells.clone());
+            if self.striped && i % 2 == 1 {
+                row = row.bg(theme.with_alpha(theme.surface, 0.5));
+            }
+            table = table.child(row);
+        }
+
+        table
+    }
+}
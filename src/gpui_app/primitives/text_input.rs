@@ -0,0 +1,415 @@
+//! Text input primitive for popup search fields.
+//!
+//! Popups are rendered from module state, so the input is split in two:
+//! [`TextInputState`] lives in the module and handles [`PopupKey`]s routed
+//! through `PopupEvent::Key`, and [`TextInput`] renders that state. Modules
+//! opt into key routing with `PopupSpec::with_keyboard()`.
+
+use gpui::{div, prelude::*, px, Div, SharedString, Styled};
+
+use super::focus::focus_ring;
+use crate::gpui_app::modules::PopupKey;
+use crate::gpui_app::theme::Theme;
+
+/// What a key press did to the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputResult {
+    /// Key not handled by the input
+    Ignored,
+    /// Text changed
+    Edited,
+    /// Cursor or selection moved
+    Moved,
+    /// Enter pressed
+    Submitted,
+}
+
+/// Editable text with a cursor and optional selection.
+///
+/// Positions are char indices, not byte offsets.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextInputState {
+    text: String,
+    cursor: usize,
+    /// Selection start; the selection spans anchor..cursor (either order)
+    anchor: Option<usize>,
+}
+
+impl TextInputState {
+    /// Creates an empty input.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the current text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text and moves the cursor to the end.
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.len();
+        self.anchor = None;
+    }
+
+    /// Returns the cursor position.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Returns the selected char range, if any.
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        let anchor = self.anchor?;
+        if anchor == self.cursor {
+            return None;
+        }
+        Some((anchor.min(self.cursor), anchor.max(self.cursor)))
+    }
+
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    fn byte_offset(&self, index: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(index)
+            .map(|(i, _)| i)
+            .unwrap_or(self.text.len())
+    }
+
+    /// Deletes the selection (if any), returning true if text was removed.
+    fn delete_selection(&mut self) -> bool {
+        let Some((start, end)) = self.selection() else {
+            self.anchor = None;
+            return false;
+        };
+        let (a, b) = (self.byte_offset(start), self.byte_offset(end));
+        self.text.replace_range(a..b, "");
+        self.cursor = start;
+        self.anchor = None;
+        true
+    }
+
+    /// Inserts text at the cursor, replacing the selection.
+    pub fn insert(&mut self, text: &str) {
+        self.delete_selection();
+        let at = self.byte_offset(self.cursor);
+        self.text.insert_str(at, text);
+        self.cursor += text.chars().count();
+    }
+
+    /// Selects all text.
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.cursor = self.len();
+    }
+
+    /// Start of the word before `index` (skips whitespace, then word chars).
+    fn word_start(&self, index: usize) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut i = index;
+        while i > 0 && chars[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        while i > 0 && !chars[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        i
+    }
+
+    /// End of the word after `index`.
+    fn word_end(&self, index: usize) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut i = index;
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+        while i < chars.len() && !chars[i].is_whitespace() {
+            i += 1;
+        }
+        i
+    }
+
+    /// Moves the cursor, extending the selection when `select` is set.
+    fn move_to(&mut self, index: usize, select: bool) {
+        if select {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = index.min(self.len());
+    }
+
+    /// Deletes from `start` to the cursor (or the selection, if any).
+    fn delete_back_to(&mut self, start: usize) {
+        if self.delete_selection() {
+            return;
+        }
+        let (a, b) = (self.byte_offset(start), self.byte_offset(self.cursor));
+        self.text.replace_range(a..b, "");
+        self.cursor = start;
+    }
+
+    /// Deletes from the cursor to `end` (or the selection, if any).
+    fn delete_forward_to(&mut self, end: usize) {
+        if self.delete_selection() {
+            return;
+        }
+        let (a, b) = (self.byte_offset(self.cursor), self.byte_offset(end));
+        self.text.replace_range(a..b, "");
+    }
+
+    /// Applies a key press.
+    pub fn handle_key(&mut self, key: &PopupKey) -> InputResult {
+        let before = self.text.clone();
+        let select = key.shift;
+        match key.key.as_str() {
+            "enter" => return InputResult::Submitted,
+            "left" if key.command => self.move_to(0, select),
+            "left" if key.alt => self.move_to(self.word_start(self.cursor), select),
+            "left" => match self.selection() {
+                Some((start, _)) if !select => self.move_to(start, false),
+                _ => self.move_to(self.cursor.saturating_sub(1), select),
+            },
+            "right" if key.command => self.move_to(self.len(), select),
+            "right" if key.alt => self.move_to(self.word_end(self.cursor), select),
+            "right" => match self.selection() {
+                Some((_, end)) if !select => self.move_to(end, false),
+                _ => self.move_to(self.cursor + 1, select),
+            },
+            "home" | "up" => self.move_to(0, select),
+            "end" | "down" => self.move_to(self.len(), select),
+            "a" if key.command => self.select_all(),
+            "backspace" if key.command => self.delete_back_to(0),
+            "backspace" if key.alt => self.delete_back_to(self.word_start(self.cursor)),
+            "backspace" => self.delete_back_to(self.cursor.saturating_sub(1)),
+            "delete" => self.delete_forward_to((self.cursor + 1).min(self.len())),
+            _ => {
+                if key.command || key.control {
+                    return InputResult::Ignored;
+                }
+                match key.text.as_deref() {
+                    Some(text) if !text.is_empty() && !text.chars().any(char::is_control) => {
+                        self.insert(text)
+                    }
+                    _ => return InputResult::Ignored,
+                }
+            }
+        }
+        if self.text != before {
+            InputResult::Edited
+        } else {
+            InputResult::Moved
+        }
+    }
+}
+
+/// Renders a [`TextInputState`] as a single-line field.
+pub struct TextInput<'a> {
+    state: &'a TextInputState,
+    placeholder: SharedString,
+    focused: bool,
+    icon: Option<SharedString>,
+}
+
+impl<'a> TextInput<'a> {
+    /// Creates a renderer for the given state.
+    pub fn new(state: &'a TextInputState) -> Self {
+        Self {
+            state,
+            placeholder: SharedString::default(),
+            focused: true,
+            icon: None,
+        }
+    }
+
+    /// Sets the placeholder shown while empty.
+    pub fn placeholder(mut self, text: impl Into<SharedString>) -> Self {
+        self.placeholder = text.into();
+        self
+    }
+
+    /// Sets whether the field shows its cursor and focus ring.
+    #[allow(dead_code)]
+    pub fn focused(mut self, focused: bool) -> Self {
+        self.focused = focused;
+        self
+    }
+
+    /// Sets a leading icon glyph (e.g. a search icon).
+    pub fn icon(mut self, glyph: impl Into<SharedString>) -> Self {
+        self.icon = Some(glyph.into());
+        self
+    }
+
+    /// Renders the input with the given theme.
+    pub fn render(self, theme: &Theme) -> Div {
+        let state = self.state;
+        let cursor = || {
+            div()
+                .w(px(1.0))
+                .h(px(theme.font_size + 2.0))
+                .bg(theme.accent)
+        };
+        let slice = |start: usize, end: usize| -> SharedString {
+            state
+                .text
+                .chars()
+                .skip(start)
+                .take(end - start)
+                .collect::<String>()
+                .into()
+        };
+
+        let mut field = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .w_full()
+            .px(px(8.0))
+            .py(px(4.0))
+            .gap(px(6.0))
+            .rounded(px(6.0))
+            .bg(theme.surface)
+            .border_1()
+            .border_color(theme.border)
+            .text_size(px(theme.font_size))
+            .text_color(theme.foreground);
+        field = focus_ring(field, theme, self.focused);
+
+        if let Some(icon) = self.icon {
+            field = field.child(div().text_color(theme.foreground_muted).child(icon));
+        }
+
+        // Text is split around the cursor/selection; whitespace is preserved
+        let mut text = div().flex().flex_row().items_center().whitespace_nowrap();
+        let len = state.len();
+        if len == 0 {
+            if self.focused {
+                text = text.child(cursor());
+            }
+            text = text.child(
+                div()
+                    .text_color(theme.foreground_subtle)
+                    .child(self.placeholder),
+            );
+        } else if let Some((start, end)) = state.selection() {
+            text = text
+                .child(slice(0, start))
+                .child(
+                    div()
+                        .bg(theme.with_alpha(theme.accent, 0.35))
+                        .child(slice(start, end)),
+                )
+                .child(slice(end, len));
+        } else {
+            text = text.child(slice(0, state.cursor));
+            if self.focused {
+                text = text.child(cursor());
+            }
+            text = text.child(slice(state.cursor, len));
+        }
+
+        field.child(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str) -> PopupKey {
+        PopupKey {
+            key: name.to_string(),
+            ..PopupKey::default()
+        }
+    }
+
+    fn typed(text: &str) -> PopupKey {
+        PopupKey {
+            key: text.to_lowercase(),
+            text: Some(text.to_string()),
+            ..PopupKey::default()
+        }
+    }
+
+    fn state(text: &str) -> TextInputState {
+        let mut state = TextInputState::new();
+        state.set_text(text);
+        state
+    }
+
+    #[test]
+    fn typing_inserts_at_cursor() {
+        let mut input = state("helo");
+        input.handle_key(&key("left"));
+        assert_eq!(input.handle_key(&typed("l")), InputResult::Edited);
+        assert_eq!(input.text(), "hello");
+        assert_eq!(input.cursor(), 4);
+    }
+
+    #[test]
+    fn backspace_and_delete_handle_multibyte_chars() {
+        let mut input = state("héllo");
+        input.handle_key(&key("home"));
+        input.handle_key(&key("right"));
+        input.handle_key(&key("delete"));
+        assert_eq!(input.text(), "hllo");
+        input.handle_key(&key("backspace"));
+        assert_eq!(input.text(), "llo");
+    }
+
+    #[test]
+    fn shift_arrows_select_and_typing_replaces_selection() {
+        let mut input = state("hello world");
+        let shift_left = PopupKey {
+            shift: true,
+            alt: true,
+            ..key("left")
+        };
+        assert_eq!(input.handle_key(&shift_left), InputResult::Moved);
+        assert_eq!(input.selection(), Some((6, 11)));
+        input.handle_key(&typed("there"));
+        assert_eq!(input.text(), "hello there");
+        assert_eq!(input.selection(), None);
+    }
+
+    #[test]
+    fn command_a_then_backspace_clears() {
+        let mut input = state("query");
+        let select_all = PopupKey {
+            command: true,
+            ..key("a")
+        };
+        input.handle_key(&select_all);
+        assert_eq!(input.selection(), Some((0, 5)));
+        input.handle_key(&key("backspace"));
+        assert_eq!(input.text(), "");
+    }
+
+    #[test]
+    fn option_backspace_deletes_previous_word() {
+        let mut input = state("open safari ");
+        let word_back = PopupKey {
+            alt: true,
+            ..key("backspace")
+        };
+        input.handle_key(&word_back);
+        assert_eq!(input.text(), "open ");
+    }
+
+    #[test]
+    fn enter_submits_and_unknown_keys_are_ignored() {
+        let mut input = state("x");
+        assert_eq!(input.handle_key(&key("enter")), InputResult::Submitted);
+        assert_eq!(input.handle_key(&key("f5")), InputResult::Ignored);
+        let copy = PopupKey {
+            command: true,
+            ..typed("c")
+        };
+        assert_eq!(input.handle_key(&copy), InputResult::Ignored);
+        assert_eq!(input.text(), "x");
+    }
+}
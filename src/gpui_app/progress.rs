@@ -0,0 +1,133 @@
+//! Progress items set over IPC.
+//!
+//! `sinew-msg progress backup 0.42` adds a slim progress bar named `backup`
+//! to the zone set by `bar.progress.zone`, or moves an existing one. Items
+//! need no config: they appear on first use, in the order they were created,
+//! and disappear `bar.progress.hide_delay` seconds after reaching 100% or
+//! right away with `sinew-msg progress backup remove`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use gpui::{div, prelude::*, px, AnyElement, SharedString, Styled};
+
+use crate::gpui_app::primitives::ProgressBar;
+use crate::gpui_app::request_immediate_refresh;
+use crate::gpui_app::theme::Theme;
+
+/// Width of each item's bar.
+const BAR_WIDTH: f32 = 60.0;
+/// Height of each item's bar.
+const BAR_HEIGHT: f32 = 4.0;
+
+/// Items on screen, in creation order.
+static ITEMS: Mutex<Vec<ProgressItem>> = Mutex::new(Vec::new());
+/// `bar.progress.hide_delay` in milliseconds.
+static HIDE_DELAY_MS: AtomicU64 = AtomicU64::new(3000);
+
+/// A named progress bar.
+#[derive(Debug, Clone)]
+struct ProgressItem {
+    name: String,
+    /// 0.0–1.0
+    value: f32,
+    /// When the value reached 1.0
+    completed_at: Option<Instant>,
+}
+
+/// Sets how long finished items stay before they're removed.
+pub fn set_hide_delay(delay: Duration) {
+    HIDE_DELAY_MS.store(delay.as_millis() as u64, Ordering::Relaxed);
+}
+
+fn hide_delay() -> Duration {
+    Duration::from_millis(HIDE_DELAY_MS.load(Ordering::Relaxed))
+}
+
+/// Creates or updates the item `name`.
+///
+/// @param name - Item name, shown next to the bar
+/// @param value - Progress in 0.0–1.0 (clamped)
+pub fn set(name: &str, value: f32) {
+    let value = value.clamp(0.0, 1.0);
+    let done = value >= 1.0;
+    if let Ok(mut items) = ITEMS.lock() {
+        let completed_at = done.then(Instant::now);
+        match items.iter_mut().find(|item| item.name == name) {
+            Some(item) => {
+                item.value = value;
+                // Keep the original finish time if 100% is sent twice
+                item.completed_at = item.completed_at.filter(|_| done).or(completed_at);
+            }
+            None => items.push(ProgressItem {
+                name: name.to_string(),
+                value,
+                completed_at,
+            }),
+        }
+    }
+    request_immediate_refresh();
+
+    if done {
+        // Redraw once the finished item is due to disappear
+        let delay = hide_delay();
+        std::thread::spawn(move || {
+            std::thread::sleep(delay);
+            request_immediate_refresh();
+        });
+    }
+}
+
+/// Removes the item `name`, returning false if there was none.
+pub fn remove(name: &str) -> bool {
+    let removed = ITEMS
+        .lock()
+        .map(|mut items| {
+            let before = items.len();
+            items.retain(|item| item.name != name);
+            items.len() != before
+        })
+        .unwrap_or(false);
+    if removed {
+        request_immediate_refresh();
+    }
+    removed
+}
+
+/// Renders the current items, dropping finished ones past the hide delay.
+pub fn render(theme: &Theme) -> Vec<AnyElement> {
+    let items = match ITEMS.lock() {
+        Ok(mut items) => {
+            let delay = hide_delay();
+            items.retain(|item| item.completed_at.is_none_or(|at| at.elapsed() < delay));
+            items.clone()
+        }
+        Err(_) => return Vec::new(),
+    };
+
+    items
+        .into_iter()
+        .map(|item| {
+            let fill = if item.completed_at.is_some() {
+                theme.success
+            } else {
+                theme.accent
+            };
+            div()
+                .flex()
+                .items_center()
+                .gap(px(6.0))
+                .text_size(px(theme.font_size * 0.85))
+                .text_color(theme.foreground_muted)
+                .child(SharedString::from(item.name))
+                .child(
+                    ProgressBar::new(item.value)
+                        .size(BAR_WIDTH, BAR_HEIGHT)
+                        .fill_color(fill)
+                        .render(theme),
+                )
+                .into_any_element()
+        })
+        .collect()
+}
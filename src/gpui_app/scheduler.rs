@@ -1,7 +1,4 @@
-//! Placeholder scheduler for c43e505 compatibility.
-//!
-//! Later commits removed this module; keep minimal API surface here so
-//! older call sites continue to compile without changing behavior.
+//! Repeating background tasks, such as evaluating alert rules.
 
 use std::sync::{
     atomic::{AtomicBool, Ordering},
@@ -9,8 +6,9 @@ use std::sync::{
 };
 use std::time::Duration;
 
+use crate::system::idle;
+
 /// Schedule a repeating task that can be stopped via the provided flag.
-#[allow(dead_code)]
 pub fn schedule<F>(interval: Duration, stop: Arc<AtomicBool>, mut task: F)
 where
     F: FnMut() + Send + 'static,
@@ -22,3 +20,15 @@ where
         }
     });
 }
+
+/// Like `schedule`, but skips runs while the screen is locked.
+pub fn schedule_unlocked<F>(interval: Duration, stop: Arc<AtomicBool>, mut task: F)
+where
+    F: FnMut() + Send + 'static,
+{
+    schedule(interval, stop, move || {
+        if !idle::is_screen_locked() {
+            task();
+        }
+    });
+}
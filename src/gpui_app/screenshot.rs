@@ -0,0 +1,235 @@
+//! `sinew --screenshot <out.png>`: renders the bar, or one module's popup,
+//! to a PNG and exits.
+//!
+//! The app starts as usual but without the IPC socket, hotkeys or the HUD,
+//! so it can run next to the real bar. Its windows sit below the desktop
+//! picture where nobody sees them; the window server still renders them,
+//! and capturing a window of our own needs no screen recording permission.
+//! Modules get a few seconds to load their data before the capture.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use core_foundation::base::TCFType;
+use core_foundation::string::CFString;
+use core_foundation::url::CFURL;
+use gpui::App;
+use objc2::MainThreadMarker;
+
+use super::modules::get_popup_spec;
+use super::popup_manager;
+use super::window_registry::{self, WindowRole};
+
+/// How long modules get to render their data before the capture.
+const SETTLE_TIME: Duration = Duration::from_secs(3);
+
+/// Window level below the desktop picture (kCGDesktopWindowLevel - 1).
+const HIDDEN_WINDOW_LEVEL: i64 = i32::MIN as i64 + 19;
+
+// CoreGraphics and ImageIO FFI bindings
+mod ffi {
+    use core_foundation::string::CFStringRef;
+    use core_foundation::url::CFURLRef;
+    use core_graphics::geometry::CGRect;
+    use std::ffi::c_void;
+
+    pub const WINDOW_LIST_OPTION_INCLUDING_WINDOW: u32 = 1 << 3;
+    pub const WINDOW_IMAGE_BOUNDS_IGNORE_FRAMING: u32 = 1 << 0;
+    pub const WINDOW_IMAGE_BEST_RESOLUTION: u32 = 1 << 3;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        pub static CGRectNull: CGRect;
+
+        pub fn CGWindowListCreateImage(
+            bounds: CGRect,
+            list_option: u32,
+            window: u32,
+            image_option: u32,
+        ) -> *const c_void;
+    }
+
+    #[link(name = "ImageIO", kind = "framework")]
+    extern "C" {
+        pub fn CGImageDestinationCreateWithURL(
+            url: CFURLRef,
+            image_type: CFStringRef,
+            count: usize,
+            options: *const c_void,
+        ) -> *const c_void;
+        pub fn CGImageDestinationAddImage(
+            destination: *const c_void,
+            image: *const c_void,
+            properties: *const c_void,
+        );
+        pub fn CGImageDestinationFinalize(destination: *const c_void) -> bool;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        pub fn CFRelease(cf: *const c_void);
+    }
+}
+
+/// What `--screenshot` was asked to capture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    /// PNG file to write
+    pub output: PathBuf,
+    /// Config file to render instead of ~/.config/sinew/config.toml
+    pub config: Option<PathBuf>,
+    /// Module whose popup is captured instead of the bar
+    pub popup: Option<String>,
+}
+
+impl Request {
+    /// Parses the arguments after `--screenshot`:
+    /// `<out.png> [--config <path>] [--popup <module-id>]`.
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut args = args.iter();
+        let output = match args.next() {
+            Some(output) if !output.starts_with("--") => PathBuf::from(output),
+            _ => return Err("--screenshot requires an output file".to_string()),
+        };
+        let mut request = Self {
+            output,
+            config: None,
+            popup: None,
+        };
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("{} requires a value", flag))?;
+            match flag.as_str() {
+                "--config" => request.config = Some(PathBuf::from(value)),
+                "--popup" => request.popup = Some(value.clone()),
+                _ => return Err(format!("Unknown --screenshot option: {}", flag)),
+            }
+        }
+        Ok(request)
+    }
+}
+
+static REQUEST: OnceLock<Request> = OnceLock::new();
+
+/// Makes this run render a screenshot instead of showing the bar.
+pub fn request(request: Request) {
+    let _ = REQUEST.set(request);
+}
+
+/// Returns true when this run only renders a screenshot.
+pub fn active() -> bool {
+    REQUEST.get().is_some()
+}
+
+/// The level to give a window: `level` normally, below the desktop
+/// picture while rendering a screenshot.
+pub fn window_level(level: i64) -> i64 {
+    if active() {
+        HIDDEN_WINDOW_LEVEL
+    } else {
+        level
+    }
+}
+
+/// Captures the requested window once the bar has settled, then exits.
+pub fn start(cx: &mut App) {
+    let Some(request) = REQUEST.get() else {
+        return;
+    };
+    cx.spawn(async move |cx| {
+        cx.background_executor().timer(SETTLE_TIME).await;
+        let role = match &request.popup {
+            Some(module_id) => {
+                if !popup_manager::toggle_popup(module_id) {
+                    eprintln!("Module '{}' has no popup", module_id);
+                    std::process::exit(1);
+                }
+                cx.background_executor().timer(SETTLE_TIME).await;
+                let popup_type = get_popup_spec(module_id)
+                    .map(|spec| spec.popup_type)
+                    .unwrap_or_default();
+                popup_manager::window_role(popup_type)
+            }
+            None => WindowRole::Bar,
+        };
+        match capture(&role, &request.output) {
+            Ok(()) => {
+                println!("Wrote {}", request.output.display());
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("Failed to capture the {:?} window: {}", role, e);
+                std::process::exit(1);
+            }
+        }
+    })
+    .detach();
+}
+
+/// Writes the contents of the window tagged `role` to a PNG at `path`.
+fn capture(role: &WindowRole, path: &Path) -> Result<(), String> {
+    let mtm = MainThreadMarker::new().ok_or("not on the main thread")?;
+    let window = window_registry::window(mtm, role).ok_or("window not found")?;
+    let path = std::path::absolute(path).map_err(|e| e.to_string())?;
+    let url = CFURL::from_path(&path, false).ok_or("invalid output path")?;
+    let png = CFString::new("public.png");
+
+    unsafe {
+        let image = ffi::CGWindowListCreateImage(
+            ffi::CGRectNull,
+            ffi::WINDOW_LIST_OPTION_INCLUDING_WINDOW,
+            window.windowNumber() as u32,
+            ffi::WINDOW_IMAGE_BOUNDS_IGNORE_FRAMING | ffi::WINDOW_IMAGE_BEST_RESOLUTION,
+        );
+        if image.is_null() {
+            return Err("the window server returned no image".to_string());
+        }
+        let destination = ffi::CGImageDestinationCreateWithURL(
+            url.as_concrete_TypeRef(),
+            png.as_concrete_TypeRef(),
+            1,
+            std::ptr::null(),
+        );
+        if destination.is_null() {
+            ffi::CFRelease(image);
+            return Err(format!("can't write {}", path.display()));
+        }
+        ffi::CGImageDestinationAddImage(destination, image, std::ptr::null());
+        let written = ffi::CGImageDestinationFinalize(destination);
+        ffi::CFRelease(destination);
+        ffi::CFRelease(image);
+        if !written {
+            return Err(format!("can't write {}", path.display()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Request, String> {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        Request::parse(&args)
+    }
+
+    #[test]
+    fn parses_screenshot_options() {
+        assert_eq!(
+            parse(&["out.png", "--config", "test.toml", "--popup", "clock"]),
+            Ok(Request {
+                output: PathBuf::from("out.png"),
+                config: Some(PathBuf::from("test.toml")),
+                popup: Some("clock".to_string()),
+            })
+        );
+        assert_eq!(parse(&["out.png"]).map(|r| r.config), Ok(None::<PathBuf>));
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["--config", "test.toml"]).is_err());
+        assert!(parse(&["out.png", "--popup"]).is_err());
+        assert!(parse(&["out.png", "--theme", "dark"]).is_err());
+    }
+}
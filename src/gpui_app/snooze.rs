@@ -0,0 +1,203 @@
+//! Modules hidden for a while.
+//!
+//! A module can be snoozed from its context menu or with
+//! `sinew-msg snooze <id> <minutes>|reload|restart`: it leaves the bar and
+//! the rest of its zone closes the gap until the time is up, the config is
+//! reloaded, or Sinew restarts. While anything is snoozed, a chevron at the
+//! far right of the bar lists the snoozed modules so they can be brought
+//! back early.
+
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// How long a module stays hidden.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Until {
+    Time(Instant),
+    Reload,
+    Restart,
+}
+
+impl Until {
+    /// Parses `<minutes>`, `reload` or `restart`.
+    pub fn parse(value: &str, now: Instant) -> Option<Self> {
+        match value {
+            "reload" => Some(Self::Reload),
+            "restart" => Some(Self::Restart),
+            _ => {
+                let minutes: f64 = value.parse().ok()?;
+                (minutes.is_finite() && minutes > 0.0)
+                    .then(|| Self::Time(now + Duration::from_secs_f64(minutes * 60.0)))
+            }
+        }
+    }
+
+    /// Short description, e.g. "12m left" or "until reload".
+    pub fn describe(&self, now: Instant) -> String {
+        match self {
+            Self::Time(at) => {
+                let minutes = at.saturating_duration_since(now).as_secs().div_ceil(60);
+                if minutes >= 60 {
+                    format!("{}h {}m left", minutes / 60, minutes % 60)
+                } else {
+                    format!("{}m left", minutes.max(1))
+                }
+            }
+            Self::Reload => "until reload".to_string(),
+            Self::Restart => "until restart".to_string(),
+        }
+    }
+}
+
+/// Snoozed modules, oldest first.
+#[derive(Debug, Default)]
+pub struct Snoozes {
+    entries: Vec<(String, Until)>,
+}
+
+impl Snoozes {
+    /// Hides module `id`, replacing an earlier snooze of it.
+    pub fn snooze(&mut self, id: &str, until: Until) {
+        self.entries.retain(|(entry, _)| entry != id);
+        self.entries.push((id.to_string(), until));
+    }
+
+    /// Brings module `id` back. Returns true if it was snoozed.
+    pub fn restore(&mut self, id: &str) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|(entry, _)| entry != id);
+        self.entries.len() != before
+    }
+
+    /// Drops snoozes whose time is up.
+    pub fn expire(&mut self, now: Instant) {
+        self.entries
+            .retain(|(_, until)| !matches!(until, Until::Time(at) if *at <= now));
+    }
+
+    /// Drops the snoozes that last until the config is reloaded.
+    pub fn reload(&mut self) {
+        self.entries.retain(|(_, until)| *until != Until::Reload);
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.entries.iter().any(|(entry, _)| entry == id)
+    }
+
+    /// When the next timed snooze ends.
+    pub fn next_expiry(&self) -> Option<Instant> {
+        self.entries
+            .iter()
+            .filter_map(|(_, until)| match until {
+                Until::Time(at) => Some(*at),
+                _ => None,
+            })
+            .min()
+    }
+}
+
+fn state() -> &'static Mutex<Snoozes> {
+    static STATE: OnceLock<Mutex<Snoozes>> = OnceLock::new();
+    STATE.get_or_init(|| Mutex::new(Snoozes::default()))
+}
+
+/// Hides module `id` and redraws the bar.
+pub fn snooze(id: &str, until: Until) {
+    if let Ok(mut snoozes) = state().lock() {
+        snoozes.snooze(id, until);
+    }
+    log::info!(
+        "Snoozed module '{}' ({})",
+        id,
+        until.describe(Instant::now())
+    );
+    crate::gpui_app::request_immediate_refresh();
+}
+
+/// Brings module `id` back early. Returns true if it was snoozed.
+pub fn restore(id: &str) -> bool {
+    let restored = state().lock().is_ok_and(|mut snoozes| snoozes.restore(id));
+    if restored {
+        crate::gpui_app::request_immediate_refresh();
+    }
+    restored
+}
+
+/// Ends the snoozes that last until the config is reloaded.
+pub fn on_reload() {
+    if let Ok(mut snoozes) = state().lock() {
+        snoozes.reload();
+    }
+}
+
+/// Whether module `id` is hidden right now.
+pub fn is_snoozed(id: &str) -> bool {
+    state().lock().is_ok_and(|mut snoozes| {
+        snoozes.expire(Instant::now());
+        snoozes.contains(id)
+    })
+}
+
+/// Snoozed modules, oldest first.
+pub fn snoozed() -> Vec<(String, Until)> {
+    state()
+        .lock()
+        .map(|mut snoozes| {
+            snoozes.expire(Instant::now());
+            snoozes.entries.clone()
+        })
+        .unwrap_or_default()
+}
+
+/// When the bar next needs to bring a module back.
+pub fn next_expiry() -> Option<Instant> {
+    state().lock().ok()?.next_expiry()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        let now = Instant::now();
+        assert_eq!(
+            Until::parse("15", now),
+            Some(Until::Time(now + Duration::from_secs(900)))
+        );
+        assert_eq!(Until::parse("reload", now), Some(Until::Reload));
+        assert_eq!(Until::parse("restart", now), Some(Until::Restart));
+        assert_eq!(Until::parse("0", now), None);
+        assert_eq!(Until::parse("soon", now), None);
+    }
+
+    #[test]
+    fn describes_time_left() {
+        let now = Instant::now();
+        let at = |secs| Until::Time(now + Duration::from_secs(secs));
+        assert_eq!(at(30).describe(now), "1m left");
+        assert_eq!(at(14 * 60 + 10).describe(now), "15m left");
+        assert_eq!(at(90 * 60).describe(now), "1h 30m left");
+        assert_eq!(Until::Reload.describe(now), "until reload");
+    }
+
+    #[test]
+    fn expires_restores_and_reloads() {
+        let now = Instant::now();
+        let mut snoozes = Snoozes::default();
+        snoozes.snooze("cpu", Until::Time(now + Duration::from_secs(60)));
+        snoozes.snooze("wifi", Until::Reload);
+        snoozes.snooze("clock", Until::Restart);
+        assert_eq!(snoozes.next_expiry(), Some(now + Duration::from_secs(60)));
+
+        snoozes.expire(now + Duration::from_secs(60));
+        assert!(!snoozes.contains("cpu"));
+        assert_eq!(snoozes.next_expiry(), None);
+
+        snoozes.reload();
+        assert!(!snoozes.contains("wifi"));
+        assert!(snoozes.contains("clock"));
+        assert!(snoozes.restore("clock"));
+        assert!(!snoozes.restore("clock"));
+    }
+}
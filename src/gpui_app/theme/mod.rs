@@ -374,3 +374,57 @@ impl BadgeVariant {
         }
     }
 }
+
+/// Button variant types.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ButtonVariant {
+    /// Transparent until hovered (toolbar/navigation buttons)
+    #[default]
+    Ghost,
+    Default,
+    Outline,
+    Accent,
+    Destructive,
+}
+
+impl ButtonVariant {
+    /// Returns the (background, border, foreground, hover background) colors for this variant.
+    pub fn colors(&self, theme: &Theme) -> (Rgba, Rgba, Rgba, Rgba) {
+        let transparent = rgba(0.0, 0.0, 0.0, 0.0);
+        match self {
+            Self::Ghost => (
+                transparent,
+                transparent,
+                theme.foreground,
+                theme.surface_hover,
+            ),
+            Self::Default => (
+                theme.surface,
+                theme.border,
+                theme.foreground,
+                theme.surface_hover,
+            ),
+            Self::Outline => (
+                transparent,
+                theme.border,
+                theme.foreground,
+                theme.surface_hover,
+            ),
+            Self::Accent => (
+                theme.accent,
+                theme.accent,
+                theme.on_accent,
+                lighten(&theme.accent, 0.1),
+            ),
+            Self::Destructive => {
+                let bg = with_alpha(&theme.destructive, 0.2);
+                (
+                    bg,
+                    theme.destructive,
+                    theme.destructive,
+                    with_alpha(&theme.destructive, 0.3),
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,196 @@
+//! Transient toast messages in the bar.
+//!
+//! `sinew-msg toast "Deployed!" --timeout 5 --style success` shows a short
+//! message next to the modules of the zone set by `bar.toast.zone`. It fades
+//! and slides in, stays for its timeout, then fades out. A new toast replaces
+//! the one on screen. Useful for script feedback without going through
+//! Notification Center.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use gpui::{div, prelude::*, px, Animation, AnimationExt, AnyElement, SharedString, Styled};
+
+use crate::gpui_app::request_immediate_refresh;
+use crate::gpui_app::theme::Theme;
+
+/// Length of the in and out animations.
+const FADE_DURATION: Duration = Duration::from_millis(200);
+
+/// Toast on screen, if any.
+static CURRENT: Mutex<Option<Toast>> = Mutex::new(None);
+static TOAST_COUNTER: AtomicU64 = AtomicU64::new(1);
+/// `bar.toast.timeout` in milliseconds, used when a toast doesn't set one.
+static DEFAULT_TIMEOUT_MS: AtomicU64 = AtomicU64::new(3000);
+
+/// Accent of a toast.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToastStyle {
+    #[default]
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl ToastStyle {
+    /// Parses a style name, returning None for unknown styles.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "info" => Some(Self::Info),
+            "success" => Some(Self::Success),
+            "warning" => Some(Self::Warning),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+/// How far along its lifetime a toast is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastPhase {
+    /// Shown, possibly still animating in
+    Visible,
+    /// Past its timeout and fading out
+    Leaving,
+    /// Gone
+    Expired,
+}
+
+/// Returns the phase of a toast shown `elapsed` ago.
+pub fn phase(elapsed: Duration, timeout: Duration) -> ToastPhase {
+    if elapsed < timeout {
+        ToastPhase::Visible
+    } else if elapsed < timeout + FADE_DURATION {
+        ToastPhase::Leaving
+    } else {
+        ToastPhase::Expired
+    }
+}
+
+/// A message shown in the bar.
+#[derive(Debug, Clone)]
+struct Toast {
+    id: u64,
+    message: String,
+    style: ToastStyle,
+    timeout: Duration,
+    shown_at: Instant,
+}
+
+/// Sets the timeout used by toasts that don't specify one.
+pub fn set_default_timeout(timeout: Duration) {
+    DEFAULT_TIMEOUT_MS.store(timeout.as_millis() as u64, Ordering::Relaxed);
+}
+
+/// Shows a toast in every bar, replacing the current one.
+///
+/// @param message - Text to show
+/// @param style - Accent color of the toast
+/// @param timeout - How long it stays (None = `bar.toast.timeout`)
+pub fn show(message: &str, style: ToastStyle, timeout: Option<Duration>) {
+    let timeout = timeout
+        .unwrap_or_else(|| Duration::from_millis(DEFAULT_TIMEOUT_MS.load(Ordering::Relaxed)));
+    let id = TOAST_COUNTER.fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut current) = CURRENT.lock() {
+        *current = Some(Toast {
+            id,
+            message: message.to_string(),
+            style,
+            timeout,
+            shown_at: Instant::now(),
+        });
+    }
+    request_immediate_refresh();
+
+    // Redraw when the toast starts leaving and once it's gone
+    std::thread::spawn(move || {
+        for wait in [timeout, FADE_DURATION] {
+            std::thread::sleep(wait);
+            let still_shown = CURRENT
+                .lock()
+                .map(|current| current.as_ref().is_some_and(|t| t.id == id))
+                .unwrap_or(false);
+            if !still_shown {
+                return;
+            }
+            request_immediate_refresh();
+        }
+    });
+}
+
+/// Renders the current toast, or None when nothing is shown.
+pub fn render(theme: &Theme) -> Option<AnyElement> {
+    let toast = {
+        let mut current = CURRENT.lock().ok()?;
+        let toast = current.as_ref()?;
+        if phase(toast.shown_at.elapsed(), toast.timeout) == ToastPhase::Expired {
+            *current = None;
+            return None;
+        }
+        toast.clone()
+    };
+
+    let accent = match toast.style {
+        ToastStyle::Info => theme.info,
+        ToastStyle::Success => theme.success,
+        ToastStyle::Warning => theme.warning,
+        ToastStyle::Error => theme.destructive,
+    };
+    let content = div()
+        .flex()
+        .items_center()
+        .gap(px(6.0))
+        .px(px(8.0))
+        .h(px(theme.font_size * 1.6))
+        .rounded(px(4.0))
+        .bg(theme.surface)
+        .text_size(px(theme.font_size))
+        .text_color(theme.foreground)
+        .child(div().size(px(6.0)).rounded_full().bg(accent))
+        .child(SharedString::from(toast.message));
+
+    // Separate ids so each direction starts its animation from scratch
+    let leaving = phase(toast.shown_at.elapsed(), toast.timeout) == ToastPhase::Leaving;
+    let id = if leaving {
+        format!("toast-out-{}", toast.id)
+    } else {
+        format!("toast-in-{}", toast.id)
+    };
+    Some(
+        content
+            .with_animation(
+                SharedString::from(id),
+                Animation::new(FADE_DURATION),
+                move |el, delta| {
+                    let t = if leaving { 1.0 - delta } else { delta };
+                    el.opacity(t).mt(px((1.0 - t) * 8.0))
+                },
+            )
+            .into_any_element(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_toast_styles() {
+        assert_eq!(ToastStyle::parse("success"), Some(ToastStyle::Success));
+        assert_eq!(ToastStyle::parse("error"), Some(ToastStyle::Error));
+        assert_eq!(ToastStyle::parse("loud"), None);
+    }
+
+    #[test]
+    fn toast_leaves_after_timeout() {
+        let timeout = Duration::from_secs(5);
+        assert_eq!(phase(Duration::from_secs(1), timeout), ToastPhase::Visible);
+        assert_eq!(
+            phase(Duration::from_millis(5100), timeout),
+            ToastPhase::Leaving
+        );
+        assert_eq!(phase(Duration::from_secs(6), timeout), ToastPhase::Expired);
+    }
+}
@@ -0,0 +1,383 @@
+//! Hover tooltips of bar modules.
+//!
+//! Resting the pointer on a module for `bar.tooltip.delay_ms` shows its
+//! `GpuiModule::tooltip()` text in a small window under the pointer: the
+//! full track title, a script's last run, the battery's charge in mAh.
+//! Moving on to another module swaps the text right away; leaving the bar
+//! or clicking hides it.
+//!
+//! The window is a fixed-size transparent overlay that ignores the mouse,
+//! so it never steals the hover from the bar; the tooltip box is drawn
+//! inside it, centered on the pointer unless that would leave the screen.
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use async_channel::{Receiver, Sender};
+use futures_util::future::FutureExt;
+use futures_util::{pin_mut, select};
+use gpui::{
+    div, point, prelude::*, px, size, App, AppContext, Bounds, Context, SharedString, Styled,
+    Window, WindowBounds, WindowKind, WindowOptions,
+};
+use objc2::MainThreadMarker;
+use objc2_app_kit::NSEvent;
+
+use crate::config::TooltipConfig;
+use crate::gpui_app::theme::Theme;
+use crate::gpui_app::window_registry::{self, WindowRole};
+
+/// Window size; the tooltip box is at most this large.
+const TOOLTIP_WIDTH: f64 = 360.0;
+const TOOLTIP_HEIGHT: f64 = 200.0;
+/// Space between the bar and the tooltip.
+const GAP: f64 = 4.0;
+/// Above the menu bar, like the HUD.
+const TOOLTIP_WINDOW_LEVEL: i64 = 25;
+
+/// Sender feeding hover changes to the tooltip view.
+static TOOLTIP_TX: OnceLock<Sender<Request>> = OnceLock::new();
+
+enum Request {
+    /// The pointer entered module `id`, which has `text` to show
+    Enter { id: String, text: String },
+    /// The pointer left module `id` (None: hide whatever is shown)
+    Leave { id: Option<String> },
+}
+
+/// Where the tooltip box sits in its window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Align {
+    #[default]
+    Center,
+    /// Pushed against the left screen edge
+    Start,
+    /// Pushed against the right screen edge
+    End,
+}
+
+/// Window x for a tooltip under `anchor_x`, kept on the screen spanning
+/// `screen_x..screen_x + screen_width`, and where the box sits in it.
+pub fn place(anchor_x: f64, screen_x: f64, screen_width: f64) -> (f64, Align) {
+    let x = anchor_x - TOOLTIP_WIDTH / 2.0;
+    if x < screen_x {
+        (screen_x, Align::Start)
+    } else if x + TOOLTIP_WIDTH > screen_x + screen_width {
+        (screen_x + screen_width - TOOLTIP_WIDTH, Align::End)
+    } else {
+        (x, Align::Center)
+    }
+}
+
+/// Hover state: which module the pointer is on and whether its tooltip is
+/// up or waiting for the delay.
+#[derive(Debug, Default)]
+struct Hover {
+    id: Option<String>,
+    text: String,
+    shown: bool,
+    /// When the pending tooltip shows
+    due: Option<Instant>,
+}
+
+impl Hover {
+    /// The pointer entered module `id`. Returns true if the tooltip is
+    /// already up and should switch to `text` now.
+    fn enter(&mut self, id: String, text: String, now: Instant, delay: Duration) -> bool {
+        self.id = Some(id);
+        self.text = text;
+        if self.shown {
+            self.due = None;
+        } else {
+            self.due = Some(now + delay);
+        }
+        self.shown
+    }
+
+    /// The pointer left module `id` (None for any). Leaving a module the
+    /// pointer already moved on from changes nothing. Returns true if the
+    /// tooltip was up and should hide.
+    fn leave(&mut self, id: Option<&str>) -> bool {
+        if id.is_some() && id != self.id.as_deref() {
+            return false;
+        }
+        self.id = None;
+        self.due = None;
+        std::mem::take(&mut self.shown)
+    }
+
+    /// The delay is up: returns the text to show.
+    fn fire(&mut self) -> String {
+        self.due = None;
+        self.shown = true;
+        self.text.clone()
+    }
+}
+
+/// Schedules the tooltip of module `id` (no-op without text or with
+/// tooltips off).
+pub fn enter(id: &str, text: Option<String>) {
+    let (Some(tx), Some(text)) = (TOOLTIP_TX.get(), text) else {
+        return;
+    };
+    let _ = tx.try_send(Request::Enter {
+        id: id.to_string(),
+        text,
+    });
+}
+
+/// Hides the tooltip of module `id`, if it's the one shown or pending.
+pub fn leave(id: &str) {
+    if let Some(tx) = TOOLTIP_TX.get() {
+        let _ = tx.try_send(Request::Leave {
+            id: Some(id.to_string()),
+        });
+    }
+}
+
+/// Hides the tooltip, e.g. on a click.
+pub fn dismiss() {
+    if let Some(tx) = TOOLTIP_TX.get() {
+        let _ = tx.try_send(Request::Leave { id: None });
+    }
+}
+
+/// Window x and box alignment for a tooltip under the pointer, with the
+/// bar's bottom edge. None without a bar window.
+fn anchor(mtm: MainThreadMarker) -> Option<(f64, Align, f64)> {
+    let bar = window_registry::window(mtm, &WindowRole::Bar)?.frame();
+    let pointer = NSEvent::mouseLocation();
+    let (x, align) = place(pointer.x, bar.origin.x, bar.size.width);
+    Some((x, align, bar.origin.y))
+}
+
+/// Moves the tooltip window under the bar at `x` and shows it.
+fn show_window(mtm: MainThreadMarker, x: f64, bar_y: f64) {
+    use objc2_foundation::{NSPoint, NSRect, NSSize};
+
+    if let Some(ns_window) = window_registry::window(mtm, &WindowRole::Tooltip) {
+        let y = bar_y - GAP - TOOLTIP_HEIGHT;
+        ns_window.setFrame_display(
+            NSRect::new(
+                NSPoint::new(x, y),
+                NSSize::new(TOOLTIP_WIDTH, TOOLTIP_HEIGHT),
+            ),
+            false,
+        );
+        ns_window.orderFrontRegardless();
+    }
+}
+
+fn hide_window() {
+    let Some(mtm) = MainThreadMarker::new() else {
+        return;
+    };
+    if let Some(ns_window) = window_registry::window(mtm, &WindowRole::Tooltip) {
+        ns_window.orderOut(None);
+    }
+}
+
+/// View drawn inside the tooltip window.
+pub struct TooltipView {
+    theme: Theme,
+    text: SharedString,
+    align: Align,
+}
+
+impl TooltipView {
+    fn new(theme: Theme, delay: Duration, rx: Receiver<Request>, cx: &mut Context<Self>) -> Self {
+        cx.spawn(async move |this, cx| {
+            let mut hover = Hover::default();
+            loop {
+                // Wait for the next hover change, or for the delay to run out
+                let request = match hover.due {
+                    None => match rx.recv().await {
+                        Ok(request) => Some(request),
+                        Err(_) => return,
+                    },
+                    Some(due) => {
+                        let next = rx.recv().fuse();
+                        let timer = cx
+                            .background_executor()
+                            .timer(due.saturating_duration_since(Instant::now()))
+                            .fuse();
+                        pin_mut!(next, timer);
+                        select! {
+                            request = next => match request {
+                                Ok(request) => Some(request),
+                                Err(_) => return,
+                            },
+                            _ = timer => None,
+                        }
+                    }
+                };
+
+                let text = match request {
+                    None => hover.fire(),
+                    Some(Request::Enter { id, text }) => {
+                        if !hover.enter(id, text, Instant::now(), delay) {
+                            continue;
+                        }
+                        hover.text.clone()
+                    }
+                    Some(Request::Leave { id }) => {
+                        if hover.leave(id.as_deref()) {
+                            hide_window();
+                        }
+                        continue;
+                    }
+                };
+
+                let Some(mtm) = MainThreadMarker::new() else {
+                    continue;
+                };
+                let Some((x, align, bar_y)) = anchor(mtm) else {
+                    continue;
+                };
+                let _ = this.update(cx, |view, cx| {
+                    view.text = SharedString::from(text);
+                    view.align = align;
+                    cx.notify();
+                });
+                show_window(mtm, x, bar_y);
+            }
+        })
+        .detach();
+
+        Self {
+            theme,
+            text: SharedString::default(),
+            align: Align::default(),
+        }
+    }
+}
+
+impl Render for TooltipView {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        let lines = self
+            .text
+            .lines()
+            .map(|line| div().child(SharedString::from(line.to_string())));
+        let tooltip = div()
+            .max_w(px(TOOLTIP_WIDTH as f32))
+            .max_h(px(TOOLTIP_HEIGHT as f32))
+            .overflow_hidden()
+            .flex()
+            .flex_col()
+            .px(px(8.0))
+            .py(px(5.0))
+            .rounded(px(6.0))
+            .bg(self.theme.background)
+            .border_1()
+            .border_color(self.theme.border)
+            .text_size(px(self.theme.font_size - 1.0))
+            .text_color(self.theme.foreground)
+            .children(lines);
+
+        let container = div().size_full().flex().flex_col();
+        match self.align {
+            Align::Center => container.items_center(),
+            Align::Start => container.items_start(),
+            Align::End => container.items_end(),
+        }
+        .child(tooltip)
+    }
+}
+
+/// Creates the (hidden) tooltip window.
+pub fn init(cx: &mut App, config: &TooltipConfig, theme: Theme) {
+    let (tx, rx) = async_channel::unbounded();
+    if TOOLTIP_TX.set(tx).is_err() {
+        return; // Already initialized
+    }
+
+    let delay = Duration::from_millis(config.delay_ms);
+    let bounds = Bounds {
+        origin: point(px(0.0), px(0.0)),
+        size: size(px(TOOLTIP_WIDTH as f32), px(TOOLTIP_HEIGHT as f32)),
+    };
+    let before = window_registry::window_numbers();
+    let result = cx.open_window(
+        WindowOptions {
+            window_bounds: Some(WindowBounds::Windowed(bounds)),
+            titlebar: None,
+            kind: WindowKind::PopUp,
+            is_movable: false,
+            focus: false,
+            show: false,
+            window_background: gpui::WindowBackgroundAppearance::Transparent,
+            ..Default::default()
+        },
+        |_window, cx| cx.new(|cx| TooltipView::new(theme, delay, rx, cx)),
+    );
+    if let Err(err) = result {
+        log::warn!("Failed to create tooltip window: {}", err);
+        return;
+    }
+    window_registry::tag_new(&before, WindowRole::Tooltip);
+
+    // Configure the NSWindow on the next run-loop turn, like the HUD
+    let block = block2::RcBlock::new(|| {
+        if let Some(mtm) = MainThreadMarker::new() {
+            configure_window(mtm);
+        }
+    });
+    unsafe {
+        objc2_foundation::NSRunLoop::mainRunLoop().performBlock(&block);
+    }
+}
+
+/// Makes the tooltip window a click-through overlay.
+fn configure_window(mtm: MainThreadMarker) {
+    use objc2_app_kit::NSWindowStyleMask;
+
+    let Some(ns_window) = window_registry::window(mtm, &WindowRole::Tooltip) else {
+        log::warn!("Tooltip window not found");
+        return;
+    };
+    unsafe {
+        ns_window.setStyleMask(NSWindowStyleMask::Borderless);
+        let _: () = objc2::msg_send![&ns_window, setLevel: TOOLTIP_WINDOW_LEVEL];
+        ns_window.setHasShadow(false);
+        ns_window.setOpaque(false);
+        ns_window.setIgnoresMouseEvents(true);
+        ns_window.orderOut(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn places_tooltips_on_screen() {
+        assert_eq!(place(800.0, 0.0, 1512.0), (620.0, Align::Center));
+        assert_eq!(place(40.0, 0.0, 1512.0), (0.0, Align::Start));
+        assert_eq!(place(1500.0, 0.0, 1512.0), (1152.0, Align::End));
+        // Second display to the right of the first
+        assert_eq!(place(1540.0, 1512.0, 1920.0), (1512.0, Align::Start));
+    }
+
+    #[test]
+    fn waits_for_the_delay_then_follows_the_pointer() {
+        let now = Instant::now();
+        let delay = Duration::from_millis(600);
+        let mut hover = Hover::default();
+
+        assert!(!hover.enter("cpu".into(), "CPU".into(), now, delay));
+        assert_eq!(hover.due, Some(now + delay));
+        assert_eq!(hover.fire(), "CPU");
+
+        // Already up: the next module's text shows right away
+        assert!(hover.enter("disk".into(), "Disk".into(), now, delay));
+        assert_eq!(hover.due, None);
+        // The pointer left cpu after entering disk
+        assert!(!hover.leave(Some("cpu")));
+        assert!(hover.leave(Some("disk")));
+
+        // Leaving before the delay cancels the pending tooltip
+        hover.enter("cpu".into(), "CPU".into(), now, delay);
+        assert!(!hover.leave(None));
+        assert_eq!(hover.due, None);
+    }
+}
@@ -0,0 +1,136 @@
+//! UI state kept across restarts and config reloads.
+//!
+//! Which `toggle = true` modules are switched on and which popups are
+//! detached into windows of their own is saved to
+//! `~/.local/state/sinew/ui_state.json` whenever it changes, and restored
+//! when the modules are built. Modules that own their toggle state (like
+//! `timetrack`, which saves its running timer) aren't tracked here.
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+/// Saved UI state.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UiState {
+    /// Ids of toggle modules that are on
+    #[serde(default)]
+    pub toggled: BTreeSet<String>,
+    /// Ids of modules whose popup is detached, oldest first
+    #[serde(default)]
+    pub detached: Vec<String>,
+}
+
+impl UiState {
+    /// Records a module's toggle state. Returns true if it changed.
+    pub fn set_toggled(&mut self, id: &str, active: bool) -> bool {
+        if active {
+            self.toggled.insert(id.to_string())
+        } else {
+            self.toggled.remove(id)
+        }
+    }
+
+    /// Records the detached popups. Returns true if they changed.
+    pub fn set_detached(&mut self, ids: &[String]) -> bool {
+        if self.detached == ids {
+            return false;
+        }
+        self.detached = ids.to_vec();
+        true
+    }
+
+    fn path() -> PathBuf {
+        crate::config::get_state_dir().join("ui_state.json")
+    }
+
+    fn load() -> Self {
+        std::fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let result = serde_json::to_string_pretty(self)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+        if let Err(err) = result {
+            log::warn!("Failed to save UI state: {}", err);
+        }
+    }
+}
+
+fn state() -> &'static Mutex<UiState> {
+    static STATE: OnceLock<Mutex<UiState>> = OnceLock::new();
+    STATE.get_or_init(|| Mutex::new(UiState::load()))
+}
+
+/// Whether toggle module `id` was left on.
+pub fn is_toggled(id: &str) -> bool {
+    state().lock().is_ok_and(|state| state.toggled.contains(id))
+}
+
+/// Saves toggle module `id`'s state.
+pub fn set_toggled(id: &str, active: bool) {
+    if let Ok(mut state) = state().lock() {
+        if state.set_toggled(id, active) {
+            state.save();
+        }
+    }
+}
+
+/// Modules whose popup was left detached.
+pub fn detached() -> Vec<String> {
+    state()
+        .lock()
+        .map(|state| state.detached.clone())
+        .unwrap_or_default()
+}
+
+/// Saves the detached popups.
+pub fn set_detached(ids: &[String]) {
+    if let Ok(mut state) = state().lock() {
+        if state.set_detached(ids) {
+            state.save();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_changes() {
+        let mut state = UiState::default();
+        assert!(state.set_toggled("wifi", true));
+        assert!(!state.set_toggled("wifi", true));
+        assert!(state.set_toggled("wifi", false));
+        assert!(!state.set_toggled("wifi", false));
+
+        let ids = vec!["cpu".to_string()];
+        assert!(state.set_detached(&ids));
+        assert!(!state.set_detached(&ids));
+        assert!(state.set_detached(&[]));
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let mut state = UiState::default();
+        state.set_toggled("focus", true);
+        state.set_detached(&["sysmon".to_string()]);
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(serde_json::from_str::<UiState>(&json).unwrap(), state);
+        assert_eq!(
+            serde_json::from_str::<UiState>("{}").unwrap(),
+            UiState::default()
+        );
+    }
+}
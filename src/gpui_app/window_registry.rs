@@ -0,0 +1,144 @@
+//! Registry of Sinew's AppKit windows by role.
+//!
+//! GPUI doesn't hand out the `NSWindow` it creates, so each window is
+//! tagged right after `open_window` by diffing the app's window numbers
+//! around the call. Everything that needs a specific window (positioning
+//! popups, click-outside checks, showing the bar) looks it up here instead
+//! of guessing its role from the frame size.
+
+use std::sync::Mutex;
+
+use objc2::rc::Retained;
+use objc2::MainThreadMarker;
+use objc2_app_kit::{NSApplication, NSWindow};
+
+/// What a window is used for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowRole {
+    Bar,
+    /// Shared full-width panel window
+    Panel,
+    /// Shared small popup window
+    Popup,
+    /// Pinned popup moved into a window of its own, by module id
+    Detached(String),
+    /// Volume/brightness overlay under the notch
+    Hud,
+    /// Hover tooltip under a module
+    Tooltip,
+}
+
+impl WindowRole {
+    /// Whether the window hosts popup content.
+    pub fn is_popup(&self) -> bool {
+        matches!(self, Self::Panel | Self::Popup | Self::Detached(_))
+    }
+}
+
+/// Tagged windows as (AppKit window number, role).
+static WINDOWS: Mutex<Vec<(i64, WindowRole)>> = Mutex::new(Vec::new());
+
+/// Tags window `number` with `role`. A role belongs to one window, so any
+/// earlier window with the same role is untagged.
+pub fn tag(number: i64, role: WindowRole) {
+    if let Ok(mut windows) = WINDOWS.lock() {
+        windows.retain(|(n, r)| *n != number && *r != role);
+        log::debug!("window_registry: {} -> {:?}", number, role);
+        windows.push((number, role));
+    }
+}
+
+/// Removes the window tagged with `role`.
+pub fn untag(role: &WindowRole) {
+    if let Ok(mut windows) = WINDOWS.lock() {
+        windows.retain(|(_, r)| r != role);
+    }
+}
+
+/// Window number tagged with `role`.
+pub fn number(role: &WindowRole) -> Option<i64> {
+    WINDOWS
+        .lock()
+        .ok()?
+        .iter()
+        .find(|(_, r)| r == role)
+        .map(|(n, _)| *n)
+}
+
+/// Role of window `number`, if it's one of ours.
+pub fn role(number: i64) -> Option<WindowRole> {
+    WINDOWS
+        .lock()
+        .ok()?
+        .iter()
+        .find(|(n, _)| *n == number)
+        .map(|(_, r)| r.clone())
+}
+
+/// The window tagged with `role`.
+pub fn window(mtm: MainThreadMarker, role: &WindowRole) -> Option<Retained<NSWindow>> {
+    let number = number(role)?;
+    NSApplication::sharedApplication(mtm).windowWithWindowNumber(number as isize)
+}
+
+/// Numbers of all the app's windows, for passing to [`tag_new`] after
+/// opening a window. Empty off the main thread.
+pub fn window_numbers() -> Vec<i64> {
+    let Some(mtm) = MainThreadMarker::new() else {
+        return Vec::new();
+    };
+    let windows = NSApplication::sharedApplication(mtm).windows();
+    (0..windows.len())
+        .map(|i| windows.objectAtIndex(i).windowNumber() as i64)
+        .collect()
+}
+
+/// Tags the window that appeared since `before` was taken.
+pub fn tag_new(before: &[i64], role: WindowRole) -> Option<i64> {
+    let Some(number) = new_number(before, &window_numbers()) else {
+        log::warn!("window_registry: no new window to tag as {:?}", role);
+        return None;
+    };
+    tag(number, role);
+    Some(number)
+}
+
+/// The newest window number in `after` that isn't in `before`.
+fn new_number(before: &[i64], after: &[i64]) -> Option<i64> {
+    after
+        .iter()
+        .rev()
+        .copied()
+        .find(|n| *n > 0 && !before.contains(n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_window_that_appeared() {
+        assert_eq!(new_number(&[3, 5], &[3, 5, 9]), Some(9));
+        assert_eq!(new_number(&[3, 5], &[3, 5]), None);
+        // Windows without a window device have no number yet
+        assert_eq!(new_number(&[3], &[3, 0]), None);
+    }
+
+    #[test]
+    fn roles_are_unique_per_window() {
+        tag(10, WindowRole::Panel);
+        tag(11, WindowRole::Detached("calendar".into()));
+        assert_eq!(number(&WindowRole::Panel), Some(10));
+        assert_eq!(role(11), Some(WindowRole::Detached("calendar".into())));
+
+        // Re-tagging a role moves it to the new window
+        tag(12, WindowRole::Panel);
+        assert_eq!(number(&WindowRole::Panel), Some(12));
+        assert_eq!(role(10), None);
+
+        untag(&WindowRole::Detached("calendar".into()));
+        assert_eq!(role(11), None);
+        assert!(WindowRole::Panel.is_popup());
+        assert!(!WindowRole::Bar.is_popup());
+    }
+}
@@ -0,0 +1,616 @@
+//! Opt-in HTTP control API (`[bar.http]`).
+//!
+//! Mirrors the IPC verbs for clients that can't talk to the Unix socket,
+//! like Stream Deck and Keyboard Maestro actions or scripts on another
+//! machine. Every request needs `Authorization: Bearer <token>`. Bodies and
+//! responses are JSON; responses are `{"ok": true, "result": ...}` or
+//! `{"ok": false, "error": "..."}`.
+//!
+//! | Request                      | Body                                |
+//! |------------------------------|-------------------------------------|
+//! | `POST /reload`               |                                     |
+//! | `GET /status`                |                                     |
+//! | `GET /modules`               |                                     |
+//! | `GET /modules/<id>`          |                                     |
+//! | `POST /modules/<id>/set`     | `{"label": "...", ...}`             |
+//! | `POST /modules/<id>/trigger` | `{"event": "update"}`               |
+//! | `POST /modules/<id>/popup`   |                                     |
+//! | `POST /toast`                | `{"message": "...", "style": ...}`  |
+//! | `POST /notify/<item>`        | `{"text": "...", "color": ...}`     |
+//! | `DELETE /notify/<item>`      |                                     |
+//! | `POST /command`              | `{"command": "progress build 40%"}` |
+//!
+//! Connections are served one at a time, like the socket listener. The
+//! token and `enabled` apply on config reload; a new address or port needs
+//! a restart.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde_json::{json, Map, Value};
+
+use crate::config::HttpConfig;
+use crate::gpui_app::notify::{self, ItemColor};
+use crate::gpui_app::toast::{self, ToastStyle};
+use crate::ipc;
+
+/// Largest request body accepted.
+const MAX_BODY: usize = 64 * 1024;
+
+/// Longest request or header line accepted.
+const MAX_LINE: u64 = 8 * 1024;
+
+/// How long a client gets to send each part of its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Config the listener checks on every request; replaced on reload.
+static CONFIG: Mutex<Option<HttpConfig>> = Mutex::new(None);
+
+/// Address the listener is bound to, once started.
+static BOUND: Mutex<Option<SocketAddr>> = Mutex::new(None);
+
+/// Applies `[bar.http]`, starting the listener the first time it's enabled.
+pub fn sync(config: &HttpConfig) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = Some(config.clone());
+    }
+    // Validation reports both of these
+    if !config.enabled || config.token.is_empty() {
+        return;
+    }
+    let Ok(ip) = config.address.parse::<IpAddr>() else {
+        return;
+    };
+    let addr = SocketAddr::new(ip, config.port);
+
+    let Ok(mut bound) = BOUND.lock() else {
+        return;
+    };
+    match *bound {
+        Some(current) if current == addr => {}
+        Some(current) => log::warn!(
+            "HTTP API: still listening on {}, restart sinew to move it to {}",
+            current,
+            addr
+        ),
+        None => match TcpListener::bind(addr) {
+            Ok(listener) => {
+                log::info!("HTTP API listening on {}", addr);
+                *bound = Some(addr);
+                std::thread::spawn(move || serve(listener));
+            }
+            Err(err) => log::error!("HTTP API: couldn't listen on {}: {}", addr, err),
+        },
+    }
+}
+
+fn serve(listener: TcpListener) {
+    for stream in listener.incoming().flatten() {
+        let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+        let response = match read_request(&mut BufReader::new(&stream)) {
+            Ok(request) => respond(&request),
+            Err(response) => response,
+        };
+        if let Err(err) = write_response(&stream, &response) {
+            log::debug!("HTTP API: couldn't send response: {}", err);
+        }
+    }
+}
+
+/// A parsed request; only the parts the API looks at.
+#[derive(Debug)]
+struct Request {
+    method: String,
+    /// Path without the query string
+    path: String,
+    authorization: Option<String>,
+    body: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Response {
+    status: u16,
+    body: Value,
+}
+
+fn ok(result: Value) -> Response {
+    Response {
+        status: 200,
+        body: json!({ "ok": true, "result": result }),
+    }
+}
+
+fn error(status: u16, message: impl Into<String>) -> Response {
+    Response {
+        status,
+        body: json!({ "ok": false, "error": message.into() }),
+    }
+}
+
+/// Reads one line, without its line ending.
+fn read_line(reader: &mut impl BufRead) -> Result<String, Response> {
+    let mut line = String::new();
+    reader
+        .by_ref()
+        .take(MAX_LINE)
+        .read_line(&mut line)
+        .map_err(|_| error(400, "couldn't read request"))?;
+    if !line.ends_with('\n') {
+        return Err(error(400, "request line too long or cut off"));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn read_request(reader: &mut impl BufRead) -> Result<Request, Response> {
+    let line = read_line(reader)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target), Some(_version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(error(400, "malformed request line"));
+    };
+    let path = target.split('?').next().unwrap_or("").to_string();
+
+    let mut authorization = None;
+    let mut content_length = 0;
+    loop {
+        let header = read_line(reader)?;
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(error(400, "malformed header"));
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("authorization") {
+            authorization = Some(value.to_string());
+        } else if name.eq_ignore_ascii_case("content-length") {
+            content_length = value
+                .parse::<usize>()
+                .map_err(|_| error(400, "invalid Content-Length"))?;
+        }
+    }
+
+    if content_length > MAX_BODY {
+        return Err(error(413, format!("body over {} bytes", MAX_BODY)));
+    }
+    let mut body = vec![0; content_length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|_| error(400, "body shorter than Content-Length"))?;
+
+    Ok(Request {
+        method: method.to_string(),
+        path,
+        authorization,
+        body,
+    })
+}
+
+fn write_response(mut stream: impl Write, response: &Response) -> std::io::Result<()> {
+    let body = response.body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason(response.status),
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        503 => "Service Unavailable",
+        _ => "Error",
+    }
+}
+
+/// Checks the bearer token, comparing in constant time.
+fn authorized(header: Option<&str>, token: &str) -> bool {
+    let Some(given) = header.and_then(|h| h.strip_prefix("Bearer ")) else {
+        return false;
+    };
+    !token.is_empty()
+        && given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn respond(request: &Request) -> Response {
+    let config = CONFIG.lock().ok().and_then(|config| config.clone());
+    let Some(config) = config.filter(|config| config.enabled) else {
+        return error(503, "the HTTP API is disabled");
+    };
+    if !authorized(request.authorization.as_deref(), &config.token) {
+        return error(401, "missing or wrong bearer token");
+    }
+    route(request)
+}
+
+fn route(request: &Request) -> Response {
+    let segments: Vec<String> = request
+        .path
+        .trim_matches('/')
+        .split('/')
+        .map(percent_decode)
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+    match segments.as_slice() {
+        ["reload"] => expect(request, "POST", || {
+            from_ipc(ipc::handle_ipc_command("reload"))
+        }),
+        ["status"] => expect(request, "GET", || {
+            from_ipc(ipc::handle_ipc_command("status"))
+        }),
+        ["modules"] => expect(request, "GET", || from_ipc(ipc::handle_ipc_command("list"))),
+        ["modules", id] => expect(request, "GET", || get_module(id)),
+        ["modules", id, "set"] => expect(request, "POST", || set(id, &request.body)),
+        ["modules", id, "trigger"] => expect(request, "POST", || trigger(id, &request.body)),
+        ["modules", id, "popup"] => expect(request, "POST", || {
+            ipc::trigger_module(id, "popup");
+            ok(Value::Null)
+        }),
+        ["toast"] => expect(request, "POST", || show_toast(&request.body)),
+        ["notify", name] => match request.method.as_str() {
+            "POST" => set_notify(name, &request.body),
+            "DELETE" if notify::remove(name) => ok(Value::Null),
+            "DELETE" => error(404, format!("notify item '{}' not found", name)),
+            _ => error(405, format!("use POST or DELETE for {}", request.path)),
+        },
+        ["command"] => expect(request, "POST", || command(&request.body)),
+        _ => error(404, format!("no such endpoint '{}'", request.path)),
+    }
+}
+
+/// Runs `handler` if the request uses `method`.
+fn expect(request: &Request, method: &str, handler: impl FnOnce() -> Response) -> Response {
+    if request.method == method {
+        handler()
+    } else {
+        error(405, format!("use {} for {}", method, request.path))
+    }
+}
+
+/// Turns a socket response (`OK`, `ERR: ...`, JSON or text) into JSON.
+fn from_ipc(response: String) -> Response {
+    if let Some(message) = response.strip_prefix("ERR: ") {
+        return error(400, message);
+    }
+    if response == "OK" || response.starts_with("OK:") {
+        return ok(Value::Null);
+    }
+    ok(serde_json::from_str(&response).unwrap_or(Value::String(response)))
+}
+
+/// Parses a JSON object body; an empty body is an empty object.
+fn json_body(body: &[u8]) -> Result<Map<String, Value>, Response> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(Map::new());
+    }
+    match serde_json::from_slice(body) {
+        Ok(Value::Object(object)) => Ok(object),
+        Ok(_) => Err(error(400, "body must be a JSON object")),
+        Err(err) => Err(error(400, format!("invalid JSON: {}", err))),
+    }
+}
+
+fn get_module(id: &str) -> Response {
+    match ipc::external_properties(id) {
+        Some(properties) => ok(properties
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), Value::String(value)))
+            .collect::<Map<_, _>>()
+            .into()),
+        None => error(404, format!("module '{}' not found or not external", id)),
+    }
+}
+
+/// `{"label": "...", "color": "#ff0000", ...}`; numbers and booleans are
+/// passed as their text.
+fn set(id: &str, body: &[u8]) -> Response {
+    let object = match json_body(body) {
+        Ok(object) => object,
+        Err(response) => return response,
+    };
+    let mut properties = Vec::new();
+    for (key, value) in object {
+        let value = match value {
+            Value::String(value) => value,
+            Value::Number(value) => value.to_string(),
+            Value::Bool(value) => value.to_string(),
+            _ => return error(400, format!("property '{}' must be a string", key)),
+        };
+        properties.push((key, value));
+    }
+    if properties.is_empty() {
+        return error(400, "set requires at least one property");
+    }
+    ipc::set_module_properties(id, properties);
+    ok(Value::Null)
+}
+
+/// `{"event": "update"|"popup"|"toggle"}`
+fn trigger(id: &str, body: &[u8]) -> Response {
+    let object = match json_body(body) {
+        Ok(object) => object,
+        Err(response) => return response,
+    };
+    let Some(event) = object.get("event").and_then(Value::as_str) else {
+        return error(400, "trigger requires \"event\"");
+    };
+    if !ipc::TRIGGER_EVENTS.contains(&event) {
+        return error(
+            400,
+            format!(
+                "unknown event '{}', expected one of: {}",
+                event,
+                ipc::TRIGGER_EVENTS.join(", ")
+            ),
+        );
+    }
+    ipc::trigger_module(id, event);
+    ok(Value::Null)
+}
+
+/// `{"message": "...", "style": "success", "timeout": 5}`
+fn show_toast(body: &[u8]) -> Response {
+    let object = match json_body(body) {
+        Ok(object) => object,
+        Err(response) => return response,
+    };
+    let Some(message) = object
+        .get("message")
+        .and_then(Value::as_str)
+        .filter(|message| !message.is_empty())
+    else {
+        return error(400, "toast requires \"message\"");
+    };
+    let style = match object.get("style") {
+        None => ToastStyle::default(),
+        Some(style) => match style.as_str().and_then(ToastStyle::parse) {
+            Some(style) => style,
+            None => {
+                return error(
+                    400,
+                    "unknown style, expected one of: info, success, warning, error",
+                )
+            }
+        },
+    };
+    let timeout = match object.get("timeout") {
+        None => None,
+        Some(timeout) => match timeout.as_f64().filter(|s| s.is_finite() && *s > 0.0) {
+            Some(seconds) => Some(Duration::from_secs_f64(seconds)),
+            None => return error(400, "timeout must be a positive number of seconds"),
+        },
+    };
+    toast::show(message, style, timeout);
+    ok(Value::Null)
+}
+
+/// `{"text": "...", "color": "success", "ttl": 60}`
+fn set_notify(name: &str, body: &[u8]) -> Response {
+    let object = match json_body(body) {
+        Ok(object) => object,
+        Err(response) => return response,
+    };
+    let Some(text) = object
+        .get("text")
+        .and_then(Value::as_str)
+        .filter(|text| !text.is_empty())
+    else {
+        return error(400, "notify requires \"text\"");
+    };
+    let color = match object.get("color") {
+        None => None,
+        Some(color) => match color.as_str().and_then(ItemColor::parse) {
+            Some(color) => Some(color),
+            None => {
+                return error(
+                    400,
+                    "color must be a hex color or info, success, warning, error",
+                )
+            }
+        },
+    };
+    let ttl = match object.get("ttl") {
+        None => None,
+        Some(ttl) => match ttl.as_f64().filter(|s| s.is_finite() && *s >= 0.0) {
+            Some(seconds) => Some(Duration::from_secs_f64(seconds)),
+            None => return error(400, "ttl must be a number of seconds, 0 or more"),
+        },
+    };
+    notify::set(name, text, color, ttl);
+    ok(Value::Null)
+}
+
+/// `{"command": "..."}`, run as if sent over the socket.
+fn command(body: &[u8]) -> Response {
+    let object = match json_body(body) {
+        Ok(object) => object,
+        Err(response) => return response,
+    };
+    match object.get("command").and_then(Value::as_str) {
+        Some(command) => from_ipc(ipc::handle_ipc_command(command)),
+        None => error(400, "command requires \"command\""),
+    }
+}
+
+/// Decodes `%XX` escapes in a path segment; invalid ones are kept as-is.
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| segment.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn request(method: &str, path: &str, body: &str) -> Request {
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            authorization: None,
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn reads_requests() {
+        let raw = "POST /modules/deploy/set?x=1 HTTP/1.1\r\nHost: localhost\r\nauthorization: Bearer abc\r\nContent-Length: 17\r\n\r\n{\"label\": \"done\"}";
+        let request = read_request(&mut Cursor::new(raw)).unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/modules/deploy/set");
+        assert_eq!(request.authorization.as_deref(), Some("Bearer abc"));
+        assert_eq!(request.body, b"{\"label\": \"done\"}");
+
+        let request = read_request(&mut Cursor::new("GET /status HTTP/1.1\n\n")).unwrap();
+        assert!(request.body.is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_requests() {
+        let status = |raw: &str| {
+            read_request(&mut Cursor::new(raw.to_string()))
+                .unwrap_err()
+                .status
+        };
+        assert_eq!(status("GET\r\n\r\n"), 400);
+        assert_eq!(status("GET / HTTP/1.1\r\nno colon\r\n\r\n"), 400);
+        assert_eq!(
+            status("POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort"),
+            400
+        );
+        assert_eq!(
+            status("POST / HTTP/1.1\r\nContent-Length: 999999\r\n\r\n"),
+            413
+        );
+        assert_eq!(
+            status(&format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(9000))),
+            400
+        );
+    }
+
+    #[test]
+    fn checks_bearer_token() {
+        assert!(authorized(Some("Bearer s3cret"), "s3cret"));
+        assert!(!authorized(Some("Bearer s3cre"), "s3cret"));
+        assert!(!authorized(Some("Bearer wrong!"), "s3cret"));
+        assert!(!authorized(Some("s3cret"), "s3cret"));
+        assert!(!authorized(None, "s3cret"));
+        assert!(!authorized(Some("Bearer "), ""));
+    }
+
+    #[test]
+    fn routes_by_path_and_method() {
+        assert_eq!(route(&request("GET", "/nope", "")).status, 404);
+        assert_eq!(route(&request("GET", "/reload", "")).status, 405);
+        assert_eq!(route(&request("POST", "/modules", "")).status, 405);
+        assert_eq!(
+            route(&request("GET", "/modules/missing-xyz", "")).status,
+            404
+        );
+
+        let status = route(&request("GET", "/status", ""));
+        assert_eq!(status.status, 200);
+        assert_eq!(status.body["result"]["running"], true);
+    }
+
+    #[test]
+    fn validates_bodies() {
+        let status = |path: &str, body: &str| route(&request("POST", path, body)).status;
+        assert_eq!(status("/modules/m/set", "[1]"), 400);
+        assert_eq!(status("/modules/m/set", "{oops"), 400);
+        assert_eq!(status("/modules/m/set", "{}"), 400);
+        assert_eq!(status("/modules/m/set", r#"{"label": ["a"]}"#), 400);
+        assert_eq!(
+            status("/modules/m/set", r#"{"label": "hi", "drawing": true}"#),
+            200
+        );
+        assert_eq!(status("/modules/m/trigger", r#"{"event": "explode"}"#), 400);
+        assert_eq!(status("/modules/m/trigger", ""), 400);
+        assert_eq!(status("/toast", r#"{"message": ""}"#), 400);
+        assert_eq!(
+            status("/toast", r#"{"message": "hi", "style": "loud"}"#),
+            400
+        );
+        assert_eq!(status("/toast", r#"{"message": "hi", "timeout": -1}"#), 400);
+        assert_eq!(status("/command", r#"{"command": "frobnicate"}"#), 400);
+        assert_eq!(status("/notify/ci", r#"{"color": "success"}"#), 400);
+        assert_eq!(
+            status("/notify/ci", r#"{"text": "ok", "color": "loud"}"#),
+            400
+        );
+        assert_eq!(
+            status("/notify/ci", r#"{"text": "ok", "ttl": "soon"}"#),
+            400
+        );
+    }
+
+    #[test]
+    fn sets_and_removes_notify_items() {
+        let notify =
+            |method: &str, body: &str| route(&request(method, "/notify/test-http-ci", body)).status;
+        assert_eq!(
+            notify(
+                "POST",
+                r##"{"text": "passed", "color": "#00ff00", "ttl": 0}"##
+            ),
+            200
+        );
+        assert_eq!(notify("DELETE", ""), 200);
+        assert_eq!(notify("DELETE", ""), 404);
+        assert_eq!(notify("GET", ""), 405);
+    }
+
+    #[test]
+    fn maps_socket_responses() {
+        assert_eq!(from_ipc("OK".to_string()), ok(Value::Null));
+        assert_eq!(
+            from_ipc("OK: refresh requested".to_string()),
+            ok(Value::Null)
+        );
+        assert_eq!(from_ipc("ERR: nope".to_string()), error(400, "nope"));
+        assert_eq!(from_ipc("[1]".to_string()), ok(json!([1])));
+        assert_eq!(from_ipc("label=x".to_string()), ok(json!("label=x")));
+    }
+
+    #[test]
+    fn decodes_path_segments() {
+        assert_eq!(percent_decode("my%20module"), "my module");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+}
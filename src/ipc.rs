@@ -4,10 +4,19 @@
 //! and drained by the GPUI bar view on each render frame.
 
 use async_channel::{Receiver, Sender};
-use std::sync::{Mutex, OnceLock};
-
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{ErrorKind, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::config::profile;
 use crate::gpui_app::modules::external::get_external_state;
+use crate::gpui_app::notify::{self, ItemColor};
 use crate::gpui_app::request_immediate_refresh;
+use crate::gpui_app::snooze::{self, Until};
+use crate::gpui_app::toast::ToastStyle;
 
 /// An IPC command destined for the GPUI main thread.
 #[derive(Debug, Clone)]
@@ -17,7 +26,7 @@ pub enum IpcCommand {
         module_id: String,
         properties: Vec<(String, String)>,
     },
-    /// Trigger a module event (e.g. "update" or "popup").
+    /// Trigger a module event ("update", "popup", "toggle" or "hide").
     Trigger { module_id: String, event: String },
 }
 
@@ -65,11 +74,20 @@ pub fn register_module_id(id: &str, module_type: &str) {
     }
 }
 
-/// Clears the registry (called on config reload).
+/// Clears the registry and module stats (called on config reload).
 pub fn clear_module_ids() {
     if let Ok(mut map) = id_type_map().lock() {
         map.clear();
     }
+    if let Ok(mut stats) = module_stats().lock() {
+        stats.clear();
+    }
+    // Watchers get the recreated modules' state even if it's unchanged
+    if let Ok(mut watchers) = WATCHERS.lock() {
+        for watcher in watchers.iter_mut() {
+            watcher.sent.clear();
+        }
+    }
 }
 
 /// Returns all registered module (id, type) pairs.
@@ -77,6 +95,259 @@ fn all_module_ids() -> Vec<(String, String)> {
     id_type_map().lock().map(|v| v.clone()).unwrap_or_default()
 }
 
+// ---------------------------------------------------------------------------
+// Module runtime stats (for `status` command)
+// ---------------------------------------------------------------------------
+
+/// Update statistics for one module.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ModuleStats {
+    updates: u64,
+    errors: u64,
+    last_update: Option<SystemTime>,
+    last_duration: Duration,
+}
+
+static MODULE_STATS: OnceLock<Mutex<HashMap<String, ModuleStats>>> = OnceLock::new();
+static HIDDEN_MODULES: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+fn module_stats() -> &'static Mutex<HashMap<String, ModuleStats>> {
+    MODULE_STATS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn hidden_modules() -> &'static Mutex<HashSet<String>> {
+    HIDDEN_MODULES.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Records one `update()` call; `ok` is false if the update panicked.
+pub fn record_module_update(id: &str, duration: Duration, ok: bool) {
+    if let Ok(mut stats) = module_stats().lock() {
+        let entry = stats.entry(id.to_string()).or_default();
+        entry.updates += 1;
+        entry.last_update = Some(SystemTime::now());
+        entry.last_duration = duration;
+        if !ok {
+            entry.errors += 1;
+        }
+    }
+}
+
+/// Replaces the set of modules hidden by the layout's overflow strategy.
+pub fn set_hidden_modules(hidden: &HashSet<String>) {
+    if let Ok(mut set) = hidden_modules().lock() {
+        *set = hidden.clone();
+    }
+}
+
+/// Returns per-module stats as JSON objects, in registration order.
+fn module_stats_json() -> Vec<serde_json::Value> {
+    let stats = module_stats().lock().map(|s| s.clone()).unwrap_or_default();
+    let hidden = hidden_modules()
+        .lock()
+        .map(|h| h.clone())
+        .unwrap_or_default();
+    all_module_ids()
+        .into_iter()
+        .map(|(id, module_type)| {
+            let entry = stats.get(&id).cloned().unwrap_or_default();
+            let last_update = entry
+                .last_update
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64);
+            serde_json::json!({
+                "id": id,
+                "type": module_type,
+                "visible": !hidden.contains(&id) && !snooze::is_snoozed(&id),
+                "updates": entry.updates,
+                "errors": entry.errors,
+                "last_update": last_update,
+                "last_update_us": entry.last_duration.as_micros() as u64,
+            })
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Module state watchers (for `watch` command)
+// ---------------------------------------------------------------------------
+
+/// How often an idle `watch` connection checks that its client is still there.
+const WATCH_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Lines queued for a slow `watch` client before newer states are coalesced.
+const WATCH_QUEUE_LEN: usize = 16;
+
+/// A `watch` connection: the modules it follows and the channel its thread
+/// writes to the socket.
+struct Watcher {
+    id: u64,
+    module_ids: Vec<String>,
+    lines: mpsc::SyncSender<String>,
+    /// Last line queued for each module, so unchanged updates send nothing
+    sent: HashMap<String, String>,
+    /// Lines that didn't fit in the channel, at most one per module
+    pending: VecDeque<(String, String)>,
+}
+
+impl Watcher {
+    /// Queues the module's state line unless it's the one last queued. While
+    /// the channel is full only the newest line per module is kept. Returns
+    /// false once the connection has closed.
+    fn send(&mut self, module_id: &str, line: &str) -> bool {
+        if self.sent.get(module_id).map(String::as_str) == Some(line) {
+            return true;
+        }
+        self.sent.insert(module_id.to_string(), line.to_string());
+        self.pending.retain(|(id, _)| id != module_id);
+        self.pending
+            .push_back((module_id.to_string(), line.to_string()));
+        self.flush()
+    }
+
+    /// Moves pending lines into the channel while it has room. Returns false
+    /// once the connection has closed.
+    fn flush(&mut self) -> bool {
+        while let Some((module_id, line)) = self.pending.pop_front() {
+            match self.lines.try_send(line) {
+                Ok(()) => {}
+                Err(mpsc::TrySendError::Full(line)) => {
+                    self.pending.push_front((module_id, line));
+                    return true;
+                }
+                Err(mpsc::TrySendError::Disconnected(_)) => return false,
+            }
+        }
+        true
+    }
+}
+
+static WATCHERS: Mutex<Vec<Watcher>> = Mutex::new(Vec::new());
+static NEXT_WATCHER_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Starts sending state lines for `module_ids` to `lines`. The modules
+/// update right away so the watcher starts with their current state.
+/// Returns the id to pass to `remove_watcher`.
+fn add_watcher(module_ids: Vec<String>, lines: mpsc::SyncSender<String>) -> u64 {
+    let id = NEXT_WATCHER_ID.fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut watchers) = WATCHERS.lock() {
+        watchers.push(Watcher {
+            id,
+            module_ids,
+            lines,
+            sent: HashMap::new(),
+            pending: VecDeque::new(),
+        });
+    }
+    request_immediate_refresh();
+    id
+}
+
+/// Queues a watcher's coalesced lines once its connection has taken some.
+fn flush_watcher(id: u64) {
+    if let Ok(mut watchers) = WATCHERS.lock() {
+        if let Some(watcher) = watchers.iter_mut().find(|watcher| watcher.id == id) {
+            watcher.flush();
+        }
+    }
+}
+
+/// Stops sending state lines to a watcher whose connection closed.
+fn remove_watcher(id: u64) {
+    if let Ok(mut watchers) = WATCHERS.lock() {
+        watchers.retain(|watcher| watcher.id != id);
+    }
+}
+
+/// Returns true if a `watch` connection follows the module.
+pub fn is_watched(id: &str) -> bool {
+    WATCHERS.lock().is_ok_and(|watchers| {
+        watchers
+            .iter()
+            .any(|watcher| watcher.module_ids.iter().any(|m| m == id))
+    })
+}
+
+/// Sends a watched module's text, value and text color to each of its
+/// watchers that hasn't had that state yet.
+pub fn record_module_state(
+    id: &str,
+    text: Option<String>,
+    value: Option<u8>,
+    color: Option<gpui::Rgba>,
+) {
+    let color = color.map(rgba_to_hex);
+    let line =
+        serde_json::json!({ "id": id, "text": text, "value": value, "color": color }).to_string();
+    if let Ok(mut watchers) = WATCHERS.lock() {
+        // A failed send means the connection closed
+        watchers.retain_mut(|watcher| {
+            !watcher.module_ids.iter().any(|m| m == id) || watcher.send(id, &line)
+        });
+    }
+}
+
+/// Streams state lines for the modules in `args` to `stream` until the
+/// client disconnects. While no module changes, the socket is polled every
+/// `WATCH_POLL_INTERVAL` so a closed client doesn't stay watching.
+fn handle_watch(args: &str, mut stream: UnixStream) {
+    let module_ids = match tokenize_args(args) {
+        Ok(tokens) if !tokens.is_empty() => tokens,
+        Ok(_) => {
+            let _ = writeln!(stream, "ERR: usage: watch <module_id> [...]");
+            return;
+        }
+        Err(err) => {
+            let _ = writeln!(stream, "ERR: {}", err);
+            return;
+        }
+    };
+    let known = all_module_ids();
+    if let Some(unknown) = module_ids
+        .iter()
+        .find(|id| !known.iter().any(|(known_id, _)| known_id == *id))
+    {
+        let _ = writeln!(stream, "ERR: unknown module '{}'", unknown);
+        return;
+    }
+    let (tx, rx) = mpsc::sync_channel(WATCH_QUEUE_LEN);
+    let watcher = add_watcher(module_ids, tx);
+    std::thread::spawn(move || {
+        loop {
+            match rx.recv_timeout(WATCH_POLL_INTERVAL) {
+                Ok(line) => {
+                    if writeln!(stream, "{}", line).is_err() {
+                        break;
+                    }
+                    flush_watcher(watcher);
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    if peer_closed(&stream) {
+                        break;
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        remove_watcher(watcher);
+    });
+}
+
+/// Returns true once the client has closed its end of `stream`. Watch
+/// clients don't send anything after the command, so a read that doesn't
+/// block only returns at end of file.
+fn peer_closed(stream: &UnixStream) -> bool {
+    if stream.set_nonblocking(true).is_err() {
+        return true;
+    }
+    let mut buf = [0u8; 64];
+    let closed = match (&*stream).read(&mut buf) {
+        Ok(0) => true,
+        Ok(_) => false,
+        Err(err) => err.kind() != ErrorKind::WouldBlock,
+    };
+    let _ = stream.set_nonblocking(false);
+    closed
+}
+
 // ---------------------------------------------------------------------------
 // Command parsing
 // ---------------------------------------------------------------------------
@@ -93,9 +364,13 @@ pub fn handle_ipc_command(command: &str) -> String {
             "OK: refresh requested".to_string()
         }
         "status" => {
+            let (profile, _, _) = profile::status();
             let status = serde_json::json!({
                 "version": crate::VERSION,
                 "running": true,
+                "profile": profile,
+                "supervisor": crate::supervisor::status_json(),
+                "modules": module_stats_json(),
             });
             status.to_string()
         }
@@ -103,6 +378,15 @@ pub fn handle_ipc_command(command: &str) -> String {
         "get" => handle_get(parts.get(1).copied().unwrap_or("")),
         "list" => handle_list(),
         "trigger" => handle_trigger(parts.get(1).copied().unwrap_or("")),
+        "pin" | "unpin" => handle_pin(verb == "pin"),
+        "snooze" => handle_snooze(parts.get(1).copied().unwrap_or("")),
+        "unsnooze" => handle_unsnooze(parts.get(1).copied().unwrap_or("")),
+        "toast" => handle_toast(parts.get(1).copied().unwrap_or("")),
+        "progress" => handle_progress(parts.get(1).copied().unwrap_or("")),
+        "notify" => handle_notify(parts.get(1).copied().unwrap_or("")),
+        "profile" => handle_profile(parts.get(1).copied().unwrap_or("")),
+        // Answered by the socket listener, which keeps the connection open
+        "watch" => "ERR: watch streams over the socket; use sinew-msg watch <id>".to_string(),
         other => format!("ERR: unknown command '{}'", other),
     }
 }
@@ -194,10 +478,7 @@ fn handle_set(args: &str) -> String {
         return "ERR: set requires at least one key=value pair".to_string();
     }
 
-    push_ipc_command(IpcCommand::Set {
-        module_id: module_id.to_string(),
-        properties,
-    });
+    set_module_properties(module_id, properties);
     "OK".to_string()
 }
 
@@ -223,7 +504,17 @@ fn handle_get(args: &str) -> String {
     let Some((module_id, rest)) = tokens.split_first() else {
         return "ERR: get requires <module_id>".to_string();
     };
-    let property = rest.first().map(String::as_str);
+    let Some(property) = rest.first() else {
+        // Return all properties as key=value lines
+        return match external_properties(module_id) {
+            Some(properties) => properties
+                .into_iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            None => format!("ERR: module '{}' not found or not external", module_id),
+        };
+    };
 
     let Some(state) = get_external_state(module_id) else {
         return format!("ERR: module '{}' not found or not external", module_id);
@@ -233,36 +524,38 @@ fn handle_get(args: &str) -> String {
         return "ERR: state lock contention".to_string();
     };
 
-    if let Some(prop) = property {
-        match prop {
-            "label" => guard.label.clone(),
-            "icon" => guard.icon.clone().unwrap_or_default(),
-            "color" => format_opt_color(guard.color),
-            "background" => format_opt_color(guard.background),
-            "drawing" => if guard.drawing { "on" } else { "off" }.to_string(),
-            other => format!("ERR: unknown property '{}'", other),
-        }
-    } else {
-        // Return all properties as key=value lines
-        let mut out = Vec::new();
-        out.push(format!("label={}", guard.label));
-        if let Some(ref icon) = guard.icon {
-            out.push(format!("icon={}", icon));
-        }
-        out.push(format!(
-            "drawing={}",
-            if guard.drawing { "on" } else { "off" }
-        ));
-        if let Some(c) = guard.color {
-            out.push(format!("color={}", rgba_to_hex(c)));
-        }
-        if let Some(c) = guard.background {
-            out.push(format!("background={}", rgba_to_hex(c)));
-        }
-        out.join("\n")
+    match property.as_str() {
+        "label" => guard.label.clone(),
+        "icon" => guard.icon.clone().unwrap_or_default(),
+        "color" => format_opt_color(guard.color),
+        "background" => format_opt_color(guard.background),
+        "drawing" => if guard.drawing { "on" } else { "off" }.to_string(),
+        other => format!("ERR: unknown property '{}'", other),
     }
 }
 
+/// The properties of an external module, in `get` order; unset ones are
+/// left out. None if the module doesn't exist or isn't external.
+pub fn external_properties(module_id: &str) -> Option<Vec<(&'static str, String)>> {
+    let state = get_external_state(module_id)?;
+    let guard = state.lock().ok()?;
+    let mut properties = vec![("label", guard.label.clone())];
+    if let Some(ref icon) = guard.icon {
+        properties.push(("icon", icon.clone()));
+    }
+    properties.push((
+        "drawing",
+        if guard.drawing { "on" } else { "off" }.to_string(),
+    ));
+    if let Some(c) = guard.color {
+        properties.push(("color", rgba_to_hex(c)));
+    }
+    if let Some(c) = guard.background {
+        properties.push(("background", rgba_to_hex(c)));
+    }
+    Some(properties)
+}
+
 /// `list` — returns JSON array of all module ids and types.
 fn handle_list() -> String {
     let entries: Vec<serde_json::Value> = all_module_ids()
@@ -272,7 +565,10 @@ fn handle_list() -> String {
     serde_json::to_string(&entries).unwrap_or_else(|_| "[]".to_string())
 }
 
-/// `trigger <module_id> update|popup`
+/// Events `trigger` accepts.
+pub const TRIGGER_EVENTS: &[&str] = &["update", "popup", "toggle", "hide", "click"];
+
+/// `trigger <module_id> update|popup|toggle|hide|click`
 fn handle_trigger(args: &str) -> String {
     let tokens = match tokenize_args(args) {
         Ok(tokens) => tokens,
@@ -283,21 +579,270 @@ fn handle_trigger(args: &str) -> String {
         return "ERR: trigger requires <module_id> <event>".to_string();
     };
     let Some(event) = rest.first() else {
-        return "ERR: trigger requires <event> (update|popup)".to_string();
+        return "ERR: trigger requires <event> (update|popup|toggle|hide|click)".to_string();
+    };
+
+    if !TRIGGER_EVENTS.contains(&event.as_str()) {
+        return format!(
+            "ERR: unknown event '{}', expected one of: {}",
+            event,
+            TRIGGER_EVENTS.join(", ")
+        );
+    }
+
+    trigger_module(module_id, event);
+    "OK".to_string()
+}
+
+/// `snooze <module_id> <minutes>|reload|restart` — hides a module for a while.
+fn handle_snooze(args: &str) -> String {
+    let tokens = match tokenize_args(args) {
+        Ok(tokens) => tokens,
+        Err(err) => return format!("ERR: {}", err),
+    };
+    let [module_id, until] = tokens.as_slice() else {
+        return "ERR: snooze requires <module_id> <minutes|reload|restart>".to_string();
+    };
+    let Some(until) = Until::parse(until, Instant::now()) else {
+        return format!(
+            "ERR: invalid snooze '{}', expected minutes, reload or restart",
+            until
+        );
+    };
+    snooze::snooze(module_id, until);
+    "OK".to_string()
+}
+
+/// `unsnooze <module_id>` — brings a snoozed module back early.
+fn handle_unsnooze(args: &str) -> String {
+    let module_id = args.trim();
+    if module_id.is_empty() {
+        return "ERR: unsnooze requires <module_id>".to_string();
+    }
+    if snooze::restore(module_id) {
+        "OK".to_string()
+    } else {
+        format!("ERR: '{}' isn't snoozed", module_id)
+    }
+}
+
+/// `pin` / `unpin` — keeps the open popup from closing on clicks outside it.
+fn handle_pin(pinned: bool) -> String {
+    if crate::gpui_app::popup_manager::set_popup_pinned(pinned) {
+        "OK".to_string()
+    } else {
+        "ERR: no popup is open".to_string()
+    }
+}
+
+/// `toast <message> [--timeout <seconds>] [--style info|success|warning|error]`
+fn handle_toast(args: &str) -> String {
+    let tokens = match tokenize_args(args) {
+        Ok(tokens) => tokens,
+        Err(err) => return format!("ERR: {}", err),
     };
+    let (message, style, timeout) = match parse_toast_args(&tokens) {
+        Ok(parsed) => parsed,
+        Err(err) => return format!("ERR: {}", err),
+    };
+
+    // Shown directly rather than queued: every bar renders the same toast
+    crate::gpui_app::toast::show(&message, style, timeout);
+    "OK".to_string()
+}
+
+/// Splits toast arguments into the message, style and optional timeout.
+fn parse_toast_args(tokens: &[String]) -> Result<(String, ToastStyle, Option<Duration>), String> {
+    let mut words: Vec<&str> = Vec::new();
+    let mut style = ToastStyle::default();
+    let mut timeout = None;
+
+    let mut iter = tokens.iter();
+    while let Some(token) = iter.next() {
+        match token.as_str() {
+            "--timeout" => {
+                let value = iter.next().ok_or("--timeout requires a value")?;
+                let seconds = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|s| s.is_finite() && *s > 0.0)
+                    .ok_or_else(|| format!("invalid timeout '{}'", value))?;
+                timeout = Some(Duration::from_secs_f64(seconds));
+            }
+            "--style" => {
+                let value = iter.next().ok_or("--style requires a value")?;
+                style = ToastStyle::parse(value).ok_or_else(|| {
+                    format!(
+                        "unknown style '{}', expected one of: info, success, warning, error",
+                        value
+                    )
+                })?;
+            }
+            word => words.push(word),
+        }
+    }
+
+    if words.is_empty() {
+        return Err("toast requires <message>".to_string());
+    }
+    Ok((words.join(" "), style, timeout))
+}
+
+/// `progress <name> <value>|remove` — value is a fraction (`0.42`) or a
+/// percentage (`42%`).
+fn handle_progress(args: &str) -> String {
+    let tokens = match tokenize_args(args) {
+        Ok(tokens) => tokens,
+        Err(err) => return format!("ERR: {}", err),
+    };
+
+    let [name, value] = tokens.as_slice() else {
+        return "ERR: progress requires <name> <value>|remove".to_string();
+    };
+
+    if value == "remove" {
+        return if crate::gpui_app::progress::remove(name) {
+            "OK".to_string()
+        } else {
+            format!("ERR: progress item '{}' not found", name)
+        };
+    }
 
-    if !matches!(event.as_str(), "update" | "popup") {
+    match parse_progress_value(value) {
+        Some(value) => {
+            crate::gpui_app::progress::set(name, value);
+            "OK".to_string()
+        }
+        None => format!(
+            "ERR: invalid progress '{}', expected 0-1 or a percentage",
+            value
+        ),
+    }
+}
+
+/// `notify <item> <text> [--color <color>] [--ttl <seconds>]` or
+/// `notify <item> remove`
+fn handle_notify(args: &str) -> String {
+    let tokens = match tokenize_args(args) {
+        Ok(tokens) => tokens,
+        Err(err) => return format!("ERR: {}", err),
+    };
+
+    if let [name, action] = tokens.as_slice() {
+        if action == "remove" {
+            return if notify::remove(name) {
+                "OK".to_string()
+            } else {
+                format!("ERR: notify item '{}' not found", name)
+            };
+        }
+    }
+
+    match parse_notify_args(&tokens) {
+        Ok((name, text, color, ttl)) => {
+            notify::set(&name, &text, color, ttl);
+            "OK".to_string()
+        }
+        Err(err) => format!("ERR: {}", err),
+    }
+}
+
+/// Splits notify arguments into the item name, text, color and ttl.
+fn parse_notify_args(
+    tokens: &[String],
+) -> Result<(String, String, Option<ItemColor>, Option<Duration>), String> {
+    let mut words: Vec<&str> = Vec::new();
+    let mut color = None;
+    let mut ttl = None;
+
+    let mut iter = tokens.iter();
+    while let Some(token) = iter.next() {
+        match token.as_str() {
+            "--ttl" => {
+                let value = iter.next().ok_or("--ttl requires a value")?;
+                let seconds = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|s| s.is_finite() && *s >= 0.0)
+                    .ok_or_else(|| format!("invalid ttl '{}'", value))?;
+                ttl = Some(Duration::from_secs_f64(seconds));
+            }
+            "--color" => {
+                let value = iter.next().ok_or("--color requires a value")?;
+                color = Some(ItemColor::parse(value).ok_or_else(|| {
+                    format!(
+                        "unknown color '{}', expected a hex color or one of: info, success, warning, error",
+                        value
+                    )
+                })?);
+            }
+            word => words.push(word),
+        }
+    }
+
+    match words.split_first() {
+        Some((name, text)) if !text.is_empty() => {
+            Ok((name.to_string(), text.join(" "), color, ttl))
+        }
+        _ => Err("notify requires <item> <text>".to_string()),
+    }
+}
+
+/// Parses `0.42` or `42%` into a 0–1 fraction.
+fn parse_progress_value(value: &str) -> Option<f32> {
+    let fraction = match value.strip_suffix('%') {
+        Some(percent) => percent.parse::<f32>().ok()? / 100.0,
+        None => value.parse::<f32>().ok()?,
+    };
+    (0.0..=1.0).contains(&fraction).then_some(fraction)
+}
+
+/// `profile [<name>|auto|none]` — without a name, reports the active
+/// profile as JSON.
+fn handle_profile(args: &str) -> String {
+    let name = args.trim();
+    let (active, requested, names) = profile::status();
+    if name.is_empty() {
+        return serde_json::json!({
+            "active": active,
+            "requested": requested,
+            "profiles": names,
+        })
+        .to_string();
+    }
+
+    if name != profile::AUTO && name != profile::NONE && !names.iter().any(|n| n == name) {
         return format!(
-            "ERR: unknown event '{}', expected one of: update, popup",
-            event
+            "ERR: unknown profile '{}', expected one of: {}",
+            name,
+            names
+                .iter()
+                .map(String::as_str)
+                .chain([profile::AUTO, profile::NONE])
+                .collect::<Vec<_>>()
+                .join(", ")
         );
     }
+    if profile::request(Some(name)) {
+        request_immediate_refresh();
+    }
+    "OK".to_string()
+}
 
+/// Queues property changes for a module, as `set` does over the socket.
+pub fn set_module_properties(module_id: &str, properties: Vec<(String, String)>) {
+    push_ipc_command(IpcCommand::Set {
+        module_id: module_id.to_string(),
+        properties,
+    });
+}
+
+/// Queues a module event for the bar, as `trigger` does over the socket.
+pub fn trigger_module(module_id: &str, event: &str) {
     push_ipc_command(IpcCommand::Trigger {
         module_id: module_id.to_string(),
         event: event.to_string(),
     });
-    "OK".to_string()
 }
 
 // ---------------------------------------------------------------------------
@@ -328,8 +873,8 @@ fn rgba_to_hex(c: gpui::Rgba) -> String {
 
 /// Starts the IPC listener on a Unix socket, spawning a background thread.
 pub fn start_ipc_listener(socket_path: &std::path::Path) -> std::io::Result<()> {
-    use std::io::{BufRead, BufReader, Write};
-    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::UnixListener;
 
     if let Some(parent) = socket_path.parent() {
         std::fs::create_dir_all(parent)?;
@@ -353,6 +898,11 @@ pub fn start_ipc_listener(socket_path: &std::path::Path) -> std::io::Result<()>
             let mut reader = BufReader::new(stream);
             let mut line = String::new();
             let _ = reader.read_line(&mut line);
+            let (verb, args) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            if verb == "watch" {
+                handle_watch(args, reader.into_inner());
+                continue;
+            }
             let response = handle_ipc_command(&line);
             if let Ok(mut stream) = reader.into_inner().try_clone() {
                 let _ = writeln!(stream, "{}", response);
@@ -413,6 +963,33 @@ mod tests {
         assert_eq!(v, "🔥");
     }
 
+    // -- module stats -------------------------------------------------------
+
+    #[test]
+    fn status_reports_module_stats() {
+        register_module_id("stats-test", "clock");
+        record_module_update("stats-test", Duration::from_micros(250), true);
+        record_module_update("stats-test", Duration::from_micros(120), false);
+        set_hidden_modules(&HashSet::from(["stats-test".to_string()]));
+
+        let status: serde_json::Value =
+            serde_json::from_str(&handle_ipc_command("status")).unwrap();
+        let module = status["modules"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|m| m["id"] == "stats-test")
+            .unwrap();
+        assert_eq!(module["type"], "clock");
+        assert_eq!(module["updates"], 2);
+        assert_eq!(module["errors"], 1);
+        assert_eq!(module["last_update_us"], 120);
+        assert_eq!(module["visible"], false);
+        assert!(module["last_update"].as_u64().is_some());
+
+        set_hidden_modules(&HashSet::new());
+    }
+
     // -- tokenize_args ------------------------------------------------------
 
     #[test]
@@ -427,6 +1004,85 @@ mod tests {
         assert!(err.contains("unterminated quote"));
     }
 
+    #[test]
+    fn parse_toast_args_reads_flags() {
+        let tokens = tokenize_args("\"Deployed!\" --timeout 5 --style success").unwrap();
+        let (message, style, timeout) = parse_toast_args(&tokens).unwrap();
+        assert_eq!(message, "Deployed!");
+        assert_eq!(style, ToastStyle::Success);
+        assert_eq!(timeout, Some(Duration::from_secs(5)));
+
+        let tokens = tokenize_args("build done").unwrap();
+        let (message, style, timeout) = parse_toast_args(&tokens).unwrap();
+        assert_eq!(message, "build done");
+        assert_eq!(style, ToastStyle::Info);
+        assert_eq!(timeout, None);
+    }
+
+    #[test]
+    fn parse_toast_args_rejects_bad_input() {
+        let parse = |args: &str| parse_toast_args(&tokenize_args(args).unwrap());
+        assert!(parse("").is_err());
+        assert!(parse("hi --timeout").is_err());
+        assert!(parse("hi --timeout -1").is_err());
+        assert!(parse("hi --style loud").is_err());
+    }
+
+    #[test]
+    fn parse_progress_value_accepts_fractions_and_percentages() {
+        assert_eq!(parse_progress_value("0.42"), Some(0.42));
+        assert_eq!(parse_progress_value("50%"), Some(0.5));
+        assert_eq!(parse_progress_value("1"), Some(1.0));
+        assert_eq!(parse_progress_value("1.5"), None);
+        assert_eq!(parse_progress_value("-10%"), None);
+        assert_eq!(parse_progress_value("half"), None);
+    }
+
+    #[test]
+    fn handle_progress_requires_name_and_value() {
+        assert!(handle_progress("").starts_with("ERR:"));
+        assert!(handle_progress("backup").starts_with("ERR:"));
+        assert!(handle_progress("backup lots").contains("invalid progress"));
+        assert!(handle_progress("missing_item_xyz remove").contains("not found"));
+    }
+
+    #[test]
+    fn parse_notify_args_reads_flags() {
+        let tokens = tokenize_args("ci \"main passing\" --color success --ttl 60").unwrap();
+        let (name, text, color, ttl) = parse_notify_args(&tokens).unwrap();
+        assert_eq!(name, "ci");
+        assert_eq!(text, "main passing");
+        assert_eq!(color, ItemColor::parse("success"));
+        assert_eq!(ttl, Some(Duration::from_secs(60)));
+
+        let tokens = tokenize_args("pager 2 alerts").unwrap();
+        let (name, text, color, ttl) = parse_notify_args(&tokens).unwrap();
+        assert_eq!(name, "pager");
+        assert_eq!(text, "2 alerts");
+        assert_eq!(color, None);
+        assert_eq!(ttl, None);
+    }
+
+    #[test]
+    fn parse_notify_args_rejects_bad_input() {
+        let parse = |args: &str| parse_notify_args(&tokenize_args(args).unwrap());
+        assert!(parse("").is_err());
+        assert!(parse("ci").is_err());
+        assert!(parse("ci --ttl 5").is_err());
+        assert!(parse("ci hi --ttl -1").is_err());
+        assert!(parse("ci hi --color loud").is_err());
+        assert!(handle_notify("missing_item_xyz remove").contains("not found"));
+    }
+
+    #[test]
+    fn handle_profile_rejects_unknown_names() {
+        let status: serde_json::Value = serde_json::from_str(&handle_profile("")).unwrap();
+        assert!(status["profiles"].is_array());
+        let resp = handle_profile("no_such_profile_xyz");
+        assert!(resp.starts_with("ERR: unknown profile"));
+        assert!(resp.contains("auto, none"));
+    }
+
     #[test]
     fn tokenize_args_rejects_trailing_escape() {
         let err = tokenize_args("mymod label=hello\\").unwrap_err();
@@ -562,6 +1218,14 @@ mod tests {
         assert!(resp.contains("unknown event"));
     }
 
+    #[test]
+    fn handle_snooze_rejects_bad_input() {
+        assert!(handle_snooze("mymod").contains("requires"));
+        assert!(handle_snooze("mymod soon").contains("invalid snooze"));
+        assert!(handle_unsnooze("").contains("requires"));
+        assert!(handle_unsnooze("never_snoozed_xyz").contains("isn't snoozed"));
+    }
+
     // -- handle_list --------------------------------------------------------
 
     #[test]
@@ -573,6 +1237,120 @@ mod tests {
 
     // -- module ID registry -------------------------------------------------
 
+    #[test]
+    fn watchers_get_each_change_once() {
+        let (tx, rx) = mpsc::sync_channel(WATCH_QUEUE_LEN);
+        add_watcher(vec!["test-watch-cpu".to_string()], tx);
+        assert!(is_watched("test-watch-cpu"));
+        assert!(!is_watched("test-watch-mem"));
+
+        let red = gpui::Rgba {
+            r: 1.0,
+            g: 0.0,
+            b: 0.0,
+            a: 1.0,
+        };
+        record_module_state("test-watch-cpu", Some("42".to_string()), Some(42), None);
+        record_module_state("test-watch-cpu", Some("42".to_string()), Some(42), None);
+        record_module_state("test-watch-mem", None, Some(7), None);
+        record_module_state(
+            "test-watch-cpu",
+            Some("42".to_string()),
+            Some(42),
+            Some(red),
+        );
+        record_module_state("test-watch-cpu", None, None, None);
+        let lines: Vec<String> = rx.try_iter().collect();
+        assert_eq!(
+            lines,
+            vec![
+                r#"{"color":null,"id":"test-watch-cpu","text":"42","value":42}"#,
+                r##"{"color":"#ff0000","id":"test-watch-cpu","text":"42","value":42}"##,
+                r#"{"color":null,"id":"test-watch-cpu","text":null,"value":null}"#,
+            ]
+        );
+
+        // A closed connection is dropped on the next change
+        drop(rx);
+        record_module_state("test-watch-cpu", Some("43".to_string()), Some(43), None);
+        assert!(!is_watched("test-watch-cpu"));
+    }
+
+    #[test]
+    fn new_watchers_dont_repeat_lines_for_others() {
+        let (tx, first) = mpsc::sync_channel(WATCH_QUEUE_LEN);
+        add_watcher(vec!["test-watch-shared".to_string()], tx);
+        record_module_state("test-watch-shared", None, Some(1), None);
+
+        let (tx, second) = mpsc::sync_channel(WATCH_QUEUE_LEN);
+        add_watcher(vec!["test-watch-shared".to_string()], tx);
+        record_module_state("test-watch-shared", None, Some(1), None);
+
+        let line = r#"{"color":null,"id":"test-watch-shared","text":null,"value":1}"#;
+        assert_eq!(first.try_iter().collect::<Vec<_>>(), [line]);
+        assert_eq!(second.try_iter().collect::<Vec<_>>(), [line]);
+    }
+
+    #[test]
+    fn slow_watchers_get_the_latest_state_per_module() {
+        let (tx, rx) = mpsc::sync_channel(1);
+        let watcher = add_watcher(
+            vec![
+                "test-watch-slow-a".to_string(),
+                "test-watch-slow-b".to_string(),
+            ],
+            tx,
+        );
+        record_module_state("test-watch-slow-a", None, Some(1), None);
+        record_module_state("test-watch-slow-a", None, Some(2), None);
+        record_module_state("test-watch-slow-b", None, Some(1), None);
+        record_module_state("test-watch-slow-a", None, Some(3), None);
+
+        let mut lines = Vec::new();
+        while let Ok(line) = rx.try_recv() {
+            lines.push(line);
+            flush_watcher(watcher);
+        }
+        assert_eq!(
+            lines,
+            vec![
+                r#"{"color":null,"id":"test-watch-slow-a","text":null,"value":1}"#,
+                r#"{"color":null,"id":"test-watch-slow-b","text":null,"value":1}"#,
+                r#"{"color":null,"id":"test-watch-slow-a","text":null,"value":3}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn removed_watchers_stop_watching() {
+        let (tx, _rx) = mpsc::sync_channel(WATCH_QUEUE_LEN);
+        let watcher = add_watcher(vec!["test-watch-removed".to_string()], tx);
+        assert!(is_watched("test-watch-removed"));
+        remove_watcher(watcher);
+        assert!(!is_watched("test-watch-removed"));
+    }
+
+    #[test]
+    fn watch_rejects_unknown_modules() {
+        use std::io::{BufRead, BufReader};
+
+        register_module_id("test-watch-known", "cpu");
+        let (server, client) = UnixStream::pair().unwrap();
+        handle_watch("test-watch-known test-watch-missing", server);
+        let mut reply = String::new();
+        BufReader::new(client).read_line(&mut reply).unwrap();
+        assert_eq!(reply.trim(), "ERR: unknown module 'test-watch-missing'");
+        assert!(!is_watched("test-watch-known"));
+    }
+
+    #[test]
+    fn peer_closed_detects_a_closed_client() {
+        let (server, client) = UnixStream::pair().unwrap();
+        assert!(!peer_closed(&server));
+        drop(client);
+        assert!(peer_closed(&server));
+    }
+
     #[test]
     fn register_and_list_module_ids() {
         register_module_id("test-ipc-mod", "external");
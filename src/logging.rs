@@ -0,0 +1,189 @@
+//! Logging: a `tracing` subscriber that prints to stderr and keeps recent
+//! warnings and errors in memory for the `logs` module.
+//!
+//! The codebase logs through the `log` macros; those records are forwarded
+//! into `tracing`, so both kinds of events end up here. `RUST_LOG` filters
+//! what's printed (default `info`); the in-memory buffer always keeps
+//! warnings and errors, up to [`CAPACITY`] of them.
+//!
+//! Each entry is attributed to a source: the `module` field of a `tracing`
+//! event when it has one, else the file the event came from (so a log line
+//! in `modules/weather.rs` belongs to "weather").
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_log::NormalizeEvent;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::time::ChronoUtc;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// Entries kept in memory; older ones are dropped first.
+pub const CAPACITY: usize = 500;
+
+/// A warning or error kept for the log viewer.
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub time: chrono::DateTime<chrono::Local>,
+    pub level: Level,
+    /// Module or part of sinew the entry came from
+    pub source: String,
+    /// Message, followed by any other fields as `key=value`
+    pub message: String,
+}
+
+static BUFFER: OnceLock<Mutex<VecDeque<LogEntry>>> = OnceLock::new();
+/// Bumped whenever the buffer changes
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn buffer() -> &'static Mutex<VecDeque<LogEntry>> {
+    BUFFER.get_or_init(|| Mutex::new(VecDeque::with_capacity(CAPACITY)))
+}
+
+fn push(entry: LogEntry) {
+    if let Ok(mut buffer) = buffer().lock() {
+        if buffer.len() == CAPACITY {
+            buffer.pop_front();
+        }
+        buffer.push_back(entry);
+        GENERATION.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Returns the kept entries, oldest first.
+pub fn recent() -> Vec<LogEntry> {
+    buffer()
+        .lock()
+        .map(|buffer| buffer.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Drops every kept entry.
+pub fn clear() {
+    if let Ok(mut buffer) = buffer().lock() {
+        buffer.clear();
+        GENERATION.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Counter that changes whenever entries are added or cleared.
+pub fn generation() -> u64 {
+    GENERATION.load(Ordering::Relaxed)
+}
+
+/// Names the source of an event: the file it came from for sinew's own
+/// code (`sinew::gpui_app::modules::weather` is "weather"), the crate name
+/// for dependencies.
+fn source_of(target: &str) -> &str {
+    match target.split_once("::") {
+        Some(("sinew", rest)) => rest.rsplit("::").next().unwrap_or(rest),
+        Some((krate, _)) => krate,
+        None => target,
+    }
+}
+
+/// Collects an event's message and fields.
+#[derive(Default)]
+struct EntryVisitor {
+    message: String,
+    module: Option<String>,
+    fields: String,
+}
+
+impl Visit for EntryVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message = value.to_string(),
+            "module" => self.module = Some(value.to_string()),
+            _ => self.record_debug(field, &value),
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        match field.name() {
+            "message" => self.message = format!("{:?}", value),
+            // Added by the `log` bridge; the normalized metadata has them
+            name if name.starts_with("log.") => {}
+            name => {
+                let _ = write!(self.fields, " {}={:?}", name, value);
+            }
+        }
+    }
+}
+
+/// Layer that copies events into the in-memory buffer.
+struct BufferLayer;
+
+impl<S: Subscriber> Layer<S> for BufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let normalized = event.normalized_metadata();
+        let metadata = normalized.as_ref().unwrap_or_else(|| event.metadata());
+        let mut visitor = EntryVisitor::default();
+        event.record(&mut visitor);
+        let source = visitor
+            .module
+            .unwrap_or_else(|| source_of(metadata.target()).to_string());
+        push(LogEntry {
+            time: chrono::Local::now(),
+            level: *metadata.level(),
+            source,
+            message: visitor.message + &visitor.fields,
+        });
+    }
+}
+
+/// Installs the subscriber and routes `log` records into it.
+pub fn init() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let stderr = tracing_subscriber::fmt::layer()
+        .with_timer(ChronoUtc::rfc_3339())
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_writer(std::io::stderr)
+        .with_filter(filter);
+    let result = tracing_subscriber::registry()
+        .with(stderr)
+        .with(BufferLayer.with_filter(LevelFilter::WARN))
+        .try_init();
+    if let Err(e) = result {
+        eprintln!("Failed to initialize logging: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_sources_after_files_and_crates() {
+        assert_eq!(source_of("sinew::gpui_app::modules::weather"), "weather");
+        assert_eq!(source_of("sinew::config"), "config");
+        assert_eq!(source_of("sinew"), "sinew");
+        assert_eq!(source_of("gpui::window"), "gpui");
+    }
+
+    #[test]
+    fn buffers_warnings_with_their_source() {
+        let subscriber =
+            tracing_subscriber::registry().with(BufferLayer.with_filter(LevelFilter::WARN));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(module = "script", status = 2, "command failed");
+            tracing::info!("not kept");
+        });
+        let entries = recent();
+        let entry = entries
+            .iter()
+            .find(|e| e.message.starts_with("command failed"))
+            .unwrap();
+        assert_eq!(entry.level, Level::WARN);
+        assert_eq!(entry.source, "script");
+        assert_eq!(entry.message, "command failed status=2");
+        assert!(!entries.iter().any(|e| e.message == "not kept"));
+    }
+}
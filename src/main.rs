@@ -5,8 +5,13 @@
 
 mod config;
 mod gpui_app;
+mod http_api;
 mod ipc;
 mod launch_agent;
+mod logging;
+mod streamdeck;
+mod supervisor;
+mod system;
 mod window;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -42,8 +47,24 @@ USAGE:
     sinew [OPTIONS]
 
 OPTIONS:
-    -h, --help       Print this help message
-    -v, --version    Print version information
+    -h, --help         Print this help message
+    -v, --version      Print version information
+    --check-config     Validate the config file and report problems
+    --dump-config      Print the effective config, defaults included
+    --init             Write a starter config if none exists
+    --profile <name>   Start with a config profile instead of its rules
+    --config <path>    Read the config from <path> instead
+    --supervise        Restart the bar when it crashes, logging the crash;
+                       combines with the other options
+    --profile-modules [seconds]
+                       Run each module headless (default 30s) and report its
+                       update time, allocations and subprocesses
+    --screenshot <out.png> [--config <path>] [--popup <module-id>]
+                       Render the bar, or a module's popup, to a PNG offscreen
+                       and exit
+    --streamdeck <args>
+                       Run as the Stream Deck plugin, which the Stream Deck
+                       app starts with its own arguments
 
 ENVIRONMENT:
     RUST_LOG         Set log level (error, warn, info, debug, trace)
@@ -54,18 +75,67 @@ CONFIG:
 EXAMPLES:
     sinew                    Run with default config
     RUST_LOG=debug sinew     Run with debug logging
+    sinew --check-config     Check the config before reloading it
+    sinew --profile work     Run with the [profile.work] overrides
+    sinew --profile-modules 60
+                             Find the modules that cost the most CPU
+    sinew --screenshot bar.png --config theme.toml
+                             Preview a config without replacing the bar
+    sinew --supervise        Run under a watchdog (crashes go to
+                             ~/.local/state/sinew/crash.log)
+    sinew --supervise --profile work
+                             Run the work profile under the watchdog
 
 For more information, see: https://github.com/dungle-scrubs/sinew",
         VERSION
     );
 }
 
+/// Reads the config from `path` instead of the default location, exiting if
+/// it doesn't exist.
+fn use_config_file(path: &std::path::Path) {
+    if !path.is_file() {
+        eprintln!("Config file not found: {}", path.display());
+        std::process::exit(1);
+    }
+    config::set_config_path(std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()));
+}
+
+/// Applies `--profile <name>` and `--config <path>`, in any order.
+fn apply_run_options(args: &[String]) {
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        match (flag.as_str(), args.next()) {
+            ("--profile", Some(name)) => {
+                config::profile::request(Some(name));
+            }
+            ("--profile", None) => {
+                eprintln!("--profile requires a profile name");
+                std::process::exit(1);
+            }
+            ("--config", Some(path)) => use_config_file(std::path::Path::new(path)),
+            ("--config", None) => {
+                eprintln!("--config requires a path");
+                std::process::exit(1);
+            }
+            _ => {
+                eprintln!("Unknown argument: {}", flag);
+                eprintln!("Try 'sinew --help' for more information.");
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
 fn main() {
     // Handle CLI arguments
-    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    // --supervise wraps whatever else the command line asks for
+    let supervise = supervisor::take_flag(&mut args);
 
     if !args.is_empty() {
-        // Only the first argument is processed (flags don't combine)
+        // Only the first argument is processed (flags don't combine, apart
+        // from --profile and --config)
         match args[0].as_str() {
             "-h" | "--help" => {
                 print_help();
@@ -75,6 +145,43 @@ fn main() {
                 println!("sinew {}", VERSION);
                 return;
             }
+            "--check-config" => {
+                std::process::exit(config::check_config());
+            }
+            "--dump-config" => {
+                std::process::exit(config::dump_config());
+            }
+            "--init" => {
+                std::process::exit(config::init_config());
+            }
+            "--profile-modules" => {
+                let seconds = match args.get(1).map(|s| s.parse::<u64>()) {
+                    None => 30,
+                    Some(Ok(seconds)) => seconds,
+                    Some(Err(_)) => {
+                        eprintln!("--profile-modules takes a number of seconds");
+                        std::process::exit(1);
+                    }
+                };
+                std::process::exit(gpui_app::bench::run(seconds));
+            }
+            "--screenshot" => match gpui_app::screenshot::Request::parse(&args[1..]) {
+                Ok(request) => {
+                    if let Some(path) = &request.config {
+                        use_config_file(path);
+                    }
+                    gpui_app::screenshot::request(request);
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            },
+            "--streamdeck" => {
+                logging::init();
+                std::process::exit(streamdeck::run(&args[1..]));
+            }
+            "--profile" | "--config" => apply_run_options(&args),
             _ => {
                 eprintln!("Unknown argument: {}", args[0]);
                 eprintln!("Try 'sinew --help' for more information.");
@@ -83,25 +190,22 @@ fn main() {
         }
     }
 
-    // Initialize logging (flush each line for interactive debugging).
-    let mut logger =
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"));
-    logger
-        .format(|buf, record| {
-            use std::io::Write;
-            writeln!(
-                buf,
-                "[{} {:>5} {}] {}",
-                chrono::Utc::now().to_rfc3339(),
-                record.level(),
-                record.target(),
-                record.args()
-            )?;
-            buf.flush()
-        })
-        .init();
+    logging::init();
+
+    if supervise {
+        log::info!("Supervising Sinew v{}", VERSION);
+        std::process::exit(supervisor::run(&args));
+    }
+
+    // Screenshots run next to the real bar, so they leave its socket alone
+    if gpui_app::screenshot::active() {
+        log::info!("Rendering a screenshot with Sinew v{}", VERSION);
+        gpui_app::run();
+        return;
+    }
 
     log::info!("Starting Sinew v{}", VERSION);
+    supervisor::install_panic_hook();
 
     if let Err(err) = start_ipc_listener() {
         log::warn!("Failed to start IPC listener: {}", err);
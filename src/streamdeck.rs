@@ -0,0 +1,497 @@
+//! Stream Deck plugin: mirrors bar modules onto Stream Deck keys.
+//!
+//! The Stream Deck app starts the plugin in `assets/streamdeck`, which runs
+//! `sinew --streamdeck` with the port and ids of the app's WebSocket. Each
+//! key of the plugin's Module action names a module in its settings. The key
+//! shows the module's bar text in its color, as `watch` reports them, and
+//! pressing it sends `trigger <id> click` to the running bar.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::system::websocket::{self, WebSocket};
+
+/// How long to wait for a Stream Deck event before looking at the bar's.
+const POLL: Duration = Duration::from_millis(50);
+
+/// Wait between attempts to reach a bar that isn't running.
+const RETRY: Duration = Duration::from_secs(2);
+
+/// Key images are drawn for the largest keys; Stream Deck scales them down.
+const KEY_SIZE: u32 = 144;
+
+/// Characters that fit on a key at the smallest font size.
+const MAX_CHARS: usize = 10;
+
+/// Text color for modules that use the theme's.
+const DEFAULT_COLOR: &str = "#ffffff";
+
+/// What Stream Deck passes on the command line.
+#[derive(Debug, PartialEq)]
+struct Launch {
+    port: u16,
+    plugin_uuid: String,
+    register_event: String,
+}
+
+impl Launch {
+    /// Parses `-port <n> -pluginUUID <uuid> -registerEvent <event> -info <json>`.
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut port = None;
+        let mut plugin_uuid = None;
+        let mut register_event = None;
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("{} requires a value", flag))?;
+            match flag.as_str() {
+                "-port" => {
+                    port = Some(
+                        value
+                            .parse::<u16>()
+                            .map_err(|_| format!("invalid port '{}'", value))?,
+                    )
+                }
+                "-pluginUUID" => plugin_uuid = Some(value.clone()),
+                "-registerEvent" => register_event = Some(value.clone()),
+                // -info describes the app and its devices, which isn't needed
+                _ => {}
+            }
+        }
+        Ok(Self {
+            port: port.ok_or("missing -port")?,
+            plugin_uuid: plugin_uuid.ok_or("missing -pluginUUID")?,
+            register_event: register_event.ok_or("missing -registerEvent")?,
+        })
+    }
+}
+
+/// A module's state from a `watch` line.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct ModuleState {
+    id: String,
+    text: Option<String>,
+    color: Option<String>,
+}
+
+/// What a Stream Deck event asks of the plugin.
+#[derive(Debug, PartialEq)]
+enum Effect {
+    /// A message for Stream Deck
+    Send(Value),
+    /// A key press on a module
+    Click(String),
+}
+
+/// The keys on the decks and the modules they show.
+#[derive(Default)]
+struct Deck {
+    /// Module shown by each visible key, by the key's context
+    keys: HashMap<String, String>,
+    /// Latest state of each watched module
+    states: HashMap<String, ModuleState>,
+}
+
+impl Deck {
+    /// Handles an event from Stream Deck.
+    fn handle_event(&mut self, event: &Value) -> Vec<Effect> {
+        let Some(context) = event["context"].as_str() else {
+            return Vec::new();
+        };
+        match event["event"].as_str() {
+            Some("willAppear" | "didReceiveSettings") => {
+                match event["payload"]["settings"]["module"].as_str() {
+                    Some(module_id) if !module_id.trim().is_empty() => {
+                        let module_id = module_id.trim().to_string();
+                        self.keys.insert(context.to_string(), module_id.clone());
+                        self.states
+                            .get(&module_id)
+                            .map(|state| Effect::Send(set_image(context, state)))
+                            .into_iter()
+                            .collect()
+                    }
+                    _ => {
+                        self.keys.remove(context);
+                        Vec::new()
+                    }
+                }
+            }
+            Some("willDisappear") => {
+                self.keys.remove(context);
+                Vec::new()
+            }
+            Some("keyUp") => self
+                .keys
+                .get(context)
+                .map(|module_id| Effect::Click(module_id.clone()))
+                .into_iter()
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Records a module's new state, returning images for the keys showing it.
+    fn handle_state(&mut self, state: ModuleState) -> Vec<Value> {
+        let messages = self
+            .keys
+            .iter()
+            .filter(|(_, module_id)| **module_id == state.id)
+            .map(|(context, _)| set_image(context, &state))
+            .collect();
+        self.states.insert(state.id.clone(), state);
+        messages
+    }
+
+    /// Modules shown on at least one key.
+    fn module_ids(&self) -> BTreeSet<String> {
+        self.keys.values().cloned().collect()
+    }
+}
+
+/// A `setImage` message drawing a module's state on a key.
+fn set_image(context: &str, state: &ModuleState) -> Value {
+    let svg = key_svg(
+        state.text.as_deref().unwrap_or(""),
+        state.color.as_deref().unwrap_or(DEFAULT_COLOR),
+    );
+    json!({
+        "event": "setImage",
+        "context": context,
+        "payload": {
+            "image": format!("data:image/svg+xml;base64,{}", websocket::base64(svg.as_bytes())),
+            "target": 0,
+        },
+    })
+}
+
+/// Draws `text` in `color` on a black key, shrinking it to fit.
+fn key_svg(text: &str, color: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let text = if text.chars().count() > MAX_CHARS {
+        let mut short: String = text.chars().take(MAX_CHARS - 1).collect();
+        short.push('…');
+        short
+    } else {
+        text
+    };
+    let font_size = match text.chars().count() {
+        0..=4 => 44,
+        5..=7 => 32,
+        _ => 24,
+    };
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\">\
+         <rect width=\"{size}\" height=\"{size}\" fill=\"#000000\"/>\
+         <text x=\"{center}\" y=\"{center}\" font-family=\"-apple-system, Helvetica, sans-serif\" \
+         font-size=\"{font_size}\" fill=\"{color}\" text-anchor=\"middle\" \
+         dominant-baseline=\"central\">{text}</text></svg>",
+        size = KEY_SIZE,
+        center = KEY_SIZE / 2,
+        font_size = font_size,
+        color = escape_xml(color),
+        text = escape_xml(&text),
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Quotes an IPC argument so spaces and quotes survive the bar's tokenizer.
+fn quote(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A `watch` connection to the bar, read on its own thread.
+struct Watch {
+    module_ids: BTreeSet<String>,
+    stream: UnixStream,
+    /// Set once the bar closes the connection
+    closed: Arc<AtomicBool>,
+}
+
+impl Watch {
+    /// Starts watching the ones of `module_ids` the bar has, sending their
+    /// states to `states`. Fails if it has none of them.
+    fn open(module_ids: BTreeSet<String>, states: mpsc::Sender<ModuleState>) -> io::Result<Self> {
+        let listed = listed_ids(&request("list")?);
+        let (ids, missing): (Vec<&String>, Vec<&String>) =
+            module_ids.iter().partition(|id| listed.contains(*id));
+        if !missing.is_empty() {
+            log::debug!("Modules not on the bar: {:?}", missing);
+        }
+        if ids.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "none of the keys' modules are on the bar",
+            ));
+        }
+        let mut stream = UnixStream::connect(crate::socket_path())?;
+        let ids: Vec<String> = ids.into_iter().map(|id| quote(id)).collect();
+        writeln!(stream, "watch {}", ids.join(" "))?;
+        let reader = BufReader::new(stream.try_clone()?);
+        let closed = Arc::new(AtomicBool::new(false));
+        let done = Arc::clone(&closed);
+        std::thread::spawn(move || {
+            for line in reader.lines().map_while(Result::ok) {
+                match serde_json::from_str(&line) {
+                    Ok(state) => {
+                        if states.send(state).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => log::warn!("Unexpected watch reply: {}", line),
+                }
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+        Ok(Self {
+            module_ids,
+            stream,
+            closed,
+        })
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// Sends one command to the bar and returns its reply.
+fn request(command: &str) -> io::Result<String> {
+    let mut stream = UnixStream::connect(crate::socket_path())?;
+    writeln!(stream, "{}", command)?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    Ok(reply)
+}
+
+/// Module ids in a `list` reply.
+fn listed_ids(reply: &str) -> HashSet<String> {
+    serde_json::from_str::<Vec<Value>>(reply)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|module| module["id"].as_str().map(str::to_string))
+        .collect()
+}
+
+/// Has the bar do what a click on the module does.
+fn click(module_id: &str) {
+    match request(&format!("trigger {} click", quote(module_id))) {
+        Ok(reply) if reply.starts_with("ERR") => {
+            log::warn!("Clicking '{}' failed: {}", module_id, reply.trim())
+        }
+        Ok(_) => {}
+        Err(e) => log::warn!("Couldn't reach sinew to click '{}': {}", module_id, e),
+    }
+}
+
+/// Runs the plugin until Stream Deck disconnects. Returns the exit code.
+pub fn run(args: &[String]) -> i32 {
+    let launch = match Launch::parse(args) {
+        Ok(launch) => launch,
+        Err(e) => {
+            eprintln!("--streamdeck: {}", e);
+            return 1;
+        }
+    };
+    let mut socket = match WebSocket::connect("127.0.0.1", launch.port, "/") {
+        Ok(socket) => socket,
+        Err(e) => {
+            log::error!("Couldn't connect to Stream Deck: {}", e);
+            return 1;
+        }
+    };
+    let register = json!({ "event": launch.register_event, "uuid": launch.plugin_uuid });
+    if let Err(e) = socket.send_text(&register.to_string()) {
+        log::error!("Couldn't register with Stream Deck: {}", e);
+        return 1;
+    }
+    log::info!("Registered with Stream Deck on port {}", launch.port);
+
+    let (states_tx, states_rx) = mpsc::channel();
+    let mut deck = Deck::default();
+    let mut watch: Option<Watch> = None;
+    let mut retry_at = Instant::now();
+    loop {
+        let message = match socket.read_text(POLL) {
+            Ok(message) => message,
+            // Stream Deck closes the connection when it quits or drops the plugin
+            Err(e) => {
+                log::info!("Stream Deck disconnected: {}", e);
+                return 0;
+            }
+        };
+
+        let mut outgoing = Vec::new();
+        if let Some(event) = message.and_then(|text| serde_json::from_str::<Value>(&text).ok()) {
+            for effect in deck.handle_event(&event) {
+                match effect {
+                    Effect::Send(message) => outgoing.push(message),
+                    Effect::Click(module_id) => click(&module_id),
+                }
+            }
+        }
+        for state in states_rx.try_iter() {
+            outgoing.extend(deck.handle_state(state));
+        }
+
+        // Follow the modules on the keys, reconnecting when they change or
+        // the bar restarts
+        let module_ids = deck.module_ids();
+        if let Some(current) = &watch {
+            if current.closed.load(Ordering::Relaxed) {
+                watch = None;
+                retry_at = Instant::now() + RETRY;
+            } else if current.module_ids != module_ids {
+                watch = None;
+                retry_at = Instant::now();
+            }
+        }
+        if watch.is_none() && !module_ids.is_empty() && Instant::now() >= retry_at {
+            match Watch::open(module_ids, states_tx.clone()) {
+                Ok(opened) => watch = Some(opened),
+                Err(e) => {
+                    log::debug!("Couldn't reach sinew: {}", e);
+                    retry_at = Instant::now() + RETRY;
+                }
+            }
+        }
+
+        for message in outgoing {
+            if let Err(e) = socket.send_text(&message.to_string()) {
+                log::info!("Stream Deck disconnected: {}", e);
+                return 0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn appear(context: &str, module: &str) -> Value {
+        json!({
+            "event": "willAppear",
+            "context": context,
+            "payload": { "settings": { "module": module } },
+        })
+    }
+
+    fn state(id: &str, text: &str) -> ModuleState {
+        ModuleState {
+            id: id.to_string(),
+            text: Some(text.to_string()),
+            color: None,
+        }
+    }
+
+    #[test]
+    fn parses_stream_deck_launch_arguments() {
+        let launch = Launch::parse(&args(&[
+            "-port",
+            "28196",
+            "-pluginUUID",
+            "ABC123",
+            "-registerEvent",
+            "registerPlugin",
+            "-info",
+            r#"{"application":{"platform":"mac"}}"#,
+        ]))
+        .unwrap();
+        assert_eq!(
+            launch,
+            Launch {
+                port: 28196,
+                plugin_uuid: "ABC123".to_string(),
+                register_event: "registerPlugin".to_string(),
+            }
+        );
+
+        assert!(Launch::parse(&args(&["-port", "x"])).is_err());
+        assert!(Launch::parse(&args(&["-port"])).is_err());
+        assert!(Launch::parse(&args(&["-port", "1", "-pluginUUID", "A"])).is_err());
+    }
+
+    #[test]
+    fn keys_show_their_module_and_click_it() {
+        let mut deck = Deck::default();
+        assert!(deck.handle_event(&appear("key-1", "cpu")).is_empty());
+        assert!(deck.handle_event(&appear("key-2", " ")).is_empty());
+        assert_eq!(deck.module_ids(), BTreeSet::from(["cpu".to_string()]));
+
+        let images = deck.handle_state(state("cpu", "12%"));
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0]["event"], "setImage");
+        assert_eq!(images[0]["context"], "key-1");
+        assert!(deck.handle_state(state("battery", "87")).is_empty());
+
+        // A key that appears later gets the module's last state right away
+        let effects = deck.handle_event(&appear("key-3", "cpu"));
+        assert!(matches!(&effects[..], [Effect::Send(image)] if image["context"] == "key-3"));
+
+        let press = json!({ "event": "keyUp", "context": "key-1", "payload": {} });
+        assert_eq!(
+            deck.handle_event(&press),
+            vec![Effect::Click("cpu".to_string())]
+        );
+
+        // Changing a key's module in its settings moves it over
+        let settings = json!({
+            "event": "didReceiveSettings",
+            "context": "key-3",
+            "payload": { "settings": { "module": "battery" } },
+        });
+        assert_eq!(deck.handle_event(&settings).len(), 1);
+
+        for context in ["key-1", "key-3"] {
+            deck.handle_event(&json!({ "event": "willDisappear", "context": context }));
+        }
+        assert!(deck.module_ids().is_empty());
+        assert!(deck.handle_event(&press).is_empty());
+    }
+
+    #[test]
+    fn key_svg_escapes_and_shortens_text() {
+        let svg = key_svg("a<b & \"c\"", "#ff0000");
+        assert!(svg.contains(">a&lt;b &amp; &quot;c&quot;</text>"));
+        assert!(svg.contains("fill=\"#ff0000\""));
+
+        let svg = key_svg("Now playing:\nA long song", DEFAULT_COLOR);
+        assert!(svg.contains(">Now playi…</text>"));
+        assert!(svg.contains("font-size=\"24\""));
+    }
+
+    #[test]
+    fn reads_module_ids_from_list_replies() {
+        let ids = listed_ids(r#"[{"id":"cpu","type":"cpu"},{"id":"clock","type":"clock"}]"#);
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains("cpu") && ids.contains("clock"));
+        assert!(listed_ids("ERR: nope").is_empty());
+    }
+
+    #[test]
+    fn quotes_ipc_arguments() {
+        assert_eq!(quote("cpu"), "\"cpu\"");
+        assert_eq!(quote(r#"my "mod""#), r#""my \"mod\"""#);
+    }
+}
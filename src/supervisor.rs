@@ -0,0 +1,343 @@
+//! Supervisor mode: `sinew --supervise` runs the bar as a child process and
+//! restarts it when it crashes.
+//!
+//! The child gets the rest of the command line (e.g. `--profile work`) and is
+//! started with `SINEW_SUPERVISED` set, which makes it install a panic hook
+//! that writes the message and backtrace to a scratch file. When the child
+//! dies of a panic the supervisor moves that report into
+//! `~/.local/state/sinew/crash.log`; other crashes (signals, non-zero exits)
+//! are logged with their exit status. Restarts back off exponentially and stop
+//! after too many crashes in a short time. A clean exit (status 0, e.g. Ctrl-C)
+//! ends the supervisor too.
+
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+/// Flag that turns on supervisor mode.
+const SUPERVISE_FLAG: &str = "--supervise";
+/// Set in the child's environment by the supervisor.
+const SUPERVISED_ENV: &str = "SINEW_SUPERVISED";
+/// Number of restarts so far, passed to the child.
+const RESTARTS_ENV: &str = "SINEW_RESTARTS";
+/// Crashes allowed within `CRASH_WINDOW` before giving up.
+const MAX_CRASHES: usize = 5;
+const CRASH_WINDOW: Duration = Duration::from_secs(60);
+const MAX_DELAY: Duration = Duration::from_secs(30);
+/// crash.log is rotated to crash.log.1 past this size.
+const MAX_LOG_BYTES: u64 = 256 * 1024;
+/// Exit status of a process whose main thread panicked.
+const PANIC_EXIT_CODE: i32 = 101;
+const SIGABRT: i32 = 6;
+/// Marks the first line of each crash.log entry.
+const ENTRY_MARKER: &str = "=== ";
+
+fn crash_log_path() -> PathBuf {
+    crate::config::get_state_dir().join("crash.log")
+}
+
+/// Where the supervised child writes its last panic.
+fn panic_report_path() -> PathBuf {
+    crate::config::get_state_dir().join("panic.txt")
+}
+
+/// Returns true when running as the child of `sinew --supervise`.
+pub fn is_supervised() -> bool {
+    std::env::var_os(SUPERVISED_ENV).is_some()
+}
+
+/// In a supervised child, records every panic's message and backtrace for
+/// the supervisor. Panics that a module update catches overwrite the report
+/// too; the supervisor only uses it if the process then dies of a panic.
+pub fn install_panic_hook() {
+    if !is_supervised() {
+        return;
+    }
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let thread = std::thread::current();
+        let report = format!(
+            "thread '{}' {}\n{}",
+            thread.name().unwrap_or("<unnamed>"),
+            info.to_string().replace('\n', " "),
+            std::backtrace::Backtrace::force_capture()
+        );
+        let path = panic_report_path();
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let _ = std::fs::write(path, report);
+        default_hook(info);
+    }));
+}
+
+/// Decides whether and when to restart after a crash.
+#[derive(Debug, Default)]
+struct RestartPolicy {
+    /// Times of recent crashes, oldest first
+    crashes: VecDeque<Instant>,
+}
+
+impl RestartPolicy {
+    /// Records a crash at `now`. Returns how long to wait before restarting,
+    /// or None if the bar crashed too often and should stay down.
+    fn on_crash(&mut self, now: Instant) -> Option<Duration> {
+        while self
+            .crashes
+            .front()
+            .is_some_and(|t| now.duration_since(*t) > CRASH_WINDOW)
+        {
+            self.crashes.pop_front();
+        }
+        self.crashes.push_back(now);
+        if self.crashes.len() > MAX_CRASHES {
+            return None;
+        }
+        let delay = Duration::from_secs(1 << (self.crashes.len() - 1));
+        Some(delay.min(MAX_DELAY))
+    }
+}
+
+/// Describes how a child process ended.
+fn describe(status: ExitStatus) -> String {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => format!("exited with status {}", code),
+        (None, Some(signal)) => format!("killed by signal {}", signal),
+        (None, None) => "exited".to_string(),
+    }
+}
+
+/// Returns true if the exit status is what a panic leaves behind: an
+/// unwound main thread, or an abort from a panic that couldn't unwind.
+fn died_of_panic(status: ExitStatus) -> bool {
+    status.code() == Some(PANIC_EXIT_CODE) || status.signal() == Some(SIGABRT)
+}
+
+/// Appends a crash entry to crash.log, rotating the log when it's large.
+fn record_crash(reason: &str, details: Option<&str>) {
+    let path = crash_log_path();
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if std::fs::metadata(&path).is_ok_and(|m| m.len() > MAX_LOG_BYTES) {
+        let _ = std::fs::rename(&path, path.with_extension("log.1"));
+    }
+    let mut entry = format!(
+        "{}{} | {}\n",
+        ENTRY_MARKER,
+        chrono::Local::now().to_rfc3339(),
+        reason
+    );
+    if let Some(details) = details {
+        entry.push_str(details.trim_end());
+        entry.push('\n');
+    }
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(entry.as_bytes()));
+    if let Err(e) = result {
+        log::warn!("Failed to write {}: {}", path.display(), e);
+    }
+}
+
+/// Time and reason of the last entry in a crash log.
+fn last_crash(log: &str) -> Option<(String, String)> {
+    let header = log
+        .lines()
+        .rev()
+        .find_map(|line| line.strip_prefix(ENTRY_MARKER))?;
+    let (time, reason) = header.split_once(" | ")?;
+    Some((time.to_string(), reason.to_string()))
+}
+
+/// Supervisor state for the `status` IPC command.
+pub fn status_json() -> serde_json::Value {
+    let restarts: u64 = std::env::var(RESTARTS_ENV)
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(0);
+    let last_crash = std::fs::read_to_string(crash_log_path())
+        .ok()
+        .and_then(|log| last_crash(&log))
+        .map(|(time, reason)| serde_json::json!({ "time": time, "reason": reason }));
+    serde_json::json!({
+        "supervised": is_supervised(),
+        "restarts": restarts,
+        "last_crash": last_crash,
+    })
+}
+
+/// Removes `--supervise` from the command line, wherever it appears. Returns
+/// true if it was there.
+pub fn take_flag(args: &mut Vec<String>) -> bool {
+    let len = args.len();
+    args.retain(|arg| arg != SUPERVISE_FLAG);
+    args.len() != len
+}
+
+/// Command that starts the bar under supervision with the given arguments.
+fn child_command(exe: &Path, args: &[String], restarts: u32) -> Command {
+    let mut command = Command::new(exe);
+    command
+        .args(args)
+        .env(SUPERVISED_ENV, "1")
+        .env(RESTARTS_ENV, restarts.to_string());
+    command
+}
+
+static CHILD_PID: AtomicU32 = AtomicU32::new(0);
+static STOPPING: AtomicBool = AtomicBool::new(false);
+
+/// Runs the bar as a child process with `args` (the command line without
+/// `--supervise`), restarting it after crashes. Returns the process exit code.
+pub fn run(args: &[String]) -> i32 {
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            log::error!("Can't find the sinew binary: {}", e);
+            return 1;
+        }
+    };
+    // Pass SIGINT/SIGTERM on to the bar and stop once it exits
+    if let Err(e) = ctrlc::set_handler(|| {
+        STOPPING.store(true, Ordering::SeqCst);
+        let pid = CHILD_PID.load(Ordering::SeqCst);
+        if pid != 0 {
+            let _ = Command::new("kill").arg(pid.to_string()).status();
+        }
+    }) {
+        log::warn!("Failed to install signal handler: {}", e);
+    }
+
+    let mut policy = RestartPolicy::default();
+    let mut restarts = 0u32;
+    loop {
+        let _ = std::fs::remove_file(panic_report_path());
+        let mut child = match child_command(&exe, args, restarts).spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                log::error!("Failed to start sinew: {}", e);
+                return 1;
+            }
+        };
+        CHILD_PID.store(child.id(), Ordering::SeqCst);
+        let status = match child.wait() {
+            Ok(status) => status,
+            Err(e) => {
+                log::error!("Failed to wait for sinew: {}", e);
+                return 1;
+            }
+        };
+        CHILD_PID.store(0, Ordering::SeqCst);
+
+        if status.success() || STOPPING.load(Ordering::SeqCst) {
+            return 0;
+        }
+
+        let reason = describe(status);
+        let report = died_of_panic(status)
+            .then(|| std::fs::read_to_string(panic_report_path()).ok())
+            .flatten();
+        match report.as_deref().and_then(|r| r.split_once('\n')) {
+            Some((panic, backtrace)) => {
+                record_crash(&format!("{}: {}", reason, panic), Some(backtrace))
+            }
+            None => record_crash(&reason, None),
+        }
+
+        let Some(delay) = policy.on_crash(Instant::now()) else {
+            log::error!(
+                "Sinew crashed {} times within {}s; not restarting. See {}",
+                MAX_CRASHES + 1,
+                CRASH_WINDOW.as_secs(),
+                crash_log_path().display()
+            );
+            return 1;
+        };
+        log::warn!("Sinew {}; restarting in {}s", reason, delay.as_secs());
+        std::thread::sleep(delay);
+        if STOPPING.load(Ordering::SeqCst) {
+            return 0;
+        }
+        restarts += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restarts_back_off_then_give_up() {
+        let start = Instant::now();
+        let mut policy = RestartPolicy::default();
+        let delays: Vec<Option<u64>> = (0..6)
+            .map(|i| {
+                policy
+                    .on_crash(start + Duration::from_secs(i))
+                    .map(|d| d.as_secs())
+            })
+            .collect();
+        assert_eq!(delays, [Some(1), Some(2), Some(4), Some(8), Some(16), None]);
+
+        // Crashes outside the window are forgotten
+        let mut policy = RestartPolicy::default();
+        for i in 0..5 {
+            policy.on_crash(start + Duration::from_secs(i));
+        }
+        let later = start + CRASH_WINDOW + Duration::from_secs(10);
+        assert_eq!(policy.on_crash(later), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn child_keeps_the_other_arguments() {
+        let mut args: Vec<String> = ["--profile", "work", "--supervise", "--config", "bar.toml"]
+            .map(String::from)
+            .to_vec();
+        assert!(take_flag(&mut args));
+        assert!(!take_flag(&mut args));
+
+        let command = child_command(Path::new("/usr/local/bin/sinew"), &args, 2);
+        let child_args: Vec<&str> = command
+            .get_args()
+            .map(|arg| arg.to_str().unwrap())
+            .collect();
+        assert_eq!(child_args, ["--profile", "work", "--config", "bar.toml"]);
+        assert!(command
+            .get_envs()
+            .any(|(key, value)| key == RESTARTS_ENV && value == Some("2".as_ref())));
+    }
+
+    #[test]
+    fn reads_last_crash_entry() {
+        let log = "\
+=== 2026-10-16T09:00:00+00:00 | killed by signal 11
+=== 2026-10-16T10:00:00+00:00 | exited with status 101: thread 'main' panicked at src/main.rs:1:1: boom
+   0: std::backtrace::Backtrace::force_capture
+";
+        assert_eq!(
+            last_crash(log),
+            Some((
+                "2026-10-16T10:00:00+00:00".to_string(),
+                "exited with status 101: thread 'main' panicked at src/main.rs:1:1: boom"
+                    .to_string()
+            ))
+        );
+        assert_eq!(last_crash(""), None);
+    }
+
+    #[test]
+    fn recognizes_panic_exits() {
+        assert!(died_of_panic(ExitStatus::from_raw(PANIC_EXIT_CODE << 8)));
+        assert!(died_of_panic(ExitStatus::from_raw(SIGABRT)));
+        assert!(!died_of_panic(ExitStatus::from_raw(1 << 8)));
+        assert_eq!(describe(ExitStatus::from_raw(11)), "killed by signal 11");
+    }
+}
@@ -0,0 +1,420 @@
+//! Audio devices and the system defaults, from CoreAudio.
+//!
+//! Lists the output and input devices, marks the current defaults and
+//! switches them. Battery levels of Bluetooth headsets come from
+//! `system_profiler SPBluetoothDataType`, which takes a second or two, so
+//! callers read them separately and off the main thread.
+
+use std::process::Command;
+
+use core_foundation::base::TCFType;
+use core_foundation::string::{CFString, CFStringRef};
+
+// CoreAudio FFI bindings
+mod ffi {
+    use std::ffi::c_void;
+
+    pub type OSStatus = i32;
+    pub type AudioObjectID = u32;
+    pub type AudioObjectPropertySelector = u32;
+    pub type AudioObjectPropertyScope = u32;
+    pub type AudioObjectPropertyElement = u32;
+
+    pub const K_AUDIO_HARDWARE_NO_ERROR: OSStatus = 0;
+    pub const K_AUDIO_OBJECT_SYSTEM_OBJECT: AudioObjectID = 1;
+    pub const K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL: AudioObjectPropertyScope = 0x676C6F62; // 'glob'
+    pub const K_AUDIO_OBJECT_PROPERTY_SCOPE_INPUT: AudioObjectPropertyScope = 0x696E7074; // 'inpt'
+    pub const K_AUDIO_OBJECT_PROPERTY_SCOPE_OUTPUT: AudioObjectPropertyScope = 0x6F757470; // 'outp'
+    pub const K_AUDIO_OBJECT_PROPERTY_ELEMENT_MAIN: AudioObjectPropertyElement = 0;
+    pub const K_AUDIO_OBJECT_PROPERTY_NAME: AudioObjectPropertySelector = 0x6C6E616D; // 'lnam'
+    pub const K_AUDIO_HARDWARE_PROPERTY_DEVICES: AudioObjectPropertySelector = 0x64657623; // 'dev#'
+    pub const K_AUDIO_HARDWARE_PROPERTY_DEFAULT_OUTPUT_DEVICE: AudioObjectPropertySelector =
+        0x644F7574; // 'dOut'
+    pub const K_AUDIO_HARDWARE_PROPERTY_DEFAULT_INPUT_DEVICE: AudioObjectPropertySelector =
+        0x64496E20; // 'dIn '
+    pub const K_AUDIO_DEVICE_PROPERTY_STREAMS: AudioObjectPropertySelector = 0x73746D23; // 'stm#'
+    pub const K_AUDIO_DEVICE_PROPERTY_TRANSPORT_TYPE: AudioObjectPropertySelector = 0x7472616E; // 'tran'
+    pub const K_AUDIO_DEVICE_TRANSPORT_TYPE_BLUETOOTH: u32 = 0x626C7565; // 'blue'
+    pub const K_AUDIO_DEVICE_TRANSPORT_TYPE_BLUETOOTH_LE: u32 = 0x626C6561; // 'blea'
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct AudioObjectPropertyAddress {
+        pub selector: AudioObjectPropertySelector,
+        pub scope: AudioObjectPropertyScope,
+        pub element: AudioObjectPropertyElement,
+    }
+
+    #[link(name = "CoreAudio", kind = "framework")]
+    extern "C" {
+        pub fn AudioObjectGetPropertyDataSize(
+            object_id: AudioObjectID,
+            address: *const AudioObjectPropertyAddress,
+            qualifier_data_size: u32,
+            qualifier_data: *const c_void,
+            data_size: *mut u32,
+        ) -> OSStatus;
+
+        pub fn AudioObjectGetPropertyData(
+            object_id: AudioObjectID,
+            address: *const AudioObjectPropertyAddress,
+            qualifier_data_size: u32,
+            qualifier_data: *const c_void,
+            data_size: *mut u32,
+            data: *mut c_void,
+        ) -> OSStatus;
+
+        pub fn AudioObjectSetPropertyData(
+            object_id: AudioObjectID,
+            address: *const AudioObjectPropertyAddress,
+            qualifier_data_size: u32,
+            qualifier_data: *const c_void,
+            data_size: u32,
+            data: *const c_void,
+        ) -> OSStatus;
+    }
+}
+
+/// Which way audio flows through a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Output,
+    Input,
+}
+
+impl Direction {
+    fn scope(self) -> ffi::AudioObjectPropertyScope {
+        match self {
+            Self::Output => ffi::K_AUDIO_OBJECT_PROPERTY_SCOPE_OUTPUT,
+            Self::Input => ffi::K_AUDIO_OBJECT_PROPERTY_SCOPE_INPUT,
+        }
+    }
+
+    fn default_selector(self) -> ffi::AudioObjectPropertySelector {
+        match self {
+            Self::Output => ffi::K_AUDIO_HARDWARE_PROPERTY_DEFAULT_OUTPUT_DEVICE,
+            Self::Input => ffi::K_AUDIO_HARDWARE_PROPERTY_DEFAULT_INPUT_DEVICE,
+        }
+    }
+}
+
+/// An output or input device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioDevice {
+    /// CoreAudio object ID
+    pub id: u32,
+    pub name: String,
+    pub bluetooth: bool,
+    /// The system default for its direction
+    pub default: bool,
+}
+
+/// Output and input devices, sorted by name.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AudioDevices {
+    pub outputs: Vec<AudioDevice>,
+    pub inputs: Vec<AudioDevice>,
+}
+
+impl AudioDevices {
+    pub fn default_device(&self, direction: Direction) -> Option<&AudioDevice> {
+        let devices = match direction {
+            Direction::Output => &self.outputs,
+            Direction::Input => &self.inputs,
+        };
+        devices.iter().find(|device| device.default)
+    }
+}
+
+/// Battery levels a Bluetooth device reports, in percent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HeadsetBattery {
+    /// Single-battery headsets
+    pub main: Option<u8>,
+    pub left: Option<u8>,
+    pub right: Option<u8>,
+    pub case: Option<u8>,
+}
+
+impl HeadsetBattery {
+    /// Short form, e.g. "L 80% R 75% Case 40%" or "60%".
+    pub fn describe(&self) -> String {
+        let parts: Vec<String> = [
+            ("", self.main),
+            ("L ", self.left),
+            ("R ", self.right),
+            ("Case ", self.case),
+        ]
+        .into_iter()
+        .filter_map(|(label, level)| level.map(|level| format!("{}{}%", label, level)))
+        .collect();
+        parts.join(" ")
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn address(
+    selector: ffi::AudioObjectPropertySelector,
+    scope: ffi::AudioObjectPropertyScope,
+) -> ffi::AudioObjectPropertyAddress {
+    ffi::AudioObjectPropertyAddress {
+        selector,
+        scope,
+        element: ffi::K_AUDIO_OBJECT_PROPERTY_ELEMENT_MAIN,
+    }
+}
+
+/// Size in bytes of a property, 0 when the object doesn't have it.
+fn data_size(object_id: ffi::AudioObjectID, address: &ffi::AudioObjectPropertyAddress) -> u32 {
+    let mut size: u32 = 0;
+    let status = unsafe {
+        ffi::AudioObjectGetPropertyDataSize(object_id, address, 0, std::ptr::null(), &mut size)
+    };
+    if status == ffi::K_AUDIO_HARDWARE_NO_ERROR {
+        size
+    } else {
+        0
+    }
+}
+
+/// Reads a 32-bit property value.
+fn read_u32(
+    object_id: ffi::AudioObjectID,
+    address: &ffi::AudioObjectPropertyAddress,
+) -> Option<u32> {
+    let mut value: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let status = unsafe {
+        ffi::AudioObjectGetPropertyData(
+            object_id,
+            address,
+            0,
+            std::ptr::null(),
+            &mut size,
+            &mut value as *mut _ as *mut _,
+        )
+    };
+    (status == ffi::K_AUDIO_HARDWARE_NO_ERROR).then_some(value)
+}
+
+/// Reads the IDs of every audio device.
+fn device_ids() -> Vec<ffi::AudioObjectID> {
+    let devices = address(
+        ffi::K_AUDIO_HARDWARE_PROPERTY_DEVICES,
+        ffi::K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+    );
+    let mut size = data_size(ffi::K_AUDIO_OBJECT_SYSTEM_OBJECT, &devices);
+    let count = size as usize / std::mem::size_of::<ffi::AudioObjectID>();
+    if count == 0 {
+        return Vec::new();
+    }
+    let mut ids: Vec<ffi::AudioObjectID> = vec![0; count];
+    let status = unsafe {
+        ffi::AudioObjectGetPropertyData(
+            ffi::K_AUDIO_OBJECT_SYSTEM_OBJECT,
+            &devices,
+            0,
+            std::ptr::null(),
+            &mut size,
+            ids.as_mut_ptr() as *mut _,
+        )
+    };
+    if status != ffi::K_AUDIO_HARDWARE_NO_ERROR {
+        return Vec::new();
+    }
+    ids.truncate(size as usize / std::mem::size_of::<ffi::AudioObjectID>());
+    ids
+}
+
+fn device_name(device_id: ffi::AudioObjectID) -> Option<String> {
+    let name = address(
+        ffi::K_AUDIO_OBJECT_PROPERTY_NAME,
+        ffi::K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+    );
+    let mut value: CFStringRef = std::ptr::null();
+    let mut size = std::mem::size_of::<CFStringRef>() as u32;
+    let status = unsafe {
+        ffi::AudioObjectGetPropertyData(
+            device_id,
+            &name,
+            0,
+            std::ptr::null(),
+            &mut size,
+            &mut value as *mut _ as *mut _,
+        )
+    };
+    if status != ffi::K_AUDIO_HARDWARE_NO_ERROR || value.is_null() {
+        return None;
+    }
+    // The caller owns the returned string
+    Some(unsafe { CFString::wrap_under_create_rule(value) }.to_string())
+}
+
+fn is_bluetooth(device_id: ffi::AudioObjectID) -> bool {
+    let transport = address(
+        ffi::K_AUDIO_DEVICE_PROPERTY_TRANSPORT_TYPE,
+        ffi::K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+    );
+    matches!(
+        read_u32(device_id, &transport),
+        Some(
+            ffi::K_AUDIO_DEVICE_TRANSPORT_TYPE_BLUETOOTH
+                | ffi::K_AUDIO_DEVICE_TRANSPORT_TYPE_BLUETOOTH_LE
+        )
+    )
+}
+
+fn default_device_id(direction: Direction) -> Option<ffi::AudioObjectID> {
+    let default = address(
+        direction.default_selector(),
+        ffi::K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+    );
+    read_u32(ffi::K_AUDIO_OBJECT_SYSTEM_OBJECT, &default)
+}
+
+/// Lists the output and input devices.
+pub fn devices() -> AudioDevices {
+    let ids = device_ids();
+    let list = |direction: Direction| {
+        let streams = address(ffi::K_AUDIO_DEVICE_PROPERTY_STREAMS, direction.scope());
+        let default = default_device_id(direction);
+        let mut devices: Vec<AudioDevice> = ids
+            .iter()
+            .filter(|&&id| data_size(id, &streams) > 0)
+            .filter_map(|&id| {
+                Some(AudioDevice {
+                    id,
+                    name: device_name(id)?,
+                    bluetooth: is_bluetooth(id),
+                    default: Some(id) == default,
+                })
+            })
+            .collect();
+        devices.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+        devices
+    };
+    AudioDevices {
+        outputs: list(Direction::Output),
+        inputs: list(Direction::Input),
+    }
+}
+
+/// Makes `device_id` the default output or input device.
+pub fn set_default(direction: Direction, device_id: u32) -> Result<(), String> {
+    let default = address(
+        direction.default_selector(),
+        ffi::K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+    );
+    let status = unsafe {
+        ffi::AudioObjectSetPropertyData(
+            ffi::K_AUDIO_OBJECT_SYSTEM_OBJECT,
+            &default,
+            0,
+            std::ptr::null(),
+            std::mem::size_of::<ffi::AudioObjectID>() as u32,
+            &device_id as *const _ as *const _,
+        )
+    };
+    if status == ffi::K_AUDIO_HARDWARE_NO_ERROR {
+        Ok(())
+    } else {
+        Err(format!("CoreAudio error {}", status))
+    }
+}
+
+/// Battery levels of the connected Bluetooth devices that report them, by
+/// device name.
+pub fn bluetooth_batteries() -> Vec<(String, HeadsetBattery)> {
+    let output = Command::new("system_profiler")
+        .args(["SPBluetoothDataType", "-json"])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_batteries(&String::from_utf8_lossy(&output.stdout))
+        }
+        Ok(output) => {
+            log::debug!(
+                "system_profiler failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            Vec::new()
+        }
+        Err(err) => {
+            log::debug!("failed to run system_profiler: {}", err);
+            Vec::new()
+        }
+    }
+}
+
+/// Parses `system_profiler SPBluetoothDataType -json`. Connected devices are
+/// listed as one-key objects, name to properties, with levels like "80%".
+fn parse_batteries(json: &str) -> Vec<(String, HeadsetBattery)> {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(json) else {
+        return Vec::new();
+    };
+    let level = |properties: &serde_json::Value, key: &str| {
+        properties
+            .get(key)?
+            .as_str()?
+            .trim_end_matches('%')
+            .trim()
+            .parse::<u8>()
+            .ok()
+    };
+    let controllers = value
+        .get("SPBluetoothDataType")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    controllers
+        .iter()
+        .filter_map(|controller| controller.get("device_connected")?.as_array())
+        .flatten()
+        .filter_map(|device| device.as_object())
+        .flatten()
+        .filter_map(|(name, properties)| {
+            let battery = HeadsetBattery {
+                main: level(properties, "device_batteryLevelMain"),
+                left: level(properties, "device_batteryLevelLeft"),
+                right: level(properties, "device_batteryLevelRight"),
+                case: level(properties, "device_batteryLevelCase"),
+            };
+            (!battery.is_empty()).then(|| (name.clone(), battery))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bluetooth_battery_levels() {
+        let json = r#"{"SPBluetoothDataType": [{
+            "controller_properties": {"controller_state": "attrib_on"},
+            "device_connected": [
+                {"AirPods Pro": {
+                    "device_batteryLevelCase": "40%",
+                    "device_batteryLevelLeft": "80%",
+                    "device_batteryLevelRight": "75%",
+                    "device_minorType": "Headphones"
+                }},
+                {"MX Master 3": {"device_batteryLevelMain": "60%"}},
+                {"Magic Keyboard": {"device_minorType": "Keyboard"}}
+            ],
+            "device_not_connected": [
+                {"Old Headset": {"device_batteryLevelMain": "10%"}}
+            ]
+        }]}"#;
+        let batteries = parse_batteries(json);
+        assert_eq!(batteries.len(), 2);
+        assert_eq!(batteries[0].0, "AirPods Pro");
+        assert_eq!(batteries[0].1.describe(), "L 80% R 75% Case 40%");
+        assert_eq!(batteries[1].0, "MX Master 3");
+        assert_eq!(batteries[1].1.describe(), "60%");
+        assert!(parse_batteries("not json").is_empty());
+    }
+}
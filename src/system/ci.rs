@@ -0,0 +1,454 @@
+//! CI providers: recent pipeline runs from GitHub Actions, Buildkite and
+//! Jenkins.
+//!
+//! Each provider turns its API's response into [`Run`]s, newest first.
+//! Requests go through `curl` with credentials passed on stdin, so tokens
+//! don't show up in the process list.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+
+const FETCH_TIMEOUT_SECS: &str = "15";
+const GITHUB_API: &str = "https://api.github.com";
+const BUILDKITE_API: &str = "https://api.buildkite.com/v2";
+
+/// Outcome of a run, or where it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Passed,
+    Failed,
+    Running,
+    /// Queued, scheduled or waiting for approval
+    Pending,
+    /// Canceled, skipped or anything the provider doesn't say more about
+    Canceled,
+}
+
+impl Status {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Passed => "passed",
+            Self::Failed => "failed",
+            Self::Running => "running",
+            Self::Pending => "pending",
+            Self::Canceled => "canceled",
+        }
+    }
+}
+
+/// A pipeline run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub number: u64,
+    /// Commit message or run name
+    pub title: String,
+    pub branch: Option<String>,
+    pub status: Status,
+    /// Page of the run in the provider's web UI
+    pub url: String,
+    pub started: Option<DateTime<Utc>>,
+}
+
+/// A source of runs for one pipeline.
+pub trait CiProvider: Send + Sync {
+    /// Name shown in the popup, e.g. `owner/repo`.
+    fn label(&self) -> String;
+    /// Up to `limit` recent runs, newest first.
+    fn runs(&self, limit: usize) -> Result<Vec<Run>, String>;
+}
+
+/// Quotes a value for a curl config file.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Fetches `url` with curl, passing `config` lines (headers, credentials)
+/// on stdin.
+fn fetch(url: &str, config: &[String]) -> Result<String, String> {
+    let mut child = Command::new("curl")
+        // -g: Jenkins' `{0,5}` ranges aren't globs
+        .args(["-sfgL", "-m", FETCH_TIMEOUT_SECS, "-K", "-", url])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        for line in config {
+            let _ = writeln!(stdin, "{}", line);
+        }
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    match output.status.code() {
+        Some(0) => String::from_utf8(output.stdout).map_err(|e| e.to_string()),
+        // --fail: the server answered with an error status
+        Some(22) => Err("request refused (check the token and pipeline)".to_string()),
+        _ => Err(format!("curl exited with {}", output.status)),
+    }
+}
+
+fn parse_time(value: Option<&str>) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value?)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// First line of a commit message.
+fn first_line(text: &str) -> String {
+    text.lines().next().unwrap_or("").trim().to_string()
+}
+
+/// GitHub Actions workflow runs of a repository.
+pub struct GitHubActions {
+    /// `owner/name`
+    pub repo: String,
+    /// Workflow file name or id (None = all workflows)
+    pub workflow: Option<String>,
+    pub branch: Option<String>,
+    /// Needed for private repositories
+    pub token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GitHubRuns {
+    workflow_runs: Vec<GitHubRun>,
+}
+
+#[derive(Deserialize)]
+struct GitHubRun {
+    run_number: u64,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    display_title: Option<String>,
+    #[serde(default)]
+    head_branch: Option<String>,
+    status: Option<String>,
+    conclusion: Option<String>,
+    html_url: String,
+    #[serde(default)]
+    run_started_at: Option<String>,
+    #[serde(default)]
+    created_at: Option<String>,
+}
+
+/// Parses `GET /repos/{repo}/actions/runs`.
+pub fn parse_github(body: &str) -> Result<Vec<Run>, String> {
+    let runs: GitHubRuns = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    Ok(runs
+        .workflow_runs
+        .into_iter()
+        .map(|run| {
+            let status = match (run.status.as_deref(), run.conclusion.as_deref()) {
+                (Some("completed"), Some("success")) => Status::Passed,
+                (
+                    Some("completed"),
+                    Some("failure" | "timed_out" | "startup_failure" | "action_required"),
+                ) => Status::Failed,
+                (Some("completed"), _) => Status::Canceled,
+                (Some("in_progress"), _) => Status::Running,
+                _ => Status::Pending,
+            };
+            Run {
+                number: run.run_number,
+                title: first_line(
+                    run.display_title
+                        .as_deref()
+                        .or(run.name.as_deref())
+                        .unwrap_or(""),
+                ),
+                branch: run.head_branch,
+                status,
+                url: run.html_url,
+                started: parse_time(run.run_started_at.or(run.created_at).as_deref()),
+            }
+        })
+        .collect())
+}
+
+impl CiProvider for GitHubActions {
+    fn label(&self) -> String {
+        match &self.branch {
+            Some(branch) => format!("{} · {}", self.repo, branch),
+            None => self.repo.clone(),
+        }
+    }
+
+    fn runs(&self, limit: usize) -> Result<Vec<Run>, String> {
+        let mut url = match &self.workflow {
+            Some(workflow) => format!(
+                "{}/repos/{}/actions/workflows/{}/runs?per_page={}",
+                GITHUB_API, self.repo, workflow, limit
+            ),
+            None => format!(
+                "{}/repos/{}/actions/runs?per_page={}",
+                GITHUB_API, self.repo, limit
+            ),
+        };
+        if let Some(branch) = &self.branch {
+            url.push_str(&format!("&branch={}", branch));
+        }
+        let mut config = vec![
+            format!("header = {}", quote("Accept: application/vnd.github+json")),
+            format!("user-agent = {}", quote("sinew")),
+        ];
+        if let Some(token) = &self.token {
+            config.push(format!(
+                "header = {}",
+                quote(&format!("Authorization: Bearer {}", token))
+            ));
+        }
+        parse_github(&fetch(&url, &config)?)
+    }
+}
+
+/// Buildkite builds of a pipeline.
+pub struct Buildkite {
+    pub org: String,
+    /// Pipeline slug
+    pub pipeline: String,
+    pub branch: Option<String>,
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+struct BuildkiteBuild {
+    number: u64,
+    state: String,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    branch: Option<String>,
+    web_url: String,
+    #[serde(default)]
+    started_at: Option<String>,
+    #[serde(default)]
+    created_at: Option<String>,
+}
+
+/// Parses `GET /organizations/{org}/pipelines/{pipeline}/builds`.
+pub fn parse_buildkite(body: &str) -> Result<Vec<Run>, String> {
+    let builds: Vec<BuildkiteBuild> = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    Ok(builds
+        .into_iter()
+        .map(|build| {
+            let status = match build.state.as_str() {
+                "passed" => Status::Passed,
+                // Failing builds are still running, but have a failed job
+                "failed" | "failing" => Status::Failed,
+                "running" => Status::Running,
+                "scheduled" | "creating" | "blocked" => Status::Pending,
+                _ => Status::Canceled,
+            };
+            Run {
+                number: build.number,
+                title: first_line(build.message.as_deref().unwrap_or("")),
+                branch: build.branch,
+                status,
+                url: build.web_url,
+                started: parse_time(build.started_at.or(build.created_at).as_deref()),
+            }
+        })
+        .collect())
+}
+
+impl CiProvider for Buildkite {
+    fn label(&self) -> String {
+        match &self.branch {
+            Some(branch) => format!("{} · {}", self.pipeline, branch),
+            None => self.pipeline.clone(),
+        }
+    }
+
+    fn runs(&self, limit: usize) -> Result<Vec<Run>, String> {
+        let mut url = format!(
+            "{}/organizations/{}/pipelines/{}/builds?per_page={}",
+            BUILDKITE_API, self.org, self.pipeline, limit
+        );
+        if let Some(branch) = &self.branch {
+            url.push_str(&format!("&branch={}", branch));
+        }
+        let config = [format!(
+            "header = {}",
+            quote(&format!("Authorization: Bearer {}", self.token))
+        )];
+        parse_buildkite(&fetch(&url, &config)?)
+    }
+}
+
+/// Jenkins builds of a job.
+pub struct Jenkins {
+    /// Job URL, e.g. `https://ci.example.com/job/web`
+    pub url: String,
+    /// User and API token (None = anonymous)
+    pub credentials: Option<(String, String)>,
+}
+
+#[derive(Deserialize)]
+struct JenkinsJob {
+    #[serde(default)]
+    builds: Vec<JenkinsBuild>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JenkinsBuild {
+    number: u64,
+    #[serde(default)]
+    display_name: Option<String>,
+    #[serde(default)]
+    building: bool,
+    result: Option<String>,
+    url: String,
+    /// Milliseconds since the epoch
+    #[serde(default)]
+    timestamp: Option<i64>,
+}
+
+/// Parses `GET {job}/api/json?tree=builds[...]`.
+pub fn parse_jenkins(body: &str) -> Result<Vec<Run>, String> {
+    let job: JenkinsJob = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    Ok(job
+        .builds
+        .into_iter()
+        .map(|build| {
+            let status = match (build.building, build.result.as_deref()) {
+                (true, _) => Status::Running,
+                (false, Some("SUCCESS")) => Status::Passed,
+                (false, Some("FAILURE" | "UNSTABLE")) => Status::Failed,
+                (false, None) => Status::Pending,
+                (false, Some(_)) => Status::Canceled,
+            };
+            Run {
+                number: build.number,
+                title: build
+                    .display_name
+                    .unwrap_or_else(|| format!("#{}", build.number)),
+                branch: None,
+                status,
+                url: build.url,
+                started: build
+                    .timestamp
+                    .and_then(|ms| Utc.timestamp_millis_opt(ms).single()),
+            }
+        })
+        .collect())
+}
+
+impl CiProvider for Jenkins {
+    fn label(&self) -> String {
+        // The job name is the last `/job/<name>` of the URL
+        self.url
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or(&self.url)
+            .to_string()
+    }
+
+    fn runs(&self, limit: usize) -> Result<Vec<Run>, String> {
+        let url = format!(
+            "{}/api/json?tree=builds[number,displayName,building,result,url,timestamp]{{0,{}}}",
+            self.url.trim_end_matches('/'),
+            limit
+        );
+        let config: Vec<String> = self
+            .credentials
+            .iter()
+            .map(|(user, token)| format!("user = {}", quote(&format!("{}:{}", user, token))))
+            .collect();
+        parse_jenkins(&fetch(&url, &config)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_github_runs() {
+        let body = r#"{"total_count": 3, "workflow_runs": [
+            {"run_number": 42, "name": "CI", "display_title": "Fix login\n\nDetails", "head_branch": "main",
+             "status": "completed", "conclusion": "failure", "html_url": "https://github.com/o/r/actions/runs/1",
+             "run_started_at": "2026-03-10T09:30:00Z"},
+            {"run_number": 41, "name": "CI", "head_branch": "main", "status": "in_progress", "conclusion": null,
+             "html_url": "https://github.com/o/r/actions/runs/2", "created_at": "2026-03-10T09:00:00Z"},
+            {"run_number": 40, "name": "CI", "status": "completed", "conclusion": "skipped",
+             "html_url": "https://github.com/o/r/actions/runs/3"}
+        ]}"#;
+        let runs = parse_github(body).unwrap();
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs[0].status, Status::Failed);
+        assert_eq!(runs[0].title, "Fix login");
+        assert_eq!(runs[0].branch.as_deref(), Some("main"));
+        assert_eq!(
+            runs[0].started,
+            Utc.with_ymd_and_hms(2026, 3, 10, 9, 30, 0).single()
+        );
+        assert_eq!(runs[1].status, Status::Running);
+        assert_eq!(runs[1].title, "CI");
+        assert!(runs[1].started.is_some());
+        assert_eq!(runs[2].status, Status::Canceled);
+        assert!(parse_github(r#"{"message": "Not Found"}"#).is_err());
+    }
+
+    #[test]
+    fn parses_buildkite_builds() {
+        let body = r#"[
+            {"number": 7, "state": "failing", "message": "Bump deps", "branch": "main",
+             "web_url": "https://buildkite.com/acme/web/builds/7", "started_at": "2026-03-10T09:30:00.000Z"},
+            {"number": 6, "state": "passed", "message": null, "web_url": "https://buildkite.com/acme/web/builds/6"},
+            {"number": 5, "state": "blocked", "web_url": "https://buildkite.com/acme/web/builds/5"}
+        ]"#;
+        let runs = parse_buildkite(body).unwrap();
+        let statuses: Vec<Status> = runs.iter().map(|r| r.status).collect();
+        assert_eq!(statuses, [Status::Failed, Status::Passed, Status::Pending]);
+        assert_eq!(runs[0].title, "Bump deps");
+        assert!(runs[0].started.is_some());
+        assert_eq!(runs[1].title, "");
+    }
+
+    #[test]
+    fn parses_jenkins_builds() {
+        let body = r##"{"_class": "hudson.model.FreeStyleProject", "builds": [
+            {"number": 12, "displayName": "#12", "building": true, "result": null,
+             "url": "https://ci.example.com/job/web/12/", "timestamp": 1773135000000},
+            {"number": 11, "building": false, "result": "UNSTABLE", "url": "https://ci.example.com/job/web/11/"},
+            {"number": 10, "building": false, "result": "ABORTED", "url": "https://ci.example.com/job/web/10/"}
+        ]}"##;
+        let runs = parse_jenkins(body).unwrap();
+        let statuses: Vec<Status> = runs.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            [Status::Running, Status::Failed, Status::Canceled]
+        );
+        assert_eq!(runs[1].title, "#11");
+        assert_eq!(
+            runs[0].started,
+            Utc.timestamp_millis_opt(1773135000000).single()
+        );
+    }
+
+    #[test]
+    fn labels_pipelines() {
+        let github = GitHubActions {
+            repo: "acme/web".to_string(),
+            workflow: None,
+            branch: Some("main".to_string()),
+            token: None,
+        };
+        assert_eq!(github.label(), "acme/web · main");
+        let jenkins = Jenkins {
+            url: "https://ci.example.com/job/web/".to_string(),
+            credentials: None,
+        };
+        assert_eq!(jenkins.label(), "web");
+    }
+
+    #[test]
+    fn quotes_curl_config_values() {
+        assert_eq!(quote("a\"b\\c"), r#""a\"b\\c""#);
+    }
+}
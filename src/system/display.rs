@@ -0,0 +1,435 @@
+//! Displays, their modes and mirroring from CoreGraphics, plus Night Shift
+//! and True Tone from the private CoreBrightness framework.
+//!
+//! Mode and mirroring changes last for the login session, like picking a
+//! resolution in System Settings without "remember". Night Shift and True
+//! Tone are None on Macs (or macOS versions) that don't offer them.
+
+use std::ffi::{c_char, c_void};
+use std::sync::OnceLock;
+
+use core_foundation::base::TCFType;
+use core_foundation::boolean::CFBoolean;
+use core_foundation::dictionary::CFDictionary;
+use core_foundation::string::CFString;
+use objc2::rc::Retained;
+use objc2::runtime::{AnyClass, AnyObject, Bool, Sel};
+use objc2::{msg_send, sel};
+
+// CoreGraphics display configuration FFI
+mod ffi {
+    use std::ffi::c_void;
+
+    use core_foundation::string::CFStringRef;
+
+    pub type CGDirectDisplayID = u32;
+    pub type CGDisplayModeRef = *mut c_void;
+    pub type CGDisplayConfigRef = *mut c_void;
+    pub type CGError = i32;
+
+    pub const K_CG_ERROR_SUCCESS: CGError = 0;
+    pub const K_CG_NULL_DIRECT_DISPLAY: CGDirectDisplayID = 0;
+    pub const K_CG_CONFIGURE_FOR_SESSION: u32 = 1;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        pub static kCGDisplayShowDuplicateLowResolutionModes: CFStringRef;
+
+        pub fn CGMainDisplayID() -> CGDirectDisplayID;
+        pub fn CGGetActiveDisplayList(
+            max_displays: u32,
+            displays: *mut CGDirectDisplayID,
+            count: *mut u32,
+        ) -> CGError;
+        pub fn CGDisplayIsBuiltin(display: CGDirectDisplayID) -> u32;
+        pub fn CGDisplayIsInMirrorSet(display: CGDirectDisplayID) -> u32;
+        pub fn CGDisplayCopyDisplayMode(display: CGDirectDisplayID) -> CGDisplayModeRef;
+        pub fn CGDisplayCopyAllDisplayModes(
+            display: CGDirectDisplayID,
+            options: *const c_void,
+        ) -> *const c_void;
+        pub fn CGDisplayModeGetWidth(mode: CGDisplayModeRef) -> usize;
+        pub fn CGDisplayModeGetHeight(mode: CGDisplayModeRef) -> usize;
+        pub fn CGDisplayModeGetPixelWidth(mode: CGDisplayModeRef) -> usize;
+        pub fn CGDisplayModeGetRefreshRate(mode: CGDisplayModeRef) -> f64;
+        pub fn CGDisplayModeIsUsableForDesktopGUI(mode: CGDisplayModeRef) -> bool;
+        pub fn CGDisplayModeRelease(mode: CGDisplayModeRef);
+        pub fn CGDisplaySetDisplayMode(
+            display: CGDirectDisplayID,
+            mode: CGDisplayModeRef,
+            options: *const c_void,
+        ) -> CGError;
+        pub fn CGBeginDisplayConfiguration(config: *mut CGDisplayConfigRef) -> CGError;
+        pub fn CGConfigureDisplayMirrorOfDisplay(
+            config: CGDisplayConfigRef,
+            display: CGDirectDisplayID,
+            primary: CGDirectDisplayID,
+        ) -> CGError;
+        pub fn CGCompleteDisplayConfiguration(config: CGDisplayConfigRef, option: u32) -> CGError;
+        pub fn CGCancelDisplayConfiguration(config: CGDisplayConfigRef) -> CGError;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        pub fn CFArrayGetCount(array: *const c_void) -> isize;
+        pub fn CFArrayGetValueAtIndex(array: *const c_void, index: isize) -> *const c_void;
+        pub fn CFRelease(object: *const c_void);
+    }
+}
+
+/// Most displays CoreGraphics is asked about at once.
+const MAX_DISPLAYS: u32 = 16;
+
+/// A display mode, in points with the pixel width for HiDPI modes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayMode {
+    pub width: u32,
+    pub height: u32,
+    pub pixel_width: u32,
+    /// Hz; None for panels that don't report one
+    pub refresh: Option<f64>,
+}
+
+impl DisplayMode {
+    /// Whether the mode draws two (or more) pixels per point.
+    pub fn hidpi(&self) -> bool {
+        self.pixel_width > self.width
+    }
+
+    /// Whether `other` has the same size in points.
+    fn same_size(&self, other: &DisplayMode) -> bool {
+        self.width == other.width && self.height == other.height
+    }
+}
+
+/// An active display.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Display {
+    pub id: u32,
+    pub builtin: bool,
+    pub main: bool,
+    pub mirrored: bool,
+    pub mode: Option<DisplayMode>,
+    /// Modes usable for the desktop
+    pub modes: Vec<DisplayMode>,
+}
+
+impl Display {
+    /// "Built-in display" or "Display <id>".
+    pub fn name(&self) -> String {
+        if self.builtin {
+            "Built-in display".to_string()
+        } else {
+            format!("Display {}", self.id)
+        }
+    }
+}
+
+/// Sizes to offer for a display, largest first, keeping to the current
+/// mode's HiDPI-ness so the list doesn't repeat each size.
+pub fn resolutions(modes: &[DisplayMode], hidpi: bool) -> Vec<(u32, u32)> {
+    let mut sizes: Vec<(u32, u32)> = modes
+        .iter()
+        .filter(|mode| mode.hidpi() == hidpi)
+        .map(|mode| (mode.width, mode.height))
+        .collect();
+    sizes.sort_by(|a, b| b.cmp(a));
+    sizes.dedup();
+    sizes
+}
+
+/// Refresh rates offered at the size of `current`, highest first.
+pub fn refresh_rates(modes: &[DisplayMode], current: &DisplayMode) -> Vec<f64> {
+    let mut rates: Vec<f64> = modes
+        .iter()
+        .filter(|mode| mode.same_size(current) && mode.hidpi() == current.hidpi())
+        .filter_map(|mode| mode.refresh)
+        .collect();
+    rates.sort_by(|a, b| b.total_cmp(a));
+    rates.dedup_by(|a, b| (*a - *b).abs() < 0.01);
+    rates
+}
+
+/// The mode to switch to for a size and refresh rate: the same HiDPI-ness
+/// as `current` when there's a choice, then `refresh` or the fastest.
+pub fn pick_mode(
+    modes: &[DisplayMode],
+    width: u32,
+    height: u32,
+    refresh: Option<f64>,
+    hidpi: bool,
+) -> Option<usize> {
+    let rate = |mode: &DisplayMode| mode.refresh.unwrap_or(0.0);
+    modes
+        .iter()
+        .enumerate()
+        .filter(|(_, mode)| mode.width == width && mode.height == height)
+        .max_by(|(_, a), (_, b)| {
+            let matches = |mode: &DisplayMode| {
+                let refresh_matches =
+                    refresh.is_none_or(|refresh| (rate(mode) - refresh).abs() < 0.01);
+                (mode.hidpi() == hidpi, refresh_matches)
+            };
+            matches(a)
+                .cmp(&matches(b))
+                .then_with(|| rate(a).total_cmp(&rate(b)))
+        })
+        .map(|(index, _)| index)
+}
+
+unsafe fn read_mode(mode: ffi::CGDisplayModeRef) -> DisplayMode {
+    let refresh = ffi::CGDisplayModeGetRefreshRate(mode);
+    DisplayMode {
+        width: ffi::CGDisplayModeGetWidth(mode) as u32,
+        height: ffi::CGDisplayModeGetHeight(mode) as u32,
+        pixel_width: ffi::CGDisplayModeGetPixelWidth(mode) as u32,
+        refresh: (refresh > 0.0).then_some(refresh),
+    }
+}
+
+/// Calls `f` with every desktop mode of `display`, HiDPI ones included.
+fn with_modes<T>(
+    display: ffi::CGDirectDisplayID,
+    f: impl FnOnce(&[ffi::CGDisplayModeRef]) -> T,
+) -> T {
+    let options = unsafe {
+        CFDictionary::from_CFType_pairs(&[(
+            CFString::wrap_under_get_rule(ffi::kCGDisplayShowDuplicateLowResolutionModes),
+            CFBoolean::true_value(),
+        )])
+    };
+    unsafe {
+        let array = ffi::CGDisplayCopyAllDisplayModes(
+            display,
+            options.as_concrete_TypeRef() as *const c_void,
+        );
+        if array.is_null() {
+            return f(&[]);
+        }
+        let modes: Vec<ffi::CGDisplayModeRef> = (0..ffi::CFArrayGetCount(array))
+            .map(|index| ffi::CFArrayGetValueAtIndex(array, index) as ffi::CGDisplayModeRef)
+            .filter(|&mode| ffi::CGDisplayModeIsUsableForDesktopGUI(mode))
+            .collect();
+        let result = f(&modes);
+        ffi::CFRelease(array);
+        result
+    }
+}
+
+fn active_display_ids() -> Vec<ffi::CGDirectDisplayID> {
+    let mut ids = [0; MAX_DISPLAYS as usize];
+    let mut count = 0;
+    let status = unsafe { ffi::CGGetActiveDisplayList(MAX_DISPLAYS, ids.as_mut_ptr(), &mut count) };
+    if status != ffi::K_CG_ERROR_SUCCESS {
+        return Vec::new();
+    }
+    ids[..count as usize].to_vec()
+}
+
+/// Lists the active displays, main display first.
+pub fn displays() -> Vec<Display> {
+    let main = unsafe { ffi::CGMainDisplayID() };
+    let mut displays: Vec<Display> = active_display_ids()
+        .into_iter()
+        .map(|id| unsafe {
+            let current = ffi::CGDisplayCopyDisplayMode(id);
+            let mode = (!current.is_null()).then(|| read_mode(current));
+            if !current.is_null() {
+                ffi::CGDisplayModeRelease(current);
+            }
+            Display {
+                id,
+                builtin: ffi::CGDisplayIsBuiltin(id) != 0,
+                main: id == main,
+                mirrored: ffi::CGDisplayIsInMirrorSet(id) != 0,
+                mode,
+                modes: with_modes(id, |modes| modes.iter().map(|&m| read_mode(m)).collect()),
+            }
+        })
+        .collect();
+    displays.sort_by_key(|display| !display.main);
+    displays
+}
+
+/// Switches `display` to a size and refresh rate, keeping its HiDPI-ness
+/// where that size allows.
+pub fn set_mode(display: u32, width: u32, height: u32, refresh: Option<f64>) -> Result<(), String> {
+    let hidpi = displays()
+        .into_iter()
+        .find(|d| d.id == display)
+        .and_then(|d| d.mode)
+        .is_some_and(|mode| mode.hidpi());
+    with_modes(display, |modes| {
+        let described: Vec<DisplayMode> = modes
+            .iter()
+            .map(|&mode| unsafe { read_mode(mode) })
+            .collect();
+        let index = pick_mode(&described, width, height, refresh, hidpi)
+            .ok_or_else(|| format!("{}×{} isn't available", width, height))?;
+        let status =
+            unsafe { ffi::CGDisplaySetDisplayMode(display, modes[index], std::ptr::null()) };
+        if status == ffi::K_CG_ERROR_SUCCESS {
+            Ok(())
+        } else {
+            Err(format!("CoreGraphics error {}", status))
+        }
+    })
+}
+
+/// Mirrors every display to the main one, or stops mirroring.
+pub fn set_mirroring(on: bool) -> Result<(), String> {
+    let main = unsafe { ffi::CGMainDisplayID() };
+    let check = |status: ffi::CGError| {
+        if status == ffi::K_CG_ERROR_SUCCESS {
+            Ok(())
+        } else {
+            Err(format!("CoreGraphics error {}", status))
+        }
+    };
+    unsafe {
+        let mut config: ffi::CGDisplayConfigRef = std::ptr::null_mut();
+        check(ffi::CGBeginDisplayConfiguration(&mut config))?;
+        let primary = if on {
+            main
+        } else {
+            ffi::K_CG_NULL_DIRECT_DISPLAY
+        };
+        for display in active_display_ids().into_iter().filter(|&id| id != main) {
+            if let Err(err) = check(ffi::CGConfigureDisplayMirrorOfDisplay(
+                config, display, primary,
+            )) {
+                ffi::CGCancelDisplayConfiguration(config);
+                return Err(err);
+            }
+        }
+        check(ffi::CGCompleteDisplayConfiguration(
+            config,
+            ffi::K_CG_CONFIGURE_FOR_SESSION,
+        ))
+    }
+}
+
+/// Loads CoreBrightness, which holds the Night Shift and True Tone clients.
+fn core_brightness() -> bool {
+    static LOADED: OnceLock<bool> = OnceLock::new();
+    *LOADED.get_or_init(|| {
+        extern "C" {
+            fn dlopen(path: *const c_char, mode: i32) -> *mut c_void;
+        }
+        const RTLD_LAZY: i32 = 1;
+        let handle = unsafe {
+            dlopen(
+                c"/System/Library/PrivateFrameworks/CoreBrightness.framework/CoreBrightness"
+                    .as_ptr(),
+                RTLD_LAZY,
+            )
+        };
+        if handle.is_null() {
+            log::debug!("display: CoreBrightness unavailable");
+        }
+        !handle.is_null()
+    })
+}
+
+fn client(class: &std::ffi::CStr) -> Option<Retained<AnyObject>> {
+    if !core_brightness() {
+        return None;
+    }
+    let class = AnyClass::get(class)?;
+    unsafe { msg_send![class, new] }
+}
+
+/// `CBBlueLightClient` status, as `getBlueLightStatus:` fills it.
+#[repr(C)]
+#[derive(Default)]
+struct BlueLightStatus {
+    active: u8,
+    enabled: u8,
+    sun_schedule_permitted: u8,
+    mode: i32,
+    /// From and to hour and minute
+    schedule: [i32; 4],
+    disable_flags: u64,
+    available: u8,
+}
+
+/// Whether Night Shift is on.
+pub fn night_shift() -> Option<bool> {
+    let client = client(c"CBBlueLightClient")?;
+    let mut status = BlueLightStatus::default();
+    // The status is an anonymous struct objc2 can't describe, so this goes
+    // through objc_msgSend directly
+    type GetStatusFn = unsafe extern "C" fn(*const AnyObject, Sel, *mut BlueLightStatus) -> Bool;
+    extern "C" {
+        fn objc_msgSend();
+    }
+    let read = unsafe {
+        let get_status: GetStatusFn = std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+        get_status(&*client, sel!(getBlueLightStatus:), &mut status)
+    };
+    (read.as_bool() && status.available != 0).then_some(status.enabled != 0)
+}
+
+/// Turns Night Shift on or off until its schedule next changes it.
+pub fn set_night_shift(on: bool) -> bool {
+    let Some(client) = client(c"CBBlueLightClient") else {
+        return false;
+    };
+    unsafe { msg_send![&*client, setEnabled: Bool::new(on)] }
+}
+
+/// Whether True Tone is on; None where the display doesn't support it.
+pub fn true_tone() -> Option<bool> {
+    let client = client(c"CBTrueToneClient")?;
+    unsafe {
+        let supported: bool = msg_send![&*client, supported];
+        let available: bool = msg_send![&*client, available];
+        if !(supported && available) {
+            return None;
+        }
+        let enabled: bool = msg_send![&*client, enabled];
+        Some(enabled)
+    }
+}
+
+pub fn set_true_tone(on: bool) -> bool {
+    let Some(client) = client(c"CBTrueToneClient") else {
+        return false;
+    };
+    unsafe { msg_send![&*client, setEnabled: Bool::new(on)] }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mode(width: u32, height: u32, scale: u32, refresh: Option<f64>) -> DisplayMode {
+        DisplayMode {
+            width,
+            height,
+            pixel_width: width * scale,
+            refresh,
+        }
+    }
+
+    #[test]
+    fn lists_sizes_and_rates_at_the_current_scale() {
+        let modes = vec![
+            mode(1512, 982, 2, Some(120.0)),
+            mode(1512, 982, 2, Some(60.0)),
+            mode(1512, 982, 1, Some(120.0)),
+            mode(1800, 1169, 2, Some(120.0)),
+            mode(3024, 1964, 1, Some(120.0)),
+        ];
+        assert_eq!(resolutions(&modes, true), vec![(1800, 1169), (1512, 982)]);
+        assert_eq!(resolutions(&modes, false), vec![(3024, 1964), (1512, 982)]);
+        assert_eq!(refresh_rates(&modes, &modes[0]), vec![120.0, 60.0]);
+
+        // Same scale and the asked-for rate win, then the fastest
+        assert_eq!(pick_mode(&modes, 1512, 982, Some(60.0), true), Some(1));
+        assert_eq!(pick_mode(&modes, 1512, 982, None, true), Some(0));
+        assert_eq!(pick_mode(&modes, 1512, 982, None, false), Some(2));
+        assert_eq!(pick_mode(&modes, 3024, 1964, None, true), Some(4));
+        assert_eq!(pick_mode(&modes, 800, 600, None, true), None);
+    }
+}
@@ -0,0 +1,26 @@
+//! Machine surroundings that config profiles can switch on: the Wi-Fi
+//! network and the number of connected displays.
+
+use std::process::Command;
+
+/// Returns the name of the Wi-Fi network, or None when not connected.
+pub fn current_ssid() -> Option<String> {
+    let output = Command::new("sh")
+        .args(["-c", "/System/Library/PrivateFrameworks/Apple80211.framework/Versions/Current/Resources/airport -I | grep ' SSID' | cut -d ':' -f 2 | tr -d ' '"])
+        .output()
+        .ok()
+        .and_then(|o| String::from_utf8(o.stdout).ok())?;
+    let ssid = output.trim();
+    if ssid.is_empty() {
+        None
+    } else {
+        Some(ssid.to_string())
+    }
+}
+
+/// Returns the number of active displays.
+pub fn display_count() -> usize {
+    core_graphics::display::CGDisplay::active_displays()
+        .map(|displays| displays.len())
+        .unwrap_or(1)
+}
@@ -0,0 +1,657 @@
+//! Minimal iCalendar (RFC 5545) reader.
+//!
+//! Handles unfolded content lines and `VEVENT` blocks with a summary,
+//! location, all-day or timed start and end (floating, UTC or `TZID`), and
+//! repetition through `RRULE` (`FREQ`, `INTERVAL`, `COUNT`, `UNTIL`, `BYDAY`,
+//! `BYMONTHDAY`), `EXDATE` and moved instances (`RECURRENCE-ID`).
+//! [`expand`] turns events into local-time occurrences within a window.
+
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+/// Periods a rule is stepped through per window before expansion gives up.
+const MAX_PERIODS: u32 = 5000;
+
+/// One content line: `NAME;PARAM=VALUE:value`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Property {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+impl Property {
+    /// Parses a content line, returning None if it has no `:`.
+    pub fn parse(line: &str) -> Option<Self> {
+        let (head, value) = split_value(line)?;
+        let mut parts = head.split(';');
+        let name = parts.next()?.to_ascii_uppercase();
+        let params = parts
+            .filter_map(|param| {
+                let (key, value) = param.split_once('=')?;
+                Some((
+                    key.to_ascii_uppercase(),
+                    value.trim_matches('"').to_string(),
+                ))
+            })
+            .collect();
+        Some(Self {
+            name,
+            params,
+            value: value.to_string(),
+        })
+    }
+
+    /// Returns the value of the parameter `key`.
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Splits a content line at the first `:` outside a quoted parameter.
+fn split_value(line: &str) -> Option<(&str, &str)> {
+    let mut quoted = false;
+    for (i, ch) in line.char_indices() {
+        match ch {
+            '"' => quoted = !quoted,
+            ':' if !quoted => return Some((&line[..i], &line[i + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Joins folded lines (continuations start with a space or tab).
+pub fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in text.lines() {
+        let raw = raw.trim_end_matches('\r');
+        match (raw.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ if raw.is_empty() => {}
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines
+}
+
+/// Undoes text escaping (`\,`, `\;`, `\n`, `\\`).
+pub fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            out.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Parses a `DATE` (`20261225`) or `DATE-TIME` (`20261225T090000`, with an
+/// optional trailing `Z`) value. Dates start at midnight.
+pub fn parse_date_time(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim().trim_end_matches('Z');
+    match value.split_once('T') {
+        Some(_) => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok(),
+        None => NaiveDate::parse_from_str(value, "%Y%m%d")
+            .ok()
+            .and_then(|d| d.and_hms_opt(0, 0, 0)),
+    }
+}
+
+/// What an event's times are relative to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventZone {
+    /// No zone: the same wall-clock time everywhere
+    Floating,
+    Utc,
+    Named(Tz),
+}
+
+impl EventZone {
+    fn of(prop: &Property) -> Self {
+        if prop.value.trim().ends_with('Z') {
+            return Self::Utc;
+        }
+        prop.param("TZID")
+            .and_then(|tzid| tzid.parse::<Tz>().ok())
+            .map(Self::Named)
+            .unwrap_or(Self::Floating)
+    }
+
+    /// Converts a wall-clock time in this zone to local time.
+    pub fn to_local(self, time: NaiveDateTime) -> NaiveDateTime {
+        let utc: Option<DateTime<Utc>> = match self {
+            Self::Floating => return time,
+            Self::Utc => Some(Utc.from_utc_datetime(&time)),
+            Self::Named(tz) => tz
+                .from_local_datetime(&time)
+                .earliest()
+                .map(|t| t.with_timezone(&Utc)),
+        };
+        utc.map(|t| t.with_timezone(&Local).naive_local())
+            .unwrap_or(time)
+    }
+}
+
+/// How often a rule repeats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A parsed `RRULE`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    /// Last possible start, in the event's zone
+    pub until: Option<NaiveDateTime>,
+    /// Weekdays with an optional ordinal within the month (`2MO`, `-1FR`)
+    pub by_day: Vec<(Option<i32>, Weekday)>,
+    /// Days of the month, negative from the end
+    pub by_month_day: Vec<i32>,
+}
+
+impl Rule {
+    /// Parses an `RRULE` value, returning None without a supported `FREQ`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut frequency = None;
+        let mut rule = Self {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+        };
+        for part in value.split(';') {
+            let Some((key, value)) = part.split_once('=') else {
+                continue;
+            };
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Some(Frequency::Daily),
+                        "WEEKLY" => Some(Frequency::Weekly),
+                        "MONTHLY" => Some(Frequency::Monthly),
+                        "YEARLY" => Some(Frequency::Yearly),
+                        _ => None,
+                    }
+                }
+                "INTERVAL" => rule.interval = value.parse().unwrap_or(1).max(1),
+                "COUNT" => rule.count = value.parse().ok(),
+                "UNTIL" => rule.until = parse_date_time(value),
+                "BYDAY" => rule.by_day = value.split(',').filter_map(parse_by_day).collect(),
+                "BYMONTHDAY" => {
+                    rule.by_month_day = value.split(',').filter_map(|d| d.parse().ok()).collect()
+                }
+                _ => {}
+            }
+        }
+        rule.frequency = frequency?;
+        Some(rule)
+    }
+
+    /// Dates of the occurrences in the `period`th repetition counted from
+    /// `start`, sorted. May include dates before `start`.
+    fn period_dates(&self, start: NaiveDate, period: u32) -> Vec<NaiveDate> {
+        let step = period as i64 * self.interval as i64;
+        let mut dates: Vec<NaiveDate> = match self.frequency {
+            Frequency::Daily => vec![start + Duration::days(step)],
+            Frequency::Weekly if self.by_day.is_empty() => vec![start + Duration::weeks(step)],
+            Frequency::Weekly => {
+                let monday = start - Duration::days(start.weekday().num_days_from_monday() as i64)
+                    + Duration::weeks(step);
+                self.by_day
+                    .iter()
+                    .map(|(_, day)| monday + Duration::days(day.num_days_from_monday() as i64))
+                    .collect()
+            }
+            Frequency::Monthly => {
+                let months = start.year() as i64 * 12 + start.month0() as i64 + step;
+                let (year, month) = (
+                    months.div_euclid(12) as i32,
+                    months.rem_euclid(12) as u32 + 1,
+                );
+                if self.by_day.is_empty() {
+                    let days = if self.by_month_day.is_empty() {
+                        vec![start.day() as i32]
+                    } else {
+                        self.by_month_day.clone()
+                    };
+                    days.iter()
+                        .filter_map(|day| month_day(year, month, *day))
+                        .collect()
+                } else {
+                    self.by_day
+                        .iter()
+                        .flat_map(|(nth, day)| weekdays_in_month(year, month, *nth, *day))
+                        .collect()
+                }
+            }
+            Frequency::Yearly => {
+                NaiveDate::from_ymd_opt(start.year() + step as i32, start.month(), start.day())
+                    .into_iter()
+                    .collect()
+            }
+        };
+        dates.sort();
+        dates
+    }
+
+    /// The first period with dates that can fall on or after `from`. Only
+    /// `COUNT` needs the periods before it walked, to count them.
+    fn first_period(&self, start: NaiveDate, from: NaiveDate) -> u32 {
+        if self.count.is_some() || from <= start {
+            return 0;
+        }
+        let elapsed = match self.frequency {
+            Frequency::Daily => (from - start).num_days(),
+            Frequency::Weekly => (from - start).num_days() / 7,
+            Frequency::Monthly => {
+                (from.year() as i64 * 12 + from.month0() as i64)
+                    - (start.year() as i64 * 12 + start.month0() as i64)
+            }
+            Frequency::Yearly => (from.year() - start.year()) as i64,
+        };
+        u32::try_from(elapsed / self.interval as i64).unwrap_or(u32::MAX)
+    }
+}
+
+/// Parses a `BYDAY` entry like `MO`, `2TU` or `-1FR`.
+fn parse_by_day(entry: &str) -> Option<(Option<i32>, Weekday)> {
+    let entry = entry.trim();
+    let split = entry.len().checked_sub(2)?;
+    let day = match entry.get(split..)?.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    };
+    let nth = &entry[..split];
+    if nth.is_empty() {
+        return Some((None, day));
+    }
+    Some((Some(nth.trim_start_matches('+').parse().ok()?), day))
+}
+
+/// The `day`th day of a month, counting back from the end when negative.
+fn month_day(year: i32, month: u32, day: i32) -> Option<NaiveDate> {
+    if day > 0 {
+        return NaiveDate::from_ymd_opt(year, month, day as u32);
+    }
+    let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let next = first.checked_add_months(chrono::Months::new(1))?;
+    let date = next + Duration::days(day as i64);
+    (date.month() == month).then_some(date)
+}
+
+/// Every `day` in a month, or only the `nth` one (negative from the end).
+fn weekdays_in_month(year: i32, month: u32, nth: Option<i32>, day: Weekday) -> Vec<NaiveDate> {
+    let all: Vec<NaiveDate> = (1..=31)
+        .filter_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+        .filter(|date| date.weekday() == day)
+        .collect();
+    let index = match nth {
+        None => return all,
+        Some(n) if n > 0 => n as usize - 1,
+        Some(n) => match all.len().checked_sub(n.unsigned_abs() as usize) {
+            Some(i) => i,
+            None => return Vec::new(),
+        },
+    };
+    all.get(index).copied().into_iter().collect()
+}
+
+/// A calendar event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub uid: Option<String>,
+    pub summary: String,
+    pub location: Option<String>,
+    /// Start in the event's zone
+    pub start: NaiveDateTime,
+    /// End in the event's zone
+    pub end: Option<NaiveDateTime>,
+    /// DTSTART is a date without a time
+    pub all_day: bool,
+    pub zone: EventZone,
+    pub rule: Option<Rule>,
+    /// Starts skipped by `EXDATE`, in the event's zone
+    pub exdates: Vec<NaiveDateTime>,
+    /// For a moved instance, the start of the instance it replaces
+    pub recurrence_id: Option<NaiveDateTime>,
+}
+
+impl Event {
+    /// Returns true if the event repeats every year on its date.
+    pub fn is_yearly(&self) -> bool {
+        self.rule.as_ref().is_some_and(|rule| {
+            rule.frequency == Frequency::Yearly && rule.interval == 1 && rule.count.is_none()
+        })
+    }
+
+    /// Length of each occurrence. All-day events without an end last a day.
+    fn duration(&self) -> Duration {
+        match self.end {
+            Some(end) if end > self.start => end - self.start,
+            _ if self.all_day => Duration::days(1),
+            _ => Duration::zero(),
+        }
+    }
+
+    /// Starts in the event's zone from `from` up to `to`.
+    fn starts_within(&self, from: NaiveDateTime, to: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let Some(rule) = &self.rule else {
+            return vec![self.start];
+        };
+        let time = self.start.time();
+        let mut starts = Vec::new();
+        let mut seen = 0;
+        let first = rule.first_period(self.start.date(), from.date());
+        for period in first..first.saturating_add(MAX_PERIODS) {
+            let dates = rule.period_dates(self.start.date(), period);
+            if dates.first().is_some_and(|date| date.and_time(time) > to) {
+                break;
+            }
+            for start in dates.into_iter().map(|date| date.and_time(time)) {
+                if start < self.start {
+                    continue;
+                }
+                if rule.until.is_some_and(|until| start > until)
+                    || rule.count.is_some_and(|count| seen >= count)
+                {
+                    return starts;
+                }
+                seen += 1;
+                if start >= from && start <= to && !self.exdates.contains(&start) {
+                    starts.push(start);
+                }
+            }
+        }
+        starts
+    }
+}
+
+/// Parses every `VEVENT` with a start date, skipping malformed ones.
+pub fn parse_events(text: &str) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut current: Option<Vec<Property>> = None;
+    for line in unfold(text) {
+        let Some(prop) = Property::parse(&line) else {
+            continue;
+        };
+        match (prop.name.as_str(), prop.value.to_ascii_uppercase().as_str()) {
+            ("BEGIN", "VEVENT") => current = Some(Vec::new()),
+            ("END", "VEVENT") => {
+                if let Some(event) = current.take().and_then(|props| event_from(&props)) {
+                    events.push(event);
+                }
+            }
+            _ => {
+                if let Some(props) = current.as_mut() {
+                    props.push(prop);
+                }
+            }
+        }
+    }
+    events
+}
+
+fn event_from(props: &[Property]) -> Option<Event> {
+    let get = |name: &str| props.iter().find(|p| p.name == name);
+    let text = |name: &str| get(name).map(|p| unescape(&p.value));
+    let dtstart = get("DTSTART")?;
+    let start = parse_date_time(&dtstart.value)?;
+    let exdates = props
+        .iter()
+        .filter(|p| p.name == "EXDATE")
+        .flat_map(|p| p.value.split(',').filter_map(parse_date_time))
+        .collect();
+    Some(Event {
+        uid: text("UID"),
+        summary: text("SUMMARY").unwrap_or_default(),
+        location: text("LOCATION").filter(|l| !l.is_empty()),
+        start,
+        end: get("DTEND").and_then(|p| parse_date_time(&p.value)),
+        all_day: !dtstart.value.contains('T'),
+        zone: EventZone::of(dtstart),
+        rule: get("RRULE").and_then(|p| Rule::parse(&p.value)),
+        exdates,
+        recurrence_id: get("RECURRENCE-ID").and_then(|p| parse_date_time(&p.value)),
+    })
+}
+
+/// One occurrence of an event, in local time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occurrence {
+    pub summary: String,
+    pub location: Option<String>,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub all_day: bool,
+}
+
+/// Expands events into the occurrences overlapping `from..to` (local time),
+/// sorted by start.
+pub fn expand(events: &[Event], from: NaiveDateTime, to: NaiveDateTime) -> Vec<Occurrence> {
+    // Instances moved by a RECURRENCE-ID override are dropped from the series
+    let moved: Vec<(&str, NaiveDateTime)> = events
+        .iter()
+        .filter_map(|e| Some((e.uid.as_deref()?, e.recurrence_id?)))
+        .collect();
+
+    let mut occurrences = Vec::new();
+    for event in events {
+        let duration = event.duration();
+        // Starts are in the event's zone, so look a day around the window,
+        // and back far enough to catch occurrences still running at `from`
+        let earliest = from - duration - Duration::days(1);
+        for start in event.starts_within(earliest, to + Duration::days(1)) {
+            let replaced = event.recurrence_id.is_none()
+                && event
+                    .uid
+                    .as_deref()
+                    .is_some_and(|uid| moved.contains(&(uid, start)));
+            if replaced {
+                continue;
+            }
+            let start = if event.all_day {
+                start
+            } else {
+                event.zone.to_local(start)
+            };
+            let end = start + duration;
+            if start < to && (end > from || start >= from) {
+                occurrences.push(Occurrence {
+                    summary: event.summary.clone(),
+                    location: event.location.clone(),
+                    start,
+                    end,
+                    all_day: event.all_day,
+                });
+            }
+        }
+    }
+    occurrences.sort_by(|a, b| a.start.cmp(&b.start).then(a.summary.cmp(&b.summary)));
+    occurrences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOLIDAYS: &str = "BEGIN:VCALENDAR\r\n\
+BEGIN:VEVENT\r\n\
+DTSTART;VALUE=DATE:20261225\r\n\
+SUMMARY:Christmas\\, Day\r\n\
+RRULE:FREQ=YEARLY\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+DTSTART;TZID=\"Europe/Berlin\":20260403T090000\r\n\
+SUMMARY:Good\r\n Friday\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+SUMMARY:No start\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n";
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+    }
+
+    fn event(lines: &str) -> String {
+        format!("BEGIN:VEVENT\nSUMMARY:Standup\n{}\nEND:VEVENT\n", lines)
+    }
+
+    fn starts(ics: &str, from: NaiveDateTime, to: NaiveDateTime) -> Vec<String> {
+        expand(&parse_events(ics), from, to)
+            .iter()
+            .map(|o| o.start.format("%Y-%m-%d %H:%M").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn parses_events_with_folding_and_escapes() {
+        let events = parse_events(HOLIDAYS);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].summary, "Christmas, Day");
+        assert!(events[0].all_day && events[0].is_yearly());
+        assert_eq!(
+            events[0].start.date(),
+            NaiveDate::from_ymd_opt(2026, 12, 25).unwrap()
+        );
+        assert_eq!(events[1].summary, "GoodFriday");
+        assert!(!events[1].all_day && !events[1].is_yearly());
+        assert_eq!(events[1].zone, EventZone::Named(chrono_tz::Europe::Berlin));
+        assert_eq!(events[1].start.format("%H:%M").to_string(), "09:00");
+    }
+
+    #[test]
+    fn parses_properties_with_quoted_params() {
+        let prop = Property::parse("DTSTART;TZID=\"America/New_York\":20260101T080000").unwrap();
+        assert_eq!(prop.name, "DTSTART");
+        assert_eq!(prop.param("TZID"), Some("America/New_York"));
+        assert_eq!(prop.value, "20260101T080000");
+        let quoted = Property::parse("X;LABEL=\"a:b\":value").unwrap();
+        assert_eq!(quoted.value, "value");
+        assert_eq!(Property::parse("garbage"), None);
+    }
+
+    #[test]
+    fn expands_weekly_rules_with_exdates() {
+        let ics = event(
+            "DTSTART:20260302T093000\nDTEND:20260302T094500\n\
+             RRULE:FREQ=WEEKLY;BYDAY=MO,WE;COUNT=5\nEXDATE:20260304T093000",
+        );
+        assert_eq!(
+            starts(&ics, at(2026, 3, 1, 0, 0), at(2026, 4, 1, 0, 0)),
+            [
+                "2026-03-02 09:30",
+                "2026-03-09 09:30",
+                "2026-03-11 09:30",
+                "2026-03-16 09:30"
+            ]
+        );
+    }
+
+    #[test]
+    fn expands_monthly_weekdays_until_a_date() {
+        let second_tuesday =
+            event("DTSTART:20260113T150000\nRRULE:FREQ=MONTHLY;BYDAY=2TU;UNTIL=20260430T000000");
+        assert_eq!(
+            starts(
+                &second_tuesday,
+                at(2026, 1, 1, 0, 0),
+                at(2026, 12, 31, 0, 0)
+            ),
+            [
+                "2026-01-13 15:00",
+                "2026-02-10 15:00",
+                "2026-03-10 15:00",
+                "2026-04-14 15:00"
+            ]
+        );
+        let last_friday = event("DTSTART:20260130T120000\nRRULE:FREQ=MONTHLY;BYDAY=-1FR;COUNT=2");
+        assert_eq!(
+            starts(&last_friday, at(2026, 1, 1, 0, 0), at(2026, 12, 31, 0, 0)),
+            ["2026-01-30 12:00", "2026-02-27 12:00"]
+        );
+    }
+
+    #[test]
+    fn expands_daily_intervals_within_the_window() {
+        let ics = event("DTSTART:20260101T080000\nRRULE:FREQ=DAILY;INTERVAL=3");
+        assert_eq!(
+            starts(&ics, at(2026, 1, 5, 0, 0), at(2026, 1, 12, 0, 0)),
+            ["2026-01-07 08:00", "2026-01-10 08:00"]
+        );
+    }
+
+    #[test]
+    fn expands_series_that_started_long_ago() {
+        let daily = event("DTSTART:20050103T090000\nRRULE:FREQ=DAILY");
+        assert_eq!(
+            starts(&daily, at(2026, 3, 2, 0, 0), at(2026, 3, 4, 0, 0)),
+            ["2026-03-02 09:00", "2026-03-03 09:00"]
+        );
+        let weekly = event("DTSTART:20050103T090000\nRRULE:FREQ=WEEKLY;BYDAY=MO,WE");
+        assert_eq!(
+            starts(&weekly, at(2026, 3, 2, 0, 0), at(2026, 3, 9, 0, 0)),
+            ["2026-03-02 09:00", "2026-03-04 09:00"]
+        );
+        let monthly = event("DTSTART:20050131T120000\nRRULE:FREQ=MONTHLY;BYDAY=-1FR");
+        assert_eq!(
+            starts(&monthly, at(2026, 2, 1, 0, 0), at(2026, 4, 1, 0, 0)),
+            ["2026-02-27 12:00", "2026-03-27 12:00"]
+        );
+    }
+
+    #[test]
+    fn moved_instances_replace_the_original() {
+        let ics = format!(
+            "{}{}",
+            event("UID:a\nDTSTART:20260302T093000\nRRULE:FREQ=DAILY;COUNT=3"),
+            event("UID:a\nRECURRENCE-ID:20260303T093000\nDTSTART:20260303T110000"),
+        );
+        assert_eq!(
+            starts(&ics, at(2026, 3, 1, 0, 0), at(2026, 3, 10, 0, 0)),
+            ["2026-03-02 09:30", "2026-03-03 11:00", "2026-03-04 09:30"]
+        );
+    }
+
+    #[test]
+    fn all_day_events_span_their_day() {
+        let ics = event("DTSTART;VALUE=DATE:20260310");
+        let day = expand(
+            &parse_events(&ics),
+            at(2026, 3, 10, 12, 0),
+            at(2026, 3, 10, 13, 0),
+        );
+        assert_eq!(day.len(), 1);
+        assert_eq!(day[0].end, at(2026, 3, 11, 0, 0));
+    }
+}
@@ -0,0 +1,112 @@
+//! User idle time and screen lock state.
+//!
+//! Both are read on demand: the idle time is the time since the last
+//! keyboard, mouse or trackpad event, and the lock state comes from the
+//! login session. Neither call spawns a process, so the bar can check them
+//! before every timed update.
+
+use std::time::Duration;
+
+use core_foundation::base::{CFType, TCFType};
+use core_foundation::boolean::CFBoolean;
+use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+use core_foundation::string::CFString;
+
+// CoreGraphics FFI bindings
+mod ffi {
+    use core_foundation::dictionary::CFDictionaryRef;
+
+    /// kCGEventSourceStateHIDSystemState: input from any process
+    pub const HID_SYSTEM_STATE: i32 = 1;
+    /// kCGAnyInputEventType
+    pub const ANY_INPUT_EVENT: u32 = u32::MAX;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        pub fn CGEventSourceSecondsSinceLastEventType(state: i32, event_type: u32) -> f64;
+        pub fn CGSessionCopyCurrentDictionary() -> CFDictionaryRef;
+    }
+}
+
+/// Whether someone is at the machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Presence {
+    Active,
+    /// No input for longer than the idle threshold
+    Idle,
+    /// The screen is locked (or another user is logged in)
+    Locked,
+}
+
+impl Presence {
+    /// Classifies a reading; `idle_after` None never counts as idle.
+    pub fn from_state(locked: bool, idle: Duration, idle_after: Option<Duration>) -> Self {
+        if locked {
+            Self::Locked
+        } else if idle_after.is_some_and(|after| idle >= after) {
+            Self::Idle
+        } else {
+            Self::Active
+        }
+    }
+
+    /// Current presence with `idle_after` as the idle threshold.
+    pub fn current(idle_after: Option<Duration>) -> Self {
+        Self::from_state(is_screen_locked(), idle_time(), idle_after)
+    }
+}
+
+/// Time since the last user input event.
+pub fn idle_time() -> Duration {
+    let secs = unsafe {
+        ffi::CGEventSourceSecondsSinceLastEventType(ffi::HID_SYSTEM_STATE, ffi::ANY_INPUT_EVENT)
+    };
+    if secs.is_finite() && secs > 0.0 {
+        Duration::from_secs_f64(secs)
+    } else {
+        Duration::ZERO
+    }
+}
+
+/// True while the screen is locked or another user has the console.
+pub fn is_screen_locked() -> bool {
+    let dict: CFDictionaryRef = unsafe { ffi::CGSessionCopyCurrentDictionary() };
+    if dict.is_null() {
+        return false;
+    }
+    let dict: CFDictionary<CFString, CFType> =
+        unsafe { CFDictionary::wrap_under_create_rule(dict) };
+    let on_console = dict
+        .find(&CFString::from_static_string("kCGSSessionOnConsoleKey"))
+        .and_then(|value| value.downcast::<CFBoolean>())
+        .is_none_or(bool::from);
+    let locked = dict
+        .find(&CFString::from_static_string("CGSSessionScreenIsLocked"))
+        .and_then(|value| value.downcast::<CFBoolean>())
+        .is_some_and(bool::from);
+    locked || !on_console
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_presence() {
+        let minute = Duration::from_secs(60);
+        let five = Some(5 * minute);
+        assert_eq!(Presence::from_state(false, minute, five), Presence::Active);
+        assert_eq!(
+            Presence::from_state(false, 5 * minute, five),
+            Presence::Idle
+        );
+        assert_eq!(
+            Presence::from_state(true, Duration::ZERO, five),
+            Presence::Locked
+        );
+        assert_eq!(
+            Presence::from_state(false, 60 * minute, None),
+            Presence::Active
+        );
+    }
+}
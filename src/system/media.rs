@@ -0,0 +1,142 @@
+//! What media players report as playing: the system-wide now playing info
+//! (MediaRemote) and player apps queried over AppleScript.
+
+use std::process::Command;
+
+/// A track as a player reports it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerTrack {
+    pub title: String,
+    pub artist: String,
+    pub playing: bool,
+}
+
+/// The track macOS shows in Control Center, from whichever app.
+pub fn system_track() -> Option<PlayerTrack> {
+    media_remote::now_playing().map(|(title, artist, playing)| PlayerTrack {
+        title,
+        artist,
+        playing,
+    })
+}
+
+/// Parses `title<TAB>artist<TAB>player state` from the AppleScript query.
+pub fn parse_app_output(output: &str) -> Option<PlayerTrack> {
+    let mut parts = output.trim_end_matches(['\r', '\n']).split('\t');
+    let title = parts.next()?.trim();
+    let artist = parts.next().unwrap_or("").trim();
+    let state = parts.next().unwrap_or("").trim();
+    if title.is_empty() {
+        return None;
+    }
+    Some(PlayerTrack {
+        title: title.to_string(),
+        artist: artist.to_string(),
+        playing: state == "playing",
+    })
+}
+
+/// Queries a player app, without launching it if it isn't running.
+pub fn app_track(app: &str) -> Option<PlayerTrack> {
+    let script = format!(
+        r#"if application "{app}" is running then tell application "{app}" to if player state is not stopped then return (name of current track) & tab & (artist of current track) & tab & (player state as string)"#
+    );
+    let output = Command::new("osascript")
+        .args(["-e", &script])
+        .output()
+        .ok()?;
+    parse_app_output(&String::from_utf8(output.stdout).ok()?)
+}
+
+// MediaRemote (private) now playing info, resolved with dlsym
+mod media_remote {
+    use std::ffi::{c_char, c_void};
+    use std::sync::{mpsc, OnceLock};
+    use std::time::Duration;
+
+    use block2::{Block, RcBlock};
+    use objc2::msg_send;
+    use objc2::runtime::AnyObject;
+    use objc2_foundation::NSString;
+
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    type GetInfoFn =
+        unsafe extern "C" fn(queue: *mut c_void, handler: &Block<dyn Fn(*mut AnyObject)>);
+
+    extern "C" {
+        fn dlopen(path: *const c_char, mode: i32) -> *mut c_void;
+        fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+        fn dispatch_get_global_queue(identifier: isize, flags: usize) -> *mut c_void;
+    }
+
+    fn get_info_fn() -> Option<GetInfoFn> {
+        static FN: OnceLock<Option<GetInfoFn>> = OnceLock::new();
+        *FN.get_or_init(|| unsafe {
+            const RTLD_LAZY: i32 = 1;
+            let handle = dlopen(
+                c"/System/Library/PrivateFrameworks/MediaRemote.framework/MediaRemote".as_ptr(),
+                RTLD_LAZY,
+            );
+            if handle.is_null() {
+                log::warn!("now_playing: MediaRemote unavailable, system source disabled");
+                return None;
+            }
+            let get = dlsym(handle, c"MRMediaRemoteGetNowPlayingInfo".as_ptr());
+            if get.is_null() {
+                return None;
+            }
+            Some(std::mem::transmute::<*mut c_void, GetInfoFn>(get))
+        })
+    }
+
+    /// Title, artist and whether it's playing, for the app macOS reports as
+    /// now playing. None when nothing is, or access is denied.
+    pub fn now_playing() -> Option<(String, String, bool)> {
+        let get_info = get_info_fn()?;
+        let (tx, rx) = mpsc::channel();
+        let handler = RcBlock::new(move |info: *mut AnyObject| {
+            let _ = tx.send(unsafe { info.as_ref() }.and_then(|info| read_info(info)));
+        });
+        unsafe { get_info(dispatch_get_global_queue(0, 0), &handler) };
+        rx.recv_timeout(TIMEOUT).ok().flatten()
+    }
+
+    fn value<'a>(info: &'a AnyObject, key: &str) -> Option<&'a AnyObject> {
+        let key = NSString::from_str(key);
+        let value: *mut AnyObject = unsafe { msg_send![info, objectForKey: &*key] };
+        unsafe { value.as_ref() }
+    }
+
+    fn string(info: &AnyObject, key: &str) -> Option<String> {
+        let value = value(info, key)?;
+        let value = unsafe { &*(value as *const AnyObject as *const NSString) };
+        Some(value.to_string())
+    }
+
+    fn read_info(info: &AnyObject) -> Option<(String, String, bool)> {
+        let title = string(info, "kMRMediaRemoteNowPlayingInfoTitle")?;
+        let artist = string(info, "kMRMediaRemoteNowPlayingInfoArtist").unwrap_or_default();
+        let rate: f64 = value(info, "kMRMediaRemoteNowPlayingInfoPlaybackRate")
+            .map(|rate| unsafe { msg_send![rate, doubleValue] })
+            .unwrap_or(0.0);
+        Some((title, artist, rate > 0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_app_output() {
+        let track = parse_app_output("Song\tBand\tplaying\n").unwrap();
+        assert_eq!(track.title, "Song");
+        assert_eq!(track.artist, "Band");
+        assert!(track.playing);
+        let paused = parse_app_output("Song\t\tpaused").unwrap();
+        assert_eq!(paused.artist, "");
+        assert!(!paused.playing);
+        assert_eq!(parse_app_output("\n"), None);
+    }
+}
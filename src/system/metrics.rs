@@ -0,0 +1,1537 @@
+//! Shared system metrics service.
+//!
+//! CPU (total and per core), memory, disk, disk I/O, temperature, battery,
+//! power and peripheral battery readings are sampled on one background thread and broadcast to
+//! subscribed modules, so two modules showing the same stat share one
+//! sample instead of each polling. Each metric is sampled at the shortest
+//! interval any of its subscribers asked for, and recent values are kept
+//! for graphs. The thread only runs while something is subscribed.
+
+use std::collections::{HashMap, VecDeque};
+use std::ffi::CString;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Values kept per metric for graphs.
+const HISTORY_LEN: usize = 120;
+/// Upper bound on how long the thread sleeps, so new subscribers are
+/// served promptly.
+const MAX_SLEEP: Duration = Duration::from_secs(1);
+
+/// Mach host_statistics and sysctl FFI for CPU ticks and memory (no
+/// process spawn needed).
+mod mach {
+    use std::ffi::{c_char, c_double, c_int, c_uint, c_void, CStr};
+    use std::mem::MaybeUninit;
+
+    const HOST_CPU_LOAD_INFO: c_uint = 3;
+    const HOST_VM_INFO64: c_int = 4;
+    const PROCESSOR_CPU_LOAD_INFO: c_int = 2;
+    const CPU_STATE_USER: usize = 0;
+    const CPU_STATE_SYSTEM: usize = 1;
+    const CPU_STATE_IDLE: usize = 2;
+    const CPU_STATE_NICE: usize = 3;
+    const CPU_STATE_MAX: usize = 4;
+
+    #[repr(C)]
+    struct HostCpuLoadInfo {
+        cpu_ticks: [u32; CPU_STATE_MAX],
+    }
+
+    #[repr(C)]
+    #[allow(dead_code)]
+    struct VmStatistics64 {
+        free_count: u32,
+        active_count: u32,
+        inactive_count: u32,
+        wire_count: u32,
+        zero_fill_count: u64,
+        reactivations: u64,
+        pageins: u64,
+        pageouts: u64,
+        faults: u64,
+        cow_faults: u64,
+        lookups: u64,
+        hits: u64,
+        purges: u64,
+        purgeable_count: u32,
+        speculative_count: u32,
+        decompressions: u64,
+        compressions: u64,
+        swapins: u64,
+        swapouts: u64,
+        compressor_page_count: u32,
+        throttled_count: u32,
+        external_page_count: u32,
+        internal_page_count: u32,
+        total_uncompressed_pages_in_compressor: u64,
+    }
+
+    /// `vm.swapusage`
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct XswUsage {
+        pub total: u64,
+        pub avail: u64,
+        pub used: u64,
+        pub pagesize: u32,
+        pub encrypted: u32,
+    }
+
+    extern "C" {
+        static mach_task_self_: c_uint;
+        fn mach_host_self() -> c_uint;
+        fn host_statistics(
+            host: c_uint,
+            flavor: c_uint,
+            info: *mut HostCpuLoadInfo,
+            count: *mut c_uint,
+        ) -> c_uint;
+        fn host_statistics64(
+            host: c_uint,
+            flavor: c_int,
+            info: *mut VmStatistics64,
+            count: *mut c_uint,
+        ) -> c_int;
+        fn host_processor_info(
+            host: c_uint,
+            flavor: c_int,
+            count: *mut c_uint,
+            info: *mut *mut c_int,
+            info_count: *mut c_uint,
+        ) -> c_int;
+        fn vm_deallocate(task: c_uint, address: usize, size: usize) -> c_int;
+        fn getloadavg(loadavg: *mut c_double, nelem: c_int) -> c_int;
+        fn sysctlbyname(
+            name: *const c_char,
+            oldp: *mut c_void,
+            oldlenp: *mut usize,
+            newp: *mut c_void,
+            newlen: usize,
+        ) -> c_int;
+    }
+
+    /// Returns cumulative (active_ticks, total_ticks).
+    pub fn cpu_ticks() -> Option<(u64, u64)> {
+        unsafe {
+            let mut info = MaybeUninit::<HostCpuLoadInfo>::uninit();
+            let mut count =
+                (std::mem::size_of::<HostCpuLoadInfo>() / std::mem::size_of::<u32>()) as c_uint;
+            let status = host_statistics(
+                mach_host_self(),
+                HOST_CPU_LOAD_INFO,
+                info.as_mut_ptr(),
+                &mut count,
+            );
+            if status != 0 {
+                return None;
+            }
+            let info = info.assume_init();
+            let user = info.cpu_ticks[CPU_STATE_USER] as u64;
+            let system = info.cpu_ticks[CPU_STATE_SYSTEM] as u64;
+            let idle = info.cpu_ticks[CPU_STATE_IDLE] as u64;
+            let nice = info.cpu_ticks[CPU_STATE_NICE] as u64;
+            let total = user + system + idle + nice;
+            let active = user + system + nice;
+            Some((active, total))
+        }
+    }
+
+    /// Returns cumulative (active_ticks, total_ticks) for each core.
+    pub fn core_ticks() -> Option<Vec<(u64, u64)>> {
+        unsafe {
+            let mut count: c_uint = 0;
+            let mut info: *mut c_int = std::ptr::null_mut();
+            let mut info_count: c_uint = 0;
+            let status = host_processor_info(
+                mach_host_self(),
+                PROCESSOR_CPU_LOAD_INFO,
+                &mut count,
+                &mut info,
+                &mut info_count,
+            );
+            if status != 0 || info.is_null() {
+                return None;
+            }
+            let ticks = std::slice::from_raw_parts(info, info_count as usize);
+            let cores = ticks
+                .chunks_exact(CPU_STATE_MAX)
+                .take(count as usize)
+                .map(|cpu| {
+                    let user = cpu[CPU_STATE_USER] as u32 as u64;
+                    let system = cpu[CPU_STATE_SYSTEM] as u32 as u64;
+                    let idle = cpu[CPU_STATE_IDLE] as u32 as u64;
+                    let nice = cpu[CPU_STATE_NICE] as u32 as u64;
+                    (user + system + nice, user + system + idle + nice)
+                })
+                .collect();
+            vm_deallocate(
+                mach_task_self_,
+                info as usize,
+                info_count as usize * std::mem::size_of::<c_int>(),
+            );
+            Some(cores)
+        }
+    }
+
+    /// Returns the 1, 5 and 15 minute load averages.
+    pub fn load_average() -> Option<[f64; 3]> {
+        let mut load = [0.0; 3];
+        let read = unsafe { getloadavg(load.as_mut_ptr(), 3) };
+        (read == 3).then_some(load)
+    }
+
+    /// Reads a string sysctl.
+    pub fn sysctl_string(name: &CStr) -> Option<String> {
+        let mut buffer = [0u8; 64];
+        let mut len = buffer.len();
+        let status = unsafe {
+            sysctlbyname(
+                name.as_ptr(),
+                buffer.as_mut_ptr() as *mut c_void,
+                &mut len,
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        if status != 0 {
+            return None;
+        }
+        let value = CStr::from_bytes_until_nul(&buffer[..len]).ok()?;
+        Some(value.to_string_lossy().into_owned())
+    }
+
+    /// Reads a fixed-size sysctl value.
+    pub fn sysctl<T: Copy>(name: &CStr) -> Option<T> {
+        let mut value = MaybeUninit::<T>::uninit();
+        let mut len = std::mem::size_of::<T>();
+        let status = unsafe {
+            sysctlbyname(
+                name.as_ptr(),
+                value.as_mut_ptr() as *mut c_void,
+                &mut len,
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        (status == 0 && len == std::mem::size_of::<T>()).then(|| unsafe { value.assume_init() })
+    }
+
+    /// Reads an integer sysctl, whether the kernel stores it in 32 or 64 bits.
+    pub fn sysctl_int(name: &CStr) -> Option<u64> {
+        let mut value: u64 = 0;
+        let mut len = std::mem::size_of::<u64>();
+        let status = unsafe {
+            sysctlbyname(
+                name.as_ptr(),
+                &mut value as *mut u64 as *mut c_void,
+                &mut len,
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        match (status, len) {
+            (0, 4) => Some(value & 0xffff_ffff),
+            (0, 8) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Percentage of memory the kernel considers free (`kern.memorystatus_level`,
+    /// the figure `memory_pressure` reports).
+    pub fn memory_free_level() -> Option<u8> {
+        sysctl_int(c"kern.memorystatus_level").map(|level| level.min(100) as u8)
+    }
+
+    /// Returns the page counts the memory breakdown is built from.
+    pub fn vm_pages() -> Option<super::VmPages> {
+        unsafe {
+            let mut info = MaybeUninit::<VmStatistics64>::uninit();
+            let mut count =
+                (std::mem::size_of::<VmStatistics64>() / std::mem::size_of::<c_int>()) as c_uint;
+            let status = host_statistics64(
+                mach_host_self(),
+                HOST_VM_INFO64,
+                info.as_mut_ptr(),
+                &mut count,
+            );
+            if status != 0 {
+                return None;
+            }
+            let info = info.assume_init();
+            Some(super::VmPages {
+                internal: info.internal_page_count as u64,
+                purgeable: info.purgeable_count as u64,
+                external: info.external_page_count as u64,
+                wired: info.wire_count as u64,
+                compressed: info.compressor_page_count as u64,
+            })
+        }
+    }
+}
+
+/// IOKit FFI for block storage statistics, CPU clock tables and battery
+/// power telemetry.
+mod iokit {
+    use std::ffi::{c_char, c_void, CStr};
+
+    use core_foundation::base::{CFType, CFTypeRef, TCFType};
+    use core_foundation::boolean::CFBoolean;
+    use core_foundation::data::CFData;
+    use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+    use core_foundation::number::CFNumber;
+    use core_foundation::string::{CFString, CFStringRef};
+
+    type IoObject = u32;
+    const IO_MAIN_PORT_DEFAULT: u32 = 0;
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IOServiceMatching(name: *const c_char) -> CFDictionaryRef;
+        fn IOServiceGetMatchingServices(
+            main_port: u32,
+            matching: CFDictionaryRef,
+            existing: *mut IoObject,
+        ) -> i32;
+        fn IOIteratorNext(iterator: IoObject) -> IoObject;
+        fn IORegistryEntryCreateCFProperty(
+            entry: IoObject,
+            key: CFStringRef,
+            allocator: *const c_void,
+            options: u32,
+        ) -> CFTypeRef;
+        fn IORegistryEntryGetName(entry: IoObject, name: *mut c_char) -> i32;
+        fn IOObjectRelease(object: IoObject) -> i32;
+    }
+
+    /// Returns cumulative (bytes read, bytes written) over all block
+    /// storage drivers.
+    pub fn disk_bytes() -> Option<(u64, u64)> {
+        let statistics_key = CFString::from_static_string("Statistics");
+        let read_key = CFString::from_static_string("Bytes (Read)");
+        let write_key = CFString::from_static_string("Bytes (Write)");
+        unsafe {
+            let matching = IOServiceMatching(c"IOBlockStorageDriver".as_ptr());
+            let mut iterator: IoObject = 0;
+            // Consumes the matching dictionary
+            if IOServiceGetMatchingServices(IO_MAIN_PORT_DEFAULT, matching, &mut iterator) != 0 {
+                return None;
+            }
+            let (mut read, mut written) = (0, 0);
+            loop {
+                let driver = IOIteratorNext(iterator);
+                if driver == 0 {
+                    break;
+                }
+                let property = IORegistryEntryCreateCFProperty(
+                    driver,
+                    statistics_key.as_concrete_TypeRef(),
+                    std::ptr::null(),
+                    0,
+                );
+                IOObjectRelease(driver);
+                if property.is_null() {
+                    continue;
+                }
+                let statistics = CFDictionary::<CFString, CFType>::wrap_under_create_rule(
+                    property as CFDictionaryRef,
+                );
+                let bytes = |key: &CFString| -> u64 {
+                    statistics
+                        .find(key)
+                        .and_then(|value| value.downcast::<CFNumber>())
+                        .and_then(|number| number.to_i64())
+                        .unwrap_or(0) as u64
+                };
+                read += bytes(&read_key);
+                written += bytes(&write_key);
+            }
+            IOObjectRelease(iterator);
+            Some((read, written))
+        }
+    }
+
+    /// Reads the power telemetry of the `AppleSmartBattery` service. None
+    /// on Macs without a battery.
+    pub fn smart_battery() -> Option<super::SmartBattery> {
+        unsafe {
+            let matching = IOServiceMatching(c"AppleSmartBattery".as_ptr());
+            let mut iterator: IoObject = 0;
+            // Consumes the matching dictionary
+            if IOServiceGetMatchingServices(IO_MAIN_PORT_DEFAULT, matching, &mut iterator) != 0 {
+                return None;
+            }
+            let battery = IOIteratorNext(iterator);
+            IOObjectRelease(iterator);
+            if battery == 0 {
+                return None;
+            }
+            let property = |key: &'static str| -> Option<CFType> {
+                let key = CFString::from_static_string(key);
+                let value = IORegistryEntryCreateCFProperty(
+                    battery,
+                    key.as_concrete_TypeRef(),
+                    std::ptr::null(),
+                    0,
+                );
+                (!value.is_null()).then(|| CFType::wrap_under_create_rule(value))
+            };
+            let number = |key: &'static str| {
+                property(key)?
+                    .downcast::<CFNumber>()
+                    .and_then(|n| n.to_i64())
+            };
+            let flag = |key: &'static str| {
+                property(key)
+                    .and_then(|v| v.downcast::<CFBoolean>())
+                    .map(bool::from)
+                    .unwrap_or(false)
+            };
+            let dictionary = |key: &'static str| {
+                property(key).map(|v| {
+                    CFDictionary::<CFString, CFType>::wrap_under_get_rule(
+                        v.as_CFTypeRef() as CFDictionaryRef
+                    )
+                })
+            };
+
+            let adapter = dictionary("AdapterDetails");
+            let adapter_field = |key: &'static str| {
+                adapter
+                    .as_ref()?
+                    .find(&CFString::from_static_string(key))
+                    .map(|value| value.clone())
+            };
+            let telemetry = dictionary("PowerTelemetryData");
+            let reading = super::SmartBattery {
+                voltage: number("Voltage").unwrap_or(0),
+                amperage: number("InstantAmperage")
+                    .or_else(|| number("Amperage"))
+                    .unwrap_or(0),
+                external_connected: flag("ExternalConnected"),
+                is_charging: flag("IsCharging"),
+                adapter_watts: adapter_field("Watts")
+                    .and_then(|v| v.downcast::<CFNumber>())
+                    .and_then(|n| n.to_i64()),
+                adapter_name: adapter_field("Name")
+                    .or_else(|| adapter_field("Description"))
+                    .and_then(|v| v.downcast::<CFString>())
+                    .map(|name| name.to_string()),
+                system_power_in: telemetry
+                    .as_ref()
+                    .and_then(|t| t.find(&CFString::from_static_string("SystemPowerIn")))
+                    .and_then(|v| v.downcast::<CFNumber>())
+                    .and_then(|n| n.to_i64()),
+                // The plain CurrentCapacity/MaxCapacity are percentages on
+                // Apple Silicon
+                charge_mah: number("AppleRawCurrentCapacity"),
+                full_mah: number("AppleRawMaxCapacity"),
+                design_mah: number("DesignCapacity"),
+                cycles: number("CycleCount"),
+            };
+            IOObjectRelease(battery);
+            Some(reading)
+        }
+    }
+
+    /// Reads the battery of every HID device that reports one, such as
+    /// Apple keyboards, mice and trackpads connected over Bluetooth.
+    pub fn hid_batteries() -> Vec<super::PeripheralBattery> {
+        let product_key = CFString::from_static_string("Product");
+        let percent_key = CFString::from_static_string("BatteryPercent");
+        unsafe {
+            let matching = IOServiceMatching(c"AppleDeviceManagementHIDEventService".as_ptr());
+            let mut iterator: IoObject = 0;
+            // Consumes the matching dictionary
+            if IOServiceGetMatchingServices(IO_MAIN_PORT_DEFAULT, matching, &mut iterator) != 0 {
+                return Vec::new();
+            }
+            let mut peripherals = Vec::new();
+            loop {
+                let service = IOIteratorNext(iterator);
+                if service == 0 {
+                    break;
+                }
+                let property = |key: &CFString| -> Option<CFType> {
+                    let value = IORegistryEntryCreateCFProperty(
+                        service,
+                        key.as_concrete_TypeRef(),
+                        std::ptr::null(),
+                        0,
+                    );
+                    (!value.is_null()).then(|| CFType::wrap_under_create_rule(value))
+                };
+                let level = property(&percent_key)
+                    .and_then(|v| v.downcast::<CFNumber>())
+                    .and_then(|n| n.to_i64());
+                let name = property(&product_key)
+                    .and_then(|v| v.downcast::<CFString>())
+                    .map(|name| name.to_string());
+                IOObjectRelease(service);
+                if let (Some(name), Some(level)) = (name, level) {
+                    peripherals.push(super::PeripheralBattery::new(name, level));
+                }
+            }
+            IOObjectRelease(iterator);
+            peripherals
+        }
+    }
+
+    /// Returns the top clock of each Apple Silicon CPU cluster in Hz,
+    /// keyed by perf level name. The power manager node lists efficiency
+    /// states under `voltage-states1-sram` and performance states under
+    /// `voltage-states5-sram`. Empty on Intel.
+    pub fn cluster_frequencies() -> Vec<(&'static str, u64)> {
+        let keys = [
+            ("Efficiency", "voltage-states1-sram"),
+            ("Performance", "voltage-states5-sram"),
+        ];
+        unsafe {
+            let matching = IOServiceMatching(c"AppleARMIODevice".as_ptr());
+            let mut iterator: IoObject = 0;
+            // Consumes the matching dictionary
+            if IOServiceGetMatchingServices(IO_MAIN_PORT_DEFAULT, matching, &mut iterator) != 0 {
+                return Vec::new();
+            }
+            let mut frequencies = Vec::new();
+            loop {
+                let device = IOIteratorNext(iterator);
+                if device == 0 {
+                    break;
+                }
+                // io_name_t is 128 bytes
+                let mut name = [0 as c_char; 128];
+                let is_pmgr = IORegistryEntryGetName(device, name.as_mut_ptr()) == 0
+                    && CStr::from_ptr(name.as_ptr()) == c"pmgr";
+                if is_pmgr {
+                    for (level, key) in keys {
+                        let key = CFString::from_static_string(key);
+                        let property = IORegistryEntryCreateCFProperty(
+                            device,
+                            key.as_concrete_TypeRef(),
+                            std::ptr::null(),
+                            0,
+                        );
+                        if property.is_null() {
+                            continue;
+                        }
+                        let top = CFType::wrap_under_create_rule(property)
+                            .downcast::<CFData>()
+                            .and_then(|data| super::max_frequency(data.bytes()));
+                        if let Some(top) = top {
+                            frequencies.push((level, top));
+                        }
+                    }
+                }
+                IOObjectRelease(device);
+                if is_pmgr {
+                    break;
+                }
+            }
+            IOObjectRelease(iterator);
+            frequencies
+        }
+    }
+}
+
+/// A stat modules can subscribe to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Metric {
+    Cpu,
+    /// Per-core usage and load averages
+    CpuCores,
+    Memory,
+    /// Usage of the volume containing this path
+    Disk(String),
+    /// Read/write throughput over all disks
+    DiskIo,
+    Temperature,
+    Battery,
+    /// Charging or discharging wattage
+    Power,
+    /// Battery levels of Bluetooth keyboards, mice and trackpads
+    Peripherals,
+}
+
+/// A group of identical cores.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuCluster {
+    /// Perf level name, e.g. `Efficiency` or `Performance`
+    pub name: String,
+    pub cores: usize,
+    /// Top clock in Hz. macOS doesn't expose live clocks without root, so
+    /// this is the ceiling the cluster boosts to.
+    pub max_frequency: Option<u64>,
+}
+
+/// Per-core usage and load.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CpuDetails {
+    /// Usage of each core in percent, in core order
+    pub cores: Vec<f32>,
+    /// 1, 5 and 15 minute load averages
+    pub load: Option<[f64; 3]>,
+}
+
+/// Usage of one volume.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskUsage {
+    /// Usage as `df` prints it, e.g. `42%`
+    pub text: String,
+    pub percent: u8,
+}
+
+/// System memory pressure as the kernel reports it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MemoryPressure {
+    #[default]
+    Normal,
+    Warning,
+    Critical,
+}
+
+impl MemoryPressure {
+    /// Maps `kern.memorystatus_vm_pressure_level` (1, 2 or 4).
+    fn from_level(level: i32) -> Self {
+        match level {
+            4 => Self::Critical,
+            2 => Self::Warning,
+            _ => Self::Normal,
+        }
+    }
+}
+
+/// Page counts from `host_statistics64`.
+#[derive(Debug, Clone, Copy, Default)]
+struct VmPages {
+    internal: u64,
+    purgeable: u64,
+    external: u64,
+    wired: u64,
+    compressed: u64,
+}
+
+/// Where memory goes, in bytes, grouped as Activity Monitor does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryDetails {
+    pub pressure: MemoryPressure,
+    pub total: u64,
+    /// Anonymous memory of running apps
+    pub app: u64,
+    pub wired: u64,
+    pub compressed: u64,
+    /// File-backed and purgeable memory the system can reclaim
+    pub cached: u64,
+    pub swap_used: u64,
+    pub swap_total: u64,
+}
+
+impl MemoryDetails {
+    fn new(pages: VmPages, page_size: u64, pressure: MemoryPressure) -> Self {
+        Self {
+            pressure,
+            app: pages.internal.saturating_sub(pages.purgeable) * page_size,
+            wired: pages.wired * page_size,
+            compressed: pages.compressed * page_size,
+            cached: (pages.external + pages.purgeable) * page_size,
+            ..Self::default()
+        }
+    }
+}
+
+/// Disk throughput in bytes per second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiskIo {
+    pub read: f64,
+    pub write: f64,
+}
+
+/// Battery charge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryStatus {
+    pub level: u8,
+    pub charging: bool,
+    /// On AC power, whether or not it's charging (held at a charge limit
+    /// or full)
+    pub plugged: bool,
+    /// Estimated minutes to empty or full, None while pmset has no estimate
+    pub minutes_left: Option<u16>,
+    /// Charge in mAh, None where IOKit doesn't report it
+    pub capacity: Option<BatteryCapacity>,
+}
+
+/// Battery charge and wear in mAh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryCapacity {
+    pub charge_mah: u32,
+    /// What a full charge holds now
+    pub full_mah: u32,
+    /// What a full charge held when new
+    pub design_mah: Option<u32>,
+    pub cycles: Option<u32>,
+}
+
+impl BatteryCapacity {
+    fn new(raw: &SmartBattery) -> Option<Self> {
+        let mah = |value: Option<i64>| value.filter(|v| *v > 0).map(|v| v as u32);
+        Some(Self {
+            charge_mah: raw.charge_mah.filter(|v| *v >= 0)? as u32,
+            full_mah: mah(raw.full_mah)?,
+            design_mah: mah(raw.design_mah),
+            cycles: raw.cycles.filter(|v| *v >= 0).map(|v| v as u32),
+        })
+    }
+
+    /// Full charge capacity as a share of the design capacity, in percent.
+    pub fn health(&self) -> Option<u8> {
+        let design = self.design_mah?;
+        Some(
+            (self.full_mah as f64 / design as f64 * 100.0)
+                .round()
+                .min(100.0) as u8,
+        )
+    }
+}
+
+/// Raw `AppleSmartBattery` readings.
+#[derive(Debug, Clone, Default)]
+struct SmartBattery {
+    /// Millivolts
+    voltage: i64,
+    /// Milliamps, negative while discharging
+    amperage: i64,
+    external_connected: bool,
+    is_charging: bool,
+    adapter_watts: Option<i64>,
+    adapter_name: Option<String>,
+    /// Milliwatts the whole system draws from the adapter
+    system_power_in: Option<i64>,
+    /// Charge and capacities in mAh
+    charge_mah: Option<i64>,
+    full_mah: Option<i64>,
+    design_mah: Option<i64>,
+    cycles: Option<i64>,
+}
+
+/// Where power is flowing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerState {
+    Charging,
+    /// Plugged in but not charging (full or held by charge limits)
+    OnAdapter,
+    Discharging,
+}
+
+/// The connected power adapter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PowerAdapter {
+    /// Rated watts
+    pub watts: u32,
+    pub name: Option<String>,
+}
+
+/// Battery power flow.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PowerStatus {
+    pub state: PowerState,
+    /// Watts into (positive) or out of (negative) the battery
+    pub battery_watts: f64,
+    /// Watts the whole system draws from the adapter
+    pub input_watts: Option<f64>,
+    pub adapter: Option<PowerAdapter>,
+}
+
+impl PowerStatus {
+    fn new(raw: SmartBattery) -> Self {
+        let state = if !raw.external_connected {
+            PowerState::Discharging
+        } else if raw.is_charging {
+            PowerState::Charging
+        } else {
+            PowerState::OnAdapter
+        };
+        let adapter = raw
+            .external_connected
+            .then(|| PowerAdapter {
+                watts: raw.adapter_watts.unwrap_or(0).max(0) as u32,
+                name: raw.adapter_name,
+            })
+            .filter(|adapter| adapter.watts > 0 || adapter.name.is_some());
+        Self {
+            state,
+            battery_watts: (raw.voltage * raw.amperage) as f64 / 1_000_000.0,
+            input_watts: raw
+                .system_power_in
+                .filter(|_| raw.external_connected)
+                .map(|mw| mw as f64 / 1000.0),
+            adapter,
+        }
+    }
+}
+
+/// Kind of peripheral, guessed from its product name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeripheralKind {
+    Keyboard,
+    Mouse,
+    Trackpad,
+    Other,
+}
+
+impl PeripheralKind {
+    pub fn from_name(name: &str) -> Self {
+        let name = name.to_lowercase();
+        if name.contains("keyboard") {
+            Self::Keyboard
+        } else if name.contains("trackpad") {
+            Self::Trackpad
+        } else if name.contains("mouse") {
+            Self::Mouse
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// A peripheral that reports its battery level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeripheralBattery {
+    pub name: String,
+    pub kind: PeripheralKind,
+    /// Charge in percent
+    pub level: u8,
+}
+
+impl PeripheralBattery {
+    fn new(name: String, level: i64) -> Self {
+        Self {
+            kind: PeripheralKind::from_name(&name),
+            level: level.clamp(0, 100) as u8,
+            name,
+        }
+    }
+}
+
+/// Sorts peripherals by name, keeping one entry per name; a device shows
+/// up once per HID service it has.
+fn dedup_peripherals(mut peripherals: Vec<PeripheralBattery>) -> Vec<PeripheralBattery> {
+    peripherals.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    peripherals.dedup_by(|a, b| a.name == b.name);
+    peripherals
+}
+
+/// Latest reading of every sampled metric.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsSnapshot {
+    /// Total CPU usage in percent
+    pub cpu: Option<u8>,
+    pub cpu_details: Option<CpuDetails>,
+    /// Memory in use (100 minus the kernel's free level), in percent
+    pub memory: Option<u8>,
+    pub memory_details: Option<MemoryDetails>,
+    /// Volume usage by subscribed path
+    pub disks: HashMap<String, DiskUsage>,
+    pub disk_io: Option<DiskIo>,
+    /// CPU temperature in °C
+    pub temperature: Option<u8>,
+    pub battery: Option<BatteryStatus>,
+    pub power: Option<PowerStatus>,
+    pub peripherals: Vec<PeripheralBattery>,
+}
+
+impl MetricsSnapshot {
+    /// The metric as a number, for history.
+    pub fn value(&self, metric: &Metric) -> Option<f32> {
+        match metric {
+            Metric::Cpu => self.cpu.map(f32::from),
+            Metric::CpuCores => self.cpu_details.as_ref().map(|d| mean(&d.cores)),
+            Metric::Memory => self.memory.map(f32::from),
+            Metric::Disk(path) => self.disks.get(path).map(|d| f32::from(d.percent)),
+            Metric::DiskIo => self.disk_io.map(|io| (io.read + io.write) as f32),
+            Metric::Temperature => self.temperature.map(f32::from),
+            Metric::Battery => self.battery.map(|b| f32::from(b.level)),
+            Metric::Power => self.power.as_ref().map(|p| p.battery_watts as f32),
+            // The emptiest battery
+            Metric::Peripherals => self
+                .peripherals
+                .iter()
+                .map(|p| f32::from(p.level))
+                .reduce(f32::min),
+        }
+    }
+}
+
+/// Whether a metric last sampled at `last` is due again.
+fn is_due(last: Option<Instant>, interval: Duration, now: Instant) -> bool {
+    last.is_none_or(|last| now.duration_since(last) >= interval)
+}
+
+/// Total CPU usage in percent between two tick snapshots.
+fn cpu_percent(prev: (u64, u64), current: (u64, u64)) -> u8 {
+    let active = current.0.saturating_sub(prev.0);
+    let total = current.1.saturating_sub(prev.1);
+    if total == 0 {
+        return 0;
+    }
+    ((active as f64 / total as f64) * 100.0).round() as u8
+}
+
+/// Per-core usage in percent between two tick snapshots.
+pub fn core_usage(prev: &[(u64, u64)], current: &[(u64, u64)]) -> Vec<f32> {
+    prev.iter()
+        .zip(current)
+        .map(|(p, c)| {
+            let active = c.0.saturating_sub(p.0);
+            let total = c.1.saturating_sub(p.1);
+            if total == 0 {
+                0.0
+            } else {
+                active as f32 / total as f32 * 100.0
+            }
+        })
+        .collect()
+}
+
+/// Average of per-core values, 0 without cores.
+pub fn mean(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f32>() / values.len() as f32
+}
+
+/// Splits per-core values into `clusters`. Falls back to one group when
+/// the topology doesn't add up to the core count.
+pub fn split_cores<'a>(cores: &'a [f32], clusters: &[CpuCluster]) -> Vec<&'a [f32]> {
+    if clusters.iter().map(|c| c.cores).sum::<usize>() != cores.len() {
+        return vec![cores];
+    }
+    let mut rest = cores;
+    clusters
+        .iter()
+        .map(|cluster| {
+            let (group, tail) = rest.split_at(cluster.cores);
+            rest = tail;
+            group
+        })
+        .collect()
+}
+
+/// Top clock in Hz from a DVFS table of (frequency, voltage) u32 pairs.
+/// Older chips list Hz and newer ones kHz.
+fn max_frequency(states: &[u8]) -> Option<u64> {
+    let top = states
+        .chunks_exact(8)
+        .map(|state| u32::from_le_bytes([state[0], state[1], state[2], state[3]]) as u64)
+        .max()
+        .filter(|top| *top > 0)?;
+    Some(if top < 100_000_000 { top * 1000 } else { top })
+}
+
+/// CPU clusters in core order, read once. Tests get a fixed Apple Silicon
+/// layout so what they render doesn't depend on the machine.
+pub fn cpu_clusters() -> &'static [CpuCluster] {
+    static CLUSTERS: OnceLock<Vec<CpuCluster>> = OnceLock::new();
+    CLUSTERS.get_or_init(|| {
+        if cfg!(test) {
+            test_clusters()
+        } else {
+            read_clusters()
+        }
+    })
+}
+
+fn test_clusters() -> Vec<CpuCluster> {
+    [("Efficiency", 4), ("Performance", 6)]
+        .into_iter()
+        .map(|(name, cores)| CpuCluster {
+            name: name.to_string(),
+            cores,
+            max_frequency: None,
+        })
+        .collect()
+}
+
+fn read_clusters() -> Vec<CpuCluster> {
+    let levels = mach::sysctl_int(c"hw.nperflevels").unwrap_or(0);
+    if levels == 0 {
+        // Intel: one kind of core
+        return vec![CpuCluster {
+            name: "CPU".to_string(),
+            cores: mach::sysctl_int(c"hw.logicalcpu").unwrap_or(0) as usize,
+            max_frequency: mach::sysctl_int(c"hw.cpufrequency_max"),
+        }];
+    }
+    let frequencies = iokit::cluster_frequencies();
+    let sysctl_name =
+        |level: u64, field: &str| CString::new(format!("hw.perflevel{level}.{field}"));
+    // perflevel0 is the fastest, but efficiency cores are numbered first
+    (0..levels)
+        .rev()
+        .filter_map(|level| {
+            let name = mach::sysctl_string(&sysctl_name(level, "name").ok()?)?;
+            let cores = mach::sysctl_int(&sysctl_name(level, "logicalcpu").ok()?)? as usize;
+            let max_frequency = frequencies
+                .iter()
+                .find(|(level, _)| *level == name)
+                .map(|(_, hz)| *hz);
+            Some(CpuCluster {
+                name,
+                cores,
+                max_frequency,
+            })
+        })
+        .collect()
+}
+
+/// Throughput between two cumulative (read, written) byte counts.
+fn disk_io(prev: (u64, u64), current: (u64, u64), elapsed: Duration) -> DiskIo {
+    let secs = elapsed.as_secs_f64().max(0.001);
+    DiskIo {
+        read: current.0.saturating_sub(prev.0) as f64 / secs,
+        write: current.1.saturating_sub(prev.1) as f64 / secs,
+    }
+}
+
+/// Parses the usage column of `df -h <path>`.
+fn parse_df(output: &str) -> Option<DiskUsage> {
+    let text = output.lines().nth(1)?.split_whitespace().nth(4)?;
+    Some(DiskUsage {
+        text: text.to_string(),
+        percent: text.strip_suffix('%')?.parse().ok()?,
+    })
+}
+
+/// Parses the first battery line of `pmset -g batt`.
+fn parse_pmset(output: &str) -> Option<BatteryStatus> {
+    let line = output.lines().find(|line| line.contains('%'))?;
+    // pmset shows "charging", "discharging", "charged", "finishing charge"
+    // or "AC attached; not charging" when held at a charge limit
+    let lower = line.to_lowercase();
+    let charging = lower.contains("charging")
+        && !lower.contains("discharging")
+        && !lower.contains("not charging");
+    // The header names the power source
+    let plugged = output.contains("'AC Power'") || lower.contains("ac attached");
+    let pct_pos = line.find('%')?;
+    let start = line[..pct_pos]
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    Some(BatteryStatus {
+        level: line[start..pct_pos].parse().ok()?,
+        charging,
+        plugged,
+        minutes_left: parse_remaining(line),
+        capacity: None,
+    })
+}
+
+/// Parses the "5:12 remaining" estimate of a pmset battery line.
+fn parse_remaining(line: &str) -> Option<u16> {
+    let before = &line[..line.find(" remaining")?];
+    let (hours, minutes) = before.rsplit(' ').next()?.split_once(':')?;
+    let total = hours.parse::<u16>().ok()? * 60 + minutes.parse::<u16>().ok()?;
+    // pmset reports 0:00 while it's still estimating
+    (total > 0).then_some(total)
+}
+
+/// Parses the main CPU sensor (TCMb on Apple Silicon) from `smctemp -l`.
+/// Format: `  TCMb  [flt ]  60.0 (bytes: ...)`
+fn parse_smctemp(output: &str) -> Option<u8> {
+    let line = output
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with("TCMb"))?;
+    // ["TCMb", "[flt", "]", "60.0", "(bytes:", ...]
+    let temp = line.split_whitespace().nth(3)?.parse::<f32>().ok()?;
+    Some(temp.round() as u8)
+}
+
+/// Parses `osx-cpu-temp` output like `63.0°C`. It prints 0.0 on Apple
+/// Silicon, which counts as no reading.
+fn parse_osx_cpu_temp(output: &str) -> Option<u8> {
+    let temp = output.trim().trim_end_matches("°C").parse::<f32>().ok()?;
+    (temp > 0.0).then(|| temp.round() as u8)
+}
+
+fn fetch_memory_details() -> Option<MemoryDetails> {
+    let page_size = mach::sysctl_int(c"hw.pagesize").unwrap_or(4096);
+    let pressure = mach::sysctl_int(c"kern.memorystatus_vm_pressure_level")
+        .map(|level| MemoryPressure::from_level(level as i32))
+        .unwrap_or_default();
+    let mut details = MemoryDetails::new(mach::vm_pages()?, page_size, pressure);
+    details.total = mach::sysctl_int(c"hw.memsize").unwrap_or(0);
+    if let Some(swap) = mach::sysctl::<mach::XswUsage>(c"vm.swapusage") {
+        details.swap_used = swap.used;
+        details.swap_total = swap.total;
+    }
+    Some(details)
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    Command::new(program)
+        .args(args)
+        .output()
+        .ok()
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+}
+
+/// Finds a Homebrew-installed tool, since launchd has a minimal PATH.
+pub fn homebrew_binary(name: &str) -> String {
+    ["/opt/homebrew/bin", "/usr/local/bin"]
+        .iter()
+        .map(|dir| format!("{}/{}", dir, name))
+        .find(|path| std::path::Path::new(path).exists())
+        .unwrap_or_else(|| name.to_string())
+}
+
+fn fetch_temperature() -> Option<u8> {
+    command_output(&homebrew_binary("smctemp"), &["-l"])
+        .and_then(|out| parse_smctemp(&out))
+        .or_else(|| {
+            command_output(&homebrew_binary("osx-cpu-temp"), &[])
+                .and_then(|out| parse_osx_cpu_temp(&out))
+        })
+}
+
+/// Sampler-thread state carried between samples.
+#[derive(Default)]
+struct Sampler {
+    prev_cpu: Option<(u64, u64)>,
+    prev_cores: Option<Vec<(u64, u64)>>,
+    prev_disk_bytes: Option<(Instant, (u64, u64))>,
+    last_sampled: HashMap<Metric, Instant>,
+}
+
+impl Sampler {
+    fn sample(&mut self, metric: &Metric, snapshot: &mut MetricsSnapshot) {
+        match metric {
+            Metric::Cpu => {
+                let current = mach::cpu_ticks();
+                if let (Some(prev), Some(current)) = (self.prev_cpu, current) {
+                    snapshot.cpu = Some(cpu_percent(prev, current));
+                }
+                self.prev_cpu = current.or(self.prev_cpu);
+            }
+            Metric::CpuCores => {
+                let current = mach::core_ticks();
+                if let (Some(prev), Some(current)) = (&self.prev_cores, &current) {
+                    snapshot.cpu_details = Some(CpuDetails {
+                        cores: core_usage(prev, current),
+                        load: mach::load_average(),
+                    });
+                }
+                if current.is_some() {
+                    self.prev_cores = current;
+                }
+            }
+            Metric::Memory => {
+                snapshot.memory = mach::memory_free_level().map(|free| 100 - free);
+                snapshot.memory_details = fetch_memory_details();
+            }
+            Metric::Disk(path) => {
+                match command_output("df", &["-h", path]).and_then(|out| parse_df(&out)) {
+                    Some(usage) => snapshot.disks.insert(path.clone(), usage),
+                    None => snapshot.disks.remove(path),
+                };
+            }
+            Metric::DiskIo => {
+                let now = Instant::now();
+                let current = iokit::disk_bytes();
+                if let (Some((at, prev)), Some(current)) = (self.prev_disk_bytes, current) {
+                    snapshot.disk_io = Some(disk_io(prev, current, now.duration_since(at)));
+                }
+                self.prev_disk_bytes = current.map(|bytes| (now, bytes));
+            }
+            Metric::Temperature => snapshot.temperature = fetch_temperature(),
+            Metric::Battery => {
+                snapshot.battery = command_output("pmset", &["-g", "batt"])
+                    .and_then(|out| parse_pmset(&out))
+                    .map(|battery| BatteryStatus {
+                        capacity: iokit::smart_battery()
+                            .as_ref()
+                            .and_then(BatteryCapacity::new),
+                        ..battery
+                    });
+            }
+            Metric::Power => snapshot.power = iokit::smart_battery().map(PowerStatus::new),
+            Metric::Peripherals => {
+                snapshot.peripherals = dedup_peripherals(iokit::hid_batteries());
+            }
+        }
+    }
+}
+
+type Callback = Arc<dyn Fn(&MetricsSnapshot) + Send + Sync>;
+
+struct Subscriber {
+    id: u64,
+    metric: Metric,
+    interval: Duration,
+    callback: Callback,
+}
+
+static SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+/// Whether the sampler thread is alive (changed only with SUBSCRIBERS held)
+static RUNNING: AtomicBool = AtomicBool::new(false);
+static LATEST: Mutex<Option<MetricsSnapshot>> = Mutex::new(None);
+static HISTORY: Mutex<Option<HashMap<Metric, VecDeque<f32>>>> = Mutex::new(None);
+
+/// Keeps a subscription alive; dropping it unsubscribes.
+pub struct Subscription {
+    id: u64,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Ok(mut subscribers) = SUBSCRIBERS.lock() {
+            subscribers.retain(|s| s.id != self.id);
+        }
+    }
+}
+
+/// Calls `callback` on the sampler thread each time `metric` is sampled,
+/// at least every `interval`. Starts the sampler if it isn't running.
+///
+/// @param metric - Stat to sample
+/// @param interval - Longest acceptable gap between samples
+/// @param callback - Receives the snapshot after each sample; keep it quick
+pub fn subscribe(
+    metric: Metric,
+    interval: Duration,
+    callback: impl Fn(&MetricsSnapshot) + Send + Sync + 'static,
+) -> Subscription {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut subscribers) = SUBSCRIBERS.lock() {
+        subscribers.push(Subscriber {
+            id,
+            metric,
+            interval,
+            callback: Arc::new(callback),
+        });
+        if !RUNNING.swap(true, Ordering::AcqRel) {
+            std::thread::spawn(run_sampler);
+        }
+    }
+    Subscription { id }
+}
+
+/// The most recent snapshot, if the sampler has run.
+pub fn latest() -> Option<MetricsSnapshot> {
+    LATEST.lock().ok()?.clone()
+}
+
+/// Recent values of `metric`, oldest first.
+#[allow(dead_code)]
+pub fn history(metric: &Metric) -> Vec<f32> {
+    HISTORY
+        .lock()
+        .ok()
+        .and_then(|history| Some(history.as_ref()?.get(metric)?.iter().copied().collect()))
+        .unwrap_or_default()
+}
+
+fn record_history(metric: &Metric, value: f32) {
+    if let Ok(mut history) = HISTORY.lock() {
+        let values = history
+            .get_or_insert_with(HashMap::new)
+            .entry(metric.clone())
+            .or_default();
+        if values.len() == HISTORY_LEN {
+            values.pop_front();
+        }
+        values.push_back(value);
+    }
+}
+
+fn run_sampler() {
+    log::debug!("metrics: sampler started");
+    let mut sampler = Sampler::default();
+    loop {
+        let now = Instant::now();
+        let (due, callbacks, sleep) = {
+            let Ok(subscribers) = SUBSCRIBERS.lock() else {
+                RUNNING.store(false, Ordering::Release);
+                return;
+            };
+            if subscribers.is_empty() {
+                RUNNING.store(false, Ordering::Release);
+                log::debug!("metrics: sampler stopped");
+                return;
+            }
+            // Each metric runs at the shortest interval asked for
+            let mut intervals: HashMap<&Metric, Duration> = HashMap::new();
+            for s in subscribers.iter() {
+                intervals
+                    .entry(&s.metric)
+                    .and_modify(|i| *i = (*i).min(s.interval))
+                    .or_insert(s.interval);
+            }
+            let due: Vec<Metric> = intervals
+                .iter()
+                .filter(|(metric, interval)| {
+                    is_due(sampler.last_sampled.get(*metric).copied(), **interval, now)
+                })
+                .map(|(metric, _)| (*metric).clone())
+                .collect();
+            let sleep = intervals
+                .values()
+                .copied()
+                .min()
+                .unwrap_or(MAX_SLEEP)
+                .min(MAX_SLEEP);
+            let callbacks: Vec<Callback> = subscribers
+                .iter()
+                .filter(|s| due.contains(&s.metric))
+                .map(|s| Arc::clone(&s.callback))
+                .collect();
+            (due, callbacks, sleep)
+        };
+
+        if !due.is_empty() {
+            let mut snapshot = latest().unwrap_or_default();
+            for metric in &due {
+                sampler.sample(metric, &mut snapshot);
+                sampler.last_sampled.insert(metric.clone(), now);
+                if let Some(value) = snapshot.value(metric) {
+                    record_history(metric, value);
+                }
+            }
+            if let Ok(mut latest) = LATEST.lock() {
+                *latest = Some(snapshot.clone());
+            }
+            // Called without the lock so callbacks may drop subscriptions
+            for callback in callbacks {
+                callback(&snapshot);
+            }
+        }
+        std::thread::sleep(sleep);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics_are_due_after_their_interval() {
+        let now = Instant::now();
+        let interval = Duration::from_secs(2);
+        assert!(is_due(None, interval, now));
+        assert!(!is_due(Some(now), interval, now + Duration::from_secs(1)));
+        assert!(is_due(Some(now), interval, now + interval));
+    }
+
+    #[test]
+    fn cpu_percent_from_tick_deltas() {
+        assert_eq!(cpu_percent((100, 400), (150, 500)), 50);
+        assert_eq!(cpu_percent((1, 1), (1, 1)), 0);
+    }
+
+    #[test]
+    fn computes_core_usage_from_tick_deltas() {
+        let prev = [(100, 400), (50, 200)];
+        let current = [(150, 500), (50, 300)];
+        assert_eq!(core_usage(&prev, &current), vec![50.0, 0.0]);
+        // A core without new ticks reads as idle
+        assert_eq!(core_usage(&[(1, 1)], &[(1, 1)]), vec![0.0]);
+        assert_eq!(mean(&[50.0, 0.0]), 25.0);
+    }
+
+    #[test]
+    fn splits_cores_into_clusters() {
+        let cluster = |name: &str, cores| CpuCluster {
+            name: name.to_string(),
+            cores,
+            max_frequency: None,
+        };
+        let clusters = [cluster("Efficiency", 2), cluster("Performance", 3)];
+        let cores = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(
+            split_cores(&cores, &clusters),
+            vec![&[1.0, 2.0][..], &[3.0, 4.0, 5.0][..]]
+        );
+        // A topology that doesn't match keeps the cores together
+        assert_eq!(split_cores(&cores[..4], &clusters), vec![&cores[..4]]);
+    }
+
+    #[test]
+    fn reads_top_clock_from_voltage_states() {
+        let table = |freqs: &[u32]| -> Vec<u8> {
+            freqs
+                .iter()
+                .flat_map(|f| [f.to_le_bytes(), 800_000u32.to_le_bytes()].concat())
+                .collect()
+        };
+        assert_eq!(
+            max_frequency(&table(&[600_000_000, 3_228_000_000])),
+            Some(3_228_000_000)
+        );
+        // Newer chips list kHz
+        assert_eq!(
+            max_frequency(&table(&[1_020_000, 4_512_000])),
+            Some(4_512_000_000)
+        );
+        assert_eq!(max_frequency(&[]), None);
+    }
+
+    #[test]
+    fn memory_breakdown_from_pages() {
+        let pages = VmPages {
+            internal: 100,
+            purgeable: 10,
+            external: 40,
+            wired: 20,
+            compressed: 5,
+        };
+        let details = MemoryDetails::new(pages, 16384, MemoryPressure::from_level(2));
+        assert_eq!(details.app, 90 * 16384);
+        assert_eq!(details.cached, 50 * 16384);
+        assert_eq!(details.wired, 20 * 16384);
+        assert_eq!(details.compressed, 5 * 16384);
+        assert_eq!(details.pressure, MemoryPressure::Warning);
+        assert_eq!(MemoryPressure::from_level(4), MemoryPressure::Critical);
+        assert_eq!(MemoryPressure::from_level(1), MemoryPressure::Normal);
+    }
+
+    #[test]
+    fn disk_io_is_bytes_per_second() {
+        let io = disk_io((1_000, 500), (5_000, 500), Duration::from_secs(2));
+        assert_eq!(
+            io,
+            DiskIo {
+                read: 2_000.0,
+                write: 0.0
+            }
+        );
+    }
+
+    #[test]
+    fn parses_df_and_pmset() {
+        let df = "Filesystem  Size  Used Avail Capacity iused ifree %iused  Mounted on\n\
+                  /dev/disk3s1s1 460Gi 10Gi 200Gi 5% 400k 2.1G 0% /\n";
+        assert_eq!(
+            parse_df(df),
+            Some(DiskUsage {
+                text: "5%".to_string(),
+                percent: 5
+            })
+        );
+
+        let pmset = "Now drawing from 'Battery Power'\n \
+                     -InternalBattery-0 (id=1234)\t87%; discharging; 5:12 remaining present: true\n";
+        assert_eq!(
+            parse_pmset(pmset),
+            Some(BatteryStatus {
+                level: 87,
+                charging: false,
+                plugged: false,
+                minutes_left: Some(312),
+                capacity: None,
+            })
+        );
+        let charging = " -InternalBattery-0 (id=1234)\t40%; charging; 1:02 remaining\n";
+        let charging = parse_pmset(charging).unwrap();
+        assert!(charging.charging);
+        assert_eq!(charging.minutes_left, Some(62));
+        let held = "Now drawing from 'AC Power'\n \
+                    -InternalBattery-0 (id=1234)\t80%; AC attached; not charging present: true\n";
+        let held = parse_pmset(held).unwrap();
+        assert!(held.plugged && !held.charging);
+        assert_eq!(held.minutes_left, None);
+        let estimating = " -InternalBattery-0 (id=1234)\t50%; discharging; (no estimate)\n";
+        assert_eq!(parse_pmset(estimating).unwrap().minutes_left, None);
+        assert_eq!(parse_pmset("Now drawing from 'AC Power'\n"), None);
+    }
+
+    #[test]
+    fn battery_capacity_from_raw_readings() {
+        let capacity = BatteryCapacity::new(&SmartBattery {
+            charge_mah: Some(3_912),
+            full_mah: Some(4_382),
+            design_mah: Some(4_790),
+            cycles: Some(213),
+            ..SmartBattery::default()
+        })
+        .unwrap();
+        assert_eq!(capacity.charge_mah, 3_912);
+        assert_eq!(capacity.health(), Some(91));
+        assert_eq!(capacity.cycles, Some(213));
+
+        let missing = SmartBattery {
+            charge_mah: Some(3_912),
+            ..SmartBattery::default()
+        };
+        assert_eq!(BatteryCapacity::new(&missing), None);
+    }
+
+    #[test]
+    fn power_flow_from_battery_readings() {
+        let discharging = PowerStatus::new(SmartBattery {
+            voltage: 12_000,
+            amperage: -1_000,
+            ..SmartBattery::default()
+        });
+        assert_eq!(discharging.state, PowerState::Discharging);
+        assert_eq!(discharging.battery_watts, -12.0);
+        assert_eq!(discharging.adapter, None);
+
+        let charging = PowerStatus::new(SmartBattery {
+            voltage: 12_500,
+            amperage: 2_000,
+            external_connected: true,
+            is_charging: true,
+            adapter_watts: Some(96),
+            adapter_name: Some("96W USB-C Power Adapter".to_string()),
+            system_power_in: Some(40_500),
+        });
+        assert_eq!(charging.state, PowerState::Charging);
+        assert_eq!(charging.battery_watts, 25.0);
+        assert_eq!(charging.input_watts, Some(40.5));
+        assert_eq!(charging.adapter.map(|a| a.watts), Some(96));
+
+        let full = PowerStatus::new(SmartBattery {
+            external_connected: true,
+            ..SmartBattery::default()
+        });
+        assert_eq!(full.state, PowerState::OnAdapter);
+    }
+
+    #[test]
+    fn peripherals_are_named_and_listed_once() {
+        let peripherals = dedup_peripherals(vec![
+            PeripheralBattery::new("Magic Trackpad".to_string(), 64),
+            PeripheralBattery::new("Magic Keyboard with Touch ID".to_string(), 12),
+            PeripheralBattery::new("Magic Trackpad".to_string(), 64),
+            PeripheralBattery::new("MX Master 3 Mouse".to_string(), 140),
+        ]);
+        let kinds: Vec<(PeripheralKind, u8)> =
+            peripherals.iter().map(|p| (p.kind, p.level)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (PeripheralKind::Keyboard, 12),
+                (PeripheralKind::Trackpad, 64),
+                (PeripheralKind::Mouse, 100),
+            ]
+        );
+        assert_eq!(PeripheralKind::from_name("AirPods"), PeripheralKind::Other);
+        let snapshot = MetricsSnapshot {
+            peripherals,
+            ..Default::default()
+        };
+        assert_eq!(snapshot.value(&Metric::Peripherals), Some(12.0));
+    }
+
+    #[test]
+    fn parses_temperature_tools() {
+        let smctemp = "  TC0P  [flt ]  48.2 (bytes: ...)\n  TCMb  [flt ]  60.4 (bytes: ...)\n";
+        assert_eq!(parse_smctemp(smctemp), Some(60));
+        assert_eq!(parse_osx_cpu_temp("63.0°C\n"), Some(63));
+        assert_eq!(parse_osx_cpu_temp("0.0°C\n"), None);
+    }
+}
@@ -0,0 +1,16 @@
+//! System data sources shared between modules.
+
+pub mod audio_devices;
+pub mod ci;
+pub mod display;
+pub mod environment;
+pub mod ics;
+pub mod idle;
+pub mod media;
+pub mod metrics;
+pub mod mqtt;
+pub mod osa;
+pub mod processes;
+pub mod providers;
+pub mod websocket;
+pub mod wifi;
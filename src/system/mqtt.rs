@@ -0,0 +1,427 @@
+//! Minimal MQTT 3.1.1 client over plain TCP.
+//!
+//! Covers what the mqtt module needs: connect with optional credentials,
+//! subscribe at QoS 0, receive publishes, publish at QoS 0 and keep the
+//! connection alive. No TLS, so use it with a broker on the local network.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const PUBACK: u8 = 0x40;
+const SUBSCRIBE: u8 = 0x82;
+const PINGREQ: u8 = 0xC0;
+const DISCONNECT: u8 = 0xE0;
+
+/// Largest packet accepted from the broker.
+const MAX_PACKET: usize = 1024 * 1024;
+
+/// How long to wait for the broker's reply to CONNECT.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where and how to connect.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub keep_alive: Duration,
+}
+
+/// Parses `mqtt://host:port`, `tcp://host:port`, `host:port` or `host`.
+pub fn parse_broker(broker: &str) -> Result<(String, u16), String> {
+    if broker.starts_with("mqtts://") || broker.starts_with("ssl://") {
+        return Err("TLS brokers aren't supported, use mqtt://".to_string());
+    }
+    let address = broker
+        .strip_prefix("mqtt://")
+        .or_else(|| broker.strip_prefix("tcp://"))
+        .unwrap_or(broker)
+        .trim_end_matches('/');
+    let (host, port) = match address.rsplit_once(':') {
+        Some((host, port)) => (
+            host,
+            port.parse()
+                .map_err(|_| format!("invalid port in '{}'", broker))?,
+        ),
+        None => (address, 1883),
+    };
+    if host.is_empty() {
+        return Err(format!("no host in '{}'", broker));
+    }
+    Ok((host.to_string(), port))
+}
+
+/// A client id unique to this process and call, short enough for the 23
+/// characters every MQTT 3.1.1 broker accepts.
+pub fn client_id() -> String {
+    static NEXT: AtomicU32 = AtomicU32::new(0);
+    format!(
+        "sinew-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// A message received on a subscribed topic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub topic: String,
+    pub payload: Vec<u8>,
+}
+
+/// A packet from the broker, as far as the client cares.
+#[derive(Debug, PartialEq)]
+enum Packet {
+    ConnAck { code: u8 },
+    Publish { message: Message, ack: Option<u16> },
+    Other,
+}
+
+/// A connected client.
+pub struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    keep_alive: Duration,
+    last_sent: Instant,
+    next_packet_id: u16,
+}
+
+impl Client {
+    /// Connects and waits for the broker to accept.
+    pub fn connect(options: &Options) -> io::Result<Self> {
+        let address = (options.host.as_str(), options.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "broker not found"))?;
+        let stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)?;
+        stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+        let mut client = Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            keep_alive: options.keep_alive,
+            last_sent: Instant::now(),
+            next_packet_id: 1,
+        };
+        client.send(&encode_connect(options))?;
+        match read_packet(&mut client.reader)? {
+            Packet::ConnAck { code: 0 } => Ok(client),
+            Packet::ConnAck { code } => Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                connack_error(code),
+            )),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "broker didn't acknowledge the connection",
+            )),
+        }
+    }
+
+    /// Subscribes to topic filters (wildcards allowed) at QoS 0.
+    pub fn subscribe(&mut self, topics: &[String]) -> io::Result<()> {
+        let id = self.packet_id();
+        self.send(&encode_subscribe(id, topics))
+    }
+
+    pub fn publish(&mut self, topic: &str, payload: &[u8], retain: bool) -> io::Result<()> {
+        self.send(&encode_publish(topic, payload, retain))
+    }
+
+    /// Waits up to `timeout` for a message, pinging the broker when the
+    /// connection has been quiet. Ok(None) when nothing arrived.
+    pub fn poll(&mut self, timeout: Duration) -> io::Result<Option<Message>> {
+        if self.last_sent.elapsed() >= self.keep_alive / 2 {
+            self.send(&[PINGREQ, 0])?;
+        }
+        self.reader.get_ref().set_read_timeout(Some(timeout))?;
+        // Wait for the first byte only; the rest of a packet follows quickly
+        match self.reader.fill_buf() {
+            Ok([]) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(_) => {}
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(None)
+            }
+            Err(e) => return Err(e),
+        }
+        self.reader
+            .get_ref()
+            .set_read_timeout(Some(CONNECT_TIMEOUT))?;
+        match read_packet(&mut self.reader)? {
+            Packet::Publish { message, ack } => {
+                if let Some(id) = ack {
+                    let [high, low] = id.to_be_bytes();
+                    self.send(&[PUBACK, 2, high, low])?;
+                }
+                Ok(Some(message))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn disconnect(mut self) {
+        let _ = self.send(&[DISCONNECT, 0]);
+    }
+
+    fn send(&mut self, packet: &[u8]) -> io::Result<()> {
+        self.writer.write_all(packet)?;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+
+    fn packet_id(&mut self) -> u16 {
+        let id = self.next_packet_id;
+        self.next_packet_id = self.next_packet_id.checked_add(1).unwrap_or(1);
+        id
+    }
+}
+
+fn connack_error(code: u8) -> &'static str {
+    match code {
+        1 => "broker refused the protocol version",
+        2 => "broker rejected the client id",
+        3 => "broker unavailable",
+        4 => "bad username or password",
+        5 => "not authorized",
+        _ => "broker refused the connection",
+    }
+}
+
+/// Appends an MQTT string: two length bytes, then the bytes.
+fn put_string(out: &mut Vec<u8>, value: &[u8]) {
+    out.extend_from_slice(&(value.len().min(u16::MAX as usize) as u16).to_be_bytes());
+    out.extend_from_slice(&value[..value.len().min(u16::MAX as usize)]);
+}
+
+/// Prefixes a packet body with its fixed header.
+fn packet(header: u8, body: Vec<u8>) -> Vec<u8> {
+    let mut out = vec![header];
+    let mut length = body.len();
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if length == 0 {
+            break;
+        }
+    }
+    out.extend(body);
+    out
+}
+
+fn encode_connect(options: &Options) -> Vec<u8> {
+    let mut flags = 0x02; // Clean session
+    if options.username.is_some() {
+        flags |= 0x80;
+    }
+    if options.password.is_some() {
+        flags |= 0x40;
+    }
+    let mut body = Vec::new();
+    put_string(&mut body, b"MQTT");
+    body.push(4); // Protocol level 3.1.1
+    body.push(flags);
+    let keep_alive = options.keep_alive.as_secs().min(u16::MAX as u64) as u16;
+    body.extend_from_slice(&keep_alive.to_be_bytes());
+    put_string(&mut body, options.client_id.as_bytes());
+    if let Some(username) = &options.username {
+        put_string(&mut body, username.as_bytes());
+    }
+    if let Some(password) = &options.password {
+        put_string(&mut body, password.as_bytes());
+    }
+    packet(CONNECT, body)
+}
+
+fn encode_subscribe(id: u16, topics: &[String]) -> Vec<u8> {
+    let mut body = id.to_be_bytes().to_vec();
+    for topic in topics {
+        put_string(&mut body, topic.as_bytes());
+        body.push(0); // QoS 0
+    }
+    packet(SUBSCRIBE, body)
+}
+
+fn encode_publish(topic: &str, payload: &[u8], retain: bool) -> Vec<u8> {
+    let mut body = Vec::new();
+    put_string(&mut body, topic.as_bytes());
+    body.extend_from_slice(payload);
+    packet(PUBLISH | u8::from(retain), body)
+}
+
+fn read_packet(reader: &mut impl Read) -> io::Result<Packet> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+    let mut byte = [0u8];
+    reader.read_exact(&mut byte)?;
+    let header = byte[0];
+    let mut length = 0usize;
+    let mut shift = 0;
+    loop {
+        reader.read_exact(&mut byte)?;
+        length |= usize::from(byte[0] & 0x7F) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+        if shift > 21 {
+            return Err(invalid("malformed packet length"));
+        }
+    }
+    if length > MAX_PACKET {
+        return Err(invalid("packet too large"));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    match header & 0xF0 {
+        CONNACK if body.len() >= 2 => Ok(Packet::ConnAck { code: body[1] }),
+        PUBLISH => {
+            let qos = (header >> 1) & 0x03;
+            if body.len() < 2 {
+                return Err(invalid("truncated publish"));
+            }
+            let topic_len = usize::from(u16::from_be_bytes([body[0], body[1]]));
+            let mut offset = 2 + topic_len;
+            let topic = body
+                .get(2..offset)
+                .and_then(|topic| std::str::from_utf8(topic).ok())
+                .ok_or_else(|| invalid("invalid publish topic"))?
+                .to_string();
+            let mut ack = None;
+            if qos > 0 {
+                let id = body
+                    .get(offset..offset + 2)
+                    .ok_or_else(|| invalid("truncated publish"))?;
+                // QoS 2 isn't requested, so only QoS 1 needs an answer
+                if qos == 1 {
+                    ack = Some(u16::from_be_bytes([id[0], id[1]]));
+                }
+                offset += 2;
+            }
+            Ok(Packet::Publish {
+                message: Message {
+                    topic,
+                    payload: body[offset..].to_vec(),
+                },
+                ack,
+            })
+        }
+        _ => Ok(Packet::Other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn parses_broker_addresses() {
+        assert_eq!(
+            parse_broker("mqtt://nas.local:1884"),
+            Ok(("nas.local".to_string(), 1884))
+        );
+        assert_eq!(
+            parse_broker("192.168.1.5"),
+            Ok(("192.168.1.5".to_string(), 1883))
+        );
+        assert_eq!(
+            parse_broker("tcp://broker:1883/"),
+            Ok(("broker".to_string(), 1883))
+        );
+        assert!(parse_broker("mqtts://broker:8883").is_err());
+        assert!(parse_broker("broker:http").is_err());
+        assert!(parse_broker("mqtt://:1883").is_err());
+    }
+
+    #[test]
+    fn encodes_connect() {
+        let options = Options {
+            host: "broker".to_string(),
+            port: 1883,
+            client_id: "bar".to_string(),
+            username: Some("u".to_string()),
+            password: Some("p".to_string()),
+            keep_alive: Duration::from_secs(30),
+        };
+        assert_eq!(
+            encode_connect(&options),
+            [
+                &[0x10, 21, 0, 4][..],
+                b"MQTT",
+                &[4, 0xC2, 0, 30, 0, 3],
+                b"bar",
+                &[0, 1, b'u', 0, 1, b'p'],
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn encodes_subscribe_and_publish() {
+        assert_eq!(
+            encode_subscribe(7, &["a/#".to_string()]),
+            [0x82, 8, 0, 7, 0, 3, b'a', b'/', b'#', 0]
+        );
+        assert_eq!(
+            encode_publish("t", b"on", true),
+            [0x31, 5, 0, 1, b't', b'o', b'n']
+        );
+    }
+
+    #[test]
+    fn encodes_long_lengths() {
+        let encoded = packet(PUBLISH, vec![0; 321]);
+        assert_eq!(&encoded[..3], [PUBLISH, 0xC1, 0x02]);
+        assert_eq!(encoded.len(), 324);
+    }
+
+    #[test]
+    fn reads_packets() {
+        let mut input = Cursor::new(vec![0x20, 2, 0, 4]);
+        assert_eq!(
+            read_packet(&mut input).unwrap(),
+            Packet::ConnAck { code: 4 }
+        );
+
+        let mut input = Cursor::new(vec![0x30, 7, 0, 3, b'a', b'/', b'b', b'4', b'2']);
+        assert_eq!(
+            read_packet(&mut input).unwrap(),
+            Packet::Publish {
+                message: Message {
+                    topic: "a/b".to_string(),
+                    payload: b"42".to_vec(),
+                },
+                ack: None,
+            }
+        );
+
+        // QoS 1 carries a packet id to acknowledge
+        let mut input = Cursor::new(vec![0x32, 6, 0, 1, b't', 0, 9, b'x']);
+        let Packet::Publish { message, ack } = read_packet(&mut input).unwrap() else {
+            panic!("expected a publish");
+        };
+        assert_eq!(message.payload, b"x");
+        assert_eq!(ack, Some(9));
+
+        let mut input = Cursor::new(vec![0xD0, 0]);
+        assert_eq!(read_packet(&mut input).unwrap(), Packet::Other);
+
+        let mut input = Cursor::new(vec![0x30, 5, 0, 9, b'a']);
+        assert!(read_packet(&mut input).is_err());
+    }
+}
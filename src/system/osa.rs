@@ -0,0 +1,145 @@
+//! AppleScript and JavaScript for Automation run in-process through OSAKit.
+//!
+//! A script is compiled once and then executed as often as needed, without
+//! spawning `osascript` each time. Scripts that talk to other apps send
+//! Apple Events, which need the Automation permission for that app; macOS
+//! asks the first time. A [`Script`] isn't shared between threads: each
+//! module compiles and runs its own on its poll thread.
+
+use std::fmt;
+
+use objc2::msg_send;
+use objc2::rc::{autoreleasepool, Allocated, Retained};
+use objc2::runtime::{AnyClass, AnyObject, Bool};
+use objc2_foundation::NSString;
+
+#[link(name = "OSAKit", kind = "framework")]
+extern "C" {
+    /// Key of the error message in OSAKit error dictionaries
+    static OSAScriptErrorMessageKey: &'static NSString;
+}
+
+/// Scripting language of a script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    AppleScript,
+    JavaScript,
+}
+
+impl Language {
+    /// Parses a config name: `applescript`, or `javascript`/`jxa`.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "applescript" => Some(Self::AppleScript),
+            "javascript" | "jxa" => Some(Self::JavaScript),
+            _ => None,
+        }
+    }
+
+    /// Name OSAKit knows the language by.
+    fn osa_name(self) -> &'static str {
+        match self {
+            Self::AppleScript => "AppleScript",
+            Self::JavaScript => "JavaScript",
+        }
+    }
+}
+
+/// Why a script didn't produce a result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptError {
+    /// OSAKit or the language isn't available
+    Unavailable,
+    /// The source doesn't compile
+    Compile(String),
+    /// Running the script failed
+    Run(String),
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unavailable => write!(f, "OSAKit is not available"),
+            Self::Compile(message) => write!(f, "compile error: {}", message),
+            Self::Run(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// A compiled `OSAScript`.
+pub struct Script(Retained<AnyObject>);
+
+impl Script {
+    /// Compiles `source` in `language`.
+    pub fn compile(source: &str, language: Language) -> Result<Self, ScriptError> {
+        autoreleasepool(|_| unsafe {
+            let language_class = AnyClass::get(c"OSALanguage").ok_or(ScriptError::Unavailable)?;
+            let script_class = AnyClass::get(c"OSAScript").ok_or(ScriptError::Unavailable)?;
+            let name = NSString::from_str(language.osa_name());
+            let osa_language: Option<Retained<AnyObject>> =
+                msg_send![language_class, languageForName: &*name];
+            let osa_language = osa_language.ok_or(ScriptError::Unavailable)?;
+
+            let source = NSString::from_str(source);
+            let allocated: Allocated<AnyObject> = msg_send![script_class, alloc];
+            let script: Option<Retained<AnyObject>> =
+                msg_send![allocated, initWithSource: &*source, language: &*osa_language];
+            let script = script.ok_or(ScriptError::Unavailable)?;
+
+            let mut error: *mut AnyObject = std::ptr::null_mut();
+            let compiled: Bool =
+                msg_send![&*script, compileAndReturnError: &mut error as *mut *mut AnyObject];
+            if compiled.as_bool() {
+                Ok(Self(script))
+            } else {
+                Err(ScriptError::Compile(error_message(error)))
+            }
+        })
+    }
+
+    /// Runs the script and returns its result as text (empty for results
+    /// that have no text form, like lists of records).
+    pub fn run(&self) -> Result<String, ScriptError> {
+        autoreleasepool(|_| unsafe {
+            let mut error: *mut AnyObject = std::ptr::null_mut();
+            let result: Option<Retained<AnyObject>> = msg_send![
+                &*self.0,
+                executeAndReturnError: &mut error as *mut *mut AnyObject
+            ];
+            let Some(result) = result else {
+                return Err(ScriptError::Run(error_message(error)));
+            };
+            let text: Option<Retained<NSString>> = msg_send![&*result, stringValue];
+            Ok(text.map(|text| text.to_string()).unwrap_or_default())
+        })
+    }
+}
+
+/// Message of an OSAKit error dictionary.
+unsafe fn error_message(error: *mut AnyObject) -> String {
+    let Some(error) = error.as_ref() else {
+        return "unknown error".to_string();
+    };
+    let message: Option<Retained<AnyObject>> =
+        msg_send![error, objectForKey: OSAScriptErrorMessageKey];
+    message
+        .map(|message| {
+            let message: Retained<NSString> = Retained::cast_unchecked(message);
+            message.to_string()
+        })
+        .unwrap_or_else(|| "unknown error".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_language_names() {
+        assert_eq!(Language::parse("applescript"), Some(Language::AppleScript));
+        assert_eq!(Language::parse("JavaScript"), Some(Language::JavaScript));
+        assert_eq!(Language::parse("jxa"), Some(Language::JavaScript));
+        assert_eq!(Language::parse("python"), None);
+    }
+}
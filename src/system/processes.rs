@@ -0,0 +1,388 @@
+//! Shared process sampler.
+//!
+//! Samples the CPU and resident memory of every process through libproc on
+//! one background thread, so modules that need the process table subscribe
+//! here instead of each shelling out to `ps`. The thread only runs while
+//! something is subscribed and samples at the shortest interval any
+//! subscriber asked for.
+//!
+//! Processes owned by other users are listed by name only: libproc won't
+//! hand out their task info without root, so their CPU and memory read 0.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// libproc and Mach FFI (both live in libSystem, so nothing extra to link).
+mod libproc {
+    use std::ffi::{c_int, c_void};
+    use std::mem::MaybeUninit;
+    use std::sync::OnceLock;
+
+    const PROC_PIDTASKINFO: c_int = 4;
+    /// `2 * MAXCOMLEN` plus the terminator
+    const NAME_SIZE: usize = 33;
+
+    #[repr(C)]
+    #[allow(dead_code)]
+    struct ProcTaskInfo {
+        pti_virtual_size: u64,
+        pti_resident_size: u64,
+        pti_total_user: u64,
+        pti_total_system: u64,
+        pti_threads_user: u64,
+        pti_threads_system: u64,
+        pti_policy: i32,
+        pti_faults: i32,
+        pti_pageins: i32,
+        pti_cow_faults: i32,
+        pti_messages_sent: i32,
+        pti_messages_received: i32,
+        pti_syscalls_mach: i32,
+        pti_syscalls_unix: i32,
+        pti_csw: i32,
+        pti_threadnum: i32,
+        pti_numrunning: i32,
+        pti_priority: i32,
+    }
+
+    #[repr(C)]
+    struct MachTimebaseInfo {
+        numer: u32,
+        denom: u32,
+    }
+
+    extern "C" {
+        fn proc_listallpids(buffer: *mut c_void, buffersize: c_int) -> c_int;
+        fn proc_pidinfo(
+            pid: c_int,
+            flavor: c_int,
+            arg: u64,
+            buffer: *mut c_void,
+            buffersize: c_int,
+        ) -> c_int;
+        fn proc_name(pid: c_int, buffer: *mut c_void, buffersize: u32) -> c_int;
+        fn proc_listchildpids(ppid: c_int, buffer: *mut c_void, buffersize: c_int) -> c_int;
+        fn mach_timebase_info(info: *mut MachTimebaseInfo) -> c_int;
+    }
+
+    /// Returns the ids of all running processes.
+    pub fn pids() -> Vec<u32> {
+        unsafe {
+            let count = proc_listallpids(std::ptr::null_mut(), 0);
+            if count <= 0 {
+                return Vec::new();
+            }
+            // Leave room for processes spawned between the two calls
+            let mut pids = vec![0 as c_int; count as usize + 64];
+            let size = (pids.len() * std::mem::size_of::<c_int>()) as c_int;
+            let count = proc_listallpids(pids.as_mut_ptr() as *mut c_void, size);
+            pids.truncate(count.max(0) as usize);
+            pids.into_iter()
+                .filter(|pid| *pid > 0)
+                .map(|pid| pid as u32)
+                .collect()
+        }
+    }
+
+    /// Returns the ids of a process's direct children.
+    pub fn child_pids(pid: u32) -> Vec<u32> {
+        let mut pids = [0 as c_int; 256];
+        let size = std::mem::size_of_val(&pids) as c_int;
+        let count =
+            unsafe { proc_listchildpids(pid as c_int, pids.as_mut_ptr() as *mut c_void, size) };
+        pids.iter()
+            .take(count.max(0) as usize)
+            .filter(|pid| **pid > 0)
+            .map(|pid| *pid as u32)
+            .collect()
+    }
+
+    /// Returns the process name, which unlike `comm` isn't cut at 16 bytes.
+    pub fn name(pid: u32) -> Option<String> {
+        let mut buffer = [0u8; NAME_SIZE];
+        let len = unsafe {
+            proc_name(
+                pid as c_int,
+                buffer.as_mut_ptr() as *mut c_void,
+                buffer.len() as u32,
+            )
+        };
+        if len <= 0 {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&buffer[..len as usize]).into_owned())
+    }
+
+    /// Returns (cpu time in ns, resident bytes), or None without permission.
+    pub fn task_info(pid: u32) -> Option<(u64, u64)> {
+        let size = std::mem::size_of::<ProcTaskInfo>() as c_int;
+        let mut info = MaybeUninit::<ProcTaskInfo>::uninit();
+        let read = unsafe {
+            proc_pidinfo(
+                pid as c_int,
+                PROC_PIDTASKINFO,
+                0,
+                info.as_mut_ptr() as *mut c_void,
+                size,
+            )
+        };
+        if read != size {
+            return None;
+        }
+        let info = unsafe { info.assume_init() };
+        // Task times are in Mach absolute time units, not nanoseconds
+        let (numer, denom) = timebase();
+        let ticks = info.pti_total_user + info.pti_total_system;
+        let cpu_ns = (ticks as u128 * numer as u128 / denom.max(1) as u128) as u64;
+        Some((cpu_ns, info.pti_resident_size))
+    }
+
+    fn timebase() -> (u32, u32) {
+        static TIMEBASE: OnceLock<(u32, u32)> = OnceLock::new();
+        *TIMEBASE.get_or_init(|| {
+            let mut info = MachTimebaseInfo { numer: 1, denom: 1 };
+            unsafe { mach_timebase_info(&mut info) };
+            (info.numer, info.denom)
+        })
+    }
+}
+
+/// A running process.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    /// CPU usage in percent of one core
+    pub cpu: f32,
+    /// Resident memory in bytes
+    pub memory: u64,
+}
+
+/// How to rank processes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProcessSort {
+    #[default]
+    Cpu,
+    Memory,
+}
+
+/// One pass over the process table.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub processes: Vec<ProcessInfo>,
+}
+
+impl Snapshot {
+    /// The `count` busiest processes by `sort`.
+    pub fn top(&self, sort: ProcessSort, count: usize) -> Vec<ProcessInfo> {
+        let mut processes = self.processes.clone();
+        match sort {
+            ProcessSort::Cpu => processes.sort_by(|a, b| b.cpu.total_cmp(&a.cpu)),
+            ProcessSort::Memory => processes.sort_by(|a, b| b.memory.cmp(&a.memory)),
+        }
+        processes.truncate(count);
+        processes
+    }
+
+    /// Sorted, deduplicated process names.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.processes.iter().map(|p| p.name.clone()).collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+/// CPU usage in percent of one core from two cumulative CPU times.
+/// A process that's new since the last sample reads as 0.
+pub fn cpu_percent(prev_ns: Option<u64>, now_ns: u64, elapsed: Duration) -> f32 {
+    let Some(prev_ns) = prev_ns else {
+        return 0.0;
+    };
+    let elapsed_ns = elapsed.as_nanos().max(1) as f64;
+    (now_ns.saturating_sub(prev_ns) as f64 / elapsed_ns * 100.0) as f32
+}
+
+/// Returns the ids of a process's direct children.
+pub fn child_pids(pid: u32) -> Vec<u32> {
+    libproc::child_pids(pid)
+}
+
+/// Returns the CPU time a process has used, in nanoseconds.
+pub fn cpu_time(pid: u32) -> Option<u64> {
+    libproc::task_info(pid).map(|(cpu_ns, _)| cpu_ns)
+}
+
+type Callback = Arc<dyn Fn(&Arc<Snapshot>) + Send + Sync>;
+
+struct Subscriber {
+    id: u64,
+    interval: Duration,
+    callback: Callback,
+}
+
+static SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+/// Whether the sampler thread is alive (changed only with SUBSCRIBERS held)
+static RUNNING: AtomicBool = AtomicBool::new(false);
+static LATEST: Mutex<Option<Arc<Snapshot>>> = Mutex::new(None);
+
+/// Keeps a subscription alive; dropping it unsubscribes.
+pub struct Subscription {
+    id: u64,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Ok(mut subscribers) = SUBSCRIBERS.lock() {
+            subscribers.retain(|s| s.id != self.id);
+        }
+    }
+}
+
+/// Calls `callback` on the sampler thread with each new snapshot, at least
+/// every `interval`. Starts the sampler if it isn't running.
+///
+/// @param interval - Longest acceptable gap between snapshots
+/// @param callback - Receives every snapshot; keep it quick
+pub fn subscribe(
+    interval: Duration,
+    callback: impl Fn(&Arc<Snapshot>) + Send + Sync + 'static,
+) -> Subscription {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut subscribers) = SUBSCRIBERS.lock() {
+        subscribers.push(Subscriber {
+            id,
+            interval,
+            callback: Arc::new(callback),
+        });
+        if !RUNNING.swap(true, Ordering::AcqRel) {
+            std::thread::spawn(run_sampler);
+        }
+    }
+    Subscription { id }
+}
+
+/// The most recent snapshot, if the sampler has run.
+pub fn latest() -> Option<Arc<Snapshot>> {
+    LATEST.lock().ok()?.clone()
+}
+
+fn run_sampler() {
+    log::debug!("processes: sampler started");
+    let mut prev: HashMap<u32, u64> = HashMap::new();
+    let mut prev_at = Instant::now();
+    loop {
+        let (interval, callbacks) = {
+            let Ok(subscribers) = SUBSCRIBERS.lock() else {
+                RUNNING.store(false, Ordering::Release);
+                return;
+            };
+            if subscribers.is_empty() {
+                RUNNING.store(false, Ordering::Release);
+                log::debug!("processes: sampler stopped");
+                return;
+            }
+            let interval = subscribers
+                .iter()
+                .map(|s| s.interval)
+                .min()
+                .unwrap_or(Duration::from_secs(2));
+            let callbacks: Vec<Callback> = subscribers
+                .iter()
+                .map(|s| Arc::clone(&s.callback))
+                .collect();
+            (interval, callbacks)
+        };
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(prev_at);
+        let mut next = HashMap::with_capacity(prev.len());
+        let processes = libproc::pids()
+            .into_iter()
+            .filter_map(|pid| {
+                let name = libproc::name(pid)?;
+                let (cpu, memory) = match libproc::task_info(pid) {
+                    Some((cpu_ns, memory)) => {
+                        next.insert(pid, cpu_ns);
+                        let cpu = cpu_percent(prev.get(&pid).copied(), cpu_ns, elapsed);
+                        (cpu, memory)
+                    }
+                    None => (0.0, 0),
+                };
+                Some(ProcessInfo {
+                    pid,
+                    name,
+                    cpu,
+                    memory,
+                })
+            })
+            .collect();
+        prev = next;
+        prev_at = now;
+
+        let snapshot = Arc::new(Snapshot { processes });
+        if let Ok(mut latest) = LATEST.lock() {
+            *latest = Some(Arc::clone(&snapshot));
+        }
+        // Called without the lock so callbacks may drop subscriptions
+        for callback in callbacks {
+            callback(&snapshot);
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, name: &str, cpu: f32, memory: u64) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.to_string(),
+            cpu,
+            memory,
+        }
+    }
+
+    #[test]
+    fn ranks_processes_by_cpu_or_memory() {
+        let snapshot = Snapshot {
+            processes: vec![
+                process(1, "launchd", 0.0, 8_000),
+                process(420, "Google Chrome Helper", 35.2, 200_000),
+                process(99, "WindowServer", 80.5, 1_000),
+            ],
+        };
+        let by_cpu = snapshot.top(ProcessSort::Cpu, 2);
+        assert_eq!(
+            by_cpu.iter().map(|p| p.pid).collect::<Vec<_>>(),
+            vec![99, 420]
+        );
+        assert_eq!(snapshot.top(ProcessSort::Memory, 1)[0].pid, 420);
+    }
+
+    #[test]
+    fn names_are_sorted_and_unique() {
+        let snapshot = Snapshot {
+            processes: vec![
+                process(3, "zsh", 0.0, 0),
+                process(1, "Finder", 0.0, 0),
+                process(2, "zsh", 0.0, 0),
+            ],
+        };
+        assert_eq!(snapshot.names(), vec!["Finder", "zsh"]);
+    }
+
+    #[test]
+    fn cpu_percent_is_relative_to_wall_time() {
+        let second = Duration::from_secs(1);
+        assert_eq!(cpu_percent(Some(0), 500_000_000, second), 50.0);
+        // Two busy cores read above 100%
+        assert_eq!(cpu_percent(Some(0), 2_000_000_000, second), 200.0);
+        assert_eq!(cpu_percent(None, 500_000_000, second), 0.0);
+    }
+}
@@ -0,0 +1,274 @@
+//! Data providers for the battery, volume, wifi and now playing modules.
+//!
+//! These modules get their readings through a provider handed to them at
+//! construction instead of calling IOKit, AppleScript or private frameworks
+//! themselves. The bar gives them the `System*` providers; tests give them
+//! the ones in [`mock`], which return fixed readings.
+
+use std::any::Any;
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::media::{self, PlayerTrack};
+use super::metrics::{self, BatteryStatus, Metric};
+use super::wifi::{self, WifiNetwork, WifiStatus};
+
+/// Called with each battery reading; None on Macs without a battery.
+pub type BatteryCallback = Box<dyn Fn(Option<BatteryStatus>) + Send + Sync>;
+
+/// Battery charge readings.
+pub trait BatteryProvider: Send + Sync {
+    /// Calls `on_reading` with a reading at least every `interval` until
+    /// the returned guard is dropped.
+    fn watch(&self, interval: Duration, on_reading: BatteryCallback) -> Box<dyn Any + Send + Sync>;
+}
+
+/// Output volume and mute state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AudioStatus {
+    /// Volume, 0–100
+    pub level: u8,
+    pub muted: bool,
+}
+
+/// Reads and sets the output volume.
+pub trait AudioProvider: Send + Sync {
+    fn status(&self) -> AudioStatus;
+    /// Sets the volume (0–100) and unmutes.
+    fn set_level(&self, level: u8) -> std::io::Result<()>;
+}
+
+/// Wi-Fi state and control.
+pub trait NetworkProvider: Send + Sync {
+    /// State of the Wi-Fi interface, None on Macs without one.
+    fn status(&self) -> Option<WifiStatus>;
+    /// Known networks in range, strongest first. Blocks while scanning.
+    fn known_networks(&self) -> Vec<WifiNetwork>;
+    /// Joins a known network with its saved password.
+    fn join(&self, ssid: &str) -> std::io::Result<()>;
+    /// Turns Wi-Fi on or off.
+    fn set_power(&self, on: bool) -> std::io::Result<()>;
+}
+
+/// What media players are playing.
+pub trait MediaProvider: Send + Sync {
+    /// The track macOS shows as now playing, from whichever app.
+    fn system_track(&self) -> Option<PlayerTrack>;
+    /// The track of a player app, None when it isn't running or is stopped.
+    fn app_track(&self, app: &str) -> Option<PlayerTrack>;
+}
+
+/// Battery readings from the shared metrics service.
+pub struct SystemBattery;
+
+impl BatteryProvider for SystemBattery {
+    fn watch(&self, interval: Duration, on_reading: BatteryCallback) -> Box<dyn Any + Send + Sync> {
+        Box::new(metrics::subscribe(
+            Metric::Battery,
+            interval,
+            move |snapshot| on_reading(snapshot.battery),
+        ))
+    }
+}
+
+/// Volume through AppleScript's volume settings.
+pub struct SystemAudio;
+
+impl SystemAudio {
+    fn osascript(script: &str) -> Option<String> {
+        Command::new("osascript")
+            .args(["-e", script])
+            .output()
+            .ok()
+            .and_then(|o| String::from_utf8(o.stdout).ok())
+    }
+}
+
+impl AudioProvider for SystemAudio {
+    fn status(&self) -> AudioStatus {
+        let level = Self::osascript("output volume of (get volume settings)")
+            .and_then(|level| level.trim().parse().ok())
+            .unwrap_or(0);
+        let muted = Self::osascript("output muted of (get volume settings)")
+            .is_some_and(|muted| muted.trim() == "true");
+        AudioStatus { level, muted }
+    }
+
+    fn set_level(&self, level: u8) -> std::io::Result<()> {
+        let script = format!("set volume output volume {} without output muted", level);
+        Command::new("osascript")
+            .args(["-e", &script])
+            .status()
+            .map(|_| ())
+    }
+}
+
+/// Wi-Fi state from CoreWLAN.
+pub struct SystemNetwork;
+
+impl NetworkProvider for SystemNetwork {
+    fn status(&self) -> Option<WifiStatus> {
+        wifi::status()
+    }
+
+    fn known_networks(&self) -> Vec<WifiNetwork> {
+        wifi::known_networks()
+    }
+
+    fn join(&self, ssid: &str) -> std::io::Result<()> {
+        wifi::join(ssid)
+    }
+
+    fn set_power(&self, on: bool) -> std::io::Result<()> {
+        wifi::set_power(on)
+    }
+}
+
+/// Tracks from MediaRemote and player apps.
+pub struct SystemMedia;
+
+impl MediaProvider for SystemMedia {
+    fn system_track(&self) -> Option<PlayerTrack> {
+        media::system_track()
+    }
+
+    fn app_track(&self, app: &str) -> Option<PlayerTrack> {
+        media::app_track(app)
+    }
+}
+
+/// The real providers, shared by every module that asks.
+pub fn battery() -> Arc<dyn BatteryProvider> {
+    Arc::new(SystemBattery)
+}
+
+pub fn audio() -> Arc<dyn AudioProvider> {
+    Arc::new(SystemAudio)
+}
+
+pub fn network() -> Arc<dyn NetworkProvider> {
+    Arc::new(SystemNetwork)
+}
+
+pub fn media() -> Arc<dyn MediaProvider> {
+    Arc::new(SystemMedia)
+}
+
+/// Providers with fixed readings, for tests.
+#[cfg(test)]
+pub mod mock {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// Hands out its readings in order as soon as it's watched.
+    pub struct MockBattery {
+        pub readings: Vec<Option<BatteryStatus>>,
+    }
+
+    impl BatteryProvider for MockBattery {
+        fn watch(
+            &self,
+            _interval: Duration,
+            on_reading: BatteryCallback,
+        ) -> Box<dyn Any + Send + Sync> {
+            for reading in &self.readings {
+                on_reading(*reading);
+            }
+            Box::new(())
+        }
+    }
+
+    /// Keeps the volume it's set to.
+    #[derive(Default)]
+    pub struct MockAudio {
+        pub status: Mutex<AudioStatus>,
+    }
+
+    impl MockAudio {
+        pub fn new(level: u8, muted: bool) -> Self {
+            Self {
+                status: Mutex::new(AudioStatus { level, muted }),
+            }
+        }
+    }
+
+    impl AudioProvider for MockAudio {
+        fn status(&self) -> AudioStatus {
+            *self.status.lock().unwrap()
+        }
+
+        fn set_level(&self, level: u8) -> std::io::Result<()> {
+            *self.status.lock().unwrap() = AudioStatus {
+                level,
+                muted: false,
+            };
+            Ok(())
+        }
+    }
+
+    /// Joins any listed network; power changes apply at once.
+    #[derive(Default)]
+    pub struct MockNetwork {
+        pub status: Mutex<Option<WifiStatus>>,
+        pub networks: Vec<WifiNetwork>,
+    }
+
+    impl NetworkProvider for MockNetwork {
+        fn status(&self) -> Option<WifiStatus> {
+            self.status.lock().unwrap().clone()
+        }
+
+        fn known_networks(&self) -> Vec<WifiNetwork> {
+            self.networks.clone()
+        }
+
+        fn join(&self, ssid: &str) -> std::io::Result<()> {
+            let network = self.networks.iter().find(|n| n.ssid == ssid);
+            let Some(network) = network else {
+                return Err(std::io::Error::other(format!(
+                    "Could not find network {}.",
+                    ssid
+                )));
+            };
+            let mut status = self.status.lock().unwrap();
+            let status = status.get_or_insert_with(Default::default);
+            status.ssid = Some(network.ssid.clone());
+            status.rssi = Some(network.rssi);
+            Ok(())
+        }
+
+        fn set_power(&self, on: bool) -> std::io::Result<()> {
+            let mut status = self.status.lock().unwrap();
+            let status = status.get_or_insert_with(Default::default);
+            *status = WifiStatus {
+                interface: status.interface.clone(),
+                power: on,
+                ..Default::default()
+            };
+            Ok(())
+        }
+    }
+
+    /// Reports the same tracks on every query.
+    #[derive(Default)]
+    pub struct MockMedia {
+        pub system: Option<PlayerTrack>,
+        /// Tracks by player app name
+        pub apps: Vec<(&'static str, PlayerTrack)>,
+    }
+
+    impl MediaProvider for MockMedia {
+        fn system_track(&self) -> Option<PlayerTrack> {
+            self.system.clone()
+        }
+
+        fn app_track(&self, app: &str) -> Option<PlayerTrack> {
+            self.apps
+                .iter()
+                .find(|(name, _)| *name == app)
+                .map(|(_, track)| track.clone())
+        }
+    }
+}
@@ -0,0 +1,306 @@
+//! Minimal WebSocket client (RFC 6455) over plain TCP.
+//!
+//! Covers what the homeassistant module and the Stream Deck plugin need:
+//! the upgrade handshake, text messages (fragmented or not) and answering
+//! pings. No TLS or extensions, so `ws://` URLs only.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const CONTINUATION: u8 = 0x0;
+const TEXT: u8 = 0x1;
+const BINARY: u8 = 0x2;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xA;
+
+/// Largest message accepted; Home Assistant's full state dump of a big
+/// install runs to a few megabytes.
+const MAX_MESSAGE: usize = 32 * 1024 * 1024;
+
+/// How long connecting, the handshake and the rest of a started message
+/// may take.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A connected WebSocket.
+pub struct WebSocket {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl WebSocket {
+    /// Connects to `host:port` and upgrades the connection on `path`.
+    pub fn connect(host: &str, port: u16, path: &str) -> io::Result<Self> {
+        let address = (host, port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host not found"))?;
+        let stream = TcpStream::connect_timeout(&address, TIMEOUT)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        let mut socket = Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        };
+
+        write!(
+            socket.writer,
+            "GET {} HTTP/1.1\r\nHost: {}:{}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n\r\n",
+            path,
+            host,
+            port,
+            base64(&random_bytes::<16>())
+        )?;
+        let mut status = String::new();
+        socket.reader.read_line(&mut status)?;
+        if status.split_whitespace().nth(1) != Some("101") {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("upgrade refused: {}", status.trim()),
+            ));
+        }
+        // Skip the response headers
+        loop {
+            let mut header = String::new();
+            if socket.reader.read_line(&mut header)? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            if header.trim().is_empty() {
+                break;
+            }
+        }
+        Ok(socket)
+    }
+
+    pub fn send_text(&mut self, text: &str) -> io::Result<()> {
+        self.writer
+            .write_all(&encode_frame(TEXT, text.as_bytes(), random_bytes::<4>()))
+    }
+
+    /// Waits up to `timeout` for a text message, answering pings on the
+    /// way. Ok(None) when nothing arrived; an error once the server closes.
+    pub fn read_text(&mut self, timeout: Duration) -> io::Result<Option<String>> {
+        self.reader.get_ref().set_read_timeout(Some(timeout))?;
+        // Wait for the first byte only; the rest of a message follows quickly
+        match self.reader.fill_buf() {
+            Ok([]) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(_) => {}
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(None)
+            }
+            Err(e) => return Err(e),
+        }
+        self.reader.get_ref().set_read_timeout(Some(TIMEOUT))?;
+
+        let mut message = Vec::new();
+        let mut message_opcode = None;
+        loop {
+            let frame = read_frame(&mut self.reader)?;
+            match frame.opcode {
+                PING => {
+                    let pong = encode_frame(PONG, &frame.payload, random_bytes::<4>());
+                    self.writer.write_all(&pong)?;
+                }
+                CLOSE => {
+                    let _ = self
+                        .writer
+                        .write_all(&encode_frame(CLOSE, &[], random_bytes::<4>()));
+                    return Err(io::Error::new(
+                        io::ErrorKind::ConnectionAborted,
+                        "server closed the connection",
+                    ));
+                }
+                TEXT | BINARY | CONTINUATION => {
+                    if frame.opcode != CONTINUATION {
+                        message_opcode = Some(frame.opcode);
+                        message.clear();
+                    }
+                    if message.len() + frame.payload.len() > MAX_MESSAGE {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "message too large",
+                        ));
+                    }
+                    message.extend_from_slice(&frame.payload);
+                    if frame.fin {
+                        return match message_opcode {
+                            Some(TEXT) => String::from_utf8(message)
+                                .map(Some)
+                                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+                            _ => Ok(None),
+                        };
+                    }
+                }
+                // Pongs and unknown control frames
+                _ => {}
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+/// Encodes a final frame, masked as clients must.
+fn encode_frame(opcode: u8, payload: &[u8], mask: [u8; 4]) -> Vec<u8> {
+    let mut out = vec![0x80 | opcode];
+    match payload.len() {
+        len @ 0..=125 => out.push(0x80 | len as u8),
+        len @ 126..=0xFFFF => {
+            out.push(0x80 | 126);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            out.push(0x80 | 127);
+            out.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(&mask);
+    out.extend(
+        payload
+            .iter()
+            .enumerate()
+            .map(|(i, byte)| byte ^ mask[i % 4]),
+    );
+    out
+}
+
+fn read_frame(reader: &mut impl Read) -> io::Result<Frame> {
+    let mut head = [0u8; 2];
+    reader.read_exact(&mut head)?;
+    let masked = head[1] & 0x80 != 0;
+    let len = match head[1] & 0x7F {
+        126 => {
+            let mut len = [0u8; 2];
+            reader.read_exact(&mut len)?;
+            usize::from(u16::from_be_bytes(len))
+        }
+        127 => {
+            let mut len = [0u8; 8];
+            reader.read_exact(&mut len)?;
+            usize::try_from(u64::from_be_bytes(len)).unwrap_or(usize::MAX)
+        }
+        len => usize::from(len),
+    };
+    if len > MAX_MESSAGE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "frame too large",
+        ));
+    }
+    let mut mask = [0u8; 4];
+    if masked {
+        reader.read_exact(&mut mask)?;
+    }
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload)?;
+    if masked {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+    }
+    Ok(Frame {
+        fin: head[0] & 0x80 != 0,
+        opcode: head[0] & 0x0F,
+        payload,
+    })
+}
+
+/// Bytes for masks and the handshake key. They only need to vary, not be
+/// unpredictable, since the server isn't a browser's target.
+fn random_bytes<const N: usize>() -> [u8; N] {
+    static STATE: AtomicU64 = AtomicU64::new(0);
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    let mut x = (seed ^ STATE.fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)) | 1;
+    let mut out = [0u8; N];
+    for byte in out.iter_mut() {
+        // xorshift64
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        *byte = x as u8;
+    }
+    out
+}
+
+pub fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | (u32::from(*byte) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn encodes_masked_frames() {
+        let frame = encode_frame(TEXT, b"Hi", [1, 2, 3, 4]);
+        assert_eq!(frame, [0x81, 0x82, 1, 2, 3, 4, b'H' ^ 1, b'i' ^ 2]);
+
+        let frame = encode_frame(TEXT, &[0; 300], [0; 4]);
+        assert_eq!(&frame[..4], [0x81, 0x80 | 126, 0x01, 0x2C]);
+        assert_eq!(frame.len(), 4 + 4 + 300);
+    }
+
+    #[test]
+    fn reads_frames() {
+        let mut input = Cursor::new(vec![0x81, 3, b'a', b'b', b'c']);
+        assert_eq!(
+            read_frame(&mut input).unwrap(),
+            Frame {
+                fin: true,
+                opcode: TEXT,
+                payload: b"abc".to_vec(),
+            }
+        );
+
+        // Round trip through a masked frame
+        let mut input = Cursor::new(encode_frame(TEXT, &[7; 200], [9, 8, 7, 6]));
+        let frame = read_frame(&mut input).unwrap();
+        assert_eq!(frame.payload, vec![7; 200]);
+
+        let mut input = Cursor::new(vec![0x01, 1, b'x']);
+        assert!(!read_frame(&mut input).unwrap().fin);
+
+        let mut input = Cursor::new(vec![0x81, 5, b'a']);
+        assert!(read_frame(&mut input).is_err());
+    }
+
+    #[test]
+    fn encodes_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64(&[0xFF; 16]).len(), 24);
+    }
+}
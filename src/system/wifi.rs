@@ -0,0 +1,287 @@
+//! Wi-Fi interface state and nearby networks from CoreWLAN.
+//!
+//! macOS 14 and later hide network names (of the current network and of
+//! scan results) from apps without Location Services access; CoreWLAN then
+//! reports a connected interface without an SSID. Switching networks and
+//! turning Wi-Fi on or off go through `networksetup`, which joins with the
+//! password saved in the keychain and refuses to change the power when the
+//! system requires an administrator for it.
+
+use std::io;
+use std::process::Command;
+
+use objc2::msg_send;
+use objc2::rc::{autoreleasepool, Retained};
+use objc2::runtime::{AnyClass, AnyObject};
+use objc2_foundation::NSString;
+
+/// Used when CoreWLAN doesn't name the interface.
+const DEFAULT_INTERFACE: &str = "en0";
+
+#[link(name = "CoreWLAN", kind = "framework")]
+extern "C" {}
+
+/// State of the Wi-Fi interface.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct WifiStatus {
+    /// BSD name, e.g. `en0`
+    pub interface: String,
+    pub power: bool,
+    /// None when disconnected or hidden for lack of Location Services access
+    pub ssid: Option<String>,
+    /// Signal strength in dBm, None when disconnected
+    pub rssi: Option<i32>,
+    /// Noise in dBm
+    pub noise: Option<i32>,
+    /// Transmit rate in Mbps
+    pub rate: Option<f64>,
+}
+
+impl WifiStatus {
+    pub fn connected(&self) -> bool {
+        self.power && self.rssi.is_some()
+    }
+
+    /// Connected, but macOS won't say to which network.
+    pub fn ssid_hidden(&self) -> bool {
+        self.connected() && self.ssid.is_none()
+    }
+}
+
+/// A network seen in a scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WifiNetwork {
+    pub ssid: String,
+    /// Signal strength in dBm
+    pub rssi: i32,
+}
+
+/// Signal bars, 0–4, for a signal strength in dBm.
+pub fn signal_bars(rssi: i32) -> u8 {
+    match rssi {
+        r if r >= -55 => 4,
+        r if r >= -67 => 3,
+        r if r >= -75 => 2,
+        r if r >= -85 => 1,
+        _ => 0,
+    }
+}
+
+/// The networks in `scanned` that are in `known`, once each with their
+/// strongest signal, strongest first.
+pub fn nearby_known(scanned: Vec<WifiNetwork>, known: &[String]) -> Vec<WifiNetwork> {
+    let mut networks: Vec<WifiNetwork> = Vec::new();
+    for network in scanned.into_iter().filter(|n| known.contains(&n.ssid)) {
+        match networks.iter_mut().find(|n| n.ssid == network.ssid) {
+            Some(seen) => seen.rssi = seen.rssi.max(network.rssi),
+            None => networks.push(network),
+        }
+    }
+    networks.sort_by(|a, b| b.rssi.cmp(&a.rssi).then_with(|| a.ssid.cmp(&b.ssid)));
+    networks
+}
+
+/// The default Wi-Fi interface (`CWInterface`).
+fn interface() -> Option<Retained<AnyObject>> {
+    let class = AnyClass::get(c"CWWiFiClient")?;
+    unsafe {
+        let client: Option<Retained<AnyObject>> = msg_send![class, sharedWiFiClient];
+        let client = client?;
+        msg_send![&*client, interface]
+    }
+}
+
+/// An `NSString` property, None when nil or empty.
+fn text(value: Option<Retained<NSString>>) -> Option<String> {
+    value.map(|s| s.to_string()).filter(|s| !s.is_empty())
+}
+
+/// Objects of an `NSArray`.
+unsafe fn objects(array: &AnyObject) -> Vec<Retained<AnyObject>> {
+    let count: usize = msg_send![array, count];
+    let mut objects = Vec::with_capacity(count);
+    for index in 0..count {
+        let object: Option<Retained<AnyObject>> = msg_send![array, objectAtIndex: index];
+        objects.extend(object);
+    }
+    objects
+}
+
+/// Reads the Wi-Fi interface, None on Macs without one.
+pub fn status() -> Option<WifiStatus> {
+    autoreleasepool(|_| {
+        let interface = interface()?;
+        unsafe {
+            let power: bool = msg_send![&*interface, powerOn];
+            let rssi: isize = msg_send![&*interface, rssiValue];
+            let noise: isize = msg_send![&*interface, noiseMeasurement];
+            let rate: f64 = msg_send![&*interface, transmitRate];
+            // CoreWLAN reports zeros while disconnected
+            let connected = power && rssi != 0;
+            Some(WifiStatus {
+                interface: text(msg_send![&*interface, interfaceName])
+                    .unwrap_or_else(|| DEFAULT_INTERFACE.to_string()),
+                power,
+                ssid: if connected {
+                    text(msg_send![&*interface, ssid])
+                } else {
+                    None
+                },
+                rssi: connected.then_some(rssi as i32),
+                noise: (connected && noise != 0).then_some(noise as i32),
+                rate: (connected && rate > 0.0).then_some(rate),
+            })
+        }
+    })
+}
+
+/// Scans for known networks in range, strongest first. Blocks for a few
+/// seconds while the interface scans.
+pub fn known_networks() -> Vec<WifiNetwork> {
+    autoreleasepool(|_| {
+        let Some(interface) = interface() else {
+            return Vec::new();
+        };
+        unsafe {
+            let known = profile_ssids(&interface);
+            if known.is_empty() {
+                return Vec::new();
+            }
+            let nil: *mut AnyObject = std::ptr::null_mut();
+            let mut error: *mut AnyObject = std::ptr::null_mut();
+            let scanned: Option<Retained<AnyObject>> = msg_send![
+                &*interface,
+                scanForNetworksWithName: nil,
+                error: &mut error as *mut *mut AnyObject
+            ];
+            let Some(scanned) = scanned else {
+                log::debug!("wifi: scan failed");
+                return Vec::new();
+            };
+            let all: Retained<AnyObject> = msg_send![&*scanned, allObjects];
+            let scanned = objects(&all)
+                .iter()
+                .filter_map(|network| {
+                    let rssi: isize = msg_send![&**network, rssiValue];
+                    Some(WifiNetwork {
+                        ssid: text(msg_send![&**network, ssid])?,
+                        rssi: rssi as i32,
+                    })
+                })
+                .collect();
+            nearby_known(scanned, &known)
+        }
+    })
+}
+
+/// Names of the networks saved in the interface's configuration.
+unsafe fn profile_ssids(interface: &AnyObject) -> Vec<String> {
+    let configuration: Option<Retained<AnyObject>> = msg_send![interface, configuration];
+    let Some(configuration) = configuration else {
+        return Vec::new();
+    };
+    let profiles: Option<Retained<AnyObject>> = msg_send![&*configuration, networkProfiles];
+    let Some(profiles) = profiles else {
+        return Vec::new();
+    };
+    let profiles: Retained<AnyObject> = msg_send![&*profiles, array];
+    objects(&profiles)
+        .iter()
+        .filter_map(|profile| text(msg_send![&**profile, ssid]))
+        .collect()
+}
+
+fn interface_name() -> String {
+    status()
+        .map(|status| status.interface)
+        .unwrap_or_else(|| DEFAULT_INTERFACE.to_string())
+}
+
+/// Joins a known network with its saved password.
+pub fn join(ssid: &str) -> io::Result<()> {
+    networksetup(&["-setairportnetwork", &interface_name(), ssid])
+}
+
+/// Turns Wi-Fi on or off.
+pub fn set_power(on: bool) -> io::Result<()> {
+    let state = if on { "on" } else { "off" };
+    networksetup(&["-setairportpower", &interface_name(), state])
+}
+
+fn networksetup(args: &[&str]) -> io::Result<()> {
+    let output = Command::new("networksetup").args(args).output()?;
+    match failure(
+        output.status.success(),
+        &String::from_utf8_lossy(&output.stdout),
+        &String::from_utf8_lossy(&output.stderr),
+    ) {
+        Some(message) => Err(io::Error::other(message)),
+        None => Ok(()),
+    }
+}
+
+/// Why a `networksetup` call failed. It prints nothing on success but
+/// exits 0 on most failures, so any output counts as an error.
+fn failure(success: bool, stdout: &str, stderr: &str) -> Option<String> {
+    let message = [stderr, stdout]
+        .into_iter()
+        .find_map(|output| output.lines().map(str::trim).find(|l| !l.is_empty()));
+    match (success, message) {
+        (true, None) => None,
+        (_, Some(message)) => Some(message.to_string()),
+        (false, None) => Some("networksetup failed".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network(ssid: &str, rssi: i32) -> WifiNetwork {
+        WifiNetwork {
+            ssid: ssid.to_string(),
+            rssi,
+        }
+    }
+
+    #[test]
+    fn counts_signal_bars() {
+        assert_eq!(signal_bars(-40), 4);
+        assert_eq!(signal_bars(-60), 3);
+        assert_eq!(signal_bars(-75), 2);
+        assert_eq!(signal_bars(-80), 1);
+        assert_eq!(signal_bars(-95), 0);
+    }
+
+    #[test]
+    fn lists_known_networks_once_strongest_first() {
+        let known = vec!["Home".to_string(), "Office".to_string()];
+        let scanned = vec![
+            network("Office", -80),
+            network("Cafe", -40),
+            network("Home", -70),
+            network("Office", -60),
+        ];
+        assert_eq!(
+            nearby_known(scanned, &known),
+            vec![network("Office", -60), network("Home", -70)]
+        );
+    }
+
+    #[test]
+    fn treats_networksetup_output_as_failure() {
+        assert_eq!(failure(true, "", ""), None);
+        assert_eq!(
+            failure(true, "Could not find network Cafe.\n", ""),
+            Some("Could not find network Cafe.".to_string())
+        );
+        assert_eq!(
+            failure(false, "", "\n** Error: requires admin privileges\n"),
+            Some("** Error: requires admin privileges".to_string())
+        );
+        assert_eq!(
+            failure(false, "", ""),
+            Some("networksetup failed".to_string())
+        );
+    }
+}
@@ -1,6 +1,6 @@
 pub mod screen;
 
-pub use screen::get_main_screen_info;
+pub use screen::{builtin_notch_width, get_main_screen_info};
 
 /// Window position within a notched display layout.
 #[allow(dead_code)]
@@ -1,5 +1,9 @@
-use objc2::MainThreadMarker;
+use core_graphics::display::CGDisplay;
+use objc2::rc::Retained;
+use objc2::runtime::AnyObject;
+use objc2::{msg_send, sel, MainThreadMarker};
 use objc2_app_kit::{NSScreen, NSStatusBar};
+use objc2_foundation::{NSEdgeInsets, NSObjectProtocol, NSRect, NSString};
 
 #[allow(dead_code)]
 pub struct ScreenInfo {
@@ -7,6 +11,48 @@ pub struct ScreenInfo {
     pub menu_bar_height: f64,
     /// macOS Y coordinate of the menu-bar bottom edge / visible-frame top edge.
     pub menu_bar_origin_y: f64,
+    /// Whether the display has a camera housing (notch) cutting into the menu bar.
+    pub has_notch: bool,
+    /// Display name as shown in System Settings, e.g. "DELL U2723QE"
+    pub name: String,
+    /// Physical width in millimeters, when the display reports it
+    pub width_mm: Option<f64>,
+}
+
+/// Top safe-area inset of a screen; non-zero when a notch cuts into the menu bar.
+fn notch_inset(screen: &NSScreen) -> f64 {
+    // `safeAreaInsets` only exists on macOS 12+, which every notched Mac runs.
+    if !screen.respondsToSelector(sel!(safeAreaInsets)) {
+        return 0.0;
+    }
+    let insets: NSEdgeInsets = unsafe { msg_send![screen, safeAreaInsets] };
+    insets.top
+}
+
+/// Physical width of a screen in millimeters, from its CoreGraphics display.
+fn width_mm(screen: &NSScreen) -> Option<f64> {
+    let description: Retained<AnyObject> = unsafe { msg_send![screen, deviceDescription] };
+    let key = NSString::from_str("NSScreenNumber");
+    let number: Option<Retained<AnyObject>> =
+        unsafe { msg_send![&description, objectForKey: &*key] };
+    let id: u32 = unsafe { msg_send![&*number?, unsignedIntValue] };
+    let width = CGDisplay::new(id).screen_size().width;
+    (width > 0.0).then_some(width)
+}
+
+/// Width of the built-in display's notch in points, when that display is
+/// on (not closed in clamshell mode) and has one.
+pub fn builtin_notch_width(mtm: MainThreadMarker) -> Option<f64> {
+    NSScreen::screens(mtm).iter().find_map(|screen| {
+        if notch_inset(&screen) <= 0.0 {
+            return None;
+        }
+        // The areas left and right of the notch; macOS 12+ like the insets
+        let left: NSRect = unsafe { msg_send![&screen, auxiliaryTopLeftArea] };
+        let right: NSRect = unsafe { msg_send![&screen, auxiliaryTopRightArea] };
+        let width = screen.frame().size.width - left.size.width - right.size.width;
+        (width > 0.0).then_some(width)
+    })
 }
 
 pub fn get_main_screen_info(mtm: MainThreadMarker) -> Option<ScreenInfo> {
@@ -31,6 +77,8 @@ pub fn get_main_screen_info(mtm: MainThreadMarker) -> Option<ScreenInfo> {
 
     let menu_bar_origin_y = visible_frame.origin.y + visible_frame.size.height;
 
+    let has_notch = notch_inset(&screen) > 0.0;
+
     Some(ScreenInfo {
         frame: (
             frame.origin.x,
@@ -40,5 +88,8 @@ pub fn get_main_screen_info(mtm: MainThreadMarker) -> Option<ScreenInfo> {
         ),
         menu_bar_height,
         menu_bar_origin_y,
+        has_notch,
+        name: screen.localizedName().to_string(),
+        width_mm: width_mm(&screen),
     })
 }